@@ -20,11 +20,13 @@ pub mod event;
 pub mod hash;
 mod header;
 mod macros;
+pub mod memory;
 pub mod message;
 pub mod prelude;
 pub mod receipt;
 pub mod signature;
 pub mod state_update;
+pub mod telemetry;
 pub mod test_utils;
 pub mod transaction;
 pub mod trie;
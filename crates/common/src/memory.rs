@@ -0,0 +1,178 @@
+//! Allocator level memory accounting per subsystem.
+//!
+//! Code running on behalf of a subsystem marks itself using [enter] or
+//! [scope]. Allocations and deallocations made by the current thread while a
+//! subsystem is active are attributed to it, provided the
+//! [TrackingAllocator] is installed as the global allocator.
+//!
+//! Attribution is by thread, so memory allocated by one subsystem and freed
+//! by another skews both figures. The values are meant to show trends during
+//! OOM investigations, not to be exact.
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Subsystem {
+    Other = 0,
+    Executor,
+    Tries,
+    Rpc,
+    Caches,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] = [
+        Subsystem::Other,
+        Subsystem::Executor,
+        Subsystem::Tries,
+        Subsystem::Rpc,
+        Subsystem::Caches,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Subsystem::Other => "other",
+            Subsystem::Executor => "executor",
+            Subsystem::Tries => "tries",
+            Subsystem::Rpc => "rpc",
+            Subsystem::Caches => "caches",
+        }
+    }
+}
+
+static ALLOCATED: [AtomicI64; Subsystem::ALL.len()] =
+    [const { AtomicI64::new(0) }; Subsystem::ALL.len()];
+
+thread_local! {
+    static CURRENT: Cell<Subsystem> = const { Cell::new(Subsystem::Other) };
+}
+
+/// Bytes currently allocated by `subsystem`.
+///
+/// Always zero unless [TrackingAllocator] is the global allocator.
+pub fn allocated_bytes(subsystem: Subsystem) -> i64 {
+    ALLOCATED[subsystem as usize].load(Ordering::Relaxed)
+}
+
+/// Attributes allocations on this thread to `subsystem` until the returned
+/// guard is dropped.
+#[must_use = "The subsystem is left as soon as the guard is dropped"]
+pub fn enter(subsystem: Subsystem) -> SubsystemGuard {
+    let previous = CURRENT.with(|current| current.replace(subsystem));
+    SubsystemGuard { previous }
+}
+
+/// Runs `f` with allocations attributed to `subsystem`.
+pub fn scope<T>(subsystem: Subsystem, f: impl FnOnce() -> T) -> T {
+    let _guard = enter(subsystem);
+    f()
+}
+
+/// Attributes allocations to `subsystem` whenever `future` is polled.
+///
+/// Use this instead of [enter] in async code, where a task may move between
+/// threads across `.await` points.
+pub async fn instrument<F: Future>(subsystem: Subsystem, future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    std::future::poll_fn(|cx| {
+        let _guard = enter(subsystem);
+        future.as_mut().poll(cx)
+    })
+    .await
+}
+
+pub struct SubsystemGuard {
+    previous: Subsystem,
+}
+
+impl Drop for SubsystemGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.previous));
+    }
+}
+
+fn track(delta: i64) {
+    // The thread local may already be gone during thread teardown.
+    let subsystem = CURRENT
+        .try_with(|current| current.get())
+        .unwrap_or(Subsystem::Other);
+    ALLOCATED[subsystem as usize].fetch_add(delta, Ordering::Relaxed);
+}
+
+/// Wraps a global allocator and keeps per [Subsystem] allocation counts.
+pub struct TrackingAllocator<A>(pub A);
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc(layout);
+        if !ptr.is_null() {
+            track(layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            track(layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.dealloc(ptr, layout);
+        track(-(layout.size() as i64));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.0.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            track(new_size as i64 - layout.size() as i64);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_nest() {
+        let current = || CURRENT.with(|current| current.get());
+
+        assert_eq!(current(), Subsystem::Other);
+        {
+            let _rpc = enter(Subsystem::Rpc);
+            assert_eq!(current(), Subsystem::Rpc);
+
+            scope(Subsystem::Executor, || {
+                assert_eq!(current(), Subsystem::Executor);
+            });
+            assert_eq!(current(), Subsystem::Rpc);
+        }
+        assert_eq!(current(), Subsystem::Other);
+    }
+
+    #[test]
+    fn tracking_allocator_attributes_to_current_subsystem() {
+        let allocator = TrackingAllocator(std::alloc::System);
+        let layout = Layout::from_size_align(1024, 8).unwrap();
+        let before = allocated_bytes(Subsystem::Caches);
+
+        let _guard = enter(Subsystem::Caches);
+        // SAFETY: The layout has a non-zero size and the pointer is freed with
+        // the same layout.
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            assert!(!ptr.is_null());
+            assert_eq!(allocated_bytes(Subsystem::Caches) - before, 1024);
+
+            allocator.dealloc(ptr, layout);
+        }
+        assert_eq!(allocated_bytes(Subsystem::Caches), before);
+    }
+}
@@ -1,5 +1,6 @@
 use fake::{Dummy, Fake, Faker};
 use pathfinder_crypto::Felt;
+use primitive_types::H256;
 
 use crate::prelude::*;
 
@@ -37,6 +38,31 @@ pub struct L2ToL1Message {
     pub to_address: ContractAddress,
 }
 
+impl L2ToL1Message {
+    /// The hash of the message as computed by the Starknet core contract on
+    /// L1, which is needed to consume the message there.
+    pub fn calculate_hash(&self) -> H256 {
+        use sha3::{Digest, Keccak256};
+
+        let mut hash = Keccak256::new();
+
+        hash.update(self.from_address.0.as_be_bytes());
+        hash.update(self.to_address.0.as_be_bytes());
+
+        // Pad the u64 to 32 bytes to match a felt.
+        hash.update([0u8; 24]);
+        hash.update((self.payload.len() as u64).to_be_bytes());
+
+        for elem in &self.payload {
+            hash.update(elem.0.as_be_bytes());
+        }
+
+        let hash = <[u8; 32]>::from(hash.finalize());
+
+        hash.into()
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ExecutionResources {
     pub builtins: BuiltinCounters,
@@ -0,0 +1,213 @@
+//! Opt-in aggregation of anonymized error fingerprints.
+//!
+//! Errors are reduced to a template by replacing numbers, hashes and
+//! addresses with placeholders, and are counted per template. The
+//! fingerprint is a digest of the error kind and its template, so that the
+//! same failure mode has the same fingerprint on every node.
+//!
+//! Nothing is recorded unless [enable] has been called, and nothing is sent
+//! anywhere: the counts are only served by the monitoring endpoint.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Panic,
+    Execution,
+    Database,
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Panic => "panic",
+            ErrorKind::Execution => "execution",
+            ErrorKind::Database => "database",
+            ErrorKind::Internal => "internal",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ErrorCount {
+    pub kind: ErrorKind,
+    pub fingerprint: String,
+    pub template: String,
+    pub count: u64,
+}
+
+/// Errors with new fingerprints are dropped once this many are known, so
+/// that a flood of unexpected messages cannot exhaust memory.
+const MAX_FINGERPRINTS: usize = 1000;
+
+/// Templates are truncated to this many characters.
+const MAX_TEMPLATE_LENGTH: usize = 256;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static COUNTS: Mutex<Option<HashMap<String, ErrorCount>>> = Mutex::new(None);
+
+/// Starts recording errors, and installs a panic hook which records panics
+/// before running the previous hook.
+pub fn enable() {
+    if ENABLED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+        // Only the file name, the full path may contain the user's home
+        // directory.
+        let location = info
+            .location()
+            .map(|location| {
+                let file = std::path::Path::new(location.file())
+                    .file_name()
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_default();
+                format!("{file}:{}", location.line())
+            })
+            .unwrap_or_default();
+        record(ErrorKind::Panic, &format!("{location}: {message}"));
+
+        previous(info)
+    }));
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Counts an occurrence of `message`. Does nothing unless [enable] has been
+/// called.
+pub fn record(kind: ErrorKind, message: &str) {
+    if !is_enabled() {
+        return;
+    }
+
+    let template = template(message);
+    let fingerprint = fingerprint(kind, &template);
+
+    // Don't panic while recording a panic.
+    let Ok(mut counts) = COUNTS.lock() else {
+        return;
+    };
+    let counts = counts.get_or_insert_with(Default::default);
+    match counts.get_mut(&fingerprint) {
+        Some(count) => count.count += 1,
+        None if counts.len() < MAX_FINGERPRINTS => {
+            counts.insert(
+                fingerprint.clone(),
+                ErrorCount {
+                    kind,
+                    fingerprint,
+                    template,
+                    count: 1,
+                },
+            );
+        }
+        None => {}
+    }
+}
+
+/// The recorded errors, most frequent first.
+pub fn snapshot() -> Vec<ErrorCount> {
+    let mut counts = COUNTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .map(|counts| counts.values().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    counts.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.fingerprint.cmp(&b.fingerprint))
+    });
+    counts
+}
+
+/// Replaces numbers and hex strings in the first line of `message` with
+/// placeholders.
+fn template(message: &str) -> String {
+    let message = message.lines().next().unwrap_or_default();
+
+    let mut template = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        template.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = &rest[end..];
+
+        let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+        if word.chars().all(|c| c.is_ascii_digit()) {
+            template.push_str("<n>");
+        } else if (word.starts_with("0x") && is_hex(&word[2..]))
+            || (word.len() >= 16 && is_hex(word))
+        {
+            template.push_str("<hex>");
+        } else {
+            template.push_str(word);
+        }
+    }
+    template.push_str(rest);
+
+    match template.char_indices().nth(MAX_TEMPLATE_LENGTH) {
+        Some((index, _)) => template[..index].to_owned(),
+        None => template,
+    }
+}
+
+fn fingerprint(kind: ErrorKind, template: &str) -> String {
+    use sha3::Digest;
+
+    let digest = sha3::Sha3_256::new()
+        .chain_update(kind.as_str())
+        .chain_update([0u8])
+        .chain_update(template)
+        .finalize();
+    digest[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_hides_values() {
+        assert_eq!(
+            template("Block 12345 with hash 0x1a2b not found\ncaused by: something"),
+            "Block <n> with hash <hex> not found"
+        );
+        assert_eq!(
+            template("Class deadbeefdeadbeefdeadbeef is missing: v0_13"),
+            "Class <hex> is missing: v0_<n>"
+        );
+    }
+
+    #[test]
+    fn fingerprint_depends_on_kind_and_template() {
+        let a = fingerprint(ErrorKind::Database, &template("Row 1 missing"));
+        let b = fingerprint(ErrorKind::Database, &template("Row 2 missing"));
+        let c = fingerprint(ErrorKind::Internal, &template("Row 1 missing"));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.len(), 16);
+    }
+}
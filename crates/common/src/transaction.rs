@@ -98,19 +98,28 @@ impl TransactionVariant {
     }
 
     pub fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+        self.calculate_hash_preimage(chain_id, query_only).hash()
+    }
+
+    /// The elements hashed to obtain the transaction hash, in order.
+    pub fn calculate_hash_preimage(
+        &self,
+        chain_id: ChainId,
+        query_only: bool,
+    ) -> TransactionHashPreimage {
         match self {
-            TransactionVariant::DeclareV0(tx) => tx.calculate_hash_v0(chain_id, query_only),
-            TransactionVariant::DeclareV1(tx) => tx.calculate_hash_v1(chain_id, query_only),
-            TransactionVariant::DeclareV2(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::DeclareV3(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::DeployV0(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::DeployV1(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::DeployAccountV1(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::DeployAccountV3(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::InvokeV0(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::InvokeV1(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::InvokeV3(tx) => tx.calculate_hash(chain_id, query_only),
-            TransactionVariant::L1Handler(tx) => tx.calculate_hash(chain_id),
+            TransactionVariant::DeclareV0(tx) => tx.hash_preimage_v0(chain_id, query_only),
+            TransactionVariant::DeclareV1(tx) => tx.hash_preimage_v1(chain_id, query_only),
+            TransactionVariant::DeclareV2(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::DeclareV3(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::DeployV0(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::DeployV1(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::DeployAccountV1(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::DeployAccountV3(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::InvokeV0(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::InvokeV1(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::InvokeV3(tx) => tx.hash_preimage(chain_id, query_only),
+            TransactionVariant::L1Handler(tx) => tx.hash_preimage(chain_id),
         }
     }
 
@@ -131,6 +140,28 @@ impl TransactionVariant {
         }
     }
 
+    /// Returns the address of the account sending this transaction.
+    ///
+    /// For deploy transactions this is the address of the deployed contract and
+    /// for L1 handler transactions it is the address of the contract being
+    /// called.
+    pub fn contract_address(&self) -> ContractAddress {
+        match self {
+            TransactionVariant::DeclareV0(tx) => tx.sender_address,
+            TransactionVariant::DeclareV1(tx) => tx.sender_address,
+            TransactionVariant::DeclareV2(tx) => tx.sender_address,
+            TransactionVariant::DeclareV3(tx) => tx.sender_address,
+            TransactionVariant::DeployV0(tx) => tx.contract_address,
+            TransactionVariant::DeployV1(tx) => tx.contract_address,
+            TransactionVariant::DeployAccountV1(tx) => tx.contract_address,
+            TransactionVariant::DeployAccountV3(tx) => tx.contract_address,
+            TransactionVariant::InvokeV0(tx) => tx.sender_address,
+            TransactionVariant::InvokeV1(tx) => tx.sender_address,
+            TransactionVariant::InvokeV3(tx) => tx.sender_address,
+            TransactionVariant::L1Handler(tx) => tx.contract_address,
+        }
+    }
+
     /// Some variants had a different hash calculations for blocks around
     /// Starknet v0.8 and earlier. The hash excluded the transaction version
     /// and nonce.
@@ -350,7 +381,7 @@ impl From<DataAvailabilityMode> for u64 {
 }
 
 impl DeclareTransactionV0V1 {
-    fn calculate_hash_v0(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage_v0(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         PreV3Hasher {
             prefix: felt_bytes!(b"declare"),
             version: TransactionVersion::ZERO.with_query_only(query_only),
@@ -359,10 +390,10 @@ impl DeclareTransactionV0V1 {
             nonce_or_class: Some(self.class_hash.0),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 
-    fn calculate_hash_v1(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage_v1(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         PreV3Hasher {
             prefix: felt_bytes!(b"declare"),
             version: TransactionVersion::ONE.with_query_only(query_only),
@@ -372,12 +403,12 @@ impl DeclareTransactionV0V1 {
             nonce_or_class: Some(self.nonce.0),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 }
 
 impl DeclareTransactionV2 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         PreV3Hasher {
             prefix: felt_bytes!(b"declare"),
             version: TransactionVersion::TWO.with_query_only(query_only),
@@ -388,12 +419,12 @@ impl DeclareTransactionV2 {
             casm_hash: Some(self.compiled_class_hash),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 }
 
 impl DeployTransactionV0 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         PreV3Hasher {
             prefix: felt_bytes!(b"deploy"),
             version: TransactionVersion::ZERO.with_query_only(query_only),
@@ -402,7 +433,7 @@ impl DeployTransactionV0 {
             data_hash: self.constructor_calldata_hash(),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 
     fn calculate_legacy_hash(&self, chain_id: ChainId) -> TransactionHash {
@@ -427,7 +458,7 @@ impl DeployTransactionV0 {
 }
 
 impl DeployTransactionV1 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         PreV3Hasher {
             prefix: felt_bytes!(b"deploy"),
             version: TransactionVersion::ONE.with_query_only(query_only),
@@ -436,7 +467,7 @@ impl DeployTransactionV1 {
             data_hash: self.constructor_calldata_hash(),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 
     fn calculate_legacy_hash(&self, chain_id: ChainId) -> TransactionHash {
@@ -461,7 +492,7 @@ impl DeployTransactionV1 {
 }
 
 impl DeployAccountTransactionV1 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         let constructor_calldata_hash = std::iter::once(self.class_hash.0)
             .chain(std::iter::once(self.contract_address_salt.0))
             .chain(self.constructor_calldata.iter().map(|x| x.0))
@@ -479,12 +510,12 @@ impl DeployAccountTransactionV1 {
             nonce_or_class: Some(self.nonce.0),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 }
 
 impl InvokeTransactionV0 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         PreV3Hasher {
             prefix: felt_bytes!(b"invoke"),
             version: TransactionVersion::ZERO.with_query_only(query_only),
@@ -494,7 +525,7 @@ impl InvokeTransactionV0 {
             max_fee: self.max_fee,
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 
     fn calculate_legacy_hash(&self, chain_id: ChainId) -> TransactionHash {
@@ -554,6 +585,10 @@ impl L1HandlerTransaction {
     }
 
     pub fn calculate_hash(&self, chain_id: ChainId) -> TransactionHash {
+        self.hash_preimage(chain_id).hash()
+    }
+
+    fn hash_preimage(&self, chain_id: ChainId) -> TransactionHashPreimage {
         PreV3Hasher {
             prefix: felt_bytes!(b"l1_handler"),
             version: TransactionVersion::ZERO,
@@ -563,7 +598,7 @@ impl L1HandlerTransaction {
             nonce_or_class: Some(self.nonce.0),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 
     fn calculate_legacy_hash(&self, chain_id: ChainId) -> TransactionHash {
@@ -601,7 +636,7 @@ impl L1HandlerTransaction {
 }
 
 impl DeclareTransactionV3 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         let deployment_hash = self
             .account_deployment_data
             .iter()
@@ -627,12 +662,12 @@ impl DeclareTransactionV3 {
             resource_bounds: self.resource_bounds,
             query_only,
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 }
 
 impl DeployAccountTransactionV3 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         let deployment_hash = self
             .constructor_calldata
             .iter()
@@ -658,12 +693,12 @@ impl DeployAccountTransactionV3 {
             resource_bounds: self.resource_bounds,
             query_only,
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 }
 
 impl InvokeTransactionV3 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         let deployment_hash = self
             .account_deployment_data
             .iter()
@@ -693,12 +728,12 @@ impl InvokeTransactionV3 {
             resource_bounds: self.resource_bounds,
             query_only,
         }
-        .hash(chain_id)
+        .preimage(chain_id)
     }
 }
 
 impl InvokeTransactionV1 {
-    fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
+    fn hash_preimage(&self, chain_id: ChainId, query_only: bool) -> TransactionHashPreimage {
         let list_hash = self
             .calldata
             .iter()
@@ -716,7 +751,47 @@ impl InvokeTransactionV1 {
             nonce_or_class: Some(self.nonce.0),
             ..Default::default()
         }
-        .hash(chain_id)
+        .preimage(chain_id)
+    }
+}
+
+/// The hash function used to compute a transaction hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashFunction {
+    /// Pedersen hash chain, which includes the number of elements as the final
+    /// element.
+    Pedersen,
+    Poseidon,
+}
+
+/// The elements hashed to obtain a transaction hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionHashPreimage {
+    pub hash_function: HashFunction,
+    pub elements: Vec<Felt>,
+}
+
+impl TransactionHashPreimage {
+    pub fn hash(&self) -> TransactionHash {
+        let hash = match self.hash_function {
+            HashFunction::Pedersen => self
+                .elements
+                .iter()
+                .fold(PedersenHasher::default(), |hasher, &element| {
+                    hasher.chain_update(element)
+                })
+                .finalize(),
+            HashFunction::Poseidon => self
+                .elements
+                .iter()
+                .fold(PoseidonHasher::default(), |hasher, &element| {
+                    hasher.chain(element.into())
+                })
+                .finish()
+                .into(),
+        };
+
+        TransactionHash(hash)
     }
 }
 
@@ -759,25 +834,23 @@ struct PreV3Hasher {
 }
 
 impl PreV3Hasher {
-    fn hash(self, chain_id: ChainId) -> TransactionHash {
-        let mut hash = PedersenHasher::default()
-            .chain_update(self.prefix)
-            .chain_update(self.version.0)
-            .chain_update(self.address.0)
-            .chain_update(self.entry_point.0)
-            .chain_update(self.data_hash)
-            .chain_update(self.max_fee.0)
-            .chain_update(chain_id.0);
-
-        if let Some(felt) = self.nonce_or_class {
-            hash.update(felt);
+    fn preimage(self, chain_id: ChainId) -> TransactionHashPreimage {
+        let mut elements = vec![
+            self.prefix,
+            self.version.0,
+            self.address.0,
+            self.entry_point.0,
+            self.data_hash,
+            self.max_fee.0,
+            chain_id.0,
+        ];
+        elements.extend(self.nonce_or_class);
+        elements.extend(self.casm_hash.map(|casm_hash| casm_hash.0));
+
+        TransactionHashPreimage {
+            hash_function: HashFunction::Pedersen,
+            elements,
         }
-
-        if let Some(felt) = self.casm_hash {
-            hash.update(felt.0);
-        }
-
-        TransactionHash(hash.finalize())
     }
 }
 
@@ -796,29 +869,23 @@ struct V3Hasher<'a> {
 }
 
 impl V3Hasher<'_> {
-    fn hash(self, chain_id: ChainId) -> TransactionHash {
-        let hasher = PoseidonHasher::default()
-            .chain(self.prefix.into())
-            .chain(
-                TransactionVersion::THREE
-                    .with_query_only(self.query_only)
-                    .0
-                    .into(),
-            )
-            .chain(self.sender_address.0.into())
-            .chain(self.hash_fee_fields().into())
-            .chain(self.hash_paymaster_data().into())
-            .chain(chain_id.0.into())
-            .chain(self.nonce.0.into())
-            .chain(self.pack_data_availability().into());
-
-        let hash = self
-            .data_hashes
-            .iter()
-            .fold(hasher, |hasher, &data| hasher.chain(data.into()))
-            .finish();
-
-        TransactionHash(hash.into())
+    fn preimage(self, chain_id: ChainId) -> TransactionHashPreimage {
+        let mut elements = vec![
+            self.prefix,
+            TransactionVersion::THREE.with_query_only(self.query_only).0,
+            self.sender_address.0,
+            self.hash_fee_fields(),
+            self.hash_paymaster_data(),
+            chain_id.0,
+            self.nonce.0,
+            Felt::from_u64(self.pack_data_availability()),
+        ];
+        elements.extend_from_slice(self.data_hashes);
+
+        TransactionHashPreimage {
+            hash_function: HashFunction::Poseidon,
+            elements,
+        }
     }
 
     fn pack_data_availability(&self) -> u64 {
@@ -1010,6 +1077,24 @@ mod tests {
         assert!(transaction.verify_hash(chain_id));
     }
 
+    #[rstest::rstest]
+    #[test]
+    #[case::pedersen(l1_handler(), ChainId::MAINNET, HashFunction::Pedersen, felt_bytes!(b"l1_handler"))]
+    #[case::poseidon(invoke_v3(), ChainId::SEPOLIA_TESTNET, HashFunction::Poseidon, felt_bytes!(b"invoke"))]
+    fn hash_preimage(
+        #[case] transaction: Transaction,
+        #[case] chain_id: ChainId,
+        #[case] hash_function: HashFunction,
+        #[case] prefix: Felt,
+    ) {
+        let preimage = transaction.variant.calculate_hash_preimage(chain_id, false);
+
+        assert_eq!(preimage.hash_function, hash_function);
+        assert_eq!(preimage.elements.first(), Some(&prefix));
+        assert!(preimage.elements.contains(&chain_id.0));
+        assert_eq!(preimage.hash(), transaction.hash);
+    }
+
     fn declare_v0() -> Transaction {
         Transaction {
             hash: transaction_hash!(
@@ -19,11 +19,11 @@ pub fn compile_to_casm(sierra_definition: &[u8]) -> anyhow::Result<Vec<u8>> {
 
     let started_at = std::time::Instant::now();
 
-    let result = std::panic::catch_unwind(|| match sierra_version {
-        SierraVersion(0, 1, 0) => v1_0_0_alpha6::compile(definition),
-        SierraVersion(1, 0, 0) => v1_0_0_rc0::compile(definition),
-        SierraVersion(1, 1, 0) => v1_1_1::compile(definition),
-        _ => v2::compile(definition),
+    let result = std::panic::catch_unwind(|| match sierra_version.compiler() {
+        Compiler::V1_0_0Alpha6 => v1_0_0_alpha6::compile(definition),
+        Compiler::V1_0_0Rc0 => v1_0_0_rc0::compile(definition),
+        Compiler::V1_1_1 => v1_1_1::compile(definition),
+        Compiler::V2 => v2::compile(definition),
     });
 
     tracing::trace!(elapsed=?started_at.elapsed(), "Sierra class compilation finished");
@@ -41,9 +41,68 @@ fn panic_error(e: Box<dyn std::any::Any>) -> anyhow::Error {
     }
 }
 
+/// Returns the Sierra version of a class definition and the version of the
+/// bundled compiler [compile_to_casm] uses for it.
+pub fn compiler_version(sierra_definition: &[u8]) -> anyhow::Result<(String, &'static str)> {
+    let definition = serde_json::from_slice::<FeederGatewayContractClass<'_>>(sierra_definition)
+        .context("Parsing Sierra class")?;
+
+    let sierra_version =
+        parse_sierra_version(definition.sierra_program).context("Parsing Sierra version")?;
+
+    Ok((
+        sierra_version.to_string(),
+        sierra_version.compiler().version(),
+    ))
+}
+
+/// The version of the bundled compiler used for current Sierra versions.
+pub fn latest_compiler_version() -> &'static str {
+    Compiler::V2.version()
+}
+
 #[derive(Debug, PartialEq)]
 struct SierraVersion(u64, u64, u64);
 
+impl SierraVersion {
+    fn compiler(&self) -> Compiler {
+        match self {
+            SierraVersion(0, 1, 0) => Compiler::V1_0_0Alpha6,
+            SierraVersion(1, 0, 0) => Compiler::V1_0_0Rc0,
+            SierraVersion(1, 1, 0) => Compiler::V1_1_1,
+            _ => Compiler::V2,
+        }
+    }
+}
+
+impl std::fmt::Display for SierraVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// The bundled compilers.
+#[derive(Debug, PartialEq)]
+enum Compiler {
+    V1_0_0Alpha6,
+    V1_0_0Rc0,
+    V1_1_1,
+    V2,
+}
+
+impl Compiler {
+    /// The version of the `cairo-lang-starknet` crate, as pinned in
+    /// `Cargo.toml`.
+    fn version(&self) -> &'static str {
+        match self {
+            Compiler::V1_0_0Alpha6 => "1.0.0-alpha.6",
+            Compiler::V1_0_0Rc0 => "1.0.0-rc0",
+            Compiler::V1_1_1 => "1.1.1",
+            Compiler::V2 => "2.7.0",
+        }
+    }
+}
+
 /// Parse Sierra version from the JSON representation of the program.
 ///
 /// Sierra programs contain the version number in two possible formats.
@@ -315,6 +374,22 @@ mod tests {
             let sierra_version = parse_sierra_version(sierra.sierra_program).unwrap();
             assert_eq!(sierra_version, expected_version);
         }
+
+        #[rstest]
+        #[case(CAIRO_1_0_0_ALPHA5_SIERRA, "0.1.0", "1.0.0-alpha.6")]
+        #[case(CAIRO_1_0_0_RC0_SIERRA, "1.0.0", "1.0.0-rc0")]
+        #[case(CAIRO_1_1_0_RC0_SIERRA, "1.1.0", "1.1.1")]
+        #[case(CAIRO_2_0_0_STACK_OVERFLOW, "1.2.0", "2.7.0")]
+        fn compiler_version(
+            #[case] sierra_json: &[u8],
+            #[case] expected_sierra_version: &str,
+            #[case] expected_compiler_version: &str,
+        ) {
+            let (sierra_version, compiler_version) =
+                super::super::compiler_version(sierra_json).unwrap();
+            assert_eq!(sierra_version, expected_sierra_version);
+            assert_eq!(compiler_version, expected_compiler_version);
+        }
     }
 
     mod starknet_v0_11_0 {
@@ -0,0 +1,209 @@
+//! Versions of the Starknet core contract's event ABI.
+//!
+//! Upgrades of the core contract may change the layout of the events it
+//! emits, which changes their signatures. Logs are therefore requested and
+//! decoded using the ABI which was in effect at the Ethereum block they were
+//! emitted in, as recorded by an [AbiRegistry].
+use alloy::primitives::B256;
+use alloy::rpc::types::Log;
+use alloy::sol_types::SolEvent;
+use pathfinder_common::BlockHash;
+use primitive_types::H256;
+
+use crate::starknet::{LegacyStarknetCoreContract, StarknetCoreContract};
+use crate::utils::*;
+use crate::EthereumStateUpdate;
+
+/// A version of the core contract's event ABI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoreContractAbi {
+    /// `LogStateUpdate(uint256 globalRoot, int256 blockNumber)`, without the
+    /// block hash.
+    Legacy,
+    /// `LogStateUpdate(uint256 globalRoot, int256 blockNumber, uint256
+    /// blockHash)`.
+    Current,
+}
+
+impl CoreContractAbi {
+    /// The topic identifying `LogStateUpdate` events.
+    pub fn state_update_signature(&self) -> B256 {
+        match self {
+            Self::Legacy => LegacyStarknetCoreContract::LogStateUpdate::SIGNATURE_HASH,
+            Self::Current => StarknetCoreContract::LogStateUpdate::SIGNATURE_HASH,
+        }
+    }
+
+    /// The topic identifying `LogStateTransitionFact` events.
+    pub fn state_transition_fact_signature(&self) -> B256 {
+        match self {
+            Self::Legacy => LegacyStarknetCoreContract::LogStateTransitionFact::SIGNATURE_HASH,
+            Self::Current => StarknetCoreContract::LogStateTransitionFact::SIGNATURE_HASH,
+        }
+    }
+
+    /// Decodes a `LogStateUpdate` event.
+    ///
+    /// Legacy events do not include the block hash, which is
+    /// [BlockHash::ZERO] for them.
+    pub fn decode_state_update(&self, log: &Log) -> anyhow::Result<EthereumStateUpdate> {
+        let state_update = match self {
+            Self::Legacy => {
+                let log = log.log_decode::<LegacyStarknetCoreContract::LogStateUpdate>()?;
+                EthereumStateUpdate {
+                    block_number: get_block_number(log.inner.blockNumber),
+                    block_hash: BlockHash::ZERO,
+                    state_root: get_state_root(log.inner.globalRoot),
+                }
+            }
+            Self::Current => {
+                let log = log.log_decode::<StarknetCoreContract::LogStateUpdate>()?;
+                EthereumStateUpdate {
+                    block_number: get_block_number(log.inner.blockNumber),
+                    block_hash: get_block_hash(log.inner.blockHash),
+                    state_root: get_state_root(log.inner.globalRoot),
+                }
+            }
+        };
+        Ok(state_update)
+    }
+
+    /// Decodes a `LogStateTransitionFact` event into the fact.
+    pub fn decode_state_transition_fact(&self, log: &Log) -> anyhow::Result<H256> {
+        let fact = match self {
+            Self::Legacy => {
+                log.log_decode::<LegacyStarknetCoreContract::LogStateTransitionFact>()?
+                    .inner
+                    .stateTransitionFact
+            }
+            Self::Current => {
+                log.log_decode::<StarknetCoreContract::LogStateTransitionFact>()?
+                    .inner
+                    .stateTransitionFact
+            }
+        };
+        Ok(H256::from(fact.0))
+    }
+}
+
+/// The core contract ABI versions in effect over ranges of Ethereum blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbiRegistry {
+    /// The first Ethereum block of each version, in ascending order. The first
+    /// version starts at block 0.
+    versions: Vec<(u64, CoreContractAbi)>,
+}
+
+impl Default for AbiRegistry {
+    /// The current ABI at all Ethereum blocks.
+    fn default() -> Self {
+        Self {
+            versions: vec![(0, CoreContractAbi::Current)],
+        }
+    }
+}
+
+impl AbiRegistry {
+    /// Creates a registry from the first Ethereum block of each ABI version.
+    ///
+    /// The versions must be in ascending order of their first block, starting
+    /// at block 0.
+    pub fn new(versions: Vec<(u64, CoreContractAbi)>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            versions.first().map(|(start, _)| *start) == Some(0),
+            "The first core contract ABI version must start at Ethereum block 0"
+        );
+        anyhow::ensure!(
+            versions.windows(2).all(|w| w[0].0 < w[1].0),
+            "Core contract ABI versions must be in ascending order of their first block"
+        );
+        Ok(Self { versions })
+    }
+
+    /// A registry with the legacy ABI before Ethereum block `upgrade_block`
+    /// and the current ABI from there on.
+    pub fn with_legacy_until(upgrade_block: u64) -> Self {
+        if upgrade_block == 0 {
+            return Self::default();
+        }
+        Self {
+            versions: vec![
+                (0, CoreContractAbi::Legacy),
+                (upgrade_block, CoreContractAbi::Current),
+            ],
+        }
+    }
+
+    /// The ABI in effect at Ethereum block `block`.
+    pub fn at(&self, block: u64) -> CoreContractAbi {
+        let index = self.versions.partition_point(|(start, _)| *start <= block);
+        // The first version starts at block 0, so there is always one.
+        self.versions[index - 1].1
+    }
+
+    /// Splits the Ethereum blocks `from..=to` into the ranges covered by a
+    /// single ABI version.
+    pub fn ranges(&self, from: u64, to: u64) -> Vec<(u64, u64, CoreContractAbi)> {
+        let mut ranges = Vec::new();
+        let mut start = from;
+        while start <= to {
+            let abi = self.at(start);
+            let end = self
+                .versions
+                .iter()
+                .find(|(version_start, _)| *version_start > start)
+                .map_or(to, |(next_start, _)| to.min(next_start - 1));
+            ranges.push((start, end, abi));
+            start = end + 1;
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_are_split_at_upgrades() {
+        let registry = AbiRegistry::with_legacy_until(100);
+
+        assert_eq!(
+            registry.ranges(90, 109),
+            vec![
+                (90, 99, CoreContractAbi::Legacy),
+                (100, 109, CoreContractAbi::Current),
+            ]
+        );
+        assert_eq!(
+            registry.ranges(100, 109),
+            vec![(100, 109, CoreContractAbi::Current)]
+        );
+        assert_eq!(registry.ranges(0, 0), vec![(0, 0, CoreContractAbi::Legacy)]);
+        assert_eq!(registry.ranges(10, 9), vec![]);
+    }
+
+    #[test]
+    fn versions_must_start_at_genesis_and_ascend() {
+        AbiRegistry::new(vec![]).unwrap_err();
+        AbiRegistry::new(vec![(1, CoreContractAbi::Current)]).unwrap_err();
+        AbiRegistry::new(vec![
+            (0, CoreContractAbi::Legacy),
+            (5, CoreContractAbi::Current),
+            (5, CoreContractAbi::Legacy),
+        ])
+        .unwrap_err();
+    }
+
+    #[test]
+    fn signatures_differ_between_versions() {
+        assert_ne!(
+            CoreContractAbi::Legacy.state_update_signature(),
+            CoreContractAbi::Current.state_update_signature()
+        );
+        assert_eq!(
+            CoreContractAbi::Legacy.state_transition_fact_signature(),
+            CoreContractAbi::Current.state_transition_fact_signature()
+        );
+    }
+}
@@ -1,11 +1,11 @@
-use std::collections::BTreeMap;
 use std::future::Future;
+use std::num::NonZeroU64;
 use std::time::Duration;
 
 use alloy::eips::{BlockId, BlockNumberOrTag, RpcBlockHash};
 use alloy::primitives::{Address, B256};
 use alloy::providers::{Provider, ProviderBuilder, WsConnect};
-use alloy::rpc::types::Log;
+use alloy::rpc::types::{Filter, Log};
 use anyhow::Context;
 use futures::StreamExt;
 use pathfinder_common::{BlockHash, BlockNumber, EthereumChain, L1ToL2MessageLog, StateCommitment};
@@ -16,9 +16,12 @@ use tokio::select;
 
 use crate::utils::*;
 
+mod abi;
 mod starknet;
 mod utils;
 
+pub use abi::{AbiRegistry, CoreContractAbi};
+
 /// Starknet core contract addresses
 pub mod core_addr {
     use const_decoder::Decoder;
@@ -41,6 +44,9 @@ pub mod core_addr {
 pub enum EthereumEvent {
     StateUpdate(EthereumStateUpdate),
     MessageLog(L1ToL2MessageLog),
+    /// All state updates up to and including this Ethereum block have been
+    /// emitted.
+    ScannedTo(u64),
 }
 
 /// State update from Ethereum
@@ -60,6 +66,7 @@ pub trait EthereumApi {
         &mut self,
         address: &H160,
         poll_interval: Duration,
+        scan: LogScan,
         callback: F,
     ) -> anyhow::Result<()>
     where
@@ -67,11 +74,21 @@ pub trait EthereumApi {
         Fut: Future<Output = ()> + Send + 'static;
 }
 
+/// Where to resume scanning for state update logs and how many Ethereum blocks
+/// to scan per request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogScan {
+    /// The last Ethereum block which has been scanned, or `None` to start at
+    /// the current finalized block.
+    pub scanned_to: Option<u64>,
+    pub chunk_size: NonZeroU64,
+}
+
 /// Ethereum client
 #[derive(Clone, Debug)]
 pub struct EthereumClient {
     url: Url,
-    pending_state_updates: BTreeMap<u64, EthereumStateUpdate>,
+    abi_registry: AbiRegistry,
 }
 
 impl EthereumClient {
@@ -79,7 +96,7 @@ impl EthereumClient {
     pub fn new<U: IntoUrl>(url: U) -> anyhow::Result<Self> {
         Ok(Self {
             url: url.into_url()?,
-            pending_state_updates: BTreeMap::new(),
+            abi_registry: AbiRegistry::default(),
         })
     }
 
@@ -91,6 +108,12 @@ impl EthereumClient {
         Self::new(url)
     }
 
+    /// Sets the core contract ABI versions used to decode state update logs.
+    pub fn with_abi_registry(mut self, abi_registry: AbiRegistry) -> Self {
+        self.abi_registry = abi_registry;
+        self
+    }
+
     /// Returns the hash of the last finalized block
     async fn get_finalized_block_hash(&self) -> anyhow::Result<H256> {
         // Create a WebSocket connection
@@ -114,10 +137,18 @@ impl EthereumApi for EthereumClient {
     /// Listens for Ethereum events and notifies the caller using the provided
     /// callback. State updates will only be emitted once they belong to a
     /// finalized block.
+    ///
+    /// State update logs are scanned in chunks from the block following
+    /// `scan.scanned_to` up to the finalized block, and each scanned chunk is
+    /// reported with [EthereumEvent::ScannedTo] so that the caller can resume
+    /// from there after a restart. Logs are requested and decoded using the
+    /// core contract ABI in effect at their Ethereum block, as set with
+    /// [EthereumClient::with_abi_registry].
     async fn listen<F, Fut>(
         &mut self,
         address: &H160,
         poll_interval: Duration,
+        scan: LogScan,
         callback: F,
     ) -> anyhow::Result<()>
     where
@@ -138,12 +169,6 @@ impl EthereumApi for EthereumClient {
             .await?
             .into_stream();
 
-        // Listen for state update events
-        let mut state_updates = provider
-            .subscribe_logs(&core_contract.LogStateUpdate_filter().filter)
-            .await?
-            .into_stream();
-
         // Poll regularly for finalized block number
         let provider_clone = provider.clone();
         let (finalized_block_tx, mut finalized_block_rx) =
@@ -162,26 +187,10 @@ impl EthereumApi for EthereumClient {
             }
         });
 
-        // Add the finalized block stream to the select! macro
+        let mut scanned_to = scan.scanned_to;
+
         loop {
             select! {
-                Some(state_update) = state_updates.next() => {
-                    // Decode the state update
-                    let eth_block = state_update.block_number.expect("missing eth block number");
-                    let state_update: Log<StarknetCoreContract::LogStateUpdate> = state_update.log_decode()?;
-                    let block_number = get_block_number(state_update.inner.blockNumber);
-                    // Add or remove to/from pending state updates accordingly
-                    if !state_update.removed {
-                        let state_update = EthereumStateUpdate {
-                            block_number,
-                            block_hash: get_block_hash(state_update.inner.blockHash),
-                            state_root: get_state_root(state_update.inner.globalRoot),
-                        };
-                        self.pending_state_updates.insert(eth_block, state_update);
-                    } else {
-                        self.pending_state_updates.remove(&eth_block);
-                    }
-                }
                 Some(log) = logs.next() => {
                     // Decode the message
                     let log: Log<StarknetCoreContract::LogMessageToL2> = log.log_decode()?;
@@ -194,16 +203,34 @@ impl EthereumApi for EthereumClient {
                     callback(EthereumEvent::MessageLog(msg)).await;
                 }
                 Some(block_number) = finalized_block_rx.recv() => {
-                    // Collect all state updates up to (and including) the finalized block
-                    let pending_state_updates: Vec<EthereumStateUpdate> = self.pending_state_updates
-                        .range(..=block_number.get())
-                        .map(|(_, &update)| update)
-                        .collect();
-                    // Remove emitted updates from the map
-                    self.pending_state_updates.retain(|&k, _| k > block_number.get());
-                    // Emit the state updates
-                    for state_update in pending_state_updates {
-                        callback(EthereumEvent::StateUpdate(state_update)).await;
+                    let finalized = block_number.get();
+                    // Without a checkpoint there is nothing to catch up on.
+                    let mut from = scanned_to.map_or(finalized, |scanned_to| scanned_to + 1);
+                    // Scan the newly finalized blocks for state updates
+                    while from <= finalized {
+                        let to = finalized.min(from + scan.chunk_size.get() - 1);
+                        // Contract upgrades may change the format of the logs, so each
+                        // range of blocks is scanned using the ABI in effect there.
+                        for (range_from, range_to, abi) in self.abi_registry.ranges(from, to) {
+                            let filter = Filter::new()
+                                .address(address)
+                                .event_signature(abi.state_update_signature())
+                                .from_block(range_from)
+                                .to_block(range_to);
+                            let state_updates = provider
+                                .get_logs(&filter)
+                                .await
+                                .with_context(|| format!("Fetching state update logs of blocks {range_from}..={range_to}"))?;
+                            for state_update in state_updates {
+                                let state_update = abi
+                                    .decode_state_update(&state_update)
+                                    .with_context(|| format!("Decoding {abi:?} state update log"))?;
+                                callback(EthereumEvent::StateUpdate(state_update)).await;
+                            }
+                        }
+                        callback(EthereumEvent::ScannedTo(to)).await;
+                        scanned_to = Some(to);
+                        from = to + 1;
                     }
                 }
             }
@@ -5,6 +5,16 @@ alloy::sol!(
     "abi/starknet_core_contract.json"
 );
 
+alloy::sol! {
+    /// Events of the core contract before block hashes were added to state
+    /// updates.
+    #[allow(missing_docs)]
+    contract LegacyStarknetCoreContract {
+        event LogStateUpdate(uint256 globalRoot, int256 blockNumber);
+        event LogStateTransitionFact(bytes32 stateTransitionFact);
+    }
+}
+
 impl StarknetCoreContract::LogMessageToL2 {
     pub fn message_hash(&self) -> alloy::primitives::U256 {
         let mut hash = alloy::primitives::Keccak256::new();
@@ -0,0 +1,133 @@
+use std::num::NonZeroUsize;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use pathfinder_common::macro_prelude::*;
+use pathfinder_common::{
+    BlockHeader,
+    BlockNumber,
+    CallParam,
+    ChainId,
+    ClassHash,
+    ContractAddress,
+    EntryPoint,
+    StarknetVersion,
+    StateUpdate,
+};
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+use pathfinder_storage::Storage;
+use starknet_gateway_test_fixtures::class_definitions::{
+    CAIRO_1_1_0_BALANCE_CASM_JSON,
+    CAIRO_1_1_0_BALANCE_SIERRA_JSON,
+    ERC20_CONTRACT_DEFINITION,
+    ERC20_CONTRACT_DEFINITION_CLASS_HASH,
+};
+
+const CAIRO_CONTRACT: ContractAddress = contract_address!("0xc0");
+const SIERRA_CONTRACT: ContractAddress = contract_address!("0xc1");
+
+/// Deploys an ERC20 Cairo 0 contract and a Sierra balance contract in the
+/// genesis block.
+fn setup() -> (Storage, BlockHeader) {
+    let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+    let mut db = storage.connection().unwrap();
+    let tx = db.transaction().unwrap();
+
+    let header = BlockHeader::builder()
+        .number(BlockNumber::GENESIS)
+        .starknet_version(StarknetVersion::new(0, 13, 2, 0))
+        .finalize_with_hash(block_hash!("0xb00"));
+    tx.insert_block_header(&header).unwrap();
+
+    let sierra_hash =
+        sierra_hash!("0x0484c163658bcce5f9916f486171ac60143a92897533aa7ff7ac800b16c63311");
+    let casm_hash =
+        casm_hash!("0x0484c163658bcce5f9916f486171ac60143a92897533aa7ff7ac800b16c63311");
+    tx.insert_cairo_class(
+        ERC20_CONTRACT_DEFINITION_CLASS_HASH,
+        ERC20_CONTRACT_DEFINITION,
+    )
+    .unwrap();
+    tx.insert_sierra_class(
+        &sierra_hash,
+        CAIRO_1_1_0_BALANCE_SIERRA_JSON,
+        &casm_hash,
+        CAIRO_1_1_0_BALANCE_CASM_JSON,
+    )
+    .unwrap();
+
+    let state_update = StateUpdate::default()
+        .with_block_hash(header.hash)
+        .with_declared_cairo_class(ERC20_CONTRACT_DEFINITION_CLASS_HASH)
+        .with_declared_sierra_class(sierra_hash, casm_hash)
+        .with_deployed_contract(CAIRO_CONTRACT, ERC20_CONTRACT_DEFINITION_CLASS_HASH)
+        .with_deployed_contract(SIERRA_CONTRACT, ClassHash(sierra_hash.0));
+    tx.insert_state_update(header.number, &state_update)
+        .unwrap();
+    tx.commit().unwrap();
+
+    (storage, header)
+}
+
+fn call(
+    tx: &pathfinder_storage::Transaction<'_>,
+    header: &BlockHeader,
+    contract_address: ContractAddress,
+    entry_point: &[u8],
+    calldata: Vec<CallParam>,
+) {
+    let state = ExecutionState::simulation(
+        tx,
+        ChainId::SEPOLIA_TESTNET,
+        header.clone(),
+        None,
+        L1BlobDataAvailability::Disabled,
+        None,
+    );
+    pathfinder_executor::call(
+        state,
+        contract_address,
+        EntryPoint::hashed(entry_point),
+        calldata,
+    )
+    .unwrap();
+}
+
+fn call_cairo(tx: &pathfinder_storage::Transaction<'_>, header: &BlockHeader) {
+    call(
+        tx,
+        header,
+        CAIRO_CONTRACT,
+        b"balanceOf",
+        vec![call_param!("0x1")],
+    );
+}
+
+fn call_sierra(tx: &pathfinder_storage::Transaction<'_>, header: &BlockHeader) {
+    call(tx, header, SIERRA_CONTRACT, b"get_balance", vec![]);
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let (storage, header) = setup();
+    let mut db = storage.connection().unwrap();
+    let tx = db.transaction().unwrap();
+
+    let mut group = c.benchmark_group("call");
+
+    group.bench_function("cairo", |b| b.iter(|| call_cairo(&tx, &header)));
+    group.bench_function("sierra", |b| b.iter(|| call_sierra(&tx, &header)));
+
+    // With room for a single class definition, every call of a mixed workload
+    // has to load its class from the database.
+    pathfinder_executor::set_class_cache_size(NonZeroUsize::MIN);
+    group.bench_function("mixed_uncached", |b| {
+        b.iter(|| {
+            call_cairo(&tx, &header);
+            call_sierra(&tx, &header);
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
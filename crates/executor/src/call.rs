@@ -5,6 +5,7 @@ use blockifier::execution::entry_point::{CallEntryPoint, EntryPointExecutionCont
 use blockifier::transaction::objects::{DeprecatedTransactionInfo, TransactionInfo};
 use blockifier::versioned_constants::VersionedConstants;
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use pathfinder_common::memory::{self, Subsystem};
 use pathfinder_common::{CallParam, CallResultValue, ContractAddress, EntryPoint};
 use starknet_api::core::PatriciaKey;
 
@@ -18,6 +19,7 @@ pub fn call(
     entry_point_selector: EntryPoint,
     calldata: Vec<CallParam>,
 ) -> Result<Vec<CallResultValue>, CallError> {
+    let _memory = memory::enter(Subsystem::Executor);
     let (mut state, block_context) = execution_state.starknet_state()?;
 
     let contract_address = starknet_api::core::ContractAddress(PatriciaKey::try_from(
@@ -0,0 +1,125 @@
+use std::collections::BTreeSet;
+
+use blockifier::fee::gas_usage::get_da_gas_cost;
+use blockifier::state::cached_state::StateChangesCount;
+
+use crate::types::StateDiff;
+
+/// The gas needed to publish a state diff on L1, in each data availability
+/// mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DataGas {
+    /// L1 gas needed to publish the state diff as calldata.
+    pub calldata_l1_gas: u128,
+    /// L1 data gas needed to publish the state diff in blobs.
+    pub blob_l1_data_gas: u128,
+}
+
+impl DataGas {
+    /// Computes the gas needed to publish `state_diff` the way fee estimation
+    /// does, which only depends on the number of changes.
+    pub fn of_state_diff(state_diff: &StateDiff) -> Self {
+        let count = state_changes_count(state_diff);
+        Self {
+            calldata_l1_gas: get_da_gas_cost(&count, false).l1_gas,
+            blob_l1_data_gas: get_da_gas_cost(&count, true).l1_data_gas,
+        }
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self {
+            calldata_l1_gas: self.calldata_l1_gas.saturating_add(other.calldata_l1_gas),
+            blob_l1_data_gas: self.blob_l1_data_gas.saturating_add(other.blob_l1_data_gas),
+        }
+    }
+}
+
+fn state_changes_count(state_diff: &StateDiff) -> StateChangesCount {
+    let modified_contracts = state_diff
+        .storage_diffs
+        .keys()
+        .chain(state_diff.nonces.keys())
+        .chain(state_diff.deployed_contracts.iter().map(|c| &c.address))
+        .chain(
+            state_diff
+                .replaced_classes
+                .iter()
+                .map(|c| &c.contract_address),
+        )
+        .collect::<BTreeSet<_>>();
+
+    StateChangesCount {
+        n_storage_updates: state_diff.storage_diffs.values().map(Vec::len).sum(),
+        n_class_hash_updates: state_diff.deployed_contracts.len()
+            + state_diff.replaced_classes.len(),
+        // Cairo 0 classes have no compiled class hash.
+        n_compiled_class_hash_updates: state_diff.declared_classes.len(),
+        n_modified_contracts: modified_contracts.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::types::{DeclaredSierraClass, DeployedContract, StorageDiff};
+
+    #[test]
+    fn changes_are_counted_per_contract() {
+        let state_diff = StateDiff {
+            storage_diffs: [
+                (
+                    contract_address!("0x1"),
+                    vec![
+                        StorageDiff {
+                            key: storage_address!("0x10"),
+                            value: storage_value!("0x1"),
+                        },
+                        StorageDiff {
+                            key: storage_address!("0x11"),
+                            value: storage_value!("0x2"),
+                        },
+                    ],
+                ),
+                (
+                    contract_address!("0x2"),
+                    vec![StorageDiff {
+                        key: storage_address!("0x10"),
+                        value: storage_value!("0x3"),
+                    }],
+                ),
+            ]
+            .into(),
+            deployed_contracts: vec![DeployedContract {
+                address: contract_address!("0x2"),
+                class_hash: class_hash!("0x100"),
+            }],
+            deprecated_declared_classes: [class_hash!("0x200")].into(),
+            declared_classes: vec![DeclaredSierraClass {
+                class_hash: sierra_hash!("0x300"),
+                compiled_class_hash: casm_hash!("0x301"),
+            }],
+            nonces: [(contract_address!("0x3"), contract_nonce!("0x1"))].into(),
+            replaced_classes: vec![],
+        };
+
+        assert_eq!(
+            state_changes_count(&state_diff),
+            StateChangesCount {
+                n_storage_updates: 3,
+                n_class_hash_updates: 1,
+                n_compiled_class_hash_updates: 1,
+                n_modified_contracts: 3,
+            }
+        );
+
+        let data_gas = DataGas::of_state_diff(&state_diff);
+        assert!(data_gas.calldata_l1_gas > 0);
+        assert!(data_gas.blob_l1_data_gas > 0);
+        assert!(
+            DataGas::of_state_diff(&StateDiff::default()).blob_l1_data_gas
+                < data_gas.blob_l1_data_gas
+        );
+    }
+}
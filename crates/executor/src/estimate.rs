@@ -1,5 +1,6 @@
 use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transactions::ExecutableTransaction;
+use pathfinder_common::memory::{self, Subsystem};
 
 use super::error::TransactionExecutionError;
 use super::execution_state::ExecutionState;
@@ -10,6 +11,34 @@ pub fn estimate(
     transactions: Vec<Transaction>,
     skip_validate: bool,
 ) -> Result<Vec<FeeEstimate>, TransactionExecutionError> {
+    estimate_impl(execution_state, transactions, skip_validate, false)
+        .map(|fees| fees.into_iter().map(|(fee, _)| fee).collect())
+}
+
+/// Same as [estimate], but each estimate is also priced in the fee token the
+/// transaction does _not_ pay in, using the gas prices of the same block.
+///
+/// The alternate estimate has the same gas amounts as the primary one and
+/// only differs in gas prices, overall fee and unit.
+pub fn estimate_in_both_units(
+    execution_state: ExecutionState<'_>,
+    transactions: Vec<Transaction>,
+    skip_validate: bool,
+) -> Result<Vec<(FeeEstimate, FeeEstimate)>, TransactionExecutionError> {
+    estimate_impl(execution_state, transactions, skip_validate, true).map(|fees| {
+        fees.into_iter()
+            .map(|(fee, alternate)| (fee, alternate.expect("Alternate unit was requested")))
+            .collect()
+    })
+}
+
+fn estimate_impl(
+    execution_state: ExecutionState<'_>,
+    transactions: Vec<Transaction>,
+    skip_validate: bool,
+    alternate_unit: bool,
+) -> Result<Vec<(FeeEstimate, Option<FeeEstimate>)>, TransactionExecutionError> {
+    let _memory = memory::enter(Subsystem::Executor);
     let block_number = execution_state.header.number;
 
     let (mut state, block_context) = execution_state.starknet_state()?;
@@ -46,12 +75,21 @@ pub fn estimate(
 
                 tracing::trace!(actual_fee=%tx_info.transaction_receipt.fee.0, actual_resources=?tx_info.transaction_receipt.resources, "Transaction estimation finished");
 
-                fees.push(FeeEstimate::from_tx_info_and_gas_price(
+                let fee = FeeEstimate::from_tx_info_and_gas_price(
                     &tx_info,
                     block_context.block_info(),
                     fee_type,
                     &minimal_l1_gas_amount_vector,
-                ));
+                );
+                let alternate = alternate_unit.then(|| {
+                    FeeEstimate::from_tx_info_and_gas_price(
+                        &tx_info,
+                        block_context.block_info(),
+                        super::transaction::other_fee_type(fee_type),
+                        &minimal_l1_gas_amount_vector,
+                    )
+                });
+                fees.push((fee, alternate));
             }
             Err(error) => {
                 tracing::debug!(%error, %transaction_idx, "Transaction estimation failed");
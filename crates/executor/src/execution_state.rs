@@ -18,6 +18,8 @@ use starknet_api::core::PatriciaKey;
 
 use super::pending::PendingStateReader;
 use super::state_reader::PathfinderStateReader;
+use crate::fork::Fork;
+use crate::witness::AccessRecorder;
 use crate::IntoStarkFelt;
 
 // NOTE: these are the same for _all_ networks
@@ -26,6 +28,11 @@ pub const ETH_FEE_TOKEN_ADDRESS: ContractAddress =
 pub const STRK_FEE_TOKEN_ADDRESS: ContractAddress =
     contract_address!("0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d");
 
+/// The version of the `blockifier` crate, as pinned in `Cargo.toml`.
+pub const BLOCKIFIER_VERSION: &str = "0.8.0-rc.3";
+
+pub use versioned_constants::BUNDLED_JSON as BUNDLED_VERSIONED_CONSTANTS;
+
 mod versioned_constants {
     use std::borrow::Cow;
     use std::sync::LazyLock;
@@ -46,6 +53,14 @@ mod versioned_constants {
     const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_2: &[u8] =
         include_bytes!("../resources/versioned_constants_13_2.json");
 
+    /// The versioned constants used for past Starknet versions.
+    pub const BUNDLED_JSON: &[&[u8]] = &[
+        BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0,
+        BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1,
+        BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1_1,
+        BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_2,
+    ];
+
     const STARKNET_VERSION_0_13_1: StarknetVersion = StarknetVersion::new(0, 13, 1, 0);
 
     const STARKNET_VERSION_0_13_1_1: StarknetVersion = StarknetVersion::new(0, 13, 1, 1);
@@ -103,6 +118,8 @@ pub struct ExecutionState<'tx> {
     pending_state: Option<Arc<StateUpdate>>,
     allow_use_kzg_data: bool,
     custom_versioned_constants: Option<VersionedConstants>,
+    fork: Option<Fork>,
+    access_recorder: Option<AccessRecorder>,
 }
 
 impl<'tx> ExecutionState<'tx> {
@@ -118,11 +135,13 @@ impl<'tx> ExecutionState<'tx> {
             Some(self.header.number)
         };
 
-        let raw_reader = PathfinderStateReader::new(
+        let mut raw_reader = PathfinderStateReader::new(
             self.transaction,
             block_number,
             self.pending_state.is_some(),
+            self.fork.clone(),
         );
+        raw_reader.access_recorder = self.access_recorder.clone();
         let pending_state_reader = PendingStateReader::new(raw_reader, self.pending_state.clone());
         let mut cached_state = CachedState::new(pending_state_reader);
 
@@ -134,10 +153,17 @@ impl<'tx> ExecutionState<'tx> {
         let old_block_number_and_hash = if self.header.number.get() >= 10 {
             let block_number_whose_hash_becomes_available =
                 pathfinder_common::BlockNumber::new_or_panic(self.header.number.get() - 10);
-            let block_hash = self
+            let block_hash = match self
                 .transaction
                 .block_hash(block_number_whose_hash_becomes_available.into())?
-                .context("Getting historical block hash")?;
+            {
+                Some(block_hash) => Some(block_hash),
+                None => match &self.fork {
+                    Some(fork) => fork.block_hash(block_number_whose_hash_becomes_available)?,
+                    None => None,
+                },
+            }
+            .context("Getting historical block hash")?;
 
             tracing::trace!(%block_number_whose_hash_becomes_available, %block_hash, "Setting historical block hash");
 
@@ -268,6 +294,8 @@ impl<'tx> ExecutionState<'tx> {
             execute_on_parent_state: true,
             allow_use_kzg_data: true,
             custom_versioned_constants,
+            fork: None,
+            access_recorder: None,
         }
     }
 
@@ -287,6 +315,21 @@ impl<'tx> ExecutionState<'tx> {
             execute_on_parent_state: false,
             allow_use_kzg_data: l1_blob_data_availability == L1BlobDataAvailability::Enabled,
             custom_versioned_constants,
+            fork: None,
+            access_recorder: None,
+        }
+    }
+
+    /// Reads the state from `fork` instead of the database, in forking mode.
+    pub fn with_fork(self, fork: Option<Fork>) -> Self {
+        Self { fork, ..self }
+    }
+
+    /// Records the state read from the database into `access_recorder`.
+    pub(crate) fn with_access_recorder(self, access_recorder: AccessRecorder) -> Self {
+        Self {
+            access_recorder: Some(access_recorder),
+            ..self
         }
     }
 }
@@ -0,0 +1,287 @@
+//! Execution against the state of a remote node.
+//!
+//! In forking mode the local database only holds the header of the block the
+//! node forked from. The state at that block, i.e. storage values, nonces,
+//! deployed classes and class definitions, is instead read from a
+//! [RemoteState] when the executor first needs it, and then kept in bounded
+//! least recently used caches for the lifetime of the [Fork]. Parsed class
+//! definitions are kept in the global class cache instead, like the classes
+//! read from the database.
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use cached::{Cached, SizedCache};
+use pathfinder_common::{
+    BlockHash,
+    BlockNumber,
+    CasmHash,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    StorageAddress,
+    StorageValue,
+};
+
+/// A class definition read from a [RemoteState].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemoteClass {
+    /// The definition of a Cairo 0 class, in the format of the feeder gateway.
+    Cairo(Vec<u8>),
+    /// The compiled definition of a Sierra class.
+    Sierra {
+        casm_definition: Vec<u8>,
+        casm_hash: CasmHash,
+    },
+}
+
+/// The state of a remote node at the block the node forked from.
+///
+/// Its methods are called from the blocking threads the executor runs on.
+pub trait RemoteState: Send + Sync {
+    /// Zero for unset storage and missing contracts.
+    fn storage_value(
+        &self,
+        contract_address: ContractAddress,
+        storage_address: StorageAddress,
+    ) -> anyhow::Result<StorageValue>;
+
+    /// Zero for missing contracts.
+    fn nonce(&self, contract_address: ContractAddress) -> anyhow::Result<ContractNonce>;
+
+    /// [None] for missing contracts.
+    fn class_hash(&self, contract_address: ContractAddress) -> anyhow::Result<Option<ClassHash>>;
+
+    /// [None] for undeclared classes.
+    fn class(&self, class_hash: ClassHash) -> anyhow::Result<Option<RemoteClass>>;
+
+    /// The hash of an earlier block, as required by the block hash system
+    /// contract.
+    fn block_hash(&self, block_number: BlockNumber) -> anyhow::Result<Option<BlockHash>>;
+}
+
+/// The number of storage values cached.
+const STORAGE_CACHE_SIZE: usize = 1 << 16;
+/// The number of nonces, class hashes and compiled class hashes cached.
+const CONTRACT_CACHE_SIZE: usize = 1 << 14;
+/// The number of block hashes cached.
+const BLOCK_HASH_CACHE_SIZE: usize = 1024;
+
+/// Caches the state read from a [RemoteState].
+#[derive(Clone)]
+pub struct Fork(Arc<Inner>);
+
+struct Inner {
+    remote: Box<dyn RemoteState>,
+    block_number: BlockNumber,
+    cache: Mutex<Cache>,
+}
+
+struct Cache {
+    storage: SizedCache<(ContractAddress, StorageAddress), StorageValue>,
+    nonces: SizedCache<ContractAddress, ContractNonce>,
+    class_hashes: SizedCache<ContractAddress, Option<ClassHash>>,
+    /// [None] for Cairo 0 and undeclared classes.
+    casm_hashes: SizedCache<ClassHash, Option<CasmHash>>,
+    block_hashes: SizedCache<BlockNumber, Option<BlockHash>>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            storage: SizedCache::with_size(STORAGE_CACHE_SIZE),
+            nonces: SizedCache::with_size(CONTRACT_CACHE_SIZE),
+            class_hashes: SizedCache::with_size(CONTRACT_CACHE_SIZE),
+            casm_hashes: SizedCache::with_size(CONTRACT_CACHE_SIZE),
+            block_hashes: SizedCache::with_size(BLOCK_HASH_CACHE_SIZE),
+        }
+    }
+}
+
+impl Fork {
+    /// Forks from `remote`, whose state is the state at `block_number`.
+    pub fn new(remote: impl RemoteState + 'static, block_number: BlockNumber) -> Self {
+        Self(Arc::new(Inner {
+            remote: Box::new(remote),
+            block_number,
+            cache: Default::default(),
+        }))
+    }
+
+    /// The block the node forked from.
+    pub fn block_number(&self) -> BlockNumber {
+        self.0.block_number
+    }
+
+    pub(crate) fn storage_value(
+        &self,
+        contract_address: ContractAddress,
+        storage_address: StorageAddress,
+    ) -> anyhow::Result<StorageValue> {
+        self.cached(
+            |cache| &mut cache.storage,
+            (contract_address, storage_address),
+            |remote| remote.storage_value(contract_address, storage_address),
+        )
+    }
+
+    pub(crate) fn nonce(&self, contract_address: ContractAddress) -> anyhow::Result<ContractNonce> {
+        self.cached(
+            |cache| &mut cache.nonces,
+            contract_address,
+            |remote| remote.nonce(contract_address),
+        )
+    }
+
+    pub(crate) fn class_hash(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        self.cached(
+            |cache| &mut cache.class_hashes,
+            contract_address,
+            |remote| remote.class_hash(contract_address),
+        )
+    }
+
+    /// Reads a class definition from the remote state. Definitions are not
+    /// cached here, since callers cache the parsed class.
+    pub(crate) fn class(&self, class_hash: ClassHash) -> anyhow::Result<Option<RemoteClass>> {
+        let class = self.0.remote.class(class_hash)?;
+        self.0
+            .cache
+            .lock()
+            .unwrap()
+            .casm_hashes
+            .cache_set(class_hash, casm_hash(class.as_ref()));
+        Ok(class)
+    }
+
+    /// The compiled class hash of a Sierra class, or [None] for Cairo 0 and
+    /// undeclared classes.
+    pub(crate) fn casm_hash(&self, class_hash: ClassHash) -> anyhow::Result<Option<CasmHash>> {
+        self.cached(
+            |cache| &mut cache.casm_hashes,
+            class_hash,
+            |remote| Ok(casm_hash(remote.class(class_hash)?.as_ref())),
+        )
+    }
+
+    pub(crate) fn block_hash(
+        &self,
+        block_number: BlockNumber,
+    ) -> anyhow::Result<Option<BlockHash>> {
+        self.cached(
+            |cache| &mut cache.block_hashes,
+            block_number,
+            |remote| remote.block_hash(block_number),
+        )
+    }
+
+    /// Returns the cached value for `key`, reading it from the remote state if
+    /// it is not cached yet.
+    ///
+    /// The cache is not locked while the remote state is read, so concurrent
+    /// executions may both read the same value.
+    fn cached<K: Eq + Hash + Clone, V: Clone>(
+        &self,
+        map: impl Fn(&mut Cache) -> &mut SizedCache<K, V>,
+        key: K,
+        read: impl FnOnce(&dyn RemoteState) -> anyhow::Result<V>,
+    ) -> anyhow::Result<V> {
+        if let Some(value) = map(&mut self.0.cache.lock().unwrap()).cache_get(&key) {
+            return Ok(value.clone());
+        }
+
+        let value = read(self.0.remote.as_ref())?;
+        map(&mut self.0.cache.lock().unwrap()).cache_set(key, value.clone());
+        Ok(value)
+    }
+}
+
+fn casm_hash(class: Option<&RemoteClass>) -> Option<CasmHash> {
+    match class {
+        Some(RemoteClass::Sierra { casm_hash, .. }) => Some(*casm_hash),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    /// Counts the reads of storage values, which equal their keys, and of
+    /// classes, which are all Sierra classes.
+    #[derive(Clone, Default)]
+    struct CountingState(Arc<AtomicUsize>);
+
+    impl RemoteState for CountingState {
+        fn storage_value(
+            &self,
+            _: ContractAddress,
+            storage_address: StorageAddress,
+        ) -> anyhow::Result<StorageValue> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(StorageValue(storage_address.0))
+        }
+
+        fn nonce(&self, _: ContractAddress) -> anyhow::Result<ContractNonce> {
+            unimplemented!()
+        }
+
+        fn class_hash(&self, _: ContractAddress) -> anyhow::Result<Option<ClassHash>> {
+            unimplemented!()
+        }
+
+        fn class(&self, class_hash: ClassHash) -> anyhow::Result<Option<RemoteClass>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(Some(RemoteClass::Sierra {
+                casm_definition: Vec::new(),
+                casm_hash: CasmHash(class_hash.0),
+            }))
+        }
+
+        fn block_hash(&self, _: BlockNumber) -> anyhow::Result<Option<BlockHash>> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn remote_state_is_read_once() {
+        let reads = CountingState::default();
+        let fork = Fork::new(reads.clone(), BlockNumber::new_or_panic(10));
+
+        for _ in 0..2 {
+            assert_eq!(
+                fork.storage_value(contract_address!("0x1"), storage_address!("0x2"))
+                    .unwrap(),
+                storage_value!("0x2")
+            );
+            assert_eq!(
+                fork.storage_value(contract_address!("0x1"), storage_address!("0x3"))
+                    .unwrap(),
+                storage_value!("0x3")
+            );
+        }
+
+        assert_eq!(reads.0.load(Ordering::Relaxed), 2);
+    }
+    #[test]
+    fn class_reads_cache_the_compiled_class_hash() {
+        let reads = CountingState::default();
+        let fork = Fork::new(reads.clone(), BlockNumber::new_or_panic(10));
+
+        fork.class(class_hash!("0x1")).unwrap();
+        for _ in 0..2 {
+            assert_eq!(
+                fork.casm_hash(class_hash!("0x1")).unwrap(),
+                Some(casm_hash!("0x1"))
+            );
+        }
+
+        assert_eq!(reads.0.load(Ordering::Relaxed), 1);
+    }
+}
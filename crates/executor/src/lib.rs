@@ -1,15 +1,19 @@
 pub(crate) mod call;
 pub(crate) mod class;
+pub(crate) mod data_gas;
 pub(crate) mod error;
 pub(crate) mod estimate;
 pub(crate) mod execution_state;
 pub(crate) mod felt;
+pub(crate) mod fork;
 pub(crate) mod lru_cache;
 pub(crate) mod pending;
 pub(crate) mod simulate;
+pub(crate) mod state_dump;
 pub(crate) mod state_reader;
 pub(crate) mod transaction;
 pub mod types;
+pub(crate) mod witness;
 
 // re-export blockifier transaction type since it's exposed on our API
 pub use blockifier::execution::contract_class::ClassInfo;
@@ -18,14 +22,26 @@ pub use blockifier::transaction::transaction_execution::Transaction;
 pub use blockifier::versioned_constants::VersionedConstants;
 pub use call::call;
 pub use class::{parse_casm_definition, parse_deprecated_class_definition};
+pub use data_gas::DataGas;
 pub use error::{CallError, TransactionExecutionError};
-pub use estimate::estimate;
+pub use estimate::{estimate, estimate_in_both_units};
 pub use execution_state::{
     ExecutionState,
     L1BlobDataAvailability,
+    BLOCKIFIER_VERSION,
+    BUNDLED_VERSIONED_CONSTANTS,
     ETH_FEE_TOKEN_ADDRESS,
     STRK_FEE_TOKEN_ADDRESS,
 };
 pub use felt::{IntoFelt, IntoStarkFelt};
+pub use fork::{Fork, RemoteClass, RemoteState};
+pub use lru_cache::{
+    class_cache_index,
+    invalidate_class_cache,
+    set_class_cache_size,
+    warm_class_cache,
+};
 pub use simulate::{simulate, trace, TraceCache};
+pub use state_dump::{dump_contract_state, CairoVersion, ContractStateDump};
 pub use transaction::transaction_hash;
+pub use witness::{state_accesses, StateAccesses};
@@ -1,15 +1,69 @@
+use std::num::NonZeroUsize;
 use std::sync::{LazyLock, Mutex, MutexGuard};
 
 use blockifier::execution::contract_class::ContractClass;
 use blockifier::state::errors::StateError;
-use blockifier::state::state_api::StateResult;
+use blockifier::state::state_api::{StateReader, StateResult};
 use cached::{Cached, SizedCache};
-use pathfinder_common::BlockNumber;
+use pathfinder_common::memory::{self, Subsystem};
+use pathfinder_common::{BlockNumber, ClassHash};
 use starknet_api::core::ClassHash as StarknetClassHash;
 use tracing::warn;
 
+use crate::felt::{IntoFelt, IntoStarkFelt};
+use crate::state_dump::CairoVersion;
+use crate::state_reader::PathfinderStateReader;
+
 pub static GLOBAL_CACHE: LazyLock<LruContractCache> = LazyLock::new(LruContractCache::new);
 
+pub static CAIRO_VERSION_CACHE: LazyLock<CairoVersionCache> = LazyLock::new(CairoVersionCache::new);
+
+/// Sets the number of contract classes kept in the global class cache.
+///
+/// Any classes cached so far are evicted.
+pub fn set_class_cache_size(size: NonZeroUsize) {
+    GLOBAL_CACHE.resize(size);
+}
+
+/// Evicts classes declared at or after `first_block` from the global class
+/// cache. Called when those blocks are reorged away, since the classes may be
+/// declared again at a different height.
+pub fn invalidate_class_cache(first_block: BlockNumber) {
+    GLOBAL_CACHE.invalidate(first_block);
+}
+
+/// Returns the hashes of the classes in the global class cache, least
+/// recently used first.
+pub fn class_cache_index() -> Vec<ClassHash> {
+    GLOBAL_CACHE.class_hashes()
+}
+
+/// Loads the classes `class_hashes` into the global class cache in order, so
+/// that the last one is the most recently used, and returns the number of
+/// classes loaded.
+///
+/// Used to restore the [class_cache_index] of a previous run. Classes which
+/// are not declared at the latest block are skipped.
+pub fn warm_class_cache<'tx>(
+    transaction: &'tx pathfinder_storage::Transaction<'tx>,
+    class_hashes: &[ClassHash],
+) -> anyhow::Result<usize> {
+    let Some((latest, _)) = transaction.block_id(pathfinder_storage::BlockId::Latest)? else {
+        return Ok(0);
+    };
+
+    let reader = PathfinderStateReader::new(transaction, Some(latest), false, None);
+    let mut loaded = 0;
+    for class_hash in class_hashes {
+        match reader.get_compiled_contract_class(StarknetClassHash(class_hash.0.into_starkfelt())) {
+            Ok(_) => loaded += 1,
+            Err(error) => tracing::debug!(%class_hash, %error, "Not restoring cached class"),
+        }
+    }
+
+    Ok(loaded)
+}
+
 #[derive(Clone)]
 pub struct Entry {
     pub definition: ContractClass,
@@ -25,6 +79,36 @@ impl LruContractCache {
         Self(Mutex::new(SizedCache::with_size(128)))
     }
 
+    fn resize(&self, size: NonZeroUsize) {
+        let mut cache = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *cache = SizedCache::with_size(size.get());
+    }
+
+    fn invalidate(&self, first_block: BlockNumber) {
+        let mut cache = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache.retain(|_, entry| entry.height < first_block);
+    }
+
+    fn class_hashes(&self) -> Vec<ClassHash> {
+        let cache = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Keys are ordered most recently used first.
+        let mut class_hashes = cache
+            .key_order()
+            .map(|class_hash| ClassHash(class_hash.0.into_felt()))
+            .collect::<Vec<_>>();
+        class_hashes.reverse();
+        class_hashes
+    }
+
     fn locked_cache(&self) -> StateResult<MutexGuard<'_, SizedCache<StarknetClassHash, Entry>>> {
         self.0.lock().map_err(|err| {
             warn!("Contract class cache lock is poisoned. Cause: {}.", err);
@@ -42,6 +126,9 @@ impl LruContractCache {
         contract_class: ContractClass,
         block_number: BlockNumber,
     ) -> StateResult<()> {
+        // Cloning the class only bumps a reference count, but growing the cache
+        // allocates, and that is accounted to the cache rather than the caller.
+        let _memory = memory::enter(Subsystem::Caches);
         self.locked_cache()?.cache_set(
             class_hash,
             Entry {
@@ -53,3 +140,30 @@ impl LruContractCache {
         Ok(())
     }
 }
+
+/// Remembers the Cairo version of classes so that loading a class which is not
+/// in [GLOBAL_CACHE] only queries the table its definition is stored in.
+///
+/// The version of a class never changes, so unlike the definitions these
+/// entries are not invalidated on reorgs, and many more of them are kept.
+pub struct CairoVersionCache(Mutex<SizedCache<StarknetClassHash, CairoVersion>>);
+
+impl CairoVersionCache {
+    fn new() -> Self {
+        Self(Mutex::new(SizedCache::with_size(16384)))
+    }
+
+    fn locked_cache(&self) -> MutexGuard<'_, SizedCache<StarknetClassHash, CairoVersion>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn get(&self, class_hash: &StarknetClassHash) -> Option<CairoVersion> {
+        self.locked_cache().cache_get(class_hash).copied()
+    }
+
+    pub fn set(&self, class_hash: StarknetClassHash, cairo_version: CairoVersion) {
+        self.locked_cache().cache_set(class_hash, cairo_version);
+    }
+}
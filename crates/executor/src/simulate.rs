@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
 use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
@@ -7,6 +8,7 @@ use blockifier::state::errors::StateError;
 use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transactions::ExecutableTransaction;
 use cached::{Cached, SizedCache};
+use pathfinder_common::memory::{self, Subsystem};
 use pathfinder_common::{
     BlockHash,
     CasmHash,
@@ -63,13 +65,47 @@ impl From<ExecutionError> for TransactionExecutionError {
 }
 
 #[derive(Debug, Clone)]
-pub struct TraceCache(Arc<Mutex<SizedCache<BlockHash, CacheItem>>>);
+pub struct TraceCache {
+    cache: Arc<Mutex<SizedCache<BlockHash, CacheItem>>>,
+    /// Whether completed traces are kept. If not, the cache only deduplicates
+    /// concurrent traces of the same block.
+    keep_results: bool,
+}
 
 type Traces = Vec<(TransactionHash, TransactionTrace)>;
 
 impl Default for TraceCache {
     fn default() -> Self {
-        Self(Arc::new(Mutex::new(SizedCache::with_size(128))))
+        Self::with_size(NonZeroUsize::new(128).unwrap())
+    }
+}
+
+impl TraceCache {
+    /// A cache which keeps the traces of up to `size` blocks.
+    pub fn with_size(size: NonZeroUsize) -> Self {
+        Self {
+            cache: Arc::new(Mutex::new(SizedCache::with_size(size.get()))),
+            keep_results: true,
+        }
+    }
+
+    /// Removes all cached traces, e.g. after a reorg.
+    ///
+    /// Traces are keyed by block hash, so those of reorged blocks are never
+    /// served for their replacements, but they would otherwise stay in memory
+    /// until evicted.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().cache_clear();
+    }
+
+    /// A cache which does not keep completed traces in memory.
+    ///
+    /// Concurrent requests for the same block still share a single execution.
+    pub fn inflight_only() -> Self {
+        Self {
+            keep_results: false,
+            ..Default::default()
+        }
     }
 }
 
@@ -79,6 +115,7 @@ pub fn simulate(
     skip_validate: bool,
     skip_fee_charge: bool,
 ) -> Result<Vec<TransactionSimulation>, TransactionExecutionError> {
+    let _memory = memory::enter(Subsystem::Executor);
     let block_number = execution_state.header.number;
 
     let (mut state, block_context) = execution_state.starknet_state()?;
@@ -145,10 +182,11 @@ pub fn trace(
     block_hash: BlockHash,
     transactions: Vec<Transaction>,
 ) -> Result<Vec<(TransactionHash, TransactionTrace)>, TransactionExecutionError> {
+    let _memory = memory::enter(Subsystem::Executor);
     let (mut state, block_context) = execution_state.starknet_state()?;
 
     let sender = {
-        let mut cache = cache.0.lock().unwrap();
+        let mut cache = cache.cache.lock().unwrap();
         match cache.cache_get(&block_hash) {
             Some(CacheItem::CachedOk(cached)) => {
                 tracing::trace!(block=%block_hash, "trace cache hit: ok");
@@ -193,15 +231,19 @@ pub fn trace(
                     transaction_index: transaction_idx,
                     error: e.to_string(),
                 };
-                let mut cache = cache.0.lock().unwrap();
+                let mut inner = cache.cache.lock().unwrap();
                 let _ = sender.send(Err(err.clone()));
-                cache.cache_set(block_hash, CacheItem::CachedErr(err.clone()));
+                if cache.keep_results {
+                    inner.cache_set(block_hash, CacheItem::CachedErr(err.clone()));
+                } else {
+                    inner.cache_remove(&block_hash);
+                }
                 err
             })?;
         let state_diff = to_state_diff(&mut tx_state, tx_declared_deprecated_class_hash)
             .inspect_err(|_| {
                 // Remove the cache entry so it's no longer inflight.
-                let mut cache = cache.0.lock().unwrap();
+                let mut cache = cache.cache.lock().unwrap();
                 cache.cache_remove(&block_hash);
             })?;
         tx_state.commit();
@@ -212,9 +254,13 @@ pub fn trace(
 
     // Lock the cache before sending to avoid race conditions between senders and
     // receivers.
-    let mut cache = cache.0.lock().unwrap();
+    let mut inner = cache.cache.lock().unwrap();
     let _ = sender.send(Ok(traces.clone()));
-    cache.cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
+    if cache.keep_results {
+        inner.cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
+    } else {
+        inner.cache_remove(&block_hash);
+    }
     Ok(traces)
 }
 
@@ -0,0 +1,88 @@
+use blockifier::execution::contract_class::ContractClass;
+use blockifier::state::state_api::StateReader;
+use pathfinder_common::memory::{self, Subsystem};
+use pathfinder_common::{
+    CasmHash,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    StorageAddress,
+    StorageValue,
+};
+use starknet_api::core::PatriciaKey;
+
+use super::error::CallError;
+use super::execution_state::ExecutionState;
+use super::felt::{IntoFelt, IntoStarkFelt};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CairoVersion {
+    Zero,
+    One,
+}
+
+/// A contract as blockifier's state reader sees it when executing on top of a
+/// block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractStateDump {
+    /// Zero if the contract is not deployed.
+    pub class_hash: ClassHash,
+    pub nonce: ContractNonce,
+    /// [None] if the contract is not deployed.
+    pub cairo_version: Option<CairoVersion>,
+    /// Only set for Sierra classes.
+    pub compiled_class_hash: Option<CasmHash>,
+    pub storage: Vec<(StorageAddress, StorageValue)>,
+}
+
+/// Reads the state of `contract_address` through the same state reader stack
+/// used for execution, including pending state and the block pre-processing
+/// blockifier applies before executing the first transaction of a block.
+pub fn dump_contract_state(
+    execution_state: ExecutionState<'_>,
+    contract_address: ContractAddress,
+    storage_keys: &[StorageAddress],
+) -> Result<ContractStateDump, CallError> {
+    let _memory = memory::enter(Subsystem::Executor);
+    let (state, _) = execution_state.starknet_state()?;
+
+    let address = starknet_api::core::ContractAddress(PatriciaKey::try_from(
+        contract_address.0.into_starkfelt(),
+    )?);
+
+    let class_hash = state.get_class_hash_at(address)?;
+    let nonce = state.get_nonce_at(address)?;
+
+    let (cairo_version, compiled_class_hash) = if class_hash.0.into_felt().is_zero() {
+        (None, None)
+    } else {
+        match state.get_compiled_contract_class(class_hash)? {
+            ContractClass::V0(_) => (Some(CairoVersion::Zero), None),
+            _ => {
+                let compiled_class_hash = state.get_compiled_class_hash(class_hash)?;
+                (
+                    Some(CairoVersion::One),
+                    Some(CasmHash(compiled_class_hash.0.into_felt())),
+                )
+            }
+        }
+    };
+
+    let storage = storage_keys
+        .iter()
+        .map(|key| {
+            let storage_key =
+                starknet_api::state::StorageKey(PatriciaKey::try_from(key.0.into_starkfelt())?);
+            let value = state.get_storage_at(address, storage_key)?;
+            Ok((*key, StorageValue(value.into_felt())))
+        })
+        .collect::<Result<Vec<_>, CallError>>()?;
+
+    Ok(ContractStateDump {
+        class_hash: ClassHash(class_hash.0.into_felt()),
+        nonce: ContractNonce(nonce.0.into_felt()),
+        cairo_version,
+        compiled_class_hash,
+        storage,
+    })
+}
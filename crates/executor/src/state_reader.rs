@@ -6,7 +6,11 @@ use starknet_api::StarknetApiError;
 use starknet_types_core::felt::Felt as CoreFelt;
 
 use super::felt::{IntoFelt, IntoStarkFelt};
-use crate::lru_cache::GLOBAL_CACHE;
+use crate::class::{parse_casm_definition, parse_deprecated_class_definition};
+use crate::fork::{Fork, RemoteClass};
+use crate::lru_cache::{CAIRO_VERSION_CACHE, GLOBAL_CACHE};
+use crate::state_dump::CairoVersion;
+use crate::witness::AccessRecorder;
 
 pub(super) struct PathfinderStateReader<'tx> {
     transaction: &'tx pathfinder_storage::Transaction<'tx>,
@@ -15,6 +19,10 @@ pub(super) struct PathfinderStateReader<'tx> {
     // This flag makes it possible to find these classes -- essentially makes the state
     // reader look up classes which are not declared at a canonical block yet.
     ignore_block_number_for_classes: bool,
+    // In forking mode the state is read from the fork instead of the database.
+    fork: Option<Fork>,
+    // Records the state read, to build the witness of an execution.
+    pub access_recorder: Option<AccessRecorder>,
 }
 
 impl<'tx> PathfinderStateReader<'tx> {
@@ -22,11 +30,14 @@ impl<'tx> PathfinderStateReader<'tx> {
         transaction: &'tx pathfinder_storage::Transaction<'tx>,
         block_number: Option<BlockNumber>,
         ignore_block_number_for_classes: bool,
+        fork: Option<Fork>,
     ) -> Self {
         Self {
             transaction,
             block_number,
             ignore_block_number_for_classes,
+            fork,
+            access_recorder: None,
         }
     }
 
@@ -53,72 +64,117 @@ impl<'tx> PathfinderStateReader<'tx> {
             ))
         })?;
 
+        if let Some(fork) = &self.fork {
+            return forked_class(fork, class_hash);
+        }
+
+        // Only look for the class where it is stored if we've seen it before, instead
+        // of always checking for a compiled Sierra class first.
+        let class = match CAIRO_VERSION_CACHE.get(class_hash) {
+            Some(CairoVersion::Zero) => self.cairo_class(block_id, pathfinder_class_hash)?,
+            Some(CairoVersion::One) => self.sierra_class(block_id, pathfinder_class_hash)?,
+            None => match self.sierra_class(block_id, pathfinder_class_hash)? {
+                Some(class) => Some(class),
+                None => self.cairo_class(block_id, pathfinder_class_hash)?,
+            },
+        };
+
+        let Some((definition_block_number, class)) = class else {
+            tracing::trace!("Class definition not found");
+            return Err(StateError::UndeclaredClassHash(*class_hash));
+        };
+
+        let cairo_version = match class {
+            blockifier::execution::contract_class::ContractClass::V0(_) => CairoVersion::Zero,
+            _ => CairoVersion::One,
+        };
+        CAIRO_VERSION_CACHE.set(*class_hash, cairo_version);
+
+        Ok((definition_block_number, class))
+    }
+
+    fn sierra_class(
+        &self,
+        block_id: pathfinder_storage::BlockId,
+        class_hash: ClassHash,
+    ) -> Result<
+        Option<(
+            Option<BlockNumber>,
+            blockifier::execution::contract_class::ContractClass,
+        )>,
+        StateError,
+    > {
         let casm_definition = if self.ignore_block_number_for_classes {
             self.transaction
-                .casm_definition_with_block_number(pathfinder_class_hash)
+                .casm_definition_with_block_number(class_hash)
         } else {
             self.transaction
-                .casm_definition_at_with_block_number(block_id, pathfinder_class_hash)
+                .casm_definition_at_with_block_number(block_id, class_hash)
         };
 
-        if let Some((definition_block_number, casm_definition)) =
+        let Some((definition_block_number, casm_definition)) =
             casm_definition.map_err(map_anyhow_to_state_err)?
-        {
-            let casm_definition = String::from_utf8(casm_definition).map_err(|error| {
-                StateError::StateReadError(format!(
-                    "Class definition is not valid UTF-8: {}",
-                    error
-                ))
-            })?;
+        else {
+            return Ok(None);
+        };
 
-            let casm_class =
-                blockifier::execution::contract_class::ContractClassV1::try_from_json_string(
-                    &casm_definition,
-                )
-                .map_err(StateError::ProgramError)?;
+        let casm_definition = String::from_utf8(casm_definition).map_err(|error| {
+            StateError::StateReadError(format!("Class definition is not valid UTF-8: {}", error))
+        })?;
 
-            return Ok((
-                definition_block_number,
-                blockifier::execution::contract_class::ContractClass::V1(casm_class),
-            ));
-        }
+        let casm_class =
+            blockifier::execution::contract_class::ContractClassV1::try_from_json_string(
+                &casm_definition,
+            )
+            .map_err(StateError::ProgramError)?;
 
+        Ok(Some((
+            definition_block_number,
+            blockifier::execution::contract_class::ContractClass::V1(casm_class),
+        )))
+    }
+
+    fn cairo_class(
+        &self,
+        block_id: pathfinder_storage::BlockId,
+        class_hash: ClassHash,
+    ) -> Result<
+        Option<(
+            Option<BlockNumber>,
+            blockifier::execution::contract_class::ContractClass,
+        )>,
+        StateError,
+    > {
         let definition = if self.ignore_block_number_for_classes {
             self.transaction
-                .class_definition_with_block_number(pathfinder_class_hash)
+                .class_definition_with_block_number(class_hash)
         } else {
             self.transaction
-                .class_definition_at_with_block_number(block_id, pathfinder_class_hash)
+                .class_definition_at_with_block_number(block_id, class_hash)
                 .map(|option| {
                     option.map(|(block_number, definition)| (Some(block_number), definition))
                 })
         };
 
-        if let Some((definition_block_number, definition)) =
+        let Some((definition_block_number, definition)) =
             definition.map_err(map_anyhow_to_state_err)?
-        {
-            let definition = String::from_utf8(definition).map_err(|error| {
-                StateError::StateReadError(format!(
-                    "Class definition is not valid UTF-8: {}",
-                    error
-                ))
-            })?;
-
-            let class =
-                blockifier::execution::contract_class::ContractClassV0::try_from_json_string(
-                    &definition,
-                )
-                .map_err(StateError::ProgramError)?;
+        else {
+            return Ok(None);
+        };
 
-            return Ok((
-                definition_block_number,
-                blockifier::execution::contract_class::ContractClass::V0(class),
-            ));
-        }
+        let definition = String::from_utf8(definition).map_err(|error| {
+            StateError::StateReadError(format!("Class definition is not valid UTF-8: {}", error))
+        })?;
 
-        tracing::trace!("Class definition not found");
+        let class = blockifier::execution::contract_class::ContractClassV0::try_from_json_string(
+            &definition,
+        )
+        .map_err(StateError::ProgramError)?;
 
-        Err(StateError::UndeclaredClassHash(*class_hash))
+        Ok(Some((
+            definition_block_number,
+            blockifier::execution::contract_class::ContractClass::V0(class),
+        )))
     }
 }
 
@@ -142,12 +198,23 @@ impl StateReader for PathfinderStateReader<'_> {
             tracing::trace_span!("get_storage_at", contract_address=%pathfinder_contract_address, %storage_key)
                 .entered();
 
+        if let Some(recorder) = &self.access_recorder {
+            recorder.storage(pathfinder_contract_address, storage_key);
+        }
+
         tracing::trace!("Getting storage value");
 
         let Some(block_id) = self.state_block_id() else {
             return Ok(Felt::ZERO.into_starkfelt());
         };
 
+        if let Some(fork) = &self.fork {
+            let storage_val = fork
+                .storage_value(pathfinder_contract_address, storage_key)
+                .map_err(map_anyhow_to_state_err)?;
+            return Ok(storage_val.0.into_starkfelt());
+        }
+
         let storage_val = self
             .transaction
             .storage_value(block_id, pathfinder_contract_address, storage_key)
@@ -172,12 +239,23 @@ impl StateReader for PathfinderStateReader<'_> {
 
         tracing::trace!("Getting nonce for contract");
 
+        if let Some(recorder) = &self.access_recorder {
+            recorder.contract(pathfinder_contract_address);
+        }
+
         let Some(block_id) = self.state_block_id() else {
             return Ok(starknet_api::core::Nonce(
                 pathfinder_common::ContractNonce::ZERO.0.into_starkfelt(),
             ));
         };
 
+        if let Some(fork) = &self.fork {
+            let nonce = fork
+                .nonce(pathfinder_contract_address)
+                .map_err(map_anyhow_to_state_err)?;
+            return Ok(starknet_api::core::Nonce(nonce.0.into_starkfelt()));
+        }
+
         let nonce = self
             .transaction
             .contract_nonce(pathfinder_contract_address, block_id)
@@ -198,16 +276,23 @@ impl StateReader for PathfinderStateReader<'_> {
 
         tracing::trace!("Getting class hash at contract");
 
+        if let Some(recorder) = &self.access_recorder {
+            recorder.contract(pathfinder_contract_address);
+        }
+
         let Some(block_id) = self.state_block_id() else {
             return Ok(starknet_api::core::ClassHash(
                 ClassHash::ZERO.0.into_starkfelt(),
             ));
         };
 
-        let class_hash = self
-            .transaction
-            .contract_class_hash(block_id, pathfinder_contract_address)
-            .map_err(map_anyhow_to_state_err)?;
+        let class_hash = match &self.fork {
+            Some(fork) => fork.class_hash(pathfinder_contract_address),
+            None => self
+                .transaction
+                .contract_class_hash(block_id, pathfinder_contract_address),
+        }
+        .map_err(map_anyhow_to_state_err)?;
 
         let Some(class_hash) = class_hash else {
             return Ok(starknet_api::core::ClassHash(
@@ -230,6 +315,10 @@ impl StateReader for PathfinderStateReader<'_> {
             tracing::trace_span!("get_compiled_contract_class", class_hash=%pathfinder_class_hash)
                 .entered();
 
+        if let Some(recorder) = &self.access_recorder {
+            recorder.class(pathfinder_class_hash);
+        }
+
         if let Some(entry) = GLOBAL_CACHE.get(&class_hash)? {
             if let Some(reader_block_number) = self.block_number {
                 if entry.height <= reader_block_number {
@@ -257,13 +346,19 @@ impl StateReader for PathfinderStateReader<'_> {
 
         tracing::trace!(%class_hash, "Getting compiled class hash");
 
+        if let Some(recorder) = &self.access_recorder {
+            recorder.class(class_hash);
+        }
+
         let block_id = self.state_block_id().ok_or_else(|| {
             StateError::UndeclaredClassHash(starknet_api::core::ClassHash(
                 class_hash.0.into_starkfelt(),
             ))
         })?;
 
-        let casm_hash = if self.ignore_block_number_for_classes {
+        let casm_hash = if let Some(fork) = &self.fork {
+            fork.casm_hash(class_hash)
+        } else if self.ignore_block_number_for_classes {
             self.transaction.casm_hash(class_hash)
         } else {
             self.transaction.casm_hash_at(block_id, class_hash)
@@ -279,6 +374,39 @@ impl StateReader for PathfinderStateReader<'_> {
     }
 }
 
+/// Reads a class from the fork, as declared at the block it forked from.
+///
+/// The class is reported as declared at that block, so that the caller keeps
+/// it in [GLOBAL_CACHE] and later reads at or after the block do not fetch and
+/// parse it again.
+fn forked_class(
+    fork: &Fork,
+    class_hash: &starknet_api::core::ClassHash,
+) -> Result<
+    (
+        Option<BlockNumber>,
+        blockifier::execution::contract_class::ContractClass,
+    ),
+    StateError,
+> {
+    let Some(class) = fork
+        .class(ClassHash(class_hash.0.into_felt()))
+        .map_err(map_anyhow_to_state_err)?
+    else {
+        return Err(StateError::UndeclaredClassHash(*class_hash));
+    };
+
+    let class = match class {
+        RemoteClass::Cairo(definition) => parse_deprecated_class_definition(definition),
+        RemoteClass::Sierra {
+            casm_definition, ..
+        } => parse_casm_definition(casm_definition),
+    }
+    .map_err(map_anyhow_to_state_err)?;
+
+    Ok((Some(fork.block_number()), class))
+}
+
 fn map_anyhow_to_state_err(error: anyhow::Error) -> StateError {
     tracing::error!(%error, "Internal error in execution state reader");
     StateError::StateReadError(error.to_string())
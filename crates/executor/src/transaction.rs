@@ -33,3 +33,11 @@ pub fn fee_type(transaction: &Transaction) -> FeeType {
         Transaction::L1HandlerTransaction(tx) => tx.fee_type(),
     }
 }
+
+/// The fee token a transaction paying in `fee_type` does _not_ pay in.
+pub(crate) fn other_fee_type(fee_type: FeeType) -> FeeType {
+    match fee_type {
+        FeeType::Eth => FeeType::Strk,
+        FeeType::Strk => FeeType::Eth,
+    }
+}
@@ -3,11 +3,13 @@ use std::collections::{BTreeMap, HashSet};
 use blockifier::blockifier::block::BlockInfo;
 use blockifier::execution::call_info::OrderedL2ToL1Message;
 use blockifier::transaction::objects::{FeeType, GasVector, TransactionExecutionInfo};
+use pathfinder_common::receipt::L2ToL1Message;
 use pathfinder_common::{
     CasmHash,
     ClassHash,
     ContractAddress,
     ContractNonce,
+    L2ToL1MessagePayloadElem,
     SierraHash,
     StorageAddress,
     StorageValue,
@@ -133,6 +135,64 @@ impl TransactionTrace {
             _ => None,
         }
     }
+
+    pub fn state_diff(&self) -> &StateDiff {
+        match self {
+            TransactionTrace::Declare(trace) => &trace.state_diff,
+            TransactionTrace::DeployAccount(trace) => &trace.state_diff,
+            TransactionTrace::Invoke(trace) => &trace.state_diff,
+            TransactionTrace::L1Handler(trace) => &trace.state_diff,
+        }
+    }
+
+    /// All L2 to L1 messages sent by the transaction, in the order they were
+    /// sent.
+    ///
+    /// Message order is only tracked within each top-level invocation, so the
+    /// invocations are visited in the order they are executed.
+    pub fn l2_to_l1_messages(&self) -> Vec<L2ToL1Message> {
+        let invocations = match self {
+            TransactionTrace::Declare(trace) => {
+                vec![&trace.validate_invocation, &trace.fee_transfer_invocation]
+            }
+            TransactionTrace::DeployAccount(trace) => vec![
+                &trace.constructor_invocation,
+                &trace.validate_invocation,
+                &trace.fee_transfer_invocation,
+            ],
+            TransactionTrace::Invoke(trace) => match &trace.execute_invocation {
+                ExecuteInvocation::FunctionInvocation(execute_invocation) => vec![
+                    &trace.validate_invocation,
+                    execute_invocation,
+                    &trace.fee_transfer_invocation,
+                ],
+                ExecuteInvocation::RevertedReason(_) => {
+                    vec![&trace.validate_invocation, &trace.fee_transfer_invocation]
+                }
+            },
+            TransactionTrace::L1Handler(trace) => vec![&trace.function_invocation],
+        };
+
+        let mut messages = Vec::new();
+        for invocation in invocations.into_iter().flatten() {
+            let mut invocation_messages = Vec::new();
+            invocation.collect_messages(&mut invocation_messages);
+            invocation_messages.sort_by_key(|message| message.order);
+            messages.extend(invocation_messages.into_iter().map(|message| {
+                L2ToL1Message {
+                    from_address: ContractAddress(message.from_address),
+                    payload: message
+                        .payload
+                        .iter()
+                        .copied()
+                        .map(L2ToL1MessagePayloadElem)
+                        .collect(),
+                    to_address: ContractAddress(message.to_address),
+                }
+            }));
+        }
+        messages
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -203,6 +263,15 @@ pub struct FunctionInvocation {
     pub computation_resources: ComputationResources,
 }
 
+impl FunctionInvocation {
+    fn collect_messages<'a>(&'a self, messages: &mut Vec<&'a MsgToL1>) {
+        messages.extend(&self.messages);
+        for call in &self.internal_calls {
+            call.collect_messages(messages);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct MsgToL1 {
     pub order: usize,
@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use blockifier::state::cached_state::CachedState;
+use blockifier::transaction::transactions::ExecutableTransaction;
+use pathfinder_common::memory::{self, Subsystem};
+use pathfinder_common::{ClassHash, ContractAddress, StorageAddress};
+
+use super::error::TransactionExecutionError;
+use super::execution_state::ExecutionState;
+use crate::Transaction;
+
+/// The state read from the database while executing transactions, i.e. the
+/// state which has to be known to execute them again without the database.
+///
+/// State written by an earlier transaction and read by a later one is not
+/// included, as executing the earlier transaction produces it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateAccesses {
+    pub storage: BTreeSet<(ContractAddress, StorageAddress)>,
+    /// Contracts whose class hash or nonce was read.
+    pub contracts: BTreeSet<ContractAddress>,
+    /// Classes whose definition or compiled class hash was read.
+    pub classes: BTreeSet<ClassHash>,
+}
+
+/// Collects the [StateAccesses] of the state reader it is given to.
+#[derive(Clone, Default)]
+pub(crate) struct AccessRecorder(Arc<Mutex<StateAccesses>>);
+
+impl AccessRecorder {
+    pub fn storage(&self, contract_address: ContractAddress, storage_address: StorageAddress) {
+        self.0
+            .lock()
+            .unwrap()
+            .storage
+            .insert((contract_address, storage_address));
+    }
+
+    pub fn contract(&self, contract_address: ContractAddress) {
+        self.0.lock().unwrap().contracts.insert(contract_address);
+    }
+
+    pub fn class(&self, class_hash: ClassHash) {
+        self.0.lock().unwrap().classes.insert(class_hash);
+    }
+
+    fn take(&self) -> StateAccesses {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Executes `transactions` in order and returns the state they read.
+///
+/// Transactions are executed like when tracing, so `execution_state` should
+/// be the state the transactions were executed on. The state read by
+/// blockifier's block pre-processing is included.
+pub fn state_accesses(
+    execution_state: ExecutionState<'_>,
+    transactions: Vec<Transaction>,
+) -> Result<StateAccesses, TransactionExecutionError> {
+    let _memory = memory::enter(Subsystem::Executor);
+    let recorder = AccessRecorder::default();
+    let (mut state, block_context) = execution_state
+        .with_access_recorder(recorder.clone())
+        .starknet_state()?;
+
+    for (transaction_idx, tx) in transactions.into_iter().enumerate() {
+        let _span = tracing::debug_span!("state_accesses", transaction_hash=%super::transaction::transaction_hash(&tx), %transaction_idx).entered();
+
+        let mut tx_state = CachedState::<_>::create_transactional(&mut state);
+        tx.execute(&mut tx_state, &block_context, true, true)
+            .map_err(|e| TransactionExecutionError::new(transaction_idx, e))?;
+        tx_state.commit();
+    }
+
+    Ok(recorder.take())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn recorded_accesses_are_deduplicated() {
+        let recorder = AccessRecorder::default();
+        recorder.storage(contract_address!("0x1"), storage_address!("0x10"));
+        recorder.storage(contract_address!("0x1"), storage_address!("0x10"));
+        recorder.storage(contract_address!("0x1"), storage_address!("0x11"));
+        recorder.contract(contract_address!("0x2"));
+        recorder.contract(contract_address!("0x2"));
+        recorder.class(class_hash!("0x100"));
+
+        let accesses = recorder.clone().take();
+        assert_eq!(
+            accesses,
+            StateAccesses {
+                storage: [
+                    (contract_address!("0x1"), storage_address!("0x10")),
+                    (contract_address!("0x1"), storage_address!("0x11")),
+                ]
+                .into(),
+                contracts: [contract_address!("0x2")].into(),
+                classes: [class_hash!("0x100")].into(),
+            }
+        );
+        assert_eq!(recorder.take(), StateAccesses::default());
+    }
+}
@@ -15,7 +15,9 @@
 use pathfinder_common::{BlockId, ClassHash, TransactionHash};
 use starknet_gateway_types::error::SequencerError;
 
+use crate::endpoints::Endpoints;
 use crate::metrics::{with_metrics, BlockTag, RequestMetadata};
+use crate::recording::Recording;
 
 const X_THROTTLING_BYPASS: &str = "X-Throttling-Bypass";
 
@@ -25,6 +27,10 @@ pub struct Request<'a, S: RequestState> {
     url: reqwest::Url,
     api_key: Option<String>,
     client: &'a reqwest::Client,
+    recording: Option<&'a Recording>,
+    /// The feeder gateway mirrors to rotate between, `url` being a url of the
+    /// feeder gateway itself.
+    endpoints: Option<&'a Endpoints>,
 }
 
 pub mod stage {
@@ -79,11 +85,15 @@ impl<'a> Request<'a, stage::Init> {
         client: &'a reqwest::Client,
         url: reqwest::Url,
         api_key: Option<String>,
+        recording: Option<&'a Recording>,
+        endpoints: Option<&'a Endpoints>,
     ) -> Request<'a, stage::Method> {
         Request {
             url,
             client,
             api_key,
+            recording,
+            endpoints,
             state: stage::Method,
         }
     }
@@ -162,6 +172,8 @@ impl<'a> Request<'a, stage::Method> {
             url: self.url,
             client: self.client,
             api_key: self.api_key,
+            recording: self.recording,
+            endpoints: self.endpoints,
             state: stage::Params {
                 meta: RequestMetadata::new(method),
             },
@@ -220,6 +232,8 @@ impl<'a> Request<'a, stage::Params> {
             url: self.url,
             client: self.client,
             api_key: self.api_key,
+            recording: self.recording,
+            endpoints: self.endpoints,
             state: stage::Final {
                 meta: self.state.meta,
                 retry,
@@ -235,6 +249,12 @@ impl<'a> Request<'a, stage::Final> {
     where
         T: serde::de::DeserializeOwned,
     {
+        if self.recording.is_some() {
+            let bytes = self.get_as_bytes().await?;
+            return serde_json::from_slice(&bytes)
+                .map_err(|e| SequencerError::Recording(format!("Parsing recorded response: {e}")));
+        }
+
         async fn send_request<T: serde::de::DeserializeOwned>(
             url: reqwest::Url,
             api_key: Option<String>,
@@ -254,25 +274,31 @@ impl<'a> Request<'a, stage::Final> {
             .await
         }
 
+        let send = || {
+            self.send_to_endpoint(|url| {
+                send_request(url, self.api_key.clone(), self.client, self.state.meta)
+            })
+        };
+
         match self.state.retry {
-            false => send_request(self.url, self.api_key, self.client, self.state.meta).await,
-            true => {
-                retry0(
-                    || async {
-                        let url = self.url.clone();
-                        let api_key = self.api_key.clone();
-                        send_request(url, api_key, self.client, self.state.meta).await
-                    },
-                    retry_condition,
-                )
-                .await
-            }
+            false => send().await,
+            true => retry0(send, retry_condition).await,
         }
     }
 
     /// Sends the Sequencer request as a REST `GET` operation and returns the
     /// response's bytes.
     pub async fn get_as_bytes(self) -> Result<bytes::Bytes, SequencerError> {
+        match self.recording {
+            Some(recording) => {
+                let url = self.url.clone();
+                recording.get(&url, self.fetch_bytes()).await
+            }
+            None => self.fetch_bytes().await,
+        }
+    }
+
+    async fn fetch_bytes(self) -> Result<bytes::Bytes, SequencerError> {
         async fn get_as_bytes_inner(
             url: reqwest::Url,
             api_key: Option<String>,
@@ -294,22 +320,38 @@ impl<'a> Request<'a, stage::Final> {
             .await
         }
 
+        let send = || {
+            self.send_to_endpoint(|url| {
+                get_as_bytes_inner(url, self.api_key.clone(), self.client, self.state.meta)
+            })
+        };
+
         match self.state.retry {
-            false => get_as_bytes_inner(self.url, self.api_key, self.client, self.state.meta).await,
-            true => {
-                retry0(
-                    || async {
-                        let url = self.url.clone();
-                        let api_key = self.api_key.clone();
-                        get_as_bytes_inner(url, api_key, self.client, self.state.meta).await
-                    },
-                    retry_condition,
-                )
-                .await
-            }
+            false => send().await,
+            true => retry0(send, retry_condition).await,
         }
     }
 
+    /// Sends the request with `send`, to the active feeder gateway endpoint
+    /// if mirrors are configured, and reports the outcome to the endpoint
+    /// rotation.
+    async fn send_to_endpoint<T, Fut>(
+        &self,
+        send: impl FnOnce(reqwest::Url) -> Fut,
+    ) -> Result<T, SequencerError>
+    where
+        Fut: futures::Future<Output = Result<T, SequencerError>>,
+    {
+        let Some(endpoints) = self.endpoints else {
+            return send(self.url.clone()).await;
+        };
+
+        let (index, url) = endpoints.resolve(&self.url);
+        let result = send(url).await;
+        endpoints.report(index, result.as_ref().err());
+        result
+    }
+
     /// Sends the Sequencer request as a REST `POST` operation, in addition to
     /// the specified JSON body. The response is parsed as type `T`.
     ///
@@ -480,7 +522,7 @@ fn retry_condition(e: &SequencerError) -> bool {
 
             true
         }
-        SequencerError::StarknetError(_) => false,
+        SequencerError::StarknetError(_) | SequencerError::Recording(_) => false,
         SequencerError::InvalidStarknetErrorVariant => {
             error!(reason=%e, "Request failed, retrying");
             true
@@ -0,0 +1,200 @@
+//! Rotation between the feeder gateway and its mirrors.
+//!
+//! Requests go to the active endpoint, which starts out as the feeder gateway.
+//! After [FAILURE_THRESHOLD] consecutive failed requests the next endpoint in
+//! order of priority becomes active. While a lower priority endpoint is
+//! active, a request is sent to the feeder gateway every [RECHECK_INTERVAL]
+//! to check whether it has recovered, in which case it becomes active again.
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::Url;
+use starknet_gateway_types::error::SequencerError;
+
+/// Number of consecutive failed requests after which the next endpoint
+/// becomes active.
+const FAILURE_THRESHOLD: usize = 5;
+
+/// How often the feeder gateway is checked for recovery while one of its
+/// mirrors is active.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+const METRIC_ACTIVE_ENDPOINT: &str = "gateway_active_endpoint";
+const METRIC_ENDPOINT_SWITCHES: &str = "gateway_endpoint_switches_total";
+
+pub(crate) struct Endpoints {
+    /// The feeder gateway followed by its mirrors, in order of priority.
+    urls: Vec<Url>,
+    state: Mutex<State>,
+}
+
+struct State {
+    active: usize,
+    consecutive_failures: usize,
+    last_switch: Instant,
+}
+
+impl Endpoints {
+    pub fn new(urls: Vec<Url>) -> Self {
+        assert!(!urls.is_empty(), "At least one endpoint is required");
+
+        metrics::register_counter!(METRIC_ENDPOINT_SWITCHES);
+        for (index, url) in urls.iter().enumerate() {
+            let value = if index == 0 { 1.0 } else { 0.0 };
+            metrics::gauge!(METRIC_ACTIVE_ENDPOINT, value, "url" => url.to_string());
+        }
+
+        Self {
+            urls,
+            state: Mutex::new(State {
+                active: 0,
+                consecutive_failures: 0,
+                last_switch: Instant::now(),
+            }),
+        }
+    }
+
+    /// Returns the endpoint the request to `url`, a url of the feeder gateway,
+    /// should be sent to, along with the request's url at that endpoint.
+    pub fn resolve(&self, url: &Url) -> (usize, Url) {
+        let index = {
+            let mut state = self.state.lock().unwrap();
+            if state.active != 0 && state.last_switch.elapsed() >= RECHECK_INTERVAL {
+                // Checks the feeder gateway without switching to it, and leaves
+                // the next check for later should it still be failing.
+                state.last_switch = Instant::now();
+                0
+            } else {
+                state.active
+            }
+        };
+
+        (index, self.rebase(url, index))
+    }
+
+    /// Records the outcome of a request sent to endpoint `index`.
+    ///
+    /// Starknet errors are replies of a working endpoint, and count as
+    /// successful requests.
+    pub fn report(&self, index: usize, error: Option<&SequencerError>) {
+        let mut state = self.state.lock().unwrap();
+        match error {
+            None | Some(SequencerError::StarknetError(_)) => {
+                if index == 0 && state.active != 0 {
+                    tracing::info!(
+                        url=%self.urls[0],
+                        "Feeder gateway recovered, switching back to it"
+                    );
+                    self.switch(&mut state, 0);
+                } else if index == state.active {
+                    state.consecutive_failures = 0;
+                }
+            }
+            Some(error) if index == state.active => {
+                state.consecutive_failures += 1;
+                if state.consecutive_failures >= FAILURE_THRESHOLD && self.urls.len() > 1 {
+                    let next = (state.active + 1) % self.urls.len();
+                    tracing::warn!(
+                        failed=%self.urls[state.active], next=%self.urls[next], %error,
+                        "Feeder gateway endpoint keeps failing, switching to the next one"
+                    );
+                    self.switch(&mut state, next);
+                }
+            }
+            Some(error) => {
+                tracing::debug!(url=%self.urls[index], %error, "Feeder gateway has not recovered");
+            }
+        }
+    }
+
+    fn switch(&self, state: &mut State, index: usize) {
+        metrics::gauge!(METRIC_ACTIVE_ENDPOINT, 0.0, "url" => self.urls[state.active].to_string());
+        metrics::gauge!(METRIC_ACTIVE_ENDPOINT, 1.0, "url" => self.urls[index].to_string());
+        metrics::increment_counter!(METRIC_ENDPOINT_SWITCHES);
+
+        state.active = index;
+        state.consecutive_failures = 0;
+        state.last_switch = Instant::now();
+    }
+
+    /// Replaces the feeder gateway prefix of `url` with that of endpoint
+    /// `index`.
+    fn rebase(&self, url: &Url, index: usize) -> Url {
+        let primary = self.urls[0].as_str().trim_end_matches('/');
+        let Some(suffix) = url.as_str().strip_prefix(primary) else {
+            return url.clone();
+        };
+        let endpoint = self.urls[index].as_str().trim_end_matches('/');
+        Url::parse(&format!("{endpoint}{suffix}")).unwrap_or_else(|_| url.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> Endpoints {
+        Endpoints::new(vec![
+            Url::parse("https://primary.io/feeder_gateway").unwrap(),
+            Url::parse("https://mirror.io/fgw/").unwrap(),
+        ])
+    }
+
+    fn error() -> SequencerError {
+        SequencerError::InvalidStarknetErrorVariant
+    }
+
+    #[test]
+    fn urls_are_rebased() {
+        let endpoints = endpoints();
+        let url = Url::parse("https://primary.io/feeder_gateway/get_block?blockNumber=1").unwrap();
+
+        assert_eq!(endpoints.rebase(&url, 0), url);
+        assert_eq!(
+            endpoints.rebase(&url, 1).as_str(),
+            "https://mirror.io/fgw/get_block?blockNumber=1"
+        );
+    }
+
+    #[test]
+    fn rotates_on_sustained_failures() {
+        let endpoints = endpoints();
+        let url = Url::parse("https://primary.io/feeder_gateway/get_block").unwrap();
+
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            endpoints.report(0, Some(&error()));
+        }
+        // A successful request resets the failure count.
+        endpoints.report(0, None);
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            endpoints.report(0, Some(&error()));
+        }
+        assert_eq!(endpoints.resolve(&url).0, 0);
+
+        endpoints.report(0, Some(&error()));
+        assert_eq!(endpoints.resolve(&url).0, 1);
+    }
+
+    #[test]
+    fn switches_back_once_recovered() {
+        let endpoints = endpoints();
+        let url = Url::parse("https://primary.io/feeder_gateway/get_block").unwrap();
+
+        for _ in 0..FAILURE_THRESHOLD {
+            endpoints.report(0, Some(&error()));
+        }
+        assert_eq!(endpoints.resolve(&url).0, 1);
+
+        // The feeder gateway is rechecked.
+        endpoints.state.lock().unwrap().last_switch -= RECHECK_INTERVAL;
+        assert_eq!(endpoints.resolve(&url).0, 0);
+        endpoints.report(0, Some(&error()));
+        assert_eq!(endpoints.resolve(&url).0, 1);
+
+        // The feeder gateway is rechecked.
+        endpoints.state.lock().unwrap().last_switch -= RECHECK_INTERVAL;
+        assert_eq!(endpoints.resolve(&url).0, 0);
+        endpoints.report(0, None);
+        assert_eq!(endpoints.resolve(&url).0, 0);
+    }
+}
@@ -19,7 +19,12 @@ use starknet_gateway_types::trace::{BlockTrace, TransactionTrace};
 use starknet_gateway_types::{reply, request};
 
 mod builder;
+mod endpoints;
 mod metrics;
+mod recording;
+
+pub use metrics::failed_requests_total;
+pub use recording::{Recording, RecordingMode};
 
 #[allow(unused_variables)]
 #[mockall::automock]
@@ -230,6 +235,13 @@ pub struct Client {
     /// Api key added to each request as a value for 'X-Throttling-Bypass'
     /// header.
     api_key: Option<String>,
+    /// Records or replays feeder gateway responses, see
+    /// [with_recording](Client::with_recording).
+    recording: Option<std::sync::Arc<Recording>>,
+    /// Rotates feeder gateway requests between the feeder gateway and its
+    /// mirrors, see
+    /// [with_feeder_gateway_mirrors](Client::with_feeder_gateway_mirrors).
+    feeder_gateway_endpoints: Option<std::sync::Arc<endpoints::Endpoints>>,
 }
 
 impl Client {
@@ -280,6 +292,8 @@ impl Client {
             feeder_gateway,
             retry: true,
             api_key: None,
+            recording: None,
+            feeder_gateway_endpoints: None,
         })
     }
 
@@ -290,6 +304,40 @@ impl Client {
         self
     }
 
+    /// Records feeder gateway responses to disk, or replays previously
+    /// recorded responses instead of contacting the feeder gateway.
+    ///
+    /// Requests to the gateway, i.e. transaction submissions, are not
+    /// affected.
+    pub fn with_recording(self, recording: Recording) -> Self {
+        Self {
+            recording: Some(std::sync::Arc::new(recording)),
+            ..self
+        }
+    }
+
+    /// Adds mirrors of the feeder gateway, in order of priority, which feeder
+    /// gateway requests are sent to instead once the feeder gateway keeps
+    /// failing.
+    ///
+    /// The next endpoint becomes active after a few consecutive failed
+    /// requests, and the feeder gateway is checked for recovery every few
+    /// minutes. The active endpoint is logged and exposed by the
+    /// `gateway_active_endpoint` metric.
+    pub fn with_feeder_gateway_mirrors(self, mirrors: Vec<Url>) -> Self {
+        if mirrors.is_empty() {
+            return self;
+        }
+
+        let urls = std::iter::once(self.feeder_gateway.clone())
+            .chain(mirrors)
+            .collect();
+        Self {
+            feeder_gateway_endpoints: Some(std::sync::Arc::new(endpoints::Endpoints::new(urls))),
+            ..self
+        }
+    }
+
     /// Use this method to disable retry logic for all __non write__ requests
     /// when testing.
     pub fn disable_retry_for_tests(self) -> Self {
@@ -300,7 +348,13 @@ impl Client {
     }
 
     fn gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
-        builder::Request::builder(&self.inner, self.gateway.clone(), self.api_key.clone())
+        builder::Request::builder(
+            &self.inner,
+            self.gateway.clone(),
+            self.api_key.clone(),
+            None,
+            None,
+        )
     }
 
     fn feeder_gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
@@ -308,6 +362,8 @@ impl Client {
             &self.inner,
             self.feeder_gateway.clone(),
             self.api_key.clone(),
+            self.recording.as_deref(),
+            self.feeder_gateway_endpoints.as_deref(),
         )
     }
 }
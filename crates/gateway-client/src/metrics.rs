@@ -1,4 +1,6 @@
 //! Metrics related utilities
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use futures::Future;
 use pathfinder_common::BlockId;
 
@@ -24,6 +26,15 @@ const REASONS: [&str; 4] = [
     REASON_TIMEOUT,
 ];
 
+/// Process wide count of failed requests, readable without going through the
+/// metrics recorder.
+static FAILED_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the total number of failed gateway requests since startup.
+pub fn failed_requests_total() -> u64 {
+    FAILED_REQUESTS.load(Ordering::Relaxed)
+}
+
 /// Register all sequencer related metrics
 pub fn register() {
     let methods_with_tags = ["get_block", "get_state_update"].into_iter();
@@ -168,6 +179,7 @@ pub async fn with_metrics<T>(
 
     result.inspect_err(|e| {
         increment(METRIC_FAILED_REQUESTS, meta);
+        FAILED_REQUESTS.fetch_add(1, Ordering::Relaxed);
 
         match &e {
             SequencerError::StarknetError(_) => {
@@ -189,7 +201,7 @@ pub async fn with_metrics<T>(
             SequencerError::ReqwestError(e) if e.is_timeout() => {
                 increment_failed(meta, REASON_TIMEOUT);
             }
-            SequencerError::ReqwestError(_) => {}
+            SequencerError::ReqwestError(_) | SequencerError::Recording(_) => {}
         }
     })
 }
@@ -0,0 +1,199 @@
+//! Records feeder gateway responses to disk and replays them, so that sync
+//! issues can be reproduced deterministically.
+//!
+//! Each response is stored as `<dir>/<method>/<query>.<n>.json`, where `n`
+//! counts the requests made with the same method and query. Starknet errors
+//! are stored as `<dir>/<method>/<query>.<n>.error.json` instead. The files
+//! contain the gateway's response body as is.
+//!
+//! When replaying, the `n`th request is answered with the `n`th recorded
+//! response. Once the recorded responses have been used up the last one is
+//! repeated, which keeps polling for e.g. the latest block working.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use starknet_gateway_types::error::{SequencerError, StarknetError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordingMode {
+    /// Responses are fetched from the gateway and written to disk.
+    Record,
+    /// Responses are read from disk, the gateway is never contacted.
+    Replay,
+}
+
+#[derive(Debug)]
+pub struct Recording {
+    dir: PathBuf,
+    mode: RecordingMode,
+    /// Number of requests made so far for each method and query.
+    counters: Mutex<HashMap<PathBuf, usize>>,
+}
+
+impl Recording {
+    pub fn new(dir: PathBuf, mode: RecordingMode) -> Self {
+        Self {
+            dir,
+            mode,
+            counters: Default::default(),
+        }
+    }
+
+    /// Records the response returned by `fetch`, or replays the recorded
+    /// response to `url` without polling `fetch`.
+    pub(crate) async fn get(
+        &self,
+        url: &reqwest::Url,
+        fetch: impl std::future::Future<Output = Result<bytes::Bytes, SequencerError>>,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        let key = self.key(url);
+
+        let sequence = {
+            let mut counters = self.counters.lock().unwrap();
+            let counter = counters.entry(key.clone()).or_default();
+            let sequence = *counter;
+            // When replaying, keep repeating the last response without
+            // advancing past it.
+            if self.mode == RecordingMode::Record || recorded(&key, sequence) {
+                *counter += 1;
+            }
+            sequence
+        };
+
+        match self.mode {
+            RecordingMode::Record => {
+                let response = fetch.await;
+                self.record(&key, sequence, &response).await?;
+                response
+            }
+            RecordingMode::Replay if recorded(&key, sequence) => self.replay(&key, sequence).await,
+            RecordingMode::Replay if sequence > 0 => self.replay(&key, sequence - 1).await,
+            RecordingMode::Replay => Err(SequencerError::Recording(format!(
+                "No response recorded for {}",
+                key.display()
+            ))),
+        }
+    }
+
+    fn key(&self, url: &reqwest::Url) -> PathBuf {
+        let method = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default();
+        let query = url.query().filter(|query| !query.is_empty()).unwrap_or("_");
+
+        self.dir.join(method).join(query)
+    }
+
+    async fn record(
+        &self,
+        key: &Path,
+        sequence: usize,
+        response: &Result<bytes::Bytes, SequencerError>,
+    ) -> Result<(), SequencerError> {
+        let (path, body) = match response {
+            Ok(body) => (path(key, sequence, false), body.to_vec()),
+            Err(SequencerError::StarknetError(error)) => (
+                path(key, sequence, true),
+                serde_json::to_vec(error).expect("StarknetError serializes"),
+            ),
+            // Transport errors are retried, or surface as a failure anyway.
+            Err(_) => return Ok(()),
+        };
+
+        let dir = key.parent().expect("Key has a method directory");
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| SequencerError::Recording(format!("Creating {}: {e}", dir.display())))?;
+        tokio::fs::write(&path, body)
+            .await
+            .map_err(|e| SequencerError::Recording(format!("Writing {}: {e}", path.display())))
+    }
+
+    async fn replay(&self, key: &Path, sequence: usize) -> Result<bytes::Bytes, SequencerError> {
+        let error_path = path(key, sequence, true);
+        if error_path.exists() {
+            let body = read(&error_path).await?;
+            let error = serde_json::from_slice::<StarknetError>(&body).map_err(|e| {
+                SequencerError::Recording(format!("Parsing {}: {e}", error_path.display()))
+            })?;
+            return Err(SequencerError::StarknetError(error));
+        }
+
+        read(&path(key, sequence, false)).await.map(Into::into)
+    }
+}
+
+fn path(key: &Path, sequence: usize, error: bool) -> PathBuf {
+    let mut path = key.as_os_str().to_owned();
+    match error {
+        true => path.push(format!(".{sequence}.error.json")),
+        false => path.push(format!(".{sequence}.json")),
+    }
+    path.into()
+}
+
+fn recorded(key: &Path, sequence: usize) -> bool {
+    path(key, sequence, false).exists() || path(key, sequence, true).exists()
+}
+
+async fn read(path: &Path) -> Result<Vec<u8>, SequencerError> {
+    tokio::fs::read(path)
+        .await
+        .map_err(|e| SequencerError::Recording(format!("Reading {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_gateway_types::error::KnownStarknetErrorCode;
+
+    use super::*;
+
+    fn url(query: &str) -> reqwest::Url {
+        format!("https://example.com/feeder_gateway/get_block?{query}")
+            .parse()
+            .unwrap()
+    }
+
+    async fn unreachable() -> Result<bytes::Bytes, SequencerError> {
+        panic!("Gateway must not be contacted")
+    }
+
+    #[tokio::test]
+    async fn replays_in_order_and_repeats_last() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let recording = Recording::new(dir.path().to_owned(), RecordingMode::Record);
+        for body in ["first", "second"] {
+            let response = recording
+                .get(&url("blockNumber=latest"), async { Ok(body.into()) })
+                .await
+                .unwrap();
+            assert_eq!(response, body);
+        }
+        let error = StarknetError {
+            code: KnownStarknetErrorCode::BlockNotFound.into(),
+            message: "Block not found".to_owned(),
+        };
+        recording
+            .get(&url("blockNumber=10"), async {
+                Err(SequencerError::StarknetError(error.clone()))
+            })
+            .await
+            .unwrap_err();
+
+        let replay = Recording::new(dir.path().to_owned(), RecordingMode::Replay);
+        for expected in ["first", "second", "second"] {
+            let response = replay
+                .get(&url("blockNumber=latest"), unreachable())
+                .await
+                .unwrap();
+            assert_eq!(response, expected);
+        }
+        let result = replay.get(&url("blockNumber=10"), unreachable()).await;
+        assert!(matches!(result, Err(SequencerError::StarknetError(e)) if e == error));
+        let result = replay.get(&url("blockNumber=11"), unreachable()).await;
+        assert!(matches!(result, Err(SequencerError::Recording(_))));
+    }
+}
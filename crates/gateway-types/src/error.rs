@@ -14,6 +14,9 @@ pub enum SequencerError {
     /// not informative enough or bloated
     #[error("error decoding response body: invalid error variant")]
     InvalidStarknetErrorVariant,
+    /// Errors recording or replaying gateway responses.
+    #[error("gateway recording: {0}")]
+    Recording(String),
 }
 
 /// Used for deserializing specific Starknet sequencer error data.
@@ -211,6 +211,35 @@ impl<'tx> StorageCommitmentTree<'tx> {
         MerkleTree::<PedersenHash, 251>::get_proof(root, &storage, address.view_bits())
     }
 
+    /// Returns the address of the contract selected by `target`, or `None` if
+    /// the trie is empty. See [`MerkleTree::sample_leaf`].
+    pub fn sample_contract(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        target: &BitSlice<u8, Msb0>,
+    ) -> anyhow::Result<Option<ContractAddress>> {
+        let root = tx
+            .storage_root_index(block)
+            .context("Querying storage root index")?;
+
+        let Some(root) = root else {
+            return Ok(None);
+        };
+
+        let storage = StorageTrieStorage {
+            tx,
+            block: Some(block),
+        };
+
+        let key = MerkleTree::<PedersenHash, 251>::sample_leaf(root, &storage, target)?;
+        key.map(|key| {
+            Felt::from_bits(&key)
+                .map(ContractAddress)
+                .context("Sampled leaf key is not a valid contract address")
+        })
+        .transpose()
+    }
+
     /// See [`MerkleTree::dfs`]
     pub fn dfs<B, F: FnMut(&InternalNode, &BitSlice<u8, Msb0>) -> ControlFlow<B, Visit>>(
         &mut self,
@@ -220,10 +249,10 @@ impl<'tx> StorageCommitmentTree<'tx> {
     }
 }
 
-struct ContractStorage<'tx> {
-    tx: &'tx Transaction<'tx>,
-    block: Option<BlockNumber>,
-    contract: ContractAddress,
+pub(crate) struct ContractStorage<'tx> {
+    pub(crate) tx: &'tx Transaction<'tx>,
+    pub(crate) block: Option<BlockNumber>,
+    pub(crate) contract: ContractAddress,
 }
 
 impl crate::storage::Storage for ContractStorage<'_> {
@@ -254,9 +283,9 @@ impl crate::storage::Storage for ContractStorage<'_> {
     }
 }
 
-struct StorageTrieStorage<'tx> {
-    tx: &'tx Transaction<'tx>,
-    block: Option<BlockNumber>,
+pub(crate) struct StorageTrieStorage<'tx> {
+    pub(crate) tx: &'tx Transaction<'tx>,
+    pub(crate) block: Option<BlockNumber>,
 }
 
 impl crate::storage::Storage for StorageTrieStorage<'_> {
@@ -22,6 +22,8 @@ use crate::ContractsStorageTree;
 pub struct ContractStateUpdateResult {
     pub state_hash: ContractStateHash,
     pub contract_address: ContractAddress,
+    class_hash: ClassHash,
+    nonce: ContractNonce,
     did_storage_updates: bool,
     trie_update: TrieUpdate,
 }
@@ -30,7 +32,7 @@ impl ContractStateUpdateResult {
     /// Inserts the results of a contract state update into the database.
     ///
     /// The new trie nodes are committed first, then the root node index and the
-    /// contract state hash is persisted.
+    /// contract state hash along with its class hash and nonce are persisted.
     pub fn insert(self, block: BlockNumber, transaction: &Transaction<'_>) -> anyhow::Result<()> {
         // Insert nodes only if we made storage updates.
         if self.did_storage_updates {
@@ -44,7 +46,13 @@ impl ContractStateUpdateResult {
         }
 
         transaction
-            .insert_contract_state_hash(block, self.contract_address, self.state_hash)
+            .insert_contract_state_hash(
+                block,
+                self.contract_address,
+                self.state_hash,
+                self.class_hash,
+                self.nonce,
+            )
             .context("Inserting contract state hash")
     }
 }
@@ -118,6 +126,8 @@ pub fn update_contract_state(
     Ok(ContractStateUpdateResult {
         contract_address,
         state_hash,
+        class_hash,
+        nonce,
         did_storage_updates: !updates.is_empty(),
         trie_update,
     })
@@ -146,19 +156,25 @@ pub fn calculate_contract_state_hash(
 /// Reverts Merkle tree state for a contract.
 ///
 /// Takes Merkle tree state at `head` and applies reverse updates.
+/// Returns the reverted state hash along with the class hash and nonce it was
+/// computed from.
 pub fn revert_contract_state(
     transaction: &Transaction<'_>,
     contract_address: ContractAddress,
     head: BlockNumber,
     target_block: BlockNumber,
     contract_update: ReverseContractUpdate,
-) -> anyhow::Result<ContractStateHash> {
+) -> anyhow::Result<(ContractStateHash, ClassHash, ContractNonce)> {
     tracing::debug!(%contract_address, "Rolling back");
 
     match contract_update {
         ReverseContractUpdate::Deleted => {
             tracing::debug!(%contract_address, "Contract has been deleted");
-            Ok(ContractStateHash::ZERO)
+            Ok((
+                ContractStateHash::ZERO,
+                ClassHash::ZERO,
+                ContractNonce::ZERO,
+            ))
         }
         ReverseContractUpdate::Updated(update) => {
             let class_hash = match update.class {
@@ -221,7 +237,7 @@ pub fn revert_contract_state(
 
             tracing::debug!(%state_hash, %contract_address, "Contract state rolled back");
 
-            Ok(state_hash)
+            Ok((state_hash, class_hash, nonce))
         }
     }
 }
@@ -0,0 +1,351 @@
+//! Reconstruction of the storage tries of blocks whose trie nodes have been
+//! pruned.
+//!
+//! The tries of the snapshot, a later block whose trie nodes are still stored,
+//! are loaded and the state diffs between the two blocks are reverted in
+//! memory, the same way a reorg reverts them. The resulting nodes are never
+//! persisted; proofs are generated by layering them over the stored nodes of
+//! the snapshot.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use bitvec::prelude::Msb0;
+use bitvec::slice::BitSlice;
+use pathfinder_common::hash::PedersenHash;
+use pathfinder_common::state_update::ReverseContractUpdate;
+use pathfinder_common::trie::TrieNode;
+use pathfinder_common::{
+    BlockNumber,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    ContractRoot,
+    ContractStateHash,
+    StorageCommitment,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::{Node, NodeRef, StoredNode, Transaction, TrieUpdate};
+
+use crate::contract::{ContractStorage, StorageTrieStorage};
+use crate::contract_state::calculate_contract_state_hash;
+use crate::storage::Storage;
+use crate::tree::MerkleTree;
+use crate::{ContractsStorageTree, StorageCommitmentTree};
+
+/// Replayed nodes are indexed from here on, so that they cannot be mistaken
+/// for stored nodes.
+const REPLAYED_INDEX_OFFSET: u64 = 1 << 63;
+
+/// The global and contract storage tries of a block, reconstructed from the
+/// tries of a later snapshot block by reverting the state diffs in between.
+pub struct HistoricalStorageTries<'tx> {
+    tx: &'tx Transaction<'tx>,
+    snapshot: BlockNumber,
+    storage_commitment: StorageCommitment,
+    global: ReplayedTrie,
+    contracts: HashMap<ContractAddress, ReplayedContract>,
+}
+
+impl<'tx> HistoricalStorageTries<'tx> {
+    /// Reconstructs the tries of `block` from the stored tries of `snapshot`.
+    pub fn replay(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        snapshot: BlockNumber,
+    ) -> anyhow::Result<Self> {
+        let updates = tx
+            .reverse_contract_updates(snapshot, block)
+            .context("Querying reverse contract updates")?;
+
+        let mut global_tree =
+            StorageCommitmentTree::load(tx, snapshot).context("Loading global storage tree")?;
+        let mut leaves = HashMap::new();
+        let mut contracts = HashMap::new();
+
+        for (contract_address, update) in updates {
+            let contract = ReplayedContract::replay(tx, contract_address, block, snapshot, update)?;
+
+            global_tree
+                .set(contract_address, contract.state_hash)
+                .context("Updating contract state hash in global tree")?;
+            leaves.insert(contract_address.0, contract.state_hash.0);
+            contracts.insert(contract_address, contract);
+        }
+
+        let snapshot_root = tx
+            .storage_root_index(snapshot)
+            .context("Querying storage root index")?;
+        let (storage_commitment, update) = global_tree
+            .commit()
+            .context("Committing global storage tree")?;
+
+        tracing::trace!(%block, %snapshot, contracts = contracts.len(), "Replayed storage tries");
+
+        Ok(Self {
+            tx,
+            snapshot,
+            storage_commitment,
+            global: ReplayedTrie::new(snapshot_root, update, leaves),
+            contracts,
+        })
+    }
+
+    /// The storage commitment of the reconstructed block.
+    pub fn storage_commitment(&self) -> StorageCommitment {
+        self.storage_commitment
+    }
+
+    /// The block the tries were reconstructed from.
+    pub fn snapshot(&self) -> BlockNumber {
+        self.snapshot
+    }
+
+    /// Generates a proof for `address` in the global storage trie. See
+    /// [`MerkleTree::get_proof`].
+    pub fn contract_proof(
+        &self,
+        address: &ContractAddress,
+    ) -> anyhow::Result<Option<Vec<TrieNode>>> {
+        let storage = StorageTrieStorage {
+            tx: self.tx,
+            block: Some(self.snapshot),
+        };
+
+        self.global.get_proof(&storage, address.view_bits())
+    }
+
+    /// Returns the class hash, nonce and storage root of `contract`, or
+    /// `Some(None)` if the contract was not deployed at the block.
+    ///
+    /// Returns `None` if the state of the contract did not change between the
+    /// block and the [snapshot](Self::snapshot), in which case it is the same
+    /// as at the snapshot.
+    pub fn contract_state(
+        &self,
+        contract: ContractAddress,
+    ) -> Option<Option<(ClassHash, ContractNonce, ContractRoot)>> {
+        self.contracts.get(&contract).map(|contract| contract.state)
+    }
+
+    /// Generates a proof for `key` in the storage trie of `contract`. See
+    /// [`MerkleTree::get_proof`].
+    pub fn storage_proof(
+        &self,
+        contract: ContractAddress,
+        key: &BitSlice<u8, Msb0>,
+    ) -> anyhow::Result<Option<Vec<TrieNode>>> {
+        let trie = self
+            .contracts
+            .get(&contract)
+            .and_then(|contract| contract.storage.as_ref());
+
+        match trie {
+            Some(trie) => {
+                let storage = ContractStorage {
+                    tx: self.tx,
+                    block: Some(self.snapshot),
+                    contract,
+                };
+                trie.get_proof(&storage, key)
+            }
+            // The storage did not change since the block.
+            None => ContractsStorageTree::get_proof(self.tx, contract, self.snapshot, key),
+        }
+    }
+}
+
+struct ReplayedContract {
+    /// `None` if the contract was not deployed at the block.
+    state: Option<(ClassHash, ContractNonce, ContractRoot)>,
+    state_hash: ContractStateHash,
+    /// `None` if the storage did not change since the block.
+    storage: Option<ReplayedTrie>,
+}
+
+impl ReplayedContract {
+    /// Reverts the state of a contract in memory, see
+    /// [revert_contract_state](crate::contract_state::revert_contract_state).
+    fn replay(
+        tx: &Transaction<'_>,
+        contract_address: ContractAddress,
+        block: BlockNumber,
+        snapshot: BlockNumber,
+        update: ReverseContractUpdate,
+    ) -> anyhow::Result<Self> {
+        let ReverseContractUpdate::Updated(update) = update else {
+            return Ok(Self {
+                state: None,
+                state_hash: ContractStateHash::ZERO,
+                storage: None,
+            });
+        };
+
+        let class_hash = match update.class {
+            Some(class) => class.class_hash(),
+            // System contracts have no class hash.
+            None if contract_address.is_system_contract() => ClassHash::ZERO,
+            None => tx
+                .contract_class_hash(block.into(), contract_address)
+                .context("Querying contract's class hash")?
+                .context("Contract's class hash is missing")?,
+        };
+
+        let nonce = match update.nonce {
+            Some(nonce) => nonce,
+            None => tx
+                .contract_nonce(contract_address, block.into())
+                .context("Querying contract's nonce")?
+                .unwrap_or_default(),
+        };
+
+        let (root, storage) = if update.storage.is_empty() {
+            let root = tx
+                .contract_root(snapshot, contract_address)
+                .context("Querying contract's root")?
+                .unwrap_or_default();
+
+            (root, None)
+        } else {
+            let mut tree = ContractsStorageTree::load(tx, contract_address, snapshot)
+                .context("Loading contract storage tree")?;
+            let mut leaves = HashMap::new();
+            for (address, value) in update.storage {
+                tree.set(address, value)
+                    .context("Updating contract storage tree")?;
+                leaves.insert(address.0, value.0);
+            }
+
+            let snapshot_root = tx
+                .contract_root_index(snapshot, contract_address)
+                .context("Querying contract root index")?;
+            let (root, update) = tree.commit().context("Committing contract storage tree")?;
+
+            (root, Some(ReplayedTrie::new(snapshot_root, update, leaves)))
+        };
+
+        let state_hash = if contract_address.is_system_contract() && root == ContractRoot::ZERO {
+            // The system contract does not exist while its storage is empty.
+            ContractStateHash::ZERO
+        } else {
+            calculate_contract_state_hash(class_hash, root, nonce)
+        };
+
+        Ok(Self {
+            state: (state_hash != ContractStateHash::ZERO).then_some((class_hash, nonce, root)),
+            state_hash,
+            storage,
+        })
+    }
+}
+
+/// A trie whose changes since the block were reverted in memory.
+struct ReplayedTrie {
+    /// The index of the root node, or `None` if the trie is empty.
+    root: Option<u64>,
+    /// The nodes which differ from the snapshot, the root last.
+    nodes: Vec<(Felt, Node)>,
+    /// The values of the leaves which differ from the snapshot.
+    leaves: HashMap<Felt, Felt>,
+}
+
+impl ReplayedTrie {
+    fn new(snapshot_root: Option<u64>, update: TrieUpdate, leaves: HashMap<Felt, Felt>) -> Self {
+        let root = match update.nodes_added.len() {
+            0 if update.root_commitment == Felt::ZERO => None,
+            // The trie is the same as at the snapshot.
+            0 => snapshot_root,
+            len => Some(REPLAYED_INDEX_OFFSET + len as u64 - 1),
+        };
+
+        Self {
+            root,
+            nodes: update.nodes_added,
+            leaves,
+        }
+    }
+
+    fn get_proof(
+        &self,
+        storage: &impl Storage,
+        key: &BitSlice<u8, Msb0>,
+    ) -> anyhow::Result<Option<Vec<TrieNode>>> {
+        let Some(root) = self.root else {
+            return Ok(None);
+        };
+
+        let storage = ReplayedStorage {
+            trie: self,
+            storage,
+        };
+
+        MerkleTree::<PedersenHash, 251>::get_proof(root, &storage, key)
+    }
+}
+
+/// Serves the nodes and leaves of a [ReplayedTrie], and everything else from
+/// the snapshot's `storage`.
+struct ReplayedStorage<'a, S> {
+    trie: &'a ReplayedTrie,
+    storage: &'a S,
+}
+
+impl<S: Storage> ReplayedStorage<'_, S> {
+    fn replayed(&self, index: u64) -> anyhow::Result<Option<&(Felt, Node)>> {
+        index
+            .checked_sub(REPLAYED_INDEX_OFFSET)
+            .map(|index| {
+                self.trie
+                    .nodes
+                    .get(index as usize)
+                    .context("Replayed node is missing")
+            })
+            .transpose()
+    }
+}
+
+fn node_index(node: NodeRef) -> u64 {
+    match node {
+        NodeRef::StorageIndex(index) => index,
+        NodeRef::Index(index) => REPLAYED_INDEX_OFFSET + index as u64,
+    }
+}
+
+impl<S: Storage> Storage for ReplayedStorage<'_, S> {
+    fn get(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        let Some((_, node)) = self.replayed(index)? else {
+            return self.storage.get(index);
+        };
+
+        let node = match node {
+            Node::Binary { left, right } => StoredNode::Binary {
+                left: node_index(*left),
+                right: node_index(*right),
+            },
+            Node::Edge { child, path } => StoredNode::Edge {
+                child: node_index(*child),
+                path: path.clone(),
+            },
+            Node::LeafBinary => StoredNode::LeafBinary,
+            Node::LeafEdge { path } => StoredNode::LeafEdge { path: path.clone() },
+        };
+
+        Ok(Some(node))
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        match self.replayed(index)? {
+            Some((hash, _)) => Ok(Some(*hash)),
+            None => self.storage.hash(index),
+        }
+    }
+
+    fn leaf(&self, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        let key = Felt::from_bits(path).context("Mapping leaf path to key")?;
+
+        match self.trie.leaves.get(&key) {
+            Some(value) => Ok(Some(*value)),
+            None => self.storage.leaf(path),
+        }
+    }
+}
@@ -5,8 +5,10 @@ pub mod tree;
 
 mod class;
 mod contract;
+mod historical;
 mod transaction;
 
 pub use class::ClassCommitmentTree;
 pub use contract::{ContractsStorageTree, StorageCommitmentTree};
+pub use historical::HistoricalStorageTries;
 pub use transaction::TransactionOrEventTree;
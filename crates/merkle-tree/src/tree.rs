@@ -624,6 +624,59 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
         Ok(Some(nodes))
     }
 
+    /// Returns the key of the leaf reached by following `target` from the
+    /// root, or `None` if the root node is missing.
+    ///
+    /// Binary nodes are descended in the direction of `target`, while edge
+    /// nodes are always followed, even where their path diverges from
+    /// `target`. Every `target` therefore selects exactly one leaf, which makes
+    /// this suitable for deterministic sampling of leaves: the choice can be
+    /// verified from a proof of the returned key.
+    pub fn sample_leaf(
+        root: u64,
+        storage: &impl Storage,
+        target: &BitSlice<u8, Msb0>,
+    ) -> anyhow::Result<Option<BitVec<u8, Msb0>>> {
+        let mut key = BitVec::<u8, Msb0>::with_capacity(HEIGHT);
+
+        let mut next = Some(root);
+        while let Some(index) = next.take() {
+            let Some(node) = storage.get(index).context("Resolving node")? else {
+                return Ok(None);
+            };
+
+            let direction = |key: &BitVec<u8, Msb0>| {
+                target
+                    .get(key.len())
+                    .map(|b| Direction::from(*b))
+                    .context("Target path too short for binary node")
+            };
+
+            match node {
+                StoredNode::Binary { left, right } => {
+                    let direction = direction(&key)?;
+                    key.push(direction.into());
+                    next = match direction {
+                        Direction::Left => Some(left),
+                        Direction::Right => Some(right),
+                    };
+                }
+                StoredNode::Edge { child, path } => {
+                    key.extend_from_bitslice(&path);
+                    next = Some(child);
+                }
+                StoredNode::LeafBinary => {
+                    key.push(direction(&key)?.into());
+                }
+                StoredNode::LeafEdge { path } => {
+                    key.extend_from_bitslice(&path);
+                }
+            }
+        }
+
+        Ok(Some(key))
+    }
+
     /// Traverses from the current root towards destination node.
     /// Returns the list of nodes along the path.
     ///
@@ -2481,4 +2534,47 @@ mod tests {
             assert!(verified.is_none());
         }
     }
+
+    mod sample_leaf {
+        use pathfinder_common::felt;
+
+        use super::*;
+
+        #[test]
+        fn follows_target_at_binary_nodes_and_edges_regardless() {
+            let mut uut = TestTree::empty();
+            let mut storage = TestStorage::default();
+
+            //           (249,0,x3)
+            //               |
+            //           (0, 0, x3)
+            //         /            \
+            //     (0,0,x1)       (1, 1, 5)
+            //      /    \             |
+            //     (2)  (3)           (5)
+
+            for (key, value) in [("0x0", "0x2"), ("0x1", "0x3"), ("0x3", "0x5")] {
+                let key = Felt::from_hex_str(key).unwrap().view_bits().to_owned();
+                uut.set(&storage, key, Felt::from_hex_str(value).unwrap())
+                    .unwrap();
+            }
+            let (_, root_idx) = commit_and_persist_with_pruning(uut, &mut storage);
+
+            for (target, expected) in [
+                (felt!("0x0"), felt!("0x0")),
+                (felt!("0x1"), felt!("0x1")),
+                // The right edge is followed even though its path diverges.
+                (felt!("0x2"), felt!("0x3")),
+                (felt!("0x3"), felt!("0x3")),
+                // The root edge is followed even though its path diverges.
+                (felt!("0x8"), felt!("0x0")),
+                (felt!("0x9"), felt!("0x1")),
+            ] {
+                let key = TestTree::sample_leaf(root_idx, &storage, target.view_bits())
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(key, expected.view_bits(), "target {target}");
+            }
+        }
+    }
 }
@@ -0,0 +1,59 @@
+//! Periodically refreshes the daily chain analytics served by the
+//! `analytics_*` RPC methods.
+//!
+//! Each refresh adds the blocks synced since the previous one, see
+//! [pathfinder_storage::Transaction::refresh_analytics].
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_storage::{Storage, TransactionBehavior};
+
+/// Blocks added per database transaction, so that catching up does not block
+/// syncing for long.
+const BATCH_SIZE: u64 = 1000;
+
+pub async fn run(storage: Storage, refresh_interval: Duration) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(refresh_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let storage = storage.clone();
+        let result = tokio::task::spawn_blocking(move || refresh(&storage))
+            .await
+            .context("Joining analytics refresh task")?;
+
+        match result {
+            Ok(Some(block)) => tracing::debug!(%block, "Refreshed analytics"),
+            Ok(None) => {}
+            Err(error) => tracing::warn!(%error, "Failed to refresh analytics"),
+        }
+    }
+}
+
+/// Refreshes the analytics up to the latest block. Returns the last block
+/// included.
+fn refresh(storage: &Storage) -> anyhow::Result<Option<BlockNumber>> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+
+    loop {
+        // Writing from the start prevents refreshing from blocks which are being
+        // purged by a reorg.
+        let tx = db
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .context("Creating database transaction")?;
+        let previous = tx.analytics_refreshed_to()?;
+        let refreshed_to = tx
+            .refresh_analytics(BATCH_SIZE)
+            .context("Refreshing analytics")?;
+        tx.commit().context("Committing analytics")?;
+
+        if refreshed_to == previous {
+            return Ok(refreshed_to);
+        }
+    }
+}
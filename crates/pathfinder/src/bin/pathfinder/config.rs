@@ -13,6 +13,8 @@ use p2p::libp2p::Multiaddr;
 use pathfinder_common::consts::VERGEN_GIT_DESCRIBE;
 use pathfinder_common::AllowedOrigins;
 use pathfinder_executor::VersionedConstants;
+use pathfinder_lib::webhook::alerts::AlertConfig;
+use pathfinder_lib::webhook::WebhookConfig;
 use pathfinder_storage::JournalMode;
 use reqwest::Url;
 
@@ -55,6 +57,14 @@ Examples:
     )]
     ethereum_url: Url,
 
+    #[arg(
+        long = "ethereum.legacy-abi-until",
+        long_help = "The Ethereum block at which the Starknet core contract was upgraded to include block hashes in its state update events. State update logs of earlier Ethereum blocks are decoded in the legacy format, without block hashes, and matched to local blocks by their state commitment instead. Only needed when catching up on L1 logs from before the upgrade.",
+        value_name = "BLOCK",
+        env = "PATHFINDER_ETHEREUM_LEGACY_ABI_UNTIL"
+    )]
+    ethereum_legacy_abi_until: Option<u64>,
+
     #[arg(
         long = "http-rpc",
         long_help = "HTTP-RPC listening address",
@@ -88,6 +98,17 @@ Examples:
     )]
     rpc_root_version: RpcVersion,
 
+    #[arg(
+        long = "rpc.deprecated-versions",
+        long_help = "Comma separated list of JSON-RPC API versions which are deprecated. These \
+                     are still served, but methods submitting transactions are disabled and \
+                     responses include `Deprecation` and `Warning` headers.",
+        value_name = "VERSION LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_RPC_DEPRECATED_VERSIONS"
+    )]
+    rpc_deprecated_versions: Vec<RpcVersion>,
+
     #[arg(
         long = "rpc.execution-concurrency",
         long_help = "The number of Cairo VM executors that can work concurrently. Defaults to the \
@@ -104,6 +125,19 @@ Examples:
     )]
     monitor_address: Option<SocketAddr>,
 
+    #[arg(
+        long = "monitor.error-telemetry",
+        long_help = "Count panics, execution failures and database errors by anonymized \
+                     fingerprint, and serve the counts at the `/errors` monitoring endpoint. \
+                     Numbers and hashes are removed from error messages, and nothing is sent \
+                     anywhere.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_MONITOR_ERROR_TELEMETRY",
+        value_name = "BOOL"
+    )]
+    error_telemetry: bool,
+
     #[clap(flatten)]
     network: NetworkCli,
 
@@ -116,6 +150,9 @@ Examples:
     )]
     sqlite_wal: bool,
 
+    #[clap(flatten)]
+    sqlite_tuning: SqliteTuningCli,
+
     #[arg(
         long = "max-rpc-connections",
         long_help = "Set the maximum number of connections allowed",
@@ -140,6 +177,34 @@ Examples:
     )]
     l1_poll_interval: std::num::NonZeroU64,
 
+    #[arg(
+        long = "sync.l1-scan-chunk-size",
+        long_help = "The number of Ethereum blocks to fetch Starknet state update logs for in a \
+                     single request when catching up with L1. Lower this if the Ethereum provider \
+                     limits the block range of log queries.",
+        default_value = "2000",
+        env = "PATHFINDER_L1_SCAN_CHUNK_SIZE"
+    )]
+    l1_scan_chunk_size: std::num::NonZeroU64,
+
+    #[arg(
+        long = "sync.class-fetch-concurrency",
+        long_help = "How many class definitions to download from the feeder gateway \
+                     concurrently while syncing, across all blocks being downloaded. Failed \
+                     downloads are retried with exponential backoff.",
+        default_value = "16",
+        env = "PATHFINDER_SYNC_CLASS_FETCH_CONCURRENCY"
+    )]
+    class_fetch_concurrency: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "sync.trie-threads",
+        long_help = "The number of threads used to update Merkle tries while syncing. Defaults to \
+                     half the number of CPUs.",
+        env = "PATHFINDER_SYNC_TRIE_THREADS"
+    )]
+    sync_trie_threads: Option<NonZeroUsize>,
+
     #[arg(
         long = "color",
         long_help = "This flag controls when to use colors in the output logs.",
@@ -173,6 +238,24 @@ Examples:
     #[clap(flatten)]
     websocket: WebsocketConfig,
 
+    #[clap(flatten)]
+    rpc_response_cache: RpcResponseCacheConfig,
+
+    #[clap(flatten)]
+    rpc_upstream: RpcUpstreamConfig,
+
+    #[clap(flatten)]
+    rpc_load_shedding: RpcLoadSheddingConfig,
+
+    #[clap(flatten)]
+    rpc_write_protection: RpcWriteProtectionConfig,
+
+    #[clap(flatten)]
+    rpc_call_stats: RpcCallStatsConfig,
+
+    #[clap(flatten)]
+    analytics: AnalyticsConfig,
+
     #[cfg(not(feature = "p2p"))]
     #[clap(skip)]
     debug: (),
@@ -202,6 +285,17 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     rpc_batch_concurrency_limit: NonZeroUsize,
 
+    #[arg(
+        long = "rpc.batch-cost-budget",
+        long_help = "Limits the total cost of the requests of a batch which are executed \
+                     concurrently. Cheap requests cost 1, event queries 4 and requests which \
+                     execute transactions 10, so that a batch of expensive requests cannot \
+                     monopolize the execution threads. By default only \
+                     `--rpc.batch-concurrency-limit` applies.",
+        env = "PATHFINDER_RPC_BATCH_COST_BUDGET"
+    )]
+    rpc_batch_cost_budget: Option<NonZeroUsize>,
+
     #[arg(
         long = "sync.enable",
         long_help = "Enable syncing the chain",
@@ -246,6 +340,39 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     feeder_gateway_fetch_concurrency: std::num::NonZeroUsize,
 
+    #[arg(
+        long = "gateway.feeder-gateway-mirrors",
+        long_help = "Comma separated list of feeder gateway mirrors, in order of priority. \
+                     Feeder gateway requests are sent to the next mirror once the active \
+                     endpoint keeps failing, and the feeder gateway is checked for recovery \
+                     every 5 minutes.",
+        value_name = "URL LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_GATEWAY_FEEDER_GATEWAY_MIRRORS"
+    )]
+    feeder_gateway_mirrors: Vec<Url>,
+
+    #[arg(
+        long = "record-gateway",
+        long_help = "Debug option which writes all feeder gateway responses to this directory, \
+                     so that they can be replayed with `--replay-gateway`. Use an empty \
+                     directory for each recording.",
+        value_name = "DIR",
+        conflicts_with = "replay_gateway",
+        env = "PATHFINDER_RECORD_GATEWAY"
+    )]
+    record_gateway: Option<PathBuf>,
+
+    #[arg(
+        long = "replay-gateway",
+        long_help = "Debug option which answers feeder gateway requests with the responses \
+                     recorded to this directory using `--record-gateway`, in the order they \
+                     were recorded. The feeder gateway is not contacted.",
+        value_name = "DIR",
+        env = "PATHFINDER_REPLAY_GATEWAY"
+    )]
+    replay_gateway: Option<PathBuf>,
+
     #[arg(
         long = "storage.event-bloom-filter-cache-size",
         long_help = "The number of blocks whose event bloom filters are cached in memory. This \
@@ -274,6 +401,39 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     get_events_max_uncached_bloom_filters_to_load: std::num::NonZeroUsize,
 
+    #[arg(
+        long = "rpc.get-events-max-page-bytes",
+        long_help = "Approximate size in bytes of the events of a `starknet_getEvents` page after \
+                     which the page is cut short and a continuation token returned, even if it \
+                     holds fewer events than the requested chunk size. Keeps responses with \
+                     large events under proxy size limits. Unlimited by default.",
+        env = "PATHFINDER_RPC_GET_EVENTS_MAX_PAGE_BYTES",
+        value_name = "BYTES"
+    )]
+    get_events_max_page_bytes: Option<std::num::NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.receipt-events-limit",
+        long_help = "Maximum number of events included in `starknet_getTransactionReceipt` \
+                     responses. Receipts of transactions with more events are truncated and \
+                     marked with `\"events_truncated\": true`, and the remaining events can be \
+                     fetched with `pathfinder_getTransactionEvents`. Unlimited by default.",
+        env = "PATHFINDER_RPC_RECEIPT_EVENTS_LIMIT",
+        value_name = "EVENTS"
+    )]
+    receipt_events_limit: Option<std::num::NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.trace-cache-size",
+        long_help = "The number of blocks whose transaction traces are cached in memory. Used by \
+                     `starknet_traceBlockTransactions` and `starknet_traceTransaction`. Ignored \
+                     with `--low-memory`, which disables caching of block traces.",
+        env = "PATHFINDER_RPC_TRACE_CACHE_SIZE",
+        value_name = "BLOCKS",
+        default_value = "128"
+    )]
+    trace_cache_size: NonZeroUsize,
+
     #[arg(
         long = "storage.state-tries",
         long_help = "When set to `archive` all historical Merkle trie state is preserved. When set to an integer N, only the last N+1 states of the Merkle tries are kept in the database. \
@@ -285,12 +445,189 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     state_tries: Option<StateTries>,
 
+    #[arg(
+        long = "storage.prune-keep-blocks",
+        long_help = "Only keep the state of the latest N+1 blocks. State updates which are no \
+                     longer needed for that are deleted incrementally in the background, and \
+                     queries for the state of earlier blocks fail with `STATE_PRUNED`. Unless \
+                     `--storage.state-tries` is set, only the Merkle tries of the same blocks \
+                     are kept.",
+        env = "PATHFINDER_STORAGE_PRUNE_KEEP_BLOCKS",
+        value_name = "N",
+        value_parser = parse_prune_keep_blocks
+    )]
+    prune_keep_blocks: Option<u64>,
+
+    #[arg(
+        long = "storage.audit-blocks",
+        long_help = "Number of most recent block headers checked on startup for parent hash and \
+                     block number continuity. When set to `all` every stored header is checked.",
+        env = "PATHFINDER_STORAGE_AUDIT_BLOCKS",
+        value_name = "all | N",
+        default_value = "1000",
+        value_parser = parse_chain_audit
+    )]
+    chain_audit: ChainAudit,
+
+    #[arg(
+        long = "storage.audit-truncate",
+        long_help = "Remove all blocks after the last consistent block if the startup audit of \
+                     the block hash chain finds an inconsistency. Otherwise the node refuses to \
+                     start.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_STORAGE_AUDIT_TRUNCATE",
+        value_name = "BOOL"
+    )]
+    chain_audit_truncate: bool,
+
     #[arg(
         long = "rpc.custom-versioned-constants-json-path",
         long_help = "Path to a JSON file containing the versioned constants to use for execution",
         env = "PATHFINDER_RPC_CUSTOM_VERSIONED_CONSTANTS_JSON_PATH"
     )]
     custom_versioned_constants_path: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.execution-fingerprint",
+        long_help = "Add a `Pathfinder-Execution-Fingerprint` header to responses to calls, fee \
+                     estimates, simulations and traces. The fingerprint identifies the blockifier \
+                     version, the versioned constants and the CASM compiler version, and changes \
+                     whenever execution results may change.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_EXECUTION_FINGERPRINT",
+        value_name = "BOOL"
+    )]
+    rpc_execution_fingerprint: bool,
+
+    #[arg(
+        long = "verified-sources.import-file",
+        long_help = "Path to a JSON file of verified class source metadata exported by a contract \
+                     verification provider. The metadata is imported into the database on startup \
+                     and served by `pathfinder_getVerifiedSource`.",
+        env = "PATHFINDER_VERIFIED_SOURCES_IMPORT_FILE",
+        value_name = "PATH"
+    )]
+    verified_sources_import_file: Option<PathBuf>,
+
+    #[arg(
+        long = "staking.contract-address",
+        long_help = "Address of the L2 staking contract queried by `pathfinder_getStakingEpoch`.",
+        env = "PATHFINDER_STAKING_CONTRACT_ADDRESS",
+        value_name = "ADDRESS",
+        value_parser = parse_contract_address
+    )]
+    staking_contract_address: Option<pathfinder_common::ContractAddress>,
+
+    #[arg(
+        long = "rpc.compression",
+        long_help = "Compress RPC responses with gzip or brotli for clients accepting either in \
+                     their `Accept-Encoding` header",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_COMPRESSION",
+        value_name = "BOOL"
+    )]
+    rpc_compression: bool,
+
+    #[arg(
+        long = "rpc.compression.min-size",
+        long_help = "RPC responses smaller than this many bytes are not compressed",
+        value_name = "BYTES",
+        default_value = "1024",
+        env = "PATHFINDER_RPC_COMPRESSION_MIN_SIZE"
+    )]
+    rpc_compression_min_size: u16,
+
+    #[arg(
+        long = "rpc.compression.level",
+        long_help = "Trade-off between the size of compressed RPC responses and the CPU time \
+                     spent compressing them",
+        value_name = "LEVEL",
+        default_value = "default",
+        env = "PATHFINDER_RPC_COMPRESSION_LEVEL"
+    )]
+    rpc_compression_level: RpcCompressionLevel,
+
+    #[arg(
+        long = "rpc.pending-gas-prices",
+        long_help = "The gas prices of fee estimates and simulations on the pending block. The \
+                     pending block's prices may change whenever it is refreshed, while the \
+                     latest block's only change once per block. Clients of \
+                     `starknet_estimateFee` can also supply their own prices with the \
+                     `pending_gas_prices` parameter.",
+        value_name = "SOURCE",
+        default_value = "pending",
+        env = "PATHFINDER_RPC_PENDING_GAS_PRICES"
+    )]
+    rpc_pending_gas_prices: RpcPendingGasPrices,
+
+    #[arg(
+        long = "rpc.streaming-response-threshold",
+        long_help = "RPC responses larger than this many bytes are sent using chunked transfer \
+                     encoding. Large class definitions can otherwise time out when served through \
+                     a proxy.",
+        value_name = "BYTES",
+        default_value = "1048576",
+        env = "PATHFINDER_RPC_STREAMING_RESPONSE_THRESHOLD"
+    )]
+    rpc_streaming_response_threshold: NonZeroUsize,
+
+    #[arg(
+        long = "low-memory",
+        long_help = "Reduce memory usage for machines with 4 to 8 GB of RAM at the cost of \
+                     performance. Shrinks the contract class and event Bloom filter caches, \
+                     limits database connection pools, concurrency and the number of tries \
+                     updated in parallel, disables caching of block traces and stops reusing \
+                     RPC response buffers larger than 64 KiB.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_LOW_MEMORY",
+        value_name = "BOOL"
+    )]
+    low_memory: bool,
+
+    #[arg(
+        long = "read-only",
+        long_help = "Serve RPC from a database which is synced by another pathfinder instance on \
+                     the same machine, without writing to it. The database must be in WAL mode \
+                     and migrated by an instance of the same version. New blocks are picked up \
+                     by polling the database, and there is no pending block. Syncing, state \
+                     pruning and other background tasks which write to the database are \
+                     disabled.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        conflicts_with_all = [
+            "fork_from",
+            "prune_keep_blocks",
+            "verified_sources_import_file",
+            "primary",
+        ],
+        env = "PATHFINDER_READ_ONLY",
+        value_name = "BOOL"
+    )]
+    read_only: bool,
+
+    #[arg(
+        long = "fork-from",
+        long_help = "Fork from the state of a remote node at a block, given as `RPC_URL@BLOCK` \
+                     where `RPC_URL` is the versioned JSON-RPC endpoint of the node, e.g. \
+                     `https://node.example/rpc/v0_7@650000`. Calls, fee estimates and simulations \
+                     then execute against the state of the remote node, which is read when first \
+                     needed and cached. Syncing is disabled and the database must not contain \
+                     any other blocks.",
+        value_name = "RPC_URL@BLOCK",
+        value_parser = parse_fork_from,
+        env = "PATHFINDER_FORK_FROM"
+    )]
+    fork_from: Option<ForkConfig>,
+
+    #[clap(flatten)]
+    webhook: WebhookCli,
+
+    #[clap(flatten)]
+    replication: ReplicationCli,
 }
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
@@ -320,6 +657,129 @@ pub enum RpcVersion {
     V07,
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum RpcCompressionLevel {
+    Fastest,
+    Default,
+    Best,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum RpcPendingGasPrices {
+    Pending,
+    Latest,
+}
+
+impl From<RpcPendingGasPrices> for pathfinder_rpc::PendingGasPriceSource {
+    fn from(value: RpcPendingGasPrices) -> Self {
+        match value {
+            RpcPendingGasPrices::Pending => Self::Pending,
+            RpcPendingGasPrices::Latest => Self::Latest,
+        }
+    }
+}
+
+impl From<RpcCompressionLevel> for pathfinder_rpc::CompressionLevel {
+    fn from(value: RpcCompressionLevel) -> Self {
+        match value {
+            RpcCompressionLevel::Fastest => Self::Fastest,
+            RpcCompressionLevel::Default => Self::Default,
+            RpcCompressionLevel::Best => Self::Best,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct SqliteTuningCli {
+    #[arg(
+        long = "sqlite.mmap-size",
+        long_help = "Maximum number of bytes of the database file memory mapped by each database \
+                     connection. Defaults to a value based on the total memory of the machine and \
+                     the storage profile, which is `--low-memory`, archive or pruned state tries.",
+        value_name = "BYTES",
+        env = "PATHFINDER_SQLITE_MMAP_SIZE"
+    )]
+    mmap_size: Option<u64>,
+
+    #[arg(
+        long = "sqlite.cache-size",
+        long_help = "Size of the page cache of each database connection. Defaults to a value \
+                     based on the total memory of the machine and the storage profile.",
+        value_name = "BYTES",
+        env = "PATHFINDER_SQLITE_CACHE_SIZE"
+    )]
+    cache_size: Option<u64>,
+
+    #[arg(
+        long = "sqlite.page-size",
+        long_help = "Page size used when creating a new database. Has no effect on existing \
+                     databases. Defaults to a value based on the storage profile.",
+        value_name = "BYTES",
+        value_parser = parse_page_size,
+        env = "PATHFINDER_SQLITE_PAGE_SIZE"
+    )]
+    page_size: Option<u32>,
+
+    #[arg(
+        long = "sqlite.temp-store",
+        long_help = "Where SQLite keeps temporary tables and indices. Defaults to `memory` unless \
+                     `--low-memory` is set.",
+        env = "PATHFINDER_SQLITE_TEMP_STORE"
+    )]
+    temp_store: Option<TempStore>,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum TempStore {
+    File,
+    Memory,
+}
+
+fn parse_page_size(s: &str) -> Result<u32, String> {
+    let page_size: u32 = s.parse().map_err(|_| "Expected a number".to_string())?;
+    if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() {
+        return Err("Expected a power of two between 512 and 65536".to_string());
+    }
+    Ok(page_size)
+}
+
+/// Explicitly configured SQLite settings, which take precedence over the
+/// values picked for the machine.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SqliteTuningConfig {
+    pub mmap_size: Option<u64>,
+    pub cache_size: Option<u64>,
+    pub page_size: Option<u32>,
+    pub temp_store: Option<TempStore>,
+}
+
+impl SqliteTuningConfig {
+    fn parse(cli: SqliteTuningCli) -> Self {
+        Self {
+            mmap_size: cli.mmap_size,
+            cache_size: cli.cache_size,
+            page_size: cli.page_size,
+            temp_store: cli.temp_store,
+        }
+    }
+
+    pub fn apply(
+        self,
+        tuning: pathfinder_storage::SqliteTuning,
+    ) -> pathfinder_storage::SqliteTuning {
+        pathfinder_storage::SqliteTuning {
+            mmap_size: self.mmap_size.unwrap_or(tuning.mmap_size),
+            cache_size: self.cache_size.unwrap_or(tuning.cache_size),
+            page_size: self.page_size.unwrap_or(tuning.page_size),
+            temp_store: match self.temp_store {
+                Some(TempStore::File) => pathfinder_storage::TempStore::File,
+                Some(TempStore::Memory) => pathfinder_storage::TempStore::Memory,
+                None => tuning.temp_store,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StateTries {
     Pruned(u64),
@@ -338,6 +798,79 @@ fn parse_state_tries(s: &str) -> Result<StateTries, String> {
     }
 }
 
+/// The least number of blocks whose state is kept when pruning. Reorgs need
+/// the state before the first reorged block, so they cannot be deeper than
+/// this.
+const MIN_PRUNE_KEEP_BLOCKS: u64 = 64;
+
+fn parse_prune_keep_blocks(s: &str) -> Result<u64, String> {
+    let value: u64 = s.parse().map_err(|_| "Expected a number".to_string())?;
+    if value < MIN_PRUNE_KEEP_BLOCKS {
+        return Err(format!(
+            "Expected at least {MIN_PRUNE_KEEP_BLOCKS}, so that reorgs can be handled"
+        ));
+    }
+    Ok(value)
+}
+
+/// Memory is only accounted for in builds with the `memory-accounting`
+/// feature, the limit would never be reached otherwise.
+fn parse_max_memory(s: &str) -> Result<std::num::NonZeroU64, String> {
+    if !cfg!(feature = "memory-accounting") {
+        return Err("Requires a build with the `memory-accounting` cargo feature".to_string());
+    }
+    s.parse()
+        .map_err(|_| "Expected a positive number".to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainAudit {
+    Recent(u64),
+    Full,
+}
+
+fn parse_chain_audit(s: &str) -> Result<ChainAudit, String> {
+    match s {
+        "all" => Ok(ChainAudit::Full),
+        _ => {
+            let value: u64 = s
+                .parse()
+                .map_err(|_| "Expected either `all` or a number".to_string())?;
+            Ok(ChainAudit::Recent(value))
+        }
+    }
+}
+
+fn parse_contract_address(s: &str) -> Result<pathfinder_common::ContractAddress, String> {
+    pathfinder_crypto::Felt::from_hex_str(s)
+        .ok()
+        .and_then(pathfinder_common::ContractAddress::new)
+        .ok_or_else(|| "Expected a hex encoded contract address".to_string())
+}
+
+fn parse_fork_from(s: &str) -> Result<ForkConfig, String> {
+    // The URL may contain an `@` itself, before the host.
+    let (url, block_number) = s
+        .rsplit_once('@')
+        .ok_or_else(|| "Expected `RPC_URL@BLOCK`".to_string())?;
+    let url = url
+        .parse()
+        .map_err(|error| format!("Invalid RPC URL: {error}"))?;
+    let block_number = block_number
+        .parse::<u64>()
+        .ok()
+        .and_then(pathfinder_common::BlockNumber::new)
+        .ok_or_else(|| "Expected a block number".to_string())?;
+    Ok(ForkConfig { url, block_number })
+}
+
+#[cfg(feature = "p2p")]
+fn parse_public_key(s: &str) -> Result<pathfinder_common::PublicKey, String> {
+    pathfinder_crypto::Felt::from_hex_str(s)
+        .map(pathfinder_common::PublicKey)
+        .map_err(|_| "Expected a hex encoded public key".to_string())
+}
+
 #[derive(clap::Args)]
 struct NetworkCli {
     #[arg(
@@ -490,6 +1023,18 @@ Example:
     )]
     l1_checkpoint_override: Option<String>,
 
+    #[arg(
+        long = "p2p.experimental.attestation-public-keys",
+        long_help = "Comma separated list of public keys whose block header signatures are \
+                     accepted for blocks received over P2P, in addition to the feeder gateway's \
+                     public key.",
+        value_name = "PUBLIC_KEY_LIST",
+        value_delimiter = ',',
+        value_parser = parse_public_key,
+        env = "PATHFINDER_P2P_EXPERIMENTAL_ATTESTATION_PUBLIC_KEYS"
+    )]
+    attestation_public_keys: Vec<pathfinder_common::PublicKey>,
+
     #[arg(
         long = "p2p.experimental.stream-timeout",
         long_help = "Timeout of the request/response-stream protocol.",
@@ -518,6 +1063,19 @@ Example:
     )]
     direct_connection_timeout: u32,
 
+    #[arg(
+        long = "p2p.experimental.header-only",
+        long_help = "Sync and verify only block headers and their L1 confirmations. Transactions, \
+                     receipts, state diffs, classes and the state tries are not synced, so only \
+                     RPC methods that serve block headers and finality are usable. Intended for \
+                     light embedded deployments. Requires syncing from the p2p network, i.e. \
+                     '--p2p.proxy false'.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_EXPERIMENTAL_HEADER_ONLY"
+    )]
+    header_only: bool,
+
     #[arg(
         long = "p2p.experimental.eviction-timeout",
         long_help = "How long to prevent evicted peers from reconnecting.",
@@ -550,6 +1108,184 @@ struct DebugCli {
     restart_delay: u64,
 }
 
+#[derive(clap::Args)]
+struct WebhookCli {
+    #[arg(
+        long = "webhook.url",
+        long_help = "URL to POST notifications about transactions and events matching the \
+                     webhook watchlist to. Requires `webhook.watch-addresses` and/or \
+                     `webhook.watch-event-keys` to be set.",
+        value_name = "URL",
+        value_hint = clap::ValueHint::Url,
+        env = "PATHFINDER_WEBHOOK_URL"
+    )]
+    url: Option<Url>,
+
+    #[arg(
+        long = "webhook.secret",
+        long_help = "Secret used to sign webhook request bodies using HMAC-SHA256. The signature \
+                     is sent in the `X-Pathfinder-Signature` header as `sha256=<hex>`.",
+        value_name = "SECRET",
+        env = "PATHFINDER_WEBHOOK_SECRET"
+    )]
+    secret: Option<String>,
+
+    #[arg(
+        long = "webhook.watch-addresses",
+        long_help = "Comma separated list of contract addresses to watch. Matches transactions \
+                     sent by and events emitted by these addresses.",
+        value_name = "ADDRESS_LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_WEBHOOK_WATCH_ADDRESSES"
+    )]
+    watch_addresses: Vec<String>,
+
+    #[arg(
+        long = "webhook.watch-event-keys",
+        long_help = "Comma separated list of event selectors to watch. Matches events whose first \
+                     key is one of these.",
+        value_name = "KEY_LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_WEBHOOK_WATCH_EVENT_KEYS"
+    )]
+    watch_event_keys: Vec<String>,
+
+    #[arg(
+        long = "webhook.max-retries",
+        long_help = "Maximum number of times delivery of a webhook notification is retried.",
+        value_name = "COUNT",
+        default_value = "5",
+        env = "PATHFINDER_WEBHOOK_MAX_RETRIES"
+    )]
+    max_retries: NonZeroUsize,
+
+    #[arg(
+        long = "webhook.request-timeout",
+        long_help = "Timeout duration for webhook requests.",
+        value_name = "SECONDS",
+        default_value = "10",
+        env = "PATHFINDER_WEBHOOK_REQUEST_TIMEOUT"
+    )]
+    request_timeout: std::num::NonZeroU64,
+
+    #[arg(
+        long = "webhook.alerts.url",
+        long_help = "URL to POST operational alerts to, such as a Slack or Discord incoming \
+                     webhook. Alerts are sent when syncing stalls, a deep reorg happens, the \
+                     database disk is nearly full or gateway requests keep failing.",
+        value_name = "URL",
+        value_hint = clap::ValueHint::Url,
+        env = "PATHFINDER_WEBHOOK_ALERTS_URL"
+    )]
+    alerts_url: Option<Url>,
+
+    #[arg(
+        long = "webhook.alerts.format",
+        long_help = "Request body format of operational alerts.",
+        value_name = "FORMAT",
+        default_value = "slack",
+        env = "PATHFINDER_WEBHOOK_ALERTS_FORMAT"
+    )]
+    alerts_format: AlertFormat,
+
+    #[arg(
+        long = "webhook.alerts.template",
+        long_help = "Template of the alert message. `{network}`, `{alert}` and `{message}` are \
+                     replaced with the network name, the kind of alert and its description.",
+        value_name = "TEMPLATE",
+        default_value = pathfinder_lib::webhook::alerts::DEFAULT_TEMPLATE,
+        env = "PATHFINDER_WEBHOOK_ALERTS_TEMPLATE"
+    )]
+    alerts_template: String,
+
+    #[arg(
+        long = "webhook.alerts.sync-stall-timeout",
+        long_help = "Alert if no new block has been synced for this long.",
+        value_name = "SECONDS",
+        default_value = "600",
+        env = "PATHFINDER_WEBHOOK_ALERTS_SYNC_STALL_TIMEOUT"
+    )]
+    alerts_sync_stall_timeout: std::num::NonZeroU64,
+
+    #[arg(
+        long = "webhook.alerts.reorg-depth",
+        long_help = "Alert on reorgs reverting at least this many blocks.",
+        value_name = "BLOCKS",
+        default_value = "10",
+        env = "PATHFINDER_WEBHOOK_ALERTS_REORG_DEPTH"
+    )]
+    alerts_reorg_depth: std::num::NonZeroU64,
+
+    #[arg(
+        long = "webhook.alerts.min-free-disk-space",
+        long_help = "Alert if free space on the disk holding the database drops below this \
+                     percentage.",
+        value_name = "PERCENT",
+        default_value = "5",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        env = "PATHFINDER_WEBHOOK_ALERTS_MIN_FREE_DISK_SPACE"
+    )]
+    alerts_min_free_disk_space: u8,
+
+    #[arg(
+        long = "webhook.alerts.gateway-errors-per-minute",
+        long_help = "Alert if at least this many gateway requests fail within a minute.",
+        value_name = "COUNT",
+        default_value = "30",
+        env = "PATHFINDER_WEBHOOK_ALERTS_GATEWAY_ERRORS_PER_MINUTE"
+    )]
+    alerts_gateway_errors_per_minute: std::num::NonZeroU64,
+}
+
+#[derive(clap::Args)]
+struct ReplicationCli {
+    #[arg(
+        long = "replication.listen-address",
+        long_help = "Address on which to serve committed blocks to warm-standby nodes. \
+                     Requires `replication.secret` to be set.",
+        value_name = "IP:PORT",
+        env = "PATHFINDER_REPLICATION_LISTEN_ADDRESS"
+    )]
+    listen_address: Option<SocketAddr>,
+
+    #[arg(
+        long = "replication.primary",
+        long_help = "Address of the primary node to replicate committed blocks from, instead of \
+                     downloading them from the feeder gateway. The node still polls the gateway \
+                     for the chain tip and the pending block. Requires `replication.secret` to \
+                     be set.",
+        value_name = "HOST:PORT",
+        env = "PATHFINDER_REPLICATION_PRIMARY"
+    )]
+    primary: Option<String>,
+
+    #[arg(
+        long = "replication.secret",
+        long_help = "Secret shared by the primary and its standbys, used to authenticate \
+                     replication messages using HMAC-SHA256. Messages are not encrypted.",
+        value_name = "SECRET",
+        env = "PATHFINDER_REPLICATION_SECRET"
+    )]
+    secret: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum AlertFormat {
+    Slack,
+    Discord,
+    Json,
+}
+
+impl From<AlertFormat> for pathfinder_lib::webhook::alerts::AlertFormat {
+    fn from(value: AlertFormat) -> Self {
+        match value {
+            AlertFormat::Slack => Self::Slack,
+            AlertFormat::Discord => Self::Discord,
+            AlertFormat::Json => Self::Json,
+        }
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
 enum Network {
@@ -673,35 +1409,83 @@ pub struct Config {
     pub rpc_address: SocketAddr,
     pub rpc_cors_domains: Option<AllowedOrigins>,
     pub rpc_root_version: RpcVersion,
+    pub rpc_deprecated_versions: Vec<RpcVersion>,
     pub websocket: WebsocketConfig,
+    pub rpc_response_cache: RpcResponseCacheConfig,
+    pub rpc_upstream: RpcUpstreamConfig,
+    pub rpc_load_shedding: RpcLoadSheddingConfig,
+    pub rpc_write_protection: RpcWriteProtectionConfig,
+    pub rpc_call_stats: RpcCallStatsConfig,
+    pub analytics: AnalyticsConfig,
     pub monitor_address: Option<SocketAddr>,
+    pub error_telemetry: bool,
     pub network: Option<NetworkConfig>,
     pub execution_concurrency: Option<std::num::NonZeroU32>,
     pub sqlite_wal: JournalMode,
+    pub sqlite_tuning: SqliteTuningConfig,
     pub max_rpc_connections: std::num::NonZeroUsize,
     pub poll_interval: std::time::Duration,
     pub l1_poll_interval: std::time::Duration,
+    pub l1_scan_chunk_size: std::num::NonZeroU64,
+    pub class_fetch_concurrency: std::num::NonZeroUsize,
+    pub sync_trie_threads: Option<NonZeroUsize>,
     pub color: Color,
     pub disable_version_update_check: bool,
     pub p2p: P2PConfig,
     pub debug: DebugConfig,
     pub verify_tree_hashes: bool,
     pub rpc_batch_concurrency_limit: NonZeroUsize,
+    pub rpc_batch_cost_budget: Option<NonZeroUsize>,
     pub is_sync_enabled: bool,
     pub is_rpc_enabled: bool,
     pub gateway_api_key: Option<String>,
     pub gateway_timeout: Duration,
+    pub gateway_recording: Option<(PathBuf, starknet_gateway_client::RecordingMode)>,
     pub event_bloom_filter_cache_size: NonZeroUsize,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_bloom_filters_to_load: NonZeroUsize,
+    pub get_events_max_page_bytes: Option<NonZeroUsize>,
+    pub receipt_events_limit: Option<NonZeroUsize>,
+    pub trace_cache_size: NonZeroUsize,
     pub state_tries: Option<StateTries>,
+    pub prune_keep_blocks: Option<u64>,
+    pub chain_audit: ChainAudit,
+    pub chain_audit_truncate: bool,
     pub custom_versioned_constants: Option<VersionedConstants>,
+    pub execution_fingerprint: Option<pathfinder_rpc::execution_fingerprint::ExecutionFingerprint>,
+    pub verified_sources_import_file: Option<PathBuf>,
+    pub staking_contract_address: Option<pathfinder_common::ContractAddress>,
+    pub rpc_compression: Option<pathfinder_rpc::CompressionConfig>,
+    pub rpc_pending_gas_prices: pathfinder_rpc::PendingGasPriceSource,
+    pub rpc_streaming_response_threshold: NonZeroUsize,
     pub feeder_gateway_fetch_concurrency: NonZeroUsize,
+    pub feeder_gateway_mirrors: Vec<Url>,
+    pub webhook: Option<WebhookConfig>,
+    pub replication: Option<ReplicationConfig>,
+    pub fork_from: Option<ForkConfig>,
+    pub alerts: Option<AlertConfig>,
+    pub low_memory: bool,
+    pub read_only: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ForkConfig {
+    pub url: Url,
+    pub block_number: pathfinder_common::BlockNumber,
+}
+
+pub struct ReplicationConfig {
+    /// Serve committed blocks to standbys on this address.
+    pub listen_address: Option<SocketAddr>,
+    /// Replicate committed blocks from this primary.
+    pub primary: Option<String>,
+    pub secret: String,
 }
 
 pub struct Ethereum {
     pub url: Url,
     pub password: Option<String>,
+    pub legacy_abi_until: Option<u64>,
 }
 
 #[derive(Clone)]
@@ -730,10 +1514,12 @@ pub struct P2PConfig {
     pub ip_whitelist: Vec<IpNet>,
     pub kad_name: Option<String>,
     pub l1_checkpoint_override: Option<pathfinder_ethereum::EthereumStateUpdate>,
+    pub attestation_public_keys: Vec<pathfinder_common::PublicKey>,
     pub stream_timeout: Duration,
     pub max_concurrent_streams: usize,
     pub direct_connection_timeout: Duration,
     pub eviction_timeout: Duration,
+    pub header_only: bool,
 }
 
 #[cfg(not(feature = "p2p"))]
@@ -847,6 +1633,15 @@ impl P2PConfig {
                 .exit()
         }
 
+        if args.header_only && args.proxy {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "p2p.experimental.header-only requires syncing from the p2p network",
+                )
+                .exit()
+        }
+
         let l1_checkpoint_override = parse_l1_checkpoint_or_exit(args.l1_checkpoint_override);
 
         Self {
@@ -867,10 +1662,12 @@ impl P2PConfig {
             ip_whitelist: args.ip_whitelist,
             kad_name: args.kad_name,
             l1_checkpoint_override,
+            attestation_public_keys: args.attestation_public_keys,
             stream_timeout: Duration::from_secs(args.stream_timeout.into()),
             max_concurrent_streams: args.max_concurrent_streams,
             direct_connection_timeout: Duration::from_secs(args.direct_connection_timeout.into()),
             eviction_timeout: Duration::from_secs(args.eviction_timeout.into()),
+            header_only: args.header_only,
         }
     }
 }
@@ -910,6 +1707,109 @@ fn parse_l1_checkpoint_or_exit(
     })
 }
 
+fn parse_alerts(args: &WebhookCli) -> Option<AlertConfig> {
+    Some(AlertConfig {
+        url: args.alerts_url.clone()?,
+        format: args.alerts_format.into(),
+        template: args.alerts_template.clone(),
+        sync_stall_timeout: Duration::from_secs(args.alerts_sync_stall_timeout.get()),
+        reorg_depth: args.alerts_reorg_depth,
+        min_free_disk_space_percent: args.alerts_min_free_disk_space,
+        gateway_errors_per_minute: args.alerts_gateway_errors_per_minute,
+        max_retries: args.max_retries,
+        timeout: Duration::from_secs(args.request_timeout.get()),
+    })
+}
+
+fn parse_webhook_or_exit(args: WebhookCli) -> Option<WebhookConfig> {
+    use clap::error::ErrorKind;
+    use pathfinder_common::{ContractAddress, EventKey};
+    use pathfinder_crypto::Felt;
+    use pathfinder_lib::webhook::Watchlist;
+
+    fn parse_felts<T>(field: &str, values: Vec<String>, f: impl Fn(Felt) -> Option<T>) -> HashSet<T>
+    where
+        T: std::hash::Hash + Eq,
+    {
+        values
+            .into_iter()
+            .map(|value| {
+                Felt::from_hex_str(&value)
+                    .ok()
+                    .and_then(&f)
+                    .ok_or_else(|| format!("{field}: invalid value {value}"))
+            })
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|error| {
+                Cli::command()
+                    .error(ErrorKind::ValueValidation, error)
+                    .exit()
+            })
+    }
+
+    let watchlist = Watchlist {
+        addresses: parse_felts("webhook.watch-addresses", args.watch_addresses, |felt| {
+            ContractAddress::new(felt)
+        }),
+        event_keys: parse_felts("webhook.watch-event-keys", args.watch_event_keys, |felt| {
+            Some(EventKey(felt))
+        }),
+    };
+
+    let Some(url) = args.url else {
+        if !watchlist.is_empty() {
+            Cli::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "webhook.url is required when a webhook watchlist is configured",
+                )
+                .exit()
+        }
+        return None;
+    };
+
+    if watchlist.is_empty() {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "webhook.url requires webhook.watch-addresses and/or webhook.watch-event-keys",
+            )
+            .exit()
+    }
+
+    Some(WebhookConfig {
+        url,
+        secret: args.secret,
+        watchlist,
+        max_retries: args.max_retries,
+        timeout: Duration::from_secs(args.request_timeout.get()),
+    })
+}
+
+fn parse_replication_or_exit(args: ReplicationCli) -> Option<ReplicationConfig> {
+    use clap::error::ErrorKind;
+
+    if args.listen_address.is_none() && args.primary.is_none() {
+        return None;
+    }
+
+    let Some(secret) = args.secret.filter(|secret| !secret.is_empty()) else {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "replication.secret is required when replication.listen-address or \
+                 replication.primary is set",
+            )
+            .exit()
+    };
+
+    Some(ReplicationConfig {
+        listen_address: args.listen_address,
+        primary: args.primary,
+        secret,
+    })
+}
+
 #[cfg(not(feature = "p2p"))]
 impl DebugConfig {
     fn parse(_: ()) -> Self {
@@ -937,32 +1837,46 @@ impl Config {
 
         let network = NetworkConfig::from_components(cli.network);
 
-        Config {
+        let config = Config {
             data_directory: cli.data_directory,
             ethereum: Ethereum {
                 password: cli.ethereum_password,
                 url: cli.ethereum_url,
+                legacy_abi_until: cli.ethereum_legacy_abi_until,
             },
             rpc_address: cli.rpc_address,
             rpc_cors_domains: parse_cors_or_exit(cli.rpc_cors_domains),
             rpc_root_version: cli.rpc_root_version,
+            rpc_deprecated_versions: cli.rpc_deprecated_versions,
             websocket: cli.websocket,
+            rpc_response_cache: cli.rpc_response_cache,
+            rpc_upstream: cli.rpc_upstream,
+            rpc_load_shedding: cli.rpc_load_shedding,
+            rpc_write_protection: cli.rpc_write_protection,
+            rpc_call_stats: cli.rpc_call_stats,
+            analytics: cli.analytics,
             monitor_address: cli.monitor_address,
+            error_telemetry: cli.error_telemetry,
             network,
             execution_concurrency: cli.execution_concurrency,
             sqlite_wal: match cli.sqlite_wal {
                 true => JournalMode::WAL,
                 false => JournalMode::Rollback,
             },
+            sqlite_tuning: SqliteTuningConfig::parse(cli.sqlite_tuning),
             max_rpc_connections: cli.max_rpc_connections,
             poll_interval: Duration::from_secs(cli.poll_interval.get()),
             l1_poll_interval: Duration::from_secs(cli.l1_poll_interval.get()),
+            l1_scan_chunk_size: cli.l1_scan_chunk_size,
+            class_fetch_concurrency: cli.class_fetch_concurrency,
+            sync_trie_threads: cli.sync_trie_threads,
             color: cli.color,
             disable_version_update_check: cli.disable_version_update_check,
             p2p: P2PConfig::parse_or_exit(cli.p2p),
             debug: DebugConfig::parse(cli.debug),
             verify_tree_hashes: cli.verify_tree_node_data,
             rpc_batch_concurrency_limit: cli.rpc_batch_concurrency_limit,
+            rpc_batch_cost_budget: cli.rpc_batch_cost_budget,
             is_sync_enabled: cli.is_sync_enabled,
             is_rpc_enabled: cli.is_rpc_enabled,
             gateway_api_key: cli.gateway_api_key,
@@ -970,12 +1884,87 @@ impl Config {
             get_events_max_blocks_to_scan: cli.get_events_max_blocks_to_scan,
             get_events_max_uncached_bloom_filters_to_load: cli
                 .get_events_max_uncached_bloom_filters_to_load,
+            get_events_max_page_bytes: cli.get_events_max_page_bytes,
+            receipt_events_limit: cli.receipt_events_limit,
+            trace_cache_size: cli.trace_cache_size,
             gateway_timeout: Duration::from_secs(cli.gateway_timeout.get()),
+            gateway_recording: match (cli.record_gateway, cli.replay_gateway) {
+                (Some(dir), _) => Some((dir, starknet_gateway_client::RecordingMode::Record)),
+                (None, Some(dir)) => Some((dir, starknet_gateway_client::RecordingMode::Replay)),
+                (None, None) => None,
+            },
             feeder_gateway_fetch_concurrency: cli.feeder_gateway_fetch_concurrency,
-            state_tries: cli.state_tries,
+            feeder_gateway_mirrors: cli.feeder_gateway_mirrors,
+            state_tries: cli
+                .state_tries
+                .or(cli.prune_keep_blocks.map(StateTries::Pruned)),
+            prune_keep_blocks: cli.prune_keep_blocks,
+            chain_audit: cli.chain_audit,
+            chain_audit_truncate: cli.chain_audit_truncate,
+            execution_fingerprint: cli.rpc_execution_fingerprint.then(|| {
+                // An unreadable file is rejected when parsing the constants below.
+                let custom_versioned_constants = cli
+                    .custom_versioned_constants_path
+                    .as_ref()
+                    .map(|path| std::fs::read(path).unwrap_or_default());
+                pathfinder_rpc::execution_fingerprint::ExecutionFingerprint::new(
+                    custom_versioned_constants.as_deref(),
+                )
+            }),
             custom_versioned_constants: cli
                 .custom_versioned_constants_path
                 .map(parse_versioned_constants_or_exit),
+            verified_sources_import_file: cli.verified_sources_import_file,
+            staking_contract_address: cli.staking_contract_address,
+            rpc_compression: cli
+                .rpc_compression
+                .then(|| pathfinder_rpc::CompressionConfig {
+                    min_size: cli.rpc_compression_min_size,
+                    level: cli.rpc_compression_level.into(),
+                }),
+            rpc_pending_gas_prices: cli.rpc_pending_gas_prices.into(),
+            rpc_streaming_response_threshold: cli.rpc_streaming_response_threshold,
+            alerts: parse_alerts(&cli.webhook),
+            webhook: parse_webhook_or_exit(cli.webhook),
+            replication: parse_replication_or_exit(cli.replication),
+            fork_from: cli.fork_from,
+            low_memory: cli.low_memory,
+            read_only: cli.read_only,
+        };
+
+        if config.low_memory {
+            config.with_low_memory_limits()
+        } else {
+            config
+        }
+    }
+
+    /// Caps settings which scale memory usage to the `--low-memory` profile.
+    ///
+    /// Explicitly configured values lower than the caps are kept.
+    fn with_low_memory_limits(self) -> Self {
+        // 32 MiB worth of Bloom filters.
+        let event_bloom_filter_cache_size = NonZeroUsize::new(16_384).unwrap();
+        let execution_concurrency = std::num::NonZeroU32::new(2).unwrap();
+        let concurrency = NonZeroUsize::new(2).unwrap();
+
+        Self {
+            event_bloom_filter_cache_size: self
+                .event_bloom_filter_cache_size
+                .min(event_bloom_filter_cache_size),
+            execution_concurrency: Some(
+                self.execution_concurrency
+                    .map_or(execution_concurrency, |x| x.min(execution_concurrency)),
+            ),
+            rpc_batch_concurrency_limit: self.rpc_batch_concurrency_limit.min(concurrency),
+            feeder_gateway_fetch_concurrency: self
+                .feeder_gateway_fetch_concurrency
+                .min(concurrency),
+            sync_trie_threads: Some(
+                self.sync_trie_threads
+                    .map_or(concurrency, |x| x.min(concurrency)),
+            ),
+            ..self
         }
     }
 }
@@ -1010,6 +1999,207 @@ pub struct WebsocketConfig {
         env = "PATHFINDER_WEBSOCKET_TOPIC_CAPACITY"
     )]
     pub topic_sender_capacity: NonZeroUsize,
+    #[arg(
+        long = "rpc.websocket.ping-interval",
+        long_help = "Ping websocket clients this often, in seconds, and disconnect clients which \
+                     stop responding. See also `rpc.websocket.ping-timeout`",
+        value_name = "SECONDS",
+        env = "PATHFINDER_WEBSOCKET_PING_INTERVAL"
+    )]
+    pub ping_interval: Option<std::num::NonZeroU64>,
+    #[arg(
+        long = "rpc.websocket.ping-timeout",
+        long_help = "Disconnect websocket clients from which nothing, including responses to \
+                     pings, has been received for this many seconds",
+        value_name = "SECONDS",
+        default_value = "60",
+        env = "PATHFINDER_WEBSOCKET_PING_TIMEOUT"
+    )]
+    pub ping_timeout: std::num::NonZeroU64,
+    #[arg(
+        long = "rpc.websocket.resumption-grace-period",
+        long_help = "Issue resumption tokens for subscriptions. A client reconnecting within this \
+                     many seconds can pass the token to `pathfinder_resumeSubscription` to \
+                     continue the subscription from the block after the last one delivered",
+        value_name = "SECONDS",
+        env = "PATHFINDER_WEBSOCKET_RESUMPTION_GRACE_PERIOD"
+    )]
+    pub resumption_grace_period: Option<std::num::NonZeroU64>,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct RpcResponseCacheConfig {
+    #[arg(
+        long = "rpc.response-cache.enabled",
+        long_help = "Cache responses to queries about blocks referenced by hash or number, and to \
+                     transaction lookups by hash. Cached responses are invalidated on reorgs.",
+        default_value = "false",
+        env = "PATHFINDER_RPC_RESPONSE_CACHE_ENABLED"
+    )]
+    pub enabled: bool,
+    #[arg(
+        long = "rpc.response-cache.memory-entries",
+        long_help = "The number of responses kept in memory by the RPC response cache",
+        value_name = "ENTRIES",
+        default_value = "10000",
+        env = "PATHFINDER_RPC_RESPONSE_CACHE_MEMORY_ENTRIES"
+    )]
+    pub memory_entries: NonZeroUsize,
+    #[arg(
+        long = "rpc.response-cache.disk-size",
+        long_help = "Also store cached responses on disk, next to the database, using at most \
+                     this many MiB. Responses stored on disk are kept across restarts.",
+        value_name = "MiB",
+        env = "PATHFINDER_RPC_RESPONSE_CACHE_DISK_SIZE"
+    )]
+    pub disk_size: Option<std::num::NonZeroU64>,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct RpcUpstreamConfig {
+    #[arg(
+        long = "rpc.upstream.url",
+        long_help = "Base URL of an upstream pathfinder node, typically an archive node. Requests \
+                     for state which has been pruned locally are forwarded to it, and responses \
+                     are marked with a `provenance` member.",
+        value_name = "URL",
+        value_hint = clap::ValueHint::Url,
+        env = "PATHFINDER_RPC_UPSTREAM_URL"
+    )]
+    pub url: Option<Url>,
+    #[arg(
+        long = "rpc.upstream.request-timeout",
+        long_help = "Timeout duration for requests forwarded to the upstream node.",
+        value_name = "SECONDS",
+        default_value = "30",
+        env = "PATHFINDER_RPC_UPSTREAM_REQUEST_TIMEOUT"
+    )]
+    pub request_timeout: std::num::NonZeroU64,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct RpcLoadSheddingConfig {
+    #[arg(
+        long = "rpc.load-shedding.enabled",
+        long_help = "Reject expensive requests with a `retry_after_seconds` hint while the node is \
+                     under pressure, so that cheap reads keep being served. Executions are \
+                     rejected while too many of them are waiting for a free executor, event \
+                     scans and executions while event scans are slow or memory usage is too \
+                     high.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_LOAD_SHEDDING_ENABLED"
+    )]
+    pub enabled: bool,
+    #[arg(
+        long = "rpc.load-shedding.max-execution-queue",
+        long_help = "Reject calls, fee estimates, simulations and traces while this many are \
+                     waiting for one of the `--rpc.execution-concurrency` executors.",
+        default_value = "64",
+        env = "PATHFINDER_RPC_LOAD_SHEDDING_MAX_EXECUTION_QUEUE"
+    )]
+    pub max_execution_queue: usize,
+    #[arg(
+        long = "rpc.load-shedding.max-event-scan-latency",
+        long_help = "Reject `starknet_getEvents` and executions once `starknet_getEvents` takes \
+                     longer than this on average.",
+        value_name = "MILLISECONDS",
+        default_value = "5000",
+        env = "PATHFINDER_RPC_LOAD_SHEDDING_MAX_EVENT_SCAN_LATENCY"
+    )]
+    pub max_event_scan_latency: std::num::NonZeroU64,
+    #[arg(
+        long = "rpc.load-shedding.max-memory",
+        long_help = "Reject `starknet_getEvents` and executions while the memory reported by the \
+                     `memory_allocated_bytes` metric exceeds this many MiB. Only available in \
+                     builds with the `memory-accounting` cargo feature.",
+        value_name = "MiB",
+        value_parser = parse_max_memory,
+        env = "PATHFINDER_RPC_LOAD_SHEDDING_MAX_MEMORY"
+    )]
+    pub max_memory: Option<std::num::NonZeroU64>,
+    #[arg(
+        long = "rpc.load-shedding.retry-after",
+        long_help = "How long clients are asked to wait before retrying a rejected request.",
+        value_name = "SECONDS",
+        default_value = "5",
+        env = "PATHFINDER_RPC_LOAD_SHEDDING_RETRY_AFTER"
+    )]
+    pub retry_after: std::num::NonZeroU64,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct RpcWriteProtectionConfig {
+    #[arg(
+        long = "rpc.write-protection.enabled",
+        long_help = "Throttle clients whose `starknet_add*Transaction` requests keep failing, to \
+                     protect the node's gateway quota. Clients are identified by their \
+                     `x-api-key` header, or otherwise by their IP address. Invalid signatures, \
+                     reused nonces and other failed validations add to a client's score, which \
+                     halves every `--rpc.write-protection.half-life`.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_WRITE_PROTECTION_ENABLED"
+    )]
+    pub enabled: bool,
+    #[arg(
+        long = "rpc.write-protection.max-score",
+        long_help = "Reject a client's transactions while its score exceeds this. An invalid \
+                     signature scores 4, a reused nonce 2 and any other failed validation 1.",
+        default_value = "20",
+        env = "PATHFINDER_RPC_WRITE_PROTECTION_MAX_SCORE"
+    )]
+    pub max_score: f64,
+    #[arg(
+        long = "rpc.write-protection.half-life",
+        long_help = "How long it takes for a client's score to halve.",
+        value_name = "SECONDS",
+        default_value = "60",
+        env = "PATHFINDER_RPC_WRITE_PROTECTION_HALF_LIFE"
+    )]
+    pub half_life: std::num::NonZeroU64,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct RpcCallStatsConfig {
+    #[arg(
+        long = "rpc.call-stats.enabled",
+        long_help = "Count the contracts and entry points targeted by `starknet_call`. The most \
+                     called targets are served by `pathfinder_getCallStats`.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_RPC_CALL_STATS_ENABLED"
+    )]
+    pub enabled: bool,
+    #[arg(
+        long = "rpc.call-stats.window",
+        long_help = "The sliding window over which calls are counted.",
+        value_name = "MINUTES",
+        default_value = "60",
+        env = "PATHFINDER_RPC_CALL_STATS_WINDOW"
+    )]
+    pub window: std::num::NonZeroU64,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct AnalyticsConfig {
+    #[arg(
+        long = "analytics.enabled",
+        long_help = "Maintain daily transaction counts, active accounts, fee totals and class \
+                     declarations, served by the `analytics_*` RPC methods.",
+        action = clap::ArgAction::Set,
+        default_value = "false",
+        env = "PATHFINDER_ANALYTICS_ENABLED"
+    )]
+    pub enabled: bool,
+    #[arg(
+        long = "analytics.refresh-interval",
+        long_help = "How often newly synced blocks are added to the analytics.",
+        value_name = "SECONDS",
+        default_value = "60",
+        env = "PATHFINDER_ANALYTICS_REFRESH_INTERVAL"
+    )]
+    pub refresh_interval: std::num::NonZeroU64,
 }
 
 #[cfg(test)]
@@ -1118,4 +2308,26 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn parse_prune_keep_blocks() {
+        assert_eq!(super::parse_prune_keep_blocks("64"), Ok(64));
+        assert_eq!(super::parse_prune_keep_blocks("1000"), Ok(1000));
+        assert!(super::parse_prune_keep_blocks("63").is_err());
+        assert!(super::parse_prune_keep_blocks("1").is_err());
+        assert!(super::parse_prune_keep_blocks("many").is_err());
+    }
+
+    #[test]
+    fn parse_max_memory() {
+        if cfg!(feature = "memory-accounting") {
+            assert_eq!(
+                super::parse_max_memory("1024").map(std::num::NonZeroU64::get),
+                Ok(1024)
+            );
+            assert!(super::parse_max_memory("0").is_err());
+        } else {
+            assert!(super::parse_max_memory("1024").is_err());
+        }
+    }
 }
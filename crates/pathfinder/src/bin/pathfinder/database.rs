@@ -0,0 +1,157 @@
+//! `pathfinder database` subcommands, which are run instead of the node.
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use pathfinder_storage::repack::Progress;
+use pathfinder_storage::{repack, snapshot};
+
+#[derive(Parser, Debug)]
+#[command(name = "pathfinder")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage the node's database.
+    #[command(subcommand)]
+    Database(DatabaseCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum DatabaseCommand {
+    /// Create and restore compressed database snapshots.
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
+    /// Rewrites a database into a new file with its tables laid out
+    /// contiguously and its indexes rebuilt, recovering performance lost to
+    /// fragmentation. The node must not run meanwhile. An interrupted repack
+    /// is resumed by running the same command again.
+    Repack {
+        /// The database file, e.g. `mainnet.sqlite` in the data directory.
+        #[arg(long, value_name = "FILE")]
+        database: PathBuf,
+        /// The database file to create. Replace the original database with it
+        /// once the repack has finished.
+        #[arg(long, value_name = "FILE")]
+        output: PathBuf,
+        /// The page size of the new database, defaults to the page size of the
+        /// original database.
+        #[arg(long, value_name = "BYTES")]
+        page_size: Option<u32>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotCommand {
+    /// Writes a consistent compressed snapshot of a database. The node may
+    /// keep running meanwhile.
+    Create {
+        /// The database file, e.g. `mainnet.sqlite` in the data directory.
+        #[arg(long, value_name = "FILE")]
+        database: PathBuf,
+        /// Where to write the snapshot, or `-` to write it to stdout, for
+        /// example to pipe it to an object store.
+        #[arg(long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Restores a snapshot to a new database file after verifying its
+    /// checksum. Existing databases are never overwritten.
+    Restore {
+        /// The snapshot to restore, or `-` to read it from stdin.
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+        /// The database file to create, e.g. `mainnet.sqlite` in the data
+        /// directory.
+        #[arg(long, value_name = "FILE")]
+        database: PathBuf,
+    },
+}
+
+/// Whether the command line invokes a subcommand instead of the node.
+pub fn is_invoked() -> bool {
+    std::env::args_os()
+        .nth(1)
+        .map_or(false, |arg| arg == "database")
+}
+
+pub fn run() -> anyhow::Result<()> {
+    let Command::Database(command) = Cli::parse().command;
+    match command {
+        DatabaseCommand::Snapshot(command) => run_snapshot(command),
+        DatabaseCommand::Repack {
+            database,
+            output,
+            page_size,
+        } => run_repack(database, output, page_size),
+    }
+}
+
+fn run_snapshot(command: SnapshotCommand) -> anyhow::Result<()> {
+    // Messages go to stderr, as the snapshot may be written to stdout.
+    match command {
+        SnapshotCommand::Create { database, output } => {
+            let info = if output.as_os_str() == "-" {
+                snapshot::create(&database, std::io::stdout().lock())?
+            } else {
+                anyhow::ensure!(!output.exists(), "{} already exists", output.display());
+                let file = File::create(&output).context("Creating snapshot file")?;
+                snapshot::create(&database, file)?
+            };
+
+            eprintln!(
+                "Created snapshot of {} ({} bytes, SHA3-256 checksum {})",
+                database.display(),
+                info.size,
+                hex::encode(info.checksum)
+            );
+        }
+        SnapshotCommand::Restore { input, database } => {
+            let info = if input.as_os_str() == "-" {
+                snapshot::restore(std::io::stdin().lock(), &database)?
+            } else {
+                let file = File::open(&input).context("Opening snapshot file")?;
+                snapshot::restore(file, &database)?
+            };
+
+            eprintln!(
+                "Restored {} ({} bytes, SHA3-256 checksum {}) and verified its checksum",
+                database.display(),
+                info.size,
+                hex::encode(info.checksum)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn run_repack(database: PathBuf, output: PathBuf, page_size: Option<u32>) -> anyhow::Result<()> {
+    // Reports progress in whole percents, so that large tables do not flood
+    // the output.
+    let mut reported = None;
+    let info = repack::repack(&database, &output, page_size, |progress| match progress {
+        Progress::Copying { table, fraction } => {
+            let percent = (fraction * 100.0) as u32;
+            if reported.as_ref() != Some(&(table.to_owned(), percent)) {
+                eprintln!("Copying {table}: {percent}%");
+                reported = Some((table.to_owned(), percent));
+            }
+        }
+        Progress::Indexing { index } => eprintln!("Building index {index}"),
+    })?;
+
+    eprintln!(
+        "Repacked {} ({} bytes) into {} ({} bytes)",
+        database.display(),
+        info.source_size,
+        output.display(),
+        info.size
+    );
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 #![deny(rust_2018_idioms)]
 
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -10,7 +10,7 @@ use anyhow::Context;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use pathfinder_common::consts::VERGEN_GIT_DESCRIBE;
 use pathfinder_common::{BlockNumber, Chain, ChainId, EthereumChain};
-use pathfinder_ethereum::{EthereumApi, EthereumClient};
+use pathfinder_ethereum::{AbiRegistry, EthereumApi, EthereumClient};
 use pathfinder_lib::monitoring::{self};
 use pathfinder_lib::state;
 use pathfinder_lib::state::SyncContext;
@@ -22,18 +22,32 @@ use starknet_gateway_client::GatewayApi;
 use tokio::signal::unix::{signal, SignalKind};
 use tracing::{info, warn};
 
-use crate::config::{NetworkConfig, StateTries};
+use crate::config::{ChainAudit, NetworkConfig, StateTries};
 
 mod config;
+mod database;
 mod update;
 
 // The Cairo VM allocates felts on the stack, so during execution it's making
 // a huge number of allocations. We get roughly two times better execution
 // performance by using jemalloc (compared to the Linux glibc allocator).
+#[cfg(not(feature = "memory-accounting"))]
 #[global_allocator]
 static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
+// Attributing allocations to subsystems updates a shared counter on every
+// allocation and free, so it is only done in builds meant for investigating
+// memory usage.
+#[cfg(feature = "memory-accounting")]
+#[global_allocator]
+static GLOBAL: pathfinder_common::memory::TrackingAllocator<jemallocator::Jemalloc> =
+    pathfinder_common::memory::TrackingAllocator(jemallocator::Jemalloc);
+
 fn main() -> anyhow::Result<()> {
+    if database::is_invoked() {
+        return database::run();
+    }
+
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .thread_stack_size(8 * 1024 * 1024)
@@ -66,6 +80,17 @@ async fn async_main() -> anyhow::Result<()> {
 
     permission_check(&config.data_directory)?;
 
+    if config.error_telemetry {
+        pathfinder_common::telemetry::enable();
+    }
+
+    if config.low_memory {
+        info!("Low memory mode enabled");
+        pathfinder_executor::set_class_cache_size(NonZeroUsize::new(16).unwrap());
+        // Every RPC thread keeps the buffer of its largest recent response.
+        pathfinder_rpc::set_shared_response_size_limit(64 * 1024);
+    }
+
     let available_parallelism = std::thread::available_parallelism()?;
 
     rayon::ThreadPoolBuilder::new()
@@ -73,13 +98,19 @@ async fn async_main() -> anyhow::Result<()> {
         .num_threads(available_parallelism.get())
         .build_global()?;
 
+    state::init_trie_thread_pool(
+        config
+            .sync_trie_threads
+            .unwrap_or_else(state::default_trie_threads),
+    )?;
+
     // A readiness flag which is used to indicate that pathfinder is ready via
     // monitoring.
     let readiness = Arc::new(AtomicBool::new(false));
 
     let sync_state = Arc::new(SyncState::default());
 
-    let ethereum = EthereumContext::setup(config.ethereum.url.clone(), &config.ethereum.password)
+    let ethereum = EthereumContext::setup(&config.ethereum)
         .await
         .context("Creating Ethereum context")?;
 
@@ -109,14 +140,18 @@ async fn async_main() -> anyhow::Result<()> {
         .context("Starting monitoring task")?;
     }
 
-    let pathfinder_context = PathfinderContext::configure_and_proxy_check(
+    let mut pathfinder_context = PathfinderContext::configure_and_proxy_check(
         network,
         &config.data_directory,
         config.gateway_api_key.clone(),
         config.gateway_timeout,
+        config.gateway_recording.clone(),
     )
     .await
     .context("Configuring pathfinder")?;
+    pathfinder_context.gateway = pathfinder_context
+        .gateway
+        .with_feeder_gateway_mirrors(config.feeder_gateway_mirrors.clone());
 
     verify_networks(pathfinder_context.network, ethereum.chain)?;
 
@@ -128,9 +163,27 @@ async fn async_main() -> anyhow::Result<()> {
 
     // Setup and verify database
 
-    let storage_manager =
+    let storage_profile = if config.low_memory {
+        pathfinder_storage::StorageProfile::LowMemory
+    } else if config.state_tries == Some(StateTries::Archive) {
+        pathfinder_storage::StorageProfile::Archive
+    } else {
+        pathfinder_storage::StorageProfile::Pruned
+    };
+    let sqlite_tuning = match total_memory() {
+        Some(total_memory) => pathfinder_storage::SqliteTuning::auto(storage_profile, total_memory),
+        None => {
+            tracing::warn!("Could not determine total memory, using default SQLite settings");
+            pathfinder_storage::SqliteTuning::default()
+        }
+    };
+    let sqlite_tuning = config.sqlite_tuning.apply(sqlite_tuning);
+    info!(?storage_profile, ?sqlite_tuning, "Configuring SQLite");
+
+    let storage_builder =
         pathfinder_storage::StorageBuilder::file(pathfinder_context.database.clone())
             .journal_mode(config.sqlite_wal)
+            .tuning(sqlite_tuning)
             .bloom_filter_cache_size(config.event_bloom_filter_cache_size.get())
             .trie_prune_mode(match config.state_tries {
                 Some(StateTries::Pruned(num_blocks_kept)) => {
@@ -138,8 +191,14 @@ async fn async_main() -> anyhow::Result<()> {
                 }
                 Some(StateTries::Archive) => Some(pathfinder_storage::TriePruneMode::Archive),
                 None => None,
-            })
-            .migrate()?;
+            });
+    // All pools of a read-only storage manager are read-only.
+    let storage_manager = if config.read_only {
+        info!("Read-only mode enabled");
+        storage_builder.open_read_only()?
+    } else {
+        storage_builder.migrate()?
+    };
     let sync_storage = storage_manager
         // 5 is enough for normal sync operations, and then `available_parallelism` for
         // the rayon thread pool workers to use.
@@ -159,7 +218,11 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         .get()
         .try_into()
         .expect("usize should cast to u32");
-    let rpc_storage = std::cmp::max(10, max_rpc_connections / 8);
+    let rpc_storage = if config.low_memory {
+        (max_rpc_connections / 8).clamp(1, 4)
+    } else {
+        std::cmp::max(10, max_rpc_connections / 8)
+    };
     let rpc_storage = NonZeroU32::new(rpc_storage).expect("A non-zero minimum is set");
     let rpc_storage = storage_manager.create_read_only_pool(rpc_storage).context(
         r"Creating database connection pool for RPC
@@ -186,6 +249,86 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         .context(
             r"Creating database connection pool for p2p
 
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+        )?;
+
+    let webhook_storage = match config.webhook {
+        Some(_) => Some(
+            storage_manager
+                .create_read_only_pool(NonZeroU32::new(1).unwrap())
+                .context(
+                    r"Creating database connection pool for webhook notifications
+
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+                )?,
+        ),
+        None => None,
+    };
+
+    let replication_storage = match config.replication {
+        Some(config::ReplicationConfig {
+            listen_address: Some(_),
+            ..
+        }) => Some(
+            storage_manager
+                .create_read_only_pool(NonZeroU32::new(1).unwrap())
+                .context(
+                    r"Creating database connection pool for replication
+
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+                )?,
+        ),
+        _ => None,
+    };
+
+    // A read-only node serves the analytics refreshed by the database's writer.
+    let analytics_storage = if config.analytics.enabled && !config.read_only {
+        Some(
+            storage_manager
+                .create_pool(NonZeroU32::new(1).unwrap())
+                .context(
+                    r"Creating database connection pool for analytics
+
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+                )?,
+        )
+    } else {
+        None
+    };
+
+    let state_pruning_storage = match config.prune_keep_blocks {
+        Some(keep_blocks) => Some((
+            storage_manager
+                .create_pool(NonZeroU32::new(1).unwrap())
+                .context(
+                    r"Creating database connection pool for state pruning
+
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+                )?,
+            keep_blocks,
+        )),
+        None => None,
+    };
+
+    let statistics_storage = storage_manager
+        .create_pool(NonZeroU32::new(1).unwrap())
+        .context(
+            r"Creating database connection pool for query planner statistics
+
+Hint: This is usually caused by exceeding the file descriptor limit of your system.
+      Try increasing the file limit to using `ulimit` or similar tooling.",
+        )?;
+
+    let casm_cache_storage = storage_manager
+        .create_pool(NonZeroU32::new(1).unwrap())
+        .context(
+            r"Creating database connection pool for the CASM cache
+
 Hint: This is usually caused by exceeding the file descriptor limit of your system.
       Try increasing the file limit to using `ulimit` or similar tooling.",
         )?;
@@ -199,26 +342,163 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     .await
     .context("Verifying database")?;
 
-    sync_storage
-        .connection()
-        .context("Creating database connection")?
-        .transaction()
-        .context(r"Creating database transaction")?
-        .prune_tries()
-        .context("Pruning tries on startup")?;
+    if !config.read_only {
+        audit_block_hash_chain(
+            &sync_storage,
+            config.chain_audit,
+            config.chain_audit_truncate,
+        )
+        .await
+        .context("Auditing block hash chain")?;
+
+        sync_storage
+            .connection()
+            .context("Creating database connection")?
+            .transaction()
+            .context(r"Creating database transaction")?
+            .prune_tries()
+            .context("Pruning tries on startup")?;
+    }
+
+    if let Some(path) = &config.verified_sources_import_file {
+        let imported = pathfinder_lib::verified_sources::import(&sync_storage, path)
+            .context("Importing verified sources")?;
+        info!(%imported, "Imported verified class sources");
+    }
 
     let (tx_pending, rx_pending) = tokio::sync::watch::channel(Default::default());
 
     let rpc_config = pathfinder_rpc::context::RpcConfig {
         batch_concurrency_limit: config.rpc_batch_concurrency_limit,
+        batch_cost_budget: config.rpc_batch_cost_budget,
         get_events_max_blocks_to_scan: config.get_events_max_blocks_to_scan,
         get_events_max_uncached_bloom_filters_to_load: config
             .get_events_max_uncached_bloom_filters_to_load,
+        get_events_max_page_bytes: config.get_events_max_page_bytes,
         custom_versioned_constants: config.custom_versioned_constants.take(),
+        staking_contract: config.staking_contract_address,
+        analytics: config.analytics.enabled,
+        pending_gas_price_source: config.rpc_pending_gas_prices,
+        receipt_events_limit: config.receipt_events_limit,
     };
 
     let notifications = Notifications::default();
 
+    // Classes declared in reorged blocks may be declared again at a different
+    // height.
+    let mut chain_events = notifications.chain_events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match chain_events.recv().await {
+                Ok(pathfinder_rpc::ChainEvent::Reorg(reorg)) => {
+                    pathfinder_executor::invalidate_class_cache(reorg.first_block_number)
+                }
+                Ok(_) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                    pathfinder_executor::invalidate_class_cache(BlockNumber::GENESIS)
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    if let (Some(webhook), Some(storage)) = (config.webhook.clone(), webhook_storage) {
+        let chain_events = notifications.chain_events.subscribe();
+        tokio::spawn(async move {
+            if let Err(error) = pathfinder_lib::webhook::run(webhook, storage, chain_events).await {
+                tracing::error!(%error, "Webhook notifier terminated");
+            }
+        });
+    }
+
+    if let (Some(replication), Some(storage)) = (config.replication.as_ref(), replication_storage) {
+        let address = replication
+            .listen_address
+            .expect("Checked when creating the pool");
+        let serve = pathfinder_lib::state::replication::serve(
+            address,
+            replication.secret.clone(),
+            pathfinder_context.network_id,
+            storage,
+            notifications.chain_events.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(error) = serve.await {
+                tracing::error!(%error, "Replication server terminated");
+            }
+        });
+    }
+
+    if let Some((storage, keep_blocks)) = state_pruning_storage {
+        tokio::spawn(async move {
+            if let Err(error) = pathfinder_lib::state_pruning::run(storage, keep_blocks).await {
+                tracing::error!(%error, "State pruning terminated");
+            }
+        });
+    }
+
+    // Statistics go stale as the database grows, and are missing for indexes
+    // added by migrations. A read-only database is analyzed by its writer.
+    if !config.read_only {
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let mut db = statistics_storage
+                    .connection()
+                    .context("Creating database connection")?;
+                let tx = db.transaction().context("Creating database transaction")?;
+                tx.refresh_planner_statistics()?;
+                tx.commit().context("Committing database transaction")
+            })
+            .await;
+
+            match result {
+                Ok(Ok(())) => tracing::debug!("Refreshed query planner statistics"),
+                Ok(Err(error)) => {
+                    tracing::warn!(%error, "Failed to refresh query planner statistics")
+                }
+                Err(error) => tracing::error!(%error, "Query planner statistics refresh panicked"),
+            }
+        });
+    }
+
+    if let Some(storage) = analytics_storage {
+        let refresh_interval =
+            std::time::Duration::from_secs(config.analytics.refresh_interval.get());
+        tokio::spawn(async move {
+            if let Err(error) = pathfinder_lib::analytics::run(storage, refresh_interval).await {
+                tracing::error!(%error, "Analytics refresh terminated");
+            }
+        });
+    }
+
+    if let Some(alerts) = config.alerts.clone() {
+        let network = pathfinder_context.network.to_string();
+        let database = pathfinder_context.database.clone();
+        let chain_events = notifications.chain_events.subscribe();
+        tokio::spawn(async move {
+            if let Err(error) =
+                pathfinder_lib::webhook::alerts::run(alerts, network, database, chain_events).await
+            {
+                tracing::error!(%error, "Alert notifier terminated");
+            }
+        });
+    }
+
+    let class_cache_index = config
+        .data_directory
+        .join(pathfinder_lib::cache_persistence::FILE_NAME);
+    {
+        let storage = execution_storage.clone();
+        let path = class_cache_index.clone();
+        tokio::task::spawn_blocking(move || {
+            match pathfinder_lib::cache_persistence::restore(&path, &storage) {
+                Ok(0) => {}
+                Ok(classes) => tracing::info!(%classes, "Restored class cache"),
+                Err(error) => tracing::warn!(%error, "Failed to restore class cache"),
+            }
+        });
+    }
+
     let context = pathfinder_rpc::context::RpcContext::new(
         rpc_storage,
         execution_storage,
@@ -230,6 +510,117 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         rpc_config,
     );
 
+    let context = if config.low_memory {
+        context.with_trace_cache(pathfinder_executor::TraceCache::inflight_only())
+    } else {
+        context.with_trace_cache(pathfinder_executor::TraceCache::with_size(
+            config.trace_cache_size,
+        ))
+    };
+
+    let context = if config.rpc_response_cache.enabled {
+        let disk = config.rpc_response_cache.disk_size.map(|size| {
+            pathfinder_rpc::response_cache::DiskCacheConfig {
+                path: pathfinder_context
+                    .database
+                    .with_extension("rpc-cache.sqlite"),
+                max_size: size.saturating_mul(NonZeroU64::new(1024 * 1024).unwrap()),
+            }
+        });
+        let cache = pathfinder_rpc::response_cache::ResponseCache::new(
+            pathfinder_rpc::response_cache::ResponseCacheConfig {
+                memory_entries: config.rpc_response_cache.memory_entries,
+                disk,
+            },
+            &context.storage,
+            notifications.chain_events.subscribe(),
+        )
+        .context("Creating RPC response cache")?;
+        context.with_response_cache(cache)
+    } else {
+        context
+    };
+
+    let context = match &config.fork_from {
+        Some(fork) => {
+            let fork = start_fork(fork, pathfinder_context.network_id, sync_storage.clone())
+                .await
+                .context("Starting forking mode")?;
+            context.with_fork(fork)
+        }
+        None => context,
+    };
+
+    let context = match config.rpc_upstream.url {
+        Some(url) => {
+            let upstream = pathfinder_rpc::upstream::Upstream::new(
+                url,
+                std::time::Duration::from_secs(config.rpc_upstream.request_timeout.get()),
+            )
+            .context("Creating RPC upstream client")?;
+            context.with_upstream(upstream)
+        }
+        None => context,
+    };
+
+    let context = if config.rpc_load_shedding.enabled {
+        let config = &config.rpc_load_shedding;
+        context.with_load_shedder(pathfinder_rpc::load_shedding::LoadShedder::new(
+            pathfinder_rpc::load_shedding::LoadSheddingConfig {
+                execution_concurrency: execution_storage_pool_size.get() as usize,
+                max_execution_queue: config.max_execution_queue,
+                max_event_scan_latency: std::time::Duration::from_millis(
+                    config.max_event_scan_latency.get(),
+                ),
+                max_memory: config
+                    .max_memory
+                    .map(|size| size.get().saturating_mul(1024 * 1024)),
+                retry_after: std::time::Duration::from_secs(config.retry_after.get()),
+            },
+        ))
+    } else {
+        context
+    };
+
+    let context = if config.rpc_write_protection.enabled {
+        let config = &config.rpc_write_protection;
+        context.with_write_protection(pathfinder_rpc::write_protection::WriteProtection::new(
+            pathfinder_rpc::write_protection::WriteProtectionConfig {
+                max_score: config.max_score,
+                half_life: std::time::Duration::from_secs(config.half_life.get()),
+            },
+        ))
+    } else {
+        context
+    };
+
+    let context = if config.rpc_call_stats.enabled {
+        context.with_call_stats(pathfinder_rpc::call_stats::CallStats::new(
+            std::time::Duration::from_secs(config.rpc_call_stats.window.get() * 60),
+        ))
+    } else {
+        context
+    };
+
+    let context = match config.execution_fingerprint.take() {
+        Some(fingerprint) => context.with_execution_fingerprint(fingerprint),
+        None => context,
+    };
+
+    let fee_tracker = pathfinder_rpc::fee_tracker::FeeTracker::new(
+        context.storage.clone(),
+        notifications.chain_events.subscribe(),
+    );
+    let context = context.with_fee_tracker(fee_tracker);
+    // Compiled classes cannot be stored in a read-only database.
+    let context = if config.read_only {
+        context
+    } else {
+        context.with_casm_cache(pathfinder_rpc::casm_cache::CasmCache::new(
+            casm_cache_storage,
+        ))
+    };
+
     let context = if config.websocket.enabled {
         context.with_websockets(WebsocketContext::new(
             config.websocket.socket_buffer_capacity,
@@ -240,12 +631,44 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         context
     };
 
-    let default_version = match config.rpc_root_version {
+    let context = match config.websocket.ping_interval {
+        Some(interval) => {
+            context.with_websocket_keepalive(pathfinder_rpc::context::WebsocketKeepalive {
+                interval: std::time::Duration::from_secs(interval.get()),
+                timeout: std::time::Duration::from_secs(config.websocket.ping_timeout.get()),
+            })
+        }
+        None => context,
+    };
+
+    let context = match config.websocket.resumption_grace_period {
+        Some(grace_period) => context.with_subscription_sessions(
+            pathfinder_rpc::subscription_sessions::SubscriptionSessions::new(
+                std::time::Duration::from_secs(grace_period.get()),
+            ),
+        ),
+        None => context,
+    };
+
+    let rpc_version = |version: config::RpcVersion| match version {
         config::RpcVersion::V06 => pathfinder_rpc::RpcVersion::V06,
         config::RpcVersion::V07 => pathfinder_rpc::RpcVersion::V07,
     };
-
-    let rpc_server = pathfinder_rpc::RpcServer::new(config.rpc_address, context, default_version);
+    let default_version = rpc_version(config.rpc_root_version);
+    let deprecated_versions = config
+        .rpc_deprecated_versions
+        .iter()
+        .copied()
+        .map(rpc_version)
+        .collect();
+
+    let rpc_server = pathfinder_rpc::RpcServer::new(config.rpc_address, context, default_version)
+        .with_deprecated_versions(deprecated_versions)
+        .with_streaming_threshold(config.rpc_streaming_response_threshold);
+    let rpc_server = match config.rpc_compression {
+        Some(compression) => rpc_server.with_compression(compression),
+        None => rpc_server,
+    };
     let rpc_server = match config.rpc_cors_domains {
         Some(ref allowed_origins) => rpc_server.with_cors(allowed_origins.clone()),
         None => rpc_server,
@@ -258,7 +681,14 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     )
     .await?;
 
-    let sync_handle = if config.is_sync_enabled {
+    let sync_handle = if config.read_only {
+        tokio::spawn(async move {
+            // There is no pending block, but the sender is kept so that its
+            // receivers do not see the channel closed.
+            let _tx_pending = tx_pending;
+            pathfinder_lib::read_only::follow(sync_storage, notifications).await
+        })
+    } else if config.is_sync_enabled && config.fork_from.is_none() {
         start_sync(
             sync_storage,
             pathfinder_context,
@@ -299,6 +729,11 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     // We are now ready.
     readiness.store(true, std::sync::atomic::Ordering::Relaxed);
 
+    let save_class_cache = || match pathfinder_lib::cache_persistence::save(&class_cache_index) {
+        Ok(classes) => tracing::debug!(%classes, "Saved class cache index"),
+        Err(error) => tracing::warn!(%error, "Failed to save class cache index"),
+    };
+
     // Monitor our critical spawned process tasks.
     tokio::select! {
         result = sync_handle => {
@@ -324,10 +759,12 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         }
         _ = term_signal.recv() => {
             tracing::info!("TERM signal received, exiting gracefully");
+            save_class_cache();
             Ok(())
         }
         _ = int_signal.recv() => {
             tracing::info!("INT signal received, exiting gracefully");
+            save_class_cache();
             Ok(())
         }
     }
@@ -529,8 +966,10 @@ fn start_sync(
             ethereum_client,
             p2p_client,
             gateway_public_key,
+            config.p2p.attestation_public_keys.clone(),
             config.p2p.l1_checkpoint_override,
             verify_tree_hashes,
+            config.p2p.header_only,
         )
     }
 }
@@ -588,6 +1027,7 @@ fn start_feeder_gateway_sync(
         state: sync_state.clone(),
         head_poll_interval: config.poll_interval,
         l1_poll_interval: config.l1_poll_interval,
+        l1_scan_chunk_size: config.l1_scan_chunk_size,
         pending_data: tx_pending,
         block_validation_mode: state::l2::BlockValidationMode::Strict,
         websocket_txs,
@@ -598,21 +1038,47 @@ fn start_feeder_gateway_sync(
         gossiper,
         sequencer_public_key: gateway_public_key,
         fetch_concurrency: config.feeder_gateway_fetch_concurrency,
+        class_fetch_concurrency: config.class_fetch_concurrency,
     };
 
-    tokio::spawn(state::sync(sync_context, state::l1::sync, state::l2::sync))
+    match &config.replication {
+        Some(config::ReplicationConfig {
+            primary: Some(primary),
+            secret,
+            ..
+        }) => {
+            let standby = state::replication::Standby::new(
+                primary.clone(),
+                secret.clone(),
+                pathfinder_context.network_id,
+                sync_context.storage.clone(),
+            );
+            tokio::spawn(state::sync(
+                sync_context,
+                state::l1::sync,
+                move |events, _, head, _, _| standby.follow(events, head),
+            ))
+        }
+        _ => tokio::spawn(state::sync(sync_context, state::l1::sync, state::l2::sync)),
+    }
 }
 
 #[cfg(feature = "p2p")]
+#[allow(clippy::too_many_arguments)]
 fn start_p2p_sync(
     storage: Storage,
     pathfinder_context: PathfinderContext,
     ethereum_client: EthereumClient,
     p2p_client: p2p::client::peer_agnostic::Client,
     gateway_public_key: pathfinder_common::PublicKey,
+    attestation_public_keys: Vec<pathfinder_common::PublicKey>,
     l1_checkpoint_override: Option<pathfinder_ethereum::EthereumStateUpdate>,
     verify_tree_hashes: bool,
+    header_only: bool,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
+    let public_keys = std::iter::once(gateway_public_key)
+        .chain(attestation_public_keys)
+        .collect();
     let sync = pathfinder_lib::sync::Sync {
         storage,
         p2p: p2p_client,
@@ -621,9 +1087,10 @@ fn start_p2p_sync(
         fgw_client: pathfinder_context.gateway,
         chain_id: pathfinder_context.network_id,
         chain: pathfinder_context.network,
-        public_key: gateway_public_key,
+        public_keys,
         l1_checkpoint_override,
         verify_tree_hashes,
+        header_only,
     };
     tokio::spawn(sync.run())
 }
@@ -656,7 +1123,9 @@ struct EthereumContext {
 impl EthereumContext {
     /// Configure an [EthereumContext]'s transport and read the chain ID using
     /// it.
-    async fn setup(mut url: reqwest::Url, password: &Option<String>) -> anyhow::Result<Self> {
+    async fn setup(config: &config::Ethereum) -> anyhow::Result<Self> {
+        let mut url = config.url.clone();
+
         // Make sure the URL is a WS URL
         if url.scheme().eq("http") {
             warn!("The provided Ethereum URL is using HTTP, converting to WS");
@@ -668,11 +1137,17 @@ impl EthereumContext {
                 .map_err(|_| anyhow::anyhow!("Failed to set Ethereum URL scheme to wss"))?;
         }
 
-        let client = if let Some(password) = password.as_ref() {
+        let client = if let Some(password) = config.password.as_ref() {
             EthereumClient::with_password(url, password).context("Creating Ethereum client")?
         } else {
             EthereumClient::new(url).context("Creating Ethereum client")?
         };
+        let client = match config.legacy_abi_until {
+            Some(upgrade_block) => {
+                client.with_abi_registry(AbiRegistry::with_legacy_until(upgrade_block))
+            }
+            None => client,
+        };
 
         let chain = client.get_chain().await.context(
             r"Determining Ethereum chain.
@@ -711,7 +1186,7 @@ struct PathfinderContext {
 
 /// Used to hide private fn's for [PathfinderContext].
 mod pathfinder_context {
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
     use std::time::Duration;
 
     use anyhow::Context;
@@ -719,7 +1194,7 @@ mod pathfinder_context {
     use pathfinder_ethereum::core_addr;
     use primitive_types::H160;
     use reqwest::Url;
-    use starknet_gateway_client::Client as GatewayClient;
+    use starknet_gateway_client::{Client as GatewayClient, Recording, RecordingMode};
 
     use super::PathfinderContext;
     use crate::config::NetworkConfig;
@@ -730,27 +1205,37 @@ mod pathfinder_context {
             data_directory: &Path,
             api_key: Option<String>,
             gateway_timeout: Duration,
+            recording: Option<(PathBuf, RecordingMode)>,
         ) -> anyhow::Result<Self> {
+            let recording = recording.map(|(dir, mode)| Recording::new(dir, mode));
             let context = match cfg {
                 NetworkConfig::Mainnet => Self {
                     network: Chain::Mainnet,
                     network_id: ChainId::MAINNET,
-                    gateway: GatewayClient::mainnet(gateway_timeout).with_api_key(api_key),
+                    gateway: with_recording(
+                        GatewayClient::mainnet(gateway_timeout).with_api_key(api_key),
+                        recording,
+                    ),
                     database: data_directory.join("mainnet.sqlite"),
                     l1_core_address: H160::from(core_addr::MAINNET),
                 },
                 NetworkConfig::SepoliaTestnet => Self {
                     network: Chain::SepoliaTestnet,
                     network_id: ChainId::SEPOLIA_TESTNET,
-                    gateway: GatewayClient::sepolia_testnet(gateway_timeout).with_api_key(api_key),
+                    gateway: with_recording(
+                        GatewayClient::sepolia_testnet(gateway_timeout).with_api_key(api_key),
+                        recording,
+                    ),
                     database: data_directory.join("testnet-sepolia.sqlite"),
                     l1_core_address: H160::from(core_addr::SEPOLIA_TESTNET),
                 },
                 NetworkConfig::SepoliaIntegration => Self {
                     network: Chain::SepoliaIntegration,
                     network_id: ChainId::SEPOLIA_INTEGRATION,
-                    gateway: GatewayClient::sepolia_integration(gateway_timeout)
-                        .with_api_key(api_key),
+                    gateway: with_recording(
+                        GatewayClient::sepolia_integration(gateway_timeout).with_api_key(api_key),
+                        recording,
+                    ),
                     database: data_directory.join("integration-sepolia.sqlite"),
                     l1_core_address: H160::from(core_addr::SEPOLIA_INTEGRATION),
                 },
@@ -765,6 +1250,7 @@ mod pathfinder_context {
                     data_directory,
                     api_key,
                     gateway_timeout,
+                    recording,
                 )
                 .await
                 .context("Configuring custom network")?,
@@ -784,6 +1270,7 @@ mod pathfinder_context {
             data_directory: &Path,
             api_key: Option<String>,
             gateway_timeout: Duration,
+            recording: Option<Recording>,
         ) -> anyhow::Result<Self> {
             use pathfinder_crypto::Felt;
             use starknet_gateway_client::GatewayApi;
@@ -791,6 +1278,7 @@ mod pathfinder_context {
             let gateway = GatewayClient::with_urls(gateway, feeder, gateway_timeout)
                 .context("Creating gateway client")?
                 .with_api_key(api_key);
+            let gateway = with_recording(gateway, recording);
 
             let network_id =
                 ChainId(Felt::from_be_slice(chain_id.as_bytes()).context("Parsing chain ID")?);
@@ -813,8 +1301,25 @@ mod pathfinder_context {
 
             if network != Chain::Custom {
                 tracing::info!(%network, "Proxy gateway detected");
+
+                let expected = match network {
+                    Chain::Mainnet => ChainId::MAINNET,
+                    Chain::SepoliaTestnet => ChainId::SEPOLIA_TESTNET,
+                    Chain::SepoliaIntegration => ChainId::SEPOLIA_INTEGRATION,
+                    Chain::Custom => unreachable!("Already checked against"),
+                };
+                anyhow::ensure!(
+                    network_id == expected,
+                    "Chain ID {} does not match the {network} gateway proxied to, expected {}",
+                    network_id.as_str(),
+                    expected.as_str()
+                );
             }
 
+            verify_chain_id(&gateway, network_id)
+                .await
+                .context("Verifying chain ID against gateway")?;
+
             let context = Self {
                 network,
                 network_id,
@@ -826,6 +1331,66 @@ mod pathfinder_context {
             Ok(context)
         }
     }
+
+    fn with_recording(gateway: GatewayClient, recording: Option<Recording>) -> GatewayClient {
+        match recording {
+            Some(recording) => gateway.with_recording(recording),
+            None => gateway,
+        }
+    }
+
+    /// Checks that transactions in the gateway's latest block hash correctly
+    /// using `chain_id`. This catches gateway URLs pointing at a different
+    /// network than the configured chain ID, which would otherwise only be
+    /// noticed once sync fails on a transaction hash mismatch.
+    async fn verify_chain_id(gateway: &GatewayClient, chain_id: ChainId) -> anyhow::Result<()> {
+        use starknet_gateway_client::GatewayApi;
+
+        let (head, _) = gateway
+            .head()
+            .await
+            .context("Fetching latest block number")?;
+        let (block, _) = gateway
+            .state_update_with_block(head)
+            .await
+            .context("Fetching latest block")?;
+
+        if block.transactions.is_empty() {
+            tracing::warn!(block=%head, "Latest block has no transactions, skipping chain ID verification");
+            return Ok(());
+        }
+
+        // There is no need to check every transaction of a large block.
+        let verified = block
+            .transactions
+            .iter()
+            .take(10)
+            .any(|transaction| transaction.verify_hash(chain_id));
+
+        anyhow::ensure!(
+            verified,
+            "Transaction hashes in block {head} do not match chain ID {}. Make sure --chain-id \
+             matches the network served by --gateway-url and --feeder-gateway-url",
+            chain_id.as_str()
+        );
+
+        Ok(())
+    }
+}
+
+/// Total physical memory of the machine in bytes.
+fn total_memory() -> Option<u64> {
+    // SAFETY: sysconf has no preconditions and only reads system configuration.
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    if pages <= 0 || page_size <= 0 {
+        return None;
+    }
+    (pages as u64).checked_mul(page_size as u64)
 }
 
 /// Errors if there is a mismatch between the starknet and ethereum networks.
@@ -848,6 +1413,158 @@ fn verify_networks(starknet: Chain, ethereum: EthereumChain) -> anyhow::Result<(
     Ok(())
 }
 
+/// Checks that the stored headers of the most recent blocks form a chain, i.e.
+/// that block numbers are contiguous and that each header's parent hash matches
+/// the hash of the block before it.
+///
+/// If `truncate` is set then all blocks after the last consistent block are
+/// removed, otherwise an inconsistency is an error.
+async fn audit_block_hash_chain(
+    storage: &Storage,
+    audit: ChainAudit,
+    truncate: bool,
+) -> anyhow::Result<()> {
+    let storage = storage.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = storage.connection().context("Create database connection")?;
+        let tx = conn.transaction().context("Create database transaction")?;
+
+        let Some((latest, _)) = tx
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .context("Fetching latest block number")?
+        else {
+            return Ok(());
+        };
+
+        let from = match audit {
+            ChainAudit::Recent(0) => return Ok(()),
+            ChainAudit::Recent(blocks) => {
+                BlockNumber::new_or_panic(latest.get().saturating_sub(blocks - 1))
+            }
+            ChainAudit::Full => BlockNumber::GENESIS,
+        };
+
+        let started = std::time::Instant::now();
+        let Some(first_invalid) = tx
+            .first_hash_chain_break(from)
+            .context("Checking block hash chain")?
+        else {
+            info!(%from, to=%latest, elapsed=?started.elapsed(), "Block hash chain is consistent");
+            return Ok(());
+        };
+
+        // The block before the first invalid one may be missing if there is a gap.
+        let last_consistent = tx
+            .next_ancestor(first_invalid)
+            .context("Fetching last consistent block")?
+            .map(|(number, _)| number);
+        warn!(%first_invalid, ?last_consistent, %latest, "Block hash chain is inconsistent");
+
+        anyhow::ensure!(
+            truncate,
+            "Block {first_invalid} does not follow on from the block before it. Restart with \
+             `--storage.audit-truncate true` to remove all blocks from block {first_invalid} \
+             onwards"
+        );
+
+        // Roll back the Merkle tries before purging blocks since the reverse updates
+        // are read from the state updates of the purged blocks.
+        if let Some(target) = last_consistent {
+            let target_header = tx
+                .block_header(target.into())
+                .context("Fetching last consistent block header")?
+                .context("Last consistent block header is missing")?;
+            state::revert::revert_starknet_state(&tx, latest, target, target_header)
+                .context("Reverting Merkle tries to the last consistent block")?;
+        }
+
+        let mut head = latest;
+        while head >= first_invalid {
+            tx.purge_block(head)
+                .with_context(|| format!("Purging block {head} from database"))?;
+
+            if head == BlockNumber::GENESIS {
+                break;
+            }
+            head -= 1;
+        }
+
+        let l1_l2_head = tx.l1_l2_pointer().context("Query L1-L2 head")?;
+        if l1_l2_head.is_some_and(|l1_l2_head| l1_l2_head >= first_invalid) {
+            tx.update_l1_l2_pointer(last_consistent)
+                .context("Updating L1-L2 head")?;
+        }
+
+        tx.commit().context("Committing database transaction")?;
+
+        warn!(
+            ?last_consistent,
+            "Truncated database to the last consistent block"
+        );
+
+        Ok(())
+    })
+    .await
+    .context("Joining database task")?
+}
+
+/// Forks from the remote node, after checking that it is on the same chain and
+/// that the database holds no blocks besides the one forked from. The header of
+/// that block is inserted into an empty database.
+async fn start_fork(
+    config: &config::ForkConfig,
+    chain_id: ChainId,
+    storage: Storage,
+) -> anyhow::Result<pathfinder_executor::Fork> {
+    let remote = pathfinder_rpc::fork::RpcRemoteState::new(
+        config.url.clone(),
+        config.block_number,
+        std::time::Duration::from_secs(30),
+    )?;
+
+    let remote_chain_id = remote
+        .chain_id()
+        .await
+        .context("Getting chain id of the remote node")?;
+    anyhow::ensure!(
+        remote_chain_id == chain_id,
+        "Remote node is on chain {} instead of {}",
+        remote_chain_id.to_hex_str(),
+        chain_id.to_hex_str()
+    );
+
+    let header = remote
+        .header()
+        .await
+        .context("Getting header of the fork block")?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = storage.connection().context("Create database connection")?;
+        let tx = conn.transaction().context("Create database transaction")?;
+
+        match tx
+            .block_header(pathfinder_storage::BlockId::Latest)
+            .context("Fetching latest block header")?
+        {
+            None => {
+                tx.insert_block_header(&header)
+                    .context("Inserting fork block header")?;
+                tx.commit().context("Committing database transaction")
+            }
+            Some(latest) if latest.hash == header.hash => Ok(()),
+            Some(latest) => Err(anyhow::anyhow!(
+                "Forking requires a database without blocks, but it contains blocks up to {}",
+                latest.number
+            )),
+        }
+    })
+    .await
+    .context("Joining database task")??;
+
+    info!(url=%config.url, block=%config.block_number, "Forking from remote node, syncing is disabled");
+
+    Ok(pathfinder_executor::Fork::new(remote, config.block_number))
+}
+
 async fn verify_database(
     storage: &Storage,
     network: Chain,
@@ -0,0 +1,104 @@
+//! Persists the index of the executor's class cache across restarts, so that
+//! the classes used most before a shutdown are loaded again on startup
+//! instead of on the first requests which need them.
+//!
+//! Only the class hashes are stored, as a concatenation of 32 byte hashes
+//! ordered from least to most recently used. The classes themselves are read
+//! back from the database.
+use std::path::Path;
+
+use anyhow::Context;
+use pathfinder_common::ClassHash;
+use pathfinder_crypto::Felt;
+use pathfinder_storage::Storage;
+
+/// Name of the index file in the data directory.
+pub const FILE_NAME: &str = "class-cache-index";
+
+/// Writes the index of the class cache to `path`.
+///
+/// The index is written to a temporary file first so that an interrupted write
+/// does not leave a truncated index behind.
+pub fn save(path: &Path) -> anyhow::Result<usize> {
+    let class_hashes = pathfinder_executor::class_cache_index();
+
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, encode(&class_hashes)).context("Writing class cache index")?;
+    std::fs::rename(&tmp_path, path).context("Replacing class cache index")?;
+
+    Ok(class_hashes.len())
+}
+
+/// Loads the classes of the index at `path`, if any, into the class cache and
+/// returns the number of classes loaded.
+///
+/// The index is removed once read so that a stale index is not restored after
+/// an unclean shutdown.
+pub fn restore(path: &Path, storage: &Storage) -> anyhow::Result<usize> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => return Err(error).context("Reading class cache index"),
+    };
+    std::fs::remove_file(path).context("Removing class cache index")?;
+    let class_hashes = decode(&bytes)?;
+
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+    pathfinder_executor::warm_class_cache(&db, &class_hashes)
+}
+
+fn encode(class_hashes: &[ClassHash]) -> Vec<u8> {
+    class_hashes
+        .iter()
+        .flat_map(|class_hash| class_hash.0.to_be_bytes())
+        .collect()
+}
+
+fn decode(bytes: &[u8]) -> anyhow::Result<Vec<ClassHash>> {
+    anyhow::ensure!(
+        bytes.len() % 32 == 0,
+        "Class cache index length {} is not a multiple of 32",
+        bytes.len()
+    );
+
+    bytes
+        .chunks_exact(32)
+        .map(|bytes| {
+            Felt::from_be_slice(bytes)
+                .map(ClassHash)
+                .context("Parsing class hash")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn index_round_trip() {
+        let class_hashes = vec![
+            class_hash!("0x1"),
+            class_hash!("0x1234"),
+            class_hash!("0xabc"),
+        ];
+        let bytes = encode(&class_hashes);
+        assert_eq!(bytes.len(), 3 * 32);
+        assert_eq!(decode(&bytes).unwrap(), class_hashes);
+
+        assert!(decode(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn missing_index_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let restored = restore(&dir.path().join(FILE_NAME), &storage).unwrap();
+        assert_eq!(restored, 0);
+    }
+}
@@ -1,7 +1,13 @@
 #![deny(rust_2018_idioms)]
 
+pub mod analytics;
+pub mod cache_persistence;
 pub mod monitoring;
+pub mod read_only;
 pub mod state;
+pub mod state_pruning;
 pub mod sync;
+pub mod verified_sources;
+pub mod webhook;
 
 pub mod p2p_network;
@@ -3,6 +3,8 @@ use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 use metrics_exporter_prometheus::PrometheusHandle;
+#[cfg(feature = "memory-accounting")]
+use pathfinder_common::memory::{self, Subsystem};
 use pathfinder_rpc::v02::types::syncing::Syncing;
 use pathfinder_rpc::SyncState;
 
@@ -25,6 +27,7 @@ pub async fn spawn_server(
         .route("/ready", axum::routing::get(ready_route))
         .route("/ready/synced", axum::routing::get(synced_route))
         .route("/metrics", axum::routing::get(metrics_route))
+        .route("/errors", axum::routing::get(errors_route))
         .with_state(State {
             readiness,
             sync: sync_state,
@@ -75,9 +78,24 @@ async fn synced_route(
 
 /// Returns Prometheus metrics snapshot at `/metrics`.
 async fn metrics_route(axum::extract::State(state): axum::extract::State<State>) -> String {
+    #[cfg(feature = "memory-accounting")]
+    for subsystem in Subsystem::ALL {
+        metrics::gauge!(
+            "memory_allocated_bytes",
+            memory::allocated_bytes(subsystem) as f64,
+            "subsystem" => subsystem.as_str()
+        );
+    }
+
     state.prometheus.render()
 }
 
+/// Returns the anonymized error counts at `/errors`, which are only collected
+/// if error telemetry is enabled.
+async fn errors_route() -> axum::Json<Vec<pathfinder_common::telemetry::ErrorCount>> {
+    axum::Json(pathfinder_common::telemetry::snapshot())
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::AtomicBool;
@@ -221,6 +221,11 @@ fn get_state_diff(
     block_number: BlockNumber,
     tx: &mpsc::Sender<StateDiffsResponse>,
 ) -> anyhow::Result<bool> {
+    // Pruning leaves the diffs of the blocks before the checkpoint incomplete.
+    if db_tx.is_state_pruned(block_number.into())? {
+        return Ok(false);
+    }
+
     let Some(state_diff) = db_tx.state_update(block_number.into())? else {
         return Ok(false);
     };
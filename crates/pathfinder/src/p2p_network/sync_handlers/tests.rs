@@ -36,11 +36,12 @@ mod boundary_conditions {
     use futures::channel::mpsc;
     use futures::StreamExt;
     use p2p_proto::class::ClassesRequest;
-    use p2p_proto::common::{BlockNumberOrHash, Iteration};
+    use p2p_proto::common::{BlockNumberOrHash, Direction, Iteration, Step};
     use p2p_proto::event::EventsRequest;
     use p2p_proto::header::BlockHeadersRequest;
     use p2p_proto::state::StateDiffsRequest;
     use p2p_proto::transaction::TransactionsRequest;
+    use pathfinder_common::BlockNumber;
     use pathfinder_storage::StorageBuilder;
     use rand::Rng;
     use rstest::rstest;
@@ -95,6 +96,34 @@ mod boundary_conditions {
         define_test!(transactions, get_transactions, TransactionsRequest);
         define_test!(events, get_events, EventsRequest);
     }
+
+    #[tokio::test]
+    async fn pruned_state_diffs_yield_fin() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        pathfinder_storage::fake::with_n_blocks(&storage, 3);
+        {
+            let mut db = storage.connection().unwrap();
+            let db_tx = db.transaction().unwrap();
+            db_tx
+                .prune_state_updates(BlockNumber::new_or_panic(2), 10)
+                .unwrap();
+            db_tx.commit().unwrap();
+        }
+
+        let iteration = Iteration {
+            start: BlockNumberOrHash::Number(0),
+            direction: Direction::Forward,
+            limit: 3,
+            step: Step::from(Some(1)),
+        };
+        let (tx, mut rx) = mpsc::channel(0);
+        let _jh = tokio::spawn(get_state_diffs(
+            storage,
+            StateDiffsRequest { iteration },
+            tx,
+        ));
+        assert_eq!(rx.next().await.unwrap(), Default::default());
+    }
 }
 
 /// Property tests, grouped to be immediately visible when executed
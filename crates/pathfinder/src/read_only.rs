@@ -0,0 +1,212 @@
+//! Follows a database which is written to by another pathfinder instance, for
+//! nodes running with `--read-only`.
+//!
+//! Such a node does not sync, so it learns of new blocks by polling the
+//! database for changes to the chain head, and publishes them as the
+//! notifications the sync would have published: committed blocks, reorgs and
+//! blocks accepted on L1. A read-only node has no pending block.
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockHeader, BlockNumber};
+use pathfinder_rpc::{Notifications, Reorg};
+use pathfinder_storage::{BlockId, Storage};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of the latest blocks remembered to find where a reorg starts.
+const HISTORY: usize = 128;
+
+/// Publishes the changes made to the database by its writer.
+pub async fn follow(storage: Storage, notifications: Notifications) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    let mut head = Head::default();
+    loop {
+        interval.tick().await;
+
+        let storage = storage.clone();
+        let (returned, result) = tokio::task::spawn_blocking(move || {
+            let result = head.poll(&storage);
+            (head, result)
+        })
+        .await
+        .context("Joining database polling task")?;
+        head = returned;
+
+        let changes = match result {
+            Ok(changes) => changes,
+            Err(error) => {
+                tracing::warn!(%error, "Failed to poll database for changes");
+                continue;
+            }
+        };
+        for change in changes {
+            match change {
+                Change::Commit(header) => notifications.publish_commit(header.into()),
+                Change::Reorg(reorg) => notifications.publish_reorg(reorg.into()),
+                Change::L1Accepted(number) => notifications.publish_l1_accepted(number),
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Commit(BlockHeader),
+    Reorg(Reorg),
+    L1Accepted(BlockNumber),
+}
+
+/// The chain head as last seen in the database.
+#[derive(Default)]
+struct Head {
+    /// The latest blocks seen, oldest first.
+    recent: VecDeque<(BlockNumber, BlockHash)>,
+    /// Whether the database has been polled before.
+    started: bool,
+    l1_accepted: Option<BlockNumber>,
+}
+
+impl Head {
+    /// Returns the changes to the chain since the last poll.
+    ///
+    /// On the first poll the current head is only recorded, as there is
+    /// nobody to notify of earlier blocks yet.
+    fn poll(&mut self, storage: &Storage) -> anyhow::Result<Vec<Change>> {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        // All reads see the same snapshot of the database.
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let mut changes = Vec::new();
+
+        let mut removed: Option<(BlockNumber, BlockHash, BlockNumber, BlockHash)> = None;
+        while let Some(&(number, hash)) = self.recent.back() {
+            if db.block_hash(number.into())? == Some(hash) {
+                break;
+            }
+            self.recent.pop_back();
+            removed = Some(match removed {
+                Some((_, _, last_number, last_hash)) => (number, hash, last_number, last_hash),
+                None => (number, hash, number, hash),
+            });
+        }
+
+        let latest = db.block_id(BlockId::Latest)?;
+        let first_new = match (self.recent.back(), &removed, latest) {
+            (Some((number, _)), _, _) => *number + 1,
+            (None, Some((first_number, ..)), _) => *first_number,
+            (None, None, Some((number, _))) if !self.started => number,
+            (None, None, _) => BlockNumber::GENESIS,
+        };
+
+        if let Some((first_block_number, first_block_hash, last_block_number, last_block_hash)) =
+            removed
+        {
+            changes.push(Change::Reorg(Reorg {
+                first_block_number,
+                first_block_hash,
+                last_block_number,
+                last_block_hash,
+            }));
+        }
+
+        if let Some((latest, _)) = latest {
+            let mut number = first_new;
+            while number <= latest {
+                let header = db
+                    .block_header(number.into())?
+                    .context("Block header is missing")?;
+                self.recent.push_back((header.number, header.hash));
+                if self.recent.len() > HISTORY {
+                    self.recent.pop_front();
+                }
+                if self.started {
+                    changes.push(Change::Commit(header));
+                }
+                number += 1;
+            }
+        }
+
+        let l1_accepted = db.l1_l2_pointer()?;
+        if l1_accepted != self.l1_accepted {
+            self.l1_accepted = l1_accepted;
+            if let (true, Some(number)) = (self.started, l1_accepted) {
+                changes.push(Change::L1Accepted(number));
+            }
+        }
+
+        self.started = true;
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockHeader;
+
+    use super::*;
+
+    fn header(number: u64, hash: BlockHash) -> BlockHeader {
+        BlockHeader {
+            number: BlockNumber::new_or_panic(number),
+            hash,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn commits_and_reorgs_are_detected() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let mut head = Head::default();
+
+        let genesis = header(0, block_hash!("0x0"));
+        let tx = connection.transaction().unwrap();
+        tx.insert_block_header(&genesis).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(head.poll(&storage).unwrap(), vec![]);
+
+        let block_1 = header(1, block_hash!("0x1"));
+        let block_2 = header(2, block_hash!("0x2"));
+        let tx = connection.transaction().unwrap();
+        tx.insert_block_header(&block_1).unwrap();
+        tx.insert_block_header(&block_2).unwrap();
+        tx.update_l1_l2_pointer(Some(BlockNumber::GENESIS)).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(
+            head.poll(&storage).unwrap(),
+            vec![
+                Change::Commit(block_1.clone()),
+                Change::Commit(block_2.clone()),
+                Change::L1Accepted(BlockNumber::GENESIS),
+            ]
+        );
+        assert_eq!(head.poll(&storage).unwrap(), vec![]);
+
+        let block_1b = header(1, block_hash!("0x1b"));
+        let tx = connection.transaction().unwrap();
+        tx.purge_block(block_2.number).unwrap();
+        tx.purge_block(block_1.number).unwrap();
+        tx.insert_block_header(&block_1b).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(
+            head.poll(&storage).unwrap(),
+            vec![
+                Change::Reorg(Reorg {
+                    first_block_number: block_1.number,
+                    first_block_hash: block_1.hash,
+                    last_block_number: block_2.number,
+                    last_block_hash: block_2.hash,
+                }),
+                Change::Commit(block_1b),
+            ]
+        );
+    }
+}
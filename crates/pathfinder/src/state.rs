@@ -2,8 +2,11 @@ pub mod block_hash;
 mod sync;
 
 pub use sync::{
+    default_trie_threads,
+    init_trie_thread_pool,
     l1,
     l2,
+    replication,
     revert,
     sync,
     update_starknet_state,
@@ -2,14 +2,17 @@ mod class;
 pub mod l1;
 pub mod l2;
 mod pending;
+pub mod replication;
 pub mod revert;
 
 use std::collections::HashMap;
 use std::future::Future;
-use std::sync::Arc;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 
 use anyhow::Context;
+use pathfinder_common::memory::{self, Subsystem};
 use pathfinder_common::prelude::*;
 use pathfinder_common::state_update::{ContractUpdate, SystemContractUpdate};
 use pathfinder_common::{
@@ -22,7 +25,7 @@ use pathfinder_common::{
 };
 use pathfinder_crypto::Felt;
 use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
-use pathfinder_merkle_tree::contract_state::update_contract_state;
+use pathfinder_merkle_tree::contract_state::{update_contract_state, ContractStateUpdateResult};
 use pathfinder_merkle_tree::{ClassCommitmentTree, StorageCommitmentTree};
 use pathfinder_rpc::v02::types::syncing::{self, NumberedBlock, Syncing};
 use pathfinder_rpc::{Notifications, PendingData, Reorg, SyncState, TopicBroadcasters};
@@ -35,6 +38,7 @@ use tokio::sync::watch::Sender as WatchSender;
 
 use crate::state::l1::L1SyncContext;
 use crate::state::l2::{BlockChain, L2SyncContext};
+use crate::state::sync::class::ClassFetcher;
 
 #[derive(Debug)]
 pub enum SyncEvent {
@@ -70,8 +74,17 @@ pub enum SyncEvent {
     Pending((Arc<PendingBlock>, Arc<StateUpdate>)),
     /// A new L1 to L2 message was finalized.
     L1ToL2Message(L1ToL2MessageLog),
+    /// All L1 state updates up to this Ethereum block have been sent.
+    L1ScannedTo(u64),
 }
 
+/// How many times a failed class download is retried before the block is
+/// downloaded again from scratch.
+const CLASS_DOWNLOAD_RETRIES: NonZeroUsize = match NonZeroUsize::new(3) {
+    Some(retries) => retries,
+    None => unreachable!(),
+};
+
 pub struct SyncContext<G, E> {
     pub storage: Storage,
     pub ethereum: E,
@@ -82,6 +95,7 @@ pub struct SyncContext<G, E> {
     pub state: Arc<SyncState>,
     pub head_poll_interval: Duration,
     pub l1_poll_interval: Duration,
+    pub l1_scan_chunk_size: std::num::NonZeroU64,
     pub pending_data: WatchSender<PendingData>,
     pub block_validation_mode: l2::BlockValidationMode,
     pub websocket_txs: Option<TopicBroadcasters>,
@@ -92,6 +106,7 @@ pub struct SyncContext<G, E> {
     pub gossiper: Gossiper,
     pub sequencer_public_key: PublicKey,
     pub fetch_concurrency: std::num::NonZeroUsize,
+    pub class_fetch_concurrency: std::num::NonZeroUsize,
 }
 
 impl<G, E> From<&SyncContext<G, E>> for L1SyncContext<E>
@@ -104,6 +119,8 @@ where
             chain: value.chain,
             core_address: value.core_address,
             poll_interval: value.l1_poll_interval,
+            scan_chunk_size: value.l1_scan_chunk_size,
+            storage: value.storage.clone(),
         }
     }
 }
@@ -121,6 +138,8 @@ where
             storage: value.storage.clone(),
             sequencer_public_key: value.sequencer_public_key,
             fetch_concurrency: value.fetch_concurrency,
+            class_fetcher: ClassFetcher::new(value.class_fetch_concurrency)
+                .with_retries(CLASS_DOWNLOAD_RETRIES),
         }
     }
 }
@@ -176,7 +195,7 @@ where
             BlockChain,
             tokio::sync::watch::Receiver<(BlockNumber, BlockHash)>,
         ) -> F2
-        + Copy,
+        + Clone,
 {
     let l1_context = L1SyncContext::from(&context);
     let l2_context = L2SyncContext::from(&context);
@@ -191,6 +210,7 @@ where
         state,
         head_poll_interval,
         l1_poll_interval: _,
+        l1_scan_chunk_size: _,
         pending_data,
         block_validation_mode: _,
         websocket_txs,
@@ -201,6 +221,7 @@ where
         gossiper,
         sequencer_public_key: _,
         fetch_concurrency: _,
+        class_fetch_concurrency: _,
     } = context;
 
     let mut db_conn = storage
@@ -257,7 +278,7 @@ where
 
     // Start L2 producer task. Clone the event sender so that the channel remains
     // open even if the producer task fails.
-    let mut l2_handle = tokio::spawn(l2_sync(
+    let mut l2_handle = tokio::spawn(l2_sync.clone()(
         event_sender.clone(),
         l2_context.clone(),
         l2_head,
@@ -359,7 +380,7 @@ where
 
                 let latest_blocks = latest_n_blocks(&mut db_conn, block_cache_size).await.context("Fetching latest blocks from storage")?;
                 let block_chain = BlockChain::with_capacity(1_000, latest_blocks);
-                let fut = l2_sync(event_sender.clone(), l2_context.clone(), l2_head, block_chain, rx_latest.clone());
+                let fut = l2_sync.clone()(event_sender.clone(), l2_context.clone(), l2_head, block_chain, rx_latest.clone());
 
                 l2_handle = tokio::spawn(async move {
                     tokio::time::sleep(restart_delay).await;
@@ -374,6 +395,11 @@ where
                     },
                     Ok(Err(e)) => {
                         tracing::error!(reason=?e, "Sync consumer task terminated with an error");
+                        // The consumer's failures are almost always database errors.
+                        pathfinder_common::telemetry::record(
+                            pathfinder_common::telemetry::ErrorKind::Database,
+                            &format!("{e:#}"),
+                        );
                     }
                     Err(e) if e.is_cancelled() => {
                         tracing::debug!("Sync consumer task cancelled successfully");
@@ -486,11 +512,19 @@ async fn consumer(
     .context("Fetching latest block time")?;
 
     while let Some(event) = events.recv().await {
+        metrics::gauge!(
+            "sync_stage_queue_depth",
+            (events.max_capacity() - events.capacity()) as f64,
+            "stage" => "persist"
+        );
+
         use SyncEvent::*;
         match event {
             L1Update(update) => {
                 tracing::trace!("Updating L1 sync to block {}", update.block_number);
-                l1_update(&mut db_conn, &update).await?;
+                if l1_update(&mut db_conn, &update).await? {
+                    notifications.publish_l1_accepted(update.block_number);
+                }
                 tracing::info!("L1 sync updated to block {}", update.block_number);
             }
             Block(
@@ -563,6 +597,22 @@ async fn consumer(
                     + timings.signature_download)
                     .as_secs_f64();
 
+                metrics::histogram!(
+                    "sync_stage_duration_seconds",
+                    timings.block_download + timings.signature_download,
+                    "stage" => "download"
+                );
+                metrics::histogram!(
+                    "sync_stage_duration_seconds",
+                    timings.verification,
+                    "stage" => "verify"
+                );
+                metrics::histogram!(
+                    "sync_stage_duration_seconds",
+                    timings.class_declaration,
+                    "stage" => "classes"
+                );
+
                 metrics::gauge!("block_download", download_time);
                 metrics::gauge!("block_processing", update_t.as_secs_f64());
                 metrics::histogram!("block_processing_duration_seconds", update_t);
@@ -676,18 +726,43 @@ async fn consumer(
                 .context("Fetching latest block hash")?;
 
                 if pending.0.parent_hash == hash {
+                    let previous = pending_data.borrow().clone();
+                    notifications
+                        .dropped_transactions
+                        .record_pending_update(&previous.block, &pending.0);
                     let data = PendingData {
                         block: pending.0,
                         state_update: pending.1,
                         number: number + 1,
                     };
                     pending_data.send_replace(data);
+                    notifications.publish_pending_update(number + 1);
                     tracing::debug!("Updated pending data");
                 }
             }
             L1ToL2Message(msg) => {
                 tracing::trace!("Got a new L1 to L2 message log: {:?}", msg);
-                // todo!()
+                tokio::task::block_in_place(|| {
+                    let tx = db_conn
+                        .transaction()
+                        .context("Create database transaction")?;
+                    tx.insert_l1_message(&msg)
+                        .context("Inserting L1 to L2 message")?;
+                    tx.commit().context("Commit database transaction")
+                })?;
+            }
+            L1ScannedTo(eth_block) => {
+                // Events are handled in order, so all state updates up to this block have been
+                // stored.
+                tokio::task::block_in_place(|| {
+                    let tx = db_conn
+                        .transaction()
+                        .context("Create database transaction")?;
+                    tx.update_l1_scanned_to(eth_block)
+                        .context("Updating L1 log scan checkpoint")?;
+                    tx.commit().context("Commit database transaction")
+                })?;
+                tracing::trace!(%eth_block, "L1 logs scanned");
             }
         }
     }
@@ -806,10 +881,12 @@ async fn propagate_head(gossiper: &Gossiper, last_propagated: &mut Instant, head
     *last_propagated = Instant::now();
 }
 
+/// Returns whether the L1 update matches the block on L2, which is then
+/// accepted on L1.
 async fn l1_update(
     connection: &mut Connection,
     update: &EthereumStateUpdate,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<bool> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
@@ -819,16 +896,26 @@ async fn l1_update(
             .upsert_l1_state(update)
             .context("Insert update")?;
 
-        let l2_hash = transaction
-            .block_hash(update.block_number.into())
-            .context("Fetching block hash")?;
-
-        if let Some(l2_hash) = l2_hash {
-            if l2_hash == update.block_hash {
+        let l2_header = transaction
+            .block_header(update.block_number.into())
+            .context("Fetching block header")?;
+
+        let mut accepted = false;
+        if let Some(l2_header) = l2_header {
+            let l2_hash = l2_header.hash;
+            // State updates logged by the legacy core contract ABI do not include the
+            // block hash, so only their state commitment can be compared.
+            let matches = if update.block_hash == BlockHash::ZERO {
+                l2_header.state_commitment == update.state_root
+            } else {
+                l2_hash == update.block_hash
+            };
+            if matches {
                 transaction
                     .update_l1_l2_pointer(Some(update.block_number))
                     .context("Updating L1-L2 pointer")?;
                 tracing::info!(block=?update.block_number, "Updated L1/L2 match");
+                accepted = true;
             } else {
                 tracing::warn!(block_number=?update.block_number, L1=?update.block_hash, L2=?l2_hash, "L1/L2 block hash mismatch");
                 if let Some(matching_block_number) = transaction.l1_l2_pointer()? {
@@ -837,7 +924,10 @@ async fn l1_update(
             }
         }
 
-        transaction.commit().context("Commit database transaction")
+        transaction
+            .commit()
+            .context("Commit database transaction")?;
+        Ok(accepted)
     })
 }
 
@@ -863,6 +953,7 @@ async fn l2_update(
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
+        let t_trie_update = std::time::Instant::now();
         let (storage_commitment, class_commitment) = update_starknet_state(
             &transaction,
             StarknetStateUpdate {
@@ -883,6 +974,12 @@ async fn l2_update(
             state_commitment == block.state_commitment,
             "State root mismatch"
         );
+        metrics::histogram!(
+            "sync_stage_duration_seconds",
+            t_trie_update.elapsed(),
+            "stage" => "trie_update"
+        );
+        let t_persist = std::time::Instant::now();
 
         let transaction_count = block.transactions.len();
         let event_count = block
@@ -979,6 +1076,11 @@ async fn l2_update(
         transaction
             .commit()
             .context("Commit database transaction")?;
+        metrics::histogram!(
+            "sync_stage_duration_seconds",
+            t_persist.elapsed(),
+            "stage" => "persist"
+        );
 
         if let Some(sender) = websocket_txs {
             if let Err(e) = sender.new_head.send_if_receiving(header.clone().into()) {
@@ -998,12 +1100,7 @@ async fn l2_update(
             }
         }
 
-        notifications
-            .block_headers
-            .send(header.into())
-            // Ignore errors in case nobody is listening. New listeners may subscribe in the
-            // future.
-            .ok();
+        notifications.publish_commit(header.into());
 
         Ok(())
     })?;
@@ -1021,6 +1118,19 @@ async fn l2_reorg(
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
+        // The state before the first reorged block is needed to revert the reorged
+        // blocks, so a reorg reaching into the pruned range cannot be handled.
+        if let Some(pruned_to) = transaction
+            .state_pruned_to()
+            .context("Querying state pruning checkpoint")?
+        {
+            anyhow::ensure!(
+                reorg_tail > pruned_to,
+                "Reorg to block {reorg_tail} is deeper than the retained block window, state \
+                 has been pruned up to block {pruned_to}"
+            );
+        }
+
         let mut head = transaction
             .block_id(pathfinder_storage::BlockId::Latest)
             .context("Querying latest block number")?
@@ -1040,6 +1150,14 @@ async fn l2_reorg(
             .increment_reorg_counter()
             .context("Incrementing reorg counter")?;
 
+        let detected_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        transaction
+            .record_reorg(detected_at, reorg_tail, head)
+            .context("Recording reorg")?;
+
         // Roll back Merkle trie updates.
         //
         // If we're rolling back genesis then there will be no blocks left so state will
@@ -1093,20 +1211,15 @@ async fn l2_reorg(
             .commit()
             .context("Commit database transaction")?;
 
-        notifications
-            .reorgs
-            .send(
-                Reorg {
-                    first_block_number: reorg_tail,
-                    first_block_hash: reorg_tail_hash,
-                    last_block_number: head,
-                    last_block_hash: head_hash,
-                }
-                .into(),
-            )
-            // Ignore errors in case nobody is listening. New listeners may subscribe in the
-            // future.
-            .ok();
+        notifications.publish_reorg(
+            Reorg {
+                first_block_number: reorg_tail,
+                first_block_hash: reorg_tail_hash,
+                last_block_number: head,
+                last_block_hash: head_hash,
+            }
+            .into(),
+        );
 
         Ok(())
     })
@@ -1118,6 +1231,83 @@ pub struct StarknetStateUpdate<'a> {
     pub declared_sierra_classes: &'a HashMap<SierraHash, CasmHash>,
 }
 
+/// Thread pool used for Merkle trie updates.
+///
+/// Kept separate from the global rayon pool so that trie updates, which
+/// dominate sync time for blocks with large state diffs, do not compete with
+/// block verification for threads.
+static TRIE_THREAD_POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// Number of contracts whose storage tries are updated and sent for persisting
+/// together.
+const CONTRACT_UPDATE_BATCH_SIZE: usize = 32;
+
+/// The default size of the trie thread pool: half of the CPUs, since the
+/// global rayon pool already runs one thread per CPU.
+pub fn default_trie_threads() -> NonZeroUsize {
+    let cpus = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    NonZeroUsize::new(cpus / 2).unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Creates the thread pool used for Merkle trie updates with `num_threads`
+/// threads.
+///
+/// Must be called before syncing starts, otherwise the pool is created with
+/// [default_trie_threads] threads on first use.
+pub fn init_trie_thread_pool(num_threads: NonZeroUsize) -> anyhow::Result<()> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .thread_name(|thread_index| format!("trie-{thread_index}"))
+        .num_threads(num_threads.get())
+        .build()
+        .context("Creating trie thread pool")?;
+    TRIE_THREAD_POOL
+        .set(pool)
+        .map_err(|_| anyhow::anyhow!("Trie thread pool already initialized"))
+}
+
+fn trie_thread_pool() -> &'static rayon::ThreadPool {
+    TRIE_THREAD_POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|thread_index| format!("trie-{thread_index}"))
+            .num_threads(default_trie_threads().get())
+            .build()
+            .expect("Creating trie thread pool")
+    })
+}
+
+/// Updates the storage tries of a batch of contracts, reading from a
+/// transaction on this worker's own database connection.
+fn update_contract_states(
+    connection: &mut anyhow::Result<Connection>,
+    batch: &[(&ContractAddress, &ContractUpdate)],
+    verify_hashes: bool,
+    block: BlockNumber,
+) -> anyhow::Result<Vec<ContractStateUpdateResult>> {
+    let _memory = memory::enter(Subsystem::Tries);
+    let connection = match connection {
+        Ok(connection) => connection,
+        Err(e) => anyhow::bail!(
+            "Failed to create database connection in rayon thread: {}",
+            e
+        ),
+    };
+    let transaction = connection.transaction()?;
+    batch
+        .iter()
+        .map(|(contract_address, update)| {
+            update_contract_state(
+                **contract_address,
+                &update.storage,
+                update.nonce,
+                update.class.as_ref().map(|x| x.class_hash()),
+                &transaction,
+                verify_hashes,
+                block,
+            )
+        })
+        .collect()
+}
+
 pub fn update_starknet_state(
     transaction: &Transaction<'_>,
     state_update: StarknetStateUpdate<'_>,
@@ -1129,6 +1319,8 @@ pub fn update_starknet_state(
 ) -> anyhow::Result<(StorageCommitment, ClassCommitment)> {
     use rayon::prelude::*;
 
+    let _memory = memory::enter(Subsystem::Tries);
+
     let mut storage_commitment_tree = match block.parent() {
         Some(parent) => StorageCommitmentTree::load(transaction, parent)
             .context("Loading storage commitment tree")?,
@@ -1136,53 +1328,44 @@ pub fn update_starknet_state(
     }
     .with_verify_hashes(verify_hashes);
 
+    let contract_updates = state_update.contract_updates.iter().collect::<Vec<_>>();
+    let contract_updates = &contract_updates;
     let (send, recv) = std::sync::mpsc::channel();
 
-    rayon::scope(|s| {
-        s.spawn(|_| {
-            let result: Result<Vec<_>, _> = state_update
-                .contract_updates
-                .par_iter()
-                .map_init(
-                    || storage.clone().connection(),
-                    |connection, (contract_address, update)| {
-                        let connection = match connection {
-                            Ok(connection) => connection,
-                            Err(e) => anyhow::bail!(
-                                "Failed to create database connection in rayon thread: {}",
-                                e
-                            ),
-                        };
-                        let transaction = connection.transaction()?;
-                        update_contract_state(
-                            *contract_address,
-                            &update.storage,
-                            update.nonce,
-                            update.class.as_ref().map(|x| x.class_hash()),
-                            &transaction,
-                            verify_hashes,
-                            block,
-                        )
+    // Contract tries are updated in batches on the trie thread pool while this
+    // thread persists each finished batch, so the (single) write transaction is
+    // never idle waiting for the slowest contract. A failed insert drops the
+    // receiver, which stops the workers at their next batch.
+    trie_thread_pool().in_place_scope(|s| {
+        s.spawn(move |_| {
+            let _ = contract_updates
+                .par_chunks(CONTRACT_UPDATE_BATCH_SIZE)
+                .try_for_each_init(
+                    || (send.clone(), storage.clone().connection()),
+                    |(send, connection), batch| {
+                        let result =
+                            update_contract_states(connection, batch, verify_hashes, block);
+                        send.send(result)
                     },
-                )
-                .collect();
-            let _ = send.send(result);
-        })
-    });
-
-    let contract_update_results = recv.recv().context("Panic on rayon thread")??;
+                );
+        });
+
+        for batch in recv {
+            for contract_update_result in batch? {
+                storage_commitment_tree
+                    .set(
+                        contract_update_result.contract_address,
+                        contract_update_result.state_hash,
+                    )
+                    .context("Updating storage commitment tree")?;
+                contract_update_result
+                    .insert(block, transaction)
+                    .context("Inserting contract update result")?;
+            }
+        }
 
-    for contract_update_result in contract_update_results.into_iter() {
-        storage_commitment_tree
-            .set(
-                contract_update_result.contract_address,
-                contract_update_result.state_hash,
-            )
-            .context("Updating storage commitment tree")?;
-        contract_update_result
-            .insert(block, transaction)
-            .context("Inserting contract update result")?;
-    }
+        anyhow::Ok(())
+    })?;
 
     for (contract, update) in state_update.system_contract_updates {
         let update_result = update_contract_state(
@@ -1547,6 +1730,70 @@ mod tests {
         assert!(block_2_exists);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reorg_into_pruned_state_is_rejected() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+        for (a, b, c, d, e) in generate_block_data() {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        drop(event_tx);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = ConsumerContext {
+            storage: storage.clone(),
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            notifications: Default::default(),
+        };
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        let tx = connection.transaction().unwrap();
+        tx.prune_state_updates(BlockNumber::new_or_panic(1), 10)
+            .unwrap();
+        tx.commit().unwrap();
+
+        // Reorging block 1 needs the pruned state of block 0.
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+        event_tx
+            .send(SyncEvent::Reorg(BlockNumber::new_or_panic(1)))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = ConsumerContext {
+            storage,
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            notifications: Default::default(),
+        };
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let error = consumer(event_rx, context, tx).await.unwrap_err();
+        assert!(
+            format!("{error:#}").contains("deeper than the retained block window"),
+            "{error:#}"
+        );
+
+        // Nothing was purged.
+        let tx = connection.transaction().unwrap();
+        for i in 0..3 {
+            assert!(tx
+                .block_exists(BlockNumber::new_or_panic(i).into())
+                .unwrap());
+        }
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn reorg_to_genesis() {
         let storage = StorageBuilder::in_memory().unwrap();
@@ -1,6 +1,12 @@
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Context;
 use pathfinder_common::{ClassHash, SierraHash};
+use pathfinder_retry::Retry;
 use starknet_gateway_client::GatewayApi;
+use tokio::sync::Semaphore;
 
 pub enum DownloadedClass {
     Cairo {
@@ -14,6 +20,60 @@ pub enum DownloadedClass {
     },
 }
 
+/// A pool of concurrent class downloads, shared by all the blocks being
+/// downloaded so that the number of requests to the feeder gateway stays
+/// bounded however many classes the blocks declare.
+#[derive(Clone)]
+pub struct ClassFetcher {
+    permits: Arc<Semaphore>,
+    max_retries: Option<NonZeroUsize>,
+}
+
+impl ClassFetcher {
+    pub fn new(concurrency: NonZeroUsize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(concurrency.get())),
+            max_retries: None,
+        }
+    }
+
+    /// Retries failed downloads up to `max_retries` times, with exponential
+    /// backoff. This covers feeder gateways which are not yet aware of a class
+    /// declared in a block they have served.
+    pub fn with_retries(mut self, max_retries: NonZeroUsize) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Downloads the class once one of the pool's requests is free.
+    pub async fn download<SequencerClient: GatewayApi>(
+        &self,
+        sequencer: &SequencerClient,
+        class_hash: ClassHash,
+    ) -> anyhow::Result<DownloadedClass> {
+        let attempt = || async {
+            // Backoff delays between attempts do not hold on to a request.
+            let _permit = self
+                .permits
+                .acquire()
+                .await
+                .expect("Semaphore is never closed");
+            download_class(sequencer, class_hash).await
+        };
+
+        match self.max_retries {
+            Some(max_retries) => {
+                Retry::exponential(attempt, NonZeroU64::new(2).unwrap())
+                    .max_delay(Duration::from_secs(30))
+                    .max_num_retries(max_retries)
+                    .on_any_err()
+                    .await
+            }
+            None => attempt().await,
+        }
+    }
+}
+
 pub async fn download_class<SequencerClient: GatewayApi>(
     sequencer: &SequencerClient,
     class_hash: ClassHash,
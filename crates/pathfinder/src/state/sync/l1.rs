@@ -1,7 +1,10 @@
+use std::num::NonZeroU64;
 use std::time::Duration;
 
+use anyhow::Context;
 use pathfinder_common::Chain;
-use pathfinder_ethereum::{EthereumApi, EthereumEvent};
+use pathfinder_ethereum::{EthereumApi, EthereumEvent, LogScan};
+use pathfinder_storage::Storage;
 use primitive_types::H160;
 use tokio::sync::mpsc;
 
@@ -16,11 +19,18 @@ pub struct L1SyncContext<EthereumClient> {
     pub core_address: H160,
     /// The interval at which to poll for updates on finalized blocks
     pub poll_interval: Duration,
+    /// The number of Ethereum blocks to fetch state update logs for at once
+    pub scan_chunk_size: NonZeroU64,
+    pub storage: Storage,
 }
 
 /// Syncs L1 state update logs. Emits [Ethereum state
 /// update](pathfinder_ethereum::EthereumStateUpdate) which should be handled to
 /// update storage and respond to queries.
+///
+/// Scanning resumes from the last Ethereum block recorded with
+/// [SyncEvent::L1ScannedTo], so that no state updates are missed across
+/// restarts.
 pub async fn sync<T>(
     tx_event: mpsc::Sender<SyncEvent>,
     context: L1SyncContext<T>,
@@ -33,16 +43,42 @@ where
         chain: _,
         core_address,
         poll_interval,
+        scan_chunk_size,
+        storage,
     } = context;
 
-    // Fetch the current Starknet state from Ethereum
-    let state_update = ethereum.get_starknet_state(&core_address).await?;
-    let _ = tx_event.send(SyncEvent::L1Update(state_update)).await;
+    let scanned_to = tokio::task::spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+        tx.l1_scanned_to()
+    })
+    .await
+    .context("Joining blocking task")?
+    .context("Querying L1 log scan checkpoint")?;
+
+    match scanned_to {
+        Some(scanned_to) => {
+            tracing::debug!(%scanned_to, "Resuming L1 log scan");
+        }
+        None => {
+            // Fetch the current Starknet state from Ethereum since there is no
+            // history to catch up on.
+            let state_update = ethereum.get_starknet_state(&core_address).await?;
+            let _ = tx_event.send(SyncEvent::L1Update(state_update)).await;
+        }
+    }
+
+    let scan = LogScan {
+        scanned_to,
+        chunk_size: scan_chunk_size,
+    };
 
     // Subscribe to subsequent state updates and message logs
     let tx_event = std::sync::Arc::new(tx_event);
     ethereum
-        .listen(&core_address, poll_interval, move |event| {
+        .listen(&core_address, poll_interval, scan, move |event| {
             let tx_event = tx_event.clone();
             async move {
                 match event {
@@ -52,6 +88,9 @@ where
                     EthereumEvent::MessageLog(log) => {
                         let _ = tx_event.send(SyncEvent::L1ToL2Message(log)).await;
                     }
+                    EthereumEvent::ScannedTo(eth_block) => {
+                        let _ = tx_event.send(SyncEvent::L1ScannedTo(eth_block)).await;
+                    }
                 }
             }
         })
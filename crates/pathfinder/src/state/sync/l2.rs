@@ -2,7 +2,7 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::Duration;
 
 use anyhow::{anyhow, Context};
-use futures::{StreamExt, TryStreamExt};
+use futures::StreamExt;
 use pathfinder_common::state_update::{ContractClassUpdate, StateUpdateData};
 use pathfinder_common::{
     BlockCommitmentSignature,
@@ -29,7 +29,7 @@ use tokio::sync::mpsc;
 use tracing::Instrument;
 
 use crate::state::block_hash::{verify_gateway_block_commitments_and_hash, VerifyResult};
-use crate::state::sync::class::{download_class, DownloadedClass};
+use crate::state::sync::class::{ClassFetcher, DownloadedClass};
 use crate::state::sync::SyncEvent;
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -37,6 +37,9 @@ pub struct Timings {
     pub block_download: Duration,
     pub class_declaration: Duration,
     pub signature_download: Duration,
+    /// Time spent verifying commitments and signatures, excluding the block
+    /// hash check done while downloading new blocks at the chain tip.
+    pub verification: Duration,
 }
 
 /// A cache containing the last `N` blocks in the chain. Used to determine reorg
@@ -101,6 +104,7 @@ pub struct L2SyncContext<GatewayClient> {
     pub storage: Storage,
     pub sequencer_public_key: PublicKey,
     pub fetch_concurrency: std::num::NonZeroUsize,
+    pub class_fetcher: ClassFetcher,
 }
 
 pub async fn sync<GatewayClient>(
@@ -132,6 +136,7 @@ where
         storage,
         sequencer_public_key,
         fetch_concurrency: _,
+        class_fetcher,
     } = context;
 
     // Start polling head of chain
@@ -235,9 +240,10 @@ where
 
         // Download and emit newly declared classes.
         let t_declare = std::time::Instant::now();
-        let downloaded_classes = download_new_classes(&state_update, &sequencer, storage.clone())
-            .await
-            .with_context(|| format!("Handling newly declared classes for block {next:?}"))?;
+        let downloaded_classes =
+            download_new_classes(&state_update, &sequencer, storage.clone(), &class_fetcher)
+                .await
+                .with_context(|| format!("Handling newly declared classes for block {next:?}"))?;
         emit_events_for_downloaded_classes(
             &tx_event,
             downloaded_classes,
@@ -280,6 +286,8 @@ where
             block.block_hash.0,
         );
 
+        let t_verification = std::time::Instant::now();
+
         // Always compute the state diff commitment from the state update.
         // If any of the feeder gateway replies (block or signature) contain a state
         // diff commitment, check if the value matches. If it doesn't, just log the
@@ -343,6 +351,7 @@ where
             }
             BlockValidationMode::AllowMismatch => (signature, state_update),
         };
+        let t_verification = t_verification.elapsed();
 
         head = Some((next, block.block_hash, state_update.state_commitment));
         blocks.push(next, block.block_hash, state_update.state_commitment);
@@ -351,6 +360,7 @@ where
             block_download: t_block,
             class_declaration: t_declare,
             signature_download: t_signature,
+            verification: t_verification,
         };
 
         tx_event
@@ -414,6 +424,7 @@ pub async fn download_new_classes(
     state_update: &StateUpdate,
     sequencer: &impl GatewayApi,
     storage: Storage,
+    class_fetcher: &ClassFetcher,
 ) -> Result<Vec<DownloadedClass>, anyhow::Error> {
     let deployed_classes = state_update
         .contract_updates
@@ -465,18 +476,19 @@ pub async fn download_new_classes(
     .context("Joining database task")?
     .context("Querying database for missing classes")?;
 
+    // All classes are requested at once, the fetcher limits how many are
+    // downloaded concurrently.
     let futures = require_downloading.into_iter().map(|class_hash| {
         async move {
-            download_class(sequencer, class_hash)
+            class_fetcher
+                .download(sequencer, class_hash)
                 .await
                 .with_context(|| format!("Downloading class {}", class_hash.0))
         }
         .in_current_span()
     });
 
-    let stream = futures::stream::iter(futures).buffer_unordered(4);
-
-    let downloaded_classes = stream.try_collect().await?;
+    let downloaded_classes = futures::future::try_join_all(futures).await?;
 
     Ok(downloaded_classes)
 }
@@ -641,6 +653,7 @@ where
         storage,
         sequencer_public_key,
         fetch_concurrency,
+        class_fetcher,
     } = context;
 
     let mut start = match head {
@@ -664,6 +677,7 @@ where
 
             let sequencer = sequencer.clone();
             let storage = storage.clone();
+            let class_fetcher = class_fetcher.clone();
 
             async move {
                 let t_block = std::time::Instant::now();
@@ -722,34 +736,39 @@ where
                     let t_verification = t_verification.elapsed();
                     tracing::trace!(elapsed=?t_verification, "Block verification done");
 
-                    let _ = tx.send(result);
+                    let _ = tx.send(result.map(|result| (result, t_verification)));
                 });
 
                 let (
-                    block,
-                    state_update,
-                    signature,
-                    transaction_commitment,
-                    event_commitment,
-                    receipt_commitment,
-                    state_diff_commitment,
+                    (
+                        block,
+                        state_update,
+                        signature,
+                        transaction_commitment,
+                        event_commitment,
+                        receipt_commitment,
+                        state_diff_commitment,
+                    ),
+                    t_verification,
                 ) = rx
                     .await
                     .expect("Panic on rayon thread while verifying block")
                     .context("Verifying block contents")?;
 
                 let t_declare = std::time::Instant::now();
-                let downloaded_classes = download_new_classes(&state_update, &sequencer, storage)
-                    .await
-                    .with_context(|| {
-                        format!("Handling newly declared classes for block {block_number:?}")
-                    })?;
+                let downloaded_classes =
+                    download_new_classes(&state_update, &sequencer, storage, &class_fetcher)
+                        .await
+                        .with_context(|| {
+                            format!("Handling newly declared classes for block {block_number:?}")
+                        })?;
                 let t_declare = t_declare.elapsed();
 
                 let timings = Timings {
                     block_download: t_block,
                     class_declaration: t_declare,
                     signature_download: t_signature,
+                    verification: t_verification,
                 };
 
                 Ok::<_, anyhow::Error>((
@@ -1135,7 +1154,7 @@ mod tests {
         use tokio::sync::mpsc;
         use tokio::task::JoinHandle;
 
-        use super::super::{bulk_sync, sync, BlockValidationMode, SyncEvent};
+        use super::super::{bulk_sync, sync, BlockValidationMode, ClassFetcher, SyncEvent};
         use crate::state::l2::{BlockChain, L2SyncContext};
 
         const MODE: BlockValidationMode = BlockValidationMode::AllowMismatch;
@@ -1318,6 +1337,7 @@ mod tests {
                 storage,
                 sequencer_public_key: PublicKey::ZERO,
                 fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+                class_fetcher: ClassFetcher::new(std::num::NonZeroUsize::new(1).unwrap()),
             };
 
             let latest = tokio::sync::watch::channel(Default::default());
@@ -1345,6 +1365,7 @@ mod tests {
                 storage,
                 sequencer_public_key: PublicKey::ZERO,
                 fetch_concurrency: std::num::NonZeroUsize::new(2).unwrap(),
+                class_fetcher: ClassFetcher::new(std::num::NonZeroUsize::new(2).unwrap()),
             };
 
             tokio::spawn(async move {
@@ -1823,6 +1844,7 @@ mod tests {
                     storage: StorageBuilder::in_memory().unwrap(),
                     sequencer_public_key: PublicKey::ZERO,
                     fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+                    class_fetcher: ClassFetcher::new(std::num::NonZeroUsize::new(1).unwrap()),
                 };
                 let latest_track = tokio::sync::watch::channel(Default::default());
 
@@ -1,3 +1,4 @@
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 
 use pathfinder_common::{BlockHash, BlockNumber};
@@ -6,6 +7,7 @@ use starknet_gateway_client::GatewayApi;
 use tokio::sync::watch;
 use tokio::time::Instant;
 
+use crate::state::sync::class::ClassFetcher;
 use crate::state::sync::SyncEvent;
 
 /// Emits new pending data events while the current block is close to the latest
@@ -20,6 +22,9 @@ pub async fn poll_pending<S: GatewayApi + Clone + Send + 'static>(
 ) {
     let mut prev_tx_count = 0;
     let mut prev_hash = BlockHash::default();
+    // Failed downloads are not retried, the pending data is polled again soon
+    // anyway.
+    let class_fetcher = ClassFetcher::new(NonZeroUsize::new(4).unwrap());
 
     loop {
         let t_fetch = Instant::now();
@@ -56,7 +61,14 @@ pub async fn poll_pending<S: GatewayApi + Clone + Send + 'static>(
         // fail when querying a desync'd feeder gateway which isn't aware of the
         // new pending classes. In this case, ignore the new pending data as it
         // is incomplete.
-        match super::l2::download_new_classes(&state_update, &sequencer, storage.clone()).await {
+        match super::l2::download_new_classes(
+            &state_update,
+            &sequencer,
+            storage.clone(),
+            &class_fetcher,
+        )
+        .await
+        {
             Err(e) => tracing::debug!(reason=?e, "Failed to download pending classes"),
             Ok(downloaded_classes) => {
                 if let Err(e) = super::l2::emit_events_for_downloaded_classes(
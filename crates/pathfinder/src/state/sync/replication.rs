@@ -0,0 +1,915 @@
+//! Warm-standby replication of committed blocks.
+//!
+//! A primary node [serves](serve) the blocks it has committed to standby nodes
+//! over a dedicated TCP listener. A standby [follows](Standby::follow) the
+//! primary in place of the L2 sync, feeding the replicated blocks to the regular
+//! sync consumer, so that its database stays within seconds of the primary's
+//! and it can take over serving at any time. Standbys still track L1 and poll
+//! the gateway for the chain tip and the pending block, but never download
+//! blocks or classes from it.
+//!
+//! The protocol exchanges length prefixed, zstd compressed JSON messages:
+//!
+//! 1. the primary sends a [Message::Hello] with a random nonce,
+//! 2. the standby replies with a random nonce of its own,
+//! 3. the standby sends a [Message::Subscribe] with the hashes of its latest
+//!    blocks,
+//! 4. the primary sends the blocks following the latest one both nodes agree
+//!    on, preceded by a [Message::Reorg] if the standby is on another fork,
+//!    and keeps sending blocks and reorgs as they are committed.
+//!
+//! Every message after the nonces is authenticated with an HMAC-SHA256 over
+//! both nonces, its direction, its sequence number and its payload, keyed by a
+//! secret shared by both nodes. Messages are not encrypted.
+//!
+//! Until the standby's subscription is authenticated, the primary only accepts
+//! small messages, gives up after [HANDSHAKE_TIMEOUT] and does not report
+//! errors back. At most [MAX_STANDBYS] standbys are served at once.
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use hmac::{Hmac, Mac};
+use pathfinder_common::state_update::ContractClassUpdate;
+use pathfinder_common::{
+    BlockCommitmentSignature,
+    BlockCommitmentSignatureElem,
+    BlockHash,
+    BlockNumber,
+    CasmHash,
+    ChainId,
+    ClassHash,
+    SierraHash,
+    StateCommitment,
+    StateUpdate,
+};
+use pathfinder_rpc::ChainEvent;
+use pathfinder_storage::Storage;
+use rand::Rng;
+use sha2::Sha256;
+use starknet_gateway_types::reply::{self, Block, GasPrices, Status};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, Semaphore};
+
+use crate::state::l2::Timings;
+use crate::state::sync::SyncEvent;
+
+/// Checked during the handshake, so that incompatible nodes refuse to
+/// replicate.
+const PROTOCOL_VERSION: u32 = 1;
+/// Messages larger than this are rejected.
+const MAX_MESSAGE_SIZE: usize = 128 * 1024 * 1024;
+/// Messages exchanged before the peer has authenticated itself larger than
+/// this are rejected.
+const MAX_HANDSHAKE_MESSAGE_SIZE: usize = 64 * 1024;
+/// The size of the nonces exchanged during the handshake.
+const NONCE_SIZE: usize = 32;
+/// How long the handshake, up to and including the subscription, may take.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// The number of standbys a primary serves at once. Further connections are
+/// dropped.
+const MAX_STANDBYS: usize = 16;
+/// The number of latest blocks a standby reports when subscribing. Standbys
+/// which forked off earlier than this are not served.
+const RECENT_BLOCKS: u64 = 64;
+/// How often the primary sends a [Message::Heartbeat] while no blocks are
+/// committed.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// Standbys reconnect if they receive nothing from the primary for this long.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long standbys wait before reconnecting to the primary.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum Message {
+    Hello {
+        version: u32,
+        chain_id: ChainId,
+        nonce: [u8; NONCE_SIZE],
+    },
+    Subscribe {
+        /// The standby's latest blocks, newest first.
+        recent_blocks: Vec<(BlockNumber, BlockHash)>,
+    },
+    Block(Box<ReplicatedBlock>),
+    /// Blocks from this one onwards were removed.
+    Reorg(BlockNumber),
+    Heartbeat,
+    /// Sent by the primary before closing the connection.
+    Error(String),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ReplicatedBlock {
+    block: Block,
+    state_update: reply::StateUpdate,
+    signature: [BlockCommitmentSignatureElem; 2],
+    /// The classes first declared in this block.
+    classes: Vec<ReplicatedClass>,
+}
+
+/// A class with its base64 encoded definitions.
+#[derive(serde::Serialize, serde::Deserialize)]
+enum ReplicatedClass {
+    Cairo {
+        hash: ClassHash,
+        definition: String,
+    },
+    Sierra {
+        hash: SierraHash,
+        definition: String,
+        casm_hash: CasmHash,
+        casm_definition: String,
+    },
+}
+
+/// The role of a node, which determines the direction of the messages it
+/// authenticates.
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Primary,
+    Standby,
+}
+
+/// A connection between a primary and a standby.
+struct Channel {
+    stream: TcpStream,
+    role: Role,
+    authenticator: Option<Authenticator>,
+    /// Raised to [MAX_MESSAGE_SIZE] once the handshake is complete.
+    max_message_size: usize,
+}
+
+/// Authenticates the messages of a connection.
+struct Authenticator {
+    secret: Arc<[u8]>,
+    /// The primary's nonce followed by the standby's.
+    nonces: [u8; 64],
+    sent: u64,
+    received: u64,
+}
+
+impl Authenticator {
+    fn mac(&self, sender: Role, sequence: u64, payload: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(&self.nonces);
+        mac.update(&[sender as u8]);
+        mac.update(&sequence.to_be_bytes());
+        mac.update(payload);
+        mac
+    }
+}
+
+impl Channel {
+    fn new(stream: TcpStream, role: Role) -> Self {
+        Self {
+            stream,
+            role,
+            authenticator: None,
+            max_message_size: MAX_HANDSHAKE_MESSAGE_SIZE,
+        }
+    }
+
+    /// Whether the peer has sent a message proving that it knows the secret.
+    fn is_authenticated(&self) -> bool {
+        self.authenticator
+            .as_ref()
+            .is_some_and(|authenticator| authenticator.received > 0)
+    }
+
+    /// Authenticates all further messages.
+    fn authenticate(
+        &mut self,
+        secret: Arc<[u8]>,
+        primary_nonce: [u8; NONCE_SIZE],
+        standby_nonce: [u8; NONCE_SIZE],
+    ) {
+        let mut nonces = [0u8; 2 * NONCE_SIZE];
+        nonces[..NONCE_SIZE].copy_from_slice(&primary_nonce);
+        nonces[NONCE_SIZE..].copy_from_slice(&standby_nonce);
+        self.authenticator = Some(Authenticator {
+            secret,
+            nonces,
+            sent: 0,
+            received: 0,
+        });
+    }
+
+    async fn send(&mut self, message: &Message) -> anyhow::Result<()> {
+        let json = serde_json::to_vec(message).context("Serializing message")?;
+        let payload = zstd::bulk::compress(&json, 3).context("Compressing message")?;
+        self.send_raw(payload).await
+    }
+
+    async fn recv(&mut self) -> anyhow::Result<Message> {
+        let payload = self.recv_raw().await?;
+        let json = zstd::bulk::decompress(&payload, self.max_message_size)
+            .context("Decompressing message")?;
+        serde_json::from_slice(&json).context("Deserializing message")
+    }
+
+    async fn send_raw(&mut self, mut payload: Vec<u8>) -> anyhow::Result<()> {
+        if let Some(authenticator) = &mut self.authenticator {
+            let mac = authenticator.mac(self.role, authenticator.sent, &payload);
+            authenticator.sent += 1;
+            payload.extend_from_slice(&mac.finalize().into_bytes());
+        }
+
+        let length = u32::try_from(payload.len()).context("Message too large")?;
+        self.stream.write_u32(length).await?;
+        self.stream.write_all(&payload).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    async fn recv_raw(&mut self) -> anyhow::Result<Vec<u8>> {
+        let length = self.stream.read_u32().await? as usize;
+        anyhow::ensure!(
+            length <= self.max_message_size,
+            "Message too large: {length} bytes"
+        );
+        let mut payload = vec![0; length];
+        self.stream.read_exact(&mut payload).await?;
+
+        if let Some(authenticator) = &mut self.authenticator {
+            const MAC_SIZE: usize = 32;
+            anyhow::ensure!(payload.len() >= MAC_SIZE, "Message too short");
+            let tag = payload.split_off(payload.len() - MAC_SIZE);
+            let sender = match self.role {
+                Role::Primary => Role::Standby,
+                Role::Standby => Role::Primary,
+            };
+            authenticator
+                .mac(sender, authenticator.received, &payload)
+                .verify_slice(&tag)
+                .map_err(|_| anyhow::anyhow!("Message authentication failed"))?;
+            authenticator.received += 1;
+        }
+
+        Ok(payload)
+    }
+
+    /// Receives the unauthenticated nonce sent by [Channel::send_raw].
+    async fn recv_nonce(&mut self) -> anyhow::Result<[u8; NONCE_SIZE]> {
+        let length = self.stream.read_u32().await? as usize;
+        anyhow::ensure!(length == NONCE_SIZE, "Invalid nonce length: {length} bytes");
+        let mut nonce = [0u8; NONCE_SIZE];
+        self.stream.read_exact(&mut nonce).await?;
+        Ok(nonce)
+    }
+}
+
+/// Serves committed blocks to standbys connecting to `address`.
+pub async fn serve(
+    address: SocketAddr,
+    secret: String,
+    chain_id: ChainId,
+    storage: Storage,
+    chain_events: broadcast::Sender<ChainEvent>,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Binding replication listener to {address}"))?;
+    tracing::info!(%address, "Serving replication to standbys");
+    let secret: Arc<[u8]> = secret.into_bytes().into();
+    let standbys = Arc::new(Semaphore::new(MAX_STANDBYS));
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                tracing::debug!(%error, "Accepting replication connection");
+                continue;
+            }
+        };
+        let Ok(permit) = standbys.clone().try_acquire_owned() else {
+            tracing::debug!(%peer, "Too many standbys, dropping replication connection");
+            continue;
+        };
+
+        let secret = secret.clone();
+        let storage = storage.clone();
+        let events = chain_events.subscribe();
+        tokio::spawn(async move {
+            let _permit = permit;
+            tracing::info!(%peer, "Standby connected");
+            let mut channel = Channel::new(stream, Role::Primary);
+            match replicate(&mut channel, secret, chain_id, storage, events).await {
+                Ok(()) => tracing::info!(%peer, "Standby disconnected"),
+                Err(error) => {
+                    tracing::info!(%peer, reason=%error, "Standby disconnected");
+                    // Peers which have not proven that they know the secret
+                    // learn nothing about the primary. Best effort, the
+                    // standby may be gone already.
+                    if channel.is_authenticated() {
+                        _ = channel.send(&Message::Error(format!("{error:#}"))).await;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Greets a standby and waits for its subscription, returning the hashes of
+/// its latest blocks.
+async fn handshake(
+    channel: &mut Channel,
+    secret: Arc<[u8]>,
+    chain_id: ChainId,
+) -> anyhow::Result<Vec<(BlockNumber, BlockHash)>> {
+    let nonce: [u8; NONCE_SIZE] = rand::thread_rng().gen();
+    channel
+        .send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+            chain_id,
+            nonce,
+        })
+        .await?;
+    let standby_nonce = channel.recv_nonce().await?;
+    channel.authenticate(secret, nonce, standby_nonce);
+
+    let Message::Subscribe { recent_blocks } = channel.recv().await? else {
+        anyhow::bail!("Expected a subscription");
+    };
+    channel.max_message_size = MAX_MESSAGE_SIZE;
+
+    Ok(recent_blocks)
+}
+
+/// Streams blocks to a standby until it disconnects.
+async fn replicate(
+    channel: &mut Channel,
+    secret: Arc<[u8]>,
+    chain_id: ChainId,
+    storage: Storage,
+    mut events: broadcast::Receiver<ChainEvent>,
+) -> anyhow::Result<()> {
+    let recent_blocks =
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake(channel, secret, chain_id))
+            .await
+            .context("Standby handshake timed out")??;
+
+    let standby_head = recent_blocks.first().map(|&(number, _)| number);
+    let mut next = {
+        let storage = storage.clone();
+        tokio::task::spawn_blocking(move || common_ancestor(&storage, &recent_blocks))
+            .await
+            .context("Joining blocking task")??
+    };
+    if standby_head.is_some_and(|head| head >= next) {
+        channel.send(&Message::Reorg(next)).await?;
+    }
+
+    loop {
+        // Send everything committed so far. Reorgs committed meanwhile are
+        // picked up from the chain events afterwards.
+        loop {
+            let storage = storage.clone();
+            let block = tokio::task::spawn_blocking(move || read_block(&storage, next))
+                .await
+                .context("Joining blocking task")??;
+            let Some(block) = block else {
+                break;
+            };
+            channel.send(&Message::Block(Box::new(block))).await?;
+            next += 1;
+        }
+
+        let event = match tokio::time::timeout(HEARTBEAT_INTERVAL, events.recv()).await {
+            Ok(event) => event,
+            Err(_) => {
+                channel.send(&Message::Heartbeat).await?;
+                continue;
+            }
+        };
+        match event {
+            Ok(ChainEvent::Reorg(reorg)) if reorg.first_block_number < next => {
+                next = reorg.first_block_number;
+                channel.send(&Message::Reorg(next)).await?;
+            }
+            Ok(_) => {}
+            // The standby reconnects and is brought back onto the right fork
+            // when subscribing.
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                anyhow::bail!("Missed chain events")
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Returns the block following the newest of `recent_blocks` which is part of
+/// the local chain, or genesis if the standby has no blocks yet.
+///
+/// Fails if the state of that block has been pruned, since its state update
+/// can then no longer be replicated.
+fn common_ancestor(
+    storage: &Storage,
+    recent_blocks: &[(BlockNumber, BlockHash)],
+) -> anyhow::Result<BlockNumber> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let mut ancestor = None;
+    for &(number, hash) in recent_blocks {
+        if db.block_hash(number.into())? == Some(hash) {
+            ancestor = Some(number);
+            break;
+        }
+    }
+
+    let next = match (ancestor, recent_blocks.last()) {
+        (Some(ancestor), _) => ancestor + 1,
+        (None, None | Some((BlockNumber::GENESIS, _))) => BlockNumber::GENESIS,
+        (None, Some(_)) => {
+            anyhow::bail!("The standby forked off more than {RECENT_BLOCKS} blocks ago")
+        }
+    };
+
+    if let Some(earliest_available_block) = db.earliest_available_state_block(next.into())? {
+        anyhow::bail!(
+            "The standby needs block {next}, but the state of blocks before \
+             {earliest_available_block} has been pruned"
+        );
+    }
+
+    Ok(next)
+}
+
+/// Reads a committed block, or returns [None] if there is none with this
+/// number yet.
+fn read_block(storage: &Storage, number: BlockNumber) -> anyhow::Result<Option<ReplicatedBlock>> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let Some(header) = db.block_header(number.into())? else {
+        return Ok(None);
+    };
+    // The block's state update would be incomplete.
+    anyhow::ensure!(
+        !db.is_state_pruned(number.into())?,
+        "The state of block {number} has been pruned"
+    );
+    let (transactions, transaction_receipts): (Vec<_>, Vec<_>) = db
+        .transaction_data_for_block(number.into())?
+        .context("Transactions missing")?
+        .into_iter()
+        .map(|(transaction, receipt, events)| (transaction, (receipt, events)))
+        .unzip();
+    let state_update = db
+        .state_update(number.into())?
+        .context("State update missing")?;
+    let signature = db
+        .signature(number.into())?
+        .context("Block signature missing")?;
+    let status = if db.block_is_l1_accepted(number.into())? {
+        Status::AcceptedOnL1
+    } else {
+        Status::AcceptedOnL2
+    };
+
+    let mut classes = Vec::new();
+    for hash in db.declared_classes_at(number.into())?.unwrap_or_default() {
+        let definition = db
+            .class_definition(hash)?
+            .with_context(|| format!("Class {hash} missing"))?;
+        let class = match db.casm_hash(hash)? {
+            Some(casm_hash) => ReplicatedClass::Sierra {
+                hash: SierraHash(hash.0),
+                definition: base64::encode(definition),
+                casm_hash,
+                casm_definition: base64::encode(
+                    db.casm_definition(hash)?
+                        .with_context(|| format!("Compiled class {hash} missing"))?,
+                ),
+            },
+            None => ReplicatedClass::Cairo {
+                hash,
+                definition: base64::encode(definition),
+            },
+        };
+        classes.push(class);
+    }
+
+    let block = Block {
+        block_hash: header.hash,
+        block_number: header.number,
+        l1_gas_price: GasPrices {
+            price_in_wei: header.eth_l1_gas_price,
+            price_in_fri: header.strk_l1_gas_price,
+        },
+        l1_data_gas_price: GasPrices {
+            price_in_wei: header.eth_l1_data_gas_price,
+            price_in_fri: header.strk_l1_data_gas_price,
+        },
+        parent_block_hash: header.parent_hash,
+        sequencer_address: Some(header.sequencer_address),
+        state_commitment: header.state_commitment,
+        status,
+        timestamp: header.timestamp,
+        transaction_receipts,
+        transactions,
+        starknet_version: header.starknet_version,
+        transaction_commitment: header.transaction_commitment,
+        event_commitment: header.event_commitment,
+        l1_da_mode: header.l1_da_mode.into(),
+        receipt_commitment: Some(header.receipt_commitment),
+        state_diff_commitment: Some(header.state_diff_commitment),
+        state_diff_length: Some(header.state_diff_length),
+    };
+
+    Ok(Some(ReplicatedBlock {
+        block,
+        state_update: gateway_state_update(state_update),
+        signature: [signature.r, signature.s],
+        classes,
+    }))
+}
+
+/// The inverse of the conversion from [reply::StateUpdate] to [StateUpdate].
+fn gateway_state_update(state_update: StateUpdate) -> reply::StateUpdate {
+    use reply::state_update::{
+        DeclaredSierraClass,
+        DeployedContract,
+        ReplacedClass,
+        StateDiff,
+        StorageDiff,
+    };
+
+    let storage_diffs = |storage: std::collections::HashMap<_, _>| {
+        storage
+            .into_iter()
+            .map(|(key, value)| StorageDiff { key, value })
+            .collect::<Vec<_>>()
+    };
+
+    let mut state_diff = StateDiff {
+        old_declared_contracts: state_update.declared_cairo_classes,
+        declared_classes: state_update
+            .declared_sierra_classes
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| DeclaredSierraClass {
+                class_hash,
+                compiled_class_hash,
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    for (address, update) in state_update.system_contract_updates {
+        state_diff
+            .storage_diffs
+            .insert(address, storage_diffs(update.storage));
+    }
+
+    for (address, update) in state_update.contract_updates {
+        if !update.storage.is_empty() {
+            state_diff
+                .storage_diffs
+                .insert(address, storage_diffs(update.storage));
+        }
+        match update.class {
+            Some(ContractClassUpdate::Deploy(class_hash)) => {
+                state_diff.deployed_contracts.push(DeployedContract {
+                    address,
+                    class_hash,
+                })
+            }
+            Some(ContractClassUpdate::Replace(class_hash)) => {
+                state_diff.replaced_classes.push(ReplacedClass {
+                    address,
+                    class_hash,
+                })
+            }
+            None => {}
+        }
+        if let Some(nonce) = update.nonce {
+            state_diff.nonces.insert(address, nonce);
+        }
+    }
+
+    reply::StateUpdate {
+        block_hash: state_update.block_hash,
+        new_root: state_update.state_commitment,
+        old_root: state_update.parent_state_commitment,
+        state_diff,
+    }
+}
+
+/// Follows a primary in place of the L2 sync.
+#[derive(Clone)]
+pub struct Standby {
+    primary: String,
+    secret: Arc<[u8]>,
+    chain_id: ChainId,
+    storage: Storage,
+}
+
+impl Standby {
+    /// Follows the primary at `primary`, a `host:port` address.
+    pub fn new(primary: String, secret: String, chain_id: ChainId, storage: Storage) -> Self {
+        Self {
+            primary,
+            secret: secret.into_bytes().into(),
+            chain_id,
+            storage,
+        }
+    }
+
+    /// Replicates blocks from the primary, reconnecting whenever the
+    /// connection is lost.
+    ///
+    /// Takes the place of [l2::sync](crate::state::l2::sync) as the producer of
+    /// committed blocks.
+    pub async fn follow(
+        self,
+        events: mpsc::Sender<SyncEvent>,
+        head: Option<(BlockNumber, BlockHash, StateCommitment)>,
+    ) -> anyhow::Result<()> {
+        tracing::info!(primary=%self.primary, head=?head.map(|head| head.0), "Following primary");
+        loop {
+            match self.replicate(&events).await {
+                Ok(()) => return Ok(()),
+                Err(error) => {
+                    tracing::warn!(primary=%self.primary, reason=%error, "Replication interrupted");
+                }
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    /// Replicates blocks until the connection is lost. Returns [Ok] once the
+    /// sync consumer has stopped.
+    async fn replicate(&self, events: &mpsc::Sender<SyncEvent>) -> anyhow::Result<()> {
+        let stream = TcpStream::connect(&self.primary)
+            .await
+            .context("Connecting to primary")?;
+        let mut channel = Channel::new(stream, Role::Standby);
+
+        let Message::Hello {
+            version,
+            chain_id,
+            nonce,
+        } = tokio::time::timeout(HANDSHAKE_TIMEOUT, channel.recv())
+            .await
+            .context("Primary did not greet")??
+        else {
+            anyhow::bail!("Expected a greeting");
+        };
+        anyhow::ensure!(
+            version == PROTOCOL_VERSION,
+            "Primary uses replication protocol version {version}, expected {PROTOCOL_VERSION}"
+        );
+        anyhow::ensure!(
+            chain_id == self.chain_id,
+            "Primary is on chain {}, expected {}",
+            chain_id.to_hex_str(),
+            self.chain_id.to_hex_str()
+        );
+        let standby_nonce: [u8; NONCE_SIZE] = rand::thread_rng().gen();
+        channel.send_raw(standby_nonce.to_vec()).await?;
+        channel.authenticate(self.secret.clone(), nonce, standby_nonce);
+        channel.max_message_size = MAX_MESSAGE_SIZE;
+
+        let storage = self.storage.clone();
+        let recent_blocks = tokio::task::spawn_blocking(move || recent_blocks(&storage))
+            .await
+            .context("Joining blocking task")??;
+        channel.send(&Message::Subscribe { recent_blocks }).await?;
+
+        loop {
+            let started = Instant::now();
+            let message = tokio::time::timeout(IDLE_TIMEOUT, channel.recv())
+                .await
+                .context("Primary stopped responding")??;
+
+            let sync_events = match message {
+                Message::Block(block) => block.into_events(started.elapsed())?,
+                Message::Reorg(first) => vec![SyncEvent::Reorg(first)],
+                Message::Heartbeat => continue,
+                Message::Error(error) => anyhow::bail!("Primary failed: {error}"),
+                Message::Hello { .. } | Message::Subscribe { .. } => {
+                    anyhow::bail!("Unexpected message")
+                }
+            };
+            for event in sync_events {
+                if events.send(event).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Returns the latest [RECENT_BLOCKS] blocks, newest first.
+fn recent_blocks(storage: &Storage) -> anyhow::Result<Vec<(BlockNumber, BlockHash)>> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let mut blocks = Vec::new();
+    let mut next = db.block_id(pathfinder_storage::BlockId::Latest)?;
+    while let Some((number, hash)) = next {
+        blocks.push((number, hash));
+        if blocks.len() as u64 == RECENT_BLOCKS || number == BlockNumber::GENESIS {
+            break;
+        }
+        next = db.block_id((number - 1).into())?;
+    }
+    Ok(blocks)
+}
+
+impl ReplicatedBlock {
+    /// Converts the block into the events the L2 sync would have emitted for
+    /// it: its new classes followed by the block itself.
+    fn into_events(self, download_time: Duration) -> anyhow::Result<Vec<SyncEvent>> {
+        let Self {
+            block,
+            state_update,
+            signature,
+            classes,
+        } = self;
+
+        let mut events = classes
+            .into_iter()
+            .map(|class| {
+                let event = match class {
+                    ReplicatedClass::Cairo { hash, definition } => SyncEvent::CairoClass {
+                        definition: base64::decode(definition)?,
+                        hash,
+                    },
+                    ReplicatedClass::Sierra {
+                        hash,
+                        definition,
+                        casm_hash,
+                        casm_definition,
+                    } => SyncEvent::SierraClass {
+                        sierra_definition: base64::decode(definition)?,
+                        sierra_hash: hash,
+                        casm_definition: base64::decode(casm_definition)?,
+                        casm_hash,
+                    },
+                };
+                Ok(event)
+            })
+            .collect::<Result<Vec<_>, base64::DecodeError>>()
+            .context("Decoding class definitions")?;
+
+        let commitments = (
+            block.transaction_commitment,
+            block.event_commitment,
+            block.receipt_commitment.unwrap_or_default(),
+        );
+        let state_diff_commitment = block.state_diff_commitment.unwrap_or_default();
+        let signature = BlockCommitmentSignature {
+            r: signature[0],
+            s: signature[1],
+        };
+        events.push(SyncEvent::Block(
+            (Box::new(block), commitments),
+            Box::new(state_update.into()),
+            Box::new(signature),
+            Box::new(state_diff_commitment),
+            Timings {
+                block_download: download_time,
+                ..Default::default()
+            },
+        ));
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::ContractAddress;
+
+    use super::*;
+
+    #[test]
+    fn state_update_roundtrip() {
+        let state_update = StateUpdate::default()
+            .with_block_hash(block_hash!("0x1"))
+            .with_parent_state_commitment(state_commitment!("0x2"))
+            .with_state_commitment(state_commitment!("0x3"))
+            .with_storage_update(
+                contract_address!("0x10"),
+                storage_address!("0x11"),
+                storage_value!("0x12"),
+            )
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x13"),
+                storage_value!("0x14"),
+            )
+            .with_deployed_contract(contract_address!("0x20"), class_hash!("0x21"))
+            .with_replaced_class(contract_address!("0x10"), class_hash!("0x22"))
+            .with_contract_nonce(contract_address!("0x10"), contract_nonce!("0x1"))
+            .with_declared_cairo_class(class_hash!("0x30"))
+            .with_declared_sierra_class(sierra_hash!("0x31"), casm_hash!("0x32"));
+
+        let roundtrip: StateUpdate = gateway_state_update(state_update.clone()).into();
+        pretty_assertions_sorted::assert_eq!(roundtrip, state_update);
+    }
+
+    async fn connected_channels() -> (Channel, Channel) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (standby, primary) = tokio::join!(TcpStream::connect(address), listener.accept());
+        (
+            Channel::new(standby.unwrap(), Role::Standby),
+            Channel::new(primary.unwrap().0, Role::Primary),
+        )
+    }
+
+    #[tokio::test]
+    async fn tampered_messages_are_rejected() {
+        let (mut standby, mut primary) = connected_channels().await;
+
+        let secret: Arc<[u8]> = b"secret".to_vec().into();
+        standby.authenticate(secret.clone(), [1; 32], [2; 32]);
+        primary.authenticate(secret, [1; 32], [2; 32]);
+
+        standby
+            .send(&Message::Reorg(BlockNumber::GENESIS))
+            .await
+            .unwrap();
+        assert!(matches!(
+            primary.recv().await.unwrap(),
+            Message::Reorg(BlockNumber::GENESIS)
+        ));
+
+        // Messages are bound to their direction and sequence number.
+        primary.send(&Message::Heartbeat).await.unwrap();
+        standby.authenticator.as_mut().unwrap().received += 1;
+        standby.recv().await.unwrap_err();
+
+        // And to the secret.
+        let mut other = Channel::new(primary.stream, Role::Primary);
+        other.authenticate(b"other".to_vec().into(), [1; 32], [2; 32]);
+        other.send(&Message::Heartbeat).await.unwrap();
+        standby.authenticator.as_mut().unwrap().received = 0;
+        standby.recv().await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn handshake_rejects_oversized_nonces() {
+        let (mut standby, mut primary) = connected_channels().await;
+        let secret: Arc<[u8]> = b"secret".to_vec().into();
+
+        let (result, _) = tokio::join!(
+            handshake(&mut primary, secret, ChainId::SEPOLIA_TESTNET),
+            async {
+                standby.recv().await.unwrap();
+                // The primary must not wait for, or allocate, the announced
+                // payload.
+                standby
+                    .stream
+                    .write_u32(MAX_MESSAGE_SIZE as u32)
+                    .await
+                    .unwrap();
+            }
+        );
+        result.unwrap_err();
+        assert!(!primary.is_authenticated());
+    }
+
+    #[test]
+    fn pruned_blocks_are_not_replicated() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let blocks = pathfinder_storage::fake::with_n_blocks(&storage, 4);
+        {
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.prune_state_updates(BlockNumber::GENESIS + 2, 10)
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        let recent_block = |number: usize| {
+            let header = &blocks[number].header.header;
+            (header.number, header.hash)
+        };
+
+        // An empty standby would need the pruned blocks from genesis onwards.
+        common_ancestor(&storage, &[]).unwrap_err();
+        common_ancestor(&storage, &[recent_block(0)]).unwrap_err();
+        assert_eq!(
+            common_ancestor(&storage, &[recent_block(1)]).unwrap(),
+            BlockNumber::GENESIS + 2
+        );
+
+        read_block(&storage, BlockNumber::GENESIS + 1).unwrap_err();
+        assert!(read_block(&storage, BlockNumber::GENESIS + 2)
+            .unwrap()
+            .is_some());
+    }
+}
@@ -68,16 +68,23 @@ fn revert_contract_updates(
         StorageCommitmentTree::load(transaction, head).context("Loading global storage tree")?;
 
     for (contract_address, contract_update) in updates {
-        let state_hash = pathfinder_merkle_tree::contract_state::revert_contract_state(
-            transaction,
-            contract_address,
-            head,
-            target_block,
-            contract_update,
-        )?;
+        let (state_hash, class_hash, nonce) =
+            pathfinder_merkle_tree::contract_state::revert_contract_state(
+                transaction,
+                contract_address,
+                head,
+                target_block,
+                contract_update,
+            )?;
 
         transaction
-            .insert_contract_state_hash(target_block, contract_address, state_hash)
+            .insert_contract_state_hash(
+                target_block,
+                contract_address,
+                state_hash,
+                class_hash,
+                nonce,
+            )
             .context("Inserting reverted contract state hash")?;
 
         global_tree
@@ -0,0 +1,70 @@
+//! Periodically deletes the state updates which are no longer needed to serve
+//! the state of the retained blocks, see
+//! [pathfinder_storage::Transaction::prune_state_updates].
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_storage::{BlockId, Storage, TransactionBehavior};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Blocks pruned per database transaction, so that catching up does not block
+/// syncing for long.
+const BATCH_SIZE: u64 = 100;
+
+/// Keeps the state of the latest `keep_blocks + 1` blocks.
+pub async fn run(storage: Storage, keep_blocks: u64) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let storage = storage.clone();
+        let result = tokio::task::spawn_blocking(move || prune(&storage, keep_blocks))
+            .await
+            .context("Joining state pruning task")?;
+
+        match result {
+            Ok(Some(block)) => tracing::debug!(%block, "Pruned state updates"),
+            Ok(None) => {}
+            Err(error) => tracing::warn!(%error, "Failed to prune state updates"),
+        }
+    }
+}
+
+/// Prunes the state updates up to `keep_blocks` blocks before the latest
+/// block. Returns the last block pruned.
+fn prune(storage: &Storage, keep_blocks: u64) -> anyhow::Result<Option<BlockNumber>> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+
+    loop {
+        // Writing from the start prevents pruning blocks which are being purged
+        // by a reorg.
+        let tx = db
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .context("Creating database transaction")?;
+        let previous = tx.state_pruned_to()?;
+        let Some(latest) = tx
+            .block_number(BlockId::Latest)
+            .context("Querying latest block number")?
+        else {
+            return Ok(previous);
+        };
+        let Some(until) = latest.get().checked_sub(keep_blocks) else {
+            return Ok(previous);
+        };
+
+        let pruned_to = tx
+            .prune_state_updates(BlockNumber::new_or_panic(until), BATCH_SIZE)
+            .context("Pruning state updates")?;
+        tx.commit().context("Committing state pruning")?;
+
+        if pruned_to == previous {
+            return Ok(pruned_to);
+        }
+    }
+}
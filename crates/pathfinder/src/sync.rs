@@ -37,6 +37,9 @@ mod transactions;
 
 const CHECKPOINT_MARGIN: u64 = 10;
 
+/// How often the L1 state is polled in header-only mode.
+const L1_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct Sync {
     pub storage: pathfinder_storage::Storage,
     pub p2p: P2PClient,
@@ -45,9 +48,13 @@ pub struct Sync {
     pub fgw_client: GatewayClient,
     pub chain: Chain,
     pub chain_id: ChainId,
-    pub public_key: PublicKey,
+    /// Public keys whose block header signatures are accepted.
+    pub public_keys: Vec<PublicKey>,
     pub l1_checkpoint_override: Option<EthereumStateUpdate>,
     pub verify_tree_hashes: bool,
+    /// Sync and verify only block headers and their L1 confirmations, without
+    /// block bodies, classes or state tries.
+    pub header_only: bool,
 }
 
 impl Sync {
@@ -90,8 +97,9 @@ impl Sync {
                 fgw_client: self.fgw_client.clone(),
                 chain: self.chain,
                 chain_id: self.chain_id,
-                public_key: self.public_key,
+                public_keys: self.public_keys.clone(),
                 verify_tree_hashes: self.verify_tree_hashes,
+                header_only: self.header_only,
             }
             .run(checkpoint)
             .await;
@@ -119,16 +127,28 @@ impl Sync {
     /// Run the track sync until it completes successfully, requires the
     /// number and parent hash of the first block to sync
     async fn track_sync(&self, next: BlockNumber, parent_hash: BlockHash) -> anyhow::Result<()> {
-        let result = track::Sync {
+        let sync = track::Sync {
             latest: LatestStream::spawn(self.fgw_client.clone(), Duration::from_secs(2)),
             p2p: self.p2p.clone(),
             storage: self.storage.clone(),
             chain: self.chain,
             chain_id: self.chain_id,
-            public_key: self.public_key,
-        }
-        .run(next, parent_hash, self.fgw_client.clone())
-        .await;
+            public_keys: self.public_keys.clone(),
+        };
+
+        let result = if self.header_only {
+            let l1 = tokio::spawn(track_l1_confirmations(
+                self.storage.clone(),
+                self.eth_client.clone(),
+                self.eth_address,
+                L1_POLL_INTERVAL,
+            ));
+            let result = sync.run_header_only(next, parent_hash).await;
+            l1.abort();
+            result
+        } else {
+            sync.run(next, parent_hash, self.fgw_client.clone()).await
+        };
 
         tracing::info!("Track sync completed: {result:#?}");
 
@@ -136,6 +156,68 @@ impl Sync {
     }
 }
 
+/// Keeps the L1 confirmation of headers up to date in header-only mode, which
+/// otherwise is only updated by checkpoint sync.
+///
+/// The latest L1 state is only accepted once the corresponding header has
+/// been synced and its hash matches, so that the L1-L2 pointer always refers
+/// to a verified header.
+async fn track_l1_confirmations(
+    storage: pathfinder_storage::Storage,
+    eth_client: pathfinder_ethereum::EthereumClient,
+    eth_address: H160,
+    poll_interval: Duration,
+) {
+    use pathfinder_ethereum::EthereumApi;
+
+    let mut interval = tokio::time::interval(poll_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let Ok(update) = eth_client
+            .get_starknet_state(&eth_address)
+            .await
+            .inspect_err(|e| tracing::debug!(error=%e, "Error fetching latest L1 state"))
+        else {
+            continue;
+        };
+
+        let storage = storage.clone();
+        let result = tokio::task::spawn_blocking(move || -> anyhow::Result<bool> {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+
+            let local_hash = db
+                .block_hash(update.block_number.into())
+                .context("Querying block hash")?;
+            if local_hash != Some(update.block_hash) {
+                return Ok(false);
+            }
+
+            db.upsert_l1_state(&update).context("Inserting L1 state")?;
+            db.update_l1_l2_pointer(Some(update.block_number))
+                .context("Updating L1-L2 pointer")?;
+            db.commit().context("Committing database transaction")?;
+            Ok(true)
+        })
+        .await
+        .context("Joining blocking task")
+        .and_then(|x| x);
+
+        match result {
+            Ok(true) => tracing::debug!(block=%update.block_number, "L1 confirmed header"),
+            Ok(false) => {
+                tracing::trace!(block=%update.block_number, "L1 state is ahead of or diverges from synced headers")
+            }
+            Err(e) => tracing::warn!(error=%e, "Error persisting L1 confirmation"),
+        }
+    }
+}
+
 struct LatestStream {
     rx: Receiver<(BlockNumber, BlockHash)>,
     stream: WatchStream<(BlockNumber, BlockHash)>,
@@ -61,8 +61,10 @@ pub struct Sync {
     pub fgw_client: Client,
     pub chain: Chain,
     pub chain_id: ChainId,
-    pub public_key: PublicKey,
+    pub public_keys: Vec<PublicKey>,
     pub verify_tree_hashes: bool,
+    /// Only sync headers, see [crate::sync::Sync::header_only].
+    pub header_only: bool,
 }
 
 impl Sync {
@@ -74,9 +76,10 @@ impl Sync {
         fgw_client: Client,
         chain: Chain,
         chain_id: ChainId,
-        public_key: PublicKey,
+        public_keys: Vec<PublicKey>,
         l1_anchor_override: Option<EthereumStateUpdate>,
         verify_tree_hashes: bool,
+        header_only: bool,
     ) -> Self {
         Self {
             storage,
@@ -86,8 +89,9 @@ impl Sync {
             fgw_client,
             chain,
             chain_id,
-            public_key,
+            public_keys,
             verify_tree_hashes,
+            header_only,
         }
     }
 
@@ -122,6 +126,10 @@ impl Sync {
         // genesis.
         self.sync_headers(anchor).await?;
 
+        if self.header_only {
+            return Ok(());
+        }
+
         // Sync the rest of the data in chronological order.
         self.sync_transactions(head, self.chain_id).await?;
         self.sync_state_updates(head, self.verify_tree_hashes)
@@ -153,7 +161,7 @@ impl Sync {
                 gap.head(),
                 self.chain,
                 self.chain_id,
-                self.public_key,
+                self.public_keys.clone(),
                 self.storage.clone(),
             )
             .await?;
@@ -284,14 +292,14 @@ async fn handle_header_stream(
     head: (BlockNumber, BlockHash),
     chain: Chain,
     chain_id: ChainId,
-    public_key: PublicKey,
+    public_keys: Vec<PublicKey>,
     storage: Storage,
 ) -> Result<(), SyncError> {
     InfallibleSource::from_stream(stream)
         .spawn()
         .pipe(headers::BackwardContinuity::new(head.0, head.1), 10)
         .pipe(
-            headers::VerifyHashAndSignature::new(chain, chain_id, public_key),
+            headers::VerifyHashAndSignature::new(chain, chain_id, public_keys),
             10,
         )
         .try_chunks(1024, 10)
@@ -792,7 +800,7 @@ mod tests {
                 head,
                 Chain::SepoliaTestnet,
                 ChainId::SEPOLIA_TESTNET,
-                public_key,
+                vec![public_key],
                 storage.clone(),
             )
             .await
@@ -836,7 +844,7 @@ mod tests {
                     head,
                     Chain::SepoliaTestnet,
                     ChainId::SEPOLIA_TESTNET,
-                    public_key,
+                    vec![public_key],
                     storage.clone(),
                 )
                 .await,
@@ -861,7 +869,7 @@ mod tests {
                     // Causes mismatches for all block hashes because setup assumes Sepolia
                     Chain::Mainnet,
                     ChainId::MAINNET,
-                    public_key,
+                    vec![public_key],
                     storage.clone(),
                 )
                 .await,
@@ -885,7 +893,7 @@ mod tests {
         //             head,
         //             Chain::SepoliaTestnet,
         //             ChainId::SEPOLIA_TESTNET,
-        //             PublicKey::ZERO, // Invalid public key
+        //             vec![PublicKey::ZERO], // Invalid public key
         //             storage.clone(),
         //         )
         //         .await,
@@ -918,7 +926,7 @@ mod tests {
                     head,
                     Chain::SepoliaTestnet,
                     ChainId::SEPOLIA_TESTNET,
-                    public_key,
+                    vec![public_key],
                     storage.clone(),
                 )
                 .await,
@@ -12,6 +12,7 @@ use pathfinder_common::{
     ClassCommitment,
     PublicKey,
     SignedBlockHeader,
+    StarknetVersion,
     StorageCommitment,
 };
 use pathfinder_storage::Storage;
@@ -156,10 +157,25 @@ pub struct BackwardContinuity {
 }
 
 /// Ensures that the block hash and signature are correct.
+///
+/// The signature has to be valid for one of the known public keys. Blocks
+/// older than [VerifyHashAndSignature::FIRST_ENFORCED_VERSION] are let through
+/// without an attestation if verification fails, as their signatures also
+/// commit to a state diff commitment which is not consistent with the feeder
+/// gateway for older blocks.
 pub struct VerifyHashAndSignature {
     chain: Chain,
     chain_id: ChainId,
-    public_key: PublicKey,
+    public_keys: Vec<PublicKey>,
+}
+
+/// A [SignedBlockHeader] which passed [VerifyHashAndSignature].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttestedBlockHeader {
+    pub header: SignedBlockHeader,
+    /// The public key the signature was verified against, if verification
+    /// succeeded.
+    pub attested_by: Option<PublicKey>,
 }
 
 impl ForwardContinuity {
@@ -224,29 +240,38 @@ impl ProcessStage for BackwardContinuity {
 impl ProcessStage for VerifyHashAndSignature {
     const NAME: &'static str = "Headers::Verify";
     type Input = SignedBlockHeader;
-    type Output = SignedBlockHeader;
+    type Output = AttestedBlockHeader;
 
     fn map(&mut self, input: Self::Input) -> Result<Self::Output, SyncError2> {
         if !self.verify_hash(&input.header) {
             return Err(SyncError2::BadBlockHash);
         }
 
-        if !self.verify_signature(&input) {
-            // TODO: make this an error once state diff commitments and signatures are fixed
-            // on the feeder gateway return Err(SyncError2::BadHeaderSignature);
+        let attested_by = self.verify_signature(&input);
+        if attested_by.is_none() {
             tracing::debug!(header=?input.header, "Header signature verification failed");
+            if input.header.starknet_version >= Self::FIRST_ENFORCED_VERSION {
+                return Err(SyncError2::BadHeaderSignature);
+            }
         }
 
-        Ok(input)
+        Ok(AttestedBlockHeader {
+            header: input,
+            attested_by,
+        })
     }
 }
 
 impl VerifyHashAndSignature {
-    pub fn new(chain: Chain, chain_id: ChainId, public_key: PublicKey) -> Self {
+    /// From this version on blocks are signed over the block hash alone, so
+    /// signature verification failures are rejected.
+    pub const FIRST_ENFORCED_VERSION: StarknetVersion = StarknetVersion::new(0, 13, 2, 0);
+
+    pub fn new(chain: Chain, chain_id: ChainId, public_keys: Vec<PublicKey>) -> Self {
         Self {
             chain,
             chain_id,
-            public_key,
+            public_keys,
         }
     }
 
@@ -293,15 +318,18 @@ impl VerifyHashAndSignature {
         }
     }
 
-    fn verify_signature(&self, header: &SignedBlockHeader) -> bool {
-        header
-            .signature
-            .verify(
-                self.public_key,
-                header.header.hash,
-                header.header.state_diff_commitment,
-            )
-            .is_ok()
+    /// Returns the first known public key the signature is valid for.
+    fn verify_signature(&self, header: &SignedBlockHeader) -> Option<PublicKey> {
+        self.public_keys.iter().copied().find(|&public_key| {
+            header
+                .signature
+                .verify(
+                    public_key,
+                    header.header.hash,
+                    header.header.state_diff_commitment,
+                )
+                .is_ok()
+        })
     }
 }
 
@@ -311,7 +339,7 @@ pub struct Persist {
 
 impl ProcessStage for Persist {
     const NAME: &'static str = "Headers::Persist";
-    type Input = Vec<SignedBlockHeader>;
+    type Input = Vec<AttestedBlockHeader>;
     type Output = ();
 
     fn map(&mut self, input: Self::Input) -> Result<Self::Output, SyncError2> {
@@ -320,11 +348,19 @@ impl ProcessStage for Persist {
             .transaction()
             .context("Creating database transaction")?;
 
-        for SignedBlockHeader { header, signature } in input {
+        for AttestedBlockHeader {
+            header: SignedBlockHeader { header, signature },
+            attested_by,
+        } in input
+        {
             tx.insert_block_header(&header)
                 .context("Persisting block header")?;
             tx.insert_signature(header.number, &signature)
                 .context("Persisting block signature")?;
+            if let Some(public_key) = attested_by {
+                tx.insert_signature_attestation(header.number, public_key)
+                    .context("Persisting block signature attestation")?;
+            }
         }
 
         tx.commit().context("Committing database transaction")?;
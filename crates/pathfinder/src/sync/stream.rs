@@ -100,6 +100,16 @@ impl<T: Send + 'static> SyncReceiver<T> {
                         // Log trace and metrics.
                         let elements_per_sec = 1.0 / t.elapsed().as_secs_f32();
                         let queue_fullness = queue_capacity - self.inner.capacity();
+                        metrics::histogram!(
+                            "sync_stage_duration_seconds",
+                            t.elapsed(),
+                            "stage" => S::NAME
+                        );
+                        metrics::gauge!(
+                            "sync_stage_queue_depth",
+                            queue_fullness as f64,
+                            "stage" => S::NAME
+                        );
                         let input_queue = Fullness(queue_fullness, queue_capacity);
                         tracing::debug!(
                             "Stage: {}, queue: {}, {elements_per_sec:.0} items/s",
@@ -160,6 +170,16 @@ impl<T: Send + 'static> SyncReceiver<T> {
                         // Log trace and metrics.
                         let elements_per_sec = count as f32 / t.elapsed().as_secs_f32();
                         let queue_fullness = queue_capacity - self.inner.capacity();
+                        metrics::histogram!(
+                            "sync_stage_duration_seconds",
+                            t.elapsed(),
+                            "stage" => S::NAME
+                        );
+                        metrics::gauge!(
+                            "sync_stage_queue_depth",
+                            queue_fullness as f64,
+                            "stage" => S::NAME
+                        );
                         let input_queue = Fullness(queue_fullness, queue_capacity);
                         tracing::trace!(stage=%S::NAME, %input_queue, %elements_per_sec,
                             "Stage metrics"
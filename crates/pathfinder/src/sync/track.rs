@@ -47,6 +47,7 @@ use super::class_definitions::CompiledClass;
 use super::{state_updates, transactions};
 use crate::sync::class_definitions::{self, ClassWithLayout};
 use crate::sync::error::SyncError2;
+use crate::sync::headers::AttestedBlockHeader;
 use crate::sync::stream::{ProcessStage, SyncReceiver, SyncResult};
 use crate::sync::{events, headers};
 
@@ -56,7 +57,7 @@ pub struct Sync<L, P> {
     pub storage: Storage,
     pub chain: Chain,
     pub chain_id: ChainId,
-    pub public_key: PublicKey,
+    pub public_keys: Vec<PublicKey>,
 }
 
 impl<L, P> Sync<L, P> {
@@ -88,7 +89,11 @@ impl<L, P> Sync<L, P> {
         .spawn()
         .pipe(headers::ForwardContinuity::new(next, parent_hash), 100)
         .pipe(
-            headers::VerifyHashAndSignature::new(self.chain, self.chain_id, self.public_key),
+            headers::VerifyHashAndSignature::new(
+                self.chain,
+                self.chain_id,
+                self.public_keys.clone(),
+            ),
             100,
         );
 
@@ -168,6 +173,52 @@ impl<L, P> Sync<L, P> {
     }
 }
 
+impl<L, P> Sync<L, P> {
+    /// Like [Sync::run], but only syncs and verifies block headers.
+    pub async fn run_header_only(
+        self,
+        next: BlockNumber,
+        parent_hash: BlockHash,
+    ) -> Result<(), PeerData<SyncError2>>
+    where
+        L: Stream<Item = (BlockNumber, BlockHash)> + Clone + Send + 'static,
+        P: Clone + HeaderStream + Send + 'static,
+    {
+        let storage_connection = self
+            .storage
+            .connection()
+            .context("Creating database connection")
+            // FIXME: PeerData should allow for None peers.
+            .map_err(|e| PeerData {
+                peer: p2p::libp2p::PeerId::random(),
+                data: SyncError2::from(e),
+            })?;
+
+        HeaderSource {
+            p2p: self.p2p,
+            latest_onchain: self.latest,
+            start: next,
+        }
+        .spawn()
+        .pipe(headers::ForwardContinuity::new(next, parent_hash), 100)
+        .pipe(
+            headers::VerifyHashAndSignature::new(self.chain, self.chain_id, self.public_keys),
+            100,
+        )
+        // Persist each header as soon as it arrives since we are following the tip.
+        .try_chunks(1, 10)
+        .pipe(
+            headers::Persist {
+                connection: storage_connection,
+            },
+            10,
+        )
+        .into_stream()
+        .try_fold((), |_, _| std::future::ready(Ok(())))
+        .await
+    }
+}
+
 struct HeaderSource<L, P> {
     p2p: P,
     latest_onchain: L,
@@ -291,14 +342,14 @@ impl TransactionsFanout {
 }
 
 struct HeaderFanout {
-    headers: SyncReceiver<SignedBlockHeader>,
+    headers: SyncReceiver<AttestedBlockHeader>,
     events: BoxStream<'static, BlockHeader>,
     state_diff: BoxStream<'static, SignedBlockHeader>,
     transactions: BoxStream<'static, BlockHeader>,
 }
 
 impl HeaderFanout {
-    fn from_source(mut source: SyncReceiver<SignedBlockHeader>, buffer: usize) -> Self {
+    fn from_source(mut source: SyncReceiver<AttestedBlockHeader>, buffer: usize) -> Self {
         let (h_tx, h_rx) = tokio::sync::mpsc::channel(buffer);
         let (e_tx, e_rx) = tokio::sync::mpsc::channel(buffer);
         let (s_tx, s_rx) = tokio::sync::mpsc::channel(buffer);
@@ -312,7 +363,10 @@ impl HeaderFanout {
                     return;
                 }
 
-                let signed_header = signed_header.expect("Error case already handled").data;
+                let signed_header = signed_header
+                    .expect("Error case already handled")
+                    .data
+                    .header;
                 let header = signed_header.header.clone();
 
                 if e_tx.send(header.clone()).await.is_err() {
@@ -642,7 +696,7 @@ impl<P> ClassSource<P> {
 }
 
 struct BlockStream {
-    pub header: SyncReceiver<SignedBlockHeader>,
+    pub header: SyncReceiver<AttestedBlockHeader>,
     pub events: SyncReceiver<HashMap<TransactionHash, Vec<Event>>>,
     pub state_diff: SyncReceiver<StateUpdateData>,
     pub transactions: SyncReceiver<Vec<(Transaction, Receipt)>>,
@@ -714,7 +768,7 @@ impl BlockStream {
 }
 
 struct BlockData {
-    pub header: SignedBlockHeader,
+    pub header: AttestedBlockHeader,
     pub events: HashMap<TransactionHash, Vec<Event>>,
     pub state_diff: StateUpdateData,
     pub transactions: Vec<(Transaction, Receipt)>,
@@ -738,7 +792,11 @@ impl ProcessStage for StoreBlock {
 
     fn map(&mut self, input: Self::Input) -> Result<Self::Output, SyncError2> {
         let BlockData {
-            header: SignedBlockHeader { header, signature },
+            header:
+                AttestedBlockHeader {
+                    header: SignedBlockHeader { header, signature },
+                    attested_by,
+                },
             mut events,
             state_diff,
             transactions,
@@ -781,6 +839,11 @@ impl ProcessStage for StoreBlock {
         db.insert_signature(block_number, &signature)
             .context("Inserting signature")?;
 
+        if let Some(public_key) = attested_by {
+            db.insert_signature_attestation(block_number, public_key)
+                .context("Inserting signature attestation")?;
+        }
+
         let mut ordered_events = Vec::new();
         transactions.iter().for_each(|(t, _)| {
             // Some transactions can emit no events, in that case we insert an empty vector.
@@ -848,7 +911,15 @@ mod tests {
     use p2p::libp2p::PeerId;
     use p2p::PeerData;
     use p2p_proto::common::Hash;
-    use pathfinder_common::{BlockHeader, ReceiptCommitment, SignedBlockHeader};
+    use pathfinder_common::{
+        BlockCommitmentSignature,
+        BlockCommitmentSignatureElem,
+        BlockHeader,
+        ReceiptCommitment,
+        SignedBlockHeader,
+    };
+    use pathfinder_crypto::signature::{ecdsa_sign, get_pk};
+    use pathfinder_crypto::Felt;
     use pathfinder_storage::fake::init::Config;
     use pathfinder_storage::fake::{self, Block};
     use pathfinder_storage::StorageBuilder;
@@ -878,6 +949,21 @@ mod tests {
             },
         );
 
+        // Sign the blocks so that the signatures can be verified.
+        let private_key = Felt::from_hex_str("0x1234").unwrap();
+        let public_key = PublicKey(get_pk(private_key).unwrap());
+        let blocks = blocks
+            .into_iter()
+            .map(|mut block| {
+                let (r, s) = ecdsa_sign(private_key, block.header.header.hash.0).unwrap();
+                block.header.signature = BlockCommitmentSignature {
+                    r: BlockCommitmentSignatureElem(r),
+                    s: BlockCommitmentSignatureElem(s),
+                };
+                block
+            })
+            .collect::<Vec<_>>();
+
         let BlockHeader { hash, number, .. } = blocks.last().unwrap().header.header;
         let latest = (number, hash);
 
@@ -893,7 +979,7 @@ mod tests {
             storage: storage.clone(),
             chain: Chain::SepoliaTestnet,
             chain_id: ChainId::SEPOLIA_TESTNET,
-            public_key: PublicKey::default(),
+            public_keys: vec![PublicKey::ZERO, public_key],
         };
 
         sync.run(BlockNumber::GENESIS, BlockHash::default(), FakeFgw)
@@ -911,6 +997,7 @@ mod tests {
             let block_id = block_number.into();
             let header = db.block_header(block_id).unwrap().unwrap();
             let signature = db.signature(block_id).unwrap().unwrap();
+            let attestation = db.signature_attestation(block_id).unwrap();
             let transaction_data = db.transaction_data_for_block(block_id).unwrap().unwrap();
             let state_update_data: StateUpdateData =
                 db.state_update(block_id).unwrap().unwrap().into();
@@ -934,6 +1021,10 @@ mod tests {
 
             pretty_assertions_sorted::assert_eq!(header, expected.header.header);
             pretty_assertions_sorted::assert_eq!(signature, expected.header.signature);
+            pretty_assertions_sorted::assert_eq!(
+                attestation,
+                Some((expected.header.signature.clone(), public_key))
+            );
             pretty_assertions_sorted::assert_eq!(
                 header.state_diff_commitment,
                 expected.header.header.state_diff_commitment
@@ -0,0 +1,125 @@
+//! Imports verified source metadata of classes exported by a contract
+//! verification provider.
+//!
+//! The export is a JSON file of the form
+//!
+//! ```json
+//! {
+//!     "provider": "<name>",
+//!     "classes": [
+//!         {
+//!             "class_hash": "0x...",
+//!             "compiler_version": "2.6.3",
+//!             "source_hash": "0x...",
+//!             "repository_url": "https://..."
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! where `repository_url` is optional. Importing the same class again replaces
+//! its metadata.
+use std::path::Path;
+
+use anyhow::Context;
+use pathfinder_common::ClassHash;
+use pathfinder_storage::{Storage, VerifiedSource};
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Export {
+    provider: String,
+    classes: Vec<Class>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Class {
+    class_hash: ClassHash,
+    compiler_version: String,
+    source_hash: String,
+    #[serde(default)]
+    repository_url: Option<String>,
+}
+
+/// Imports the verification provider export at `path` into storage. Returns the
+/// number of classes imported.
+pub fn import(storage: &Storage, path: &Path) -> anyhow::Result<usize> {
+    let file = std::fs::File::open(path).context("Opening verified sources file")?;
+    let export: Export = serde_json::from_reader(std::io::BufReader::new(file))
+        .context("Parsing verified sources file")?;
+
+    let mut connection = storage
+        .connection()
+        .context("Creating database connection")?;
+    let tx = connection
+        .transaction()
+        .context("Creating database transaction")?;
+
+    for class in &export.classes {
+        tx.upsert_verified_source(
+            class.class_hash,
+            &VerifiedSource {
+                compiler_version: class.compiler_version.clone(),
+                source_hash: class.source_hash.clone(),
+                repository_url: class.repository_url.clone(),
+                provider: export.provider.clone(),
+            },
+        )
+        .with_context(|| format!("Importing verified source of {}", class.class_hash))?;
+    }
+
+    tx.commit().context("Committing database transaction")?;
+
+    Ok(export.classes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn import_export() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"{{
+                "provider": "explorer",
+                "classes": [
+                    {{
+                        "class_hash": "0x1",
+                        "compiler_version": "2.6.3",
+                        "source_hash": "0xabc",
+                        "repository_url": "https://example.com/repo"
+                    }},
+                    {{
+                        "class_hash": "0x2",
+                        "compiler_version": "0.13.1",
+                        "source_hash": "0xdef"
+                    }}
+                ]
+            }}"#
+        )
+        .unwrap();
+
+        let imported = import(&storage, file.path()).unwrap();
+        assert_eq!(imported, 2);
+
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        assert_eq!(
+            tx.verified_source(class_hash!("0x2")).unwrap(),
+            Some(VerifiedSource {
+                compiler_version: "0.13.1".to_owned(),
+                source_hash: "0xdef".to_owned(),
+                repository_url: None,
+                provider: "explorer".to_owned(),
+            })
+        );
+    }
+}
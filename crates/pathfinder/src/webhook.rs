@@ -0,0 +1,480 @@
+//! Posts transactions and events matching a watchlist to a webhook.
+//!
+//! New blocks are picked up from the [pathfinder_rpc::Notifications] block
+//! header channel once they have been committed to storage. Every block
+//! containing matches results in a single `POST` request with a JSON body. If a
+//! secret is configured, the body is signed using HMAC-SHA256 and the hex
+//! encoded signature is sent in the [SIGNATURE_HEADER] header.
+//!
+//! Notifications are delivered in order by a separate task, so that a slow
+//! webhook does not hold up watching new blocks. Blocks skipped while the
+//! delivery queue is full are read back from storage.
+use std::collections::HashSet;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::time::Duration;
+
+use anyhow::Context;
+use hmac::{Hmac, Mac};
+use pathfinder_common::event::Event;
+use pathfinder_common::transaction::Transaction;
+use pathfinder_common::{
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    ContractAddress,
+    EventData,
+    EventKey,
+    TransactionHash,
+};
+use pathfinder_retry::Retry;
+use pathfinder_rpc::ChainEvent;
+use pathfinder_storage::Storage;
+use reqwest::Url;
+use sha2::Sha256;
+use tokio::sync::{broadcast, mpsc};
+
+pub mod alerts;
+
+/// Header carrying the `sha256=<hex>` HMAC signature of the request body.
+pub const SIGNATURE_HEADER: &str = "X-Pathfinder-Signature";
+
+/// The number of notifications waiting for delivery before watching new blocks
+/// pauses.
+const DELIVERY_QUEUE_SIZE: usize = 64;
+
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub url: Url,
+    /// Key used to sign request bodies. Requests are not signed if this is
+    /// [None].
+    pub secret: Option<String>,
+    pub watchlist: Watchlist,
+    pub max_retries: NonZeroUsize,
+    pub timeout: Duration,
+}
+
+/// Addresses and event keys to notify about.
+///
+/// A transaction matches if it is sent by (or deploys, or targets in case of L1
+/// handlers) a watched address. An event matches if it is emitted by a watched
+/// address or if its first key, the event selector, is watched.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Watchlist {
+    pub addresses: HashSet<ContractAddress>,
+    pub event_keys: HashSet<EventKey>,
+}
+
+impl Watchlist {
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.event_keys.is_empty()
+    }
+
+    fn matches_transaction(&self, transaction: &Transaction) -> bool {
+        self.addresses
+            .contains(&transaction.variant.contract_address())
+    }
+
+    fn matches_event(&self, event: &Event) -> bool {
+        self.addresses.contains(&event.from_address)
+            || event
+                .keys
+                .first()
+                .is_some_and(|selector| self.event_keys.contains(selector))
+    }
+
+    /// Returns the notification for this block, or [None] if nothing in the
+    /// block matches the watchlist.
+    fn notification(
+        &self,
+        header: &BlockHeader,
+        block: Vec<(Transaction, Vec<Event>)>,
+    ) -> Option<Notification> {
+        let mut transactions = Vec::new();
+        let mut events = Vec::new();
+
+        for (transaction, tx_events) in block {
+            if self.matches_transaction(&transaction) {
+                transactions.push(MatchedTransaction {
+                    transaction_hash: transaction.hash,
+                    address: transaction.variant.contract_address(),
+                });
+            }
+
+            events.extend(
+                tx_events
+                    .into_iter()
+                    .filter(|event| self.matches_event(event))
+                    .map(|event| MatchedEvent {
+                        transaction_hash: transaction.hash,
+                        from_address: event.from_address,
+                        keys: event.keys,
+                        data: event.data,
+                    }),
+            );
+        }
+
+        if transactions.is_empty() && events.is_empty() {
+            return None;
+        }
+
+        Some(Notification {
+            block_number: header.number,
+            block_hash: header.hash,
+            transactions,
+            events,
+        })
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Notification {
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    transactions: Vec<MatchedTransaction>,
+    events: Vec<MatchedEvent>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct MatchedTransaction {
+    transaction_hash: TransactionHash,
+    address: ContractAddress,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct MatchedEvent {
+    transaction_hash: TransactionHash,
+    from_address: ContractAddress,
+    keys: Vec<EventKey>,
+    data: Vec<EventData>,
+}
+
+/// Watches new blocks and posts notifications for watchlist matches until the
+/// chain event channel is closed.
+pub async fn run(
+    config: WebhookConfig,
+    storage: Storage,
+    mut chain_events: broadcast::Receiver<ChainEvent>,
+) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .build()
+        .context("Creating webhook HTTP client")?;
+
+    let (queue, notifications) = mpsc::channel(DELIVERY_QUEUE_SIZE);
+    let delivery = tokio::spawn(deliver(client, config.clone(), notifications));
+
+    // The latest block notifications were gathered for.
+    let mut latest: Option<BlockNumber> = None;
+
+    loop {
+        let header = match chain_events.recv().await {
+            Ok(ChainEvent::Commit(header)) => header,
+            Ok(ChainEvent::Reorg(reorg)) => {
+                // The replacing blocks are gathered once they are committed.
+                latest = reorg.first_block_number.parent();
+                continue;
+            }
+            Ok(ChainEvent::PendingUpdate(_)) => continue,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!(%skipped, "Webhook notifier lagging behind, reading skipped blocks from storage");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        // Picks up the blocks skipped while lagging behind.
+        let first = match latest {
+            Some(latest) if latest < header.number => latest + 1,
+            _ => header.number,
+        };
+        let last = header.number;
+        latest = Some(last);
+
+        let watchlist = config.watchlist.clone();
+        let storage = storage.clone();
+        let gathered =
+            tokio::task::spawn_blocking(move || gather(&storage, &watchlist, first, last))
+                .await
+                .context("Joining database task")?;
+
+        let gathered = match gathered {
+            Ok(gathered) => gathered,
+            Err(error) => {
+                tracing::warn!(%error, "Failed to gather webhook notifications");
+                continue;
+            }
+        };
+
+        for notification in gathered {
+            queue
+                .send(notification)
+                .await
+                .context("Webhook delivery task stopped")?;
+        }
+    }
+
+    tracing::info!("Chain event channel closed, exiting webhook notifier");
+
+    // Delivers the notifications which are still queued.
+    drop(queue);
+    delivery.await.context("Joining webhook delivery task")?;
+
+    Ok(())
+}
+
+/// Returns the notifications for the blocks from `first` to `last`.
+fn gather(
+    storage: &Storage,
+    watchlist: &Watchlist,
+    first: BlockNumber,
+    last: BlockNumber,
+) -> anyhow::Result<Vec<Notification>> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let mut notifications = Vec::new();
+    for number in first.get()..=last.get() {
+        let number = BlockNumber::new_or_panic(number);
+        let header = db
+            .block_header(number.into())
+            .context("Querying block header")?
+            .context("Block is missing from storage")?;
+        let block = db
+            .transaction_data_for_block(number.into())
+            .context("Querying transactions")?
+            .context("Block is missing from storage")?
+            .into_iter()
+            .map(|(transaction, _, events)| (transaction, events))
+            .collect();
+
+        notifications.extend(watchlist.notification(&header, block));
+    }
+
+    Ok(notifications)
+}
+
+/// Posts the queued notifications in order until the queue is closed.
+async fn deliver(
+    client: reqwest::Client,
+    config: WebhookConfig,
+    mut notifications: mpsc::Receiver<Notification>,
+) {
+    while let Some(notification) = notifications.recv().await {
+        let block_number = notification.block_number;
+        match post(
+            &client,
+            &config.url,
+            config.secret.as_deref(),
+            config.max_retries,
+            &notification,
+        )
+        .await
+        {
+            Ok(()) => tracing::debug!(%block_number, "Webhook notification delivered"),
+            Err(error) => {
+                tracing::warn!(%block_number, %error, "Failed to deliver webhook notification")
+            }
+        }
+    }
+}
+
+/// Posts `payload` as JSON to `url`, retrying on server and network errors.
+async fn post(
+    client: &reqwest::Client,
+    url: &Url,
+    secret: Option<&str>,
+    max_retries: NonZeroUsize,
+    payload: &impl serde::Serialize,
+) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(payload).context("Serializing notification")?;
+    let signature = secret.map(|secret| sign(secret.as_bytes(), &body));
+
+    let body = &body;
+    let signature = &signature;
+
+    Retry::exponential(
+        move || async move {
+            let mut request = client
+                .post(url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            request.send().await?.error_for_status().map(|_| ())
+        },
+        NonZeroU64::new(2).unwrap(),
+    )
+    .factor(NonZeroU64::new(1).unwrap())
+    .max_delay(Duration::from_secs(60))
+    .max_num_retries(max_retries)
+    .when(|error: &reqwest::Error| {
+        // Client errors won't go away by retrying.
+        !error
+            .status()
+            .is_some_and(|status| status.is_client_error())
+    })
+    .await
+    .context("Posting notification")
+}
+
+/// Returns the `sha256=<hex>` HMAC signature of `body`.
+fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+    use pathfinder_common::transaction::{InvokeTransactionV1, TransactionVariant};
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+
+    fn invoke(hash: TransactionHash, sender_address: ContractAddress) -> Transaction {
+        Transaction {
+            hash,
+            variant: TransactionVariant::InvokeV1(InvokeTransactionV1 {
+                sender_address,
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn notification_contains_only_matches() {
+        let watched = contract_address!("0x1");
+        let selector = event_key!("0x99");
+        let watchlist = Watchlist {
+            addresses: [watched].into(),
+            event_keys: [selector].into(),
+        };
+
+        let block = vec![
+            (
+                invoke(transaction_hash!("0xa"), watched),
+                vec![Event {
+                    data: vec![],
+                    from_address: contract_address!("0x2"),
+                    keys: vec![event_key!("0x3")],
+                }],
+            ),
+            (
+                invoke(transaction_hash!("0xb"), contract_address!("0x2")),
+                vec![
+                    Event {
+                        data: vec![event_data!("0x5")],
+                        from_address: contract_address!("0x2"),
+                        keys: vec![selector],
+                    },
+                    Event {
+                        data: vec![],
+                        from_address: contract_address!("0x2"),
+                        // Only the first key is the selector.
+                        keys: vec![event_key!("0x3"), selector],
+                    },
+                    Event {
+                        data: vec![],
+                        from_address: watched,
+                        keys: vec![],
+                    },
+                ],
+            ),
+        ];
+
+        let notification = watchlist
+            .notification(&BlockHeader::default(), block)
+            .unwrap();
+
+        assert_eq!(notification.transactions.len(), 1);
+        assert_eq!(
+            notification.transactions[0].transaction_hash,
+            transaction_hash!("0xa")
+        );
+        assert_eq!(notification.events.len(), 2);
+        assert_eq!(notification.events[0].data, vec![event_data!("0x5")]);
+        assert_eq!(notification.events[1].from_address, watched);
+    }
+
+    #[test]
+    fn notification_is_skipped_without_matches() {
+        let watchlist = Watchlist {
+            addresses: [contract_address!("0x1")].into(),
+            event_keys: Default::default(),
+        };
+
+        let block = vec![(
+            invoke(transaction_hash!("0xa"), contract_address!("0x2")),
+            vec![],
+        )];
+
+        assert!(watchlist
+            .notification(&BlockHeader::default(), block)
+            .is_none());
+    }
+
+    #[test]
+    fn skipped_blocks_are_read_from_storage() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let watched = contract_address!("0x1");
+        {
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            for number in 0..3 {
+                let hash = TransactionHash(Felt::from_u64(number));
+                let header = BlockHeader::builder()
+                    .number(BlockNumber::new_or_panic(number))
+                    .finalize_with_hash(BlockHash(Felt::from_u64(number)));
+                tx.insert_block_header(&header).unwrap();
+                tx.insert_transaction_data(
+                    header.number,
+                    &[(
+                        invoke(hash, watched),
+                        Receipt {
+                            transaction_hash: hash,
+                            ..Default::default()
+                        },
+                    )],
+                    Some(&[vec![]]),
+                )
+                .unwrap();
+            }
+            tx.commit().unwrap();
+        }
+        let watchlist = Watchlist {
+            addresses: [watched].into(),
+            event_keys: Default::default(),
+        };
+
+        let notifications = gather(
+            &storage,
+            &watchlist,
+            BlockNumber::GENESIS + 1,
+            BlockNumber::GENESIS + 2,
+        )
+        .unwrap();
+
+        assert_eq!(
+            notifications
+                .iter()
+                .map(|notification| notification.block_number)
+                .collect::<Vec<_>>(),
+            vec![BlockNumber::GENESIS + 1, BlockNumber::GENESIS + 2]
+        );
+    }
+
+    #[test]
+    fn signature() {
+        // Test vector from RFC 4231, test case 2.
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
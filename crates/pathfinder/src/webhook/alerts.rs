@@ -0,0 +1,493 @@
+//! Operational alerts for small deployments.
+//!
+//! Posts a short, templated message to a chat webhook (Slack, Discord or a
+//! plain JSON receiver) when the node needs attention:
+//!
+//! - no new block was synced for a while,
+//! - a reorg deeper than the configured threshold happened,
+//! - the disk holding the database is nearly full,
+//! - gateway requests are failing at an elevated rate.
+//!
+//! Each condition alerts once and is re-armed when it clears, so a stalled node
+//! does not flood the channel.
+use std::fmt;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_rpc::{ChainEvent, Reorg};
+use reqwest::Url;
+use tokio::sync::broadcast;
+
+/// How often the periodic checks (sync stall, disk space, gateway errors) run.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Window over which gateway errors are counted.
+const GATEWAY_ERROR_WINDOW: Duration = Duration::from_secs(60);
+
+pub const DEFAULT_TEMPLATE: &str = "[{network}] pathfinder {alert}: {message}";
+
+#[derive(Clone, Debug)]
+pub struct AlertConfig {
+    pub url: Url,
+    pub format: AlertFormat,
+    /// Message template. `{network}`, `{alert}` and `{message}` are replaced
+    /// with the network name, the alert kind and a human readable description.
+    pub template: String,
+    pub sync_stall_timeout: Duration,
+    pub reorg_depth: NonZeroU64,
+    pub min_free_disk_space_percent: u8,
+    pub gateway_errors_per_minute: NonZeroU64,
+    pub max_retries: NonZeroUsize,
+    pub timeout: Duration,
+}
+
+/// Shape of the request body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertFormat {
+    /// `{"text": "<message>"}`
+    Slack,
+    /// `{"content": "<message>"}`
+    Discord,
+    /// `{"alert": "<kind>", "message": "<message>"}`
+    Json,
+}
+
+impl AlertFormat {
+    fn payload(self, alert: &Alert, text: String) -> serde_json::Value {
+        match self {
+            AlertFormat::Slack => serde_json::json!({ "text": text }),
+            AlertFormat::Discord => serde_json::json!({ "content": text }),
+            AlertFormat::Json => serde_json::json!({ "alert": alert.kind(), "message": text }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Alert {
+    SyncStalled {
+        last_block: Option<BlockNumber>,
+        stalled_for: Duration,
+    },
+    DeepReorg {
+        first_block_number: BlockNumber,
+        last_block_number: BlockNumber,
+        depth: u64,
+    },
+    DiskSpaceLow {
+        available: u64,
+        total: u64,
+    },
+    GatewayErrors {
+        count: u64,
+        window: Duration,
+    },
+}
+
+impl Alert {
+    fn kind(&self) -> &'static str {
+        match self {
+            Alert::SyncStalled { .. } => "sync_stalled",
+            Alert::DeepReorg { .. } => "deep_reorg",
+            Alert::DiskSpaceLow { .. } => "disk_space_low",
+            Alert::GatewayErrors { .. } => "gateway_errors",
+        }
+    }
+
+    fn render(&self, template: &str, network: &str) -> String {
+        template
+            .replace("{network}", network)
+            .replace("{alert}", self.kind())
+            .replace("{message}", &self.to_string())
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alert::SyncStalled {
+                last_block: Some(last_block),
+                stalled_for,
+            } => write!(
+                f,
+                "no new block since block {last_block} for {}s",
+                stalled_for.as_secs()
+            ),
+            Alert::SyncStalled {
+                last_block: None,
+                stalled_for,
+            } => write!(f, "no block synced for {}s", stalled_for.as_secs()),
+            Alert::DeepReorg {
+                first_block_number,
+                last_block_number,
+                depth,
+            } => write!(
+                f,
+                "reorg of {depth} blocks ({first_block_number} to {last_block_number})"
+            ),
+            Alert::DiskSpaceLow { available, total } => write!(
+                f,
+                "{} MiB of {} MiB free on the database disk",
+                available / (1024 * 1024),
+                total / (1024 * 1024)
+            ),
+            Alert::GatewayErrors { count, window } => write!(
+                f,
+                "{count} failed gateway requests in the last {}s",
+                window.as_secs()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct DiskSpace {
+    available: u64,
+    total: u64,
+}
+
+/// Tracks node health and decides when to alert. Kept free of I/O so the
+/// alerting logic can be tested in isolation.
+struct Monitor {
+    sync_stall_timeout: Duration,
+    reorg_depth: u64,
+    min_free_disk_space_percent: u8,
+    gateway_errors_per_minute: u64,
+
+    last_block: Option<BlockNumber>,
+    last_progress: Instant,
+    stalled: bool,
+    disk_space_low: bool,
+    gateway_errors_seen: u64,
+    gateway_window_start: Instant,
+    gateway_errors_high: bool,
+}
+
+impl Monitor {
+    fn new(config: &AlertConfig, now: Instant, gateway_errors: u64) -> Self {
+        Self {
+            sync_stall_timeout: config.sync_stall_timeout,
+            reorg_depth: config.reorg_depth.get(),
+            min_free_disk_space_percent: config.min_free_disk_space_percent,
+            gateway_errors_per_minute: config.gateway_errors_per_minute.get(),
+            last_block: None,
+            last_progress: now,
+            stalled: false,
+            disk_space_low: false,
+            gateway_errors_seen: gateway_errors,
+            gateway_window_start: now,
+            gateway_errors_high: false,
+        }
+    }
+
+    fn on_block(&mut self, number: Option<BlockNumber>, now: Instant) {
+        if number.is_some() {
+            self.last_block = number;
+        }
+        self.last_progress = now;
+        self.stalled = false;
+    }
+
+    fn on_reorg(&self, reorg: &Reorg) -> Option<Alert> {
+        let depth = reorg
+            .last_block_number
+            .get()
+            .saturating_sub(reorg.first_block_number.get())
+            + 1;
+
+        (depth >= self.reorg_depth).then_some(Alert::DeepReorg {
+            first_block_number: reorg.first_block_number,
+            last_block_number: reorg.last_block_number,
+            depth,
+        })
+    }
+
+    fn check(&mut self, now: Instant, disk: Option<DiskSpace>, gateway_errors: u64) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+
+        let stalled_for = now.saturating_duration_since(self.last_progress);
+        if !self.stalled && stalled_for >= self.sync_stall_timeout {
+            self.stalled = true;
+            alerts.push(Alert::SyncStalled {
+                last_block: self.last_block,
+                stalled_for,
+            });
+        }
+
+        if let Some(disk) = disk.filter(|disk| disk.total > 0) {
+            let free_percent = disk.available.saturating_mul(100) / disk.total;
+            let low = free_percent < u64::from(self.min_free_disk_space_percent);
+            if low && !self.disk_space_low {
+                alerts.push(Alert::DiskSpaceLow {
+                    available: disk.available,
+                    total: disk.total,
+                });
+            }
+            self.disk_space_low = low;
+        }
+
+        let window = now.saturating_duration_since(self.gateway_window_start);
+        if window >= GATEWAY_ERROR_WINDOW {
+            let count = gateway_errors.saturating_sub(self.gateway_errors_seen);
+            // Scale the threshold to the actual window, ticks are not exact.
+            let threshold = self.gateway_errors_per_minute * window.as_secs() / 60;
+            let high = count >= threshold.max(1);
+            if high && !self.gateway_errors_high {
+                alerts.push(Alert::GatewayErrors { count, window });
+            }
+            self.gateway_errors_high = high;
+            self.gateway_errors_seen = gateway_errors;
+            self.gateway_window_start = now;
+        }
+
+        alerts
+    }
+}
+
+/// Watches sync progress, reorgs, disk space and gateway errors and posts
+/// alerts until the chain event channel is closed.
+///
+/// Free space is checked on the filesystem containing `database`.
+pub async fn run(
+    config: AlertConfig,
+    network: String,
+    database: PathBuf,
+    mut chain_events: broadcast::Receiver<ChainEvent>,
+) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .build()
+        .context("Creating alert webhook HTTP client")?;
+
+    let mut monitor = Monitor::new(
+        &config,
+        Instant::now(),
+        starknet_gateway_client::failed_requests_total(),
+    );
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        let alerts = tokio::select! {
+            event = chain_events.recv() => match event {
+                Ok(ChainEvent::Commit(header)) => {
+                    monitor.on_block(Some(header.number), Instant::now());
+                    continue;
+                }
+                Ok(ChainEvent::Reorg(reorg)) => monitor.on_reorg(&reorg).into_iter().collect(),
+                Ok(ChainEvent::PendingUpdate(_)) => continue,
+                // Missing a few events still means sync is progressing.
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(%skipped, "Alert notifier lagging behind, skipped chain events");
+                    monitor.on_block(None, Instant::now());
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = interval.tick() => {
+                let disk = match disk_space(&database) {
+                    Ok(disk) => Some(disk),
+                    Err(error) => {
+                        tracing::debug!(%error, "Failed to query free disk space");
+                        None
+                    }
+                };
+                monitor.check(
+                    Instant::now(),
+                    disk,
+                    starknet_gateway_client::failed_requests_total(),
+                )
+            }
+        };
+
+        for alert in alerts {
+            let kind = alert.kind();
+            let text = alert.render(&config.template, &network);
+            tracing::warn!(alert=%kind, "{text}");
+
+            // Delivered in the background, so that retries against a slow or
+            // unreachable webhook do not hold up monitoring.
+            let payload = config.format.payload(&alert, text);
+            let client = client.clone();
+            let url = config.url.clone();
+            let max_retries = config.max_retries;
+            tokio::spawn(async move {
+                if let Err(error) = super::post(&client, &url, None, max_retries, &payload).await {
+                    tracing::warn!(alert=%kind, %error, "Failed to deliver alert");
+                }
+            });
+        }
+    }
+
+    tracing::info!("Notification channel closed, exiting alert notifier");
+
+    Ok(())
+}
+
+/// Free and total space of the filesystem containing `path`.
+fn disk_space(path: &Path) -> anyhow::Result<DiskSpace> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).context("Invalid path")?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: `path` is a valid NUL terminated string and `stat` is only read
+    // after `statvfs` reports success.
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error()).context("Querying filesystem statistics");
+        }
+        stat.assume_init()
+    };
+
+    let fragment_size = stat.f_frsize as u64;
+    Ok(DiskSpace {
+        available: stat.f_bavail as u64 * fragment_size,
+        total: stat.f_blocks as u64 * fragment_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::BlockHash;
+
+    use super::*;
+
+    fn config() -> AlertConfig {
+        AlertConfig {
+            url: "http://localhost".parse().unwrap(),
+            format: AlertFormat::Slack,
+            template: DEFAULT_TEMPLATE.to_owned(),
+            sync_stall_timeout: Duration::from_secs(300),
+            reorg_depth: NonZeroU64::new(5).unwrap(),
+            min_free_disk_space_percent: 10,
+            gateway_errors_per_minute: NonZeroU64::new(10).unwrap(),
+            max_retries: NonZeroUsize::new(1).unwrap(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn sync_stall_alerts_once_until_progress() {
+        let start = Instant::now();
+        let mut monitor = Monitor::new(&config(), start, 0);
+        monitor.on_block(Some(BlockNumber::new_or_panic(10)), start);
+
+        assert!(monitor
+            .check(start + Duration::from_secs(100), None, 0)
+            .is_empty());
+
+        let alerts = monitor.check(start + Duration::from_secs(300), None, 0);
+        assert_eq!(
+            alerts,
+            vec![Alert::SyncStalled {
+                last_block: Some(BlockNumber::new_or_panic(10)),
+                stalled_for: Duration::from_secs(300),
+            }]
+        );
+        assert!(monitor
+            .check(start + Duration::from_secs(400), None, 0)
+            .is_empty());
+
+        monitor.on_block(
+            Some(BlockNumber::new_or_panic(11)),
+            start + Duration::from_secs(500),
+        );
+        assert_eq!(
+            monitor
+                .check(start + Duration::from_secs(800), None, 0)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn reorg_depth_threshold() {
+        let monitor = Monitor::new(&config(), Instant::now(), 0);
+        let reorg = |first, last| Reorg {
+            first_block_number: BlockNumber::new_or_panic(first),
+            first_block_hash: BlockHash::default(),
+            last_block_number: BlockNumber::new_or_panic(last),
+            last_block_hash: BlockHash::default(),
+        };
+
+        assert_eq!(monitor.on_reorg(&reorg(10, 13)), None);
+        assert_eq!(
+            monitor.on_reorg(&reorg(10, 14)),
+            Some(Alert::DeepReorg {
+                first_block_number: BlockNumber::new_or_panic(10),
+                last_block_number: BlockNumber::new_or_panic(14),
+                depth: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn disk_space_alert_rearms_when_cleared() {
+        let start = Instant::now();
+        let mut monitor = Monitor::new(&config(), start, 0);
+        let low = Some(DiskSpace {
+            available: 5,
+            total: 100,
+        });
+        let ok = Some(DiskSpace {
+            available: 50,
+            total: 100,
+        });
+
+        assert_eq!(monitor.check(start, low, 0).len(), 1);
+        assert!(monitor.check(start, low, 0).is_empty());
+        assert!(monitor.check(start, ok, 0).is_empty());
+        assert_eq!(monitor.check(start, low, 0).len(), 1);
+    }
+
+    #[test]
+    fn gateway_error_spike() {
+        let start = Instant::now();
+        let mut monitor = Monitor::new(&config(), start, 100);
+
+        // Window not elapsed yet.
+        assert!(monitor
+            .check(start + Duration::from_secs(30), None, 200)
+            .is_empty());
+
+        let alerts = monitor.check(start + Duration::from_secs(60), None, 200);
+        assert_eq!(
+            alerts,
+            vec![Alert::GatewayErrors {
+                count: 100,
+                window: Duration::from_secs(60),
+            }]
+        );
+
+        // Below threshold in the next window.
+        assert!(monitor
+            .check(start + Duration::from_secs(120), None, 205)
+            .is_empty());
+    }
+
+    #[test]
+    fn rendering() {
+        let alert = Alert::DeepReorg {
+            first_block_number: BlockNumber::new_or_panic(10),
+            last_block_number: BlockNumber::new_or_panic(14),
+            depth: 5,
+        };
+        let text = alert.render(DEFAULT_TEMPLATE, "mainnet");
+        assert_eq!(
+            text,
+            "[mainnet] pathfinder deep_reorg: reorg of 5 blocks (10 to 14)"
+        );
+
+        assert_eq!(
+            AlertFormat::Discord.payload(&alert, text.clone()),
+            serde_json::json!({ "content": text })
+        );
+        assert_eq!(
+            AlertFormat::Json.payload(&alert, text.clone()),
+            serde_json::json!({ "alert": "deep_reorg", "message": text })
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Counts the targets of `starknet_call` over a sliding window, so that
+//! operators can see which contracts and entry points their traffic goes to
+//! using `pathfinder_getCallStats`.
+//!
+//! Calls are counted in one minute buckets, the oldest of which is dropped as
+//! the window slides.
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pathfinder_common::{ContractAddress, EntryPoint};
+
+const BUCKET: Duration = Duration::from_secs(60);
+
+/// Distinct targets counted per bucket. Calls to further targets are only
+/// counted in the total, which bounds memory usage if calls target many
+/// different contracts.
+const MAX_TARGETS_PER_BUCKET: usize = 10_000;
+
+type Target = (ContractAddress, EntryPoint);
+
+#[derive(Clone)]
+pub struct CallStats(Arc<Mutex<Inner>>);
+
+struct Inner {
+    started: Instant,
+    /// Number of buckets in the window.
+    buckets: usize,
+    /// Buckets by their index since `started`, newest last.
+    counts: VecDeque<(u64, Bucket)>,
+}
+
+#[derive(Default)]
+struct Bucket {
+    total: u64,
+    targets: HashMap<Target, u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct CallStatsSummary {
+    pub window_seconds: u64,
+    /// All calls in the window, including calls to targets not in `targets`.
+    pub total_calls: u64,
+    /// The most called targets, most calls first.
+    pub targets: Vec<CallTarget>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct CallTarget {
+    pub contract_address: ContractAddress,
+    pub entry_point_selector: EntryPoint,
+    pub calls: u64,
+}
+
+impl CallStats {
+    /// Counts calls over the last `window`, rounded up to whole minutes.
+    pub fn new(window: Duration) -> Self {
+        let buckets = window.as_secs().div_ceil(BUCKET.as_secs()).max(1) as usize;
+        Self(Arc::new(Mutex::new(Inner {
+            started: Instant::now(),
+            buckets,
+            counts: VecDeque::with_capacity(buckets),
+        })))
+    }
+
+    pub fn record(&self, contract_address: ContractAddress, entry_point_selector: EntryPoint) {
+        self.record_at(Instant::now(), (contract_address, entry_point_selector));
+    }
+
+    fn record_at(&self, now: Instant, target: Target) {
+        let mut inner = self.0.lock().unwrap();
+        let index = inner.bucket_index(now);
+        inner.expire(index);
+
+        if inner.counts.back().map(|(i, _)| *i) != Some(index) {
+            inner.counts.push_back((index, Bucket::default()));
+        }
+        let (_, bucket) = inner.counts.back_mut().expect("Bucket was just added");
+        bucket.total += 1;
+        if let Some(count) = bucket.targets.get_mut(&target) {
+            *count += 1;
+        } else if bucket.targets.len() < MAX_TARGETS_PER_BUCKET {
+            bucket.targets.insert(target, 1);
+        }
+    }
+
+    /// The `limit` most called targets in the window.
+    pub fn top(&self, limit: usize) -> CallStatsSummary {
+        self.top_at(Instant::now(), limit)
+    }
+
+    fn top_at(&self, now: Instant, limit: usize) -> CallStatsSummary {
+        let mut inner = self.0.lock().unwrap();
+        let index = inner.bucket_index(now);
+        inner.expire(index);
+
+        let mut counts = HashMap::<Target, u64>::new();
+        for (_, bucket) in &inner.counts {
+            for (target, calls) in &bucket.targets {
+                *counts.entry(*target).or_default() += calls;
+            }
+        }
+
+        let mut targets = counts
+            .into_iter()
+            .map(
+                |((contract_address, entry_point_selector), calls)| CallTarget {
+                    contract_address,
+                    entry_point_selector,
+                    calls,
+                },
+            )
+            .collect::<Vec<_>>();
+        targets.sort_by(|a, b| {
+            b.calls
+                .cmp(&a.calls)
+                .then_with(|| a.contract_address.cmp(&b.contract_address))
+                .then_with(|| a.entry_point_selector.cmp(&b.entry_point_selector))
+        });
+        targets.truncate(limit);
+
+        CallStatsSummary {
+            window_seconds: inner.buckets as u64 * BUCKET.as_secs(),
+            total_calls: inner.counts.iter().map(|(_, bucket)| bucket.total).sum(),
+            targets,
+        }
+    }
+}
+
+impl Inner {
+    fn bucket_index(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.started).as_secs() / BUCKET.as_secs()
+    }
+
+    /// Drops the buckets which are no longer in the window ending in bucket
+    /// `index`.
+    fn expire(&mut self, index: u64) {
+        let oldest = index.saturating_sub(self.buckets as u64 - 1);
+        while self.counts.front().is_some_and(|(i, _)| *i < oldest) {
+            self.counts.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn top_targets_in_window() {
+        let uut = CallStats::new(Duration::from_secs(120));
+        let start = uut.0.lock().unwrap().started;
+        let first = (contract_address!("0x1"), entry_point!("0x10"));
+        let second = (contract_address!("0x2"), entry_point!("0x20"));
+        let third = (contract_address!("0x3"), entry_point!("0x30"));
+
+        uut.record_at(start, first);
+        uut.record_at(start, first);
+        uut.record_at(start + BUCKET, second);
+        uut.record_at(start + BUCKET, third);
+        uut.record_at(start + BUCKET, third);
+        uut.record_at(start + BUCKET, third);
+
+        let summary = uut.top_at(start + BUCKET, 2);
+        assert_eq!(summary.window_seconds, 120);
+        assert_eq!(summary.total_calls, 6);
+        assert_eq!(
+            summary.targets,
+            vec![
+                CallTarget {
+                    contract_address: third.0,
+                    entry_point_selector: third.1,
+                    calls: 3,
+                },
+                CallTarget {
+                    contract_address: first.0,
+                    entry_point_selector: first.1,
+                    calls: 2,
+                },
+            ]
+        );
+
+        // The first bucket has left the window.
+        let summary = uut.top_at(start + 2 * BUCKET, 10);
+        assert_eq!(summary.total_calls, 4);
+        assert_eq!(summary.targets.len(), 2);
+    }
+}
@@ -0,0 +1,216 @@
+//! Persistent cache of the CASM compiled from Sierra classes which are not
+//! part of the chain, such as those of simulated declare transactions.
+//!
+//! Compilation runs on a worker task which stores its output in the database,
+//! so that a class is compiled once instead of on every simulation, including
+//! across restarts. Concurrent requests for a class being compiled wait for
+//! the same compilation.
+//!
+//! Lookups are counted by the `casm_cache_requests_total` metric, labelled
+//! with a `result` of `hit` or `miss`.
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use pathfinder_common::SierraHash;
+use pathfinder_storage::Storage;
+use starknet_gateway_types::class_hash::{compute_class_hash, ComputedClassHash};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinSet;
+
+type CompilationResult = Result<Arc<Vec<u8>>, String>;
+
+struct Job {
+    sierra_hash: SierraHash,
+    definition: Vec<u8>,
+    reply: oneshot::Sender<CompilationResult>,
+}
+
+#[derive(Clone)]
+pub struct CasmCache {
+    storage: Storage,
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl CasmCache {
+    /// Creates the cache and spawns its compilation worker.
+    pub fn new(storage: Storage) -> Self {
+        let (jobs, rx) = mpsc::unbounded_channel();
+        tokio::spawn(compile_jobs(storage.clone(), rx));
+        Self { storage, jobs }
+    }
+
+    /// Returns the CASM compiled from the Sierra class `definition`, compiling
+    /// it if it is not cached yet.
+    ///
+    /// Blocks the current thread, so it must not be called from an async
+    /// context.
+    pub(crate) fn compile(&self, definition: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let sierra_hash = match compute_class_hash(&definition).context("Computing class hash")? {
+            ComputedClassHash::Sierra(class_hash) => SierraHash(class_hash.0),
+            ComputedClassHash::Cairo(_) => anyhow::bail!("Sierra class hashed as Cairo class"),
+        };
+
+        let compiler_version = pathfinder_compiler::latest_compiler_version();
+        let mut db = self
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        let cached = db
+            .cached_casm(&sierra_hash, compiler_version)
+            .context("Querying cached compiled class definition")?;
+        drop(db);
+        if let Some(casm_definition) = cached {
+            metrics::increment_counter!("casm_cache_requests_total", "result" => "hit");
+            return Ok(casm_definition);
+        }
+        metrics::increment_counter!("casm_cache_requests_total", "result" => "miss");
+
+        let (reply, rx) = oneshot::channel();
+        let job = Job {
+            sierra_hash,
+            definition,
+            reply,
+        };
+        if let Err(mpsc::error::SendError(job)) = self.jobs.send(job) {
+            // The worker has stopped, which only happens on shutdown.
+            return pathfinder_compiler::compile_to_casm(&job.definition)
+                .context("Compiling Sierra class definition to CASM");
+        }
+
+        let casm_definition = rx
+            .blocking_recv()
+            .context("Compilation worker stopped")?
+            .map_err(|error| anyhow::anyhow!(error))
+            .context("Compiling Sierra class definition to CASM")?;
+        Ok(casm_definition.as_ref().clone())
+    }
+}
+
+/// Compiles the classes of `jobs` and stores them in the database, replying
+/// to all jobs for a class once its compilation is done.
+async fn compile_jobs(storage: Storage, mut jobs: mpsc::UnboundedReceiver<Job>) {
+    let mut in_flight: HashMap<SierraHash, Vec<oneshot::Sender<CompilationResult>>> =
+        HashMap::new();
+    let mut compilations = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            job = jobs.recv() => {
+                let Some(job) = job else {
+                    return;
+                };
+                match in_flight.entry(job.sierra_hash) {
+                    Entry::Occupied(mut waiting) => waiting.get_mut().push(job.reply),
+                    Entry::Vacant(entry) => {
+                        entry.insert(vec![job.reply]);
+                        let storage = storage.clone();
+                        let compilation = tokio::task::spawn_blocking(move || {
+                            compile_and_store(&storage, job.sierra_hash, &job.definition)
+                        });
+                        compilations.spawn(async move { (job.sierra_hash, compilation.await) });
+                    }
+                }
+            }
+            Some(Ok((sierra_hash, compiled))) = compilations.join_next() => {
+                let result = match compiled {
+                    Ok(Ok(casm_definition)) => Ok(Arc::new(casm_definition)),
+                    Ok(Err(error)) => Err(format!("{error:#}")),
+                    Err(error) => Err(format!("Compilation task failed: {error}")),
+                };
+                for reply in in_flight.remove(&sierra_hash).unwrap_or_default() {
+                    // The request may have been cancelled.
+                    let _ = reply.send(result.clone());
+                }
+            }
+        }
+    }
+}
+
+fn compile_and_store(
+    storage: &Storage,
+    sierra_hash: SierraHash,
+    definition: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let casm_definition = pathfinder_compiler::compile_to_casm(definition)?;
+
+    let store = || -> anyhow::Result<()> {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.insert_cached_casm(
+            &sierra_hash,
+            pathfinder_compiler::latest_compiler_version(),
+            &casm_definition,
+        )?;
+        db.commit().context("Committing database transaction")
+    };
+    if let Err(error) = store() {
+        // The class is compiled again on the next miss.
+        tracing::warn!(%sierra_hash, error=%format!("{error:#}"), "Failed to store compiled class");
+    }
+
+    Ok(casm_definition)
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_gateway_test_fixtures::class_definitions::CAIRO_0_11_SIERRA;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn compiled_classes_are_persisted() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let cache = CasmCache::new(storage.clone());
+
+        let sierra_hash = match compute_class_hash(CAIRO_0_11_SIERRA).unwrap() {
+            ComputedClassHash::Sierra(class_hash) => SierraHash(class_hash.0),
+            ComputedClassHash::Cairo(_) => panic!("Sierra class hashed as Cairo class"),
+        };
+        let compiled = {
+            let cache = cache.clone();
+            tokio::task::spawn_blocking(move || cache.compile(CAIRO_0_11_SIERRA.to_vec()))
+                .await
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(
+            compiled,
+            pathfinder_compiler::compile_to_casm(CAIRO_0_11_SIERRA).unwrap()
+        );
+
+        let cached = storage
+            .connection()
+            .unwrap()
+            .transaction()
+            .unwrap()
+            .cached_casm(&sierra_hash, pathfinder_compiler::latest_compiler_version())
+            .unwrap();
+        assert_eq!(cached, Some(compiled));
+
+        // A cache over the same database, as after a restart, returns the stored
+        // class instead of compiling it.
+        {
+            let mut db = storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.insert_cached_casm(
+                &sierra_hash,
+                pathfinder_compiler::latest_compiler_version(),
+                b"stored",
+            )
+            .unwrap();
+            db.commit().unwrap();
+        }
+        let restarted = CasmCache::new(storage);
+        let cached =
+            tokio::task::spawn_blocking(move || restarted.compile(CAIRO_0_11_SIERRA.to_vec()))
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(cached, b"stored");
+    }
+}
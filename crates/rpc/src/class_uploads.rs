@@ -0,0 +1,204 @@
+//! Temporary store of Sierra classes uploaded with `pathfinder_uploadClass`.
+//!
+//! Declare transactions passed to the methods in [DECLARE_METHODS] may
+//! reference an uploaded class by setting `contract_class` to its class hash
+//! instead of the full definition. This avoids sending a large class three
+//! times when estimating, simulating and then submitting a declaration.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pathfinder_common::ClassHash;
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+use crate::jsonrpc::RpcError;
+
+/// Methods whose declare transactions may reference an uploaded class.
+pub const DECLARE_METHODS: &[&str] = &[
+    "starknet_addDeclareTransaction",
+    "starknet_estimateFee",
+    "starknet_simulateTransactions",
+    "pathfinder_estimateFeeBundle",
+];
+
+/// How long an uploaded class is kept after its last upload.
+pub const TTL: Duration = Duration::from_secs(30 * 60);
+
+/// The oldest uploads are evicted once the stored definitions exceed this many
+/// bytes.
+const MAX_TOTAL_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Clone, Default)]
+pub struct ClassUploads(Arc<Mutex<HashMap<ClassHash, Upload>>>);
+
+struct Upload {
+    definition: Arc<RawValue>,
+    expires_at: Instant,
+}
+
+impl ClassUploads {
+    /// Stores the JSON `definition` of the class with `class_hash`, or extends
+    /// the lifetime of an earlier upload of the same class.
+    pub fn insert(&self, class_hash: ClassHash, definition: Box<RawValue>) {
+        let now = Instant::now();
+        let mut uploads = self.0.lock().unwrap();
+        uploads.retain(|_, upload| upload.expires_at > now);
+
+        uploads.insert(
+            class_hash,
+            Upload {
+                definition: definition.into(),
+                expires_at: now + TTL,
+            },
+        );
+
+        let mut total_size: usize = uploads
+            .values()
+            .map(|upload| upload.definition.get().len())
+            .sum();
+        while total_size > MAX_TOTAL_SIZE {
+            let Some((&oldest, _)) = uploads
+                .iter()
+                .filter(|(hash, _)| **hash != class_hash)
+                .min_by_key(|(_, upload)| upload.expires_at)
+            else {
+                break;
+            };
+            let upload = uploads.remove(&oldest).expect("Key exists");
+            total_size -= upload.definition.get().len();
+        }
+    }
+
+    fn get(&self, class_hash: ClassHash) -> Option<Arc<RawValue>> {
+        let uploads = self.0.lock().unwrap();
+        uploads
+            .get(&class_hash)
+            .filter(|upload| upload.expires_at > Instant::now())
+            .map(|upload| upload.definition.clone())
+    }
+
+    /// Replaces class hashes in the `contract_class` of declare transactions
+    /// in `params` with the uploaded definitions.
+    ///
+    /// Returns [None] if no class is referenced, so that the params only have
+    /// to be parsed if they contain a reference.
+    pub fn substitute(&self, params: &RawValue) -> Result<Option<Box<RawValue>>, RpcError> {
+        if !references_class(params.get()) {
+            return Ok(None);
+        }
+
+        let mut params: Value = serde_json::from_str(params.get())
+            .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+        self.substitute_value(&mut params)?;
+
+        let params = serde_json::to_string(&params)
+            .and_then(RawValue::from_string)
+            .map_err(|e| RpcError::InternalError(e.into()))?;
+        Ok(Some(params))
+    }
+
+    fn substitute_value(&self, value: &mut Value) -> Result<(), RpcError> {
+        match value {
+            Value::Object(object) => {
+                let is_declare = object.get("type").and_then(Value::as_str) == Some("DECLARE");
+                match object.get_mut("contract_class") {
+                    Some(class) if is_declare && class.is_string() => {
+                        let hash = class.as_str().expect("Checked to be a string");
+                        let class_hash = serde_json::from_value::<ClassHash>(Value::from(hash))
+                            .map_err(|e| {
+                                RpcError::InvalidParams(format!("Invalid class hash: {e}"))
+                            })?;
+                        let definition = self.get(class_hash).ok_or_else(|| {
+                            RpcError::InvalidParams(format!(
+                                "Class {hash} has not been uploaded or its upload has expired"
+                            ))
+                        })?;
+                        *class = serde_json::from_str(definition.get())
+                            .map_err(|e| RpcError::InternalError(e.into()))?;
+                        Ok(())
+                    }
+                    _ => object
+                        .values_mut()
+                        .try_for_each(|value| self.substitute_value(value)),
+                }
+            }
+            Value::Array(array) => array
+                .iter_mut()
+                .try_for_each(|value| self.substitute_value(value)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns true if `params` contain `"contract_class"` with a string value.
+fn references_class(params: &str) -> bool {
+    const KEY: &str = "\"contract_class\"";
+
+    params.match_indices(KEY).any(|(index, _)| {
+        let rest = params[index + KEY.len()..].trim_start();
+        rest.strip_prefix(':')
+            .is_some_and(|rest| rest.trim_start().starts_with('"'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    use super::*;
+
+    fn raw(value: Value) -> Box<RawValue> {
+        RawValue::from_string(value.to_string()).unwrap()
+    }
+
+    #[test]
+    fn substitutes_uploaded_class() {
+        let uploads = ClassUploads::default();
+        uploads.insert(
+            class_hash!("0x123"),
+            raw(json!({"sierra_program": ["0x1"]})),
+        );
+
+        let params = raw(json!({
+            "request": [
+                {"type": "DECLARE", "contract_class": "0x123", "version": "0x2"},
+                {"type": "INVOKE", "calldata": ["0x1"]},
+            ],
+            "block_id": "latest",
+        }));
+        let params = uploads.substitute(&params).unwrap().unwrap();
+
+        let params: Value = serde_json::from_str(params.get()).unwrap();
+        assert_eq!(
+            params,
+            json!({
+                "request": [
+                    {"type": "DECLARE", "contract_class": {"sierra_program": ["0x1"]}, "version": "0x2"},
+                    {"type": "INVOKE", "calldata": ["0x1"]},
+                ],
+                "block_id": "latest",
+            })
+        );
+    }
+
+    #[test]
+    fn full_definitions_are_left_alone() {
+        let uploads = ClassUploads::default();
+        let params = raw(json!([{"type": "DECLARE", "contract_class": {"abi": "[]"}}]));
+
+        assert!(uploads.substitute(&params).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_class_is_rejected() {
+        let uploads = ClassUploads::default();
+        let params = raw(json!([{"type": "DECLARE", "contract_class": "0x123"}]));
+
+        assert_matches::assert_matches!(
+            uploads.substitute(&params),
+            Err(RpcError::InvalidParams(_))
+        );
+    }
+}
@@ -1,13 +1,24 @@
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 
-use pathfinder_common::ChainId;
-use pathfinder_executor::{TraceCache, VersionedConstants};
+use pathfinder_common::{ChainId, ContractAddress};
+use pathfinder_executor::{Fork, TraceCache, VersionedConstants};
 use pathfinder_storage::Storage;
 
+use crate::call_stats::CallStats;
+use crate::casm_cache::CasmCache;
+use crate::class_uploads::ClassUploads;
+use crate::execution_fingerprint::ExecutionFingerprint;
+use crate::fee_tracker::FeeTracker;
 pub use crate::jsonrpc::websocket::WebsocketContext;
-use crate::jsonrpc::Notifications;
-use crate::pending::{PendingData, PendingWatcher};
+use crate::jsonrpc::{ChainEvent, Notifications};
+use crate::load_shedding::LoadShedder;
+use crate::pending::{PendingData, PendingGasPriceSource, PendingWatcher};
+use crate::response_cache::ResponseCache;
+use crate::shadow_execution::ShadowExecution;
+use crate::subscription_sessions::SubscriptionSessions;
+use crate::upstream::Upstream;
+use crate::write_protection::WriteProtection;
 use crate::SyncState;
 
 type SequencerClient = starknet_gateway_client::Client;
@@ -16,9 +27,34 @@ use tokio::sync::watch as tokio_watch;
 #[derive(Clone)]
 pub struct RpcConfig {
     pub batch_concurrency_limit: NonZeroUsize,
+    /// Limits the total cost of the requests of a batch running at the same
+    /// time, see [crate::load_shedding::MethodClass::batch_cost].
+    pub batch_cost_budget: Option<NonZeroUsize>,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_bloom_filters_to_load: NonZeroUsize,
+    /// Approximate size in bytes after which pages of `starknet_getEvents` are
+    /// cut short, regardless of the requested chunk size.
+    pub get_events_max_page_bytes: Option<NonZeroUsize>,
     pub custom_versioned_constants: Option<VersionedConstants>,
+    /// Address of the L2 staking contract queried by
+    /// `pathfinder_getStakingEpoch`.
+    pub staking_contract: Option<ContractAddress>,
+    /// Maximum number of events in `starknet_getTransactionReceipt` responses.
+    pub receipt_events_limit: Option<NonZeroUsize>,
+    /// Whether daily analytics are maintained, see `analytics_getDailyStats`.
+    pub analytics: bool,
+    /// Gas prices of fee estimates and simulations on the pending block.
+    pub pending_gas_price_source: PendingGasPriceSource,
+}
+
+/// Pings sent to websocket clients, which are disconnected if they stop
+/// responding.
+#[derive(Clone, Copy, Debug)]
+pub struct WebsocketKeepalive {
+    pub interval: std::time::Duration,
+    /// Clients are disconnected once nothing, including pongs, has been
+    /// received from them for this long.
+    pub timeout: std::time::Duration,
 }
 
 #[derive(Clone)]
@@ -31,6 +67,19 @@ pub struct RpcContext {
     pub chain_id: ChainId,
     pub sequencer: SequencerClient,
     pub websocket: Option<WebsocketContext>,
+    pub response_cache: Option<ResponseCache>,
+    pub fee_tracker: Option<FeeTracker>,
+    pub casm_cache: Option<CasmCache>,
+    pub upstream: Option<Upstream>,
+    pub load_shedder: Option<LoadShedder>,
+    pub write_protection: Option<WriteProtection>,
+    pub class_uploads: ClassUploads,
+    pub execution_fingerprint: Option<ExecutionFingerprint>,
+    pub websocket_keepalive: Option<WebsocketKeepalive>,
+    pub subscription_sessions: Option<SubscriptionSessions>,
+    pub call_stats: Option<CallStats>,
+    pub shadow_execution: Option<ShadowExecution>,
+    pub fork: Option<Fork>,
     pub notifications: Notifications,
     pub config: RpcConfig,
 }
@@ -57,6 +106,19 @@ impl RpcContext {
             pending_data,
             sequencer,
             websocket: None,
+            response_cache: None,
+            fee_tracker: None,
+            casm_cache: None,
+            upstream: None,
+            load_shedder: None,
+            write_protection: None,
+            class_uploads: Default::default(),
+            execution_fingerprint: None,
+            websocket_keepalive: None,
+            subscription_sessions: None,
+            call_stats: None,
+            shadow_execution: None,
+            fork: None,
             notifications,
             config,
         }
@@ -104,9 +166,15 @@ impl RpcContext {
 
         let config = RpcConfig {
             batch_concurrency_limit: NonZeroUsize::new(8).unwrap(),
+            batch_cost_budget: None,
             get_events_max_blocks_to_scan: NonZeroUsize::new(1000).unwrap(),
             get_events_max_uncached_bloom_filters_to_load: NonZeroUsize::new(1000).unwrap(),
+            get_events_max_page_bytes: None,
             custom_versioned_constants: None,
+            staking_contract: None,
+            receipt_events_limit: None,
+            analytics: false,
+            pending_gas_price_source: PendingGasPriceSource::Pending,
         };
 
         Self::new(
@@ -156,4 +224,113 @@ impl RpcContext {
             ..self
         }
     }
+
+    /// Replaces the trace cache and spawns a task which clears it on reorgs.
+    pub fn with_trace_cache(self, cache: TraceCache) -> Self {
+        tokio::spawn(clear_trace_cache_on_reorg(
+            cache.clone(),
+            self.notifications.chain_events.subscribe(),
+        ));
+        Self { cache, ..self }
+    }
+
+    pub fn with_response_cache(self, response_cache: ResponseCache) -> Self {
+        Self {
+            response_cache: Some(response_cache),
+            ..self
+        }
+    }
+
+    pub fn with_fee_tracker(self, fee_tracker: FeeTracker) -> Self {
+        Self {
+            fee_tracker: Some(fee_tracker),
+            ..self
+        }
+    }
+
+    pub fn with_casm_cache(self, casm_cache: CasmCache) -> Self {
+        Self {
+            casm_cache: Some(casm_cache),
+            ..self
+        }
+    }
+
+    pub fn with_upstream(self, upstream: Upstream) -> Self {
+        Self {
+            upstream: Some(upstream),
+            ..self
+        }
+    }
+
+    pub fn with_load_shedder(self, load_shedder: LoadShedder) -> Self {
+        Self {
+            load_shedder: Some(load_shedder),
+            ..self
+        }
+    }
+
+    pub fn with_write_protection(self, write_protection: WriteProtection) -> Self {
+        Self {
+            write_protection: Some(write_protection),
+            ..self
+        }
+    }
+
+    pub fn with_execution_fingerprint(self, fingerprint: ExecutionFingerprint) -> Self {
+        Self {
+            execution_fingerprint: Some(fingerprint),
+            ..self
+        }
+    }
+
+    pub fn with_websocket_keepalive(self, keepalive: WebsocketKeepalive) -> Self {
+        Self {
+            websocket_keepalive: Some(keepalive),
+            ..self
+        }
+    }
+
+    pub fn with_subscription_sessions(self, sessions: SubscriptionSessions) -> Self {
+        Self {
+            subscription_sessions: Some(sessions),
+            ..self
+        }
+    }
+
+    pub fn with_call_stats(self, call_stats: CallStats) -> Self {
+        Self {
+            call_stats: Some(call_stats),
+            ..self
+        }
+    }
+
+    pub fn with_shadow_execution(self, shadow_execution: ShadowExecution) -> Self {
+        Self {
+            shadow_execution: Some(shadow_execution),
+            ..self
+        }
+    }
+
+    pub fn with_fork(self, fork: Fork) -> Self {
+        Self {
+            fork: Some(fork),
+            ..self
+        }
+    }
+}
+
+async fn clear_trace_cache_on_reorg(
+    cache: TraceCache,
+    mut chain_events: tokio::sync::broadcast::Receiver<ChainEvent>,
+) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    loop {
+        match chain_events.recv().await {
+            // We don't know which blocks were affected if we lagged.
+            Ok(ChainEvent::Reorg(_)) | Err(RecvError::Lagged(_)) => cache.clear(),
+            Ok(ChainEvent::Commit(_) | ChainEvent::PendingUpdate(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
 }
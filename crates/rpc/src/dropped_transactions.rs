@@ -0,0 +1,199 @@
+//! Record of transactions which disappeared from the pending block.
+//!
+//! The gateway may drop transactions it previously included in the pending
+//! block, or replace them with a different transaction from the same sender
+//! and with the same nonce. Such transactions will never be accepted, so the
+//! transaction status subscription and `pathfinder_getTransactionStatus` report
+//! them as `DROPPED` or `REPLACED` for a while.
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pathfinder_common::transaction::TransactionVariant;
+use pathfinder_common::{ContractAddress, TransactionHash, TransactionNonce};
+use starknet_gateway_types::reply::PendingBlock;
+
+/// How long a dropped transaction is remembered.
+const RETENTION: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DroppedStatus {
+    /// The transaction was removed from the pending block.
+    Dropped,
+    /// The transaction was removed from the pending block, and the given
+    /// transaction with the same sender and nonce took its place.
+    Replaced(TransactionHash),
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct DroppedTransactions(Arc<Mutex<HashMap<TransactionHash, Record>>>);
+
+#[derive(Debug)]
+struct Record {
+    status: DroppedStatus,
+    expires_at: Instant,
+}
+
+impl DroppedTransactions {
+    /// Compares the transactions of two successive pending blocks, recording
+    /// the transactions missing from `next`.
+    ///
+    /// Nothing is recorded if `next` is built on a different parent, since
+    /// the transactions of `previous` are then expected to be in the parent
+    /// block instead.
+    pub fn record_pending_update(&self, previous: &PendingBlock, next: &PendingBlock) {
+        let now = Instant::now();
+        let mut records = self.0.lock().unwrap();
+        records.retain(|_, record| record.expires_at > now);
+
+        // A dropped transaction might reappear.
+        for tx in &next.transactions {
+            records.remove(&tx.hash);
+        }
+
+        if previous.parent_hash != next.parent_hash {
+            return;
+        }
+
+        let next_hashes = next
+            .transactions
+            .iter()
+            .map(|tx| tx.hash)
+            .collect::<HashSet<_>>();
+        let next_senders = next
+            .transactions
+            .iter()
+            .filter_map(|tx| sender_and_nonce(&tx.variant).map(|key| (key, tx.hash)))
+            .collect::<HashMap<_, _>>();
+
+        for tx in &previous.transactions {
+            if next_hashes.contains(&tx.hash) {
+                continue;
+            }
+
+            let replacement = sender_and_nonce(&tx.variant).and_then(|key| next_senders.get(&key));
+            let status = match replacement {
+                Some(replacement) => DroppedStatus::Replaced(*replacement),
+                None => DroppedStatus::Dropped,
+            };
+            tracing::debug!(transaction_hash=%tx.hash, ?status, "Transaction left pending block");
+            records.insert(
+                tx.hash,
+                Record {
+                    status,
+                    expires_at: now + RETENTION,
+                },
+            );
+        }
+    }
+
+    pub fn get(&self, transaction_hash: &TransactionHash) -> Option<DroppedStatus> {
+        let records = self.0.lock().unwrap();
+        records
+            .get(transaction_hash)
+            .filter(|record| record.expires_at > Instant::now())
+            .map(|record| record.status)
+    }
+}
+
+/// Transactions without a nonce cannot be replaced.
+fn sender_and_nonce(tx: &TransactionVariant) -> Option<(ContractAddress, TransactionNonce)> {
+    let nonce = match tx {
+        TransactionVariant::DeclareV1(tx) => tx.nonce,
+        TransactionVariant::DeclareV2(tx) => tx.nonce,
+        TransactionVariant::DeclareV3(tx) => tx.nonce,
+        TransactionVariant::DeployAccountV1(tx) => tx.nonce,
+        TransactionVariant::DeployAccountV3(tx) => tx.nonce,
+        TransactionVariant::InvokeV1(tx) => tx.nonce,
+        TransactionVariant::InvokeV3(tx) => tx.nonce,
+        TransactionVariant::DeclareV0(_)
+        | TransactionVariant::DeployV0(_)
+        | TransactionVariant::DeployV1(_)
+        | TransactionVariant::InvokeV0(_)
+        | TransactionVariant::L1Handler(_) => return None,
+    };
+
+    Some((tx.contract_address(), nonce))
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::{InvokeTransactionV1, Transaction};
+    use pathfinder_common::BlockHash;
+
+    use super::*;
+
+    fn invoke(hash: TransactionHash, nonce: TransactionNonce) -> Transaction {
+        Transaction {
+            hash,
+            variant: TransactionVariant::InvokeV1(InvokeTransactionV1 {
+                sender_address: contract_address!("0x1"),
+                nonce,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn pending(parent_hash: BlockHash, transactions: Vec<Transaction>) -> PendingBlock {
+        PendingBlock {
+            parent_hash,
+            transactions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dropped_and_replaced() {
+        let uut = DroppedTransactions::default();
+        let kept = invoke(transaction_hash!("0x1"), transaction_nonce!("0x0"));
+        let dropped = invoke(transaction_hash!("0x2"), transaction_nonce!("0x1"));
+        let replaced = invoke(transaction_hash!("0x3"), transaction_nonce!("0x2"));
+        let replacement = invoke(transaction_hash!("0x4"), transaction_nonce!("0x2"));
+
+        uut.record_pending_update(
+            &pending(block_hash!("0x1"), vec![kept.clone(), dropped, replaced]),
+            &pending(block_hash!("0x1"), vec![kept, replacement]),
+        );
+
+        assert_eq!(uut.get(&transaction_hash!("0x1")), None);
+        assert_eq!(
+            uut.get(&transaction_hash!("0x2")),
+            Some(DroppedStatus::Dropped)
+        );
+        assert_eq!(
+            uut.get(&transaction_hash!("0x3")),
+            Some(DroppedStatus::Replaced(transaction_hash!("0x4")))
+        );
+    }
+
+    #[test]
+    fn new_parent_is_ignored() {
+        let uut = DroppedTransactions::default();
+        let tx = invoke(transaction_hash!("0x1"), transaction_nonce!("0x0"));
+
+        uut.record_pending_update(
+            &pending(block_hash!("0x1"), vec![tx]),
+            &pending(block_hash!("0x2"), vec![]),
+        );
+
+        assert_eq!(uut.get(&transaction_hash!("0x1")), None);
+    }
+
+    #[test]
+    fn reappearing_transaction_is_forgotten() {
+        let uut = DroppedTransactions::default();
+        let tx = invoke(transaction_hash!("0x1"), transaction_nonce!("0x0"));
+
+        uut.record_pending_update(
+            &pending(block_hash!("0x1"), vec![tx.clone()]),
+            &pending(block_hash!("0x1"), vec![]),
+        );
+        uut.record_pending_update(
+            &pending(block_hash!("0x1"), vec![]),
+            &pending(block_hash!("0x1"), vec![tx]),
+        );
+
+        assert_eq!(uut.get(&transaction_hash!("0x1")), None);
+    }
+}
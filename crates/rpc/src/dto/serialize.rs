@@ -1,14 +1,24 @@
+use std::cell::RefCell;
+
+use serde_json::value::RawValue;
+
 use crate::RpcVersion;
 
 #[derive(Copy, Clone)]
 #[cfg_attr(test, derive(Default))]
 pub struct Serializer {
     pub version: RpcVersion,
+    /// Whether values are written to [OUTPUT] as JSON text instead of being
+    /// returned, see [to_raw_value].
+    direct: bool,
 }
 
 pub struct SerializeStruct {
     pub version: RpcVersion,
     fields: serde_json::Map<String, Ok>,
+    direct: bool,
+    /// Whether no field has been written to [OUTPUT] yet.
+    empty: bool,
 }
 
 type BaseSerializer = serde_json::value::Serializer;
@@ -25,14 +35,67 @@ impl<T> SerializeForVersion for T
 where
     T: serde::Serialize,
 {
-    fn serialize(&self, _serializer: Serializer) -> Result<Ok, Error> {
+    fn serialize(&self, serializer: Serializer) -> Result<Ok, Error> {
+        if serializer.direct {
+            return write_json(self);
+        }
         self.serialize(BaseSerializer {})
     }
 }
 
+thread_local! {
+    /// The JSON text written by serializers in direct mode.
+    static OUTPUT: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+}
+
+/// Serializes `value` straight to JSON text, without building a
+/// [serde_json::Value] of it first.
+///
+/// Implementations need not be aware of this, as long as they only use the
+/// values returned by the [Serializer] they are given as their own result.
+/// Implementations which inspect serialized values have to serialize them
+/// with a [Serializer::new] instead.
+pub(crate) fn to_raw_value(
+    value: &dyn SerializeForVersion,
+    version: RpcVersion,
+) -> Result<Box<RawValue>, Error> {
+    let start = output_len();
+    let result = Serializer {
+        version,
+        direct: true,
+    }
+    .write(value);
+    let output = OUTPUT.with_borrow_mut(|output| match start {
+        // Leaves no large buffer behind.
+        0 => std::mem::take(output),
+        start => output.split_off(start),
+    });
+    result?;
+
+    let output = String::from_utf8(output).expect("JSON text is valid UTF-8");
+    RawValue::from_string(output)
+}
+
+fn output_len() -> usize {
+    OUTPUT.with_borrow(Vec::len)
+}
+
+fn write_bytes(bytes: &[u8]) {
+    OUTPUT.with_borrow_mut(|output| output.extend_from_slice(bytes));
+}
+
+/// Writes `value` to [OUTPUT]. The returned value is only a placeholder.
+fn write_json(value: &(impl serde::Serialize + ?Sized)) -> Result<Ok, Error> {
+    OUTPUT.with_borrow_mut(|output| serde_json::to_writer(output, value))?;
+    Ok(serde_json::Value::Null)
+}
+
 impl Serializer {
     pub fn new(version: RpcVersion) -> Self {
-        Self { version }
+        Self {
+            version,
+            direct: false,
+        }
     }
 
     pub fn serialize(self, value: &dyn SerializeForVersion) -> Result<Ok, Error> {
@@ -41,23 +104,37 @@ impl Serializer {
 
     pub fn serialize_str(self, value: &str) -> Result<Ok, Error> {
         use serde::Serializer;
+        if self.direct {
+            return write_json(value);
+        }
         BaseSerializer {}.serialize_str(value)
     }
 
     pub fn serialize_u64(self, value: u64) -> Result<Ok, Error> {
         use serde::Serializer;
+        if self.direct {
+            return write_json(&value);
+        }
         BaseSerializer {}.serialize_u64(value)
     }
 
     pub fn serialize_bool(self, value: bool) -> Result<Ok, Error> {
         use serde::Serializer;
+        if self.direct {
+            return write_json(&value);
+        }
         BaseSerializer {}.serialize_bool(value)
     }
 
     pub fn serialize_struct(self) -> Result<SerializeStruct, Error> {
+        if self.direct {
+            write_bytes(b"{");
+        }
         Ok(SerializeStruct {
             version: self.version,
             fields: Default::default(),
+            direct: self.direct,
+            empty: true,
         })
     }
 
@@ -66,15 +143,36 @@ impl Serializer {
         len: usize,
         values: &mut dyn Iterator<Item = impl SerializeForVersion>,
     ) -> Result<Ok, Error> {
-        use serde::ser::SerializeSeq;
-        use serde::Serializer;
+        if self.direct {
+            write_bytes(b"[");
+            for (i, value) in values.enumerate() {
+                if i > 0 {
+                    write_bytes(b",");
+                }
+                self.write(&value)?;
+            }
+            write_bytes(b"]");
+            return Ok(serde_json::Value::Null);
+        }
 
-        let mut serializer = BaseSerializer {}.serialize_seq(Some(len))?;
+        // Elements are collected directly instead of going through a serde
+        // sequence serializer, which would copy each serialized element.
+        let mut elements = Vec::with_capacity(len);
         for value in values {
-            let value = self.serialize(&value)?;
-            serializer.serialize_element(&value)?;
+            elements.push(self.serialize(&value)?);
+        }
+        Ok(serde_json::Value::Array(elements))
+    }
+
+    /// Writes `value` to [OUTPUT] in direct mode.
+    fn write(self, value: &dyn SerializeForVersion) -> Result<(), Error> {
+        let start = output_len();
+        let value = value.serialize(self)?;
+        // Nothing was written if the value was built without the serializer.
+        if output_len() == start {
+            write_json(&value)?;
         }
-        serializer.end()
+        Ok(())
     }
 }
 
@@ -84,7 +182,12 @@ impl SerializeStruct {
         key: &'static str,
         value: &dyn SerializeForVersion,
     ) -> Result<(), Error> {
-        let value = value.serialize(Serializer::new(self.version))?;
+        if self.direct {
+            self.write_key(key)?;
+            return self.serializer().write(value);
+        }
+
+        let value = value.serialize(self.serializer())?;
         self.fields.insert(key.to_owned(), value);
         Ok(())
     }
@@ -95,8 +198,14 @@ impl SerializeStruct {
         len: usize,
         values: &mut dyn Iterator<Item = impl SerializeForVersion>,
     ) -> Result<(), Error> {
-        let seq = Serializer::new(self.version).serialize_iter(len, values)?;
-        self.serialize_field(key, &seq)
+        if self.direct {
+            self.write_key(key)?;
+        }
+        let seq = self.serializer().serialize_iter(len, values)?;
+        if !self.direct {
+            self.fields.insert(key.to_owned(), seq);
+        }
+        Ok(())
     }
 
     /// Skips serialization if its [`None`].
@@ -113,7 +222,29 @@ impl SerializeStruct {
     }
 
     pub fn flatten(&mut self, value: &dyn SerializeForVersion) -> Result<(), Error> {
-        let value = value.serialize(Serializer::new(self.version))?;
+        if self.direct {
+            let start = output_len();
+            self.serializer().write(value)?;
+            // Splices the object's fields into this one.
+            OUTPUT.with_borrow_mut(|output| {
+                let object = &output[start..];
+                let is_object = object.first() == Some(&b'{') && object.last() == Some(&b'}');
+                if !is_object || object.len() == 2 {
+                    output.truncate(start);
+                    return;
+                }
+                output.pop();
+                if self.empty {
+                    output.remove(start);
+                } else {
+                    output[start] = b',';
+                }
+                self.empty = false;
+            });
+            return Ok(());
+        }
+
+        let value = value.serialize(self.serializer())?;
 
         if let serde_json::Value::Object(value) = value {
             for (k, v) in value {
@@ -125,8 +256,29 @@ impl SerializeStruct {
     }
 
     pub fn end(self) -> Result<Ok, Error> {
+        if self.direct {
+            write_bytes(b"}");
+            return Ok(serde_json::Value::Null);
+        }
         Ok(serde_json::Value::Object(self.fields))
     }
+
+    fn serializer(&self) -> Serializer {
+        Serializer {
+            version: self.version,
+            direct: self.direct,
+        }
+    }
+
+    fn write_key(&mut self, key: &str) -> Result<(), Error> {
+        if !self.empty {
+            write_bytes(b",");
+        }
+        self.empty = false;
+        write_json(key)?;
+        write_bytes(b":");
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -232,4 +384,79 @@ mod tests {
             assert_eq!(encoded, expected);
         }
     }
+
+    mod to_raw_value {
+        use super::*;
+
+        struct Inner(u64);
+
+        impl SerializeForVersion for Inner {
+            fn serialize(&self, serializer: Serializer) -> Result<Ok, Error> {
+                let mut serializer = serializer.serialize_struct()?;
+                serializer.serialize_field("inner", &self.0)?;
+                serializer.end()
+            }
+        }
+
+        struct Empty;
+
+        impl SerializeForVersion for Empty {
+            fn serialize(&self, serializer: Serializer) -> Result<Ok, Error> {
+                serializer.serialize_struct()?.end()
+            }
+        }
+
+        struct BuiltByHand;
+
+        impl SerializeForVersion for BuiltByHand {
+            fn serialize(&self, _serializer: Serializer) -> Result<Ok, Error> {
+                Ok(json!({"by_hand": [1, 2]}))
+            }
+        }
+
+        struct Outer;
+
+        impl SerializeForVersion for Outer {
+            fn serialize(&self, serializer: Serializer) -> Result<Ok, Error> {
+                let mut serializer = serializer.serialize_struct()?;
+                serializer.serialize_optional("missing", None::<u64>)?;
+                serializer.flatten(&Inner(1))?;
+                serializer.flatten(&Empty)?;
+                serializer.serialize_field("escaped", &"a\"b")?;
+                serializer.serialize_iter("list", 2, &mut [Inner(2), Inner(3)].into_iter())?;
+                serializer.serialize_field("serde", &json!({"serde": true}))?;
+                serializer.serialize_field("built", &BuiltByHand)?;
+                serializer.serialize_field("empty", &Empty)?;
+                serializer.end()
+            }
+        }
+
+        #[test]
+        fn matches_value() {
+            let raw = to_raw_value(&Outer, RpcVersion::default()).unwrap();
+            let value: serde_json::Value = serde_json::from_str(raw.get()).unwrap();
+            let expected = Outer.serialize(Serializer::default()).unwrap();
+
+            assert_eq!(value, expected);
+            assert_eq!(output_len(), 0);
+        }
+
+        #[test]
+        fn flatten_after_field() {
+            struct Flattened;
+
+            impl SerializeForVersion for Flattened {
+                fn serialize(&self, serializer: Serializer) -> Result<Ok, Error> {
+                    let mut serializer = serializer.serialize_struct()?;
+                    serializer.serialize_field("field", &0u64)?;
+                    serializer.flatten(&Inner(1))?;
+                    serializer.end()
+                }
+            }
+
+            let raw = to_raw_value(&Flattened, RpcVersion::default()).unwrap();
+
+            assert_eq!(raw.get(), r#"{"field":0,"inner":1}"#);
+        }
+    }
 }
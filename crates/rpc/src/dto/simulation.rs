@@ -114,6 +114,178 @@ impl crate::dto::serialize::SerializeForVersion for TransactionTrace<'_> {
                 )?;
             }
         }
+        // Pathfinder extension: all messages of the transaction in one list,
+        // along with the hashes needed to consume them on L1.
+        let messages = self.trace.l2_to_l1_messages();
+        if !messages.is_empty() {
+            serializer.serialize_iter(
+                "l2_to_l1_messages",
+                messages.len(),
+                &mut messages
+                    .iter()
+                    .enumerate()
+                    .map(|(order, message)| OrderedMsgToL1 { order, message }),
+            )?;
+        }
+        serializer.end()
+    }
+}
+
+struct OrderedMsgToL1<'a> {
+    order: usize,
+    message: &'a pathfinder_common::receipt::L2ToL1Message,
+}
+
+impl crate::dto::serialize::SerializeForVersion for OrderedMsgToL1<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("order", &self.order)?;
+        serializer.flatten(&crate::dto::MsgToL1(self.message))?;
+        serializer.serialize_field(
+            "message_hash",
+            &crate::dto::H256Hex(self.message.calculate_hash()),
+        )?;
+        serializer.end()
+    }
+}
+
+/// Pathfinder extension: the encoding of transaction traces.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceFormat {
+    /// Invocations with their nested calls, as in the specification.
+    #[default]
+    Nested,
+    /// A list of calls, see [FlatTransactionTrace].
+    Flat,
+}
+
+/// A transaction trace as a flat list of calls, in the style of Parity's EVM
+/// traces.
+///
+/// Each top-level invocation of the transaction is the root of its own call
+/// tree, named by the `type` of its calls. Calls are listed depth first in
+/// the order they were executed, and are located within their tree by their
+/// `trace_address`, the indices of the calls leading to them.
+#[derive(Debug)]
+pub struct FlatTransactionTrace<'a>(pub &'a pathfinder_executor::types::TransactionTrace);
+
+impl crate::dto::serialize::SerializeForVersion for FlatTransactionTrace<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        use pathfinder_executor::types::{ExecuteInvocation, TransactionTrace};
+
+        let mut calls = Vec::new();
+        match self.0 {
+            TransactionTrace::Declare(trace) => {
+                FlatCall::push_all(&mut calls, "VALIDATE", &trace.validate_invocation);
+                FlatCall::push_all(&mut calls, "FEE_TRANSFER", &trace.fee_transfer_invocation);
+            }
+            TransactionTrace::DeployAccount(trace) => {
+                FlatCall::push_all(&mut calls, "CONSTRUCTOR", &trace.constructor_invocation);
+                FlatCall::push_all(&mut calls, "VALIDATE", &trace.validate_invocation);
+                FlatCall::push_all(&mut calls, "FEE_TRANSFER", &trace.fee_transfer_invocation);
+            }
+            TransactionTrace::Invoke(trace) => {
+                FlatCall::push_all(&mut calls, "VALIDATE", &trace.validate_invocation);
+                match &trace.execute_invocation {
+                    ExecuteInvocation::FunctionInvocation(invocation) => {
+                        FlatCall::push_all(&mut calls, "EXECUTE", invocation)
+                    }
+                    ExecuteInvocation::RevertedReason(revert_reason) => {
+                        calls.push(FlatCall::Reverted {
+                            invocation_type: "EXECUTE",
+                            revert_reason,
+                        })
+                    }
+                }
+                FlatCall::push_all(&mut calls, "FEE_TRANSFER", &trace.fee_transfer_invocation);
+            }
+            TransactionTrace::L1Handler(trace) => {
+                FlatCall::push_all(&mut calls, "L1_HANDLER", &trace.function_invocation);
+            }
+        }
+
+        serializer.serialize_iter(calls.len(), &mut calls.into_iter())
+    }
+}
+
+enum FlatCall<'a> {
+    Call {
+        invocation_type: &'static str,
+        trace_address: Vec<usize>,
+        invocation: &'a pathfinder_executor::types::FunctionInvocation,
+    },
+    /// A reverted invocation, of which no calls are known.
+    Reverted {
+        invocation_type: &'static str,
+        revert_reason: &'a str,
+    },
+}
+
+impl<'a> FlatCall<'a> {
+    /// Pushes `invocation` and all its nested calls, depth first.
+    fn push_all(
+        calls: &mut Vec<Self>,
+        invocation_type: &'static str,
+        invocation: &'a Option<pathfinder_executor::types::FunctionInvocation>,
+    ) {
+        fn push(
+            calls: &mut Vec<FlatCall<'_>>,
+            invocation_type: &'static str,
+            trace_address: &mut Vec<usize>,
+            invocation: &pathfinder_executor::types::FunctionInvocation,
+        ) {
+            calls.push(FlatCall::Call {
+                invocation_type,
+                trace_address: trace_address.clone(),
+                invocation,
+            });
+            for (index, call) in invocation.internal_calls.iter().enumerate() {
+                trace_address.push(index);
+                push(calls, invocation_type, trace_address, call);
+                trace_address.pop();
+            }
+        }
+
+        if let Some(invocation) = invocation {
+            push(calls, invocation_type, &mut Vec::new(), invocation);
+        }
+    }
+}
+
+impl crate::dto::serialize::SerializeForVersion for FlatCall<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        match self {
+            FlatCall::Call {
+                invocation_type,
+                trace_address,
+                invocation,
+            } => {
+                serializer.serialize_field("type", invocation_type)?;
+                serializer.serialize_field("trace_address", trace_address)?;
+                serializer.serialize_field("subtraces", &invocation.internal_calls.len())?;
+                FunctionInvocation(invocation).serialize_fields(&mut serializer, false)?;
+            }
+            FlatCall::Reverted {
+                invocation_type,
+                revert_reason,
+            } => {
+                serializer.serialize_field("type", invocation_type)?;
+                serializer.serialize_field("trace_address", &[0usize; 0])?;
+                serializer.serialize_field("subtraces", &0usize)?;
+                serializer.serialize_field("revert_reason", revert_reason)?;
+            }
+        }
         serializer.end()
     }
 }
@@ -127,6 +299,19 @@ impl crate::dto::serialize::SerializeForVersion for FunctionInvocation<'_> {
         serializer: super::serialize::Serializer,
     ) -> Result<super::serialize::Ok, super::serialize::Error> {
         let mut serializer = serializer.serialize_struct()?;
+        self.serialize_fields(&mut serializer, true)?;
+        serializer.end()
+    }
+}
+
+impl FunctionInvocation<'_> {
+    /// Serializes the fields of the invocation, including its nested calls
+    /// only if `include_calls` is set.
+    fn serialize_fields(
+        &self,
+        serializer: &mut SerializeStruct,
+        include_calls: bool,
+    ) -> Result<(), super::serialize::Error> {
         serializer.serialize_field(
             "call_type",
             &match self.0.call_type {
@@ -135,11 +320,13 @@ impl crate::dto::serialize::SerializeForVersion for FunctionInvocation<'_> {
             },
         )?;
         serializer.serialize_field("caller_address", &crate::dto::Felt(&self.0.caller_address))?;
-        serializer.serialize_iter(
-            "calls",
-            self.0.internal_calls.len(),
-            &mut self.0.internal_calls.iter().map(FunctionInvocation),
-        )?;
+        if include_calls {
+            serializer.serialize_iter(
+                "calls",
+                self.0.internal_calls.len(),
+                &mut self.0.internal_calls.iter().map(FunctionInvocation),
+            )?;
+        }
         if let Some(class_hash) = &self.0.class_hash {
             serializer.serialize_field("class_hash", &crate::dto::Felt(class_hash))?;
         }
@@ -178,9 +365,8 @@ impl crate::dto::serialize::SerializeForVersion for FunctionInvocation<'_> {
         )?;
         serializer.serialize_field(
             "execution_resources",
-            &ComputationResources(&self.0.computation_resources),
-        )?;
-        serializer.end()
+            &CairoResources(&self.0.computation_resources),
+        )
     }
 }
 
@@ -227,9 +413,9 @@ impl crate::dto::serialize::SerializeForVersion for MsgToL1<'_> {
     }
 }
 
-struct ComputationResources<'a>(&'a pathfinder_executor::types::ComputationResources);
+pub struct CairoResources<'a>(pub &'a pathfinder_executor::types::ComputationResources);
 
-impl crate::dto::serialize::SerializeForVersion for ComputationResources<'_> {
+impl crate::dto::serialize::SerializeForVersion for CairoResources<'_> {
     fn serialize(
         &self,
         serializer: super::serialize::Serializer,
@@ -441,7 +627,7 @@ impl crate::dto::serialize::SerializeForVersion for ExecutionResources<'_> {
         serializer: super::serialize::Serializer,
     ) -> Result<super::serialize::Ok, super::serialize::Error> {
         let mut serializer = serializer.serialize_struct()?;
-        serializer.flatten(&ComputationResources(&self.0.computation_resources))?;
+        serializer.flatten(&CairoResources(&self.0.computation_resources))?;
         serializer.serialize_field(
             "data_availability",
             &DataAvailabilityResources(&self.0.data_availability),
@@ -487,3 +673,155 @@ impl crate::dto::serialize::SerializeForVersion for ExecuteInvocation<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::L2ToL1MessagePayloadElem;
+    use pathfinder_crypto::Felt;
+    use pathfinder_executor::types::{
+        CallType,
+        EntryPointType,
+        ExecuteInvocation,
+        ExecutionResources,
+        FunctionInvocation,
+        InvokeTransactionTrace,
+        MsgToL1,
+        StateDiff,
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::serialize::SerializeForVersion;
+
+    fn invocation(
+        contract_address: ContractAddress,
+        messages: Vec<MsgToL1>,
+        internal_calls: Vec<FunctionInvocation>,
+    ) -> FunctionInvocation {
+        FunctionInvocation {
+            calldata: vec![],
+            contract_address,
+            selector: Felt::ZERO,
+            call_type: CallType::Call,
+            caller_address: Felt::ZERO,
+            internal_calls,
+            class_hash: None,
+            entry_point_type: EntryPointType::External,
+            events: vec![],
+            messages,
+            result: vec![],
+            computation_resources: Default::default(),
+        }
+    }
+
+    fn message(order: usize, from_address: ContractAddress, payload: u64) -> MsgToL1 {
+        MsgToL1 {
+            order,
+            payload: vec![Felt::from_u64(payload)],
+            to_address: Felt::from_u64(0x1234),
+            from_address: from_address.0,
+        }
+    }
+
+    #[test]
+    fn l2_to_l1_messages_are_ordered() {
+        let outer = contract_address!("0x1");
+        let inner = contract_address!("0x2");
+        // The inner call sends its message in between the two messages of the
+        // outer call.
+        let execute_invocation = invocation(
+            outer,
+            vec![message(0, outer, 10), message(2, outer, 12)],
+            vec![invocation(inner, vec![message(1, inner, 11)], vec![])],
+        );
+        let trace = pathfinder_executor::types::TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: None,
+            execute_invocation: ExecuteInvocation::FunctionInvocation(Some(execute_invocation)),
+            fee_transfer_invocation: None,
+            state_diff: StateDiff::default(),
+            execution_resources: ExecutionResources::default(),
+        });
+
+        let messages = trace.l2_to_l1_messages();
+        let serialized = TransactionTrace {
+            trace: &trace,
+            include_state_diff: false,
+        }
+        .serialize(Default::default())
+        .unwrap();
+
+        let serialized = serialized["l2_to_l1_messages"].as_array().unwrap();
+        assert_eq!(serialized.len(), 3);
+        for (order, (serialized, message)) in serialized.iter().zip(&messages).enumerate() {
+            assert_eq!(serialized["order"], json!(order));
+            assert_eq!(
+                serialized["message_hash"],
+                crate::dto::H256Hex(message.calculate_hash())
+                    .serialize(Default::default())
+                    .unwrap()
+            );
+        }
+
+        let payloads = messages
+            .iter()
+            .map(|message| message.payload[0])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            payloads,
+            [10, 11, 12].map(|elem| L2ToL1MessagePayloadElem(Felt::from_u64(elem)))
+        );
+        assert_eq!(messages[1].from_address, inner);
+    }
+    #[test]
+    fn flat_trace_lists_calls_depth_first() {
+        let execute_invocation = invocation(
+            contract_address!("0x1"),
+            vec![],
+            vec![
+                invocation(
+                    contract_address!("0x2"),
+                    vec![],
+                    vec![invocation(contract_address!("0x3"), vec![], vec![])],
+                ),
+                invocation(contract_address!("0x4"), vec![], vec![]),
+            ],
+        );
+        let trace = pathfinder_executor::types::TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: Some(invocation(contract_address!("0x5"), vec![], vec![])),
+            execute_invocation: ExecuteInvocation::FunctionInvocation(Some(execute_invocation)),
+            fee_transfer_invocation: None,
+            state_diff: StateDiff::default(),
+            execution_resources: ExecutionResources::default(),
+        });
+
+        let serialized = FlatTransactionTrace(&trace)
+            .serialize(Default::default())
+            .unwrap();
+
+        let calls = serialized
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|call| {
+                (
+                    call["type"].as_str().unwrap(),
+                    call["trace_address"].clone(),
+                    call["subtraces"].as_u64().unwrap(),
+                    call["contract_address"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            vec![
+                ("VALIDATE", json!([]), 0, "0x5"),
+                ("EXECUTE", json!([]), 2, "0x1"),
+                ("EXECUTE", json!([0]), 1, "0x2"),
+                ("EXECUTE", json!([0, 0]), 0, "0x3"),
+                ("EXECUTE", json!([1]), 0, "0x4"),
+            ]
+        );
+        assert!(serialized[1].get("calls").is_none());
+    }
+}
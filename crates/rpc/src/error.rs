@@ -5,7 +5,7 @@
 //! be used by each JSON-RPC method to trivially create its subset of
 //! [ApplicationError] along with the boilerplate involved.
 #![macro_use]
-use pathfinder_common::TransactionHash;
+use pathfinder_common::{BlockNumber, TransactionHash};
 use serde_json::json;
 
 #[derive(serde::Serialize, Clone, Copy, Debug)]
@@ -93,6 +93,16 @@ pub enum ApplicationError {
     SubscriptionGatewayDown { subscription_id: u32 },
     #[error("Proof is missing")]
     ProofMissing,
+    #[error("Requested state has been pruned")]
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+    #[error("Node is overloaded, retry later")]
+    Overloaded { retry_after_seconds: u64 },
+    #[error("Too many failed transaction submissions, retry later")]
+    Throttled { retry_after_seconds: u64 },
+    #[error("Not supported by the Starknet version of the block")]
+    UnsupportedStarknetVersion(crate::version_gate::UnsupportedVersion),
     /// Internal errors are errors whose details we don't want to show to the
     /// end user. These are logged, and a simple "internal error" message is
     /// shown to the end user.
@@ -142,6 +152,10 @@ impl ApplicationError {
             // doc/rpc/pathfinder_rpc_api.json
             ApplicationError::ProofLimitExceeded { .. } => 10000,
             ApplicationError::ProofMissing => 10001,
+            ApplicationError::StatePruned { .. } => 10002,
+            ApplicationError::Overloaded { .. } => 10003,
+            ApplicationError::Throttled { .. } => 10005,
+            ApplicationError::UnsupportedStarknetVersion(_) => 10004,
             ApplicationError::SubscriptionTransactionHashNotFound { .. } => 10029,
             ApplicationError::SubscriptionGatewayDown { .. } => 10030,
             // https://www.jsonrpc.org/specification#error_object
@@ -217,6 +231,24 @@ impl ApplicationError {
                 "requested": requested,
             })),
             ApplicationError::ProofMissing => None,
+            ApplicationError::StatePruned {
+                earliest_available_block,
+            } => Some(json!({
+                "earliest_available_block": earliest_available_block,
+            })),
+            ApplicationError::Overloaded {
+                retry_after_seconds,
+            }
+            | ApplicationError::Throttled {
+                retry_after_seconds,
+            } => Some(json!({
+                "retry_after_seconds": retry_after_seconds,
+            })),
+            ApplicationError::UnsupportedStarknetVersion(unsupported) => Some(json!({
+                "feature": unsupported.feature,
+                "block_starknet_version": unsupported.block_version.to_string(),
+                "minimum_starknet_version": unsupported.minimum_version.to_string(),
+            })),
             ApplicationError::SubscriptionTransactionHashNotFound {
                 subscription_id,
                 transaction_hash,
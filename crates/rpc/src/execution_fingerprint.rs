@@ -0,0 +1,65 @@
+//! Identifies the execution semantics of the node.
+//!
+//! Results of calls, fee estimates, simulations and traces only change if the
+//! executor, the versioned constants or the compiler producing CASM change.
+//! When enabled, HTTP-RPC responses to these methods carry the fingerprint in
+//! the [HEADER] header, so that clients can key cached results by it and drop
+//! them once the node is upgraded.
+use http::HeaderValue;
+use sha2::Digest;
+
+pub const HEADER: &str = "pathfinder-execution-fingerprint";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionFingerprint(HeaderValue);
+
+impl ExecutionFingerprint {
+    /// `custom_versioned_constants` is the JSON of the versioned constants
+    /// configured for the latest Starknet version, if any.
+    pub fn new(custom_versioned_constants: Option<&[u8]>) -> Self {
+        let mut constants = sha2::Sha256::new();
+        for json in pathfinder_executor::BUNDLED_VERSIONED_CONSTANTS {
+            constants.update(sha2::Sha256::digest(json));
+        }
+        if let Some(json) = custom_versioned_constants {
+            constants.update(sha2::Sha256::digest(json));
+        }
+        let constants = constants
+            .finalize()
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        let fingerprint = format!(
+            "blockifier={}; constants={}; casm={}",
+            pathfinder_executor::BLOCKIFIER_VERSION,
+            constants,
+            pathfinder_compiler::latest_compiler_version(),
+        );
+        Self(HeaderValue::from_str(&fingerprint).expect("Fingerprint is a valid header value"))
+    }
+
+    pub fn header_value(&self) -> &HeaderValue {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_constants_change_fingerprint() {
+        let bundled = ExecutionFingerprint::new(None);
+        let custom = ExecutionFingerprint::new(Some(br#"{"invoke_tx_max_n_steps": 1}"#));
+
+        assert_ne!(bundled, custom);
+        assert_eq!(bundled, ExecutionFingerprint::new(None));
+        assert!(bundled
+            .header_value()
+            .to_str()
+            .unwrap()
+            .starts_with("blockifier=0.8.0-rc.3; constants="));
+    }
+}
@@ -4,6 +4,7 @@ use pathfinder_common::{ChainId, StarknetVersion};
 use pathfinder_executor::{ClassInfo, IntoStarkFelt};
 use starknet_api::core::PatriciaKey;
 
+use crate::casm_cache::CasmCache;
 use crate::v02::types::request::{
     BroadcastedDeployAccountTransaction,
     BroadcastedInvokeTransaction,
@@ -25,9 +26,26 @@ impl From<anyhow::Error> for ExecutionStateError {
 pub const VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY:
     StarknetVersion = StarknetVersion::new(0, 13, 1, 1);
 
+/// Compiles a declared Sierra class, through the CASM cache if there is one.
+fn compile_to_casm(
+    class: &SierraContractClass,
+    casm_cache: Option<&CasmCache>,
+) -> anyhow::Result<Vec<u8>> {
+    let definition = class
+        .serialize_to_json()
+        .context("Serializing Sierra class definition")?;
+
+    match casm_cache {
+        Some(casm_cache) => casm_cache.compile(definition),
+        None => pathfinder_compiler::compile_to_casm(&definition)
+            .context("Compiling Sierra class definition to CASM"),
+    }
+}
+
 pub(crate) fn map_broadcasted_transaction(
     transaction: &BroadcastedTransaction,
     chain_id: ChainId,
+    casm_cache: Option<&CasmCache>,
 ) -> anyhow::Result<pathfinder_executor::Transaction> {
     use crate::v02::types::request::BroadcastedDeclareTransaction;
 
@@ -55,12 +73,7 @@ pub(crate) fn map_broadcasted_transaction(
             Some(ClassInfo::new(&contract_class, 0, 0)?)
         }
         BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
-            let casm_contract_definition = pathfinder_compiler::compile_to_casm(
-                &tx.contract_class
-                    .serialize_to_json()
-                    .context("Serializing Sierra class definition")?,
-            )
-            .context("Compiling Sierra class definition to CASM")?;
+            let casm_contract_definition = compile_to_casm(&tx.contract_class, casm_cache)?;
 
             let casm_contract_definition =
                 pathfinder_executor::parse_casm_definition(casm_contract_definition)
@@ -72,12 +85,7 @@ pub(crate) fn map_broadcasted_transaction(
             )?)
         }
         BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
-            let casm_contract_definition = pathfinder_compiler::compile_to_casm(
-                &tx.contract_class
-                    .serialize_to_json()
-                    .context("Serializing Sierra class definition")?,
-            )
-            .context("Compiling Sierra class definition to CASM")?;
+            let casm_contract_definition = compile_to_casm(&tx.contract_class, casm_cache)?;
 
             let casm_contract_definition =
                 pathfinder_executor::parse_casm_definition(casm_contract_definition)
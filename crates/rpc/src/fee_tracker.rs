@@ -0,0 +1,344 @@
+//! Compares the fee estimates of transactions submitted through this node
+//! with the fees actually charged in their receipts.
+//!
+//! Estimates are matched to submitted transactions by sender address and
+//! nonce, since the fee bounds of a transaction, and therefore its hash, are
+//! usually only decided after the estimate. The receipts of submitted
+//! transactions are looked up as blocks are committed.
+//!
+//! The ratios of actual to estimated fee are exposed by
+//! `pathfinder_getFeeEstimateStats` and the `fee_estimate_ratio` metric, so
+//! that the margin added to estimates can be based on data.
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use cached::{Cached, SizedCache};
+use pathfinder_common::{BlockHeader, ContractAddress, TransactionHash, TransactionNonce};
+use pathfinder_executor::types::FeeEstimate;
+use pathfinder_storage::Storage;
+use primitive_types::U256;
+use tokio::sync::broadcast;
+
+use crate::jsonrpc::ChainEvent;
+use crate::v02::types::request::{
+    BroadcastedDeclareTransaction,
+    BroadcastedDeployAccountTransaction,
+    BroadcastedInvokeTransaction,
+    BroadcastedTransaction,
+};
+
+/// Number of estimates and submitted transactions kept while waiting for a
+/// match.
+const TRACKED_ENTRIES: usize = 10_000;
+
+/// Number of most recently resolved transactions the ratio statistics are
+/// computed over.
+const RATIO_WINDOW: NonZeroUsize = match NonZeroUsize::new(1000) {
+    Some(window) => window,
+    None => unreachable!(),
+};
+
+/// Identifies a transaction independently of its fee bounds.
+pub(crate) type TrackingKey = (ContractAddress, TransactionNonce);
+
+#[derive(Clone)]
+pub struct FeeTracker(Arc<Mutex<Inner>>);
+
+struct Inner {
+    estimates: SizedCache<TrackingKey, U256>,
+    submitted: SizedCache<TransactionHash, U256>,
+    stats: Stats,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    resolved: u64,
+    underestimated: u64,
+    /// Ratios of actual to estimated fee of the most recently resolved
+    /// transactions.
+    ratios: VecDeque<f64>,
+    window: usize,
+}
+
+/// Calibration statistics of the fee estimates of submitted transactions.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct FeeEstimateStats {
+    /// Submitted transactions which have not been included in a block yet.
+    pub pending: u64,
+    /// Submitted transactions whose receipt has been compared to the estimate.
+    pub resolved: u64,
+    /// Resolved transactions which were charged more than estimated.
+    pub underestimated: u64,
+    /// Distribution of the ratio of actual to estimated fee over the most
+    /// recently resolved transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<RatioStats>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct RatioStats {
+    pub samples: usize,
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl FeeTracker {
+    /// Creates the tracker and spawns a task which matches the receipts of
+    /// blocks committed on `chain_events` against submitted transactions.
+    pub fn new(storage: Storage, chain_events: broadcast::Receiver<ChainEvent>) -> Self {
+        let tracker = Self::with_window(RATIO_WINDOW);
+        tokio::spawn(tracker.clone().resolve_on_commit(storage, chain_events));
+        tracker
+    }
+
+    fn with_window(window: NonZeroUsize) -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            estimates: SizedCache::with_size(TRACKED_ENTRIES),
+            submitted: SizedCache::with_size(TRACKED_ENTRIES),
+            stats: Stats {
+                window: window.get(),
+                ..Default::default()
+            },
+        })))
+    }
+
+    /// Records the estimate of a transaction, replacing any earlier estimate
+    /// for the same sender and nonce.
+    pub(crate) fn estimated(&self, key: TrackingKey, estimate: &FeeEstimate) {
+        self.0
+            .lock()
+            .unwrap()
+            .estimates
+            .cache_set(key, estimate.overall_fee);
+    }
+
+    /// Records that a transaction was submitted. Transactions which were not
+    /// estimated through this node are ignored.
+    pub(crate) fn submitted(&self, key: TrackingKey, hash: TransactionHash) {
+        let mut inner = self.0.lock().unwrap();
+        if let Some(estimate) = inner.estimates.cache_remove(&key) {
+            inner.submitted.cache_set(hash, estimate);
+        }
+    }
+
+    /// Compares the actual fee of `hash` with its estimate, if the transaction
+    /// was submitted through this node.
+    fn resolve(&self, hash: TransactionHash, actual_fee: U256) {
+        let mut inner = self.0.lock().unwrap();
+        let Some(estimate) = inner.submitted.cache_remove(&hash) else {
+            return;
+        };
+        // A zero estimate can't be scaled by a safety margin so it carries no
+        // information.
+        if estimate.is_zero() {
+            return;
+        }
+
+        let ratio = actual_fee.low_u128() as f64 / estimate.low_u128() as f64;
+        metrics::histogram!("fee_estimate_ratio", ratio);
+
+        let stats = &mut inner.stats;
+        stats.resolved += 1;
+        if actual_fee > estimate {
+            stats.underestimated += 1;
+        }
+        if stats.ratios.len() == stats.window {
+            stats.ratios.pop_front();
+        }
+        stats.ratios.push_back(ratio);
+    }
+
+    pub(crate) fn stats(&self) -> FeeEstimateStats {
+        let inner = self.0.lock().unwrap();
+
+        let ratio = (!inner.stats.ratios.is_empty()).then(|| {
+            let mut ratios = Vec::from(inner.stats.ratios.clone());
+            ratios.sort_by(f64::total_cmp);
+            let percentile = |p: usize| ratios[(ratios.len() - 1) * p / 100];
+
+            RatioStats {
+                samples: ratios.len(),
+                min: ratios[0],
+                mean: ratios.iter().sum::<f64>() / ratios.len() as f64,
+                p50: percentile(50),
+                p90: percentile(90),
+                p99: percentile(99),
+                max: ratios[ratios.len() - 1],
+            }
+        });
+
+        FeeEstimateStats {
+            pending: inner.submitted.cache_size() as u64,
+            resolved: inner.stats.resolved,
+            underestimated: inner.stats.underestimated,
+            ratio,
+        }
+    }
+
+    async fn resolve_on_commit(
+        self,
+        storage: Storage,
+        mut chain_events: broadcast::Receiver<ChainEvent>,
+    ) {
+        loop {
+            match chain_events.recv().await {
+                Ok(ChainEvent::Commit(header)) => {
+                    if self.0.lock().unwrap().submitted.cache_size() == 0 {
+                        continue;
+                    }
+                    let tracker = self.clone();
+                    let storage = storage.clone();
+                    let result = tokio::task::spawn_blocking(move || {
+                        tracker.resolve_block(&storage, &header)
+                    })
+                    .await;
+                    match result {
+                        Ok(Ok(())) => {}
+                        Ok(Err(error)) => {
+                            tracing::warn!(%error, "Matching receipts against fee estimates failed")
+                        }
+                        Err(error) => tracing::warn!(%error, "Fee tracker task failed"),
+                    }
+                }
+                Ok(ChainEvent::Reorg(_) | ChainEvent::PendingUpdate(_)) => {}
+                // Transactions in the missed blocks stay pending until they are evicted.
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn resolve_block(&self, storage: &Storage, header: &BlockHeader) -> anyhow::Result<()> {
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let Some(transactions) = db
+            .transactions_with_receipts_for_block(header.number.into())
+            .context("Querying block receipts")?
+        else {
+            return Ok(());
+        };
+
+        for (_, receipt) in transactions {
+            let actual_fee = U256::from_big_endian(receipt.actual_fee.0.as_be_bytes());
+            self.resolve(receipt.transaction_hash, actual_fee);
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn tracking_key(tx: &BroadcastedTransaction) -> Option<TrackingKey> {
+    match tx {
+        BroadcastedTransaction::Declare(tx) => declare_tracking_key(tx),
+        BroadcastedTransaction::Invoke(tx) => invoke_tracking_key(tx),
+        BroadcastedTransaction::DeployAccount(tx) => Some(deploy_account_tracking_key(tx)),
+    }
+}
+
+/// Declare v0 transactions have no nonce and are therefore not tracked.
+pub(crate) fn declare_tracking_key(tx: &BroadcastedDeclareTransaction) -> Option<TrackingKey> {
+    match tx {
+        BroadcastedDeclareTransaction::V0(_) => None,
+        BroadcastedDeclareTransaction::V1(tx) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedDeclareTransaction::V2(tx) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedDeclareTransaction::V3(tx) => Some((tx.sender_address, tx.nonce)),
+    }
+}
+
+/// Invoke v0 transactions have no nonce and are therefore not tracked.
+pub(crate) fn invoke_tracking_key(tx: &BroadcastedInvokeTransaction) -> Option<TrackingKey> {
+    match tx {
+        BroadcastedInvokeTransaction::V0(_) => None,
+        BroadcastedInvokeTransaction::V1(tx) => Some((tx.sender_address, tx.nonce)),
+        BroadcastedInvokeTransaction::V3(tx) => Some((tx.sender_address, tx.nonce)),
+    }
+}
+
+pub(crate) fn deploy_account_tracking_key(tx: &BroadcastedDeployAccountTransaction) -> TrackingKey {
+    let nonce = match tx {
+        BroadcastedDeployAccountTransaction::V1(tx) => tx.nonce,
+        BroadcastedDeployAccountTransaction::V3(tx) => tx.nonce,
+    };
+    (tx.deployed_contract_address(), nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_executor::types::PriceUnit;
+
+    use super::*;
+
+    fn estimate(overall_fee: u64) -> FeeEstimate {
+        FeeEstimate {
+            gas_consumed: Default::default(),
+            gas_price: Default::default(),
+            data_gas_consumed: Default::default(),
+            data_gas_price: Default::default(),
+            overall_fee: overall_fee.into(),
+            unit: PriceUnit::Fri,
+        }
+    }
+
+    #[test]
+    fn estimates_are_matched_to_receipts() {
+        let tracker = FeeTracker::with_window(NonZeroUsize::new(2).unwrap());
+        let sender = contract_address_bytes!(b"sender");
+
+        // Not estimated through this node.
+        tracker.submitted(
+            (sender, transaction_nonce!("0x0")),
+            transaction_hash_bytes!(b"0"),
+        );
+
+        for (i, (estimated, actual)) in [(100, 90), (100, 120), (100, 100)].into_iter().enumerate()
+        {
+            let nonce = TransactionNonce(pathfinder_crypto::Felt::from_u64(i as u64 + 1));
+            let hash = TransactionHash(pathfinder_crypto::Felt::from_u64(i as u64 + 1));
+            tracker.estimated((sender, nonce), &estimate(estimated));
+            tracker.submitted((sender, nonce), hash);
+            tracker.resolve(hash, actual.into());
+        }
+        tracker.resolve(transaction_hash_bytes!(b"0"), 1.into());
+
+        let stats = tracker.stats();
+        assert_eq!(stats.pending, 0);
+        assert_eq!(stats.resolved, 3);
+        assert_eq!(stats.underestimated, 1);
+        // Only the last two ratios are kept.
+        let ratio = stats.ratio.unwrap();
+        assert_eq!(ratio.samples, 2);
+        assert_eq!(ratio.min, 1.0);
+        assert_eq!(ratio.max, 1.2);
+        assert!((ratio.mean - 1.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_samples() {
+        let tracker = FeeTracker::with_window(NonZeroUsize::new(10).unwrap());
+        let key = (
+            contract_address_bytes!(b"sender"),
+            transaction_nonce!("0x1"),
+        );
+        tracker.estimated(key, &estimate(100));
+        tracker.submitted(key, transaction_hash_bytes!(b"hash"));
+
+        let stats = tracker.stats();
+        assert_eq!(
+            stats,
+            FeeEstimateStats {
+                pending: 1,
+                ..Default::default()
+            }
+        );
+    }
+}
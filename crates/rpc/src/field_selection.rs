@@ -0,0 +1,123 @@
+//! Partial responses for consumers which only need a subset of a result.
+//!
+//! Requests may carry a `fields` member next to `params`, listing the paths of
+//! the result fields to return, e.g. `["transaction_trace.state_diff"]` for
+//! `starknet_simulateTransactions`. Path segments are separated by dots and
+//! arrays are traversed transparently, so that a path applies to every
+//! element. The method still runs in full, but the response only contains the
+//! selected fields.
+use serde_json::{Map, Value};
+
+use crate::jsonrpc::RpcError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FieldSelection(Vec<Vec<String>>);
+
+impl FieldSelection {
+    pub fn parse(fields: &[String]) -> Result<Self, RpcError> {
+        let paths = fields
+            .iter()
+            .map(|field| {
+                let path = field.split('.').map(str::to_owned).collect::<Vec<_>>();
+                if path.iter().any(String::is_empty) {
+                    return Err(RpcError::InvalidParams(format!(
+                        "Invalid field selector {field:?}"
+                    )));
+                }
+                Ok(path)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self(paths))
+    }
+
+    /// Drops the fields of `value` which are not selected.
+    pub fn apply(&self, value: Value) -> Value {
+        let paths = self.0.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        select(value, &paths)
+    }
+}
+
+fn select(value: Value, paths: &[&[String]]) -> Value {
+    match value {
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(|item| select(item, paths)).collect())
+        }
+        // An empty path selects the whole value.
+        Value::Object(mut object) if !paths.iter().any(|path| path.is_empty()) => {
+            let mut selected = Map::new();
+            for (key, _) in paths.iter().filter_map(|path| path.split_first()) {
+                let Some(field) = object.remove(key) else {
+                    continue;
+                };
+                let rest = paths
+                    .iter()
+                    .filter_map(|path| path.split_first())
+                    .filter(|(first, _)| *first == key)
+                    .map(|(_, rest)| rest)
+                    .collect::<Vec<_>>();
+                selected.insert(key.clone(), select(field, &rest));
+            }
+            Value::Object(selected)
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn selection(fields: &[&str]) -> FieldSelection {
+        let fields = fields
+            .iter()
+            .map(|field| field.to_string())
+            .collect::<Vec<_>>();
+        FieldSelection::parse(&fields).unwrap()
+    }
+
+    #[test]
+    fn selects_nested_fields_of_each_element() {
+        let simulations = json!([
+            {
+                "fee_estimation": { "overall_fee": "0x1" },
+                "transaction_trace": {
+                    "type": "INVOKE",
+                    "execute_invocation": { "events": [] },
+                    "state_diff": { "nonces": [] }
+                }
+            },
+            {
+                "fee_estimation": { "overall_fee": "0x2" },
+                "transaction_trace": { "type": "DECLARE" }
+            }
+        ]);
+
+        let selected = selection(&["transaction_trace.state_diff", "transaction_trace.type"])
+            .apply(simulations);
+
+        assert_eq!(
+            selected,
+            json!([
+                { "transaction_trace": { "type": "INVOKE", "state_diff": { "nonces": [] } } },
+                { "transaction_trace": { "type": "DECLARE" } }
+            ])
+        );
+    }
+
+    #[test]
+    fn whole_field_wins_over_nested_selection() {
+        let value = json!({ "a": { "b": 1, "c": 2 }, "d": 3 });
+
+        let selected = selection(&["a.b", "a"]).apply(value);
+
+        assert_eq!(selected, json!({ "a": { "b": 1, "c": 2 } }));
+    }
+
+    #[test]
+    fn empty_segment_is_rejected() {
+        FieldSelection::parse(&["a..b".to_owned()]).unwrap_err();
+    }
+}
@@ -0,0 +1,396 @@
+//! Forking mode, in which calls and simulations execute against the state of a
+//! remote node at a fixed block instead of the local state.
+//!
+//! [RpcRemoteState] reads that state from the JSON-RPC API of the remote node
+//! for a [pathfinder_executor::Fork], which caches it.
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::{
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    BlockTimestamp,
+    ChainId,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    GasPrice,
+    L1DataAvailabilityMode,
+    SequencerAddress,
+    StateCommitment,
+    StorageAddress,
+    StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_executor::{RemoteClass, RemoteState};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::v02::types::ContractClass;
+
+/// The JSON-RPC error code for `CONTRACT_NOT_FOUND`.
+const CONTRACT_NOT_FOUND: i64 = 20;
+/// The JSON-RPC error code for `BLOCK_NOT_FOUND`.
+const BLOCK_NOT_FOUND: i64 = 24;
+/// The JSON-RPC error code for `CLASS_HASH_NOT_FOUND`.
+const CLASS_HASH_NOT_FOUND: i64 = 28;
+
+/// The state of a remote node at a block, read using its JSON-RPC API.
+#[derive(Clone, Debug)]
+pub struct RpcRemoteState {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    block_number: BlockNumber,
+    runtime: tokio::runtime::Handle,
+}
+
+impl RpcRemoteState {
+    /// `url` is the versioned JSON-RPC endpoint of the remote node, e.g.
+    /// `https://node.example/rpc/v0_7`.
+    ///
+    /// Must be called from within a tokio runtime, which is then used to read
+    /// the state on behalf of the executor.
+    pub fn new(
+        url: reqwest::Url,
+        block_number: BlockNumber,
+        timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Creating HTTP client")?;
+
+        Ok(Self {
+            client,
+            url,
+            block_number,
+            runtime: tokio::runtime::Handle::current(),
+        })
+    }
+
+    pub async fn chain_id(&self) -> anyhow::Result<ChainId> {
+        let chain_id: Felt = self
+            .request("starknet_chainId", json!([]), None)
+            .await?
+            .context("Missing chain id")?;
+        Ok(ChainId(chain_id))
+    }
+
+    /// The header of the block the state is read at.
+    ///
+    /// Commitments which are not part of the JSON-RPC API, and are not needed
+    /// for execution, are left at their defaults.
+    pub async fn header(&self) -> anyhow::Result<BlockHeader> {
+        #[derive(serde::Deserialize)]
+        struct Block {
+            block_hash: BlockHash,
+            parent_hash: BlockHash,
+            block_number: BlockNumber,
+            new_root: StateCommitment,
+            timestamp: BlockTimestamp,
+            sequencer_address: SequencerAddress,
+            l1_gas_price: ResourcePrice,
+            #[serde(default)]
+            l1_data_gas_price: Option<ResourcePrice>,
+            #[serde(default)]
+            l1_da_mode: L1DataAvailabilityMode,
+            starknet_version: String,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ResourcePrice {
+            price_in_fri: Felt,
+            price_in_wei: Felt,
+        }
+
+        let block: Block = self
+            .request(
+                "starknet_getBlockWithTxHashes",
+                json!({ "block_id": self.block_id() }),
+                Some(BLOCK_NOT_FOUND),
+            )
+            .await?
+            .with_context(|| format!("Block {} not found", self.block_number))?;
+        anyhow::ensure!(
+            block.block_number == self.block_number,
+            "Received block {} instead of {}",
+            block.block_number,
+            self.block_number
+        );
+
+        let data_gas_price = block.l1_data_gas_price.unwrap_or(ResourcePrice {
+            price_in_fri: Felt::ZERO,
+            price_in_wei: Felt::ZERO,
+        });
+        Ok(BlockHeader {
+            hash: block.block_hash,
+            parent_hash: block.parent_hash,
+            number: block.block_number,
+            timestamp: block.timestamp,
+            eth_l1_gas_price: GasPrice::try_from(block.l1_gas_price.price_in_wei)?,
+            strk_l1_gas_price: GasPrice::try_from(block.l1_gas_price.price_in_fri)?,
+            eth_l1_data_gas_price: GasPrice::try_from(data_gas_price.price_in_wei)?,
+            strk_l1_data_gas_price: GasPrice::try_from(data_gas_price.price_in_fri)?,
+            sequencer_address: block.sequencer_address,
+            starknet_version: block
+                .starknet_version
+                .parse()
+                .context("Parsing Starknet version")?,
+            state_commitment: block.new_root,
+            l1_da_mode: block.l1_da_mode,
+            ..Default::default()
+        })
+    }
+
+    fn block_id(&self) -> Value {
+        json!({ "block_number": self.block_number })
+    }
+
+    /// Sends a request to the remote node. Returns [None] if it fails with
+    /// the `not_found` error code.
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+        not_found: Option<i64>,
+    ) -> anyhow::Result<Option<T>> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: Option<Value>,
+            error: Option<Error>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Error {
+            code: i64,
+            message: String,
+        }
+
+        let response: Response = self
+            .client
+            .post(self.url.clone())
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .context("Sending request")?
+            .error_for_status()
+            .context("Remote node status")?
+            .json()
+            .await
+            .context("Parsing response")?;
+
+        match (response.result, response.error) {
+            (_, Some(error)) if Some(error.code) == not_found => Ok(None),
+            (_, Some(error)) => Err(anyhow::anyhow!(
+                "Remote node returned error {}: {}",
+                error.code,
+                error.message
+            )),
+            (Some(result), None) => serde_json::from_value(result)
+                .map(Some)
+                .with_context(|| format!("Parsing {method} result")),
+            (None, None) => Err(anyhow::anyhow!("Remote node response has no result")),
+        }
+    }
+
+    /// Sends a request on behalf of the executor, which runs on a blocking
+    /// thread.
+    fn blocking_request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+        not_found: Option<i64>,
+    ) -> anyhow::Result<Option<T>> {
+        self.runtime
+            .block_on(self.request(method, params, not_found))
+            .with_context(|| format!("Reading {method} from the remote node"))
+    }
+}
+
+impl RemoteState for RpcRemoteState {
+    fn storage_value(
+        &self,
+        contract_address: ContractAddress,
+        storage_address: StorageAddress,
+    ) -> anyhow::Result<StorageValue> {
+        let value = self.blocking_request(
+            "starknet_getStorageAt",
+            json!({
+                "contract_address": contract_address,
+                "key": storage_address,
+                "block_id": self.block_id(),
+            }),
+            Some(CONTRACT_NOT_FOUND),
+        )?;
+        Ok(value.unwrap_or_default())
+    }
+
+    fn nonce(&self, contract_address: ContractAddress) -> anyhow::Result<ContractNonce> {
+        let nonce = self.blocking_request(
+            "starknet_getNonce",
+            json!({
+                "block_id": self.block_id(),
+                "contract_address": contract_address,
+            }),
+            Some(CONTRACT_NOT_FOUND),
+        )?;
+        Ok(nonce.unwrap_or_default())
+    }
+
+    fn class_hash(&self, contract_address: ContractAddress) -> anyhow::Result<Option<ClassHash>> {
+        self.blocking_request(
+            "starknet_getClassHashAt",
+            json!({
+                "block_id": self.block_id(),
+                "contract_address": contract_address,
+            }),
+            Some(CONTRACT_NOT_FOUND),
+        )
+    }
+
+    fn class(&self, class_hash: ClassHash) -> anyhow::Result<Option<RemoteClass>> {
+        let class: Option<ContractClass> = self.blocking_request(
+            "starknet_getClass",
+            json!({
+                "block_id": self.block_id(),
+                "class_hash": class_hash,
+            }),
+            Some(CLASS_HASH_NOT_FOUND),
+        )?;
+
+        let class = match class {
+            None => return Ok(None),
+            Some(ContractClass::Cairo(class)) => RemoteClass::Cairo(
+                class
+                    .serialize_to_json()
+                    .context("Serializing Cairo class to JSON")?,
+            ),
+            Some(ContractClass::Sierra(class)) => {
+                let casm_definition = pathfinder_compiler::compile_to_casm(
+                    &class
+                        .serialize_to_json()
+                        .context("Serializing Sierra class definition")?,
+                )
+                .context("Compiling Sierra class definition to CASM")?;
+                let casm_hash = pathfinder_compiler::casm_class_hash(&casm_definition)
+                    .context("Computing CASM class hash")?;
+                RemoteClass::Sierra {
+                    casm_definition,
+                    casm_hash,
+                }
+            }
+        };
+        Ok(Some(class))
+    }
+
+    fn block_hash(&self, block_number: BlockNumber) -> anyhow::Result<Option<BlockHash>> {
+        #[derive(serde::Deserialize)]
+        struct Block {
+            block_hash: BlockHash,
+        }
+
+        let block: Option<Block> = self.blocking_request(
+            "starknet_getBlockWithTxHashes",
+            json!({ "block_id": { "block_number": block_number } }),
+            Some(BLOCK_NOT_FOUND),
+        )?;
+        Ok(block.map(|block| block.block_hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::post;
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    /// Answers requests using `respond`, which is passed the method and
+    /// parameters of the request.
+    async fn serve(respond: fn(&str, &Value) -> Value) -> reqwest::Url {
+        let router = axum::Router::new().route(
+            "/rpc/v0_7",
+            post(move |axum::Json(request): axum::Json<Value>| async move {
+                let result = respond(request["method"].as_str().unwrap(), &request["params"]);
+                axum::Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router.into_make_service()).await });
+        format!("http://{addr}/rpc/v0_7").parse().unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn state_is_read_at_the_fork_block() {
+        let url = serve(|method, params| {
+            assert_eq!(params["block_id"], json!({ "block_number": 5 }));
+            match method {
+                "starknet_getStorageAt" => json!("0x123"),
+                "starknet_getClassHashAt" => json!("0xabc"),
+                _ => unreachable!(),
+            }
+        })
+        .await;
+        let state =
+            RpcRemoteState::new(url, BlockNumber::new_or_panic(5), Duration::from_secs(5)).unwrap();
+
+        let (value, class_hash) = tokio::task::spawn_blocking(move || {
+            (
+                state
+                    .storage_value(contract_address!("0x1"), storage_address!("0x2"))
+                    .unwrap(),
+                state.class_hash(contract_address!("0x1")).unwrap(),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(value, storage_value!("0x123"));
+        assert_eq!(class_hash, Some(class_hash!("0xabc")));
+    }
+
+    #[tokio::test]
+    async fn not_found_errors_are_none() {
+        let router = axum::Router::new().route(
+            "/",
+            post(|| async {
+                axum::Json(json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "error": { "code": CONTRACT_NOT_FOUND, "message": "Contract not found" }
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router.into_make_service()).await });
+        let state = RpcRemoteState::new(
+            format!("http://{addr}/").parse().unwrap(),
+            BlockNumber::GENESIS,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let class_hash: Option<ClassHash> = state
+            .request(
+                "starknet_getClassHashAt",
+                json!({}),
+                Some(CONTRACT_NOT_FOUND),
+            )
+            .await
+            .unwrap();
+        assert_eq!(class_hash, None);
+
+        state
+            .request::<ClassHash>("starknet_getClassHashAt", json!({}), Some(BLOCK_NOT_FOUND))
+            .await
+            .unwrap_err();
+    }
+}
@@ -9,7 +9,7 @@ use std::sync::Arc;
 pub use error::RpcError;
 use pathfinder_common::{BlockHash, BlockNumber};
 pub use request::RpcRequest;
-pub use response::RpcResponse;
+pub use response::{set_shared_response_size_limit, RpcOutput, RpcResponse};
 #[cfg(test)]
 pub use router::handle_json_rpc_socket;
 pub use router::{
@@ -21,6 +21,8 @@ pub use router::{
 };
 use tokio::sync::broadcast;
 
+use crate::dropped_transactions::DroppedTransactions;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum RequestId {
     Number(i64),
@@ -37,13 +39,32 @@ impl RequestId {
 
 /// Channels used to notify the RPC of new events. Used by the RPC subscription
 /// system.
+///
+/// Events should be published using the `publish_*` methods.
 #[derive(Debug, Clone)]
 pub struct Notifications {
-    pub block_headers: broadcast::Sender<Arc<pathfinder_common::BlockHeader>>,
-    pub reorgs: broadcast::Sender<Arc<Reorg>>,
+    /// The number of the latest block accepted on L1, whenever it is matched
+    /// with a block on L2.
+    pub l1_accepted: broadcast::Sender<BlockNumber>,
+    /// All chain events in the order they occurred. Caches should subscribe
+    /// to this instead of polling the database for changes to the chain head.
+    pub chain_events: broadcast::Sender<ChainEvent>,
+    /// Transactions which were removed from the pending block.
+    pub dropped_transactions: DroppedTransactions,
 }
 
+/// A change to the chain as seen by this node.
 #[derive(Debug, Clone)]
+pub enum ChainEvent {
+    /// A new block was committed to the database.
+    Commit(Arc<pathfinder_common::BlockHeader>),
+    /// Blocks were removed from the database.
+    Reorg(Arc<Reorg>),
+    /// The pending block was replaced. Holds the new pending block's number.
+    PendingUpdate(BlockNumber),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Reorg {
     pub first_block_number: BlockNumber,
     pub first_block_hash: BlockHash,
@@ -53,11 +74,34 @@ pub struct Reorg {
 
 impl Default for Notifications {
     fn default() -> Self {
-        let (block_headers, _) = broadcast::channel(1024);
-        let (reorgs, _) = broadcast::channel(1024);
+        let (l1_accepted, _) = broadcast::channel(1024);
+        let (chain_events, _) = broadcast::channel(1024);
         Self {
-            block_headers,
-            reorgs,
+            l1_accepted,
+            chain_events,
+            dropped_transactions: Default::default(),
         }
     }
 }
+
+impl Notifications {
+    pub fn publish_commit(&self, header: Arc<pathfinder_common::BlockHeader>) {
+        // Ignore errors in case nobody is listening. New listeners may subscribe in the
+        // future.
+        self.chain_events.send(ChainEvent::Commit(header)).ok();
+    }
+
+    pub fn publish_reorg(&self, reorg: Arc<Reorg>) {
+        self.chain_events.send(ChainEvent::Reorg(reorg)).ok();
+    }
+
+    pub fn publish_l1_accepted(&self, number: BlockNumber) {
+        self.l1_accepted.send(number).ok();
+    }
+
+    pub fn publish_pending_update(&self, number: BlockNumber) {
+        self.chain_events
+            .send(ChainEvent::PendingUpdate(number))
+            .ok();
+    }
+}
@@ -12,6 +12,8 @@ pub struct RpcRequest<'a> {
     pub method: Cow<'a, str>,
     pub params: RawParams<'a>,
     pub id: RequestId,
+    /// See [crate::field_selection].
+    pub fields: Option<Vec<String>>,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -96,6 +98,8 @@ impl<'de> Deserialize<'de> for RpcRequest<'de> {
             method: Cow<'a, str>,
             #[serde(default, borrow)]
             params: RawParams<'a>,
+            #[serde(default)]
+            fields: Option<Vec<String>>,
         }
 
         // Any value that is present is considered Some value, including null.
@@ -124,6 +128,7 @@ impl<'de> Deserialize<'de> for RpcRequest<'de> {
             id,
             method: helper.method,
             params: helper.params,
+            fields: helper.fields,
         })
     }
 }
@@ -178,6 +183,7 @@ mod tests {
             method: "sum".into(),
             params: RawParams(Some(&params)),
             id: expected,
+            fields: None,
         };
 
         assert_eq!(request, expected);
@@ -220,6 +226,7 @@ mod tests {
             method: "sum".into(),
             params: RawParams(None),
             id: RequestId::Number(456),
+            fields: None,
         };
         assert_eq!(result, expected);
     }
@@ -1,6 +1,14 @@
-use axum::response::IntoResponse;
+use std::cell::RefCell;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use axum::body::Body;
+use axum::response::{IntoResponse, Response};
+use bytes::{BufMut, Bytes, BytesMut};
 use serde::Serialize;
+use serde_json::value::RawValue;
 use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
 
 use crate::error::ApplicationError;
 use crate::jsonrpc::error::RpcError;
@@ -49,7 +57,63 @@ impl RpcResponse {
     }
 }
 
-pub type RpcResult = Result<Value, RpcError>;
+pub type RpcResult = Result<RpcOutput, RpcError>;
+
+/// The result of a successful request.
+///
+/// Method outputs are serialized straight to JSON text, and are only parsed
+/// into a [Value] if it has to be inspected.
+#[derive(Debug, Clone)]
+pub enum RpcOutput {
+    Value(Value),
+    Raw(Box<RawValue>),
+    /// A result served by the upstream node, see [crate::upstream].
+    Upstream(Value),
+}
+
+impl RpcOutput {
+    pub fn into_value(self) -> serde_json::Result<Value> {
+        match self {
+            Self::Value(value) | Self::Upstream(value) => Ok(value),
+            Self::Raw(raw) => serde_json::from_str(raw.get()),
+        }
+    }
+
+    /// Marks the output as served by the upstream node.
+    pub fn into_upstream(self) -> serde_json::Result<Self> {
+        self.into_value().map(Self::Upstream)
+    }
+}
+
+impl From<Value> for RpcOutput {
+    fn from(value: Value) -> Self {
+        Self::Value(value)
+    }
+}
+
+/// Outputs are equal if they hold the same JSON value served by the same node.
+impl PartialEq for RpcOutput {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Value(a), Self::Value(b)) | (Self::Upstream(a), Self::Upstream(b)) => a == b,
+            (Self::Upstream(_), _) | (_, Self::Upstream(_)) => false,
+            (Self::Raw(a), Self::Raw(b)) if a.get() == b.get() => true,
+            _ => self.clone().into_value().ok() == other.clone().into_value().ok(),
+        }
+    }
+}
+
+impl Serialize for RpcOutput {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Value(value) | Self::Upstream(value) => value.serialize(serializer),
+            Self::Raw(raw) => raw.serialize(serializer),
+        }
+    }
+}
 
 impl Serialize for RpcResponse {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -66,6 +130,10 @@ impl Serialize for RpcResponse {
             Err(e) => obj.serialize_entry("error", &e)?,
         };
 
+        if let Ok(RpcOutput::Upstream(_)) = &self.output {
+            obj.serialize_entry("provenance", "upstream")?;
+        }
+
         match &self.id {
             RequestId::Number(x) => obj.serialize_entry("id", &x)?,
             RequestId::String(x) => obj.serialize_entry("id", &x)?,
@@ -77,9 +145,9 @@ impl Serialize for RpcResponse {
     }
 }
 
-impl IntoResponse for RpcResponse {
-    fn into_response(self) -> axum::response::Response {
-        // Log internal errors.
+impl RpcResponse {
+    /// Logs internal errors.
+    pub(crate) fn log_error(&self) {
         match &self.output {
             Err(RpcError::InternalError(e))
             | Err(RpcError::ApplicationError(ApplicationError::Internal(e))) => {
@@ -90,8 +158,154 @@ impl IntoResponse for RpcResponse {
             }
             _ => {}
         }
+    }
+}
+
+impl IntoResponse for RpcResponse {
+    fn into_response(self) -> axum::response::Response {
+        self.log_error();
+        to_json_bytes(&self).into_response()
+    }
+}
+
+thread_local! {
+    /// Responses are serialized into this buffer and split off it, so that its
+    /// allocation is reused once earlier responses have been sent.
+    static OUTPUT_BUFFER: RefCell<BytesMut> = RefCell::new(BytesMut::new());
+}
+
+/// Responses larger than this get an allocation of their own instead of
+/// keeping a large shared buffer alive.
+const MAX_SHARED_RESPONSE_SIZE: usize = 1024 * 1024;
+
+static SHARED_RESPONSE_SIZE_LIMIT: AtomicUsize = AtomicUsize::new(MAX_SHARED_RESPONSE_SIZE);
+
+/// Sets the size of the largest response whose buffer is kept for reuse by
+/// the thread which serialized it. Zero disables reusing buffers.
+pub fn set_shared_response_size_limit(size: usize) {
+    SHARED_RESPONSE_SIZE_LIMIT.store(size, Ordering::Relaxed);
+}
+
+/// Serializes `value` as JSON using the shared output buffer of this thread.
+pub(crate) fn to_json_bytes(value: &impl Serialize) -> Bytes {
+    OUTPUT_BUFFER.with_borrow_mut(|buffer| {
+        serde_json::to_writer(BufMut::writer(&mut *buffer), value).unwrap();
+        let bytes = buffer.split().freeze();
+        if bytes.len() > SHARED_RESPONSE_SIZE_LIMIT.load(Ordering::Relaxed) {
+            *buffer = BytesMut::new();
+        }
+        bytes
+    })
+}
+
+/// Size of the chunks large responses are streamed in.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// The number of chunks serialized ahead of the client.
+const CHUNKS_IN_FLIGHT: usize = 4;
+
+/// Serializes `value` into a response, switching to chunked transfer encoding
+/// once it grows larger than `threshold` bytes.
+///
+/// Some Sierra class definitions are tens of megabytes, and proxies sitting in
+/// front of the node tend to time out waiting for a body of that size. The
+/// value is serialized on a blocking thread straight into the body, so that
+/// they can forward the first chunk while the rest is still being written.
+pub(crate) async fn streaming_response<T>(value: T, threshold: NonZeroUsize) -> Response
+where
+    T: Serialize + Send + 'static,
+{
+    let (start_tx, start_rx) = oneshot::channel();
+    let span = tracing::Span::current();
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut writer = StreamingWriter {
+            buffer: BytesMut::new(),
+            threshold: threshold.get(),
+            start: Some(start_tx),
+            chunks: None,
+        };
+        let result = serde_json::to_writer(&mut writer, &value);
+        writer.finish(result);
+    });
+
+    match start_rx.await {
+        Ok(Start::Complete(bytes)) => bytes.into_response(),
+        Ok(Start::Streaming(chunks)) => {
+            let chunks = futures::stream::unfold(chunks, |mut chunks| async move {
+                chunks.recv().await.map(|chunk| (chunk, chunks))
+            });
+            Body::from_stream(chunks).into_response()
+        }
+        Err(_) => http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// How a [streaming_response] starts.
+enum Start {
+    /// The value was serialized without exceeding the threshold.
+    Complete(Bytes),
+    Streaming(mpsc::Receiver<std::io::Result<Bytes>>),
+}
 
-        serde_json::to_vec(&self).unwrap().into_response()
+struct StreamingWriter {
+    buffer: BytesMut,
+    threshold: usize,
+    /// Taken once the response starts.
+    start: Option<oneshot::Sender<Start>>,
+    /// Set once the threshold is exceeded.
+    chunks: Option<mpsc::Sender<std::io::Result<Bytes>>>,
+}
+
+impl StreamingWriter {
+    fn finish(mut self, result: serde_json::Result<()>) {
+        match (result, self.chunks.take(), self.start.take()) {
+            (Ok(()), Some(chunks), _) => {
+                if !self.buffer.is_empty() {
+                    _ = chunks.blocking_send(Ok(self.buffer.split().freeze()));
+                }
+            }
+            (Ok(()), None, Some(start)) => {
+                _ = start.send(Start::Complete(self.buffer.split().freeze()));
+            }
+            (Err(error), Some(chunks), _) => {
+                tracing::debug!(%error, "Streaming response aborted");
+                // Fails the body, so that the client does not mistake the
+                // truncated response for a complete one.
+                _ = chunks.blocking_send(Err(std::io::Error::other(error)));
+            }
+            (Err(error), None, _) => {
+                tracing::warn!(%error, "Failed to serialize response");
+            }
+            (Ok(()), None, None) => unreachable!("The response starts exactly once"),
+        }
+    }
+}
+
+impl std::io::Write for StreamingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let closed = || std::io::Error::from(std::io::ErrorKind::BrokenPipe);
+
+        self.buffer.extend_from_slice(buf);
+
+        if self.chunks.is_none() && self.buffer.len() > self.threshold {
+            let (tx, rx) = mpsc::channel(CHUNKS_IN_FLIGHT);
+            let start = self.start.take().expect("The response starts exactly once");
+            start.send(Start::Streaming(rx)).map_err(|_| closed())?;
+            self.chunks = Some(tx);
+        }
+
+        if let Some(chunks) = &self.chunks {
+            while self.buffer.len() >= CHUNK_SIZE {
+                let chunk = self.buffer.split_to(CHUNK_SIZE).freeze();
+                chunks.blocking_send(Ok(chunk)).map_err(|_| closed())?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
@@ -130,7 +344,7 @@ mod tests {
     #[test]
     fn output_is_ok() {
         let serialized = serde_json::to_value(&RpcResponse {
-            output: Ok(Value::String("foobar".to_owned())),
+            output: Ok(Value::String("foobar".to_owned()).into()),
             id: RequestId::Number(1),
         })
         .unwrap();
@@ -143,4 +357,44 @@ mod tests {
 
         assert_eq!(serialized, expected);
     }
+
+    #[test]
+    fn shared_buffer_output() {
+        let small = json!({"key": "value"});
+        let large = json!("a".repeat(MAX_SHARED_RESPONSE_SIZE + 1));
+
+        for value in [&small, &large, &small] {
+            let bytes = to_json_bytes(value);
+            assert_eq!(bytes, serde_json::to_vec(value).unwrap());
+        }
+    }
+
+    const THRESHOLD: NonZeroUsize = match NonZeroUsize::new(CHUNK_SIZE) {
+        Some(threshold) => threshold,
+        None => unreachable!(),
+    };
+
+    #[tokio::test]
+    async fn small_responses_are_not_streamed() {
+        // Serialized with its quotes, the string is exactly at the threshold.
+        let value = "a".repeat(CHUNK_SIZE - 2);
+        let response = streaming_response(value, THRESHOLD).await;
+
+        use http_body::Body as _;
+        assert_eq!(response.body().size_hint().exact(), Some(CHUNK_SIZE as u64));
+    }
+
+    #[tokio::test]
+    async fn large_responses_are_streamed() {
+        let value = json!({"data": "a".repeat(3 * CHUNK_SIZE)});
+        let response = streaming_response(value.clone(), THRESHOLD).await;
+
+        use http_body::Body as _;
+        assert_eq!(response.body().size_hint().exact(), None);
+
+        let streamed = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(streamed, serde_json::to_vec(&value).unwrap());
+    }
 }
@@ -1,19 +1,27 @@
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 
-use axum::extract::{State, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, State, WebSocketUpgrade};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use futures::{Future, FutureExt, StreamExt};
 use http::HeaderValue;
 use method::RpcMethodEndpoint;
+use pathfinder_common::memory::{self, Subsystem};
 pub use subscription::{handle_json_rpc_socket, RpcSubscriptionFlow, SubscriptionMessage};
 use subscription::{split_ws, RpcSubscriptionEndpoint};
 
+use crate::class_uploads::DECLARE_METHODS;
 use crate::context::RpcContext;
+use crate::error::ApplicationError;
+use crate::field_selection::FieldSelection;
 use crate::jsonrpc::error::RpcError;
-use crate::jsonrpc::request::RpcRequest;
-use crate::jsonrpc::response::RpcResponse;
+use crate::jsonrpc::request::{RawParams, RpcRequest};
+use crate::jsonrpc::response::{streaming_response, RpcOutput, RpcResponse};
+use crate::load_shedding::MethodClass;
+use crate::response_cache::{CacheKey, ResponseCache};
+use crate::write_protection::Client;
 use crate::RpcVersion;
 
 mod method;
@@ -27,14 +35,26 @@ pub struct RpcRouter {
     method_endpoints: &'static HashMap<&'static str, Box<dyn RpcMethodEndpoint>>,
     subscription_endpoints: &'static HashMap<&'static str, Box<dyn RpcSubscriptionEndpoint>>,
     version: RpcVersion,
+    deprecated: bool,
+    streaming_threshold: NonZeroUsize,
 }
 
 pub struct RpcRouterBuilder {
     method_endpoints: HashMap<&'static str, Box<dyn RpcMethodEndpoint>>,
     subscription_endpoints: HashMap<&'static str, Box<dyn RpcSubscriptionEndpoint>>,
     version: RpcVersion,
+    deprecated: bool,
+    streaming_threshold: NonZeroUsize,
 }
 
+/// Methods which submit transactions, and are therefore not served by
+/// deprecated versions.
+const WRITE_METHODS: &[&str] = &[
+    "starknet_addDeclareTransaction",
+    "starknet_addDeployAccountTransaction",
+    "starknet_addInvokeTransaction",
+];
+
 impl RpcRouterBuilder {
     /// Registers an RPC method.
     ///
@@ -69,6 +89,23 @@ impl RpcRouterBuilder {
         self
     }
 
+    /// Marks the version as deprecated. Only read-only methods are served, and
+    /// responses carry `Deprecation` and `Warning` headers.
+    pub fn deprecated(mut self) -> Self {
+        for method_name in WRITE_METHODS {
+            self.method_endpoints.remove(method_name);
+        }
+        self.deprecated = true;
+        self
+    }
+
+    /// Responses larger than `threshold` bytes are streamed using chunked
+    /// transfer encoding.
+    pub fn streaming_threshold(mut self, threshold: NonZeroUsize) -> Self {
+        self.streaming_threshold = threshold;
+        self
+    }
+
     pub fn build(self, context: RpcContext) -> RpcRouter {
         // Intentionally leak the hashmaps to give them a static lifetime.
         // Since the router is expected to be long lived, this shouldn't be an issue.
@@ -81,6 +118,8 @@ impl RpcRouterBuilder {
             method_endpoints: methods,
             subscription_endpoints: subscriptions,
             version: self.version,
+            deprecated: self.deprecated,
+            streaming_threshold: self.streaming_threshold,
         }
     }
 
@@ -89,6 +128,8 @@ impl RpcRouterBuilder {
             method_endpoints: Default::default(),
             subscription_endpoints: Default::default(),
             version,
+            deprecated: false,
+            streaming_threshold: crate::DEFAULT_STREAMING_THRESHOLD,
         }
     }
 }
@@ -98,17 +139,61 @@ impl RpcRouter {
         RpcRouterBuilder::new(version)
     }
 
+    /// Headers to add to every response if this version is deprecated.
+    pub(crate) fn deprecation_headers(&self) -> http::HeaderMap {
+        let mut headers = http::HeaderMap::new();
+        if self.deprecated {
+            headers.insert("deprecation", HeaderValue::from_static("true"));
+            let warning = format!(
+                "299 - \"JSON-RPC {} is deprecated and only serves read-only methods\"",
+                self.version.to_str()
+            );
+            headers.insert(
+                http::header::WARNING,
+                HeaderValue::from_str(&warning).expect("Warning is a valid header value"),
+            );
+        }
+        headers
+    }
+
     /// Parses and executes a request. Returns [None] if its a notification.
     async fn run_request(&self, request: &str) -> Option<RpcResponse> {
+        self.run_tagged_request(request)
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Like [Self::run_request], but also returns whether the response can
+    /// only change through a reorg, which is the case for responses about
+    /// blocks accepted on L1.
+    async fn run_tagged_request(&self, request: &str) -> Option<(RpcResponse, bool)> {
         tracing::trace!(%request, "Running request");
 
         let request = match serde_json::from_str::<RpcRequest<'_>>(request) {
             Ok(request) => request,
             Err(e) => {
-                return Some(RpcResponse::invalid_request(e.to_string()));
+                return Some((RpcResponse::invalid_request(e.to_string()), false));
             }
         };
 
+        let cache_key = ResponseCache::key(
+            self.version,
+            &request.method,
+            request.params.0.map(|params| params.get()),
+            &self.context.pending_data.0.borrow(),
+        );
+
+        self.execute(request, cache_key).await
+    }
+
+    /// Executes a parsed request, and returns the response along with whether
+    /// it can only change through a reorg. Returns [None] if its a
+    /// notification.
+    async fn execute(
+        &self,
+        request: RpcRequest<'_>,
+        cache_key: Option<CacheKey>,
+    ) -> Option<(RpcResponse, bool)> {
         // Ignore notification requests.
         if request.id.is_notification() {
             return None;
@@ -119,12 +204,110 @@ impl RpcRouter {
         let Some((&method_name, method)) =
             self.method_endpoints.get_key_value(request.method.as_ref())
         else {
-            return Some(RpcResponse::method_not_found(request.id));
+            return Some((RpcResponse::method_not_found(request.id), false));
         };
 
         metrics::increment_counter!("rpc_method_calls_total", "method" => method_name, "version" => self.version.to_str());
 
-        let method = method.invoke(self.context.clone(), request.params, self.version);
+        let selection = match request.fields.as_deref().map(FieldSelection::parse) {
+            Some(Ok(selection)) => Some(selection),
+            Some(Err(error)) => {
+                return Some((
+                    RpcResponse {
+                        output: Err(error),
+                        id: request.id,
+                    },
+                    false,
+                ))
+            }
+            None => None,
+        };
+        let select = |output: RpcOutput| match &selection {
+            Some(selection) => output
+                .into_value()
+                .map(|output| selection.apply(output).into())
+                .map_err(|e| RpcError::InternalError(e.into())),
+            None => Ok(output),
+        };
+
+        let mut cache_generation = None;
+        if let (Some(cache), Some(key)) = (&self.context.response_cache, &cache_key) {
+            if let Some(output) = cache.get(key).await {
+                metrics::increment_counter!("rpc_response_cache_hits_total", "method" => method_name);
+                // Only responses about blocks accepted on L1 are cached.
+                return Some((
+                    RpcResponse {
+                        output: select(output),
+                        id: request.id,
+                    },
+                    true,
+                ));
+            }
+            metrics::increment_counter!("rpc_response_cache_misses_total", "method" => method_name);
+            // Read before executing, so that a reorg handled in the meantime
+            // keeps the response out of the cache.
+            cache_generation = Some(cache.generation());
+        }
+
+        let _admission = match self
+            .context
+            .load_shedder
+            .as_ref()
+            .map(|shedder| shedder.admit(method_name))
+            .transpose()
+        {
+            Ok(admission) => admission,
+            Err(error) => {
+                metrics::increment_counter!("rpc_requests_shed_total", "method" => method_name);
+                return Some((
+                    RpcResponse {
+                        output: Err(error.into()),
+                        id: request.id,
+                    },
+                    false,
+                ));
+            }
+        };
+
+        let scored_client = match self
+            .context
+            .write_protection
+            .as_ref()
+            .map(|protection| protection.admit(method_name))
+            .transpose()
+        {
+            Ok(client) => client.flatten(),
+            Err(error) => {
+                metrics::increment_counter!("rpc_write_requests_throttled_total", "method" => method_name);
+                return Some((
+                    RpcResponse {
+                        output: Err(error.into()),
+                        id: request.id,
+                    },
+                    false,
+                ));
+            }
+        };
+
+        let substituted = match request.params.0 {
+            Some(params) if DECLARE_METHODS.contains(&method_name) => {
+                match self.context.class_uploads.substitute(params) {
+                    Ok(substituted) => substituted,
+                    Err(error) => {
+                        return Some((
+                            RpcResponse {
+                                output: Err(error),
+                                id: request.id,
+                            },
+                            false,
+                        ))
+                    }
+                }
+            }
+            _ => None,
+        };
+        let params = substituted.as_deref().or(request.params.0);
+        let method = method.invoke(self.context.clone(), RawParams(params), self.version);
         let result = std::panic::AssertUnwindSafe(method).catch_unwind().await;
 
         let output = match result {
@@ -137,19 +320,100 @@ impl RpcRouter {
             }
         };
 
-        if output.is_err() {
+        if let (Some(protection), Some(client)) = (&self.context.write_protection, scored_client) {
+            protection.record(client, &output);
+        }
+
+        if let (
+            Some(upstream),
+            Err(RpcError::ApplicationError(ApplicationError::StatePruned { .. })),
+        ) = (&self.context.upstream, &output)
+        {
+            match upstream.forward(self.version, method_name, params).await {
+                Ok(output) => {
+                    metrics::increment_counter!("rpc_upstream_requests_total", "method" => method_name, "result" => "success");
+                    // Upstream responses are not cached, the upstream node is
+                    // the authority on whether they remain valid.
+                    let output = select(output.into()).and_then(|output| {
+                        output
+                            .into_upstream()
+                            .map_err(|e| RpcError::InternalError(e.into()))
+                    });
+                    return Some((
+                        RpcResponse {
+                            output,
+                            id: request.id,
+                        },
+                        false,
+                    ));
+                }
+                Err(error) => {
+                    metrics::increment_counter!("rpc_upstream_requests_total", "method" => method_name, "result" => "failure");
+                    tracing::debug!(method=%method_name, ?error, "Forwarding request to upstream failed");
+                }
+            }
+        }
+
+        if let Err(error) = &output {
             metrics::increment_counter!("rpc_method_calls_failed_total", "method" => method_name, "version" => self.version.to_str());
+            record_error(error);
         }
 
-        Some(RpcResponse {
-            output,
-            id: request.id,
-        })
+        let l1_accepted_block = match (&cache_key, &output) {
+            (Some(key), Ok(_)) => key.l1_accepted_block(&self.context.storage).await,
+            _ => None,
+        };
+        let immutable = l1_accepted_block.is_some();
+
+        if let (Some(cache), Some(key), Some(generation), Some(block), Ok(output)) = (
+            &self.context.response_cache,
+            cache_key,
+            cache_generation,
+            l1_accepted_block,
+            &output,
+        ) {
+            let cache = cache.clone();
+            let response = output.clone();
+            tokio::spawn(async move { cache.insert(key, generation, block, response).await });
+        }
+
+        Some((
+            RpcResponse {
+                output: output.and_then(select),
+                id: request.id,
+            },
+            immutable,
+        ))
+    }
+}
+
+/// Counts unexpected errors in the error telemetry, if it is enabled.
+fn record_error(error: &RpcError) {
+    use pathfinder_common::telemetry::{self, ErrorKind};
+
+    if !telemetry::is_enabled() {
+        return;
+    }
+
+    match error {
+        RpcError::InternalError(e) | RpcError::ApplicationError(ApplicationError::Internal(e)) => {
+            let kind = if e.chain().any(|cause| cause.is::<rusqlite::Error>()) {
+                ErrorKind::Database
+            } else {
+                ErrorKind::Internal
+            };
+            telemetry::record(kind, &format!("{e:#}"));
+        }
+        // Custom errors are mostly execution failures reported by the executor.
+        RpcError::ApplicationError(ApplicationError::Custom(e)) => {
+            telemetry::record(ErrorKind::Execution, &format!("{e:#}"));
+        }
+        _ => {}
     }
 }
 
 // A slight variation on the axum json extractor.
-fn is_utf8_encoded_json(headers: http::HeaderMap) -> bool {
+fn is_utf8_encoded_json(headers: &http::HeaderMap) -> bool {
     let Some(content_type) = headers.get(http::header::CONTENT_TYPE) else {
         return false;
     };
@@ -180,45 +444,174 @@ fn is_utf8_encoded_json(headers: http::HeaderMap) -> bool {
 #[axum::debug_handler]
 pub async fn rpc_handler(
     State(state): State<RpcRouter>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     headers: http::HeaderMap,
     ws: Option<WebSocketUpgrade>,
     body: axum::body::Bytes,
 ) -> impl axum::response::IntoResponse {
-    match ws {
-        Some(ws) => ws.on_upgrade(|ws| async move {
-            let (ws_tx, ws_rx) = split_ws(ws);
-            handle_json_rpc_socket(state, ws_tx, ws_rx);
+    let deprecation_headers = state.deprecation_headers();
+    let client = Client::identify(&headers, connect_info.map(|ConnectInfo(address)| address));
+
+    let mut response = match ws {
+        Some(ws) => ws.on_upgrade(|ws| {
+            Client::scope(client, async move {
+                let (ws_tx, ws_rx) = split_ws(ws, state.context.websocket_keepalive);
+                handle_json_rpc_socket(state, ws_tx, ws_rx);
+            })
         }),
         None => {
-            // Only utf8 json content allowed.
-            if !is_utf8_encoded_json(headers) {
-                return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
-            }
+            memory::instrument(Subsystem::Rpc, async move {
+                // Only utf8 json content allowed.
+                if !is_utf8_encoded_json(&headers) {
+                    return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+                }
 
-            let mut response = match handle_json_rpc_body(&state, body.as_ref()).await {
-                Ok(responses) => match responses {
-                    RpcResponses::Empty => ().into_response(),
-                    RpcResponses::Single(response) => response.into_response(),
-                    RpcResponses::Multiple(responses) => {
-                        serde_json::to_string(&responses).unwrap().into_response()
+                let handled = handle_json_rpc_body(&state, body.as_ref());
+                let mut response = match Client::scope(client, handled).await {
+                    Ok(responses) => match responses {
+                        RpcResponses::Empty => ().into_response(),
+                        RpcResponses::Single {
+                            response,
+                            immutable: true,
+                        } if response.output.is_ok() => {
+                            with_etag(&headers, response, state.streaming_threshold).await
+                        }
+                        RpcResponses::Single { response, .. } => {
+                            let retry_after = retry_after(&response);
+                            response.log_error();
+                            let mut response =
+                                streaming_response(response, state.streaming_threshold).await;
+                            if let Some(retry_after) = retry_after {
+                                response
+                                    .headers_mut()
+                                    .insert(http::header::RETRY_AFTER, retry_after);
+                            }
+                            response
+                        }
+                        RpcResponses::Multiple(responses) => {
+                            streaming_response(responses, state.streaming_threshold).await
+                        }
+                    },
+                    Err(RpcRequestError::ParseError(e)) => {
+                        RpcResponse::parse_error(e).into_response()
+                    }
+                    Err(RpcRequestError::InvalidRequest(e)) => {
+                        RpcResponse::invalid_request(e).into_response()
+                    }
+                };
+
+                use http::header::CONTENT_TYPE;
+                static APPLICATION_JSON: HeaderValue = HeaderValue::from_static("application/json");
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, APPLICATION_JSON.clone());
+                if let Some(fingerprint) = &state.context.execution_fingerprint {
+                    if executes(body.as_ref()) {
+                        response.headers_mut().insert(
+                            crate::execution_fingerprint::HEADER,
+                            fingerprint.header_value().clone(),
+                        );
                     }
-                },
-                Err(RpcRequestError::ParseError(e)) => RpcResponse::parse_error(e).into_response(),
-                Err(RpcRequestError::InvalidRequest(e)) => {
-                    RpcResponse::invalid_request(e).into_response()
                 }
-            };
-
-            use http::header::CONTENT_TYPE;
-            static APPLICATION_JSON: HeaderValue = HeaderValue::from_static("application/json");
-            response
-                .headers_mut()
-                .insert(CONTENT_TYPE, APPLICATION_JSON.clone());
-            response
+                response
+            })
+            .await
         }
+    };
+
+    response.headers_mut().extend(deprecation_headers);
+    response
+}
+
+/// The `Retry-After` header for responses to requests rejected by the load
+/// shedder.
+fn retry_after(response: &RpcResponse) -> Option<HeaderValue> {
+    match &response.output {
+        Err(RpcError::ApplicationError(
+            ApplicationError::Overloaded {
+                retry_after_seconds,
+            }
+            | ApplicationError::Throttled {
+                retry_after_seconds,
+            },
+        )) => Some(HeaderValue::from(*retry_after_seconds)),
+        _ => None,
     }
 }
 
+/// Returns true if `request`, or any request of a batch, executes
+/// transactions.
+fn executes(request: &[u8]) -> bool {
+    #[derive(serde::Deserialize)]
+    struct Method {
+        method: String,
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Request {
+        Single(Method),
+        Batch(Vec<Method>),
+    }
+
+    let is_execution =
+        |request: &Method| MethodClass::of(&request.method) == MethodClass::Execution;
+    match serde_json::from_slice::<Request>(request) {
+        Ok(Request::Single(request)) => is_execution(&request),
+        Ok(Request::Batch(requests)) => requests.iter().any(is_execution),
+        Err(_) => false,
+    }
+}
+
+/// Adds a strong `ETag` to a successful response, and replaces it with `304
+/// Not Modified` if the request's `If-None-Match` header contains the tag.
+///
+/// The tag is a digest of the result only, so that requests for the same
+/// block with different ids share it. This allows CDNs and clients to
+/// revalidate cached responses about specific blocks. The body is sent with
+/// [streaming_response], like untagged responses.
+async fn with_etag(
+    request_headers: &http::HeaderMap,
+    response: RpcResponse,
+    streaming_threshold: NonZeroUsize,
+) -> axum::response::Response {
+    use http::header::{ETAG, IF_NONE_MATCH};
+    use sha2::Digest;
+
+    let Ok(result) = &response.output else {
+        return streaming_response(response, streaming_threshold).await;
+    };
+    let mut hasher = sha2::Sha256::new();
+    serde_json::to_writer(&mut hasher, result).expect("Serializing to a digest cannot fail");
+    let digest = hasher.finalize();
+    let etag = format!(
+        "\"{}\"",
+        digest[..16]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    );
+
+    let not_modified = request_headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag.trim_start_matches("W/") == etag);
+
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        streaming_response(response, streaming_threshold).await
+    };
+    response.headers_mut().insert(
+        ETAG,
+        HeaderValue::from_str(&etag).expect("Hex digest is a valid header value"),
+    );
+    response
+}
+
 pub(super) enum RpcRequestError {
     ParseError(String),
     InvalidRequest(String),
@@ -227,7 +620,11 @@ pub(super) enum RpcRequestError {
 #[derive(Debug)]
 pub(super) enum RpcResponses {
     Empty,
-    Single(RpcResponse),
+    Single {
+        response: RpcResponse,
+        /// Whether the response can only change through a reorg.
+        immutable: bool,
+    },
     Multiple(Vec<RpcResponse>),
 }
 
@@ -238,7 +635,7 @@ impl serde::ser::Serialize for RpcResponses {
     {
         match self {
             Self::Empty => serde::ser::Serialize::serialize(&(), serializer),
-            Self::Single(response) => serde::ser::Serialize::serialize(response, serializer),
+            Self::Single { response, .. } => serde::ser::Serialize::serialize(response, serializer),
             Self::Multiple(responses) => serde::ser::Serialize::serialize(responses, serializer),
         }
     }
@@ -355,6 +752,7 @@ where
 
 #[cfg(test)]
 mod tests {
+    use pathfinder_common::BlockNumber;
     use serde::{Deserialize, Serialize};
     use serde_json::{json, Value};
 
@@ -655,7 +1053,7 @@ mod tests {
     #[tokio::test]
     async fn rejects_non_json_content_header() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
-            Ok(json!("Success"))
+            Ok(json!("Success").into())
         }
 
         let router = RpcRouter::builder(Default::default())
@@ -685,7 +1083,7 @@ mod tests {
     #[tokio::test]
     async fn accepts_json_with_charset_utf8() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
-            Ok(json!("Success"))
+            Ok(json!("Success").into())
         }
 
         let router = RpcRouter::builder(Default::default())
@@ -722,7 +1120,7 @@ mod tests {
     #[tokio::test]
     async fn rejects_json_with_charset_utf16() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
-            Ok(json!("Success"))
+            Ok(json!("Success").into())
         }
 
         let router = RpcRouter::builder(Default::default())
@@ -827,6 +1225,281 @@ mod tests {
         assert_eq!(content_type, "application/json");
     }
 
+    #[tokio::test]
+    async fn execution_fingerprint_header() {
+        fn always_success() -> &'static str {
+            "Success"
+        }
+
+        let fingerprint = crate::execution_fingerprint::ExecutionFingerprint::new(None);
+        let router = RpcRouter::builder(Default::default())
+            .register("starknet_call", always_success)
+            .register("starknet_blockNumber", always_success)
+            .build(RpcContext::for_tests().with_execution_fingerprint(fingerprint.clone()));
+
+        let url = spawn_server(router).await;
+        let client = reqwest::Client::new();
+        let request = |body: Value| client.post(url.clone()).json(&body);
+
+        let header = crate::execution_fingerprint::HEADER;
+        let call = json!({"jsonrpc": "2.0", "method": "starknet_call", "id": 1});
+        let block_number = json!({"jsonrpc": "2.0", "method": "starknet_blockNumber", "id": 2});
+
+        let res = request(call.clone()).send().await.unwrap();
+        assert_eq!(res.headers().get(header), Some(fingerprint.header_value()));
+
+        let res = request(json!([block_number, call])).send().await.unwrap();
+        assert_eq!(res.headers().get(header), Some(fingerprint.header_value()));
+
+        let res = request(block_number).send().await.unwrap();
+        assert!(res.headers().get(header).is_none());
+    }
+
+    #[tokio::test]
+    async fn field_selection() {
+        crate::error::generate_rpc_error_subset!(ExampleError:);
+
+        async fn trace() -> Result<Value, ExampleError> {
+            Ok(json!([
+                { "transaction_hash": "0x1", "trace_root": { "events": [], "calls": [] } }
+            ]))
+        }
+
+        let router = RpcRouter::builder(Default::default())
+            .register("starknet_traceBlockTransactions", trace)
+            .build(RpcContext::for_tests());
+
+        let response = serve_and_query(
+            router,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "starknet_traceBlockTransactions",
+                "fields": ["transaction_hash", "trace_root.events"],
+                "id": 1
+            }),
+        )
+        .await;
+
+        assert_eq!(
+            response["result"],
+            json!([{ "transaction_hash": "0x1", "trace_root": { "events": [] } }])
+        );
+    }
+
+    #[tokio::test]
+    async fn upstream_scalar_result_is_marked() {
+        async fn pruned(_ctx: RpcContext) -> RpcResult {
+            Err(ApplicationError::StatePruned {
+                earliest_available_block: BlockNumber::new_or_panic(10),
+            }
+            .into())
+        }
+
+        let upstream = axum::Router::new().route(
+            "/rpc/v0_7",
+            axum::routing::post(|| async {
+                axum::Json(json!({"jsonrpc": "2.0", "id": 0, "result": "0x1"}))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, upstream.into_make_service()).await });
+        let upstream = crate::upstream::Upstream::new(
+            format!("http://{addr}").parse().unwrap(),
+            std::time::Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let router = RpcRouter::builder(Default::default())
+            .register("starknet_getNonce", pruned)
+            .build(RpcContext::for_tests().with_upstream(upstream));
+
+        let response = serve_and_query(
+            router,
+            json!({"jsonrpc": "2.0", "method": "starknet_getNonce", "id": 1}),
+        )
+        .await;
+
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": "0x1", "provenance": "upstream", "id": 1})
+        );
+    }
+
+    #[tokio::test]
+    async fn etag_for_specific_blocks() {
+        crate::error::generate_rpc_error_subset!(ExampleError:);
+
+        struct BlockInput;
+
+        impl crate::dto::DeserializeForVersion for BlockInput {
+            fn deserialize(_: crate::dto::Value) -> Result<Self, serde_json::Error> {
+                Ok(Self)
+            }
+        }
+
+        async fn get_block(_: BlockInput) -> Result<Value, ExampleError> {
+            Ok(json!({"block_number": 1}))
+        }
+
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.update_l1_l2_pointer(Some(BlockNumber::new_or_panic(1)))
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        let router = RpcRouter::builder(Default::default())
+            .register("starknet_getBlockWithTxHashes", get_block)
+            .build(context);
+
+        let url = spawn_server(router).await;
+        let client = reqwest::Client::new();
+        let request_with_id = |block_id: Value, id: u64| {
+            client.post(url.clone()).json(&json!({
+                "jsonrpc": "2.0",
+                "method": "starknet_getBlockWithTxHashes",
+                "params": [block_id],
+                "id": id
+            }))
+        };
+        let request = |block_id: Value| request_with_id(block_id, 1);
+
+        use reqwest::header::{ETAG, IF_NONE_MATCH};
+
+        let res = request(json!("latest")).send().await.unwrap();
+        assert!(res.headers().get(ETAG).is_none());
+
+        // Block 2 is not accepted on L1 yet, so its status may still change.
+        let res = request(json!({"block_number": 2})).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+        assert!(res.headers().get(ETAG).is_none());
+
+        let res = request(json!({"block_number": 1})).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+        let etag = res.headers().get(ETAG).expect("etag should be set").clone();
+
+        let res = request(json!({"block_number": 1}))
+            .header(IF_NONE_MATCH, etag.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(ETAG), Some(&etag));
+
+        let res = request(json!({"block_number": 1}))
+            .header(IF_NONE_MATCH, "\"other\"")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+        let res = request(json!({"block_number": 1}))
+            .header(IF_NONE_MATCH, "*")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+        // The tag does not depend on the request id.
+        let res = request_with_id(json!({"block_number": 1}), 2)
+            .header(IF_NONE_MATCH, etag.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn tagged_responses_are_streamed() {
+        crate::error::generate_rpc_error_subset!(ExampleError:);
+
+        struct BlockInput;
+
+        impl crate::dto::DeserializeForVersion for BlockInput {
+            fn deserialize(_: crate::dto::Value) -> Result<Self, serde_json::Error> {
+                Ok(Self)
+            }
+        }
+
+        async fn get_block(_: BlockInput) -> Result<Value, ExampleError> {
+            Ok(json!({"block_number": 1, "transactions": vec!["0x1"; 1000]}))
+        }
+
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.update_l1_l2_pointer(Some(BlockNumber::new_or_panic(1)))
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        let router = RpcRouter::builder(Default::default())
+            .register("starknet_getBlockWithTxHashes", get_block)
+            .streaming_threshold(NonZeroUsize::new(1024).unwrap())
+            .build(context);
+
+        let url = spawn_server(router).await;
+        let res = reqwest::Client::new()
+            .post(url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "method": "starknet_getBlockWithTxHashes",
+                "params": [{"block_number": 1}],
+                "id": 1
+            }))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+        assert!(res.headers().get(reqwest::header::ETAG).is_some());
+        // Chunked responses have no content length.
+        assert_eq!(res.content_length(), None);
+        let response = res.json::<Value>().await.unwrap();
+        assert_eq!(response["result"]["transactions"][999], json!("0x1"));
+    }
+
+    #[tokio::test]
+    async fn batch_cost_budget() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        crate::error::generate_rpc_error_subset!(ExampleError:);
+
+        static RUNNING: AtomicUsize = AtomicUsize::new(0);
+        static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+        async fn run() -> Result<Value, ExampleError> {
+            let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+            MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            RUNNING.fetch_sub(1, Ordering::SeqCst);
+            Ok(json!(null))
+        }
+
+        let mut context = RpcContext::for_tests();
+        context.config.batch_cost_budget = NonZeroUsize::new(10);
+        let router = RpcRouter::builder(Default::default())
+            .register("starknet_call", run)
+            .register("starknet_blockNumber", run)
+            .build(context);
+
+        let batch = |method: &str| {
+            (0..4)
+                .map(|id| json!({"jsonrpc": "2.0", "method": method, "id": id}))
+                .collect::<Vec<_>>()
+        };
+
+        let response = serve_and_query(router.clone(), json!(batch("starknet_call"))).await;
+        assert_eq!(response.as_array().unwrap().len(), 4);
+        assert_eq!(MAX_RUNNING.swap(0, Ordering::SeqCst), 1);
+
+        let response = serve_and_query(router, json!(batch("starknet_blockNumber"))).await;
+        assert_eq!(response.as_array().unwrap().len(), 4);
+        assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 4);
+    }
+
     mod concurrent_futures {
         use std::cmp::max;
         use std::sync::Arc;
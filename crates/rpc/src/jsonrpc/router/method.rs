@@ -2,9 +2,11 @@
 
 use std::future::Future;
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 
 use axum::async_trait;
 use serde_json::value::RawValue;
+use tokio::sync::Semaphore;
 use tracing::Instrument;
 
 use super::{
@@ -16,12 +18,13 @@ use super::{
     RpcRouter,
 };
 use crate::context::RpcContext;
-use crate::dto::serialize::{SerializeForVersion, Serializer};
+use crate::dto::serialize::{to_raw_value, SerializeForVersion};
 use crate::dto::DeserializeForVersion;
-use crate::jsonrpc::request::RawParams;
-use crate::jsonrpc::response::RpcResult;
+use crate::jsonrpc::request::{RawParams, RpcRequest};
+use crate::jsonrpc::response::{RpcOutput, RpcResult};
 use crate::jsonrpc::router::RpcEndpointInner;
 use crate::jsonrpc::{RpcError, RpcResponse};
+use crate::load_shedding::MethodClass;
 use crate::RpcVersion;
 
 #[axum::async_trait]
@@ -53,8 +56,11 @@ pub async fn handle_json_rpc_body(
             }
         };
 
-        match state.run_request(request.get()).await {
-            Some(response) => Ok(RpcResponses::Single(response)),
+        match state.run_tagged_request(request.get()).await {
+            Some((response, immutable)) => Ok(RpcResponses::Single {
+                response,
+                immutable,
+            }),
             None => Ok(RpcResponses::Empty),
         }
     } else {
@@ -71,13 +77,27 @@ pub async fn handle_json_rpc_body(
             ));
         }
 
+        let budget = state.context.config.batch_cost_budget;
+        let budget_permits = budget.map(|budget| Semaphore::new(budget.get()));
         let responses = run_concurrently(
             state.context.config.batch_concurrency_limit,
             requests.into_iter().enumerate(),
             |(idx, request)| {
-                state
-                    .run_request(request.get())
-                    .instrument(tracing::debug_span!("batch", idx))
+                let budget_permits = budget_permits.as_ref().zip(budget);
+                async move {
+                    // Held until the request is done.
+                    let _permits = match budget_permits {
+                        Some((permits, budget)) => Some(
+                            permits
+                                .acquire_many(batch_cost(request.get(), budget))
+                                .await
+                                .expect("The semaphore is never closed"),
+                        ),
+                        None => None,
+                    };
+                    state.run_request(request.get()).await
+                }
+                .instrument(tracing::debug_span!("batch", idx))
             },
         )
         .await
@@ -93,6 +113,23 @@ pub async fn handle_json_rpc_body(
     }
 }
 
+/// Returns the share of the batch cost `budget` taken by `request`, which is
+/// at most the whole budget.
+fn batch_cost(request: &str, budget: NonZeroUsize) -> u32 {
+    let cost = serde_json::from_str::<RpcRequest<'_>>(request)
+        .map(|request| MethodClass::of(&request.method).batch_cost())
+        // Invalid requests fail without doing any work.
+        .unwrap_or(1);
+    cost.min(u32::try_from(budget.get()).unwrap_or(u32::MAX))
+}
+
+/// Serializes a method's output straight to JSON text.
+fn serialize_output(output: &dyn SerializeForVersion, version: RpcVersion) -> RpcResult {
+    to_raw_value(output, version)
+        .map(RpcOutput::Raw)
+        .map_err(|e| RpcError::InternalError(e.into()))
+}
+
 /// ```
 /// async fn example(RpcContext, impl DeserializeForVersion, RpcVersion) -> Result<Output, Into<RpcError>>
 /// ```
@@ -127,11 +164,8 @@ where
                 version: RpcVersion,
             ) -> RpcResult {
                 let input = input.deserialize_for_version(version)?;
-                (self.f)(state, input, version)
-                    .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                let output = (self.f)(state, input, version).await.map_err(Into::into)?;
+                serialize_output(&output, version)
             }
         }
 
@@ -176,11 +210,8 @@ where
                 version: RpcVersion,
             ) -> RpcResult {
                 let input = input.deserialize_for_version(version)?;
-                (self.f)(state, input)
-                    .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                let output = (self.f)(state, input).await.map_err(Into::into)?;
+                serialize_output(&output, version)
             }
         }
 
@@ -225,11 +256,8 @@ where
                 version: RpcVersion,
             ) -> RpcResult {
                 let input = input.deserialize_for_version(version)?;
-                (self.f)(input)
-                    .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                let output = (self.f)(input).await.map_err(Into::into)?;
+                serialize_output(&output, version)
             }
         }
 
@@ -276,11 +304,8 @@ where
                         "This method takes no inputs".to_owned(),
                     ));
                 }
-                (self.f)(state)
-                    .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                let output = (self.f)(state).await.map_err(Into::into)?;
+                serialize_output(&output, version)
             }
         }
 
@@ -327,11 +352,8 @@ where
                         "This method takes no inputs".to_owned(),
                     ));
                 }
-                (self.f)()
-                    .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                let output = (self.f)().await.map_err(Into::into)?;
+                serialize_output(&output, version)
             }
         }
 
@@ -371,9 +393,7 @@ where
                         "This method takes no inputs".to_owned(),
                     ));
                 }
-                (self.f)()
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                serialize_output(&(self.f)(), version)
             }
         }
         RpcEndpoint(RpcEndpointInner::Method(Box::new(Helper { f: self })))
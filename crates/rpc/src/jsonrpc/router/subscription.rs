@@ -1,5 +1,6 @@
-use std::sync::Arc;
-use std::time::Duration;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use axum::extract::ws::{Message, WebSocket};
 use dashmap::DashMap;
@@ -8,11 +9,14 @@ use pathfinder_common::{BlockId, BlockNumber};
 use tokio::sync::mpsc;
 
 use super::RpcRouter;
-use crate::context::RpcContext;
+use crate::context::{RpcContext, WebsocketKeepalive};
 use crate::dto::serialize::SerializeForVersion;
 use crate::dto::DeserializeForVersion;
 use crate::error::ApplicationError;
 use crate::jsonrpc::{RequestId, RpcError, RpcRequest, RpcResponse};
+use crate::simulation_sessions::{self, SimulationSessions};
+use crate::subscription_sessions::{ResumptionToken, SubscriptionSessions};
+use crate::write_protection::Client;
 use crate::{RpcVersion, SubscriptionId};
 
 /// See [`RpcSubscriptionFlow`].
@@ -27,9 +31,27 @@ pub(super) trait RpcSubscriptionEndpoint: Send + Sync {
         subscriptions: Arc<DashMap<SubscriptionId, tokio::task::JoinHandle<()>>>,
         req_id: RequestId,
         tx: mpsc::Sender<Result<Message, RpcResponse>>,
+        session: Option<SubscriptionSession>,
     ) -> Result<(), RpcError>;
 }
 
+/// The resumption session of a subscription, see
+/// [crate::subscription_sessions].
+#[derive(Clone)]
+pub(super) struct SubscriptionSession {
+    sessions: SubscriptionSessions,
+    token: ResumptionToken,
+    /// The first block to stream when resuming the session.
+    resume_from: Option<BlockNumber>,
+}
+
+impl SubscriptionSession {
+    fn delivered(&self, subscription_id: SubscriptionId, block_number: BlockNumber) {
+        self.sessions
+            .delivered(&self.token, subscription_id, block_number);
+    }
+}
+
 /// This trait is the main entry point for subscription endpoint
 /// implementations.
 ///
@@ -109,6 +131,7 @@ where
         subscriptions: Arc<DashMap<SubscriptionId, tokio::task::JoinHandle<()>>>,
         req_id: RequestId,
         ws_tx: mpsc::Sender<Result<Message, RpcResponse>>,
+        session: Option<SubscriptionSession>,
     ) -> Result<(), RpcError> {
         let req = T::Request::deserialize(crate::dto::Value::new(input, router.version))
             .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
@@ -120,7 +143,10 @@ where
             _phantom: Default::default(),
         };
 
-        let first_block = T::starting_block(&req);
+        let first_block = match session.as_ref().and_then(|session| session.resume_from) {
+            Some(block_number) => BlockId::Number(block_number),
+            None => T::starting_block(&req),
+        };
 
         let current_block = match &first_block {
             BlockId::Pending => {
@@ -131,17 +157,7 @@ where
             BlockId::Latest => {
                 // No need to catch up. The code below will subscribe to new blocks.
                 // Only needs to send the subscription ID to the client.
-                if ws_tx
-                    .send(Ok(Message::Text(
-                        serde_json::to_string(&RpcResponse {
-                            output: Ok(serde_json::to_value(&SubscriptionIdResult {
-                                subscription_id,
-                            })
-                            .unwrap()),
-                            id: req_id.clone(),
-                        })
-                        .unwrap(),
-                    )))
+                if send_subscribed(&ws_tx, req_id, &tx, session.as_ref())
                     .await
                     .is_err()
                 {
@@ -152,33 +168,29 @@ where
             BlockId::Number(_) | BlockId::Hash(_) => {
                 // Catch up to the latest block in batches of BATCH_SIZE.
 
-                // Load the first block number, return an error if it's invalid.
-                let first_block = pathfinder_storage::BlockId::try_from(T::starting_block(&req))
-                    .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
-                let storage = router.context.storage.clone();
-                let mut current_block =
-                    tokio::task::spawn_blocking(move || -> Result<_, RpcError> {
-                        let mut conn = storage.connection().map_err(RpcError::InternalError)?;
-                        let db = conn.transaction().map_err(RpcError::InternalError)?;
-                        db.block_number(first_block)
-                            .map_err(RpcError::InternalError)?
-                            .ok_or_else(|| ApplicationError::BlockNotFound.into())
-                    })
-                    .await
-                    .map_err(|e| RpcError::InternalError(e.into()))??;
+                let mut current_block = match session.as_ref().and_then(|s| s.resume_from) {
+                    // The block after the last one delivered before the connection was lost,
+                    // which might not exist yet.
+                    Some(resume_from) => resume_from,
+                    None => {
+                        // Load the first block number, return an error if it's invalid.
+                        let first_block = pathfinder_storage::BlockId::try_from(first_block)
+                            .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
+                        let storage = router.context.storage.clone();
+                        tokio::task::spawn_blocking(move || -> Result<_, RpcError> {
+                            let mut conn = storage.connection().map_err(RpcError::InternalError)?;
+                            let db = conn.transaction().map_err(RpcError::InternalError)?;
+                            db.block_number(first_block)
+                                .map_err(RpcError::InternalError)?
+                                .ok_or_else(|| ApplicationError::BlockNotFound.into())
+                        })
+                        .await
+                        .map_err(|e| RpcError::InternalError(e.into()))??
+                    }
+                };
 
                 // Send the subscription ID to the client.
-                if ws_tx
-                    .send(Ok(Message::Text(
-                        serde_json::to_string(&RpcResponse {
-                            output: Ok(serde_json::to_value(&SubscriptionIdResult {
-                                subscription_id,
-                            })
-                            .unwrap()),
-                            id: req_id.clone(),
-                        })
-                        .unwrap(),
-                    )))
+                if send_subscribed(&ws_tx, req_id, &tx, session.as_ref())
                     .await
                     .is_err()
                 {
@@ -208,6 +220,9 @@ where
                             return Ok(());
                         }
                         current_block = msg.block_number;
+                        if let Some(session) = &session {
+                            session.delivered(subscription_id, msg.block_number);
+                        }
                     }
                     // Increment the current block by 1 because the catch_up range is inclusive.
                     current_block += 1;
@@ -245,6 +260,9 @@ where
                     // Subscription closing.
                     return Ok(());
                 }
+                if let Some(session) = &session {
+                    session.delivered(subscription_id, msg.block_number);
+                }
             }
         }
 
@@ -257,13 +275,35 @@ where
             // Subscription closing.
             return Ok(());
         }
+        if let Some(session) = &session {
+            session.delivered(subscription_id, first_msg.block_number);
+        }
         let mut last_block = first_msg.block_number;
+        let missed_tx = tx.for_notification::<Missed>();
         tokio::spawn(async move {
             while let Some(msg) = rx1.recv().await {
                 if msg.block_number.get() > last_block.get() + 1 {
-                    // One or more blocks have been skipped. This is likely due to a race condition
-                    // resulting from a reorg. This message should be ignored.
-                    continue;
+                    // Messages for one or more blocks were dropped, usually because the
+                    // subscription fell too far behind. Tell the client which blocks to
+                    // backfill using regular queries.
+                    let missed = Missed {
+                        first_block_number: last_block + 1,
+                        last_block_number: msg.block_number - 1,
+                    };
+                    tracing::debug!(
+                        subscription_id=%missed_tx.subscription_id.0,
+                        first=%missed.first_block_number,
+                        last=%missed.last_block_number,
+                        "Subscription missed blocks"
+                    );
+                    if missed_tx
+                        .send(missed, MISSED_SUBSCRIPTION_NAME)
+                        .await
+                        .is_err()
+                    {
+                        // Subscription closing.
+                        break;
+                    }
                 }
                 if tx
                     .send(msg.notification, msg.subscription_name)
@@ -273,6 +313,9 @@ where
                     // Subscription closing.
                     break;
                 }
+                if let Some(session) = &session {
+                    session.delivered(subscription_id, msg.block_number);
+                }
                 last_block = msg.block_number;
             }
         });
@@ -280,6 +323,103 @@ where
     }
 }
 
+/// The `method` of the notification sent when messages for a range of blocks
+/// were dropped.
+const MISSED_SUBSCRIPTION_NAME: &str = "pathfinder_subscriptionMissed";
+
+/// The range of blocks, inclusive on both ends, for which subscription
+/// messages were dropped.
+#[derive(Debug)]
+struct Missed {
+    first_block_number: BlockNumber,
+    last_block_number: BlockNumber,
+}
+
+impl crate::dto::serialize::SerializeForVersion for Missed {
+    fn serialize(
+        &self,
+        serializer: crate::dto::serialize::Serializer,
+    ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(
+            "first_block_number",
+            &crate::dto::BlockNumber(self.first_block_number),
+        )?;
+        serializer.serialize_field(
+            "last_block_number",
+            &crate::dto::BlockNumber(self.last_block_number),
+        )?;
+        serializer.end()
+    }
+}
+
+/// Sends the subscription ID in response to the subscription request, followed
+/// by the resumption token of the subscription.
+async fn send_subscribed<T>(
+    ws_tx: &WsSender,
+    req_id: RequestId,
+    tx: &SubscriptionSender<T>,
+    session: Option<&SubscriptionSession>,
+) -> Result<(), mpsc::error::SendError<()>> {
+    ws_tx
+        .send(Ok(Message::Text(
+            serde_json::to_string(&RpcResponse {
+                output: Ok(serde_json::to_value(&SubscriptionIdResult {
+                    subscription_id: tx.subscription_id,
+                })
+                .unwrap()
+                .into()),
+                id: req_id,
+            })
+            .unwrap(),
+        )))
+        .await
+        .map_err(|_| mpsc::error::SendError(()))?;
+
+    if let Some(session) = session {
+        // Sent directly, since the subscription might not have been registered
+        // yet.
+        let notification = RpcNotification {
+            jsonrpc: "2.0",
+            method: RESUMPTION_TOKEN_SUBSCRIPTION_NAME,
+            params: SubscriptionResult {
+                subscription_id: tx.subscription_id,
+                result: ResumptionTokenResult(&session.token),
+            },
+        }
+        .serialize(crate::dto::serialize::Serializer::new(tx.version))
+        .unwrap();
+        ws_tx
+            .send(Ok(Message::Text(
+                serde_json::to_string(&notification).unwrap(),
+            )))
+            .await
+            .map_err(|_| mpsc::error::SendError(()))?;
+    }
+
+    Ok(())
+}
+
+/// The `method` of the notification carrying the resumption token of a
+/// subscription.
+const RESUMPTION_TOKEN_SUBSCRIPTION_NAME: &str = "pathfinder_subscriptionResumptionToken";
+
+/// Resumes a subscription of a closed connection.
+const RESUME_SUBSCRIPTION_METHOD: &str = "pathfinder_resumeSubscription";
+
+struct ResumptionTokenResult<'a>(&'a ResumptionToken);
+
+impl crate::dto::serialize::SerializeForVersion for ResumptionTokenResult<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::serialize::Serializer,
+    ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("resumption_token", &self.0)?;
+        serializer.end()
+    }
+}
+
 type WsSender = mpsc::Sender<Result<Message, RpcResponse>>;
 type WsReceiver = mpsc::Receiver<Result<Message, axum::Error>>;
 
@@ -288,26 +428,42 @@ type WsReceiver = mpsc::Receiver<Result<Message, axum::Error>>;
 /// serves to allow easier testing. The sender sends `Result<_, RpcResponse>`
 /// purely for convenience, and the [`RpcResponse`] will be encoded into a
 /// [`Message::Text`].
-pub fn split_ws(ws: WebSocket) -> (WsSender, WsReceiver) {
+///
+/// With `keepalive` set, the client is pinged periodically and disconnected
+/// once it stops responding.
+pub fn split_ws(ws: WebSocket, keepalive: Option<WebsocketKeepalive>) -> (WsSender, WsReceiver) {
     let (mut ws_sender, mut ws_receiver) = ws.split();
+    let last_received = Arc::new(Mutex::new(Instant::now()));
+    // Dropped once sending stops, which stops receiving as well.
+    let (closed_tx, mut closed_rx) = tokio::sync::oneshot::channel::<()>();
     // Send messages to the websocket using an MPSC channel.
     let (sender_tx, mut sender_rx) = mpsc::channel::<Result<Message, RpcResponse>>(1024);
-    tokio::spawn(async move {
-        while let Some(msg) = sender_rx.recv().await {
-            match msg {
-                Ok(msg) => {
-                    if ws_sender.send(msg).await.is_err() {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    if ws_sender
-                        .send(Message::Text(serde_json::to_string(&e).unwrap()))
-                        .await
-                        .is_err()
-                    {
-                        break;
+    tokio::spawn({
+        let last_received = last_received.clone();
+        async move {
+            let _closed_tx = closed_tx;
+            let mut pings = keepalive.map(|keepalive| {
+                let start = tokio::time::Instant::now() + keepalive.interval;
+                tokio::time::interval_at(start, keepalive.interval)
+            });
+            loop {
+                let msg = tokio::select! {
+                    msg = sender_rx.recv() => match msg {
+                        Some(Ok(msg)) => msg,
+                        Some(Err(e)) => Message::Text(serde_json::to_string(&e).unwrap()),
+                        None => break,
+                    },
+                    _ = next_ping(&mut pings) => {
+                        let timeout = keepalive.expect("Pings are only sent with keepalive").timeout;
+                        if last_received.lock().unwrap().elapsed() > timeout {
+                            tracing::debug!("Websocket client stopped responding, disconnecting");
+                            break;
+                        }
+                        Message::Ping(Vec::new())
                     }
+                };
+                if ws_sender.send(msg).await.is_err() {
+                    break;
                 }
             }
         }
@@ -315,7 +471,15 @@ pub fn split_ws(ws: WebSocket) -> (WsSender, WsReceiver) {
     // Receive messages from the websocket using an MPSC channel.
     let (receiver_tx, receiver_rx) = mpsc::channel::<Result<Message, axum::Error>>(1024);
     tokio::spawn(async move {
-        while let Some(msg) = ws_receiver.next().await {
+        loop {
+            let msg = tokio::select! {
+                msg = ws_receiver.next() => msg,
+                _ = &mut closed_rx => break,
+            };
+            let Some(msg) = msg else {
+                break;
+            };
+            *last_received.lock().unwrap() = Instant::now();
             if receiver_tx.send(msg).await.is_err() {
                 break;
             }
@@ -324,6 +488,16 @@ pub fn split_ws(ws: WebSocket) -> (WsSender, WsReceiver) {
     (sender_tx, receiver_rx)
 }
 
+/// Completes at the next ping, or never if pings are disabled.
+async fn next_ping(pings: &mut Option<tokio::time::Interval>) {
+    match pings {
+        Some(pings) => {
+            pings.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
 pub fn handle_json_rpc_socket(
     state: RpcRouter,
     ws_tx: mpsc::Sender<Result<Message, RpcResponse>>,
@@ -331,8 +505,11 @@ pub fn handle_json_rpc_socket(
 ) {
     let subscriptions: Arc<DashMap<SubscriptionId, tokio::task::JoinHandle<()>>> =
         Default::default();
-    // Read and handle messages from the websocket.
-    tokio::spawn(async move {
+    // Read and handle messages from the websocket, on behalf of the client
+    // which opened it.
+    let client = Client::current();
+    tokio::spawn(Client::scope(client, async move {
+        let mut simulation_sessions = SimulationSessions::default();
         loop {
             let request = match ws_rx.recv().await {
                 Some(Ok(Message::Text(msg))) => msg.into_bytes(),
@@ -343,11 +520,11 @@ pub fn handle_json_rpc_socket(
                 }
                 Some(Ok(Message::Close(_))) | None => {
                     // Websocket closed.
-                    return;
+                    break;
                 }
                 Some(Err(e)) => {
                     tracing::trace!(error = ?e, "Error receiving websocket message");
-                    return;
+                    break;
                 }
             };
 
@@ -367,6 +544,34 @@ pub fn handle_json_rpc_socket(
             };
             let req_id = rpc_request.id;
 
+            if let Some(&method_name) = simulation_sessions::METHODS
+                .iter()
+                .find(|&&method| method == rpc_request.method)
+                .filter(|_| state.version == RpcVersion::PathfinderV01)
+            {
+                // Handled in order, so that each call sees the effects of the
+                // previous ones.
+                let output = simulation_sessions
+                    .handle(
+                        &state.context,
+                        state.version,
+                        method_name,
+                        rpc_request.params.0,
+                    )
+                    .await;
+                if ws_tx
+                    .send(Ok(Message::Text(
+                        serde_json::to_string(&RpcResponse { output, id: req_id }).unwrap(),
+                    )))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                metrics::increment_counter!("rpc_method_calls_total", "method" => method_name, "version" => state.version.to_str());
+                continue;
+            }
+
             if rpc_request.method == "starknet_unsubscribe" {
                 // End the subscription.
                 let Some(params) = rpc_request.params.0 else {
@@ -409,10 +614,13 @@ pub fn handle_json_rpc_socket(
                     continue;
                 };
                 handle.abort();
+                if let Some(sessions) = &state.context.subscription_sessions {
+                    sessions.ended(params.subscription_id);
+                }
                 if ws_tx
                     .send(Ok(Message::Text(
                         serde_json::to_string(&RpcResponse {
-                            output: Ok(true.into()),
+                            output: Ok(serde_json::Value::Bool(true).into()),
                             id: req_id.clone(),
                         })
                         .unwrap(),
@@ -426,11 +634,76 @@ pub fn handle_json_rpc_socket(
                 continue;
             }
 
+            let subscription_id = SubscriptionId::next();
+
+            let (method, params, resumed) = if rpc_request.method == RESUME_SUBSCRIPTION_METHOD {
+                let Some(sessions) = &state.context.subscription_sessions else {
+                    ws_tx
+                        .send(Ok(Message::Text(
+                            serde_json::to_string(&RpcResponse::method_not_found(req_id)).unwrap(),
+                        )))
+                        .await
+                        .ok();
+                    continue;
+                };
+                let resumed = rpc_request
+                    .params
+                    .0
+                    .ok_or_else(|| format!("Missing params for {RESUME_SUBSCRIPTION_METHOD}"))
+                    .and_then(|params| {
+                        serde_json::from_str::<ResumeSubscriptionParams>(params.get())
+                            .map_err(|e| e.to_string())
+                    })
+                    .and_then(|params| {
+                        sessions
+                            .resume(&params.resumption_token, subscription_id)
+                            .map(|resumed| (params.resumption_token, resumed))
+                            .ok_or_else(|| "Unknown or expired resumption token".to_string())
+                    });
+                let (token, resumed) = match resumed {
+                    Ok(resumed) => resumed,
+                    Err(e) => {
+                        if ws_tx
+                            .send(Err(RpcResponse::invalid_params(req_id, e)))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                (
+                    Cow::Borrowed(resumed.method),
+                    resumed.params,
+                    Some((token, resumed.first_block)),
+                )
+            } else {
+                let params = match serde_json::to_value(rpc_request.params) {
+                    Ok(params) => params,
+                    Err(_e) => {
+                        if ws_tx
+                            .send(Ok(Message::Text(
+                                serde_json::to_string(&RpcError::InvalidParams(
+                                    "Invalid params".to_string(),
+                                ))
+                                .unwrap(),
+                            )))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                (rpc_request.method, params, None)
+            };
+
             // Also grab the method_name as it is a static str, which is required by the
             // metrics.
-            let Some((&method_name, endpoint)) = state
-                .subscription_endpoints
-                .get_key_value(rpc_request.method.as_ref())
+            let Some((&method_name, endpoint)) =
+                state.subscription_endpoints.get_key_value(method.as_ref())
             else {
                 ws_tx
                     .send(Ok(Message::Text(
@@ -442,32 +715,27 @@ pub fn handle_json_rpc_socket(
             };
             metrics::increment_counter!("rpc_method_calls_total", "method" => method_name, "version" => state.version.to_str());
 
-            let params = match serde_json::to_value(rpc_request.params) {
-                Ok(params) => params,
-                Err(_e) => {
-                    if ws_tx
-                        .send(Ok(Message::Text(
-                            serde_json::to_string(&RpcError::InvalidParams(
-                                "Invalid params".to_string(),
-                            ))
-                            .unwrap(),
-                        )))
-                        .await
-                        .is_err()
-                    {
-                        break;
-                    }
-                    continue;
-                }
+            let session = match (state.context.subscription_sessions.clone(), resumed) {
+                (Some(sessions), Some((token, resume_from))) => Some(SubscriptionSession {
+                    sessions,
+                    token,
+                    resume_from,
+                }),
+                (Some(sessions), None) => Some(SubscriptionSession {
+                    token: sessions.start(method_name, params.clone(), subscription_id),
+                    sessions,
+                    resume_from: None,
+                }),
+                (None, _) => None,
             };
 
             // Start the subscription.
             let state = state.clone();
-            let subscription_id = SubscriptionId::next();
             let ws_tx = ws_tx.clone();
             let handle = tokio::spawn({
                 let subscriptions = subscriptions.clone();
                 async move {
+                    let sessions = state.context.subscription_sessions.clone();
                     if let Err(e) = endpoint
                         .invoke(
                             state,
@@ -476,6 +744,7 @@ pub fn handle_json_rpc_socket(
                             subscriptions.clone(),
                             req_id.clone(),
                             ws_tx.clone(),
+                            session,
                         )
                         .await
                     {
@@ -486,6 +755,9 @@ pub fn handle_json_rpc_socket(
                             }))
                             .await
                             .ok();
+                        if let Some(sessions) = sessions {
+                            sessions.ended(subscription_id);
+                        }
                         while subscriptions.remove(&subscription_id).is_none() {
                             // Race condition, the insert has not yet happened.
                             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -497,7 +769,15 @@ pub fn handle_json_rpc_socket(
                 panic!("subscription id overflow");
             }
         }
-    });
+
+        if let Some(sessions) = &state.context.subscription_sessions {
+            let subscription_ids = subscriptions
+                .iter()
+                .map(|entry| *entry.key())
+                .collect::<Vec<_>>();
+            sessions.disconnected(&subscription_ids);
+        }
+    }));
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -506,6 +786,12 @@ struct StarknetUnsubscribeParams {
     subscription_id: SubscriptionId,
 }
 
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResumeSubscriptionParams {
+    resumption_token: ResumptionToken,
+}
+
 #[derive(Debug, serde::Serialize)]
 struct SubscriptionIdResult {
     subscription_id: SubscriptionId,
@@ -532,6 +818,20 @@ impl<T> Clone for SubscriptionSender<T> {
     }
 }
 
+impl<T> SubscriptionSender<T> {
+    /// A sender for the same subscription which sends a different type of
+    /// notification.
+    fn for_notification<U>(&self) -> SubscriptionSender<U> {
+        SubscriptionSender {
+            subscription_id: self.subscription_id,
+            subscriptions: self.subscriptions.clone(),
+            tx: self.tx.clone(),
+            version: self.version,
+            _phantom: Default::default(),
+        }
+    }
+}
+
 impl<T: crate::dto::serialize::SerializeForVersion> SubscriptionSender<T> {
     pub async fn send(
         &self,
@@ -2,7 +2,7 @@
 
 use std::sync::Arc;
 
-use pathfinder_common::{EventKey, TransactionHash};
+use pathfinder_common::{BlockHash, BlockNumber, EventKey, TransactionHash};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -20,6 +20,10 @@ pub(super) enum Params {
     Events(EventFilterParams),
     #[serde(rename = "transactionStatus")]
     TransactionStatus(TransactionStatusParams),
+    #[serde(rename = "reorgs")]
+    Reorgs(ReorgParams),
+    #[serde(rename = "l1Acceptance")]
+    L1Acceptance(L1AcceptanceParams),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -35,6 +39,32 @@ pub(super) struct TransactionStatusParams {
     pub(super) transaction_hash: TransactionHash,
 }
 
+/// Reorgs are sent starting from the logged reorg with id `from_id`, or from
+/// the next reorg if it is not set.
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct ReorgParams {
+    #[serde(default)]
+    pub(super) from_id: Option<u64>,
+}
+
+/// The block or transaction whose acceptance on L1 is awaited.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub(super) enum L1AcceptanceParams {
+    BlockNumber { block_number: BlockNumber },
+    BlockHash { block_hash: BlockHash },
+    Transaction { transaction_hash: TransactionHash },
+}
+
+/// Sent once the awaited block or transaction has been accepted on L1.
+#[derive(Debug, Serialize)]
+pub(super) struct L1Acceptance {
+    pub(super) block_number: BlockNumber,
+    pub(super) block_hash: BlockHash,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) transaction_hash: Option<TransactionHash>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(super) struct SubscriptionId {
     pub(super) id: u32,
@@ -93,6 +123,8 @@ pub(super) enum ResponseEvent {
     Responses(RpcResponses),
     Event(SubscriptionItem<Arc<EmittedEvent>>),
     TransactionStatus(SubscriptionItem<Arc<TransactionStatusUpdate>>),
+    Reorg(SubscriptionItem<Value>),
+    L1Accepted(SubscriptionItem<L1Acceptance>),
     RpcError(RpcError),
 }
 
@@ -103,6 +135,11 @@ pub enum TransactionStatusUpdate {
     Rejected = 1,
     Succeeded = 2,
     Reverted = 3,
+    /// The transaction was removed from the pending block.
+    Dropped = 4,
+    /// The transaction was removed from the pending block, and a different
+    /// transaction with the same sender and nonce took its place.
+    Replaced = 5,
 }
 
 impl ResponseEvent {
@@ -117,6 +154,8 @@ impl ResponseEvent {
             ResponseEvent::Responses(_) => "Responses",
             ResponseEvent::Event(_) => "Event",
             ResponseEvent::TransactionStatus(_) => "TransactionStatus",
+            ResponseEvent::Reorg(_) => "Reorg",
+            ResponseEvent::L1Accepted(_) => "L1Accepted",
             ResponseEvent::InternalError(_, _) => "InternalError",
             ResponseEvent::RpcError(_) => "RpcError",
         }
@@ -165,6 +204,8 @@ impl Serialize for ResponseEvent {
             .serialize(serializer),
             ResponseEvent::Responses(responses) => responses.serialize(serializer),
             ResponseEvent::TransactionStatus(status) => status.serialize(serializer),
+            ResponseEvent::Reorg(reorg) => reorg.serialize(serializer),
+            ResponseEvent::L1Accepted(acceptance) => acceptance.serialize(serializer),
             ResponseEvent::RpcError(error) => error.serialize(serializer),
         }
     }
@@ -179,7 +220,7 @@ where
 {
     let payload = serde_json::to_value(payload)?;
     Ok(RpcResponse {
-        output: Ok(payload),
+        output: Ok(payload.into()),
         id: request_id,
     })
 }
@@ -2,18 +2,20 @@
 
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 use std::ops::ControlFlow;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use axum::extract::ws::{Message, WebSocket};
-use axum::extract::{State, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, State, WebSocketUpgrade};
 use axum::response::IntoResponse;
 use futures::sink::Buffer;
 use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt};
 use pathfinder_common::{BlockNumber, TransactionHash};
+use pathfinder_storage::Storage;
 use serde::Serialize;
 use serde_json::Value;
 use starknet_gateway_client::GatewayApi;
@@ -24,21 +26,30 @@ use tokio::sync::{broadcast, mpsc, watch};
 use tracing::error;
 
 use super::{Params, TransactionStatusUpdate};
+use crate::context::RpcContext;
+use crate::dropped_transactions::{DroppedStatus, DroppedTransactions};
+use crate::dto::serialize::{SerializeForVersion, Serializer};
 use crate::error::ApplicationError;
 use crate::jsonrpc::request::RawParams;
 use crate::jsonrpc::router::RpcRequestError;
 use crate::jsonrpc::websocket::data::{
     EventFilterParams,
+    L1Acceptance,
+    L1AcceptanceParams,
     ResponseEvent,
     SubscriptionId,
     SubscriptionItem,
 };
-use crate::jsonrpc::{RequestId, RpcError, RpcRequest, RpcRouter};
+use crate::jsonrpc::{ChainEvent, RequestId, RpcError, RpcRequest, RpcRouter};
 use crate::method::get_events::types::EmittedEvent;
-use crate::{BlockHeader, PendingData};
+use crate::pathfinder::methods::get_reorgs::ReorgEntry;
+use crate::write_protection::Client;
+use crate::{BlockHeader, PendingData, RpcVersion};
 
 const SUBSCRIBE_METHOD: &str = "pathfinder_subscribe";
 const UNSUBSCRIBE_METHOD: &str = "pathfinder_unsubscribe";
+/// Reorgs read from the log per database transaction.
+const REORG_BATCH_SIZE: usize = 100;
 
 #[derive(Clone)]
 pub struct WebsocketContext {
@@ -62,16 +73,21 @@ impl WebsocketContext {
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(router): State<RpcRouter>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: http::HeaderMap,
 ) -> impl IntoResponse {
+    let deprecation_headers = router.deprecation_headers();
+    let client = Client::identify(&headers, connect_info.map(|ConnectInfo(address)| address));
     let mut upgrade_response = ws
         .max_message_size(crate::REQUEST_MAX_SIZE)
         .on_failed_upgrade(|error| tracing::debug!(%error, "Websocket upgrade failed"))
-        .on_upgrade(|socket| handle_socket(socket, router));
+        .on_upgrade(|socket| Client::scope(client, handle_socket(socket, router)));
 
     static APPLICATION_JSON: http::HeaderValue = http::HeaderValue::from_static("application/json");
     upgrade_response
         .headers_mut()
         .insert(http::header::CONTENT_TYPE, APPLICATION_JSON.clone());
+    upgrade_response.headers_mut().extend(deprecation_headers);
 
     upgrade_response
 }
@@ -91,7 +107,10 @@ async fn handle_socket(socket: WebSocket, router: RpcRouter) {
         response_receiver,
         websocket_context.socket_buffer_capacity,
     ));
-    tokio::spawn(read(ws_receiver, response_sender, router));
+    tokio::spawn(Client::scope(
+        Client::current(),
+        read(ws_receiver, response_sender, router),
+    ));
 }
 
 async fn write(
@@ -185,7 +204,7 @@ async fn read(
                 parsed_request.params,
                 response_sender.clone(),
                 source.clone(),
-                router.context.sequencer.clone(),
+                &router.context,
             ) {
                 Ok(resp) => resp,
                 Err(e) => {
@@ -266,7 +285,7 @@ impl SubscriptionManager {
         request_params: RawParams<'_>,
         response_sender: mpsc::Sender<ResponseEvent>,
         websocket_source: TopicBroadcasters,
-        gateway: impl GatewayApi + Send + 'static,
+        context: &RpcContext,
     ) -> anyhow::Result<ResponseEvent> {
         let params = match request_params.deserialize::<Params>() {
             Ok(x) => x,
@@ -307,7 +326,22 @@ impl SubscriptionManager {
                 response_sender,
                 subscription_id,
                 params.transaction_hash,
-                gateway,
+                context.sequencer.clone(),
+                context.notifications.dropped_transactions.clone(),
+            )),
+            Params::Reorgs(params) => tokio::spawn(reorg_subscription(
+                response_sender,
+                context.notifications.chain_events.subscribe(),
+                context.storage.clone(),
+                subscription_id,
+                params.from_id,
+            )),
+            Params::L1Acceptance(params) => tokio::spawn(l1_acceptance_subscription(
+                response_sender,
+                context.notifications.l1_accepted.subscribe(),
+                context.storage.clone(),
+                subscription_id,
+                params,
             )),
         };
 
@@ -492,6 +526,7 @@ async fn transaction_status_subscription(
     subscription_id: u32,
     transaction_hash: TransactionHash,
     gateway: impl GatewayApi + Send + 'static,
+    dropped_transactions: DroppedTransactions,
 ) {
     let mut last_status = None;
     let start = Instant::now();
@@ -503,6 +538,23 @@ async fn transaction_status_subscription(
     let mut poll_interval = tokio::time::interval(Duration::from_millis(500));
     let mut num_consecutive_errors = 0;
     loop {
+        // The gateway keeps reporting transactions removed from the pending
+        // block as received, so check for these first.
+        if let Some(status) = dropped_transactions.get(&transaction_hash) {
+            let update = match status {
+                DroppedStatus::Dropped => TransactionStatusUpdate::Dropped,
+                DroppedStatus::Replaced(_) => TransactionStatusUpdate::Replaced,
+            };
+            msg_sender
+                .send(ResponseEvent::TransactionStatus(SubscriptionItem {
+                    subscription_id,
+                    item: Arc::new(update),
+                }))
+                .await
+                .ok();
+            break;
+        }
+
         match gateway.transaction(transaction_hash).await {
             Ok(tx_status) => {
                 num_consecutive_errors = 0;
@@ -582,6 +634,169 @@ async fn transaction_status_subscription(
     }
 }
 
+/// Sends the logged reorgs from `from_id`, followed by the reorgs logged
+/// while the subscription is open.
+async fn reorg_subscription(
+    msg_sender: mpsc::Sender<ResponseEvent>,
+    mut chain_events: broadcast::Receiver<ChainEvent>,
+    storage: Storage,
+    subscription_id: u32,
+    from_id: Option<u64>,
+) {
+    // Reorgs are logged before they are published on `chain_events`, which was
+    // subscribed to before the log is first read, so none are missed.
+    let mut next_id = from_id;
+    loop {
+        let storage = storage.clone();
+        let result = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            let mut db = storage.connection()?;
+            let db = db.transaction()?;
+            let from_id = match next_id {
+                Some(next_id) => next_id,
+                None => db.latest_reorg_id()?.map_or(0, |id| id + 1),
+            };
+            Ok((from_id, db.recorded_reorgs(from_id, REORG_BATCH_SIZE)?))
+        })
+        .await;
+
+        let (from_id, logged) = match result {
+            Ok(Ok(result)) => result,
+            Ok(Err(error)) => {
+                tracing::warn!(%subscription_id, %error, kind="reorg", "Failed to read reorg log, closing.");
+                let response = ResponseEvent::SubscriptionClosed {
+                    subscription_id,
+                    reason: "Failed to read the reorg log. Closing subscription.".to_owned(),
+                };
+                msg_sender.send(response).await.ok();
+                break;
+            }
+            Err(error) => {
+                error!("Websocket subscription join error: {}", error);
+                break;
+            }
+        };
+        next_id = Some(from_id);
+
+        if logged.is_empty() {
+            let closed = loop {
+                match chain_events.recv().await {
+                    // Lagging only delays reading the log.
+                    Ok(ChainEvent::Reorg(_)) | Err(RecvError::Lagged(_)) => break false,
+                    Ok(ChainEvent::Commit(_) | ChainEvent::PendingUpdate(_)) => {}
+                    Err(RecvError::Closed) => break true,
+                }
+            };
+            if closed {
+                break;
+            }
+            continue;
+        }
+
+        for reorg in logged {
+            next_id = Some(reorg.id + 1);
+            let item = match ReorgEntry(&reorg)
+                .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            {
+                Ok(item) => item,
+                Err(error) => {
+                    tracing::warn!(%subscription_id, %error, kind="reorg", "Failed to serialize reorg");
+                    continue;
+                }
+            };
+            let response = ResponseEvent::Reorg(SubscriptionItem {
+                subscription_id,
+                item,
+            });
+            if msg_sender.send(response).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Sends a single notification once the awaited block or transaction has been
+/// accepted on L1, which ends the subscription.
+async fn l1_acceptance_subscription(
+    msg_sender: mpsc::Sender<ResponseEvent>,
+    mut l1_accepted: broadcast::Receiver<BlockNumber>,
+    storage: Storage,
+    subscription_id: u32,
+    params: L1AcceptanceParams,
+) {
+    // `l1_accepted` was subscribed to before the first check, so no acceptance
+    // is missed.
+    loop {
+        let storage = storage.clone();
+        let params = params.clone();
+        let result = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            let mut db = storage.connection()?;
+            let db = db.transaction()?;
+            let Some(l1_head) = db.l1_l2_pointer()? else {
+                return Ok(None);
+            };
+
+            let (block_id, transaction_hash) = match params {
+                L1AcceptanceParams::BlockNumber { block_number } => {
+                    (pathfinder_storage::BlockId::Number(block_number), None)
+                }
+                L1AcceptanceParams::BlockHash { block_hash } => {
+                    (pathfinder_storage::BlockId::Hash(block_hash), None)
+                }
+                L1AcceptanceParams::Transaction { transaction_hash } => {
+                    match db.transaction_block_hash(transaction_hash)? {
+                        Some(block_hash) => (
+                            pathfinder_storage::BlockId::Hash(block_hash),
+                            Some(transaction_hash),
+                        ),
+                        None => return Ok(None),
+                    }
+                }
+            };
+            let Some((block_number, block_hash)) = db.block_id(block_id)? else {
+                return Ok(None);
+            };
+
+            Ok((block_number <= l1_head).then_some(L1Acceptance {
+                block_number,
+                block_hash,
+                transaction_hash,
+            }))
+        })
+        .await;
+
+        match result {
+            Ok(Ok(Some(acceptance))) => {
+                let response = ResponseEvent::L1Accepted(SubscriptionItem {
+                    subscription_id,
+                    item: acceptance,
+                });
+                msg_sender.send(response).await.ok();
+                break;
+            }
+            Ok(Ok(None)) => {}
+            Ok(Err(error)) => {
+                tracing::warn!(%subscription_id, %error, kind="l1Acceptance", "Failed to check L1 acceptance, closing.");
+                let response = ResponseEvent::SubscriptionClosed {
+                    subscription_id,
+                    reason: "Failed to check L1 acceptance. Closing subscription.".to_owned(),
+                };
+                msg_sender.send(response).await.ok();
+                break;
+            }
+            Err(error) => {
+                error!("Websocket subscription join error: {}", error);
+                break;
+            }
+        }
+
+        match l1_accepted.recv().await {
+            // Lagging only delays the check.
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
 /// A Tokio broadcast sender pre-serializing the value once for all subscribers.
 /// Relies on `Arc`s to flatten the cloning costs inherent to Tokio broadcast
 /// channels.
@@ -657,6 +872,7 @@ mod tests {
     use pathfinder_common::transaction::Transaction;
     use pathfinder_common::{
         block_hash,
+        block_hash_bytes,
         event_commitment,
         event_key,
         receipt_commitment,
@@ -664,6 +880,7 @@ mod tests {
         state_diff_commitment,
         transaction_commitment,
         transaction_hash,
+        transaction_hash_bytes,
         BlockNumber,
         BlockTimestamp,
         ContractAddress,
@@ -690,6 +907,7 @@ mod tests {
     use crate::context::RpcContext;
     use crate::jsonrpc::websocket::data::successful_response;
     use crate::jsonrpc::{RpcError, RpcResponse};
+    use crate::Reorg;
 
     #[tokio::test]
     async fn params_are_required() {
@@ -700,6 +918,7 @@ mod tests {
                 method: Cow::from(SUBSCRIBE_METHOD),
                 params: Default::default(),
                 id: RequestId::Null,
+                fields: None,
             })
             .await;
 
@@ -727,6 +946,7 @@ mod tests {
                     &RawValue::from_string(r#"["newHeads"]"#.to_owned()).unwrap(),
                 )),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
 
@@ -759,6 +979,7 @@ mod tests {
                     id: expected_subscription_id,
                 }))),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
         client
@@ -785,12 +1006,13 @@ mod tests {
                 method: Cow::from("pathfinder_test"),
                 params: Default::default(),
                 id: RequestId::Number(1),
+                fields: None,
             })
             .await;
 
         client
             .expect_response(&RpcResponse {
-                output: Ok(json!("0x534e5f5345504f4c4941")),
+                output: Ok(json!("0x534e5f5345504f4c4941").into()),
                 id: RequestId::Number(1),
             })
             .await;
@@ -811,6 +1033,7 @@ mod tests {
                     &RawValue::from_string(r#"["events"]"#.to_owned()).unwrap(),
                 )),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
 
@@ -877,6 +1100,7 @@ mod tests {
                 method: Cow::from(UNSUBSCRIBE_METHOD),
                 params: RawParams(Some(&value(&SubscriptionId { id: 0 }))),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
         client
@@ -894,6 +1118,7 @@ mod tests {
                     .unwrap(),
                 )),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
 
@@ -928,6 +1153,7 @@ mod tests {
                 method: Cow::from(UNSUBSCRIBE_METHOD),
                 params: RawParams(Some(&value(&SubscriptionId { id: 1 }))),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
         client
@@ -943,6 +1169,7 @@ mod tests {
                         .unwrap(),
                 )),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
 
@@ -1158,6 +1385,7 @@ mod tests {
                     .unwrap(),
                 )),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
 
@@ -1232,6 +1460,7 @@ mod tests {
                 .into_iter()
                 .collect(),
             )),
+            Default::default(),
         ));
 
         let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
@@ -1317,6 +1546,7 @@ mod tests {
                 .into_iter()
                 .collect(),
             )),
+            Default::default(),
         ));
 
         let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
@@ -1402,6 +1632,7 @@ mod tests {
                 .into_iter()
                 .collect(),
             )),
+            Default::default(),
         ));
 
         let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
@@ -1436,6 +1667,203 @@ mod tests {
         assert!(msg.is_none());
     }
 
+    #[tokio::test]
+    async fn subscribe_transaction_status_dropped() {
+        struct Mock;
+
+        #[async_trait::async_trait]
+        impl GatewayApi for Mock {
+            async fn transaction(
+                &self,
+                _transaction_hash: TransactionHash,
+            ) -> Result<TransactionStatus, SequencerError> {
+                Ok(TransactionStatus {
+                    status: Status::Received,
+                    finality_status: FinalityStatus::Received,
+                    execution_status: ExecutionStatus::Succeeded,
+                })
+            }
+        }
+
+        let dropped_transactions = DroppedTransactions::default();
+        let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        tokio::spawn(transaction_status_subscription(
+            msg_sender,
+            0,
+            transaction_hash!("0x1"),
+            Mock,
+            dropped_transactions.clone(),
+        ));
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        match msg {
+            ResponseEvent::TransactionStatus(SubscriptionItem {
+                subscription_id: 0,
+                item,
+            }) if item.as_ref() == &TransactionStatusUpdate::Received => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+
+        let transaction = Transaction {
+            hash: transaction_hash!("0x1"),
+            variant: Default::default(),
+        };
+        dropped_transactions.record_pending_update(
+            &PendingBlock {
+                transactions: vec![transaction],
+                ..Default::default()
+            },
+            &PendingBlock::default(),
+        );
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        match msg {
+            ResponseEvent::TransactionStatus(SubscriptionItem {
+                subscription_id: 0,
+                item,
+            }) if item.as_ref() == &TransactionStatusUpdate::Dropped => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap();
+        assert!(msg.is_none());
+    }
+
+    #[tokio::test]
+    async fn subscribe_reorgs() {
+        let context = RpcContext::for_tests();
+        let record_reorg = |detected_at| {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            let reorg = db
+                .record_reorg(
+                    detected_at,
+                    BlockNumber::new_or_panic(2),
+                    BlockNumber::new_or_panic(2),
+                )
+                .unwrap();
+            db.commit().unwrap();
+            reorg
+        };
+        let logged = record_reorg(1);
+
+        let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        tokio::spawn(reorg_subscription(
+            msg_sender,
+            context.notifications.chain_events.subscribe(),
+            context.storage.clone(),
+            0,
+            Some(logged.id),
+        ));
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match msg {
+            ResponseEvent::Reorg(SubscriptionItem {
+                subscription_id: 0,
+                item,
+            }) if item["id"] == json!(logged.id) && item["detected_at"] == json!(1) => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+
+        let published = record_reorg(2);
+        context.notifications.publish_reorg(Arc::new(Reorg {
+            first_block_number: BlockNumber::new_or_panic(2),
+            first_block_hash: block_hash!("0x2"),
+            last_block_number: BlockNumber::new_or_panic(2),
+            last_block_hash: block_hash!("0x2"),
+        }));
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match msg {
+            ResponseEvent::Reorg(SubscriptionItem {
+                subscription_id: 0,
+                item,
+            }) if item["id"] == json!(published.id) && item["depth"] == json!(1) => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+
+    #[tokio::test]
+    async fn subscribe_l1_acceptance() {
+        let context = RpcContext::for_tests();
+
+        let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        tokio::spawn(l1_acceptance_subscription(
+            msg_sender,
+            context.notifications.l1_accepted.subscribe(),
+            context.storage.clone(),
+            0,
+            L1AcceptanceParams::BlockNumber {
+                block_number: BlockNumber::GENESIS,
+            },
+        ));
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match msg {
+            ResponseEvent::L1Accepted(SubscriptionItem {
+                subscription_id: 0,
+                item,
+            }) if item.block_number == BlockNumber::GENESIS && item.transaction_hash.is_none() => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+
+        // Transaction in block 1, which is not accepted on L1 yet.
+        let transaction_hash = transaction_hash_bytes!(b"txn 1");
+        let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        tokio::spawn(l1_acceptance_subscription(
+            msg_sender,
+            context.notifications.l1_accepted.subscribe(),
+            context.storage.clone(),
+            1,
+            L1AcceptanceParams::Transaction { transaction_hash },
+        ));
+        assert!(timeout(Duration::from_millis(100), msg_receiver.recv())
+            .await
+            .is_err());
+
+        {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.update_l1_l2_pointer(Some(BlockNumber::new_or_panic(1)))
+                .unwrap();
+            db.commit().unwrap();
+        }
+        context
+            .notifications
+            .publish_l1_accepted(BlockNumber::new_or_panic(1));
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match msg {
+            ResponseEvent::L1Accepted(SubscriptionItem {
+                subscription_id: 1,
+                item,
+            }) if item.block_hash == block_hash_bytes!(b"block 1")
+                && item.transaction_hash == Some(transaction_hash) => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+
     #[tokio::test]
     async fn subscribe_transaction_status_does_not_exist() {
         let mut client = Client::new().await;
@@ -1451,6 +1879,7 @@ mod tests {
                     .unwrap(),
                 )),
                 id: req_id.clone(),
+                fields: None,
             })
             .await;
 
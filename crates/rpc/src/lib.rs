@@ -1,23 +1,40 @@
 //! Starknet node JSON-RPC related modules.
+pub mod call_stats;
+pub mod casm_cache;
+pub mod class_uploads;
 pub mod context;
+pub mod dropped_transactions;
 mod dto;
 mod error;
+pub mod execution_fingerprint;
 mod executor;
+pub mod fee_tracker;
 mod felt;
+mod field_selection;
+pub mod fork;
 mod jsonrpc;
+pub mod load_shedding;
 pub(crate) mod method;
 pub mod middleware;
 mod pathfinder;
 mod pending;
+pub mod response_cache;
+pub mod shadow_execution;
+mod simulation_sessions;
+pub mod subscription_sessions;
 #[cfg(test)]
 mod test_setup;
+pub mod upstream;
 pub mod v02;
 pub mod v03;
 pub mod v06;
 pub mod v07;
 pub mod v08;
+mod version_gate;
+pub mod write_protection;
 
 use std::net::SocketAddr;
+use std::num::NonZeroUsize;
 use std::result::Result;
 use std::sync::atomic::{AtomicU32, Ordering};
 
@@ -28,20 +45,26 @@ use axum::response::IntoResponse;
 use context::RpcContext;
 pub use executor::compose_executor_transaction;
 use http_body::Body;
-pub use jsonrpc::{Notifications, Reorg};
+pub use jsonrpc::{set_shared_response_size_limit, ChainEvent, Notifications, Reorg};
 use pathfinder_common::AllowedOrigins;
-pub use pending::PendingData;
+pub use pending::{PendingData, PendingGasPriceSource};
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
+use tower_http::compression::predicate::SizeAbove;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::ServiceBuilderExt;
 
-use crate::jsonrpc::rpc_handler;
 use crate::jsonrpc::websocket::websocket_handler;
 pub use crate::jsonrpc::websocket::{BlockHeader, TopicBroadcasters};
+use crate::jsonrpc::{rpc_handler, RpcRouterBuilder};
 use crate::v02::types::syncing::Syncing;
 
 const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+const DEFAULT_STREAMING_THRESHOLD: NonZeroUsize = match NonZeroUsize::new(1024 * 1024) {
+    Some(threshold) => threshold,
+    None => unreachable!(),
+};
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
 pub enum RpcVersion {
@@ -63,6 +86,33 @@ impl RpcVersion {
     }
 }
 
+/// Compression of HTTP-RPC responses, negotiated with the client using the
+/// `Accept-Encoding` header.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CompressionConfig {
+    /// Responses smaller than this many bytes are sent uncompressed.
+    pub min_size: u16,
+    pub level: CompressionLevel,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum CompressionLevel {
+    Fastest,
+    #[default]
+    Default,
+    Best,
+}
+
+impl From<CompressionLevel> for tower_http::CompressionLevel {
+    fn from(level: CompressionLevel) -> Self {
+        match level {
+            CompressionLevel::Fastest => Self::Fastest,
+            CompressionLevel::Default => Self::Default,
+            CompressionLevel::Best => Self::Best,
+        }
+    }
+}
+
 // TODO: make this configurable
 const REQUEST_MAX_SIZE: usize = 10 * 1024 * 1024;
 // TODO: make this configurable
@@ -73,7 +123,10 @@ pub struct RpcServer {
     context: RpcContext,
     max_connections: usize,
     cors: Option<CorsLayer>,
+    compression: Option<CompressionConfig>,
+    streaming_threshold: NonZeroUsize,
     default_version: RpcVersion,
+    deprecated_versions: Vec<RpcVersion>,
 }
 
 impl RpcServer {
@@ -83,7 +136,10 @@ impl RpcServer {
             context,
             max_connections: DEFAULT_MAX_CONNECTIONS,
             cors: None,
+            compression: None,
+            streaming_threshold: DEFAULT_STREAMING_THRESHOLD,
             default_version,
+            deprecated_versions: Vec::new(),
         }
     }
 
@@ -99,6 +155,32 @@ impl RpcServer {
         }
     }
 
+    /// Compresses responses with gzip or brotli for clients that accept it.
+    pub fn with_compression(self, compression: CompressionConfig) -> Self {
+        Self {
+            compression: Some(compression),
+            ..self
+        }
+    }
+
+    /// Responses larger than `threshold` bytes are sent using chunked
+    /// transfer encoding.
+    pub fn with_streaming_threshold(self, threshold: NonZeroUsize) -> Self {
+        Self {
+            streaming_threshold: threshold,
+            ..self
+        }
+    }
+
+    /// Serves `versions` read-only, with deprecation warnings in the response
+    /// headers.
+    pub fn with_deprecated_versions(self, versions: Vec<RpcVersion>) -> Self {
+        Self {
+            deprecated_versions: versions,
+            ..self
+        }
+    }
+
     /// Starts the HTTP-RPC server.
     pub async fn spawn(
         self,
@@ -141,6 +223,16 @@ impl RpcServer {
             }
         }
 
+        let compression = self.compression.unwrap_or(CompressionConfig {
+            min_size: 0,
+            level: Default::default(),
+        });
+        let compression = CompressionLayer::new()
+            .gzip(self.compression.is_some())
+            .br(self.compression.is_some())
+            .quality(compression.level.into())
+            .compress_when(SizeAbove::new(compression.min_size));
+
         let middleware = tower::ServiceBuilder::new()
             // Convert errors created by middleware layers into responses.
             // This is required by axum -- axum doesn't deal with Result, errors
@@ -152,6 +244,7 @@ impl RpcServer {
             .layer(DefaultBodyLimit::max(REQUEST_MAX_SIZE))
             .timeout(REQUEST_TIMEOUT)
             .layer(middleware::tracing::trace_layer())
+            .layer(compression)
             .option_layer(self.cors)
             .propagate_x_request_id();
 
@@ -165,10 +258,23 @@ impl RpcServer {
             }
         }
 
-        let v06_routes = v06::register_routes().build(self.context.clone());
-        let v07_routes = v07::register_routes().build(self.context.clone());
-        let v08_routes = v08::register_routes().build(self.context.clone());
-        let pathfinder_routes = pathfinder::register_routes().build(self.context.clone());
+        let build = |routes: RpcRouterBuilder, version| {
+            let routes = if self.deprecated_versions.contains(&version) {
+                routes.deprecated()
+            } else {
+                routes
+            };
+            routes
+                .streaming_threshold(self.streaming_threshold)
+                .build(self.context.clone())
+        };
+
+        let v06_routes = build(v06::register_routes(), RpcVersion::V06);
+        let v07_routes = build(v07::register_routes(), RpcVersion::V07);
+        let v08_routes = build(v08::register_routes(), RpcVersion::V08);
+        let pathfinder_routes = pathfinder::register_routes()
+            .streaming_threshold(self.streaming_threshold)
+            .build(self.context.clone());
 
         let default_router = match self.default_version {
             RpcVersion::V06 => v06_routes.clone(),
@@ -209,9 +315,12 @@ impl RpcServer {
         let router = router.layer(middleware);
 
         let server_handle = tokio::spawn(async move {
-            axum::serve(listener, router.into_make_service())
-                .await
-                .map_err(Into::into)
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .map_err(Into::into)
         });
 
         Ok((server_handle, addr))
@@ -858,6 +967,52 @@ mod tests {
         assert!(!status.is_success());
     }
 
+    #[tokio::test]
+    async fn deprecated_version_is_read_only() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let context = RpcContext::for_tests();
+        let (_jh, addr) = RpcServer::new(addr, context, RpcVersion::V07)
+            .with_deprecated_versions(vec![RpcVersion::V06])
+            .spawn()
+            .await
+            .unwrap();
+
+        let client = reqwest::Client::new();
+        let request = |route: &str, method: &str| {
+            client.post(format!("http://{addr}{route}")).json(&json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "id": 0,
+            }))
+        };
+
+        let response = request("/rpc/v0_6", "starknet_chainId")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert!(response.headers().contains_key(http::header::WARNING));
+        let response: serde_json::Value = response.json().await.unwrap();
+        assert!(response.get("result").is_some());
+
+        let response: serde_json::Value = request("/rpc/v0_6", "starknet_addInvokeTransaction")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], json!(-32601));
+
+        let response = request("/rpc/v0_7", "starknet_addInvokeTransaction")
+            .send()
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key("deprecation"));
+        let response: serde_json::Value = response.json().await.unwrap();
+        assert_ne!(response["error"]["code"], json!(-32601));
+    }
+
     #[rustfmt::skip]
     #[rstest::rstest]
     #[case::root_api  ("/", "v06/starknet_api_openrpc.json",       &[])]
@@ -0,0 +1,268 @@
+//! Rejects the most expensive requests while the node is under pressure, so
+//! that cheap reads keep being served during traffic spikes.
+//!
+//! Methods are grouped into classes by cost. Each pressure signal sheds a
+//! class and every class more expensive than it:
+//!
+//! - executions waiting for a free executor shed executions,
+//! - slow event scans shed event scans,
+//! - memory usage above the limit sheds event scans and executions.
+//!
+//! Rejected requests fail with [ApplicationError::Overloaded], which tells the
+//! client when to retry.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pathfinder_common::memory::{self, Subsystem};
+
+use crate::error::ApplicationError;
+
+#[derive(Clone, Debug)]
+pub struct LoadSheddingConfig {
+    /// The number of executions which can run at the same time. Further
+    /// executions wait for one of these to finish.
+    pub execution_concurrency: usize,
+    /// Executions are shed once this many are waiting.
+    pub max_execution_queue: usize,
+    /// Event scans are shed once they take longer than this on average.
+    pub max_event_scan_latency: Duration,
+    /// Event scans and executions are shed while the memory tracked by
+    /// [pathfinder_common::memory] exceeds this many bytes.
+    pub max_memory: Option<u64>,
+    /// How long clients are asked to wait before retrying, and how long event
+    /// scans are shed once they are found to be slow.
+    pub retry_after: Duration,
+}
+
+/// Methods ordered by how expensive they are to serve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MethodClass {
+    Cheap,
+    EventScan,
+    Execution,
+}
+
+impl MethodClass {
+    pub fn of(method: &str) -> Self {
+        match method {
+            "starknet_getEvents" => MethodClass::EventScan,
+            "starknet_call"
+            | "starknet_estimateFee"
+            | "starknet_estimateMessageFee"
+            | "starknet_simulateTransactions"
+            | "starknet_traceTransaction"
+            | "starknet_traceBlockTransactions"
+            | "debug_dumpContractState"
+            | "debug_diffTransactionTraces"
+            | "debug_exportTransactionTrace"
+            | "pathfinder_estimateDataGas"
+            | "pathfinder_estimateFeeBundle"
+            | "pathfinder_getStakingEpoch"
+            | "pathfinder_getStateWitness"
+            | "pathfinder_simulateCalls" => MethodClass::Execution,
+            _ => MethodClass::Cheap,
+        }
+    }
+
+    /// The share of a batch's cost budget taken by a request of this class
+    /// while it runs.
+    pub fn batch_cost(self) -> u32 {
+        match self {
+            MethodClass::Cheap => 1,
+            MethodClass::EventScan => 4,
+            MethodClass::Execution => 10,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LoadShedder(Arc<Inner>);
+
+struct Inner {
+    config: LoadSheddingConfig,
+    executions: AtomicUsize,
+    event_scans: Mutex<EventScanLatency>,
+}
+
+#[derive(Default)]
+struct EventScanLatency {
+    /// Exponential moving average of recent event scans.
+    average: Option<Duration>,
+    /// Event scans are shed until this time.
+    shed_until: Option<Instant>,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self(Arc::new(Inner {
+            config,
+            executions: Default::default(),
+            event_scans: Default::default(),
+        }))
+    }
+
+    /// Admits a request for `method` unless it is being shed. The request
+    /// counts towards the load until the returned guard is dropped.
+    pub fn admit(&self, method: &str) -> Result<Admission, ApplicationError> {
+        let class = MethodClass::of(method);
+        if let Some(shed) = self.shed_class() {
+            if class >= shed {
+                return Err(ApplicationError::Overloaded {
+                    retry_after_seconds: self.0.config.retry_after.as_secs().max(1),
+                });
+            }
+        }
+
+        if class == MethodClass::Execution {
+            self.0.executions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(Admission {
+            shedder: self.clone(),
+            class,
+            started: Instant::now(),
+        })
+    }
+
+    /// The cheapest class currently being shed.
+    fn shed_class(&self) -> Option<MethodClass> {
+        let config = &self.0.config;
+
+        if let Some(max_memory) = config.max_memory {
+            let allocated: i64 = Subsystem::ALL
+                .iter()
+                .map(|subsystem| memory::allocated_bytes(*subsystem))
+                .sum();
+            if u64::try_from(allocated).unwrap_or_default() > max_memory {
+                return Some(MethodClass::EventScan);
+            }
+        }
+
+        let mut event_scans = self.0.event_scans.lock().unwrap();
+        match event_scans.shed_until {
+            Some(until) if until > Instant::now() => return Some(MethodClass::EventScan),
+            // Forget the slow scans, so that the next one decides whether to
+            // keep shedding.
+            Some(_) => *event_scans = Default::default(),
+            None => {}
+        }
+        drop(event_scans);
+
+        let queued = self
+            .0
+            .executions
+            .load(Ordering::Relaxed)
+            .saturating_sub(config.execution_concurrency);
+        if queued >= config.max_execution_queue {
+            return Some(MethodClass::Execution);
+        }
+
+        None
+    }
+
+    fn event_scan_finished(&self, latency: Duration) {
+        let config = &self.0.config;
+        let mut event_scans = self.0.event_scans.lock().unwrap();
+
+        let average = match event_scans.average {
+            Some(average) => (average * 4 + latency) / 5,
+            None => latency,
+        };
+        event_scans.average = Some(average);
+
+        if average > config.max_event_scan_latency && event_scans.shed_until.is_none() {
+            tracing::debug!(?average, "Event scans are slow, shedding them");
+            event_scans.shed_until = Some(Instant::now() + config.retry_after);
+        }
+    }
+}
+
+/// Keeps an admitted request accounted for until it completes.
+pub struct Admission {
+    shedder: LoadShedder,
+    class: MethodClass,
+    started: Instant,
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        match self.class {
+            MethodClass::Execution => {
+                self.shedder.0.executions.fetch_sub(1, Ordering::Relaxed);
+            }
+            MethodClass::EventScan => self.shedder.event_scan_finished(self.started.elapsed()),
+            MethodClass::Cheap => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn config() -> LoadSheddingConfig {
+        LoadSheddingConfig {
+            execution_concurrency: 1,
+            max_execution_queue: 1,
+            max_event_scan_latency: Duration::from_millis(100),
+            max_memory: None,
+            retry_after: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn queued_executions_are_shed() {
+        let uut = LoadShedder::new(config());
+
+        let running = uut.admit("starknet_call").unwrap();
+        let queued = uut.admit("starknet_estimateFee").unwrap();
+        assert_matches!(
+            uut.admit("starknet_simulateTransactions"),
+            Err(ApplicationError::Overloaded {
+                retry_after_seconds: 60
+            })
+        );
+        uut.admit("starknet_getEvents").unwrap();
+        uut.admit("starknet_getBlockWithTxHashes").unwrap();
+
+        drop(queued);
+        drop(running);
+        uut.admit("starknet_call").unwrap();
+    }
+
+    #[test]
+    fn simulated_calls_are_executions() {
+        assert_eq!(
+            MethodClass::of("pathfinder_simulateCalls"),
+            MethodClass::Execution
+        );
+
+        let uut = LoadShedder::new(config());
+        let _running = uut.admit("starknet_call").unwrap();
+        let _queued = uut.admit("starknet_call").unwrap();
+        assert_matches!(
+            uut.admit("pathfinder_simulateCalls"),
+            Err(ApplicationError::Overloaded { .. })
+        );
+    }
+
+    #[test]
+    fn slow_event_scans_are_shed() {
+        let uut = LoadShedder::new(config());
+
+        uut.event_scan_finished(Duration::from_secs(1));
+
+        assert_matches!(
+            uut.admit("starknet_getEvents"),
+            Err(ApplicationError::Overloaded { .. })
+        );
+        assert_matches!(
+            uut.admit("starknet_call"),
+            Err(ApplicationError::Overloaded { .. })
+        );
+        uut.admit("starknet_blockNumber").unwrap();
+    }
+}
@@ -14,6 +14,7 @@ pub mod get_block_with_txs;
 pub mod get_class;
 pub mod get_class_at;
 pub mod get_class_hash_at;
+pub mod get_compiled_casm;
 pub mod get_events;
 pub mod get_nonce;
 pub mod get_state_update;
@@ -25,6 +26,7 @@ pub mod get_transaction_status;
 pub mod simulate_transactions;
 pub mod subscribe_new_heads;
 pub mod subscribe_pending_transactions;
+pub mod subscribe_transaction_status;
 pub mod syncing;
 pub mod trace_block_transactions;
 pub mod trace_transaction;
@@ -45,6 +47,7 @@ pub use get_block_with_txs::get_block_with_txs;
 pub use get_class::get_class;
 pub use get_class_at::get_class_at;
 pub use get_class_hash_at::get_class_hash_at;
+pub use get_compiled_casm::get_compiled_casm;
 pub use get_events::get_events;
 pub use get_nonce::get_nonce;
 pub use get_state_update::get_state_update;
@@ -177,7 +177,11 @@ pub async fn add_declare_transaction(
 ) -> Result<Output, AddDeclareTransactionError> {
     use starknet_gateway_types::request::add_transaction;
 
-    match input.declare_transaction {
+    let tracking_key = match &input.declare_transaction {
+        Transaction::Declare(tx) => crate::fee_tracker::declare_tracking_key(tx),
+    };
+
+    let output = match input.declare_transaction {
         Transaction::Declare(BroadcastedDeclareTransaction::V0(_)) => {
             Err(AddDeclareTransactionError::UnsupportedTransactionVersion)
         }
@@ -277,7 +281,13 @@ pub async fn add_declare_transaction(
                 class_hash: response.class_hash,
             })
         }
+    }?;
+
+    if let (Some(fee_tracker), Some(key)) = (&context.fee_tracker, tracking_key) {
+        fee_tracker.submitted(key, output.transaction_hash);
     }
+
+    Ok(output)
 }
 
 impl crate::dto::serialize::SerializeForVersion for Output {
@@ -296,7 +306,7 @@ impl crate::dto::serialize::SerializeForVersion for Output {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use std::sync::LazyLock;
 
     use pathfinder_common::macro_prelude::*;
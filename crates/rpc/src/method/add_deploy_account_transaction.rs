@@ -127,8 +127,13 @@ pub async fn add_deploy_account_transaction(
         Transaction::DeployAccount(tx) => tx.deployed_contract_address(),
     };
     let Transaction::DeployAccount(tx) = input.deploy_account_transaction;
+    let tracking_key = crate::fee_tracker::deploy_account_tracking_key(&tx);
     let response = add_deploy_account_transaction_impl(&context, tx).await?;
 
+    if let Some(fee_tracker) = &context.fee_tracker {
+        fee_tracker.submitted(tracking_key, response.transaction_hash);
+    }
+
     Ok(Output {
         transaction_hash: response.transaction_hash,
         contract_address,
@@ -117,8 +117,13 @@ pub async fn add_invoke_transaction(
     input: Input,
 ) -> Result<Output, AddInvokeTransactionError> {
     let Transaction::Invoke(tx) = input.invoke_transaction;
+    let tracking_key = crate::fee_tracker::invoke_tracking_key(&tx);
     let response = add_invoke_transaction_impl(&context, tx).await?;
 
+    if let (Some(fee_tracker), Some(key)) = (&context.fee_tracker, tracking_key) {
+        fee_tracker.submitted(key, response.transaction_hash);
+    }
+
     Ok(Output {
         transaction_hash: response.transaction_hash,
     })
@@ -1,5 +1,12 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, CallParam, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_common::{
+    BlockId,
+    BlockNumber,
+    CallParam,
+    CallResultValue,
+    ContractAddress,
+    EntryPoint,
+};
 use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 
 use crate::context::RpcContext;
@@ -10,8 +17,13 @@ pub enum CallError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     ContractNotFound,
-    ContractError { revert_error: Option<String> },
+    ContractError {
+        revert_error: Option<String>,
+    },
 }
 
 impl From<anyhow::Error> for CallError {
@@ -49,6 +61,11 @@ impl From<CallError> for ApplicationError {
     fn from(value: CallError) -> Self {
         match value {
             CallError::BlockNotFound => ApplicationError::BlockNotFound,
+            CallError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             CallError::ContractNotFound => ApplicationError::ContractNotFound,
             CallError::ContractError { revert_error } => {
                 ApplicationError::ContractError { revert_error }
@@ -97,6 +114,13 @@ impl crate::dto::DeserializeForVersion for Input {
 pub struct Output(pub Vec<CallResultValue>);
 
 pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError> {
+    if let Some(call_stats) = &context.call_stats {
+        call_stats.record(
+            input.request.contract_address,
+            input.request.entry_point_selector,
+        );
+    }
+
     let span = tracing::Span::current();
     let result = tokio::task::spawn_blocking(move || {
         let _g = span.enter();
@@ -122,6 +146,14 @@ pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError
                     .block_header(block_id)
                     .context("Querying block header")?
                     .ok_or(CallError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(CallError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
@@ -134,7 +166,8 @@ pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError
             pending,
             L1BlobDataAvailability::Disabled,
             context.config.custom_versioned_constants,
-        );
+        )
+        .with_fork(context.fork.clone());
 
         let result = pathfinder_executor::call(
             state,
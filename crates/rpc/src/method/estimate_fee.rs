@@ -1,17 +1,25 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockId, BlockNumber};
 use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 use serde::de::Error;
 
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
+use crate::pending::GasPriceOverrides;
+use crate::shadow_execution::{Served, ShadowRequest, ShadowRequestKind};
 use crate::v02::types::request::BroadcastedTransaction;
+use crate::version_gate::UnsupportedVersion;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
     pub request: Vec<BroadcastedTransaction>,
     pub simulation_flags: Vec<SimulationFlag>,
     pub block_id: BlockId,
+    /// Pathfinder extension: also price each estimate in the other fee token.
+    pub include_alternate_unit: bool,
+    /// Pathfinder extension: the gas prices to estimate with on the pending
+    /// block.
+    pub pending_gas_prices: Option<GasPriceOverrides>,
 }
 
 impl crate::dto::DeserializeForVersion for Input {
@@ -22,6 +30,10 @@ impl crate::dto::DeserializeForVersion for Input {
                 simulation_flags: value
                     .deserialize_array("simulation_flags", SimulationFlag::deserialize)?,
                 block_id: value.deserialize_serde("block_id")?,
+                include_alternate_unit: value
+                    .deserialize_optional_serde("include_alternate_unit")?
+                    .unwrap_or_default(),
+                pending_gas_prices: value.deserialize_optional("pending_gas_prices")?,
             })
         })
     }
@@ -42,10 +54,25 @@ impl crate::dto::DeserializeForVersion for SimulationFlag {
     }
 }
 
+/// The estimates, and if requested the same estimates priced in the fee token
+/// each transaction does _not_ pay in.
 #[derive(Debug, PartialEq, Eq)]
-pub struct Output(Vec<pathfinder_executor::types::FeeEstimate>);
+pub struct Output(
+    pub(crate) Vec<pathfinder_executor::types::FeeEstimate>,
+    pub(crate) Option<Vec<pathfinder_executor::types::FeeEstimate>>,
+);
 
 pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, EstimateFeeError> {
+    let fee_tracker = context.fee_tracker.clone();
+    let tracking_keys: Vec<_> = match &fee_tracker {
+        Some(_) => input
+            .request
+            .iter()
+            .map(crate::fee_tracker::tracking_key)
+            .collect(),
+        None => vec![],
+    };
+
     let span = tracing::Span::current();
 
     let result = tokio::task::spawn_blocking(move || {
@@ -63,7 +90,13 @@ pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, E
                     .get(&db)
                     .context("Querying pending data")?;
 
-                (pending.header(), Some(pending.state_update.clone()))
+                let header = pending.simulation_header(
+                    &db,
+                    context.config.pending_gas_price_source,
+                    input.pending_gas_prices,
+                )?;
+
+                (header, Some(pending.state_update.clone()))
             }
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
@@ -71,39 +104,91 @@ pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, E
                     .block_header(block_id)
                     .context("Querying block header")?
                     .ok_or(EstimateFeeError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(EstimateFeeError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
         };
 
+        crate::version_gate::check_transactions(&input.request, header.starknet_version)?;
+
+        let skip_validate = input
+            .simulation_flags
+            .iter()
+            .any(|flag| flag == &SimulationFlag::SkipValidate);
+
+        // Estimates in both units have no shadow counterpart.
+        let shadow = context
+            .shadow_execution
+            .clone()
+            .filter(|shadow| !input.include_alternate_unit && shadow.sample())
+            .map(|shadow| {
+                let request = ShadowRequest {
+                    header: header.clone(),
+                    pending: pending.clone(),
+                    transactions: input.request.clone(),
+                    kind: ShadowRequestKind::Estimate { skip_validate },
+                };
+                (shadow, request)
+            });
+
         let state = ExecutionState::simulation(
             &db,
             context.chain_id,
             header,
             pending,
             L1BlobDataAvailability::Enabled,
-            context.config.custom_versioned_constants,
-        );
-
-        let skip_validate = input
-            .simulation_flags
-            .iter()
-            .any(|flag| flag == &SimulationFlag::SkipValidate);
+            context.config.custom_versioned_constants.clone(),
+        )
+        .with_fork(context.fork.clone());
 
         let transactions = input
             .request
             .into_iter()
-            .map(|tx| crate::executor::map_broadcasted_transaction(&tx, context.chain_id))
+            .map(|tx| {
+                crate::executor::map_broadcasted_transaction(
+                    &tx,
+                    context.chain_id,
+                    context.casm_cache.as_ref(),
+                )
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
-        let result = pathfinder_executor::estimate(state, transactions, skip_validate)?;
+        let output = if input.include_alternate_unit {
+            let (estimates, alternates) =
+                pathfinder_executor::estimate_in_both_units(state, transactions, skip_validate)?
+                    .into_iter()
+                    .unzip();
+            Output(estimates, Some(alternates))
+        } else {
+            let estimates = pathfinder_executor::estimate(state, transactions, skip_validate)?;
+            if let Some((shadow, request)) = shadow {
+                shadow.spawn(context, request, Served::Estimates(&estimates));
+            }
+            Output(estimates, None)
+        };
 
-        Ok::<_, EstimateFeeError>(result)
+        Ok::<_, EstimateFeeError>(output)
     })
     .await
     .context("Executing transaction")??;
 
-    Ok(Output(result.into_iter().map(Into::into).collect()))
+    if let Some(fee_tracker) = fee_tracker {
+        for (key, estimate) in tracking_keys.into_iter().zip(&result.0) {
+            if let Some(key) = key {
+                fee_tracker.estimated(key, estimate);
+            }
+        }
+    }
+
+    Ok(result)
 }
 
 #[derive(Debug)]
@@ -111,10 +196,14 @@ pub enum EstimateFeeError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     TransactionExecutionError {
         transaction_index: usize,
         error: String,
     },
+    UnsupportedStarknetVersion(UnsupportedVersion),
 }
 
 impl From<anyhow::Error> for EstimateFeeError {
@@ -123,6 +212,12 @@ impl From<anyhow::Error> for EstimateFeeError {
     }
 }
 
+impl From<UnsupportedVersion> for EstimateFeeError {
+    fn from(e: UnsupportedVersion) -> Self {
+        Self::UnsupportedStarknetVersion(e)
+    }
+}
+
 impl From<pathfinder_executor::TransactionExecutionError> for EstimateFeeError {
     fn from(value: pathfinder_executor::TransactionExecutionError) -> Self {
         use pathfinder_executor::TransactionExecutionError::*;
@@ -154,6 +249,11 @@ impl From<EstimateFeeError> for ApplicationError {
     fn from(value: EstimateFeeError) -> Self {
         match value {
             EstimateFeeError::BlockNotFound => ApplicationError::BlockNotFound,
+            EstimateFeeError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             EstimateFeeError::TransactionExecutionError {
                 transaction_index,
                 error,
@@ -163,6 +263,9 @@ impl From<EstimateFeeError> for ApplicationError {
             },
             EstimateFeeError::Internal(e) => ApplicationError::Internal(e),
             EstimateFeeError::Custom(e) => ApplicationError::Custom(e),
+            EstimateFeeError::UnsupportedStarknetVersion(e) => {
+                ApplicationError::UnsupportedStarknetVersion(e)
+            }
         }
     }
 }
@@ -172,10 +275,41 @@ impl crate::dto::serialize::SerializeForVersion for Output {
         &self,
         serializer: crate::dto::serialize::Serializer,
     ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
-        serializer.serialize_iter(
-            self.0.len(),
-            &mut self.0.iter().map(crate::dto::FeeEstimate),
-        )
+        match &self.1 {
+            Some(alternates) => serializer.serialize_iter(
+                self.0.len(),
+                &mut self.0.iter().zip(alternates).map(|(estimate, alternate)| {
+                    FeeEstimateWithAlternate {
+                        estimate,
+                        alternate,
+                    }
+                }),
+            ),
+            None => serializer.serialize_iter(
+                self.0.len(),
+                &mut self.0.iter().map(crate::dto::FeeEstimate),
+            ),
+        }
+    }
+}
+
+struct FeeEstimateWithAlternate<'a> {
+    estimate: &'a pathfinder_executor::types::FeeEstimate,
+    alternate: &'a pathfinder_executor::types::FeeEstimate,
+}
+
+impl crate::dto::serialize::SerializeForVersion for FeeEstimateWithAlternate<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::serialize::Serializer,
+    ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.flatten(&crate::dto::FeeEstimate(self.estimate))?;
+        serializer.serialize_field(
+            "alternate_unit_estimate",
+            &crate::dto::FeeEstimate(self.alternate),
+        )?;
+        serializer.end()
     }
 }
 
@@ -372,6 +506,8 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            include_alternate_unit: false,
+            pending_gas_prices: None,
         };
         let result = estimate_fee(context, input).await.unwrap();
         let declare_expected = FeeEstimate {
@@ -417,13 +553,16 @@ mod tests {
         };
         assert_eq!(
             result,
-            Output(vec![
-                declare_expected,
-                deploy_expected,
-                invoke_expected,
-                invoke_v0_expected,
-                invoke_v3_expected,
-            ])
+            Output(
+                vec![
+                    declare_expected,
+                    deploy_expected,
+                    invoke_expected,
+                    invoke_v0_expected,
+                    invoke_v3_expected,
+                ],
+                None
+            )
         );
     }
 
@@ -457,6 +596,8 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            include_alternate_unit: false,
+            pending_gas_prices: None,
         };
         let result = estimate_fee(context, input).await.unwrap();
         let declare_expected = FeeEstimate {
@@ -502,13 +643,16 @@ mod tests {
         };
         assert_eq!(
             result,
-            Output(vec![
-                declare_expected,
-                deploy_expected,
-                invoke_expected,
-                invoke_v0_expected,
-                invoke_v3_expected,
-            ])
+            Output(
+                vec![
+                    declare_expected,
+                    deploy_expected,
+                    invoke_expected,
+                    invoke_v0_expected,
+                    invoke_v3_expected,
+                ],
+                None
+            )
         );
     }
 
@@ -542,6 +686,8 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            include_alternate_unit: false,
+            pending_gas_prices: None,
         };
         let result = super::estimate_fee(context, input).await.unwrap();
         let declare_expected = FeeEstimate {
@@ -587,13 +733,16 @@ mod tests {
         };
         assert_eq!(
             result,
-            Output(vec![
-                declare_expected,
-                deploy_expected,
-                invoke_expected,
-                invoke_v0_expected,
-                invoke_v3_expected,
-            ])
+            Output(
+                vec![
+                    declare_expected,
+                    deploy_expected,
+                    invoke_expected,
+                    invoke_v0_expected,
+                    invoke_v3_expected,
+                ],
+                None
+            )
         );
     }
 
@@ -627,6 +776,8 @@ mod tests {
             ],
             simulation_flags: vec![],
             block_id: BlockId::Number(last_block_header.number),
+            include_alternate_unit: false,
+            pending_gas_prices: None,
         };
         let result = super::estimate_fee(context, input).await.unwrap();
         let declare_expected = FeeEstimate {
@@ -672,13 +823,108 @@ mod tests {
         };
         assert_eq!(
             result,
-            Output(vec![
-                declare_expected,
-                deploy_expected,
-                invoke_expected,
-                invoke_v0_expected,
-                invoke_v3_expected,
-            ])
+            Output(
+                vec![
+                    declare_expected,
+                    deploy_expected,
+                    invoke_expected,
+                    invoke_v0_expected,
+                    invoke_v3_expected,
+                ],
+                None
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn alternate_unit() {
+        let (context, last_block_header, account_contract_address, universal_deployer_address) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(
+                0, 13, 2, 1,
+            ))
+            .await;
+
+        let input = Input {
+            request: vec![
+                declare_transaction(account_contract_address),
+                deploy_transaction(account_contract_address, universal_deployer_address),
+                invoke_transaction(account_contract_address),
+                invoke_v3_transaction(account_contract_address),
+            ],
+            simulation_flags: vec![],
+            block_id: BlockId::Number(last_block_header.number),
+            include_alternate_unit: true,
+            pending_gas_prices: None,
+        };
+        let Output(estimates, alternates) = estimate_fee(context, input).await.unwrap();
+        let alternates = alternates.unwrap();
+
+        assert_eq!(alternates.len(), estimates.len());
+        for (estimate, alternate) in estimates.iter().zip(&alternates) {
+            assert_eq!(alternate.gas_consumed, estimate.gas_consumed);
+            assert_eq!(alternate.data_gas_consumed, estimate.data_gas_consumed);
+            assert_ne!(alternate.unit, estimate.unit);
+        }
+
+        // The invoke v1 pays in WEI, so the alternate is priced in FRI.
+        assert_eq!(
+            alternates[2],
+            FeeEstimate {
+                gas_consumed: 14.into(),
+                // STRK gas price is 2
+                gas_price: 2.into(),
+                overall_fee: 284.into(),
+                unit: PriceUnit::Fri,
+                data_gas_consumed: 128.into(),
+                data_gas_price: 2.into(),
+            }
+        );
+        // The invoke v3 pays in FRI, so the alternate is priced in WEI.
+        assert_eq!(
+            alternates[3],
+            FeeEstimate {
+                gas_consumed: 14.into(),
+                gas_price: 1.into(),
+                overall_fee: 270.into(),
+                unit: PriceUnit::Wei,
+                data_gas_consumed: 128.into(),
+                data_gas_price: 2.into(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn v3_transactions_before_starknet_0_13() {
+        let (context, last_block_header, account_contract_address, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(
+                0, 12, 3, 0,
+            ))
+            .await;
+
+        let input = Input {
+            request: vec![
+                invoke_transaction(account_contract_address),
+                invoke_v3_transaction(account_contract_address),
+            ],
+            simulation_flags: vec![],
+            block_id: BlockId::Number(last_block_header.number),
+            include_alternate_unit: false,
+            pending_gas_prices: None,
+        };
+        let result = estimate_fee(context, input).await;
+
+        assert_matches::assert_matches!(
+            result,
+            Err(EstimateFeeError::UnsupportedStarknetVersion(unsupported)) => {
+                assert_eq!(
+                    unsupported,
+                    UnsupportedVersion {
+                        feature: "version 3 transactions",
+                        block_version: StarknetVersion::new(0, 12, 3, 0),
+                        minimum_version: StarknetVersion::new(0, 13, 0, 0),
+                    }
+                );
+            }
         );
     }
 }
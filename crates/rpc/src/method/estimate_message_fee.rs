@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use pathfinder_common::{BlockId, CallParam, ChainId, TransactionNonce};
+use pathfinder_common::{BlockId, BlockNumber, CallParam, ChainId, TransactionNonce};
 use pathfinder_crypto::Felt;
 use pathfinder_executor::{ExecutionState, IntoStarkFelt, L1BlobDataAvailability};
 use starknet_api::core::PatriciaKey;
@@ -34,7 +34,13 @@ pub async fn estimate_message_fee(
                     .get(&db)
                     .context("Querying pending data")?;
 
-                (pending.header(), Some(pending.state_update.clone()))
+                let header = pending.simulation_header(
+                    &db,
+                    context.config.pending_gas_price_source,
+                    None,
+                )?;
+
+                (header, Some(pending.state_update.clone()))
             }
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
@@ -42,6 +48,14 @@ pub async fn estimate_message_fee(
                     .block_header(block_id)
                     .context("Querying block header")?
                     .ok_or(EstimateMessageFeeError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(EstimateMessageFeeError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
@@ -148,8 +162,13 @@ impl crate::dto::serialize::SerializeForVersion for Output {
 pub enum EstimateMessageFeeError {
     Internal(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     ContractNotFound,
-    ContractError { revert_error: String },
+    ContractError {
+        revert_error: String,
+    },
     Custom(anyhow::Error),
 }
 
@@ -186,6 +205,11 @@ impl From<EstimateMessageFeeError> for ApplicationError {
     fn from(value: EstimateMessageFeeError) -> Self {
         match value {
             EstimateMessageFeeError::BlockNotFound => ApplicationError::BlockNotFound,
+            EstimateMessageFeeError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             EstimateMessageFeeError::ContractNotFound => ApplicationError::ContractNotFound,
             EstimateMessageFeeError::ContractError { revert_error } => {
                 ApplicationError::ContractError {
@@ -1,9 +1,40 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, ClassHash, ContractAddress};
+use pathfinder_common::{BlockId, BlockNumber, ClassHash, ContractAddress};
 
 use crate::context::RpcContext;
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ContractNotFound);
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    ContractNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(internal) => Self::Internal(internal),
+            Error::Custom(internal) => Self::Custom(internal),
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::ContractNotFound => Self::ContractNotFound,
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
@@ -60,6 +91,15 @@ pub async fn get_class_hash_at(context: RpcContext, input: Input) -> Result<Outp
             return Err(Error::BlockNotFound);
         }
 
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(Error::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         tx.contract_class_hash(block_id, input.contract_address)
             .context("Fetching class hash from database")?
             .ok_or(Error::ContractNotFound)
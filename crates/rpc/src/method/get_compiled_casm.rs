@@ -0,0 +1,138 @@
+use anyhow::Context;
+use pathfinder_common::ClassHash;
+
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(Error: ClassHashNotFound, CompilationFailed);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    class_hash: ClassHash,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                class_hash: ClassHash(value.deserialize("class_hash")?),
+            })
+        })
+    }
+}
+
+/// The compiled class definition, as stored.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(transparent)]
+pub struct Output(serde_json::Value);
+
+/// Returns the CASM the node executes for a Sierra class, along with the
+/// version of the compiler which produced it.
+///
+/// Cairo 0 classes have no CASM and are reported as not found. Classes which
+/// could neither be compiled nor fetched from the gateway are reported as
+/// having failed to compile.
+pub async fn get_compiled_casm(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let definition = db
+            .casm_definition(input.class_hash)
+            .context("Querying compiled class definition")?
+            .ok_or(Error::ClassHashNotFound)?;
+
+        let mut casm = match serde_json::from_slice::<serde_json::Value>(&definition) {
+            Ok(casm @ serde_json::Value::Object(_)) => casm,
+            _ => return Err(Error::CompilationFailed),
+        };
+
+        if let Some(compiler_version) = db
+            .casm_compiler_version(input.class_hash)
+            .context("Querying compiler version")?
+        {
+            casm["compiler_version"] = compiler_version.into();
+        }
+
+        Ok(Output(casm))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::SierraHash;
+    use starknet_gateway_test_fixtures::class_definitions::CAIRO_1_1_0_BALANCE_CASM_JSON;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn sierra_class() {
+        let context = RpcContext::for_tests();
+        let class_hash = class_hash_bytes!(b"balance");
+
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.insert_sierra_class(
+                &SierraHash(class_hash.0),
+                b"sierra definition",
+                &casm_hash_bytes!(b"balance casm"),
+                CAIRO_1_1_0_BALANCE_CASM_JSON,
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let output = get_compiled_casm(context, Input { class_hash })
+            .await
+            .unwrap();
+
+        let expected = serde_json::from_slice(CAIRO_1_1_0_BALANCE_CASM_JSON).unwrap();
+        assert_eq!(output, Output(expected));
+        assert_eq!(output.0["compiler_version"], "1.1.0");
+    }
+
+    #[tokio::test]
+    async fn cairo_class() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            class_hash: class_hash_bytes!(b"class 0 hash"),
+        };
+
+        let result = get_compiled_casm(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::ClassHashNotFound));
+    }
+
+    #[tokio::test]
+    async fn class_not_found() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            class_hash: class_hash_bytes!(b"non-existent"),
+        };
+
+        let result = get_compiled_casm(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::ClassHashNotFound));
+    }
+
+    #[tokio::test]
+    async fn compilation_failed() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            class_hash: class_hash_bytes!(b"class 2 hash (sierra)"),
+        };
+
+        let result = get_compiled_casm(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::CompilationFailed));
+    }
+}
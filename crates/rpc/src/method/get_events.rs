@@ -1,7 +1,8 @@
 use std::str::FromStr;
 
 use anyhow::Context;
-use pathfinder_common::{BlockId, BlockNumber, ContractAddress, EventKey};
+use pathfinder_common::event::Event;
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, EventKey, TransactionHash};
 use pathfinder_storage::EventFilterError;
 use starknet_gateway_types::reply::PendingBlock;
 use tokio::task::JoinHandle;
@@ -207,6 +208,7 @@ pub async fn get_events(
                 &filter,
                 context.config.get_events_max_blocks_to_scan,
                 context.config.get_events_max_uncached_bloom_filters_to_load,
+                context.config.get_events_max_page_bytes,
             )
             .map_err(|e| match e {
                 EventFilterError::PageSizeTooBig(_) => GetEventsError::PageSizeTooBig,
@@ -221,6 +223,7 @@ pub async fn get_events(
                 ContinuationToken {
                     block_number: token.block_number,
                     offset: token.offset,
+                    pending_anchor: None,
                 }
                 .to_string()
             }),
@@ -236,37 +239,36 @@ pub async fn get_events(
             if events.events.len() < request.chunk_size {
                 let amount = request.chunk_size - events.events.len();
 
-                let current_offset = match continuation_token {
-                    Some(continuation_token) => {
-                        continuation_token.offset_in_block(pending.number)?
-                    }
-                    None => 0,
-                };
-
                 let keys: Vec<std::collections::HashSet<_>> = request
                     .keys
                     .into_iter()
                     .map(|keys| keys.into_iter().collect())
                     .collect();
 
-                let is_last_page = append_pending_events(
+                let current_offset = match continuation_token {
+                    Some(continuation_token) => {
+                        continuation_token.offset_in_pending(&pending, request.address, &keys)?
+                    }
+                    None => 0,
+                };
+
+                let next_event = append_pending_events(
                     &pending.block,
                     &mut events.events,
                     current_offset,
                     amount,
                     request.address,
-                    keys,
+                    &keys,
                 );
 
-                events.continuation_token = if is_last_page {
-                    None
-                } else {
-                    let continuation_token = ContinuationToken {
+                events.continuation_token = next_event.map(|anchor| {
+                    ContinuationToken {
                         block_number: pending.number,
                         offset: current_offset + amount,
-                    };
-                    Some(continuation_token.to_string())
-                };
+                        pending_anchor: Some(anchor),
+                    }
+                    .to_string()
+                });
             } else {
                 // We have a full page from the database, but there might be more pending
                 // events. Return a continuation token for the pending block.
@@ -274,6 +276,7 @@ pub async fn get_events(
                     ContinuationToken {
                         block_number: pending.number,
                         offset: 0,
+                        pending_anchor: None,
                     }
                     .to_string(),
                 );
@@ -295,39 +298,38 @@ fn get_pending_events(
     pending: &PendingData,
     continuation_token: Option<ContinuationToken>,
 ) -> Result<types::GetEventsResult, GetEventsError> {
-    let current_offset = match continuation_token {
-        Some(continuation_token) => continuation_token.offset_in_block(pending.number)?,
-        None => 0,
-    };
-
     let keys: Vec<std::collections::HashSet<_>> = request
         .keys
         .iter()
         .map(|keys| keys.iter().copied().collect())
         .collect();
 
+    let current_offset = match continuation_token {
+        Some(continuation_token) => {
+            continuation_token.offset_in_pending(pending, request.address, &keys)?
+        }
+        None => 0,
+    };
+
     let mut events = Vec::new();
 
-    let is_last_page = append_pending_events(
+    let next_event = append_pending_events(
         &pending.block,
         &mut events,
         current_offset,
         request.chunk_size,
         request.address,
-        keys,
+        &keys,
     );
 
-    let continuation_token = if is_last_page {
-        None
-    } else {
-        Some(
-            ContinuationToken {
-                block_number: pending.number,
-                offset: current_offset + request.chunk_size,
-            }
-            .to_string(),
-        )
-    };
+    let continuation_token = next_event.map(|anchor| {
+        ContinuationToken {
+            block_number: pending.number,
+            offset: current_offset + request.chunk_size,
+            pending_anchor: Some(anchor),
+        }
+        .to_string()
+    });
 
     Ok(types::GetEventsResult {
         events,
@@ -395,99 +397,151 @@ fn map_from_block_to_number(
     }
 }
 
-/// Append's pending events to `dst` based on the filter requirements and
-/// returns true if this was the last pending data i.e. `is_last_page`.
-fn append_pending_events(
-    pending_block: &PendingBlock,
-    dst: &mut Vec<types::EmittedEvent>,
-    skip: usize,
-    amount: usize,
+/// Returns the pending events matching the filter, together with the hash of
+/// the emitting transaction and the event's index amongst the matching events
+/// of that transaction.
+fn matching_pending_events<'a>(
+    pending_block: &'a PendingBlock,
     address: Option<ContractAddress>,
-    keys: Vec<std::collections::HashSet<EventKey>>,
-) -> bool {
-    let original_len = dst.len();
-
+    keys: &'a [std::collections::HashSet<EventKey>],
+) -> impl Iterator<Item = (&'a Event, TransactionHash, usize)> + 'a {
     let key_filter_is_empty = keys.iter().flatten().count() == 0;
 
-    let pending_events = pending_block
+    pending_block
         .transaction_receipts
         .iter()
-        .flat_map(|(receipt, events)| {
+        .flat_map(move |(receipt, events)| {
             events
                 .iter()
-                .zip(std::iter::repeat(receipt.transaction_hash))
-        })
-        .filter(|(event, _)| match address {
-            Some(address) => event.from_address == address,
-            None => true,
-        })
-        .filter(|(event, _)| {
-            if key_filter_is_empty {
-                return true;
-            }
+                .filter(move |event| match address {
+                    Some(address) => event.from_address == address,
+                    None => true,
+                })
+                .filter(move |event| {
+                    if key_filter_is_empty {
+                        return true;
+                    }
 
-            if event.keys.len() < keys.len() {
-                return false;
-            }
+                    if event.keys.len() < keys.len() {
+                        return false;
+                    }
 
-            event
-                .keys
-                .iter()
-                .zip(keys.iter())
-                .all(|(key, filter)| filter.is_empty() || filter.contains(key))
+                    event
+                        .keys
+                        .iter()
+                        .zip(keys.iter())
+                        .all(|(key, filter)| filter.is_empty() || filter.contains(key))
+                })
+                .enumerate()
+                .map(move |(index, event)| (event, receipt.transaction_hash, index))
         })
-        .skip(skip)
-        // We need to take an extra event to determine is_last_page.
-        .take(amount + 1)
-        .map(|(event, tx_hash)| types::EmittedEvent {
-            data: event.data.clone(),
-            keys: event.keys.clone(),
-            from_address: event.from_address,
-            block_hash: None,
-            block_number: None,
-            transaction_hash: tx_hash,
-        });
+}
 
-    dst.extend(pending_events);
-    let is_last_page = dst.len() <= (original_len + amount);
-    if !is_last_page {
-        dst.pop();
-    }
+/// Append's pending events to `dst` based on the filter requirements.
+///
+/// Returns the anchor of the first matching event which did not fit into
+/// `dst`, or [None] if this was the last page.
+fn append_pending_events(
+    pending_block: &PendingBlock,
+    dst: &mut Vec<types::EmittedEvent>,
+    skip: usize,
+    amount: usize,
+    address: Option<ContractAddress>,
+    keys: &[std::collections::HashSet<EventKey>],
+) -> Option<PendingAnchor> {
+    let mut pending_events = matching_pending_events(pending_block, address, keys).skip(skip);
+
+    dst.extend(
+        pending_events
+            .by_ref()
+            .take(amount)
+            .map(|(event, tx_hash, _)| types::EmittedEvent {
+                data: event.data.clone(),
+                keys: event.keys.clone(),
+                from_address: event.from_address,
+                block_hash: None,
+                block_number: None,
+                transaction_hash: tx_hash,
+            }),
+    );
 
-    is_last_page
+    pending_events
+        .next()
+        .map(|(_, transaction_hash, event_index)| PendingAnchor {
+            transaction_hash,
+            event_index,
+        })
+}
+
+/// Identifies a pending event by the transaction that emitted it instead of by
+/// its position in the pending block.
+///
+/// The pending block may be resealed between two requests, which can shift
+/// event positions around. Transaction hashes are stable across reseals so
+/// they let us resume paging deterministically.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PendingAnchor {
+    transaction_hash: TransactionHash,
+    /// Index of the event amongst the matching events of the transaction.
+    event_index: usize,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct ContinuationToken {
     block_number: BlockNumber,
     offset: usize,
+    /// Only set for tokens pointing into the pending block.
+    pending_anchor: Option<PendingAnchor>,
 }
 
 impl FromStr for ContinuationToken {
     type Err = ParseContinuationTokenError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some((block_number, offset)) = s.split_once('-') {
-            let block_number = block_number
-                .parse::<u64>()
-                .map_err(|_| ParseContinuationTokenError)?;
-            let offset = offset.parse().map_err(|_| ParseContinuationTokenError)?;
+        let parts = s.split('-').collect::<Vec<_>>();
+        let (block_number, offset, pending_anchor) = match parts.as_slice() {
+            [block_number, offset] => (block_number, offset, None),
+            [block_number, offset, transaction_hash, event_index] => {
+                let transaction_hash = transaction_hash
+                    .strip_prefix("0x")
+                    .ok_or(ParseContinuationTokenError)?;
+                let transaction_hash = pathfinder_crypto::Felt::from_hex_str(transaction_hash)
+                    .map_err(|_| ParseContinuationTokenError)?;
+                let event_index = event_index
+                    .parse()
+                    .map_err(|_| ParseContinuationTokenError)?;
+
+                let anchor = PendingAnchor {
+                    transaction_hash: TransactionHash(transaction_hash),
+                    event_index,
+                };
+                (block_number, offset, Some(anchor))
+            }
+            _ => return Err(ParseContinuationTokenError),
+        };
 
-            let block_number = BlockNumber::new(block_number).ok_or(ParseContinuationTokenError)?;
+        let block_number = block_number
+            .parse::<u64>()
+            .map_err(|_| ParseContinuationTokenError)?;
+        let offset = offset.parse().map_err(|_| ParseContinuationTokenError)?;
 
-            Ok(ContinuationToken {
-                block_number,
-                offset,
-            })
-        } else {
-            Err(ParseContinuationTokenError)
-        }
+        let block_number = BlockNumber::new(block_number).ok_or(ParseContinuationTokenError)?;
+
+        Ok(ContinuationToken {
+            block_number,
+            offset,
+            pending_anchor,
+        })
     }
 }
 
 impl std::fmt::Display for ContinuationToken {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}-{}", self.block_number.get(), self.offset)
+        write!(f, "{}-{}", self.block_number.get(), self.offset)?;
+        if let Some(anchor) = &self.pending_anchor {
+            write!(f, "-{}-{}", anchor.transaction_hash, anchor.event_index)?;
+        }
+        Ok(())
     }
 }
 
@@ -501,6 +555,31 @@ impl ContinuationToken {
         }
     }
 
+    /// Resolves the offset within the current pending block.
+    ///
+    /// Tokens which carry a [PendingAnchor] for this pending block are
+    /// resolved by locating the anchored event, so that they remain valid
+    /// even if the pending block was resealed in the meantime.
+    fn offset_in_pending(
+        &self,
+        pending: &PendingData,
+        address: Option<ContractAddress>,
+        keys: &[std::collections::HashSet<EventKey>],
+    ) -> Result<usize, GetEventsError> {
+        match self.pending_anchor {
+            Some(anchor) if self.block_number == pending.number => {
+                matching_pending_events(&pending.block, address, keys)
+                    .position(|(_, transaction_hash, event_index)| {
+                        transaction_hash == anchor.transaction_hash
+                            && event_index == anchor.event_index
+                    })
+                    // The anchored transaction is no longer part of the pending block.
+                    .ok_or(GetEventsError::InvalidContinuationToken)
+            }
+            _ => self.offset_in_block(pending.number),
+        }
+    }
+
     fn start_block_and_offset(
         &self,
         from_block: Option<BlockNumber>,
@@ -648,13 +727,32 @@ mod tests {
             Err(ParseContinuationTokenError)
         );
 
+        assert_matches!(
+            "1234-5678-abcd-0".parse::<ContinuationToken>(),
+            Err(ParseContinuationTokenError)
+        );
+
         assert_eq!(
             "1234-4567".parse::<ContinuationToken>().unwrap(),
             ContinuationToken {
                 block_number: BlockNumber::new_or_panic(1234),
-                offset: 4567
+                offset: 4567,
+                pending_anchor: None,
             }
         );
+
+        let anchored = ContinuationToken {
+            block_number: BlockNumber::new_or_panic(1234),
+            offset: 4567,
+            pending_anchor: Some(PendingAnchor {
+                transaction_hash: transaction_hash!("0xabcd"),
+                event_index: 2,
+            }),
+        };
+        assert_eq!(
+            anchored.to_string().parse::<ContinuationToken>().unwrap(),
+            anchored
+        );
     }
 
     fn setup() -> (RpcContext, Vec<EmittedEvent>) {
@@ -901,6 +999,21 @@ mod tests {
 
         use super::*;
 
+        /// Token pointing at the `event_index`-th event of the first pending
+        /// transaction, which emits all of the pending events in the test
+        /// data.
+        fn pending_token(offset: usize, event_index: usize) -> String {
+            ContinuationToken {
+                block_number: BlockNumber::new_or_panic(3),
+                offset,
+                pending_anchor: Some(PendingAnchor {
+                    transaction_hash: transaction_hash_bytes!(b"pending tx hash 0"),
+                    event_index,
+                }),
+            }
+            .to_string()
+        }
+
         #[tokio::test]
         async fn backward_range() {
             let context = RpcContext::for_tests_with_pending().await;
@@ -982,13 +1095,13 @@ mod tests {
             input.filter.continuation_token = None;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[0..2]);
-            assert_eq!(result.continuation_token, Some("3-1".to_string()));
+            assert_eq!(result.continuation_token, Some(pending_token(1, 1)));
 
             input.filter.chunk_size = 1;
             input.filter.continuation_token = result.continuation_token;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[2..3]);
-            assert_eq!(result.continuation_token, Some("3-2".to_string()));
+            assert_eq!(result.continuation_token, Some(pending_token(2, 2)));
 
             input.filter.chunk_size = 100; // Only a single event remains though
             input.filter.continuation_token = result.continuation_token;
@@ -1018,6 +1131,55 @@ mod tests {
             assert_eq!(error, GetEventsError::InvalidContinuationToken);
         }
 
+        #[tokio::test]
+        async fn paging_survives_pending_reseal() {
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let mut input = GetEventsInput {
+                filter: EventFilter {
+                    from_block: Some(BlockId::Pending),
+                    to_block: Some(BlockId::Pending),
+                    chunk_size: 1,
+                    ..Default::default()
+                },
+            };
+
+            let first_page = get_events(context.clone(), input.clone()).await.unwrap();
+            assert_eq!(first_page.continuation_token, Some(pending_token(1, 1)));
+
+            // Reseal the pending block with an additional transaction emitting an event
+            // in front of the ones we've already paged through.
+            let mut pending = context.pending_data.get_unchecked();
+            let mut block = (*pending.block).clone();
+            block.transaction_receipts.insert(
+                0,
+                (
+                    pathfinder_common::receipt::Receipt {
+                        transaction_hash: transaction_hash_bytes!(b"resealed tx hash"),
+                        ..Default::default()
+                    },
+                    vec![Event {
+                        data: vec![],
+                        from_address: contract_address!("0x1234"),
+                        keys: vec![event_key_bytes!(b"resealed key")],
+                    }],
+                ),
+            );
+            pending.block = block.into();
+            let (_tx, rx) = tokio::sync::watch::channel(pending);
+            let resealed = context.clone().with_pending_data(rx);
+
+            // The old token still points at the second event of the first pending
+            // transaction, even though its position in the block has moved.
+            input.filter.chunk_size = 100;
+            input.filter.continuation_token = first_page.continuation_token;
+            let result = get_events(resealed, input.clone()).await.unwrap();
+            let expected = get_events(context, input).await.unwrap();
+            assert_eq!(result.events, expected.events);
+            assert_eq!(result.events.len(), 2);
+            assert_eq!(result.continuation_token, None);
+        }
+
         #[tokio::test]
         async fn paging_with_no_more_matching_events_in_pending() {
             let context = RpcContext::for_tests_with_pending().await;
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, ContractAddress, ContractNonce};
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, ContractNonce};
 
 use crate::context::RpcContext;
 
@@ -23,7 +23,38 @@ impl crate::dto::DeserializeForVersion for Input {
 #[derive(Debug)]
 pub struct Output(ContractNonce);
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ContractNotFound);
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    ContractNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(internal) => Self::Internal(internal),
+            Error::Custom(internal) => Self::Custom(internal),
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::ContractNotFound => Self::ContractNotFound,
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 pub async fn get_nonce(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
@@ -60,6 +91,15 @@ pub async fn get_nonce(context: RpcContext, input: Input) -> Result<Output, Erro
             return Err(Error::BlockNotFound);
         }
 
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(Error::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         let nonce = tx
             .contract_nonce(input.contract_address, block_id)
             .context("Querying contract nonce from database")?;
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use pathfinder_common::{BlockId, StateUpdate};
+use pathfinder_common::{BlockId, BlockNumber, StateUpdate};
 
 use crate::{dto, RpcContext};
 
@@ -20,7 +20,36 @@ impl crate::dto::DeserializeForVersion for Input {
     }
 }
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(internal) => Self::Internal(internal),
+            Error::Custom(internal) => Self::Custom(internal),
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 #[derive(PartialEq, Debug)]
 pub enum Output {
@@ -67,6 +96,16 @@ pub async fn get_state_update(context: RpcContext, input: Input) -> Result<Outpu
             .try_into()
             .expect("Only pending cast should fail");
 
+        // The state diffs of blocks whose state has been pruned are incomplete.
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(Error::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         let state_update = tx
             .state_update(block_id)
             .context("Fetching state diff")?
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, ContractAddress, StorageAddress, StorageValue};
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, StorageAddress, StorageValue};
 
 use crate::context::RpcContext;
 
@@ -25,7 +25,38 @@ impl crate::dto::DeserializeForVersion for Input {
 #[derive(Debug)]
 pub struct Output(StorageValue);
 
-crate::error::generate_rpc_error_subset!(Error: ContractNotFound, BlockNotFound);
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    ContractNotFound,
+    BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(internal) => Self::Internal(internal),
+            Error::Custom(internal) => Self::Custom(internal),
+            Error::ContractNotFound => Self::ContractNotFound,
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 /// Get the value of the storage at the given address and key.
 pub async fn get_storage_at(context: RpcContext, input: Input) -> Result<Output, Error> {
@@ -62,6 +93,15 @@ pub async fn get_storage_at(context: RpcContext, input: Input) -> Result<Output,
             return Err(Error::BlockNotFound);
         }
 
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(Error::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         let value = tx
             .storage_value(block_id, input.contract_address, input.key)
             .context("Querying storage value")?;
@@ -94,7 +134,6 @@ impl crate::dto::serialize::SerializeForVersion for Output {
 mod tests {
     use assert_matches::assert_matches;
     use pathfinder_common::macro_prelude::*;
-    use pathfinder_common::BlockNumber;
     use serde_json::json;
 
     use super::*;
@@ -269,6 +308,46 @@ mod tests {
         assert_eq!(result.0, storage_value_bytes!(b"storage value 1"));
     }
 
+    #[tokio::test]
+    async fn state_pruned() {
+        let ctx = RpcContext::for_tests_with_pending().await;
+        {
+            let mut db = ctx.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.prune_state_updates(BlockNumber::GENESIS + 2, 10)
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        let contract_address = contract_address_bytes!(b"contract 1");
+        let key = storage_address_bytes!(b"storage addr 0");
+
+        let result = get_storage_at(
+            ctx.clone(),
+            Input {
+                contract_address,
+                key,
+                block_id: BlockId::Number(BlockNumber::GENESIS + 1),
+            },
+        )
+        .await;
+        assert_matches!(
+            result,
+            Err(Error::StatePruned { earliest_available_block }) if earliest_available_block == BlockNumber::GENESIS + 2
+        );
+
+        let result = get_storage_at(
+            ctx,
+            Input {
+                contract_address,
+                key,
+                block_id: BlockId::Number(BlockNumber::GENESIS + 2),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.0, storage_value_bytes!(b"storage value 2"));
+    }
+
     #[tokio::test]
     async fn unknown_contract() {
         let ctx = RpcContext::for_tests_with_pending().await;
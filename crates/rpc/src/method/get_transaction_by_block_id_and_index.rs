@@ -53,7 +53,7 @@ pub async fn get_transaction_by_block_id_and_index(
                 let result = context
                     .pending_data
                     .get(&db_tx)
-                    .context("Querying pending dat")?
+                    .context("Querying pending data")?
                     .block
                     .transactions
                     .get(index)
@@ -93,6 +93,7 @@ pub async fn get_transaction_by_block_id_and_index(
 #[cfg(test)]
 mod tests {
     use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::TransactionHash;
 
     use super::*;
 
@@ -135,4 +136,54 @@ mod tests {
             )
         }
     }
+
+    #[rstest::rstest]
+    #[case::first(0, transaction_hash_bytes!(b"pending tx hash 0"))]
+    #[case::last(2, transaction_hash_bytes!(b"pending reverted"))]
+    #[tokio::test]
+    async fn pending(#[case] index: u64, #[case] expected: TransactionHash) {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = Input {
+            block_id: BlockId::Pending,
+            index: TransactionIndex::new_or_panic(index),
+        };
+
+        let result = get_transaction_by_block_id_and_index(context, input)
+            .await
+            .unwrap();
+
+        assert_eq!(result.transaction_hash, expected);
+    }
+
+    #[tokio::test]
+    async fn pending_invalid_index() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = Input {
+            block_id: BlockId::Pending,
+            index: TransactionIndex::new_or_panic(3),
+        };
+
+        let result = get_transaction_by_block_id_and_index(context, input).await;
+
+        assert_matches::assert_matches!(
+            result,
+            Err(GetTransactionByBlockIdAndIndexError::InvalidTxnIndex)
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            block_id: block_hash_bytes!(b"invalid").into(),
+            index: TransactionIndex::new_or_panic(0),
+        };
+
+        let result = get_transaction_by_block_id_and_index(context, input).await;
+
+        assert_matches::assert_matches!(
+            result,
+            Err(GetTransactionByBlockIdAndIndexError::BlockNotFound)
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use std::num::NonZeroUsize;
+
 use anyhow::Context;
 use pathfinder_common::event::Event;
 use pathfinder_common::receipt::Receipt;
@@ -28,12 +30,14 @@ pub enum Output {
         receipt: Receipt,
         transaction: Transaction,
         events: Vec<Event>,
+        events_truncated: bool,
         finality: dto::TxnFinalityStatus,
     },
     Pending {
         receipt: Receipt,
         transaction: Transaction,
         events: Vec<Event>,
+        events_truncated: bool,
     },
 }
 
@@ -42,36 +46,62 @@ impl serialize::SerializeForVersion for Output {
         &self,
         serializer: serialize::Serializer,
     ) -> Result<serialize::Ok, serialize::Error> {
-        match self {
+        let (receipt, events_truncated) = match self {
             Output::Full {
                 block_hash,
                 block_number,
                 receipt,
                 transaction,
                 events,
+                events_truncated,
                 finality,
-            } => dto::TxnReceiptWithBlockInfo {
-                block_hash: Some(block_hash),
-                block_number: Some(*block_number),
-                receipt,
-                transaction,
-                events,
-                finality: *finality,
-            },
+            } => (
+                dto::TxnReceiptWithBlockInfo {
+                    block_hash: Some(block_hash),
+                    block_number: Some(*block_number),
+                    receipt,
+                    transaction,
+                    events,
+                    finality: *finality,
+                },
+                *events_truncated,
+            ),
             Output::Pending {
                 receipt,
                 transaction,
                 events,
-            } => dto::TxnReceiptWithBlockInfo {
-                block_hash: None,
-                block_number: None,
-                receipt,
-                transaction,
-                events,
-                finality: dto::TxnFinalityStatus::AcceptedOnL2,
-            },
+                events_truncated,
+            } => (
+                dto::TxnReceiptWithBlockInfo {
+                    block_hash: None,
+                    block_number: None,
+                    receipt,
+                    transaction,
+                    events,
+                    finality: dto::TxnFinalityStatus::AcceptedOnL2,
+                },
+                *events_truncated,
+            ),
+        };
+
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.flatten(&receipt)?;
+        // Not part of the specification, so only present if events were left out.
+        if events_truncated {
+            serializer.serialize_field("events_truncated", &true)?;
         }
-        .serialize(serializer)
+        serializer.end()
+    }
+}
+
+/// Truncates `events` to `limit`, returning whether any events were removed.
+fn truncate_events(events: &mut Vec<Event>, limit: Option<NonZeroUsize>) -> bool {
+    match limit {
+        Some(limit) if events.len() > limit.get() => {
+            events.truncate(limit.get());
+            true
+        }
+        _ => false,
     }
 }
 
@@ -95,21 +125,24 @@ pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Resul
             .get(&db_tx)
             .context("Querying pending data")?;
 
-        if let Some((transaction, (receipt, events))) = pending
+        if let Some((transaction, (receipt, mut events))) = pending
             .block
             .transactions
             .iter()
             .zip(pending.block.transaction_receipts.iter())
             .find_map(|(t, r)| (t.hash == input.transaction_hash).then(|| (t.clone(), r.clone())))
         {
+            let events_truncated =
+                truncate_events(&mut events, context.config.receipt_events_limit);
             return Ok(Output::Pending {
                 receipt,
                 transaction,
                 events,
+                events_truncated,
             });
         }
 
-        let (transaction, receipt, events, block_number) = db_tx
+        let (transaction, receipt, mut events, block_number) = db_tx
             .transaction_with_receipt(input.transaction_hash)
             .context("Reading transaction receipt from database")?
             .ok_or(Error::TxnHashNotFound)?;
@@ -129,10 +162,13 @@ pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Resul
             dto::TxnFinalityStatus::AcceptedOnL2
         };
 
+        let events_truncated = truncate_events(&mut events, context.config.receipt_events_limit);
+
         Ok(Output::Full {
             transaction,
             receipt,
             events,
+            events_truncated,
             block_hash,
             block_number,
             finality,
@@ -141,3 +177,45 @@ pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Resul
     .await
     .context("Joining blocking task")?
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::dto::serialize::SerializeForVersion;
+    use crate::RpcVersion;
+
+    async fn receipt(limit: Option<usize>) -> serde_json::Value {
+        let mut context = RpcContext::for_tests_with_pending().await;
+        context.config.receipt_events_limit = limit.map(|limit| limit.try_into().unwrap());
+        let input = Input {
+            transaction_hash: transaction_hash_bytes!(b"pending tx hash 0"),
+        };
+
+        get_transaction_receipt(context, input)
+            .await
+            .unwrap()
+            .serialize(serialize::Serializer::new(RpcVersion::V07))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn events_truncated() {
+        let output = receipt(Some(2)).await;
+
+        assert_eq!(output["events"].as_array().unwrap().len(), 2);
+        assert_eq!(output["events_truncated"], true);
+    }
+
+    #[rstest::rstest]
+    #[case::unlimited(None)]
+    #[case::within_limit(Some(3))]
+    #[tokio::test]
+    async fn events_not_truncated(#[case] limit: Option<usize>) {
+        let output = receipt(limit).await;
+
+        assert_eq!(output["events"].as_array().unwrap().len(), 3);
+        assert!(output.get("events_truncated").is_none());
+    }
+}
@@ -6,7 +6,7 @@ use crate::dto::TxnExecutionStatus;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
-    transaction_hash: TransactionHash,
+    pub(crate) transaction_hash: TransactionHash,
 }
 
 impl crate::dto::DeserializeForVersion for Input {
@@ -19,7 +19,7 @@ impl crate::dto::DeserializeForVersion for Input {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Output {
     Received,
     Rejected,
@@ -1,12 +1,14 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockId, BlockNumber};
 use pathfinder_executor::TransactionExecutionError;
 
 use crate::context::RpcContext;
 use crate::executor::ExecutionStateError;
+use crate::shadow_execution::{Served, ShadowRequest, ShadowRequestKind};
 use crate::v06::method::simulate_transactions as v06;
+use crate::version_gate::UnsupportedVersion;
 
-pub struct Output(Vec<pathfinder_executor::types::TransactionSimulation>);
+pub struct Output(pub(crate) Vec<pathfinder_executor::types::TransactionSimulation>);
 
 pub async fn simulate_transactions(
     context: RpcContext,
@@ -41,7 +43,13 @@ pub async fn simulate_transactions(
                     .get(&db)
                     .context("Querying pending data")?;
 
-                (pending.header(), Some(pending.state_update.clone()))
+                let header = pending.simulation_header(
+                    &db,
+                    context.config.pending_gas_price_source,
+                    None,
+                )?;
+
+                (header, Some(pending.state_update.clone()))
             }
             other => {
                 let block_id = other.try_into().expect("Only pending should fail");
@@ -50,28 +58,67 @@ pub async fn simulate_transactions(
                     .block_header(block_id)
                     .context("Fetching block header")?
                     .ok_or(SimulateTransactionError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(SimulateTransactionError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
         };
 
+        crate::version_gate::check_transactions(&input.transactions, header.starknet_version)?;
+
+        let shadow = context
+            .shadow_execution
+            .clone()
+            .filter(|shadow| shadow.sample())
+            .map(|shadow| {
+                let request = ShadowRequest {
+                    header: header.clone(),
+                    pending: pending.clone(),
+                    transactions: input.transactions.clone(),
+                    kind: ShadowRequestKind::Simulate {
+                        skip_validate,
+                        skip_fee_charge,
+                    },
+                };
+                (shadow, request)
+            });
+
         let state = pathfinder_executor::ExecutionState::simulation(
             &db,
             context.chain_id,
             header,
             pending,
             pathfinder_executor::L1BlobDataAvailability::Enabled,
-            context.config.custom_versioned_constants,
-        );
+            context.config.custom_versioned_constants.clone(),
+        )
+        .with_fork(context.fork.clone());
 
         let transactions = input
             .transactions
             .into_iter()
-            .map(|tx| crate::executor::map_broadcasted_transaction(&tx, context.chain_id))
+            .map(|tx| {
+                crate::executor::map_broadcasted_transaction(
+                    &tx,
+                    context.chain_id,
+                    context.casm_cache.as_ref(),
+                )
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
         let txs =
             pathfinder_executor::simulate(state, transactions, skip_validate, skip_fee_charge)?;
+
+        if let Some((shadow, request)) = shadow {
+            shadow.spawn(context, request, Served::Simulations(&txs));
+        }
+
         Ok(Output(txs))
     })
     .await
@@ -87,7 +134,9 @@ impl crate::dto::serialize::SerializeForVersion for Output {
     }
 }
 
-struct TransactionSimulation<'a>(&'a pathfinder_executor::types::TransactionSimulation);
+pub(crate) struct TransactionSimulation<'a>(
+    pub &'a pathfinder_executor::types::TransactionSimulation,
+);
 
 impl crate::dto::serialize::SerializeForVersion for TransactionSimulation<'_> {
     fn serialize(
@@ -115,10 +164,14 @@ pub enum SimulateTransactionError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     TransactionExecutionError {
         transaction_index: usize,
         error: String,
     },
+    UnsupportedStarknetVersion(UnsupportedVersion),
 }
 
 impl From<anyhow::Error> for SimulateTransactionError {
@@ -127,12 +180,26 @@ impl From<anyhow::Error> for SimulateTransactionError {
     }
 }
 
+impl From<UnsupportedVersion> for SimulateTransactionError {
+    fn from(e: UnsupportedVersion) -> Self {
+        Self::UnsupportedStarknetVersion(e)
+    }
+}
+
 impl From<SimulateTransactionError> for crate::error::ApplicationError {
     fn from(e: SimulateTransactionError) -> Self {
         match e {
             SimulateTransactionError::Internal(internal) => Self::Internal(internal),
             SimulateTransactionError::Custom(internal) => Self::Custom(internal),
+            SimulateTransactionError::UnsupportedStarknetVersion(e) => {
+                Self::UnsupportedStarknetVersion(e)
+            }
             SimulateTransactionError::BlockNotFound => Self::BlockNotFound,
+            SimulateTransactionError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
             SimulateTransactionError::TransactionExecutionError {
                 transaction_index,
                 error,
@@ -1,12 +1,12 @@
 use std::sync::Arc;
 
 use axum::async_trait;
-use pathfinder_common::{BlockId, BlockNumber};
-use tokio::sync::mpsc;
+use pathfinder_common::{BlockHash, BlockId, BlockNumber};
+use tokio::sync::{broadcast, mpsc};
 
 use super::REORG_SUBSCRIPTION_NAME;
 use crate::context::RpcContext;
-use crate::jsonrpc::{RpcError, RpcSubscriptionFlow, SubscriptionMessage};
+use crate::jsonrpc::{ChainEvent, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
 use crate::Reorg;
 
 pub struct SubscribeNewHeads;
@@ -87,59 +87,66 @@ impl RpcSubscriptionFlow for SubscribeNewHeads {
         _req: Self::Request,
         tx: mpsc::Sender<SubscriptionMessage<Self::Notification>>,
     ) {
-        let mut headers = state.notifications.block_headers.subscribe();
-        let mut reorgs = state.notifications.reorgs.subscribe();
+        let mut chain_events = state.notifications.chain_events.subscribe();
+        // The latest header sent since the latest reorg.
+        let mut latest = None;
+        let mut lagged = false;
         loop {
-            tokio::select! {
-                reorg = reorgs.recv() => {
-                    match reorg {
-                        Ok(reorg) => {
-                            let block_number = reorg.first_block_number;
-                            if tx.send(SubscriptionMessage {
-                                notification: Notification::Reorg(reorg),
-                                block_number,
-                                subscription_name: REORG_SUBSCRIPTION_NAME,
-                            }).await.is_err() {
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            tracing::debug!(
-                                "Error receiving reorg from notifications channel, node might be \
-                                 lagging: {:?}",
-                                e
-                            );
-                            break;
-                        }
+            let message = match chain_events.recv().await {
+                Ok(ChainEvent::Commit(header)) => {
+                    // Skipped headers are reported to the client as missed
+                    // blocks, but a skipped reorg can only be detected by the
+                    // latest header no longer being canonical.
+                    if std::mem::take(&mut lagged) && !is_canonical(&state, latest).await {
+                        tracing::debug!("Block header subscription missed a reorg, closing");
+                        break;
+                    }
+                    latest = Some((header.number, header.hash));
+                    SubscriptionMessage {
+                        block_number: header.number,
+                        notification: Notification::BlockHeader(header),
+                        subscription_name: SUBSCRIPTION_NAME,
                     }
                 }
-                header = headers.recv() => {
-                    match header {
-                        Ok(header) => {
-                            let block_number = header.number;
-                            if tx.send(SubscriptionMessage {
-                                notification: Notification::BlockHeader(header),
-                                block_number,
-                                subscription_name: SUBSCRIPTION_NAME,
-                            }).await.is_err() {
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            tracing::debug!(
-                                "Error receiving block header from notifications channel, node might be \
-                                 lagging: {:?}",
-                                e
-                            );
-                            break;
-                        }
+                Ok(ChainEvent::Reorg(reorg)) => {
+                    latest = None;
+                    SubscriptionMessage {
+                        block_number: reorg.first_block_number,
+                        notification: Notification::Reorg(reorg),
+                        subscription_name: REORG_SUBSCRIPTION_NAME,
                     }
                 }
+                Ok(ChainEvent::PendingUpdate(_)) => continue,
+                Err(broadcast::error::RecvError::Lagged(amount)) => {
+                    tracing::debug!(%amount, "Block header subscription lagging");
+                    lagged = true;
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if tx.send(message).await.is_err() {
+                break;
             }
         }
     }
 }
 
+/// Returns true if `block` is still part of the canonical chain. Unknown
+/// blocks are not.
+async fn is_canonical(state: &RpcContext, block: Option<(BlockNumber, BlockHash)>) -> bool {
+    let Some((number, hash)) = block else {
+        return false;
+    };
+    let storage = state.storage.clone();
+    let canonical = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let mut conn = storage.connection()?;
+        let db = conn.transaction()?;
+        db.block_hash(number.into())
+    })
+    .await;
+    matches!(canonical, Ok(Ok(Some(canonical))) if canonical == hash)
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -152,8 +159,16 @@ mod tests {
     use tokio::sync::mpsc;
 
     use crate::context::{RpcConfig, RpcContext};
-    use crate::jsonrpc::{handle_json_rpc_socket, RequestId, RpcError, RpcResponse, RpcRouter};
+    use crate::jsonrpc::{
+        handle_json_rpc_socket,
+        ChainEvent,
+        RequestId,
+        RpcError,
+        RpcResponse,
+        RpcRouter,
+    };
     use crate::pending::PendingWatcher;
+    use crate::subscription_sessions::SubscriptionSessions;
     use crate::v02::types::syncing::Syncing;
     use crate::{v08, Notifications, Reorg, SubscriptionId, SyncState};
 
@@ -183,8 +198,8 @@ mod tests {
         router
             .context
             .notifications
-            .reorgs
-            .send(
+            .chain_events
+            .send(ChainEvent::Reorg(
                 Reorg {
                     first_block_number: BlockNumber::new_or_panic(1),
                     first_block_hash: BlockHash(felt!("0x1")),
@@ -192,7 +207,7 @@ mod tests {
                     last_block_hash: BlockHash(felt!("0x2")),
                 }
                 .into(),
-            )
+            ))
             .unwrap();
         let res = rx.recv().await.unwrap().unwrap();
         let json: serde_json::Value = match res {
@@ -217,6 +232,47 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn missed_blocks() {
+        let (_, mut rx, subscription_id, router) = happy_path_test(0).await;
+        retry(|| {
+            router
+                .context
+                .notifications
+                .chain_events
+                .send(ChainEvent::Commit(sample_header(15).into()))
+        })
+        .await
+        .unwrap();
+
+        let res = rx.recv().await.unwrap().unwrap();
+        let json: serde_json::Value = match res {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "pathfinder_subscriptionMissed",
+                "params": {
+                    "result": {
+                        "first_block_number": 10,
+                        "last_block_number": 14
+                    },
+                    "subscription_id": subscription_id.0
+                }
+            })
+        );
+
+        let header = rx.recv().await.unwrap().unwrap();
+        let json: serde_json::Value = match header {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(json, sample_new_heads_message(15, subscription_id.0.into()));
+    }
+
     #[tokio::test]
     async fn race_condition_with_historic_blocks() {
         let num_blocks = 1000;
@@ -278,8 +334,8 @@ mod tests {
                 router
                     .context
                     .notifications
-                    .block_headers
-                    .send(sample_header(i + 2 * num_blocks).into())
+                    .chain_events
+                    .send(ChainEvent::Commit(sample_header(i + 2 * num_blocks).into()))
             })
             .await
             .unwrap();
@@ -373,8 +429,8 @@ mod tests {
                 router
                     .context
                     .notifications
-                    .block_headers
-                    .send(sample_header(i).into())
+                    .chain_events
+                    .send(ChainEvent::Commit(sample_header(i).into()))
             })
             .await
             .unwrap();
@@ -415,8 +471,8 @@ mod tests {
         router
             .context
             .notifications
-            .block_headers
-            .send(sample_header(10).into())
+            .chain_events
+            .send(ChainEvent::Commit(sample_header(10).into()))
             // Might error if the receiver is closed.
             .ok();
         // Give time for background tasks to process.
@@ -427,6 +483,101 @@ mod tests {
         assert!(rx.is_empty());
     }
 
+    #[tokio::test]
+    async fn resume_after_reconnect() {
+        let mut router = setup(10).await;
+        router.context.subscription_sessions =
+            Some(SubscriptionSessions::new(Duration::from_secs(60)));
+
+        // Receive the historic blocks, then drop the connection.
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "starknet_subscribeNewHeads",
+                    "params": {"block": {"block_number": 0}}
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let json = receive_json(&mut sender_rx).await;
+        let subscription_id = json["result"]["subscription_id"].as_u64().unwrap();
+        let json = receive_json(&mut sender_rx).await;
+        assert_eq!(json["method"], "pathfinder_subscriptionResumptionToken");
+        let token = json["params"]["result"]["resumption_token"].clone();
+        for i in 0..10 {
+            assert_eq!(
+                receive_json(&mut sender_rx).await,
+                sample_new_heads_message(i, subscription_id)
+            );
+        }
+        drop(receiver_tx);
+        drop(sender_rx);
+
+        // Resume on a new connection, and receive the blocks added in the meantime.
+        let (sender_tx, mut sender_rx) = mpsc::channel(1024);
+        let (receiver_tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router.clone(), sender_tx, receiver_rx);
+        receiver_tx
+            .send(Ok(Message::Text(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "method": "pathfinder_resumeSubscription",
+                    "params": {"resumption_token": token}
+                })
+                .to_string(),
+            )))
+            .await
+            .unwrap();
+        let json = receive_json(&mut sender_rx).await;
+        assert_eq!(json["id"], 2);
+        let subscription_id = json["result"]["subscription_id"].as_u64().unwrap();
+        let json = receive_json(&mut sender_rx).await;
+        assert_eq!(json["params"]["result"]["resumption_token"], token);
+
+        let storage = router.context.storage.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = storage.connection().unwrap();
+            let db = conn.transaction().unwrap();
+            for i in 10..12 {
+                db.insert_block_header(&sample_header(i)).unwrap();
+            }
+            db.commit().unwrap();
+        })
+        .await
+        .unwrap();
+        retry(|| {
+            router
+                .context
+                .notifications
+                .chain_events
+                .send(ChainEvent::Commit(sample_header(12).into()))
+        })
+        .await
+        .unwrap();
+        for i in 10..13 {
+            assert_eq!(
+                receive_json(&mut sender_rx).await,
+                sample_new_heads_message(i, subscription_id)
+            );
+        }
+    }
+
+    async fn receive_json(
+        rx: &mut mpsc::Receiver<Result<Message, RpcResponse>>,
+    ) -> serde_json::Value {
+        match rx.recv().await.unwrap().unwrap() {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        }
+    }
+
     async fn setup(num_blocks: u64) -> RpcRouter {
         let storage = StorageBuilder::in_memory().unwrap();
         tokio::task::spawn_blocking({
@@ -457,12 +608,31 @@ mod tests {
             chain_id: ChainId::MAINNET,
             sequencer: Client::mainnet(Duration::from_secs(10)),
             websocket: None,
+            response_cache: None,
+            fee_tracker: None,
+            casm_cache: None,
+            upstream: None,
+            load_shedder: None,
+            write_protection: None,
+            class_uploads: Default::default(),
+            execution_fingerprint: None,
+            websocket_keepalive: None,
+            subscription_sessions: None,
+            call_stats: None,
+            shadow_execution: None,
+            fork: None,
             notifications,
             config: RpcConfig {
                 batch_concurrency_limit: 1.try_into().unwrap(),
+                batch_cost_budget: None,
                 get_events_max_blocks_to_scan: 1.try_into().unwrap(),
                 get_events_max_uncached_bloom_filters_to_load: 1.try_into().unwrap(),
+                get_events_max_page_bytes: None,
                 custom_versioned_constants: None,
+                staking_contract: None,
+                receipt_events_limit: None,
+                analytics: false,
+                pending_gas_price_source: crate::PendingGasPriceSource::Pending,
             },
         };
         v08::register_routes().build(ctx)
@@ -525,8 +695,8 @@ mod tests {
                 router
                     .context
                     .notifications
-                    .block_headers
-                    .send(sample_header(i).into())
+                    .chain_events
+                    .send(ChainEvent::Commit(sample_header(i).into()))
             })
             .await
             .unwrap();
@@ -494,12 +494,31 @@ mod tests {
             chain_id: ChainId::MAINNET,
             sequencer: Client::mainnet(Duration::from_secs(10)),
             websocket: None,
+            response_cache: None,
+            fee_tracker: None,
+            casm_cache: None,
+            upstream: None,
+            load_shedder: None,
+            write_protection: None,
+            class_uploads: Default::default(),
+            execution_fingerprint: None,
+            websocket_keepalive: None,
+            subscription_sessions: None,
+            call_stats: None,
+            shadow_execution: None,
+            fork: None,
             notifications,
             config: RpcConfig {
                 batch_concurrency_limit: 1.try_into().unwrap(),
+                batch_cost_budget: None,
                 get_events_max_blocks_to_scan: 1.try_into().unwrap(),
                 get_events_max_uncached_bloom_filters_to_load: 1.try_into().unwrap(),
+                get_events_max_page_bytes: None,
                 custom_versioned_constants: None,
+                staking_contract: None,
+                receipt_events_limit: None,
+                analytics: false,
+                pending_gas_price_source: crate::PendingGasPriceSource::Pending,
             },
         };
         let router = v08::register_routes().build(ctx);
@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use axum::async_trait;
+use pathfinder_common::{BlockId, BlockNumber, TransactionHash};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::context::RpcContext;
+use crate::jsonrpc::{RpcError, RpcSubscriptionFlow, SubscriptionMessage};
+use crate::method::get_transaction_status::{self, get_transaction_status};
+
+pub struct SubscribeTransactionStatus;
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    transaction_hash: TransactionHash,
+}
+
+impl crate::dto::DeserializeForVersion for Request {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: value.deserialize("transaction_hash").map(TransactionHash)?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Notification {
+    transaction_hash: TransactionHash,
+    status: get_transaction_status::Output,
+}
+
+impl crate::dto::serialize::SerializeForVersion for Notification {
+    fn serialize(
+        &self,
+        serializer: crate::dto::serialize::Serializer,
+    ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(
+            "transaction_hash",
+            &crate::dto::TxnHash(&self.transaction_hash),
+        )?;
+        serializer.serialize_field("status", &self.status)?;
+        serializer.end()
+    }
+}
+
+const SUBSCRIPTION_NAME: &str = "starknet_subscriptionTransactionStatus";
+
+/// How often the status is checked in the absence of chain events, which is
+/// how transactions known only to the gateway are noticed.
+const POLL_INTERVAL: Duration = if cfg!(test) {
+    Duration::from_millis(100)
+} else {
+    Duration::from_secs(5)
+};
+
+#[async_trait]
+impl RpcSubscriptionFlow for SubscribeTransactionStatus {
+    type Request = Request;
+    type Notification = Notification;
+
+    fn starting_block(_req: &Self::Request) -> BlockId {
+        // Rollback is not supported.
+        BlockId::Latest
+    }
+
+    async fn catch_up(
+        _state: &RpcContext,
+        _req: &Self::Request,
+        _from: BlockNumber,
+        _to: BlockNumber,
+    ) -> Result<Vec<SubscriptionMessage<Self::Notification>>, RpcError> {
+        Ok(vec![])
+    }
+
+    /// Sends the status of the transaction whenever it changes, until it is
+    /// accepted on L1 or rejected.
+    ///
+    /// The status is checked whenever a block is committed, the pending block
+    /// changes or a block is accepted on L1, and at least every
+    /// [POLL_INTERVAL].
+    async fn subscribe(
+        state: RpcContext,
+        req: Self::Request,
+        tx: mpsc::Sender<SubscriptionMessage<Self::Notification>>,
+    ) {
+        let mut chain_events = state.notifications.chain_events.subscribe();
+        let mut l1_accepted = state.notifications.l1_accepted.subscribe();
+        let mut pending_data = state.pending_data.0.clone();
+        let mut poll = tokio::time::interval(POLL_INTERVAL);
+        poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        let mut last_status = None;
+        loop {
+            let input = get_transaction_status::Input {
+                transaction_hash: req.transaction_hash,
+            };
+            match get_transaction_status(state.clone(), input).await {
+                Ok(status) if last_status != Some(status) => {
+                    last_status = Some(status);
+                    if tx
+                        .send(SubscriptionMessage {
+                            notification: Notification {
+                                transaction_hash: req.transaction_hash,
+                                status,
+                            },
+                            // Notifications do not belong to a block, and must not be taken
+                            // for missed blocks.
+                            block_number: BlockNumber::GENESIS,
+                            subscription_name: SUBSCRIPTION_NAME,
+                        })
+                        .await
+                        .is_err()
+                    {
+                        // Subscription has been closed.
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                // Not received by the gateway yet.
+                Err(get_transaction_status::Error::TxnHashNotFound) => {}
+                Err(
+                    get_transaction_status::Error::Internal(error)
+                    | get_transaction_status::Error::Custom(error),
+                ) => {
+                    tracing::debug!(transaction_hash=%req.transaction_hash, %error, "Failed to check transaction status");
+                }
+            }
+
+            if matches!(
+                last_status,
+                Some(
+                    get_transaction_status::Output::AcceptedOnL1(_)
+                        | get_transaction_status::Output::Rejected
+                )
+            ) {
+                // Final status reached.
+                return;
+            }
+
+            let closed = tokio::select! {
+                _ = poll.tick() => false,
+                result = chain_events.recv() => matches!(result, Err(RecvError::Closed)),
+                result = l1_accepted.recv() => matches!(result, Err(RecvError::Closed)),
+                result = pending_data.changed() => result.is_err(),
+            };
+            if closed {
+                tracing::debug!("Notification channel closed, stopping subscription");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::extract::ws::Message;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockNumber, TransactionHash};
+    use tokio::sync::mpsc;
+
+    use crate::context::RpcContext;
+    use crate::jsonrpc::{handle_json_rpc_socket, RpcResponse};
+    use crate::v08;
+
+    #[tokio::test]
+    async fn l2_then_l1_accepted() {
+        let context = RpcContext::for_tests();
+        let router = v08::register_routes().build(context.clone());
+        let (sender_tx, mut rx) = mpsc::channel(1024);
+        let (tx, receiver_rx) = mpsc::channel(1024);
+        handle_json_rpc_socket(router, sender_tx, receiver_rx);
+
+        // This transaction is in block 1 which is not L1 accepted.
+        let transaction_hash = transaction_hash_bytes!(b"txn 1");
+        tx.send(Ok(Message::Text(
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "starknet_subscribeTransactionStatus",
+                "params": {"transaction_hash": transaction_hash}
+            })
+            .to_string(),
+        )))
+        .await
+        .unwrap();
+        let response = recv(&mut rx).await;
+        let subscription_id = response["result"]["subscription_id"].as_u64().unwrap();
+
+        assert_eq!(
+            recv(&mut rx).await,
+            message(transaction_hash, "ACCEPTED_ON_L2", subscription_id)
+        );
+
+        {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.update_l1_l2_pointer(Some(BlockNumber::new_or_panic(1)))
+                .unwrap();
+            db.commit().unwrap();
+        }
+        context
+            .notifications
+            .publish_l1_accepted(BlockNumber::new_or_panic(1));
+
+        assert_eq!(
+            recv(&mut rx).await,
+            message(transaction_hash, "ACCEPTED_ON_L1", subscription_id)
+        );
+        // The subscription ends with the final status.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(rx.is_empty());
+    }
+
+    async fn recv(rx: &mut mpsc::Receiver<Result<Message, RpcResponse>>) -> serde_json::Value {
+        let res = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match res {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            _ => panic!("Expected text message"),
+        }
+    }
+
+    fn message(
+        transaction_hash: TransactionHash,
+        finality_status: &str,
+        subscription_id: u64,
+    ) -> serde_json::Value {
+        use crate::dto::serialize::SerializeForVersion;
+        let transaction_hash = crate::dto::TxnHash(&transaction_hash)
+            .serialize(Default::default())
+            .unwrap();
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "starknet_subscriptionTransactionStatus",
+            "params": {
+                "result": {
+                    "transaction_hash": transaction_hash,
+                    "status": {
+                        "finality_status": finality_status,
+                        "execution_status": "SUCCEEDED"
+                    }
+                },
+                "subscription_id": subscription_id
+            }
+        })
+    }
+}
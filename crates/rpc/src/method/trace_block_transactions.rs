@@ -1,15 +1,43 @@
+use std::num::NonZeroUsize;
+
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockHash, BlockId, BlockNumber};
 use pathfinder_executor::TransactionExecutionError;
 use starknet_gateway_client::GatewayApi;
 
 use crate::compose_executor_transaction;
 use crate::context::RpcContext;
+use crate::dto::{FlatTransactionTrace, TraceFormat};
 use crate::executor::{
     ExecutionStateError,
     VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
 };
-use crate::v06::method::trace_block_transactions as v06;
+
+#[derive(Debug, Clone)]
+pub struct Input {
+    block_id: BlockId,
+    /// Pathfinder extension: return the traces in pages of this many
+    /// transactions.
+    chunk_size: Option<NonZeroUsize>,
+    continuation_token: Option<String>,
+    /// Pathfinder extension: the encoding of the traces.
+    trace_format: TraceFormat,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize_serde("block_id")?,
+                chunk_size: value.deserialize_optional_serde("chunk_size")?,
+                continuation_token: value.deserialize_optional_serde("continuation_token")?,
+                trace_format: value
+                    .deserialize_optional_serde("trace_format")?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
 
 pub struct Output {
     traces: Vec<(
@@ -17,17 +45,78 @@ pub struct Output {
         pathfinder_executor::types::TransactionTrace,
     )>,
     include_state_diffs: bool,
+    /// Set if the traces were requested in pages, to the token of the next
+    /// page if there is one.
+    page: Option<Option<String>>,
+    trace_format: TraceFormat,
+}
+
+/// The position of a page of traces within a block.
+///
+/// The token names the block by hash so that it becomes invalid if the block
+/// is reorged away, instead of silently continuing with the traces of its
+/// replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ContinuationToken {
+    block_hash: BlockHash,
+    offset: usize,
+}
+
+impl std::fmt::Display for ContinuationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.block_hash.0.to_hex_str(), self.offset)
+    }
 }
 
+impl std::str::FromStr for ContinuationToken {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (block_hash, offset) = s.split_once('-').ok_or(())?;
+        Ok(Self {
+            block_hash: BlockHash(
+                pathfinder_crypto::Felt::from_hex_str(block_hash).map_err(|_| ())?,
+            ),
+            offset: offset.parse().map_err(|_| ())?,
+        })
+    }
+}
+
+/// Traces all transactions of a block.
+///
+/// If `chunk_size` is set, only that many traces are returned along with a
+/// continuation token for the rest. The block's traces are executed once and
+/// kept in the trace cache, which serves the following pages as long as the
+/// block is not evicted from it.
 pub async fn trace_block_transactions(
     context: RpcContext,
-    input: v06::TraceBlockTransactionsInput,
+    input: Input,
 ) -> Result<Output, TraceBlockTransactionsError> {
     enum LocalExecution {
         Success(Output),
         Unsupported(Vec<pathfinder_common::transaction::Transaction>),
     }
 
+    let token = match (&input.continuation_token, input.chunk_size) {
+        (None, _) => None,
+        (Some(_), None) => {
+            return Err(TraceBlockTransactionsError::Custom(anyhow::anyhow!(
+                "continuation_token requires chunk_size"
+            )))
+        }
+        (Some(token), Some(_)) => Some(
+            token
+                .parse::<ContinuationToken>()
+                .map_err(|_| TraceBlockTransactionsError::InvalidContinuationToken)?,
+        ),
+    };
+    if input.chunk_size.is_some() && input.block_id == BlockId::Pending {
+        return Err(TraceBlockTransactionsError::Custom(anyhow::anyhow!(
+            "Traces of the pending block cannot be paginated"
+        )));
+    }
+
+    let trace_format = input.trace_format;
     let span = tracing::Span::current();
 
     let storage = context.execution_storage.clone();
@@ -60,6 +149,10 @@ pub async fn trace_block_transactions(
                     .block_header(block_id)?
                     .ok_or(TraceBlockTransactionsError::BlockNotFound)?;
 
+                if token.is_some_and(|token| token.block_hash != header.hash) {
+                    return Err(TraceBlockTransactionsError::InvalidContinuationToken);
+                }
+
                 let transactions = db
                     .transactions_for_block(block_id)?
                     .context("Transaction data missing")?
@@ -70,6 +163,7 @@ pub async fn trace_block_transactions(
                 (header, transactions, context.cache.clone())
             }
         };
+        let hash = header.hash;
 
         if header.starknet_version
             < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
@@ -81,19 +175,31 @@ pub async fn trace_block_transactions(
                     )))
                 }
                 _ => {
-                    return Ok::<_, TraceBlockTransactionsError>(LocalExecution::Unsupported(
-                        transactions,
+                    return Ok::<_, TraceBlockTransactionsError>((
+                        hash,
+                        LocalExecution::Unsupported(transactions),
                     ))
                 }
             }
         }
 
+        // Transactions are traced on the parent block's state.
+        if let Some(parent) = header.number.parent() {
+            if let Some(earliest_available_block) = db
+                .earliest_available_state_block(parent.into())
+                .context("Checking state pruning")?
+            {
+                return Err(TraceBlockTransactionsError::StatePruned {
+                    earliest_available_block,
+                });
+            }
+        }
+
         let executor_transactions = transactions
             .iter()
             .map(|transaction| compose_executor_transaction(transaction, &db))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let hash = header.hash;
         let state = pathfinder_executor::ExecutionState::trace(
             &db,
             context.chain_id,
@@ -104,7 +210,7 @@ pub async fn trace_block_transactions(
         let traces = match pathfinder_executor::trace(state, cache, hash, executor_transactions) {
             Ok(traces) => traces,
             Err(TransactionExecutionError::ExecutionError { .. }) => {
-                return Ok(LocalExecution::Unsupported(transactions))
+                return Ok((hash, LocalExecution::Unsupported(transactions)))
             }
             Err(e) => return Err(e.into()),
         };
@@ -114,20 +220,28 @@ pub async fn trace_block_transactions(
             .map(|(hash, trace)| Ok((hash, trace)))
             .collect::<Result<Vec<_>, TraceBlockTransactionsError>>()?;
 
-        Ok(LocalExecution::Success(Output {
-            traces,
-            include_state_diffs: true,
-        }))
+        Ok((
+            hash,
+            LocalExecution::Success(Output {
+                traces,
+                include_state_diffs: true,
+                page: None,
+                trace_format,
+            }),
+        ))
     })
     .await
     .context("trace_block_transactions: fetch block & transactions")??;
 
+    let (block_hash, traces) = traces;
     let transactions = match traces {
-        LocalExecution::Success(output) => return Ok(output),
+        LocalExecution::Success(output) => {
+            return paginate(output, input.chunk_size, token, block_hash)
+        }
         LocalExecution::Unsupported(transactions) => transactions,
     };
 
-    context
+    let output = context
         .sequencer
         .block_traces(input.block_id)
         .await
@@ -148,8 +262,47 @@ pub async fn trace_block_transactions(
                     .collect::<Result<Vec<_>, TraceBlockTransactionsError>>()?,
                 // State diffs are not available for traces fetched from the gateway.
                 include_state_diffs: false,
+                page: None,
+                trace_format,
             })
-        })?
+        })??;
+
+    paginate(output, input.chunk_size, token, block_hash)
+}
+
+/// Keeps the page of `output` starting at `token`, if pages were requested.
+///
+/// The token was checked to refer to `block_hash` before tracing.
+fn paginate(
+    mut output: Output,
+    chunk_size: Option<NonZeroUsize>,
+    token: Option<ContinuationToken>,
+    block_hash: BlockHash,
+) -> Result<Output, TraceBlockTransactionsError> {
+    let Some(chunk_size) = chunk_size else {
+        return Ok(output);
+    };
+
+    let offset = token.map_or(0, |token| token.offset);
+    if offset > output.traces.len() {
+        return Err(TraceBlockTransactionsError::InvalidContinuationToken);
+    }
+
+    let end = offset
+        .saturating_add(chunk_size.get())
+        .min(output.traces.len());
+    let continuation_token = (end < output.traces.len()).then(|| {
+        ContinuationToken {
+            block_hash,
+            offset: end,
+        }
+        .to_string()
+    });
+
+    output.traces.truncate(end);
+    output.traces.drain(..offset);
+    output.page = Some(continuation_token);
+    Ok(output)
 }
 
 pub(crate) fn map_gateway_trace(
@@ -483,14 +636,22 @@ impl crate::dto::serialize::SerializeForVersion for Output {
         &self,
         serializer: crate::dto::serialize::Serializer,
     ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
-        serializer.serialize_iter(
-            self.traces.len(),
-            &mut self.traces.iter().map(|(hash, trace)| Trace {
-                transaction_hash: hash,
-                transaction_trace: trace,
-                include_state_diff: self.include_state_diffs,
-            }),
-        )
+        let mut traces = self.traces.iter().map(|(hash, trace)| Trace {
+            transaction_hash: hash,
+            transaction_trace: trace,
+            include_state_diff: self.include_state_diffs,
+            trace_format: self.trace_format,
+        });
+
+        match &self.page {
+            None => serializer.serialize_iter(self.traces.len(), &mut traces),
+            Some(continuation_token) => {
+                let mut serializer = serializer.serialize_struct()?;
+                serializer.serialize_iter("traces", self.traces.len(), &mut traces)?;
+                serializer.serialize_optional("continuation_token", continuation_token.as_ref())?;
+                serializer.end()
+            }
+        }
     }
 }
 
@@ -498,6 +659,7 @@ struct Trace<'a> {
     transaction_hash: &'a pathfinder_common::TransactionHash,
     transaction_trace: &'a pathfinder_executor::types::TransactionTrace,
     include_state_diff: bool,
+    trace_format: TraceFormat,
 }
 
 impl crate::dto::serialize::SerializeForVersion for Trace<'_> {
@@ -510,13 +672,17 @@ impl crate::dto::serialize::SerializeForVersion for Trace<'_> {
             "transaction_hash",
             &crate::dto::TxnHash(self.transaction_hash),
         )?;
-        serializer.serialize_field(
-            "trace_root",
-            &crate::dto::TransactionTrace {
-                trace: self.transaction_trace,
-                include_state_diff: self.include_state_diff,
-            },
-        )?;
+        match self.trace_format {
+            TraceFormat::Nested => serializer.serialize_field(
+                "trace_root",
+                &crate::dto::TransactionTrace {
+                    trace: self.transaction_trace,
+                    include_state_diff: self.include_state_diff,
+                },
+            )?,
+            TraceFormat::Flat => serializer
+                .serialize_field("flat_trace", &FlatTransactionTrace(self.transaction_trace))?,
+        }
         serializer.end()
     }
 }
@@ -526,6 +692,10 @@ pub enum TraceBlockTransactionsError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+    InvalidContinuationToken,
 }
 
 impl From<anyhow::Error> for TraceBlockTransactionsError {
@@ -539,7 +709,13 @@ impl From<TraceBlockTransactionsError> for crate::error::ApplicationError {
         match value {
             TraceBlockTransactionsError::Internal(e) => Self::Internal(e),
             TraceBlockTransactionsError::BlockNotFound => Self::BlockNotFound,
+            TraceBlockTransactionsError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
             TraceBlockTransactionsError::Custom(e) => Self::Custom(e),
+            TraceBlockTransactionsError::InvalidContinuationToken => Self::InvalidContinuationToken,
         }
     }
 }
@@ -588,10 +764,11 @@ pub(crate) mod tests {
     use starknet_gateway_types::reply::GasPrices;
     use tokio::task::JoinSet;
 
-    use super::v06::{Trace, TraceBlockTransactionsInput, TraceBlockTransactionsOutput};
-    use super::{trace_block_transactions, RpcContext};
+    use super::{trace_block_transactions, Input, RpcContext};
     use crate::dto::serialize::{SerializeForVersion, Serializer};
+    use crate::dto::TraceFormat;
     use crate::v06::method::simulate_transactions::tests::setup_storage_with_starknet_version;
+    use crate::v06::method::trace_block_transactions::{Trace, TraceBlockTransactionsOutput};
     use crate::RpcVersion;
 
     pub(crate) async fn setup_multi_tx_trace_test(
@@ -702,23 +879,135 @@ pub(crate) mod tests {
     async fn test_multiple_transactions() -> anyhow::Result<()> {
         let (context, next_block_header, traces) = setup_multi_tx_trace_test().await?;
 
-        let input = TraceBlockTransactionsInput {
+        let input = Input {
             block_id: next_block_header.hash.into(),
+            chunk_size: None,
+            continuation_token: None,
+            trace_format: TraceFormat::Nested,
         };
         let output = trace_block_transactions(context, input).await.unwrap();
         let expected = TraceBlockTransactionsOutput(traces);
 
         pretty_assertions_sorted::assert_eq!(
             output
-                .serialize(Serializer {
-                    version: RpcVersion::V06,
-                })
+                .serialize(Serializer::new(RpcVersion::V06))
                 .unwrap(),
             serde_json::to_value(expected).unwrap()
         );
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_flat_trace_format() -> anyhow::Result<()> {
+        let (context, next_block_header, traces) = setup_multi_tx_trace_test().await?;
+
+        let input = Input {
+            block_id: next_block_header.hash.into(),
+            chunk_size: None,
+            continuation_token: None,
+            trace_format: TraceFormat::Flat,
+        };
+        let output = trace_block_transactions(context, input)
+            .await
+            .unwrap()
+            .serialize(Serializer::new(RpcVersion::V07))
+            .unwrap();
+
+        let output = output.as_array().unwrap();
+        assert_eq!(output.len(), traces.len());
+        for (output, expected) in output.iter().zip(traces.iter()) {
+            assert_eq!(
+                output["transaction_hash"],
+                crate::dto::TxnHash(&expected.transaction_hash)
+                    .serialize(Default::default())
+                    .unwrap()
+            );
+            assert!(output.get("trace_root").is_none());
+            let calls = output["flat_trace"].as_array().unwrap();
+            assert!(!calls.is_empty());
+            for call in calls {
+                assert!(call["trace_address"].is_array());
+                assert!(call["subtraces"].is_u64());
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pagination() -> anyhow::Result<()> {
+        let (context, next_block_header, traces) = setup_multi_tx_trace_test().await?;
+
+        let input = Input {
+            block_id: next_block_header.hash.into(),
+            chunk_size: Some(std::num::NonZeroUsize::new(2).unwrap()),
+            continuation_token: None,
+            trace_format: TraceFormat::Nested,
+        };
+        let first = trace_block_transactions(context.clone(), input.clone())
+            .await
+            .unwrap()
+            .serialize(Serializer::new(RpcVersion::V06))
+            .unwrap();
+        pretty_assertions_sorted::assert_eq!(
+            first["traces"],
+            serde_json::to_value(TraceBlockTransactionsOutput(traces[..2].to_vec())).unwrap()
+        );
+        let token = first["continuation_token"].as_str().unwrap().to_owned();
+
+        let input = Input {
+            continuation_token: Some(token.clone()),
+            ..input
+        };
+        let second = trace_block_transactions(context.clone(), input.clone())
+            .await
+            .unwrap()
+            .serialize(Serializer::new(RpcVersion::V06))
+            .unwrap();
+        pretty_assertions_sorted::assert_eq!(
+            second,
+            serde_json::json!({
+                "traces": TraceBlockTransactionsOutput(traces[2..].to_vec()),
+            })
+        );
+
+        // The token is only valid for the block it was issued for.
+        let input = Input {
+            block_id: BlockId::Number(next_block_header.number - 1),
+            ..input
+        };
+        let error = trace_block_transactions(context, input).await.err();
+        assert_matches::assert_matches!(
+            error,
+            Some(super::TraceBlockTransactionsError::InvalidContinuationToken)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn state_pruned() -> anyhow::Result<()> {
+        let (context, next_block_header, _) = setup_multi_tx_trace_test().await?;
+        {
+            let mut db = context.storage.connection()?;
+            let tx = db.transaction()?;
+            tx.prune_state_updates(next_block_header.number, 10)?;
+            tx.commit()?;
+        }
+
+        let input = Input {
+            block_id: next_block_header.hash.into(),
+            chunk_size: None,
+            continuation_token: None,
+            trace_format: TraceFormat::Nested,
+        };
+        let error = trace_block_transactions(context, input).await.err();
+        assert_matches::assert_matches!(
+            error,
+            Some(super::TraceBlockTransactionsError::StatePruned { earliest_available_block })
+                if earliest_available_block == next_block_header.number
+        );
+        Ok(())
+    }
+
     /// Test that multiple requests for the same block return correctly. This
     /// checks that the trace request coalescing doesn't do anything
     /// unexpected.
@@ -728,8 +1017,11 @@ pub(crate) mod tests {
 
         let (context, next_block_header, traces) = setup_multi_tx_trace_test().await?;
 
-        let input = TraceBlockTransactionsInput {
+        let input = Input {
             block_id: next_block_header.hash.into(),
+            chunk_size: None,
+            continuation_token: None,
+            trace_format: TraceFormat::Nested,
         };
         let mut joins = JoinSet::new();
         for _ in 0..NUM_REQUESTS {
@@ -742,9 +1034,7 @@ pub(crate) mod tests {
             outputs.push(
                 output
                     .unwrap()
-                    .serialize(Serializer {
-                        version: RpcVersion::V06,
-                    })
+                    .serialize(Serializer::new(RpcVersion::V06))
                     .unwrap(),
             );
         }
@@ -877,17 +1167,18 @@ pub(crate) mod tests {
     async fn test_multiple_pending_transactions() -> anyhow::Result<()> {
         let (context, traces) = setup_multi_tx_trace_pending_test().await?;
 
-        let input = TraceBlockTransactionsInput {
+        let input = Input {
             block_id: BlockId::Pending,
+            chunk_size: None,
+            continuation_token: None,
+            trace_format: TraceFormat::Nested,
         };
         let output = trace_block_transactions(context, input).await.unwrap();
         let expected = TraceBlockTransactionsOutput(traces);
 
         pretty_assertions_sorted::assert_eq!(
             output
-                .serialize(Serializer {
-                    version: RpcVersion::V06,
-                })
+                .serialize(Serializer::new(RpcVersion::V06))
                 .unwrap(),
             serde_json::to_value(expected).unwrap()
         );
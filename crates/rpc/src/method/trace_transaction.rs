@@ -1,26 +1,48 @@
 use anyhow::Context;
+use pathfinder_common::{BlockNumber, TransactionHash};
 use pathfinder_executor::TransactionExecutionError;
 use starknet_gateway_client::GatewayApi;
 
 use crate::compose_executor_transaction;
 use crate::context::RpcContext;
+use crate::dto::{FlatTransactionTrace, TraceFormat};
 use crate::error::{ApplicationError, TraceError};
 use crate::executor::{
     ExecutionStateError,
     VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
 };
 use crate::method::trace_block_transactions::map_gateway_trace;
-use crate::v06::method::trace_transaction as v06;
+
+#[derive(Debug)]
+pub struct Input {
+    pub(crate) transaction_hash: TransactionHash,
+    /// Pathfinder extension: the encoding of the trace.
+    pub(crate) trace_format: TraceFormat,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: value.deserialize("transaction_hash").map(TransactionHash)?,
+                trace_format: value
+                    .deserialize_optional_serde("trace_format")?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
 
 #[derive(Debug)]
 pub struct Output {
-    trace: pathfinder_executor::types::TransactionTrace,
+    pub(crate) trace: pathfinder_executor::types::TransactionTrace,
     include_state_diff: bool,
+    trace_format: TraceFormat,
 }
 
 pub async fn trace_transaction(
     context: RpcContext,
-    input: v06::TraceTransactionInput,
+    input: Input,
 ) -> Result<Output, TraceTransactionError> {
     #[allow(clippy::large_enum_variant)]
     enum LocalExecution {
@@ -28,6 +50,7 @@ pub async fn trace_transaction(
         Unsupported(pathfinder_common::transaction::Transaction),
     }
 
+    let trace_format = input.trace_format;
     let span = tracing::Span::current();
     let local =
         tokio::task::spawn_blocking(move || -> Result<LocalExecution, TraceTransactionError> {
@@ -97,6 +120,18 @@ pub async fn trace_transaction(
                 (header, transactions.clone(), context.cache.clone())
             };
 
+            // Transactions are traced on the parent block's state.
+            if let Some(parent) = header.number.parent() {
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(parent.into())
+                    .context("Checking state pruning")?
+                {
+                    return Err(TraceTransactionError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
+            }
+
             let hash = header.hash;
             let state = pathfinder_executor::ExecutionState::trace(
                 &db,
@@ -150,6 +185,7 @@ pub async fn trace_transaction(
             return Ok(Output {
                 trace,
                 include_state_diff: true,
+                trace_format,
             })
         }
         LocalExecution::Unsupported(tx) => tx,
@@ -167,6 +203,7 @@ pub async fn trace_transaction(
         trace,
         // State diffs are not available for traces fetched from the gateway.
         include_state_diff: false,
+        trace_format,
     })
 }
 
@@ -175,11 +212,14 @@ impl crate::dto::serialize::SerializeForVersion for Output {
         &self,
         serializer: crate::dto::serialize::Serializer,
     ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
-        crate::dto::TransactionTrace {
-            trace: &self.trace,
-            include_state_diff: self.include_state_diff,
+        match self.trace_format {
+            TraceFormat::Nested => crate::dto::TransactionTrace {
+                trace: &self.trace,
+                include_state_diff: self.include_state_diff,
+            }
+            .serialize(serializer),
+            TraceFormat::Flat => FlatTransactionTrace(&self.trace).serialize(serializer),
         }
-        .serialize(serializer)
     }
 }
 
@@ -188,8 +228,13 @@ pub enum TraceTransactionError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     TxnHashNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     NoTraceAvailable(TraceError),
-    ContractError { revert_error: String },
+    ContractError {
+        revert_error: String,
+    },
 }
 
 impl From<ExecutionStateError> for TraceTransactionError {
@@ -231,7 +276,13 @@ impl From<super::trace_block_transactions::TraceBlockTransactionsError> for Trac
         match e {
             Internal(e) => Self::Internal(e),
             BlockNotFound => Self::Custom(anyhow::anyhow!("Block not found")),
+            StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
             Custom(e) => Self::Custom(e),
+            InvalidContinuationToken => Self::Custom(anyhow::anyhow!("Invalid continuation token")),
         }
     }
 }
@@ -240,6 +291,11 @@ impl From<TraceTransactionError> for ApplicationError {
     fn from(value: TraceTransactionError) -> Self {
         match value {
             TraceTransactionError::TxnHashNotFound => ApplicationError::TxnHashNotFound,
+            TraceTransactionError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             TraceTransactionError::NoTraceAvailable(status) => {
                 ApplicationError::NoTraceAvailable(status)
             }
@@ -260,9 +316,9 @@ pub mod tests {
         setup_multi_tx_trace_pending_test,
         setup_multi_tx_trace_test,
     };
-    use super::v06::{TraceTransactionInput, TraceTransactionOutput};
     use super::*;
     use crate::dto::serialize::{SerializeForVersion, Serializer};
+    use crate::v06::method::trace_transaction::TraceTransactionOutput;
     use crate::RpcVersion;
 
     #[tokio::test]
@@ -270,8 +326,9 @@ pub mod tests {
         let (context, _, traces) = setup_multi_tx_trace_test().await?;
 
         for trace in traces {
-            let input = TraceTransactionInput {
+            let input = Input {
                 transaction_hash: trace.transaction_hash,
+                trace_format: TraceFormat::Nested,
             };
             let output = trace_transaction(context.clone(), input).await.unwrap();
             let expected = TraceTransactionOutput(trace.trace_root);
@@ -293,8 +350,9 @@ pub mod tests {
         let (context, traces) = setup_multi_tx_trace_pending_test().await?;
 
         for trace in traces {
-            let input = TraceTransactionInput {
+            let input = Input {
                 transaction_hash: trace.transaction_hash,
+                trace_format: TraceFormat::Nested,
             };
             let output = trace_transaction(context.clone(), input).await.unwrap();
             let expected = TraceTransactionOutput(trace.trace_root);
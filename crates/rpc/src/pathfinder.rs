@@ -5,7 +5,37 @@ pub(crate) mod methods;
 #[rustfmt::skip]
 pub fn register_routes() -> RpcRouterBuilder {
     RpcRouter::builder(crate::RpcVersion::PathfinderV01)
-        .register("pathfinder_version",              || { pathfinder_common::consts::VERGEN_GIT_DESCRIBE })
-        .register("pathfinder_getProof",             methods::get_proof)
-        .register("pathfinder_getTransactionStatus", methods::get_transaction_status)
+        .register("analytics_getDailyStats",                    methods::get_daily_stats)
+        .register("analytics_getStatus",                        methods::get_analytics_status)
+        .register("debug_diagnoseCompiledClassHash",            methods::diagnose_compiled_class_hash)
+        .register("debug_diffTransactionTraces",                methods::diff_transaction_traces)
+        .register("debug_dumpContractState",                    methods::dump_contract_state)
+        .register("debug_exportTransactionTrace",               methods::export_transaction_trace)
+        .register("pathfinder_version",                         || { pathfinder_common::consts::VERGEN_GIT_DESCRIBE })
+        .register("pathfinder_estimateDataGas",                 methods::estimate_data_gas)
+        .register("pathfinder_estimateFeeBundle",               methods::estimate_fee_bundle)
+        .register("pathfinder_getBlockAttestation",             methods::get_block_attestation)
+        .register("pathfinder_getBlockTransactionCountByType", methods::get_block_transaction_count_by_type)
+        .register("pathfinder_getCallStats",                    methods::get_call_stats)
+        .register("pathfinder_getChainTip",                     methods::get_chain_tip)
+        .register("pathfinder_getClassHashesAt",                methods::get_class_hashes_at)
+        .register("pathfinder_getClassesBySelector",            methods::get_classes_by_selector)
+        .register("pathfinder_getContractRootHistory",          methods::get_contract_root_history)
+        .register("pathfinder_getFeeEstimateStats",             methods::get_fee_estimate_stats)
+        .register("pathfinder_getMessageStatus",                methods::get_message_status)
+        .register("pathfinder_getNonces",                       methods::get_nonces)
+        .register("pathfinder_getProof",                        methods::get_proof)
+        .register("pathfinder_getProofs",                       methods::get_proofs)
+        .register("pathfinder_getReorgs",                       methods::get_reorgs)
+        .register("pathfinder_getStakingEpoch",                 methods::get_staking_epoch)
+        .register("pathfinder_getStateSample",                  methods::get_state_sample)
+        .register("pathfinder_getStateWitness",                 methods::get_state_witness)
+        .register("pathfinder_getStorageMany",                  methods::get_storage_many)
+        .register("pathfinder_getTransactionEvents",            methods::get_transaction_events)
+        .register("pathfinder_getTransactionHashPreimage",      methods::get_transaction_hash_preimage)
+        .register("pathfinder_getTransactionStatus",            methods::get_transaction_status)
+        .register("pathfinder_getTransactionsByContract",       methods::get_transactions_by_contract)
+        .register("pathfinder_getVerifiedSource",               methods::get_verified_source)
+        .register("pathfinder_simulateCalls",                   methods::simulate_calls)
+        .register("pathfinder_uploadClass",                     methods::upload_class)
 }
@@ -1,5 +1,63 @@
+mod diagnose_compiled_class_hash;
+pub(crate) mod diff_transaction_traces;
+mod dump_contract_state;
+mod estimate_data_gas;
+mod estimate_fee_bundle;
+mod export_transaction_trace;
+mod get_analytics_status;
+mod get_block_attestation;
+mod get_block_transaction_count_by_type;
+mod get_call_stats;
+mod get_chain_tip;
+mod get_class_hashes_at;
+mod get_classes_by_selector;
+mod get_contract_root_history;
+mod get_daily_stats;
+mod get_fee_estimate_stats;
+mod get_message_status;
+mod get_nonces;
 mod get_proof;
+pub(crate) mod get_reorgs;
+mod get_staking_epoch;
+mod get_state_sample;
+mod get_state_witness;
+mod get_storage_many;
+mod get_transaction_events;
+mod get_transaction_hash_preimage;
 mod get_transaction_status;
+mod get_transactions_by_contract;
+mod get_verified_source;
+mod simulate_calls;
+mod upload_class;
 
-pub(crate) use get_proof::get_proof;
+pub(crate) use diagnose_compiled_class_hash::diagnose_compiled_class_hash;
+pub(crate) use diff_transaction_traces::diff_transaction_traces;
+pub(crate) use dump_contract_state::dump_contract_state;
+pub(crate) use estimate_data_gas::estimate_data_gas;
+pub(crate) use estimate_fee_bundle::estimate_fee_bundle;
+pub(crate) use export_transaction_trace::export_transaction_trace;
+pub(crate) use get_analytics_status::get_analytics_status;
+pub(crate) use get_block_attestation::get_block_attestation;
+pub(crate) use get_block_transaction_count_by_type::get_block_transaction_count_by_type;
+pub(crate) use get_call_stats::get_call_stats;
+pub(crate) use get_chain_tip::get_chain_tip;
+pub(crate) use get_class_hashes_at::get_class_hashes_at;
+pub(crate) use get_classes_by_selector::get_classes_by_selector;
+pub(crate) use get_contract_root_history::get_contract_root_history;
+pub(crate) use get_daily_stats::get_daily_stats;
+pub(crate) use get_fee_estimate_stats::get_fee_estimate_stats;
+pub(crate) use get_message_status::get_message_status;
+pub(crate) use get_nonces::get_nonces;
+pub(crate) use get_proof::{get_proof, get_proofs};
+pub(crate) use get_reorgs::get_reorgs;
+pub(crate) use get_staking_epoch::get_staking_epoch;
+pub(crate) use get_state_sample::get_state_sample;
+pub(crate) use get_state_witness::get_state_witness;
+pub(crate) use get_storage_many::get_storage_many;
+pub(crate) use get_transaction_events::get_transaction_events;
+pub(crate) use get_transaction_hash_preimage::get_transaction_hash_preimage;
 pub(crate) use get_transaction_status::get_transaction_status;
+pub(crate) use get_transactions_by_contract::get_transactions_by_contract;
+pub(crate) use get_verified_source::get_verified_source;
+pub(crate) use simulate_calls::simulate_calls;
+pub(crate) use upload_class::upload_class;
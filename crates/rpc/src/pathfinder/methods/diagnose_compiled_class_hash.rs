@@ -0,0 +1,161 @@
+use anyhow::Context;
+use pathfinder_common::{CasmHash, ClassHash};
+
+use crate::context::RpcContext;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    class_hash: ClassHash,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                class_hash: ClassHash(value.deserialize("class_hash")?),
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error: ClassHashNotFound);
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    class_hash: ClassHash,
+    sierra_version: String,
+    compiler_version: &'static str,
+    declared_compiled_class_hash: CasmHash,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compiled_class_hash: Option<CasmHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compilation_error: Option<String>,
+    matches: bool,
+}
+
+/// Compiles a stored Sierra class with the bundled compiler and compares the
+/// resulting compiled class hash with the one it was declared with.
+///
+/// Compilation failures are reported in the output rather than as an error,
+/// since they are one of the causes this method is meant to diagnose.
+pub async fn diagnose_compiled_class_hash(
+    context: RpcContext,
+    input: Input,
+) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let (definition, declared_compiled_class_hash) = {
+            let mut db = context
+                .storage
+                .connection()
+                .context("Opening database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+
+            let definition = db
+                .class_definition(input.class_hash)
+                .context("Querying class definition")?
+                .ok_or(Error::ClassHashNotFound)?;
+            let declared_compiled_class_hash = db
+                .casm_hash(input.class_hash)
+                .context("Querying compiled class hash")?
+                .ok_or_else(|| {
+                    Error::Custom(anyhow::anyhow!(
+                        "Class is a Cairo 0 class and has no compiled class hash"
+                    ))
+                })?;
+
+            (definition, declared_compiled_class_hash)
+        };
+
+        let (sierra_version, compiler_version) = pathfinder_compiler::compiler_version(&definition)
+            .context("Determining compiler version")?;
+
+        let (compiled_class_hash, compilation_error) =
+            match pathfinder_compiler::compile_to_casm(&definition)
+                .and_then(|casm| pathfinder_compiler::casm_class_hash(&casm))
+            {
+                Ok(hash) => (Some(hash), None),
+                Err(e) => (None, Some(format!("{e:#}"))),
+            };
+
+        Ok(Output {
+            class_hash: input.class_hash,
+            sierra_version,
+            compiler_version,
+            declared_compiled_class_hash,
+            matches: compiled_class_hash == Some(declared_compiled_class_hash),
+            compiled_class_hash,
+            compilation_error,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use starknet_gateway_test_fixtures::class_definitions::CAIRO_0_11_SIERRA;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn mismatch() {
+        let context = RpcContext::for_tests();
+        let class_hash = class_hash_bytes!(b"class 2 hash (sierra)");
+
+        let output = diagnose_compiled_class_hash(context, Input { class_hash })
+            .await
+            .unwrap();
+
+        let casm = pathfinder_compiler::compile_to_casm(CAIRO_0_11_SIERRA).unwrap();
+        let expected = pathfinder_compiler::casm_class_hash(&casm).unwrap();
+
+        assert_eq!(
+            output,
+            Output {
+                class_hash,
+                sierra_version: "0.1.0".to_owned(),
+                compiler_version: "1.0.0-alpha.6",
+                // The test database declares the class with a made up hash.
+                declared_compiled_class_hash: casm_hash_bytes!(b"non-existent"),
+                compiled_class_hash: Some(expected),
+                compilation_error: None,
+                matches: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn cairo_0_class() {
+        let context = RpcContext::for_tests();
+
+        let result = diagnose_compiled_class_hash(
+            context,
+            Input {
+                class_hash: class_hash_bytes!(b"class 0 hash"),
+            },
+        )
+        .await;
+
+        assert_matches::assert_matches!(result, Err(Error::Custom(_)));
+    }
+
+    #[tokio::test]
+    async fn class_not_found() {
+        let context = RpcContext::for_tests();
+
+        let result = diagnose_compiled_class_hash(
+            context,
+            Input {
+                class_hash: class_hash_bytes!(b"missing"),
+            },
+        )
+        .await;
+
+        assert_matches::assert_matches!(result, Err(Error::ClassHashNotFound));
+    }
+}
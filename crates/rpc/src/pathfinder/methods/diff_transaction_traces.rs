@@ -0,0 +1,362 @@
+use std::collections::BTreeSet;
+
+use anyhow::Context;
+use pathfinder_common::BlockId;
+use pathfinder_executor::types::TransactionSimulation;
+use serde_json::Value;
+
+use crate::context::RpcContext;
+use crate::dto::serialize::{SerializeForVersion, Serializer};
+use crate::method::simulate_transactions::{self, SimulateTransactionError as Error};
+use crate::v02::types::request::BroadcastedTransaction;
+use crate::v06::method::simulate_transactions::dto::{SimulationFlag, SimulationFlags};
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Input {
+    base_block_id: BlockId,
+    target_block_id: BlockId,
+    transactions: Vec<BroadcastedTransaction>,
+    #[serde(default = "no_simulation_flags")]
+    simulation_flags: SimulationFlags,
+}
+
+fn no_simulation_flags() -> SimulationFlags {
+    SimulationFlags(vec![])
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_serde()
+    }
+}
+
+pub struct Output {
+    base: Vec<TransactionSimulation>,
+    target: Vec<TransactionSimulation>,
+}
+
+/// Simulates the same transactions on top of two blocks and returns the
+/// differences between the resulting traces and fee estimates.
+///
+/// Intended for debugging why an estimate changed between blocks.
+pub async fn diff_transaction_traces(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let skip_validate = input
+            .simulation_flags
+            .0
+            .contains(&SimulationFlag::SkipValidate);
+        let skip_fee_charge = input
+            .simulation_flags
+            .0
+            .contains(&SimulationFlag::SkipFeeCharge);
+
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let simulate = |block_id: BlockId, side: &str| -> Result<_, Error> {
+            let (header, pending) = match block_id {
+                BlockId::Pending => {
+                    let pending = context
+                        .pending_data
+                        .get(&db)
+                        .context("Querying pending data")?;
+
+                    (pending.header(), Some(pending.state_update.clone()))
+                }
+                other => {
+                    let block_id = other.try_into().expect("Only pending should fail");
+
+                    let header = db
+                        .block_header(block_id)
+                        .context("Fetching block header")?
+                        .ok_or(Error::BlockNotFound)?;
+                    if let Some(earliest_available_block) = db
+                        .earliest_available_state_block(block_id)
+                        .context("Checking state pruning")?
+                    {
+                        return Err(Error::StatePruned {
+                            earliest_available_block,
+                        });
+                    }
+
+                    (header, None)
+                }
+            };
+
+            let state = pathfinder_executor::ExecutionState::simulation(
+                &db,
+                context.chain_id,
+                header,
+                pending,
+                pathfinder_executor::L1BlobDataAvailability::Enabled,
+                context.config.custom_versioned_constants.clone(),
+            );
+
+            let transactions = input
+                .transactions
+                .iter()
+                .map(|tx| {
+                    crate::executor::map_broadcasted_transaction(
+                        tx,
+                        context.chain_id,
+                        context.casm_cache.as_ref(),
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            pathfinder_executor::simulate(state, transactions, skip_validate, skip_fee_charge)
+                .map_err(|e| match Error::from(e) {
+                    Error::TransactionExecutionError {
+                        transaction_index,
+                        error,
+                    } => Error::TransactionExecutionError {
+                        transaction_index,
+                        error: format!("On {side} block: {error}"),
+                    },
+                    other => other,
+                })
+        };
+
+        let base = simulate(input.base_block_id, "base")?;
+        let target = simulate(input.target_block_id, "target")?;
+
+        Ok(Output { base, target })
+    })
+    .await
+    .context("Simulating transactions")?
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: Serializer,
+    ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
+        let diffs = self
+            .base
+            .iter()
+            .zip(&self.target)
+            .map(|(base, target)| {
+                // The simulations are compared as values.
+                let value_serializer = Serializer::new(serializer.version);
+                let base = simulate_transactions::TransactionSimulation(base)
+                    .serialize(value_serializer)?;
+                let target = simulate_transactions::TransactionSimulation(target)
+                    .serialize(value_serializer)?;
+
+                let mut differences = Vec::new();
+                diff(&mut String::new(), &base, &target, &mut differences);
+
+                Ok(TransactionDiff {
+                    base_fee_estimate: base["fee_estimation"].clone(),
+                    target_fee_estimate: target["fee_estimation"].clone(),
+                    differences,
+                })
+            })
+            .collect::<Result<Vec<_>, crate::dto::serialize::Error>>()?;
+
+        serializer.serialize_iter(diffs.len(), &mut diffs.into_iter())
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct TransactionDiff {
+    base_fee_estimate: Value,
+    target_fee_estimate: Value,
+    differences: Vec<Difference>,
+}
+
+/// A value which differs between the two simulations. `path` is a JSON pointer
+/// into the simulation, and a missing side means the value is not present in
+/// that simulation.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub(crate) struct Difference {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<Value>,
+}
+
+/// Recursively collects the leaves which differ between `base` and `target`.
+pub(crate) fn diff(
+    path: &mut String,
+    base: &Value,
+    target: &Value,
+    differences: &mut Vec<Difference>,
+) {
+    let len = path.len();
+
+    match (base, target) {
+        (Value::Object(base), Value::Object(target)) => {
+            let keys = base.keys().chain(target.keys()).collect::<BTreeSet<_>>();
+            for key in keys {
+                path.push('/');
+                path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                diff_optional(path, base.get(key), target.get(key), differences);
+                path.truncate(len);
+            }
+        }
+        (Value::Array(base), Value::Array(target)) => {
+            for i in 0..base.len().max(target.len()) {
+                path.push('/');
+                path.push_str(&i.to_string());
+                diff_optional(path, base.get(i), target.get(i), differences);
+                path.truncate(len);
+            }
+        }
+        (base, target) if base != target => differences.push(Difference {
+            path: path.clone(),
+            base: Some(base.clone()),
+            target: Some(target.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_optional(
+    path: &mut String,
+    base: Option<&Value>,
+    target: Option<&Value>,
+    differences: &mut Vec<Difference>,
+) {
+    match (base, target) {
+        (Some(base), Some(target)) => diff(path, base, target, differences),
+        (None, None) => {}
+        (base, target) => differences.push(Difference {
+            path: path.clone(),
+            base: base.cloned(),
+            target: target.cloned(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use starknet_gateway_test_fixtures::class_definitions::DUMMY_ACCOUNT_CLASS_HASH;
+
+    use super::*;
+    use crate::RpcVersion;
+
+    #[test]
+    fn structural_diff() {
+        let base = json!({
+            "fee_estimation": { "gas_consumed": "0x10", "unit": "WEI" },
+            "transaction_trace": { "calls": [{ "result": [] }, { "a/b": 1 }], "removed": true }
+        });
+        let target = json!({
+            "fee_estimation": { "gas_consumed": "0x12", "unit": "WEI" },
+            "transaction_trace": { "calls": [{ "result": ["0x1"] }, { "a/b": 1 }] }
+        });
+
+        let mut differences = Vec::new();
+        diff(&mut String::new(), &base, &target, &mut differences);
+
+        assert_eq!(
+            differences,
+            vec![
+                Difference {
+                    path: "/fee_estimation/gas_consumed".to_owned(),
+                    base: Some(json!("0x10")),
+                    target: Some(json!("0x12")),
+                },
+                Difference {
+                    path: "/transaction_trace/calls/0/result/0".to_owned(),
+                    base: None,
+                    target: Some(json!("0x1")),
+                },
+                Difference {
+                    path: "/transaction_trace/removed".to_owned(),
+                    base: Some(json!(true)),
+                    target: None,
+                },
+            ]
+        );
+    }
+
+    fn input(base_block_id: Value, target_block_id: Value) -> Input {
+        serde_json::from_value(json!({
+            "base_block_id": base_block_id,
+            "target_block_id": target_block_id,
+            "transactions": [
+                {
+                    "contract_address_salt": "0x46c0d4abf0192a788aca261e58d7031576f7d8ea5229f452b0f23e691dd5971",
+                    "max_fee": "0x0",
+                    "signature": [],
+                    "class_hash": DUMMY_ACCOUNT_CLASS_HASH,
+                    "nonce": "0x0",
+                    "version": "0x100000000000000000000000000000001",
+                    "constructor_calldata": [],
+                    "type": "DEPLOY_ACCOUNT"
+                }
+            ],
+            "simulation_flags": ["SKIP_FEE_CHARGE"]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn same_block_has_no_differences() {
+        let (context, header, _, _) = crate::test_setup::test_context().await;
+        let block_id = json!({ "block_number": header.number });
+
+        let output = diff_transaction_traces(context, input(block_id.clone(), block_id))
+            .await
+            .unwrap();
+        let output = output
+            .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            .unwrap();
+
+        let diffs = output.as_array().unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0]["base_fee_estimate"],
+            diffs[0]["target_fee_estimate"]
+        );
+        assert_eq!(diffs[0]["differences"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let (context, header, _, _) = crate::test_setup::test_context().await;
+        let input = input(
+            json!({ "block_number": header.number }),
+            json!({ "block_hash": "0x123456" }),
+        );
+
+        let result = diff_transaction_traces(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::BlockNotFound));
+    }
+
+    #[tokio::test]
+    async fn state_pruned() {
+        let (context, header, _, _) = crate::test_setup::test_context().await;
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.prune_state_updates(header.number, 10).unwrap();
+            tx.commit().unwrap();
+        }
+        let input = input(
+            json!({ "block_number": 0 }),
+            json!({ "block_number": header.number }),
+        );
+
+        let result = diff_transaction_traces(context, input).await;
+
+        assert_matches::assert_matches!(
+            result,
+            Err(Error::StatePruned { earliest_available_block })
+                if earliest_available_block == header.number
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::Context;
+use pathfinder_common::prelude::*;
+use pathfinder_common::BlockId;
+use pathfinder_executor::{
+    CairoVersion,
+    ContractStateDump,
+    ExecutionState,
+    L1BlobDataAvailability,
+};
+
+use crate::context::RpcContext;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    block_id: BlockId,
+    contract_address: ContractAddress,
+    storage_keys: Vec<StorageAddress>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                contract_address: ContractAddress(value.deserialize("contract_address")?),
+                storage_keys: value
+                    .deserialize_optional_array("storage_keys", |value| {
+                        Ok(StorageAddress(value.deserialize()?))
+                    })?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(internal) => Self::Internal(internal),
+            Error::Custom(internal) => Self::Custom(internal),
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
+
+impl From<pathfinder_executor::CallError> for Error {
+    fn from(value: pathfinder_executor::CallError) -> Self {
+        use pathfinder_executor::CallError::*;
+        match value {
+            Internal(e) => Self::Internal(e),
+            ContractNotFound | InvalidMessageSelector => {
+                Self::Internal(anyhow::anyhow!("Unexpected error reading contract state"))
+            }
+            ContractError(e) | Custom(e) => Self::Custom(e),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    class_hash: ClassHash,
+    nonce: ContractNonce,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cairo_version: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compiled_class_hash: Option<CasmHash>,
+    storage: Vec<StorageEntry>,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+struct StorageEntry {
+    key: StorageAddress,
+    value: StorageValue,
+}
+
+impl From<ContractStateDump> for Output {
+    fn from(dump: ContractStateDump) -> Self {
+        Self {
+            class_hash: dump.class_hash,
+            nonce: dump.nonce,
+            cairo_version: dump.cairo_version.map(|version| match version {
+                CairoVersion::Zero => "CAIRO_0",
+                CairoVersion::One => "CAIRO_1",
+            }),
+            compiled_class_hash: dump.compiled_class_hash,
+            storage: dump
+                .storage
+                .into_iter()
+                .map(|(key, value)| StorageEntry { key, value })
+                .collect(),
+        }
+    }
+}
+
+/// Returns a contract's class hash, nonce and the requested storage values
+/// exactly as the executor sees them after the given block. Used to diagnose
+/// discrepancies between storage and execution.
+pub async fn dump_contract_state(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(Error::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(Error::StatePruned {
+                        earliest_available_block,
+                    });
+                }
+
+                (header, None)
+            }
+        };
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            L1BlobDataAvailability::Disabled,
+            context.config.custom_versioned_constants,
+        );
+
+        let dump = pathfinder_executor::dump_contract_state(
+            state,
+            input.contract_address,
+            &input.storage_keys,
+        )?;
+
+        Ok(dump.into())
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn deployed_contract() {
+        let context = RpcContext::for_tests();
+        let key = storage_address_bytes!(b"storage addr 0");
+        let input = Input {
+            block_id: BlockId::Latest,
+            contract_address: contract_address_bytes!(b"contract 1"),
+            storage_keys: vec![key, storage_address_bytes!(b"unset key")],
+        };
+
+        let output = dump_contract_state(context, input).await.unwrap();
+
+        assert_eq!(output.class_hash, class_hash_bytes!(b"class 1 hash"));
+        assert_eq!(output.nonce, contract_nonce!("0x10"));
+        assert_eq!(output.cairo_version, Some("CAIRO_0"));
+        assert_eq!(
+            output.storage,
+            vec![
+                StorageEntry {
+                    key,
+                    value: storage_value_bytes!(b"storage value 2"),
+                },
+                StorageEntry {
+                    key: storage_address_bytes!(b"unset key"),
+                    value: StorageValue::ZERO,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn undeployed_contract() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            block_id: BlockId::Latest,
+            contract_address: contract_address_bytes!(b"undeployed"),
+            storage_keys: vec![],
+        };
+
+        let output = dump_contract_state(context, input).await.unwrap();
+
+        assert_eq!(output.class_hash, ClassHash::ZERO);
+        assert_eq!(output.nonce, ContractNonce::ZERO);
+        assert_eq!(output.cairo_version, None);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            block_id: block_hash_bytes!(b"invalid").into(),
+            contract_address: contract_address_bytes!(b"contract 0"),
+            storage_keys: vec![],
+        };
+
+        let result = dump_contract_state(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::BlockNotFound));
+    }
+
+    #[tokio::test]
+    async fn state_pruned() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.prune_state_updates(BlockNumber::GENESIS + 2, 10)
+                .unwrap();
+            tx.commit().unwrap();
+        }
+        let input = Input {
+            block_id: BlockNumber::new_or_panic(1).into(),
+            contract_address: contract_address_bytes!(b"contract 1"),
+            storage_keys: vec![storage_address_bytes!(b"storage addr 0")],
+        };
+
+        let result = dump_contract_state(context, input).await;
+
+        assert_matches::assert_matches!(
+            result,
+            Err(Error::StatePruned { earliest_available_block }) if earliest_available_block == BlockNumber::GENESIS + 2
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+
+use pathfinder_common::{
+    BlockId,
+    CasmHash,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    SierraHash,
+    StorageAddress,
+    StorageValue,
+};
+use pathfinder_executor::types::{
+    DeclaredSierraClass,
+    DeployedContract,
+    ReplacedClass,
+    StateDiff,
+    StorageDiff,
+};
+use pathfinder_executor::DataGas;
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+use crate::method::simulate_transactions::{self, SimulateTransactionError as Error};
+use crate::v02::types::request::BroadcastedTransaction;
+use crate::v06::method::simulate_transactions as v06;
+
+/// Maximum number of transactions per request.
+const MAX_TRANSACTIONS: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    StateDiff(StateDiff),
+    Transactions {
+        block_id: BlockId,
+        transactions: Vec<BroadcastedTransaction>,
+        simulation_flags: Vec<v06::dto::SimulationFlag>,
+    },
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            if value.contains_key("state_diff") {
+                return Ok(Self::StateDiff(
+                    value.deserialize_map("state_diff", deserialize_state_diff)?,
+                ));
+            }
+
+            Ok(Self::Transactions {
+                block_id: value.deserialize("block_id")?,
+                transactions: value
+                    .deserialize_array("transactions", BroadcastedTransaction::deserialize)?,
+                simulation_flags: value
+                    .deserialize_optional_serde("simulation_flags")?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
+
+/// Parses a state diff in the format of `starknet_getStateUpdate`, where all
+/// fields are optional.
+fn deserialize_state_diff(value: &mut crate::dto::Map) -> Result<StateDiff, serde_json::Error> {
+    let storage_diffs = value
+        .deserialize_optional_array("storage_diffs", |value| {
+            value.deserialize_map(|value| {
+                let address = ContractAddress(value.deserialize("address")?);
+                let entries = value.deserialize_array("storage_entries", |value| {
+                    value.deserialize_map(|value| {
+                        Ok(StorageDiff {
+                            key: StorageAddress(value.deserialize("key")?),
+                            value: StorageValue(value.deserialize("value")?),
+                        })
+                    })
+                })?;
+                Ok((address, entries))
+            })
+        })?
+        .unwrap_or_default()
+        .into_iter()
+        .fold(
+            BTreeMap::<_, Vec<_>>::new(),
+            |mut storage_diffs, (address, entries)| {
+                storage_diffs.entry(address).or_default().extend(entries);
+                storage_diffs
+            },
+        );
+
+    Ok(StateDiff {
+        storage_diffs,
+        deprecated_declared_classes: value
+            .deserialize_optional_array("deprecated_declared_classes", |value| {
+                Ok(ClassHash(value.deserialize()?))
+            })?
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+        declared_classes: value
+            .deserialize_optional_array("declared_classes", |value| {
+                value.deserialize_map(|value| {
+                    Ok(DeclaredSierraClass {
+                        class_hash: SierraHash(value.deserialize("class_hash")?),
+                        compiled_class_hash: CasmHash(value.deserialize("compiled_class_hash")?),
+                    })
+                })
+            })?
+            .unwrap_or_default(),
+        deployed_contracts: value
+            .deserialize_optional_array("deployed_contracts", |value| {
+                value.deserialize_map(|value| {
+                    Ok(DeployedContract {
+                        address: ContractAddress(value.deserialize("address")?),
+                        class_hash: ClassHash(value.deserialize("class_hash")?),
+                    })
+                })
+            })?
+            .unwrap_or_default(),
+        replaced_classes: value
+            .deserialize_optional_array("replaced_classes", |value| {
+                value.deserialize_map(|value| {
+                    Ok(ReplacedClass {
+                        contract_address: ContractAddress(value.deserialize("contract_address")?),
+                        class_hash: ClassHash(value.deserialize("class_hash")?),
+                    })
+                })
+            })?
+            .unwrap_or_default(),
+        nonces: value
+            .deserialize_optional_array("nonces", |value| {
+                value.deserialize_map(|value| {
+                    Ok((
+                        ContractAddress(value.deserialize("contract_address")?),
+                        ContractNonce(value.deserialize("nonce")?),
+                    ))
+                })
+            })?
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output {
+    /// The data gas of each transaction, if transactions were given.
+    transactions: Option<Vec<DataGas>>,
+    total: DataGas,
+}
+
+/// Computes the L1 gas needed to publish a state diff, or the state diffs of
+/// a set of transactions executed in order on top of a block, when publishing
+/// as calldata and when publishing in blobs.
+///
+/// The gas is computed the same way as by fee estimation. Transactions are
+/// charged for their own state diffs, so the total of a set of transactions
+/// is the sum of their data gas.
+pub async fn estimate_data_gas(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let (block_id, transactions, simulation_flags) = match input {
+        Input::StateDiff(state_diff) => {
+            return Ok(Output {
+                transactions: None,
+                total: DataGas::of_state_diff(&state_diff),
+            })
+        }
+        Input::Transactions {
+            block_id,
+            transactions,
+            simulation_flags,
+        } => (block_id, transactions, simulation_flags),
+    };
+
+    if transactions.is_empty() {
+        return Err(Error::Custom(anyhow::anyhow!(
+            "No transactions to estimate"
+        )));
+    }
+    if transactions.len() > MAX_TRANSACTIONS {
+        return Err(Error::Custom(anyhow::anyhow!(
+            "Too many transactions, the maximum is {MAX_TRANSACTIONS}"
+        )));
+    }
+
+    let simulate_transactions::Output(simulations) = simulate_transactions::simulate_transactions(
+        context,
+        v06::SimulateTransactionInput {
+            block_id,
+            transactions,
+            simulation_flags: v06::dto::SimulationFlags(simulation_flags),
+        },
+    )
+    .await?;
+
+    let transactions = simulations
+        .iter()
+        .map(|simulation| DataGas::of_state_diff(simulation.trace.state_diff()))
+        .collect::<Vec<_>>();
+    let total = transactions
+        .iter()
+        .fold(DataGas::default(), |total, data_gas| {
+            total.saturating_add(*data_gas)
+        });
+
+    Ok(Output {
+        transactions: Some(transactions),
+        total,
+    })
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.flatten(&DataGasAmounts(&self.total))?;
+        if let Some(transactions) = &self.transactions {
+            serializer.serialize_iter(
+                "transactions",
+                transactions.len(),
+                &mut transactions.iter().map(DataGasAmounts),
+            )?;
+        }
+        serializer.end()
+    }
+}
+
+struct DataGasAmounts<'a>(&'a DataGas);
+
+impl SerializeForVersion for DataGasAmounts<'_> {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("calldata_l1_gas", &dto::U128Hex(self.0.calldata_l1_gas))?;
+        serializer.serialize_field("blob_l1_data_gas", &dto::U128Hex(self.0.blob_l1_data_gas))?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::DeserializeForVersion;
+    use crate::RpcVersion;
+
+    #[tokio::test]
+    async fn state_diff() {
+        let input = Input::deserialize(crate::dto::Value::new(
+            json!({
+                "state_diff": {
+                    "storage_diffs": [
+                        {
+                            "address": "0x1",
+                            "storage_entries": [
+                                {"key": "0x10", "value": "0x1"},
+                                {"key": "0x11", "value": "0x2"}
+                            ]
+                        }
+                    ],
+                    "nonces": [{"contract_address": "0x1", "nonce": "0x1"}],
+                    "deployed_contracts": [{"address": "0x2", "class_hash": "0x100"}]
+                }
+            }),
+            RpcVersion::V07,
+        ))
+        .unwrap();
+
+        let expected = DataGas::of_state_diff(&StateDiff {
+            storage_diffs: [(
+                contract_address!("0x1"),
+                vec![
+                    StorageDiff {
+                        key: storage_address!("0x10"),
+                        value: storage_value!("0x1"),
+                    },
+                    StorageDiff {
+                        key: storage_address!("0x11"),
+                        value: storage_value!("0x2"),
+                    },
+                ],
+            )]
+            .into(),
+            nonces: [(contract_address!("0x1"), contract_nonce!("0x1"))].into(),
+            deployed_contracts: vec![DeployedContract {
+                address: contract_address!("0x2"),
+                class_hash: class_hash!("0x100"),
+            }],
+            ..Default::default()
+        });
+
+        let output = estimate_data_gas(RpcContext::for_tests(), input)
+            .await
+            .unwrap();
+        assert_eq!(
+            output,
+            Output {
+                transactions: None,
+                total: expected,
+            }
+        );
+
+        let output = output.serialize(Default::default()).unwrap();
+        assert_eq!(
+            output,
+            json!({
+                "calldata_l1_gas": dto::U128Hex(expected.calldata_l1_gas)
+                    .serialize(Default::default())
+                    .unwrap(),
+                "blob_l1_data_gas": dto::U128Hex(expected.blob_l1_data_gas)
+                    .serialize(Default::default())
+                    .unwrap(),
+            })
+        );
+    }
+}
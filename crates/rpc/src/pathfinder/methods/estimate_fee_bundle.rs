@@ -0,0 +1,182 @@
+use pathfinder_common::BlockId;
+use pathfinder_executor::types::{FeeEstimate, PriceUnit};
+use primitive_types::U256;
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+use crate::method::estimate_fee::{self, EstimateFeeError as Error, SimulationFlag};
+use crate::v02::types::request::BroadcastedTransaction;
+
+/// Maximum number of transactions per bundle.
+const MAX_TRANSACTIONS: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    block_id: BlockId,
+    transactions: Vec<BroadcastedTransaction>,
+    simulation_flags: Vec<SimulationFlag>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                transactions: value
+                    .deserialize_array("transactions", BroadcastedTransaction::deserialize)?,
+                simulation_flags: value
+                    .deserialize_optional_array("simulation_flags", SimulationFlag::deserialize)?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output {
+    estimates: Vec<FeeEstimate>,
+}
+
+/// Estimates the fees of a bundle of transactions, such as the approval and
+/// swap of a multicall flow, executed in order on top of each other.
+///
+/// Besides the estimate of each transaction, returns the running total of the
+/// fees paid in its unit and the total fees of the bundle in each unit.
+pub async fn estimate_fee_bundle(context: RpcContext, input: Input) -> Result<Output, Error> {
+    if input.transactions.is_empty() {
+        return Err(Error::Custom(anyhow::anyhow!(
+            "No transactions to estimate"
+        )));
+    }
+    if input.transactions.len() > MAX_TRANSACTIONS {
+        return Err(Error::Custom(anyhow::anyhow!(
+            "Too many transactions, the maximum is {MAX_TRANSACTIONS}"
+        )));
+    }
+
+    let estimate_fee::Output(estimates, _) = estimate_fee::estimate_fee(
+        context,
+        estimate_fee::Input {
+            request: input.transactions,
+            simulation_flags: input.simulation_flags,
+            block_id: input.block_id,
+            include_alternate_unit: false,
+            pending_gas_prices: None,
+        },
+    )
+    .await?;
+
+    Ok(Output { estimates })
+}
+
+/// The fees paid in each unit.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Totals {
+    wei: U256,
+    fri: U256,
+}
+
+impl Totals {
+    fn add(&mut self, estimate: &FeeEstimate) -> U256 {
+        let total = match estimate.unit {
+            PriceUnit::Wei => &mut self.wei,
+            PriceUnit::Fri => &mut self.fri,
+        };
+        *total = total.saturating_add(estimate.overall_fee);
+        *total
+    }
+}
+
+impl Output {
+    /// The running total of each estimate's unit after it, and the totals of
+    /// the bundle.
+    fn cumulative_fees(&self) -> (Vec<U256>, Totals) {
+        let mut totals = Totals::default();
+        let cumulative = self
+            .estimates
+            .iter()
+            .map(|estimate| totals.add(estimate))
+            .collect();
+        (cumulative, totals)
+    }
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let (cumulative, totals) = self.cumulative_fees();
+
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_iter(
+            "estimates",
+            self.estimates.len(),
+            &mut self
+                .estimates
+                .iter()
+                .zip(cumulative)
+                .map(|(estimate, cumulative_fee)| BundleEstimate {
+                    estimate,
+                    cumulative_fee,
+                }),
+        )?;
+        serializer.serialize_field("overall_fee_wei", &dto::U256Hex(totals.wei))?;
+        serializer.serialize_field("overall_fee_fri", &dto::U256Hex(totals.fri))?;
+        serializer.end()
+    }
+}
+
+struct BundleEstimate<'a> {
+    estimate: &'a FeeEstimate,
+    cumulative_fee: U256,
+}
+
+impl SerializeForVersion for BundleEstimate<'_> {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("fee_estimation", &dto::FeeEstimate(self.estimate))?;
+        serializer.serialize_field("cumulative_fee", &dto::U256Hex(self.cumulative_fee))?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate(overall_fee: u64, unit: PriceUnit) -> FeeEstimate {
+        FeeEstimate {
+            gas_consumed: Default::default(),
+            gas_price: Default::default(),
+            data_gas_consumed: Default::default(),
+            data_gas_price: Default::default(),
+            overall_fee: overall_fee.into(),
+            unit,
+        }
+    }
+
+    #[test]
+    fn fees_are_accumulated_per_unit() {
+        let output = Output {
+            estimates: vec![
+                estimate(1, PriceUnit::Wei),
+                estimate(10, PriceUnit::Fri),
+                estimate(2, PriceUnit::Wei),
+                estimate(20, PriceUnit::Fri),
+            ],
+        };
+
+        let (cumulative, totals) = output.cumulative_fees();
+
+        assert_eq!(
+            cumulative,
+            vec![U256::from(1), U256::from(10), U256::from(3), U256::from(30)]
+        );
+        assert_eq!(
+            totals,
+            Totals {
+                wei: 3.into(),
+                fri: 30.into(),
+            }
+        );
+    }
+}
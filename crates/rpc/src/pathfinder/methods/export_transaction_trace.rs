@@ -0,0 +1,405 @@
+use std::fmt::Write;
+
+use pathfinder_common::TransactionHash;
+use pathfinder_executor::types::{
+    CallType,
+    EntryPointType,
+    ExecuteInvocation,
+    FunctionInvocation,
+    TransactionTrace,
+};
+
+use crate::context::RpcContext;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+use crate::dto::TraceFormat;
+use crate::method::trace_transaction::{self, TraceTransactionError as Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// A Graphviz DOT digraph of the calls.
+    Graphviz,
+    /// A Chrome trace-event JSON object.
+    ChromeTrace,
+}
+
+#[derive(Debug)]
+pub struct Input {
+    transaction_hash: TransactionHash,
+    format: ExportFormat,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: value.deserialize("transaction_hash").map(TransactionHash)?,
+                format: value.deserialize_serde("format")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Output {
+    Graphviz(String),
+    ChromeTrace(ChromeTrace),
+}
+
+/// Exports the call tree of a transaction's trace for viewing in standard
+/// visualizers, either as a Graphviz DOT digraph or as Chrome trace events
+/// which can be opened in `chrome://tracing` or Perfetto.
+///
+/// The trace is the one returned by `starknet_traceTransaction`.
+pub async fn export_transaction_trace(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let trace_transaction::Output { trace, .. } = trace_transaction::trace_transaction(
+        context,
+        trace_transaction::Input {
+            transaction_hash: input.transaction_hash,
+            trace_format: TraceFormat::Nested,
+        },
+    )
+    .await?;
+
+    let roots = roots(&trace);
+    Ok(match input.format {
+        ExportFormat::Graphviz => Output::Graphviz(graphviz(&roots)),
+        ExportFormat::ChromeTrace => Output::ChromeTrace(chrome_trace(&roots)),
+    })
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        match self {
+            Output::Graphviz(dot) => serializer.serialize_str(dot),
+            Output::ChromeTrace(trace) => serializer.serialize(trace),
+        }
+    }
+}
+
+/// A top-level invocation of a transaction, named by its type.
+enum Root<'a> {
+    Call(&'static str, &'a FunctionInvocation),
+    Reverted(&'static str, &'a str),
+}
+
+/// Returns the top-level invocations of `trace` in the order they were
+/// executed.
+fn roots(trace: &TransactionTrace) -> Vec<Root<'_>> {
+    fn push<'a>(
+        roots: &mut Vec<Root<'a>>,
+        invocation_type: &'static str,
+        invocation: &'a Option<FunctionInvocation>,
+    ) {
+        if let Some(invocation) = invocation {
+            roots.push(Root::Call(invocation_type, invocation));
+        }
+    }
+
+    let mut roots = Vec::new();
+    match trace {
+        TransactionTrace::Declare(trace) => {
+            push(&mut roots, "VALIDATE", &trace.validate_invocation);
+            push(&mut roots, "FEE_TRANSFER", &trace.fee_transfer_invocation);
+        }
+        TransactionTrace::DeployAccount(trace) => {
+            push(&mut roots, "CONSTRUCTOR", &trace.constructor_invocation);
+            push(&mut roots, "VALIDATE", &trace.validate_invocation);
+            push(&mut roots, "FEE_TRANSFER", &trace.fee_transfer_invocation);
+        }
+        TransactionTrace::Invoke(trace) => {
+            push(&mut roots, "VALIDATE", &trace.validate_invocation);
+            match &trace.execute_invocation {
+                ExecuteInvocation::FunctionInvocation(invocation) => {
+                    push(&mut roots, "EXECUTE", invocation)
+                }
+                ExecuteInvocation::RevertedReason(revert_reason) => {
+                    roots.push(Root::Reverted("EXECUTE", revert_reason))
+                }
+            }
+            push(&mut roots, "FEE_TRANSFER", &trace.fee_transfer_invocation);
+        }
+        TransactionTrace::L1Handler(trace) => {
+            push(&mut roots, "L1_HANDLER", &trace.function_invocation);
+        }
+    }
+
+    roots
+}
+
+fn call_type(call: &FunctionInvocation) -> &'static str {
+    match call.call_type {
+        CallType::Call => "CALL",
+        CallType::Delegate => "DELEGATE",
+    }
+}
+
+fn entry_point_type(call: &FunctionInvocation) -> &'static str {
+    match call.entry_point_type {
+        EntryPointType::Constructor => "CONSTRUCTOR",
+        EntryPointType::External => "EXTERNAL",
+        EntryPointType::L1Handler => "L1_HANDLER",
+    }
+}
+
+/// Renders the calls as a digraph with an edge from each call to the calls it
+/// made, in order.
+fn graphviz(roots: &[Root<'_>]) -> String {
+    fn node(dot: &mut String, next_id: &mut usize, call: &FunctionInvocation) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let _ = writeln!(
+            dot,
+            "  n{id} [label=\"{}\\n{}\\n{} {}\\nsteps: {}\"];",
+            call.contract_address.0.to_hex_str(),
+            call.selector.to_hex_str(),
+            call_type(call),
+            entry_point_type(call),
+            call.computation_resources.steps,
+        );
+        for inner in &call.internal_calls {
+            let inner = node(dot, next_id, inner);
+            let _ = writeln!(dot, "  n{id} -> n{inner};");
+        }
+
+        id
+    }
+
+    let mut dot = String::from("digraph trace {\n  node [shape=box, fontname=\"monospace\"];\n");
+    let mut next_id = 0;
+    for root in roots {
+        let label = match root {
+            Root::Call(invocation_type, _) => invocation_type.to_string(),
+            Root::Reverted(invocation_type, revert_reason) => {
+                format!("{invocation_type}\\nreverted: {}", escape(revert_reason))
+            }
+        };
+        let id = next_id;
+        next_id += 1;
+        let _ = writeln!(dot, "  n{id} [label=\"{label}\", shape=ellipse];");
+        if let Root::Call(_, call) = root {
+            let call = node(&mut dot, &mut next_id, call);
+            let _ = writeln!(dot, "  n{id} -> n{call};");
+        }
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+/// Escapes `text` for use in a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// A trace in the [Chrome trace-event format](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU).
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct ChromeTrace {
+    #[serde(rename = "traceEvents")]
+    trace_events: Vec<ChromeTraceEvent>,
+}
+
+/// A complete event, spanning a call and the calls it made.
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct ChromeTraceEvent {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    ts: usize,
+    dur: usize,
+    pid: u32,
+    tid: u32,
+    args: serde_json::Value,
+}
+
+/// Lays the calls out on a single timeline where each microsecond stands for
+/// a Cairo step, as no wall-clock times are known.
+///
+/// A call spans its steps, which include those of the calls it made. The
+/// calls it made are placed one after the other from its start, since where
+/// they happened within it is unknown.
+fn chrome_trace(roots: &[Root<'_>]) -> ChromeTrace {
+    /// Pushes the events of `call` starting at `ts` and returns its duration.
+    fn push(
+        events: &mut Vec<ChromeTraceEvent>,
+        invocation_type: &'static str,
+        ts: usize,
+        call: &FunctionInvocation,
+    ) -> usize {
+        let index = events.len();
+        events.push(ChromeTraceEvent {
+            name: format!(
+                "{} {}",
+                call.contract_address.0.to_hex_str(),
+                call.selector.to_hex_str()
+            ),
+            cat: invocation_type,
+            ph: "X",
+            ts,
+            dur: 0,
+            pid: 1,
+            tid: 1,
+            args: serde_json::json!({
+                "contract_address": call.contract_address.0.to_hex_str(),
+                "selector": call.selector.to_hex_str(),
+                "class_hash": call.class_hash.map(|class_hash| class_hash.to_hex_str()),
+                "call_type": call_type(call),
+                "entry_point_type": entry_point_type(call),
+                "steps": call.computation_resources.steps,
+                "events": call.events.len(),
+                "messages": call.messages.len(),
+            }),
+        });
+
+        let mut inner_ts = ts;
+        for inner in &call.internal_calls {
+            inner_ts += push(events, invocation_type, inner_ts, inner);
+        }
+        // Keeps the calls it made within the call, should their steps not add up.
+        let dur = call.computation_resources.steps.max(inner_ts - ts);
+        events[index].dur = dur;
+
+        dur
+    }
+
+    let mut trace_events = Vec::new();
+    let mut ts = 0;
+    for root in roots {
+        match root {
+            Root::Call(invocation_type, call) => {
+                ts += push(&mut trace_events, invocation_type, ts, call);
+            }
+            Root::Reverted(invocation_type, revert_reason) => trace_events.push(ChromeTraceEvent {
+                name: format!("{invocation_type} reverted"),
+                cat: invocation_type,
+                ph: "X",
+                ts,
+                dur: 0,
+                pid: 1,
+                tid: 1,
+                args: serde_json::json!({ "revert_reason": revert_reason }),
+            }),
+        }
+    }
+
+    ChromeTrace { trace_events }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_crypto::Felt;
+    use pathfinder_executor::types::{ComputationResources, InvokeTransactionTrace};
+
+    use super::*;
+
+    fn call(
+        address: &str,
+        steps: usize,
+        internal_calls: Vec<FunctionInvocation>,
+    ) -> FunctionInvocation {
+        FunctionInvocation {
+            calldata: vec![],
+            contract_address: pathfinder_common::ContractAddress(
+                Felt::from_hex_str(address).unwrap(),
+            ),
+            selector: felt!("0x5"),
+            call_type: CallType::Call,
+            caller_address: Felt::ZERO,
+            internal_calls,
+            class_hash: None,
+            entry_point_type: EntryPointType::External,
+            events: vec![],
+            messages: vec![],
+            result: vec![],
+            computation_resources: ComputationResources {
+                steps,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn trace() -> TransactionTrace {
+        TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: Some(call("0x1", 10, vec![])),
+            execute_invocation: ExecuteInvocation::FunctionInvocation(Some(call(
+                "0x1",
+                100,
+                vec![
+                    call("0x2", 30, vec![call("0x3", 5, vec![])]),
+                    call("0x4", 20, vec![]),
+                ],
+            ))),
+            fee_transfer_invocation: None,
+            state_diff: Default::default(),
+            execution_resources: Default::default(),
+        })
+    }
+
+    #[test]
+    fn graphviz_edges_follow_calls() {
+        let dot = graphviz(&roots(&trace()));
+        let edges = dot
+            .lines()
+            .filter(|line| line.contains("->"))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            edges,
+            vec![
+                "n0 -> n1;",
+                "n4 -> n5;",
+                "n3 -> n4;",
+                "n3 -> n6;",
+                "n2 -> n3;",
+            ]
+        );
+        assert!(dot.contains("n4 [label=\"0x2\\n0x5\\nCALL EXTERNAL\\nsteps: 30\"];"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn chrome_trace_nests_calls() {
+        let trace = chrome_trace(&roots(&trace()));
+        let spans = trace
+            .trace_events
+            .iter()
+            .map(|event| (event.name.as_str(), event.cat, event.ts, event.dur))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            vec![
+                ("0x1 0x5", "VALIDATE", 0, 10),
+                ("0x1 0x5", "EXECUTE", 10, 100),
+                ("0x2 0x5", "EXECUTE", 10, 30),
+                ("0x3 0x5", "EXECUTE", 10, 5),
+                ("0x4 0x5", "EXECUTE", 40, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn reverted_execution_is_marked() {
+        let trace = TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: None,
+            execute_invocation: ExecuteInvocation::RevertedReason("out of \"gas\"".to_owned()),
+            fee_transfer_invocation: None,
+            state_diff: Default::default(),
+            execution_resources: Default::default(),
+        });
+
+        let dot = graphviz(&roots(&trace));
+        assert!(dot.contains(r#"label="EXECUTE\nreverted: out of \"gas\"""#));
+
+        let trace = chrome_trace(&roots(&trace));
+        assert_eq!(
+            trace.trace_events[0].args,
+            serde_json::json!({ "revert_reason": "out of \"gas\"" })
+        );
+    }
+}
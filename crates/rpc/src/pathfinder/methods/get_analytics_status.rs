@@ -0,0 +1,83 @@
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+
+crate::error::generate_rpc_error_subset!(Error);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output {
+    enabled: bool,
+    refreshed_to: Option<BlockNumber>,
+}
+
+/// Returns whether analytics are enabled and the last block included in them.
+pub async fn get_analytics_status(context: RpcContext) -> Result<Output, Error> {
+    let enabled = context.config.analytics;
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let refreshed_to = db
+            .analytics_refreshed_to()
+            .context("Querying analytics checkpoint")?;
+
+        Ok(Output {
+            enabled,
+            refreshed_to,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("enabled", &self.enabled)?;
+        serializer.serialize_optional(
+            "refreshed_to_block",
+            self.refreshed_to.map(dto::BlockNumber),
+        )?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn refreshed_to() {
+        let mut context = RpcContext::for_tests();
+        context.config.analytics = true;
+
+        let output = get_analytics_status(context.clone()).await.unwrap();
+        assert_eq!(
+            output,
+            Output {
+                enabled: true,
+                refreshed_to: None,
+            }
+        );
+
+        let latest = {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            let latest = db.refresh_analytics(1000).unwrap();
+            db.commit().unwrap();
+            latest
+        };
+
+        let output = get_analytics_status(context).await.unwrap();
+        assert_eq!(output.refreshed_to, latest);
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::Context;
+use pathfinder_common::{BlockCommitmentSignatureElem, BlockHash, BlockId, BlockNumber, PublicKey};
+
+use crate::context::RpcContext;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    block_hash: BlockHash,
+    block_number: BlockNumber,
+    signature: [BlockCommitmentSignatureElem; 2],
+    public_key: PublicKey,
+}
+
+/// Returns the signature of a block along with the public key it was verified
+/// against when the block was received over P2P, or `null` if the signature
+/// was not verified. The pending block is never attested.
+pub async fn get_block_attestation(
+    context: RpcContext,
+    input: Input,
+) -> Result<Option<Output>, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let block_id = match input.block_id {
+            BlockId::Pending => return Ok(None),
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+
+        let (block_number, block_hash) = db
+            .block_id(block_id)
+            .context("Querying block id")?
+            .ok_or(Error::BlockNotFound)?;
+
+        let output = db
+            .signature_attestation(block_number.into())
+            .context("Querying signature attestation")?
+            .map(|(signature, public_key)| Output {
+                block_hash,
+                block_number,
+                signature: [signature.r, signature.s],
+                public_key,
+            });
+
+        Ok(output)
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockCommitmentSignature;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn attested() {
+        let context = RpcContext::for_tests();
+        let public_key = public_key_bytes!(b"public key");
+        let signature = BlockCommitmentSignature {
+            r: block_commitment_signature_elem_bytes!(b"r"),
+            s: block_commitment_signature_elem_bytes!(b"s"),
+        };
+        let (block_number, block_hash) = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            let (block_number, block_hash) = tx
+                .block_id(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap();
+            tx.insert_signature(block_number, &signature).unwrap();
+            tx.insert_signature_attestation(block_number, public_key)
+                .unwrap();
+            tx.commit().unwrap();
+            (block_number, block_hash)
+        };
+
+        let output = get_block_attestation(
+            context,
+            Input {
+                block_id: BlockId::Latest,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            output,
+            Some(Output {
+                block_hash,
+                block_number,
+                signature: [signature.r, signature.s],
+                public_key,
+            })
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::not_attested(BlockId::Number(BlockNumber::GENESIS))]
+    #[case::pending(BlockId::Pending)]
+    #[tokio::test]
+    async fn not_attested(#[case] block_id: BlockId) {
+        let context = RpcContext::for_tests_with_pending().await;
+
+        let output = get_block_attestation(context, Input { block_id })
+            .await
+            .unwrap();
+
+        assert_eq!(output, None);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let input = Input {
+            block_id: block_hash_bytes!(b"invalid").into(),
+        };
+        let context = RpcContext::for_tests();
+        let result = get_block_attestation(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::BlockNotFound));
+    }
+}
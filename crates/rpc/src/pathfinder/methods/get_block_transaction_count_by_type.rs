@@ -0,0 +1,132 @@
+use anyhow::Context;
+use pathfinder_common::transaction::TransactionKind;
+use pathfinder_common::{BlockId, TransactionVersion};
+use pathfinder_storage::TransactionTypeCount;
+
+use crate::context::RpcContext;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    total: u64,
+    counts: Vec<TypeCount>,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+struct TypeCount {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    version: TransactionVersion,
+    count: u64,
+}
+
+impl From<Vec<TransactionTypeCount>> for Output {
+    fn from(counts: Vec<TransactionTypeCount>) -> Self {
+        let counts: Vec<_> = counts
+            .into_iter()
+            .map(|count| TypeCount {
+                kind: match count.kind {
+                    TransactionKind::Declare => "DECLARE",
+                    TransactionKind::Deploy => "DEPLOY",
+                    TransactionKind::DeployAccount => "DEPLOY_ACCOUNT",
+                    TransactionKind::Invoke => "INVOKE",
+                    TransactionKind::L1Handler => "L1_HANDLER",
+                },
+                version: count.version,
+                count: count.count,
+            })
+            .collect();
+
+        Self {
+            total: counts.iter().map(|count| count.count).sum(),
+            counts,
+        }
+    }
+}
+
+/// Returns the number of transactions in a block broken down by transaction
+/// type and version.
+pub async fn get_block_transaction_count_by_type(
+    context: RpcContext,
+    input: Input,
+) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let block_id = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+                let counts =
+                    pathfinder_storage::count_transactions_by_type(&pending.block.transactions);
+                return Ok(counts.into());
+            }
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+
+        db.transaction_counts_by_type(block_id)
+            .context("Reading transaction counts from database")?
+            .map(Output::from)
+            .ok_or(Error::BlockNotFound)
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::latest(BlockId::Latest, 5)]
+    #[case::pending(BlockId::Pending, 3)]
+    #[tokio::test]
+    async fn total_matches_transaction_count(#[case] block_id: BlockId, #[case] expected: u64) {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = Input { block_id };
+        let output = get_block_transaction_count_by_type(context, input)
+            .await
+            .unwrap();
+
+        assert_eq!(output.total, expected);
+        assert!(output.counts.iter().all(|count| count.count > 0));
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let input = Input {
+            block_id: block_hash_bytes!(b"invalid").into(),
+        };
+        let context = RpcContext::for_tests_with_pending().await;
+        let result = get_block_transaction_count_by_type(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::BlockNotFound));
+    }
+}
@@ -0,0 +1,36 @@
+use crate::call_stats::CallStatsSummary;
+use crate::context::RpcContext;
+
+/// Number of targets returned if the request does not set a limit.
+const DEFAULT_LIMIT: usize = 20;
+/// Maximum number of targets returned per request.
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    limit: Option<usize>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                limit: value.deserialize_optional_serde("limit")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error);
+
+/// Returns the contracts and entry points most targeted by `starknet_call`
+/// within the configured window. Empty unless call statistics are enabled.
+pub async fn get_call_stats(context: RpcContext, input: Input) -> Result<CallStatsSummary, Error> {
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    Ok(context
+        .call_stats
+        .as_ref()
+        .map(|call_stats| call_stats.top(limit))
+        .unwrap_or_default())
+}
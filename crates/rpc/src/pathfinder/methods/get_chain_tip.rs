@@ -0,0 +1,234 @@
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber, BlockTimestamp};
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+use crate::v02::types::syncing::Syncing;
+
+crate::error::generate_rpc_error_subset!(Error);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output {
+    latest: Option<LatestBlock>,
+    pending: PendingBlock,
+    l1_accepted: Option<L1AcceptedBlock>,
+    syncing: Syncing,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LatestBlock {
+    number: BlockNumber,
+    hash: BlockHash,
+    timestamp: BlockTimestamp,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct PendingBlock {
+    number: BlockNumber,
+    parent_hash: BlockHash,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct L1AcceptedBlock {
+    number: BlockNumber,
+    hash: BlockHash,
+}
+
+/// Returns the latest block, the pending block's parent, the latest L1
+/// accepted block and the sync status in a single call.
+///
+/// All block information is read in the same database transaction so that it
+/// is consistent. The pending block is the empty successor of the latest block
+/// if there is no pending data for it.
+pub async fn get_chain_tip(context: RpcContext) -> Result<Output, Error> {
+    let syncing = { context.sync_status.status.read().await.clone() };
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let latest = db
+            .block_header(pathfinder_storage::BlockId::Latest)
+            .context("Querying latest block header")?
+            .map(|header| LatestBlock {
+                number: header.number,
+                hash: header.hash,
+                timestamp: header.timestamp,
+            });
+
+        let pending = context
+            .pending_data
+            .get(&db)
+            .context("Querying pending data")?;
+        let pending = PendingBlock {
+            number: pending.number,
+            parent_hash: pending.block.parent_hash,
+        };
+
+        let l1_accepted = match db.l1_l2_pointer().context("Querying L1-L2 head")? {
+            Some(number) => db
+                .block_hash(number.into())
+                .context("Querying L1 accepted block hash")?
+                .map(|hash| L1AcceptedBlock { number, hash }),
+            None => None,
+        };
+
+        Ok(Output {
+            latest,
+            pending,
+            l1_accepted,
+            syncing,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+
+        serializer.serialize_optional("latest_block", self.latest)?;
+        serializer.serialize_field("pending_block", &self.pending)?;
+        serializer.serialize_optional("l1_accepted_block", self.l1_accepted)?;
+        serializer.serialize_field("syncing", &SyncingDto(&self.syncing))?;
+
+        serializer.end()
+    }
+}
+
+impl SerializeForVersion for LatestBlock {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &dto::BlockNumber(self.number))?;
+        serializer.serialize_field("block_hash", &dto::BlockHash(&self.hash))?;
+        serializer.serialize_field("timestamp", &self.timestamp.get())?;
+        serializer.end()
+    }
+}
+
+impl SerializeForVersion for PendingBlock {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &dto::BlockNumber(self.number))?;
+        serializer.serialize_field("parent_hash", &dto::BlockHash(&self.parent_hash))?;
+        serializer.end()
+    }
+}
+
+impl SerializeForVersion for L1AcceptedBlock {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &dto::BlockNumber(self.number))?;
+        serializer.serialize_field("block_hash", &dto::BlockHash(&self.hash))?;
+        serializer.end()
+    }
+}
+
+struct SyncingDto<'a>(&'a Syncing);
+
+impl SerializeForVersion for SyncingDto<'_> {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        match self.0 {
+            Syncing::False(_) => serializer.serialize_bool(false),
+            Syncing::Status(status) => serializer.serialize(&dto::SyncStatus(status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_storage::BlockId;
+    use serde_json::json;
+
+    use super::*;
+    use crate::RpcVersion;
+
+    #[tokio::test]
+    async fn chain_tip() {
+        let context = RpcContext::for_tests_with_pending().await;
+
+        let (latest, genesis) = {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            (
+                db.block_header(BlockId::Latest).unwrap().unwrap(),
+                db.block_header(BlockNumber::GENESIS.into())
+                    .unwrap()
+                    .unwrap(),
+            )
+        };
+
+        let output = get_chain_tip(context).await.unwrap();
+
+        assert_eq!(
+            output.latest,
+            Some(LatestBlock {
+                number: latest.number,
+                hash: latest.hash,
+                timestamp: latest.timestamp,
+            })
+        );
+        assert_eq!(
+            output.pending,
+            PendingBlock {
+                number: latest.number + 1,
+                parent_hash: latest.hash,
+            }
+        );
+        // The test database marks genesis as L1 accepted.
+        assert_eq!(
+            output.l1_accepted,
+            Some(L1AcceptedBlock {
+                number: genesis.number,
+                hash: genesis.hash,
+            })
+        );
+        assert_eq!(output.syncing, Syncing::False(false));
+    }
+
+    #[test]
+    fn serialization() {
+        let output = Output {
+            latest: Some(LatestBlock {
+                number: BlockNumber::new_or_panic(2),
+                hash: block_hash!("0x2"),
+                timestamp: BlockTimestamp::new_or_panic(10),
+            }),
+            pending: PendingBlock {
+                number: BlockNumber::new_or_panic(3),
+                parent_hash: block_hash!("0x2"),
+            },
+            l1_accepted: None,
+            syncing: Syncing::False(false),
+        };
+
+        let serialized = output
+            .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            .unwrap();
+
+        assert_eq!(
+            serialized,
+            json!({
+                "latest_block": {
+                    "block_number": 2,
+                    "block_hash": "0x2",
+                    "timestamp": 10,
+                },
+                "pending_block": {
+                    "block_number": 3,
+                    "parent_hash": "0x2",
+                },
+                "syncing": false,
+            })
+        );
+    }
+}
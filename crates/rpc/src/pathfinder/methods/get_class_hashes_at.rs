@@ -0,0 +1,220 @@
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, ClassHash, ContractAddress};
+
+use crate::context::RpcContext;
+
+/// Maximum number of contracts per request.
+const MAX_CONTRACTS: usize = 10_000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    contract_addresses: Vec<ContractAddress>,
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contract_addresses: value.deserialize_array("contract_addresses", |value| {
+                    Ok(ContractAddress(value.deserialize()?))
+                })?,
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    PageSizeTooBig,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(internal) => Self::Internal(internal),
+            Error::Custom(internal) => Self::Custom(internal),
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::PageSizeTooBig => Self::PageSizeTooBig,
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
+
+/// The class hash of each contract, in the order of the request. `None` for
+/// contracts which are not deployed at the block.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output(Vec<Option<ClassHash>>);
+
+/// Batch variant of `starknet_getClassHashAt`, which looks up the class
+/// hashes of all contracts within a single database transaction.
+///
+/// Unlike `starknet_getClassHashAt`, contracts which are not deployed do not
+/// fail the request but have a `null` class hash.
+pub async fn get_class_hashes_at(context: RpcContext, input: Input) -> Result<Output, Error> {
+    if input.contract_addresses.len() > MAX_CONTRACTS {
+        return Err(Error::PageSizeTooBig);
+    }
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let pending = match input.block_id {
+            BlockId::Pending => Some(
+                context
+                    .pending_data
+                    .get(&tx)
+                    .context("Querying pending data")?
+                    .state_update,
+            ),
+            _ => None,
+        };
+
+        let block_id = match input.block_id {
+            BlockId::Pending => pathfinder_storage::BlockId::Latest,
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+        let (block_number, _) = tx
+            .block_id(block_id)
+            .context("Querying block id")?
+            .ok_or(Error::BlockNotFound)?;
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_number.into())
+            .context("Checking state pruning")?
+        {
+            return Err(Error::StatePruned {
+                earliest_available_block,
+            });
+        }
+
+        let mut class_hashes = tx
+            .contract_class_hashes(block_number, &input.contract_addresses)
+            .context("Fetching class hashes from database")?;
+
+        if let Some(pending) = pending {
+            for (class_hash, contract_address) in
+                class_hashes.iter_mut().zip(&input.contract_addresses)
+            {
+                if let Some(pending) = pending.contract_class(*contract_address) {
+                    *class_hash = Some(pending);
+                }
+            }
+        }
+
+        Ok(Output(class_hashes))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn at_block() {
+        let context = RpcContext::for_tests();
+
+        let input = Input {
+            contract_addresses: vec![
+                contract_address_bytes!(b"contract 0"),
+                contract_address_bytes!(b"contract 1"),
+                contract_address_bytes!(b"invalid"),
+            ],
+            block_id: BlockNumber::new_or_panic(1).into(),
+        };
+        let result = get_class_hashes_at(context, input).await.unwrap();
+        assert_eq!(
+            result,
+            Output(vec![
+                Some(class_hash_bytes!(b"class 0 hash")),
+                Some(class_hash_bytes!(b"class 1 hash")),
+                None,
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+
+        let input = Input {
+            contract_addresses: vec![
+                contract_address_bytes!(b"contract 0"),
+                contract_address_bytes!(b"pending contract 0 address"),
+                contract_address_bytes!(b"pending contract 2 (replaced)"),
+            ],
+            block_id: BlockId::Pending,
+        };
+        let result = get_class_hashes_at(context, input).await.unwrap();
+        assert_eq!(
+            result,
+            Output(vec![
+                Some(class_hash_bytes!(b"class 0 hash")),
+                Some(class_hash_bytes!(b"pending class 0 hash")),
+                Some(class_hash_bytes!(b"pending class 2 hash (replaced)")),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+
+        let input = Input {
+            contract_addresses: vec![contract_address_bytes!(b"contract 0")],
+            block_id: BlockId::Hash(block_hash_bytes!(b"invalid")),
+        };
+        let result = get_class_hashes_at(context, input).await;
+        assert_matches!(result, Err(Error::BlockNotFound));
+    }
+
+    #[tokio::test]
+    async fn state_pruned() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.prune_state_updates(BlockNumber::GENESIS + 2, 10)
+                .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let input = Input {
+            contract_addresses: vec![contract_address_bytes!(b"contract 1")],
+            block_id: BlockNumber::new_or_panic(1).into(),
+        };
+        let result = get_class_hashes_at(context, input).await;
+        assert_matches!(
+            result,
+            Err(Error::StatePruned { earliest_available_block }) if earliest_available_block == BlockNumber::GENESIS + 2
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Context;
+use pathfinder_common::{ClassHash, EntryPoint};
+
+use crate::context::RpcContext;
+
+/// Number of classes returned if the request does not set a limit.
+const DEFAULT_LIMIT: usize = 100;
+/// Maximum number of classes returned per request.
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    selector: EntryPoint,
+    continuation_token: Option<ClassHash>,
+    limit: Option<usize>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                selector: EntryPoint(value.deserialize("selector")?),
+                continuation_token: value
+                    .deserialize_optional("continuation_token")?
+                    .map(ClassHash),
+                limit: value.deserialize_optional_serde("limit")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error);
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    class_hashes: Vec<ClassHash>,
+    /// Set if there are more classes, in which case it continues the search
+    /// from the last class returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    continuation_token: Option<ClassHash>,
+}
+
+/// Returns the hashes of all stored classes which have an entry point of
+/// `selector`, of any type, in ascending order.
+///
+/// Classes are only found once their definitions have been downloaded, and
+/// remain found if the block declaring them is reorged away.
+pub async fn get_classes_by_selector(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        // Query one more class to learn whether there is another page.
+        let mut class_hashes = db
+            .classes_by_selector(input.selector, input.continuation_token, limit + 1)
+            .context("Querying classes by selector")?;
+
+        let continuation_token = if class_hashes.len() > limit {
+            class_hashes.truncate(limit);
+            class_hashes.last().copied()
+        } else {
+            None
+        };
+
+        Ok(Output {
+            class_hashes,
+            continuation_token,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn pages_through_classes() {
+        let context = RpcContext::for_tests();
+        let definition =
+            br#"{"entry_points_by_type":{"EXTERNAL":[{"selector":"0xabc","offset":"0x0"}]}}"#;
+        let class_hashes = [
+            class_hash!("0x1001"),
+            class_hash!("0x1002"),
+            class_hash!("0x1003"),
+        ];
+        {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            for class_hash in class_hashes {
+                db.insert_cairo_class(class_hash, definition).unwrap();
+            }
+            db.commit().unwrap();
+        }
+
+        let input = Input {
+            selector: entry_point!("0xabc"),
+            continuation_token: None,
+            limit: Some(2),
+        };
+        let output = get_classes_by_selector(context.clone(), input)
+            .await
+            .unwrap();
+        assert_eq!(
+            output,
+            Output {
+                class_hashes: class_hashes[..2].to_vec(),
+                continuation_token: Some(class_hashes[1]),
+            }
+        );
+
+        let input = Input {
+            selector: entry_point!("0xabc"),
+            continuation_token: output.continuation_token,
+            limit: Some(2),
+        };
+        let output = get_classes_by_selector(context, input).await.unwrap();
+        assert_eq!(
+            output,
+            Output {
+                class_hashes: class_hashes[2..].to_vec(),
+                continuation_token: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, ContractRoot};
+
+use crate::context::RpcContext;
+
+/// Maximum number of roots returned per request.
+const PAGE_SIZE: usize = 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    contract_address: ContractAddress,
+    from_block: Option<BlockId>,
+    to_block: Option<BlockId>,
+    continuation_token: Option<String>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contract_address: ContractAddress(value.deserialize("contract_address")?),
+                from_block: value.deserialize_optional("from_block")?,
+                to_block: value.deserialize_optional("to_block")?,
+                continuation_token: value.deserialize_optional_serde("continuation_token")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error: BlockNotFound, InvalidContinuationToken);
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    roots: Vec<RootEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    continuation_token: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct RootEntry {
+    block_number: BlockNumber,
+    /// `None` if the contract's storage trie at this block has been pruned.
+    root: Option<ContractRoot>,
+}
+
+/// Returns the storage roots of a contract in a block range, as the blocks at
+/// which the root changed along with the new root. The first entry is the root
+/// in effect at `from_block`, which allows anchoring storage proofs at any
+/// block in the range.
+///
+/// The pending block is treated as the latest block since pending roots are
+/// not stored. The continuation token is the block number of the first root
+/// of the next page.
+pub async fn get_contract_root_history(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let block_number = |block_id: BlockId| -> Result<BlockNumber, Error> {
+            let block_id = match block_id {
+                BlockId::Pending => pathfinder_storage::BlockId::Latest,
+                other => other.try_into().expect("Only pending cast should fail"),
+            };
+            let (block_number, _) = db
+                .block_id(block_id)
+                .context("Querying block id")?
+                .ok_or(Error::BlockNotFound)?;
+            Ok(block_number)
+        };
+
+        let mut from = match input.from_block {
+            Some(block_id) => block_number(block_id)?,
+            None => BlockNumber::GENESIS,
+        };
+        let to = block_number(input.to_block.unwrap_or(BlockId::Latest))?;
+
+        if let Some(token) = &input.continuation_token {
+            let token = token
+                .parse::<u64>()
+                .ok()
+                .and_then(BlockNumber::new)
+                .filter(|token| (from..=to).contains(token))
+                .ok_or(Error::InvalidContinuationToken)?;
+            from = token;
+        }
+
+        let mut history = db
+            .contract_root_history(input.contract_address, from, to, PAGE_SIZE + 1)
+            .context("Querying contract root history")?;
+
+        let continuation_token = if history.len() > PAGE_SIZE {
+            history
+                .pop()
+                .map(|(block_number, _)| block_number.get().to_string())
+        } else {
+            None
+        };
+
+        let roots = history
+            .into_iter()
+            .map(|(block_number, root)| RootEntry { block_number, root })
+            .collect();
+
+        Ok(Output {
+            roots,
+            continuation_token,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn input(contract_address: ContractAddress) -> Input {
+        Input {
+            contract_address,
+            from_block: None,
+            to_block: None,
+            continuation_token: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn history() {
+        let context = RpcContext::for_tests();
+        let contract = contract_address_bytes!(b"contract 1");
+
+        let expected = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            [BlockNumber::new_or_panic(1), BlockNumber::new_or_panic(2)].map(|block_number| {
+                RootEntry {
+                    block_number,
+                    root: tx.contract_root(block_number, contract).unwrap(),
+                }
+            })
+        };
+
+        let output = get_contract_root_history(context.clone(), input(contract))
+            .await
+            .unwrap();
+        assert_eq!(output.roots, expected);
+        assert_eq!(output.continuation_token, None);
+
+        let output = get_contract_root_history(
+            context,
+            Input {
+                from_block: Some(BlockId::Number(BlockNumber::new_or_panic(2))),
+                ..input(contract)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.roots, expected[1..]);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+
+        let result = get_contract_root_history(
+            context,
+            Input {
+                to_block: Some(BlockId::Hash(block_hash_bytes!(b"missing"))),
+                ..input(contract_address_bytes!(b"contract 1"))
+            },
+        )
+        .await;
+
+        assert_matches::assert_matches!(result, Err(Error::BlockNotFound));
+    }
+
+    #[tokio::test]
+    async fn invalid_continuation_token() {
+        let context = RpcContext::for_tests();
+
+        for token in ["100", "not a number"] {
+            let result = get_contract_root_history(
+                context.clone(),
+                Input {
+                    continuation_token: Some(token.to_owned()),
+                    ..input(contract_address_bytes!(b"contract 1"))
+                },
+            )
+            .await;
+            assert_matches::assert_matches!(result, Err(Error::InvalidContinuationToken));
+        }
+    }
+}
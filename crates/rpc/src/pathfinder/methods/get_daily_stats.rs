@@ -0,0 +1,171 @@
+use anyhow::Context;
+use pathfinder_storage::DailyAnalytics;
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Maximum number of days returned per request.
+const MAX_DAYS: u64 = 366;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    from_timestamp: u64,
+    to_timestamp: Option<u64>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                from_timestamp: value.deserialize_serde("from_timestamp")?,
+                to_timestamp: value.deserialize_optional_serde("to_timestamp")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output(Vec<DailyAnalytics>);
+
+/// Returns the daily aggregates of the UTC days from `from_timestamp` to
+/// `to_timestamp`, oldest first. Days without blocks, or which were not
+/// refreshed yet, are omitted.
+///
+/// Up to [MAX_DAYS] days are returned, starting from `from_timestamp` if
+/// `to_timestamp` is not set.
+pub async fn get_daily_stats(context: RpcContext, input: Input) -> Result<Output, Error> {
+    if !context.config.analytics {
+        return Err(Error::Custom(anyhow::anyhow!("Analytics are not enabled")));
+    }
+
+    let from_day = input.from_timestamp / SECONDS_PER_DAY;
+    let to_day = match input.to_timestamp {
+        Some(to_timestamp) => to_timestamp / SECONDS_PER_DAY,
+        None => from_day.saturating_add(MAX_DAYS - 1),
+    };
+    if to_day < from_day {
+        return Err(Error::Custom(anyhow::anyhow!(
+            "to_timestamp is before from_timestamp"
+        )));
+    }
+    if to_day - from_day >= MAX_DAYS {
+        return Err(Error::Custom(anyhow::anyhow!(
+            "Range exceeds the maximum of {MAX_DAYS} days"
+        )));
+    }
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let days = db
+            .daily_analytics(from_day, to_day)
+            .context("Querying daily analytics")?;
+
+        Ok(Output(days))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter().map(DailyStats))
+    }
+}
+
+struct DailyStats<'a>(&'a DailyAnalytics);
+
+impl SerializeForVersion for DailyStats<'_> {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("day_start", &(self.0.day * SECONDS_PER_DAY))?;
+        serializer.serialize_field("first_block", &dto::BlockNumber(self.0.first_block))?;
+        serializer.serialize_field("last_block", &dto::BlockNumber(self.0.last_block))?;
+        serializer.serialize_field("transactions", &self.0.transactions)?;
+        serializer.serialize_field("active_accounts", &self.0.active_accounts)?;
+        serializer.serialize_field("fees_wei", &dto::Felt(&self.0.fees_wei.0))?;
+        serializer.serialize_field("fees_fri", &dto::Felt(&self.0.fees_fri.0))?;
+        serializer.serialize_field("class_declarations", &self.0.class_declarations)?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::BlockNumber;
+
+    use super::*;
+
+    fn input(from_timestamp: u64, to_timestamp: Option<u64>) -> Input {
+        Input {
+            from_timestamp,
+            to_timestamp,
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshed_days() {
+        let mut context = RpcContext::for_tests();
+        context.config.analytics = true;
+
+        let latest = {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            let latest = db.refresh_analytics(1000).unwrap().unwrap();
+            db.commit().unwrap();
+            latest
+        };
+
+        let Output(days) = get_daily_stats(context, input(0, None)).await.unwrap();
+
+        // All test blocks are on the first day.
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].day, 0);
+        assert_eq!(days[0].first_block, BlockNumber::GENESIS);
+        assert_eq!(days[0].last_block, latest);
+    }
+
+    #[tokio::test]
+    async fn not_refreshed() {
+        let mut context = RpcContext::for_tests();
+        context.config.analytics = true;
+
+        let Output(days) = get_daily_stats(context, input(0, Some(SECONDS_PER_DAY)))
+            .await
+            .unwrap();
+
+        assert!(days.is_empty());
+    }
+
+    #[tokio::test]
+    async fn range_too_large() {
+        let mut context = RpcContext::for_tests();
+        context.config.analytics = true;
+
+        let error = get_daily_stats(context, input(0, Some(MAX_DAYS * SECONDS_PER_DAY)))
+            .await
+            .unwrap_err();
+
+        assert_matches::assert_matches!(error, Error::Custom(_));
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+
+        let error = get_daily_stats(context, input(0, None)).await.unwrap_err();
+
+        assert_matches::assert_matches!(error, Error::Custom(_));
+    }
+}
@@ -0,0 +1,14 @@
+use crate::context::RpcContext;
+use crate::fee_tracker::FeeEstimateStats;
+
+crate::error::generate_rpc_error_subset!(Error);
+
+/// Returns how the fee estimates of transactions submitted through this node
+/// compare to the fees charged in their receipts.
+pub async fn get_fee_estimate_stats(context: RpcContext) -> Result<FeeEstimateStats, Error> {
+    Ok(context
+        .fee_tracker
+        .as_ref()
+        .map(|fee_tracker| fee_tracker.stats())
+        .unwrap_or_default())
+}
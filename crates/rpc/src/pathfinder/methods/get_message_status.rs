@@ -0,0 +1,206 @@
+use anyhow::Context;
+use pathfinder_common::transaction::TransactionVariant;
+use pathfinder_common::{BlockNumber, TransactionHash};
+use pathfinder_serde::H256AsNoLeadingZerosHexStr;
+use primitive_types::H256;
+
+use crate::context::RpcContext;
+use crate::dto::H256Hex;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    message_hash: H256,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                message_hash: value.deserialize::<H256Hex>("message_hash")?.0,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error);
+
+#[serde_with::serde_as]
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    sent_on_l1: bool,
+    #[serde_as(as = "Option<H256AsNoLeadingZerosHexStr>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    l1_transaction_hash: Option<H256>,
+    consumed_on_l2: bool,
+    l2_transactions: Vec<L2Transaction>,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct L2Transaction {
+    /// `None` for transactions of the pending block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_number: Option<BlockNumber>,
+    transaction_hash: TransactionHash,
+}
+
+/// Returns the status of an L1 to L2 message: the L1 transaction which sent
+/// it, and the L1 handler transactions which consumed it on L2, including
+/// those of the pending block.
+///
+/// Messages are known to have been sent only if the L1 sync saw them, i.e.
+/// messages sent while the node was not running are only reported once
+/// consumed.
+pub async fn get_message_status(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let l1_transaction_hash = db
+            .l1_message_transaction(input.message_hash)
+            .context("Querying L1 message")?;
+
+        let mut l2_transactions = db
+            .l1_handler_transactions(input.message_hash)
+            .context("Querying L1 handler transactions")?
+            .into_iter()
+            .map(|(block_number, transaction_hash)| L2Transaction {
+                block_number: Some(block_number),
+                transaction_hash,
+            })
+            .collect::<Vec<_>>();
+
+        let pending = context
+            .pending_data
+            .get(&db)
+            .context("Querying pending data")?;
+        l2_transactions.extend(
+            pending
+                .block
+                .transactions
+                .iter()
+                .filter(|transaction| match &transaction.variant {
+                    TransactionVariant::L1Handler(l1_handler) => {
+                        l1_handler.calculate_message_hash() == input.message_hash
+                    }
+                    _ => false,
+                })
+                .map(|transaction| L2Transaction {
+                    block_number: None,
+                    transaction_hash: transaction.hash,
+                }),
+        );
+
+        Ok(Output {
+            sent_on_l1: l1_transaction_hash.is_some(),
+            l1_transaction_hash,
+            consumed_on_l2: !l2_transactions.is_empty(),
+            l2_transactions,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+    use pathfinder_common::transaction::{L1HandlerTransaction, Transaction};
+    use pathfinder_common::{BlockHeader, L1ToL2MessageLog};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unknown_message() {
+        let context = RpcContext::for_tests();
+
+        let output = get_message_status(
+            context,
+            Input {
+                message_hash: H256::from_low_u64_be(1),
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            output,
+            Output {
+                sent_on_l1: false,
+                l1_transaction_hash: None,
+                consumed_on_l2: false,
+                l2_transactions: vec![],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn sent_and_consumed() {
+        let context = RpcContext::for_tests();
+
+        let l1_handler = L1HandlerTransaction {
+            contract_address: contract_address_bytes!(b"contract"),
+            calldata: vec![call_param_bytes!(b"from"), call_param_bytes!(b"payload")],
+            ..Default::default()
+        };
+        let message_hash = l1_handler.calculate_message_hash();
+        let l1_transaction_hash = H256::from_low_u64_be(1);
+        let transaction = Transaction {
+            hash: transaction_hash_bytes!(b"l1 handler"),
+            variant: TransactionVariant::L1Handler(l1_handler),
+        };
+
+        let block_number = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.insert_l1_message(&L1ToL2MessageLog {
+                message_hash,
+                l1_tx_hash: l1_transaction_hash,
+            })
+            .unwrap();
+
+            let latest = tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap();
+            let header = BlockHeader {
+                number: latest.number + 1,
+                hash: block_hash_bytes!(b"l1 handler block"),
+                parent_hash: latest.hash,
+                ..Default::default()
+            };
+            tx.insert_block_header(&header).unwrap();
+            let receipt = Receipt {
+                transaction_hash: transaction.hash,
+                ..Default::default()
+            };
+            tx.insert_transaction_data(header.number, &[(transaction.clone(), receipt)], None)
+                .unwrap();
+            tx.commit().unwrap();
+            header.number
+        };
+
+        let output = get_message_status(context, Input { message_hash })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            output,
+            Output {
+                sent_on_l1: true,
+                l1_transaction_hash: Some(l1_transaction_hash),
+                consumed_on_l2: true,
+                l2_transactions: vec![L2Transaction {
+                    block_number: Some(block_number),
+                    transaction_hash: transaction.hash,
+                }],
+            }
+        );
+    }
+}
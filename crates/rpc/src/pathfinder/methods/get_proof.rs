@@ -1,9 +1,12 @@
+use std::collections::HashSet;
+
 use anyhow::{anyhow, Context};
+use pathfinder_common::hash::PedersenHash;
 use pathfinder_common::prelude::*;
 use pathfinder_common::trie::TrieNode;
 use pathfinder_common::BlockId;
 use pathfinder_crypto::Felt;
-use pathfinder_merkle_tree::{ContractsStorageTree, StorageCommitmentTree};
+use pathfinder_merkle_tree::{ContractsStorageTree, HistoricalStorageTries, StorageCommitmentTree};
 use serde::Serialize;
 use serde_with::skip_serializing_none;
 
@@ -29,14 +32,56 @@ impl crate::dto::DeserializeForVersion for GetProofInput {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetProofsInput {
+    pub block_id: BlockId,
+    pub contracts: Vec<ContractKeys>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ContractKeys {
+    pub contract_address: ContractAddress,
+    pub keys: Vec<StorageAddress>,
+}
+
+impl crate::dto::DeserializeForVersion for GetProofsInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                contracts: value.deserialize_array("contracts", |value| {
+                    value.deserialize_map(|value| {
+                        Ok(ContractKeys {
+                            contract_address: ContractAddress(
+                                value.deserialize("contract_address")?,
+                            ),
+                            keys: value
+                                .deserialize_optional_array("keys", |value| {
+                                    Ok(StorageAddress(value.deserialize()?))
+                                })?
+                                .unwrap_or_default(),
+                        })
+                    })
+                })?,
+            })
+        })
+    }
+}
+
 // FIXME: allow `generate_rpc_error_subset!` to work with enum struct variants.
 // This may not actually be possible though.
 #[derive(Debug)]
 pub enum GetProofError {
     Internal(anyhow::Error),
     BlockNotFound,
-    ProofLimitExceeded { limit: u32, requested: u32 },
+    ProofLimitExceeded {
+        limit: u32,
+        requested: u32,
+    },
     ProofMissing,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
 }
 
 impl From<anyhow::Error> for GetProofError {
@@ -54,6 +99,11 @@ impl From<GetProofError> for crate::error::ApplicationError {
             GetProofError::BlockNotFound => Self::BlockNotFound,
             GetProofError::Internal(internal) => Self::Internal(internal),
             GetProofError::ProofMissing => Self::ProofMissing,
+            GetProofError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
         }
     }
 }
@@ -68,60 +118,104 @@ struct PathWrapper {
 /// Wrapper around [`Vec<TrieNode>`] as we don't control [TrieNode] in this
 /// crate.
 #[derive(Debug)]
-pub struct ProofNodes(Vec<TrieNode>);
+pub struct ProofNodes(pub(super) Vec<TrieNode>);
 
 impl Serialize for ProofNodes {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        use serde::ser::{SerializeSeq, SerializeStructVariant};
+        use serde::ser::SerializeSeq;
         let mut sequence = serializer.serialize_seq(Some(self.0.len()))?;
 
         for node in &self.0 {
-            struct SerProofNode<'a>(&'a TrieNode);
-
-            impl Serialize for SerProofNode<'_> {
-                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-                where
-                    S: serde::Serializer,
-                {
-                    match self.0 {
-                        TrieNode::Binary { left, right } => {
-                            let mut state = serializer.serialize_struct_variant(
-                                "proof_node",
-                                0,
-                                "binary",
-                                2,
-                            )?;
-                            state.serialize_field("left", &left)?;
-                            state.serialize_field("right", &right)?;
-                            state.end()
-                        }
-                        TrieNode::Edge { child, path } => {
-                            let value = Felt::from_bits(path).unwrap();
-                            let path = PathWrapper {
-                                value,
-                                len: path.len(),
-                            };
-
-                            let mut state =
-                                serializer.serialize_struct_variant("proof_node", 1, "edge", 2)?;
-                            state.serialize_field("path", &path)?;
-                            state.serialize_field("child", &child)?;
-                            state.end()
-                        }
-                    }
-                }
+            sequence.serialize_element(&SerProofNode(node))?;
+        }
+
+        sequence.end()
+    }
+}
+
+/// Trie nodes from one or more proofs keyed by their hash, with the nodes
+/// shared between proofs included only once.
+#[derive(Debug, Default)]
+pub struct ProofNodeMapping {
+    nodes: Vec<(Felt, TrieNode)>,
+    hashes: HashSet<Felt>,
+}
+
+impl ProofNodeMapping {
+    fn insert_proof(&mut self, proof: Vec<TrieNode>) {
+        for node in proof {
+            let hash = node.hash::<PedersenHash>();
+            if self.hashes.insert(hash) {
+                self.nodes.push((hash, node));
             }
+        }
+    }
+}
 
-            sequence.serialize_element(&SerProofNode(node))?;
+impl Serialize for ProofNodeMapping {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{SerializeSeq, SerializeStruct};
+
+        struct Entry<'a>(&'a Felt, &'a TrieNode);
+
+        impl Serialize for Entry<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut state = serializer.serialize_struct("node_hash_to_node", 2)?;
+                state.serialize_field("node_hash", self.0)?;
+                state.serialize_field("node", &SerProofNode(self.1))?;
+                state.end()
+            }
         }
 
+        let mut sequence = serializer.serialize_seq(Some(self.nodes.len()))?;
+        for (hash, node) in &self.nodes {
+            sequence.serialize_element(&Entry(hash, node))?;
+        }
         sequence.end()
     }
 }
 
+struct SerProofNode<'a>(&'a TrieNode);
+
+impl Serialize for SerProofNode<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStructVariant;
+        match self.0 {
+            TrieNode::Binary { left, right } => {
+                let mut state =
+                    serializer.serialize_struct_variant("proof_node", 0, "binary", 2)?;
+                state.serialize_field("left", &left)?;
+                state.serialize_field("right", &right)?;
+                state.end()
+            }
+            TrieNode::Edge { child, path } => {
+                let value = Felt::from_bits(path).unwrap();
+                let path = PathWrapper {
+                    value,
+                    len: path.len(),
+                };
+
+                let mut state = serializer.serialize_struct_variant("proof_node", 1, "edge", 2)?;
+                state.serialize_field("path", &path)?;
+                state.serialize_field("child", &child)?;
+                state.end()
+            }
+        }
+    }
+}
+
 /// Holds the data and proofs for a specific contract.
 #[derive(Debug, Serialize)]
 pub struct ContractData {
@@ -164,6 +258,44 @@ pub struct GetProofOutput {
     contract_data: Option<ContractData>,
 }
 
+/// Holds the data and deduplicated storage proof nodes for a specific
+/// contract.
+#[derive(Debug, Serialize)]
+pub struct MultiProofContractData {
+    class_hash: ClassHash,
+    nonce: ContractNonce,
+    root: ContractRoot,
+    contract_state_hash_version: Felt,
+    /// The nodes of the proofs of all queried storage keys.
+    storage_proof_nodes: ProofNodeMapping,
+}
+
+#[derive(Debug, Serialize)]
+#[skip_serializing_none]
+pub struct ContractProofs {
+    contract_address: ContractAddress,
+    /// Additional contract data if it exists.
+    contract_data: Option<MultiProofContractData>,
+}
+
+#[derive(Debug, Serialize)]
+#[skip_serializing_none]
+pub struct GetProofsOutput {
+    /// See [GetProofOutput#state_commitment].
+    state_commitment: Option<StateCommitment>,
+    /// See [GetProofOutput#class_commitment].
+    class_commitment: Option<ClassCommitment>,
+    /// The nodes of the membership / non-membership proofs of all queried
+    /// contracts.
+    contract_proof_nodes: ProofNodeMapping,
+    /// The queried contracts, in order of request.
+    contracts: Vec<ContractProofs>,
+}
+
+/// The number of blocks whose state diffs are replayed at most to reconstruct
+/// the tries of a pruned block.
+const MAX_REPLAYED_BLOCKS: u64 = 1000;
+
 /// Returns all the necessary data to trustlessly verify storage slots for a
 /// particular contract.
 pub async fn get_proof(
@@ -178,14 +310,7 @@ pub async fn get_proof(
         });
     }
 
-    let block_id = match input.block_id {
-        BlockId::Pending => {
-            return Err(GetProofError::Internal(anyhow!(
-                "'pending' is not currently supported by this method!"
-            )))
-        }
-        other => other.try_into().expect("Only pending cast should fail"),
-    };
+    let block_id = proof_block_id(input.block_id)?;
 
     let storage = context.storage.clone();
     let span = tracing::Span::current();
@@ -198,36 +323,19 @@ pub async fn get_proof(
 
         let tx = db.transaction().context("Creating database transaction")?;
 
-        // Use internal error to indicate that the process of querying for a particular
-        // block failed, which is not the same as being sure that the block is
-        // not in the db.
-        let header = tx
-            .block_header(block_id)
-            .context("Fetching block header")?
-            .ok_or(GetProofError::BlockNotFound)?;
-
-        let state_commitment = match header.state_commitment {
-            StateCommitment::ZERO => None,
-            other => Some(other),
-        };
-        let class_commitment = match header.class_commitment {
-            ClassCommitment::ZERO => None,
-            other => Some(other),
-        };
+        let (tries, state_commitment, class_commitment) = proof_tries(&tx, block_id)?;
 
         // Generate a proof for this contract. If the contract does not exist, this will
         // be a "non membership" proof.
-        let contract_proof =
-            StorageCommitmentTree::get_proof(&tx, header.number, &input.contract_address)
-                .context("Creating contract proof")?
-                .ok_or(GetProofError::ProofMissing)?;
+        let contract_proof = tries
+            .contract_proof(&input.contract_address)
+            .context("Creating contract proof")?
+            .ok_or(GetProofError::ProofMissing)?;
         let contract_proof = ProofNodes(contract_proof);
 
-        let contract_state_hash = tx
-            .contract_state_hash(header.number, input.contract_address)
-            .context("Fetching contract's state hash")?;
-
-        if contract_state_hash.is_none() {
+        let Some((class_hash, nonce, contract_root)) =
+            tries.contract_state(input.contract_address)?
+        else {
             return Ok(GetProofOutput {
                 state_commitment,
                 class_commitment,
@@ -236,38 +344,9 @@ pub async fn get_proof(
             });
         };
 
-        let contract_root = tx
-            .contract_root(header.number, input.contract_address)
-            .context("Querying contract's root")?
-            .unwrap_or_default();
-
-        let class_hash = tx
-            .contract_class_hash(header.number.into(), input.contract_address)
-            .context("Querying contract's class hash")?
-            .unwrap_or_default();
-
-        let nonce = tx
-            .contract_nonce(input.contract_address, header.number.into())
-            .context("Querying contract's nonce")?
-            .unwrap_or_default();
-
         let mut storage_proofs = Vec::new();
         for k in &input.keys {
-            let proof = ContractsStorageTree::get_proof(
-                &tx,
-                input.contract_address,
-                header.number,
-                k.view_bits(),
-            )
-            .context("Get proof from contract state tree")?
-            .ok_or_else(|| {
-                let e = anyhow!(
-                    "Storage proof missing for key {:?}, but should be present",
-                    k
-                );
-                tracing::warn!("{e}");
-                e
-            })?;
+            let proof = tries.storage_proof(input.contract_address, k)?;
             storage_proofs.push(ProofNodes(proof));
         }
 
@@ -291,6 +370,353 @@ pub async fn get_proof(
     jh.await.context("Database read panic or shutting down")?
 }
 
+/// Returns the proofs of [get_proof] for many contracts and storage keys at
+/// once.
+///
+/// Instead of one proof per contract and key, the nodes of all contract
+/// proofs, and the nodes of all storage proofs of a contract, are returned as
+/// a single mapping from node hash to node. Nodes shared between proofs, such
+/// as those close to the root, are thus only included once.
+pub async fn get_proofs(
+    context: RpcContext,
+    input: GetProofsInput,
+) -> Result<GetProofsOutput, GetProofError> {
+    const MAX_PROOFS: usize = 1000;
+    let requested = input.contracts.len()
+        + input
+            .contracts
+            .iter()
+            .map(|contract| contract.keys.len())
+            .sum::<usize>();
+    if requested > MAX_PROOFS {
+        return Err(GetProofError::ProofLimitExceeded {
+            limit: MAX_PROOFS as u32,
+            requested: requested as u32,
+        });
+    }
+
+    let block_id = proof_block_id(input.block_id)?;
+
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        proofs(&tx, block_id, input.contracts)
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+/// Creates the proofs of [get_proofs], without limiting their number.
+pub(super) fn proofs<'tx>(
+    tx: &'tx pathfinder_storage::Transaction<'tx>,
+    block_id: pathfinder_storage::BlockId,
+    contracts: Vec<ContractKeys>,
+) -> Result<GetProofsOutput, GetProofError> {
+    let (tries, state_commitment, class_commitment) = proof_tries(tx, block_id)?;
+
+    let mut contract_proof_nodes = ProofNodeMapping::default();
+    let mut contract_proofs = Vec::with_capacity(contracts.len());
+
+    for ContractKeys {
+        contract_address,
+        keys,
+    } in contracts
+    {
+        let contract_proof = tries
+            .contract_proof(&contract_address)
+            .context("Creating contract proof")?
+            .ok_or(GetProofError::ProofMissing)?;
+        contract_proof_nodes.insert_proof(contract_proof);
+
+        let contract_data = match tries.contract_state(contract_address)? {
+            Some((class_hash, nonce, root)) => {
+                let mut storage_proof_nodes = ProofNodeMapping::default();
+                for k in &keys {
+                    let proof = tries.storage_proof(contract_address, k)?;
+                    storage_proof_nodes.insert_proof(proof);
+                }
+
+                Some(MultiProofContractData {
+                    class_hash,
+                    nonce,
+                    root,
+                    contract_state_hash_version: Felt::ZERO,
+                    storage_proof_nodes,
+                })
+            }
+            None => None,
+        };
+
+        contract_proofs.push(ContractProofs {
+            contract_address,
+            contract_data,
+        });
+    }
+
+    Ok(GetProofsOutput {
+        state_commitment,
+        class_commitment,
+        contract_proof_nodes,
+        contracts: contract_proofs,
+    })
+}
+
+pub(super) fn proof_block_id(
+    block_id: BlockId,
+) -> Result<pathfinder_storage::BlockId, GetProofError> {
+    match block_id {
+        BlockId::Pending => Err(GetProofError::Internal(anyhow!(
+            "'pending' is not currently supported by this method!"
+        ))),
+        other => Ok(other.try_into().expect("Only pending cast should fail")),
+    }
+}
+
+/// Returns the number of the block to create proofs for along with its state
+/// and class commitments, if the block's tries and state are still available.
+pub(super) fn proof_block(
+    tx: &pathfinder_storage::Transaction<'_>,
+    block_id: pathfinder_storage::BlockId,
+) -> Result<
+    (
+        BlockNumber,
+        Option<StateCommitment>,
+        Option<ClassCommitment>,
+    ),
+    GetProofError,
+> {
+    let header = proof_block_header(tx, block_id)?;
+
+    if let Some(earliest_available_block) = tx
+        .earliest_unpruned_trie_block()
+        .context("Querying earliest unpruned trie block")?
+    {
+        if header.number < earliest_available_block {
+            return Err(GetProofError::StatePruned {
+                earliest_available_block,
+            });
+        }
+    }
+
+    // The sampled contracts' states are read from the state tables.
+    if let Some(earliest_available_block) = tx
+        .earliest_available_state_block(header.number.into())
+        .context("Checking state pruning")?
+    {
+        return Err(GetProofError::StatePruned {
+            earliest_available_block,
+        });
+    }
+
+    let (state_commitment, class_commitment) = commitments(&header);
+    Ok((header.number, state_commitment, class_commitment))
+}
+
+/// Returns the tries to create proofs from along with the block's state and
+/// class commitments.
+///
+/// The tries of blocks which have been pruned are reconstructed from the
+/// earliest block whose tries are still available, as long as that is at most
+/// [MAX_REPLAYED_BLOCKS] blocks later and the block's state has not been
+/// pruned.
+fn proof_tries<'tx>(
+    tx: &'tx pathfinder_storage::Transaction<'tx>,
+    block_id: pathfinder_storage::BlockId,
+) -> Result<
+    (
+        ProofTries<'tx>,
+        Option<StateCommitment>,
+        Option<ClassCommitment>,
+    ),
+    GetProofError,
+> {
+    let header = proof_block_header(tx, block_id)?;
+
+    let earliest_unpruned_block = tx
+        .earliest_unpruned_trie_block()
+        .context("Querying earliest unpruned trie block")?;
+
+    let tries = match earliest_unpruned_block {
+        Some(snapshot) if header.number < snapshot => {
+            // Replaying relies on the superseded state updates, which state
+            // pruning deletes.
+            let state_pruned_to = tx
+                .state_pruned_to()
+                .context("Querying state pruning checkpoint")?
+                .unwrap_or_default();
+            let earliest_replayable_block =
+                BlockNumber::new_or_panic(snapshot.get().saturating_sub(MAX_REPLAYED_BLOCKS))
+                    .max(state_pruned_to);
+            if header.number < earliest_replayable_block {
+                return Err(GetProofError::StatePruned {
+                    earliest_available_block: earliest_replayable_block,
+                });
+            }
+
+            let tries = HistoricalStorageTries::replay(tx, header.number, snapshot)
+                .context("Replaying state diffs")?;
+            if tries.storage_commitment() != header.storage_commitment {
+                return Err(anyhow!(
+                    "Replayed storage commitment {} does not match block {}'s {}",
+                    tries.storage_commitment(),
+                    header.number,
+                    header.storage_commitment
+                )
+                .into());
+            }
+
+            ProofTries::Replayed(tx, tries)
+        }
+        _ => ProofTries::Stored(tx, header.number),
+    };
+
+    let (state_commitment, class_commitment) = commitments(&header);
+    Ok((tries, state_commitment, class_commitment))
+}
+
+fn proof_block_header(
+    tx: &pathfinder_storage::Transaction<'_>,
+    block_id: pathfinder_storage::BlockId,
+) -> Result<BlockHeader, GetProofError> {
+    // Use internal error to indicate that the process of querying for a particular
+    // block failed, which is not the same as being sure that the block is
+    // not in the db.
+    tx.block_header(block_id)
+        .context("Fetching block header")?
+        .ok_or(GetProofError::BlockNotFound)
+}
+
+fn commitments(header: &BlockHeader) -> (Option<StateCommitment>, Option<ClassCommitment>) {
+    let state_commitment = match header.state_commitment {
+        StateCommitment::ZERO => None,
+        other => Some(other),
+    };
+    let class_commitment = match header.class_commitment {
+        ClassCommitment::ZERO => None,
+        other => Some(other),
+    };
+
+    (state_commitment, class_commitment)
+}
+
+/// The tries of the block to create proofs for.
+enum ProofTries<'tx> {
+    Stored(&'tx pathfinder_storage::Transaction<'tx>, BlockNumber),
+    /// Reconstructed from a later block, since the block's tries were pruned.
+    Replayed(
+        &'tx pathfinder_storage::Transaction<'tx>,
+        HistoricalStorageTries<'tx>,
+    ),
+}
+
+impl ProofTries<'_> {
+    fn contract_proof(
+        &self,
+        contract_address: &ContractAddress,
+    ) -> anyhow::Result<Option<Vec<TrieNode>>> {
+        match self {
+            Self::Stored(tx, block_number) => {
+                StorageCommitmentTree::get_proof(tx, *block_number, contract_address)
+            }
+            Self::Replayed(_, tries) => tries.contract_proof(contract_address),
+        }
+    }
+
+    fn contract_state(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<(ClassHash, ContractNonce, ContractRoot)>> {
+        match self {
+            Self::Stored(tx, block_number) => contract_state(tx, *block_number, contract_address),
+            Self::Replayed(tx, tries) => match tries.contract_state(contract_address) {
+                Some(state) => Ok(state),
+                // The contract's state did not change since the block.
+                None => contract_state(tx, tries.snapshot(), contract_address),
+            },
+        }
+    }
+
+    fn storage_proof(
+        &self,
+        contract_address: ContractAddress,
+        key: &StorageAddress,
+    ) -> anyhow::Result<Vec<TrieNode>> {
+        let proof = match self {
+            Self::Stored(tx, block_number) => ContractsStorageTree::get_proof(
+                tx,
+                contract_address,
+                *block_number,
+                key.view_bits(),
+            ),
+            Self::Replayed(_, tries) => tries.storage_proof(contract_address, key.view_bits()),
+        };
+
+        proof
+            .context("Get proof from contract state tree")?
+            .ok_or_else(|| {
+                let e = anyhow!(
+                    "Storage proof missing for key {:?}, but should be present",
+                    key
+                );
+                tracing::warn!("{e}");
+                e
+            })
+    }
+}
+
+/// Returns the class hash, nonce and storage root of a contract, or `None` if
+/// the contract is not deployed at the given block.
+pub(super) fn contract_state(
+    tx: &pathfinder_storage::Transaction<'_>,
+    block_number: BlockNumber,
+    contract_address: ContractAddress,
+) -> anyhow::Result<Option<(ClassHash, ContractNonce, ContractRoot)>> {
+    let contract_state_hash = tx
+        .contract_state_hash(block_number, contract_address)
+        .context("Fetching contract's state hash")?;
+
+    if contract_state_hash.is_none() {
+        return Ok(None);
+    };
+
+    let contract_root = tx
+        .contract_root(block_number, contract_address)
+        .context("Querying contract's root")?
+        .unwrap_or_default();
+
+    let preimage = tx
+        .contract_state_preimage(block_number, contract_address)
+        .context("Querying contract's state preimage")?;
+
+    // States stored before the preimage was recorded need separate lookups.
+    let (class_hash, nonce) = match preimage {
+        Some(preimage) => preimage,
+        None => {
+            let class_hash = tx
+                .contract_class_hash(block_number.into(), contract_address)
+                .context("Querying contract's class hash")?
+                .unwrap_or_default();
+
+            let nonce = tx
+                .contract_nonce(contract_address, block_number.into())
+                .context("Querying contract's nonce")?
+                .unwrap_or_default();
+
+            (class_hash, nonce)
+        }
+    };
+
+    Ok(Some((class_hash, nonce, contract_root)))
+}
+
 #[cfg(test)]
 mod tests {
     use pathfinder_common::macro_prelude::*;
@@ -312,6 +738,94 @@ mod tests {
         assert_matches::assert_matches!(err, GetProofError::ProofLimitExceeded { .. });
     }
 
+    #[tokio::test]
+    async fn multi_proof_limit_exceeded() {
+        let context = RpcContext::for_tests();
+        let input = GetProofsInput {
+            block_id: BlockId::Latest,
+            contracts: (0..10)
+                .map(|idx| ContractKeys {
+                    contract_address: ContractAddress::new_or_panic(Felt::from_u64(idx)),
+                    keys: (0..100)
+                        .map(|idx| StorageAddress::new_or_panic(Felt::from_u64(idx)))
+                        .collect(),
+                })
+                .collect(),
+        };
+
+        let err = get_proofs(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(
+            err,
+            GetProofError::ProofLimitExceeded {
+                limit: 1000,
+                requested: 1010
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn multi_proof_deduplicates_nodes() {
+        let context = RpcContext::for_tests();
+        let contract1 = contract_address_bytes!(b"contract 1");
+        let contract2 = contract_address_bytes!(b"contract 2 (sierra)");
+        let missing = contract_address!("0xdeadbeef");
+        let key = storage_address_bytes!(b"storage addr 0");
+
+        let mut single_proofs = Vec::new();
+        for contract_address in [contract1, contract2, missing] {
+            let output = get_proof(
+                context.clone(),
+                GetProofInput {
+                    block_id: BlockId::Latest,
+                    contract_address,
+                    keys: vec![key],
+                },
+            )
+            .await
+            .unwrap();
+            single_proofs.push(output);
+        }
+
+        let output = get_proofs(
+            context,
+            GetProofsInput {
+                block_id: BlockId::Latest,
+                contracts: [contract1, contract2, missing]
+                    .into_iter()
+                    .map(|contract_address| ContractKeys {
+                        contract_address,
+                        keys: vec![key],
+                    })
+                    .collect(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let nodes = &output.contract_proof_nodes.nodes;
+        let total: usize = single_proofs.iter().map(|p| p.contract_proof.0.len()).sum();
+        // All proofs share at least the root node.
+        assert!(nodes.len() < total);
+        for proof in &single_proofs {
+            for node in &proof.contract_proof.0 {
+                assert!(nodes.contains(&(node.hash::<PedersenHash>(), node.clone())));
+            }
+        }
+
+        assert_eq!(output.contracts.len(), 3);
+        assert_eq!(output.contracts[0].contract_address, contract1);
+        let data = output.contracts[0].contract_data.as_ref().unwrap();
+        let single = single_proofs[0].contract_data.as_ref().unwrap();
+        assert_eq!(data.root, single.root);
+        for node in &single.storage_proofs[0].0 {
+            assert!(data
+                .storage_proof_nodes
+                .nodes
+                .contains(&(node.hash::<PedersenHash>(), node.clone())));
+        }
+        assert!(output.contracts[2].contract_data.is_none());
+    }
+
     #[tokio::test]
     async fn proof_pruned() {
         let context =
@@ -353,4 +867,70 @@ mod tests {
         let err = get_proof(context, input).await.unwrap_err();
         assert_matches::assert_matches!(err, GetProofError::ProofMissing);
     }
+
+    #[tokio::test]
+    async fn pruned_state_is_replayed() {
+        let archive = RpcContext::for_tests();
+        let pruned =
+            RpcContext::for_tests_with_trie_pruning(pathfinder_storage::TriePruneMode::Prune {
+                num_blocks_kept: 0,
+            });
+        let key = storage_address_bytes!(b"storage addr 0");
+
+        for block in [BlockNumber::GENESIS, BlockNumber::GENESIS + 1] {
+            for contract_address in [
+                contract_address_bytes!(b"contract 0"),
+                contract_address_bytes!(b"contract 1"),
+                contract_address_bytes!(b"contract 2 (sierra)"),
+                contract_address!("0xdeadbeef"),
+            ] {
+                let input = || GetProofInput {
+                    block_id: BlockId::Number(block),
+                    contract_address,
+                    keys: vec![key],
+                };
+
+                let expected = get_proof(archive.clone(), input()).await.unwrap();
+                let replayed = get_proof(pruned.clone(), input()).await.unwrap();
+                assert_eq!(
+                    serde_json::to_value(replayed).unwrap(),
+                    serde_json::to_value(expected).unwrap(),
+                    "{contract_address} at {block}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn state_pruned() {
+        let context =
+            RpcContext::for_tests_with_trie_pruning(pathfinder_storage::TriePruneMode::Prune {
+                num_blocks_kept: 0,
+            });
+        // Move the earliest unpruned block out of the range of blocks which can be
+        // replayed.
+        let latest = BlockNumber::GENESIS + MAX_REPLAYED_BLOCKS + 2;
+        {
+            let mut conn = context.storage.connection().unwrap();
+            let tx = conn.transaction().unwrap();
+            tx.insert_block_header(&BlockHeader {
+                number: latest,
+                hash: block_hash_bytes!(b"far ahead"),
+                ..Default::default()
+            })
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let input = GetProofInput {
+            block_id: BlockId::Number(BlockNumber::GENESIS + 1),
+            contract_address: contract_address!("0xdeadbeef"),
+            keys: vec![],
+        };
+        let err = get_proof(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(
+            err,
+            GetProofError::StatePruned { earliest_available_block } if earliest_available_block == BlockNumber::GENESIS + 2
+        );
+    }
 }
@@ -0,0 +1,131 @@
+use anyhow::Context;
+use pathfinder_storage::RecordedReorg;
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+
+/// Number of reorgs returned if the request does not set a limit.
+const DEFAULT_LIMIT: usize = 100;
+/// Maximum number of reorgs returned per request.
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    from_id: Option<u64>,
+    limit: Option<usize>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                from_id: value.deserialize_optional_serde("from_id")?,
+                limit: value.deserialize_optional_serde("limit")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output(Vec<RecordedReorg>);
+
+/// Returns the L2 reorgs detected by this node, oldest first, starting from
+/// the one with id `from_id`.
+///
+/// Clients page through the log by continuing from the id after the last
+/// reorg returned.
+pub async fn get_reorgs(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let from_id = input.from_id.unwrap_or_default();
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let reorgs = db
+            .recorded_reorgs(from_id, limit)
+            .context("Querying reorgs")?;
+
+        Ok(Output(reorgs))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter().map(ReorgEntry))
+    }
+}
+
+pub(crate) struct ReorgEntry<'a>(pub &'a RecordedReorg);
+
+impl SerializeForVersion for ReorgEntry<'_> {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("id", &self.0.id)?;
+        serializer.serialize_field("detected_at", &self.0.detected_at)?;
+        serializer.serialize_field("old_head_number", &dto::BlockNumber(self.0.old_head_number))?;
+        serializer.serialize_field("old_head_hash", &dto::BlockHash(&self.0.old_head_hash))?;
+        serializer.serialize_optional(
+            "new_head_number",
+            self.0.new_head_number.map(dto::BlockNumber),
+        )?;
+        serializer.serialize_optional(
+            "new_head_hash",
+            self.0.new_head_hash.as_ref().map(dto::BlockHash),
+        )?;
+        serializer.serialize_field("depth", &self.0.depth)?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::BlockNumber;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn pages_through_reorgs() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            for _ in 0..3 {
+                db.record_reorg(
+                    0,
+                    BlockNumber::new_or_panic(2),
+                    BlockNumber::new_or_panic(2),
+                )
+                .unwrap();
+            }
+            db.commit().unwrap();
+        }
+
+        let input = Input {
+            from_id: None,
+            limit: Some(2),
+        };
+        let Output(first_page) = get_reorgs(context.clone(), input).await.unwrap();
+        assert_eq!(first_page.len(), 2);
+
+        let input = Input {
+            from_id: Some(first_page[1].id + 1),
+            limit: None,
+        };
+        let Output(second_page) = get_reorgs(context, input).await.unwrap();
+        assert_eq!(second_page.len(), 1);
+        assert!(second_page[0].id > first_page[1].id);
+        assert_eq!(second_page[0].depth, 1);
+    }
+}
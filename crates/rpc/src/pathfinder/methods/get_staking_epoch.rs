@@ -0,0 +1,169 @@
+use anyhow::Context;
+use pathfinder_common::{BlockId, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_crypto::Felt;
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+
+use crate::context::RpcContext;
+use crate::felt::RpcFelt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ContractNotFound);
+
+impl From<pathfinder_executor::CallError> for Error {
+    fn from(value: pathfinder_executor::CallError) -> Self {
+        use pathfinder_executor::CallError::*;
+        match value {
+            ContractNotFound => Self::ContractNotFound,
+            InvalidMessageSelector => Self::Custom(anyhow::anyhow!(
+                "Staking contract is missing a view function"
+            )),
+            ContractError(e) => Self::Custom(anyhow::anyhow!("Staking contract error: {e}")),
+            Internal(e) => Self::Internal(e),
+            Custom(e) => Self::Custom(e),
+        }
+    }
+}
+
+#[serde_with::serde_as]
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    contract_address: ContractAddress,
+    current_epoch: u64,
+    #[serde_as(as = "RpcFelt")]
+    total_stake: Felt,
+}
+
+/// Returns the current epoch and total stake of the L2 staking contract at the
+/// given block.
+///
+/// Only these two values are exposed. The validator set, the epoch schedule and
+/// the L1 staking contract are not tracked.
+///
+/// The staking contract address has to be configured, as it is not known for
+/// every network.
+pub async fn get_staking_epoch(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let contract_address = context
+        .config
+        .staking_contract
+        .ok_or_else(|| Error::Custom(anyhow::anyhow!("Staking contract is not configured")))?;
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(Error::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        let call = |function: &str| -> Result<Felt, Error> {
+            let state = ExecutionState::simulation(
+                &db,
+                context.chain_id,
+                header.clone(),
+                pending.clone(),
+                L1BlobDataAvailability::Disabled,
+                context.config.custom_versioned_constants.clone(),
+            );
+            let result = pathfinder_executor::call(
+                state,
+                contract_address,
+                EntryPoint::hashed(function.as_bytes()),
+                vec![],
+            )?;
+
+            match result.as_slice() {
+                [CallResultValue(value)] => Ok(*value),
+                other => Err(Error::Custom(anyhow::anyhow!(
+                    "Unexpected result length {} from {function}",
+                    other.len()
+                ))),
+            }
+        };
+
+        let current_epoch = call("get_current_epoch")?;
+        let (high, low) = current_epoch.as_be_bytes().split_at(24);
+        if high.iter().any(|&byte| byte != 0) {
+            return Err(Error::Custom(anyhow::anyhow!(
+                "Epoch does not fit into u64"
+            )));
+        }
+        let current_epoch = u64::from_be_bytes(low.try_into().expect("8 bytes remain"));
+        let total_stake = call("get_total_stake")?;
+
+        Ok(Output {
+            contract_address,
+            current_epoch,
+            total_stake,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn not_configured() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            block_id: BlockId::Latest,
+        };
+
+        let result = get_staking_epoch(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::Custom(_)));
+    }
+
+    #[tokio::test]
+    async fn contract_not_deployed() {
+        let mut context = RpcContext::for_tests();
+        context.config.staking_contract = Some(contract_address_bytes!(b"staking"));
+        let input = Input {
+            block_id: BlockId::Latest,
+        };
+
+        let result = get_staking_epoch(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::ContractNotFound));
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::Context;
+use pathfinder_common::prelude::*;
+use pathfinder_common::BlockId;
+use pathfinder_crypto::hash::pedersen_hash;
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::StorageCommitmentTree;
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+use super::get_proof::{contract_state, proof_block, proof_block_id, GetProofError, ProofNodes};
+use crate::context::RpcContext;
+
+/// Maximum number of leaves sampled per request.
+const MAX_SAMPLES: u64 = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    block_id: BlockId,
+    seed: Felt,
+    count: u64,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                seed: value.deserialize("seed")?,
+                count: value.deserialize_serde("count")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[skip_serializing_none]
+pub struct Output {
+    /// See [GetProofOutput](super::get_proof::GetProofOutput).
+    state_commitment: Option<StateCommitment>,
+    /// See [GetProofOutput](super::get_proof::GetProofOutput).
+    class_commitment: Option<ClassCommitment>,
+    samples: Vec<Sample>,
+}
+
+#[derive(Debug, Serialize)]
+struct Sample {
+    /// The path used to select the leaf.
+    target: Felt,
+    contract_address: ContractAddress,
+    /// Together with the contract state hash version these hash to the leaf's
+    /// value, the contract state hash.
+    class_hash: ClassHash,
+    nonce: ContractNonce,
+    root: ContractRoot,
+    contract_state_hash_version: Felt,
+    /// Membership proof of the contract state hash.
+    contract_proof: ProofNodes,
+}
+
+/// Samples `count` leaves of the global state trie at a block, returning each
+/// leaf's contract state together with its membership proof.
+///
+/// The `i`th leaf is selected by walking the trie from the root along the
+/// target path `pedersen(seed, i)`: binary nodes are descended in the
+/// direction of the target's bit at that height, edge nodes are always
+/// followed. Given the seed, anyone can therefore check from the proofs alone
+/// that the samples were not chosen by the node.
+pub async fn get_state_sample(context: RpcContext, input: Input) -> Result<Output, GetProofError> {
+    if input.count > MAX_SAMPLES {
+        return Err(GetProofError::ProofLimitExceeded {
+            limit: MAX_SAMPLES as u32,
+            requested: input.count.try_into().unwrap_or(u32::MAX),
+        });
+    }
+
+    let block_id = proof_block_id(input.block_id)?;
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (block_number, state_commitment, class_commitment) = proof_block(&tx, block_id)?;
+
+        let mut samples = Vec::with_capacity(input.count as usize);
+        for i in 0..input.count {
+            let target = pedersen_hash(input.seed, Felt::from_u64(i));
+
+            let contract_address =
+                StorageCommitmentTree::sample_contract(&tx, block_number, target.view_bits())
+                    .context("Sampling global state trie")?
+                    .ok_or(GetProofError::ProofMissing)?;
+
+            let contract_proof =
+                StorageCommitmentTree::get_proof(&tx, block_number, &contract_address)
+                    .context("Creating contract proof")?
+                    .ok_or(GetProofError::ProofMissing)?;
+
+            let (class_hash, nonce, root) = contract_state(&tx, block_number, contract_address)?
+                .with_context(|| {
+                    format!("Contract state missing for sampled contract {contract_address}")
+                })?;
+
+            samples.push(Sample {
+                target,
+                contract_address,
+                class_hash,
+                nonce,
+                root,
+                contract_state_hash_version: Felt::ZERO,
+                contract_proof: ProofNodes(contract_proof),
+            });
+        }
+
+        Ok(Output {
+            state_commitment,
+            class_commitment,
+            samples,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::felt;
+    use pathfinder_common::hash::PedersenHash;
+    use pathfinder_common::trie::TrieNode;
+
+    use super::*;
+
+    fn input(seed: Felt, count: u64) -> Input {
+        Input {
+            block_id: BlockId::Latest,
+            seed,
+            count,
+        }
+    }
+
+    #[tokio::test]
+    async fn samples_are_deterministic_and_proven() {
+        let context = RpcContext::for_tests();
+
+        let output = get_state_sample(context.clone(), input(felt!("0x1234"), 10))
+            .await
+            .unwrap();
+        let again = get_state_sample(context, input(felt!("0x1234"), 10))
+            .await
+            .unwrap();
+
+        assert_eq!(output.samples.len(), 10);
+        for (sample, again) in output.samples.iter().zip(&again.samples) {
+            assert_eq!(sample.contract_address, again.contract_address);
+
+            // The proof leads to the sampled contract's state hash.
+            let leaf = match sample.contract_proof.0.last().unwrap() {
+                TrieNode::Edge { child, .. } => *child,
+                TrieNode::Binary { left, right } => {
+                    if sample.contract_address.view_bits()[250] {
+                        *right
+                    } else {
+                        *left
+                    }
+                }
+            };
+            let state_hash = pathfinder_merkle_tree::contract_state::calculate_contract_state_hash(
+                sample.class_hash,
+                sample.root,
+                sample.nonce,
+            );
+            assert_eq!(leaf, state_hash.0);
+
+            // Each binary node was descended in the direction of the target.
+            let target = sample.target.view_bits();
+            let mut height = 0;
+            for node in &sample.contract_proof.0 {
+                match node {
+                    TrieNode::Binary { .. } => {
+                        assert_eq!(target[height], sample.contract_address.view_bits()[height]);
+                        height += 1;
+                    }
+                    TrieNode::Edge { path, .. } => height += path.len(),
+                }
+            }
+            assert_eq!(height, 251);
+        }
+
+        // The proofs share the state commitment tree's root.
+        let root = output.samples[0].contract_proof.0[0].hash::<PedersenHash>();
+        assert!(output
+            .samples
+            .iter()
+            .all(|sample| sample.contract_proof.0[0].hash::<PedersenHash>() == root));
+    }
+
+    #[tokio::test]
+    async fn limit_exceeded() {
+        let context = RpcContext::for_tests();
+
+        let err = get_state_sample(context, input(felt!("0x1"), MAX_SAMPLES + 1))
+            .await
+            .unwrap_err();
+
+        assert_matches::assert_matches!(err, GetProofError::ProofLimitExceeded { .. });
+    }
+
+    #[tokio::test]
+    async fn state_pruned() {
+        let context = RpcContext::for_tests();
+        let mut conn = context.storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        tx.prune_state_updates(BlockNumber::GENESIS + 2, 10)
+            .unwrap();
+        tx.commit().unwrap();
+
+        let input = Input {
+            block_id: BlockId::Number(BlockNumber::GENESIS + 1),
+            seed: felt!("0x1"),
+            count: 1,
+        };
+        let err = get_state_sample(context, input).await.unwrap_err();
+
+        assert_matches::assert_matches!(
+            err,
+            GetProofError::StatePruned { earliest_available_block }
+                if earliest_available_block == BlockNumber::GENESIS + 2
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use pathfinder_common::prelude::*;
+use pathfinder_common::BlockId;
+use pathfinder_executor::{ExecutionState, TransactionExecutionError};
+
+use super::get_proof::{proofs, ContractKeys, GetProofError, GetProofsOutput};
+use crate::compose_executor_transaction;
+use crate::context::RpcContext;
+use crate::version_gate::UnsupportedVersion;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    Block(BlockId),
+    Transaction(TransactionHash),
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            if value.contains_key("transaction_hash") {
+                return Ok(Self::Transaction(TransactionHash(
+                    value.deserialize("transaction_hash")?,
+                )));
+            }
+
+            Ok(Self::Block(value.deserialize("block_id")?))
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    TxnHashNotFound,
+    TransactionExecutionError {
+        transaction_index: usize,
+        error: String,
+    },
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+    UnsupportedStarknetVersion(UnsupportedVersion),
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<UnsupportedVersion> for Error {
+    fn from(e: UnsupportedVersion) -> Self {
+        Self::UnsupportedStarknetVersion(e)
+    }
+}
+
+impl From<GetProofError> for Error {
+    fn from(e: GetProofError) -> Self {
+        match e {
+            GetProofError::Internal(e) => Self::Internal(e),
+            GetProofError::BlockNotFound => Self::BlockNotFound,
+            GetProofError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+            GetProofError::ProofMissing | GetProofError::ProofLimitExceeded { .. } => {
+                Self::Internal(anyhow::anyhow!("Unexpected proof error: {e:?}"))
+            }
+        }
+    }
+}
+
+impl From<TransactionExecutionError> for Error {
+    fn from(e: TransactionExecutionError) -> Self {
+        match e {
+            TransactionExecutionError::ExecutionError {
+                transaction_index,
+                error,
+            } => Self::TransactionExecutionError {
+                transaction_index,
+                error,
+            },
+            TransactionExecutionError::Internal(e) => Self::Internal(e),
+            TransactionExecutionError::Custom(e) => Self::Custom(e),
+        }
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(e) => Self::Internal(e),
+            Error::Custom(e) => Self::Custom(e),
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::TxnHashNotFound => Self::TxnHashNotFound,
+            Error::TransactionExecutionError {
+                transaction_index,
+                error,
+            } => Self::TransactionExecutionError {
+                transaction_index,
+                error,
+            },
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+            Error::UnsupportedStarknetVersion(e) => Self::UnsupportedStarknetVersion(e),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct Output {
+    block_number: BlockNumber,
+    /// The transactions to execute, in order.
+    transactions: Vec<TransactionHash>,
+    /// Proofs of the contracts read against the parent block's state.
+    #[serde(flatten)]
+    proofs: GetProofsOutput,
+    storage: Vec<StorageEntry>,
+    classes: Vec<Class>,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+struct StorageEntry {
+    contract_address: ContractAddress,
+    key: StorageAddress,
+    value: StorageValue,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+struct Class {
+    class_hash: ClassHash,
+    /// Only set for Sierra classes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compiled_class_hash: Option<CasmHash>,
+}
+
+/// Returns the state witness needed to execute a block, or a transaction,
+/// without the database: every contract, storage slot and class read while
+/// executing it, along with proofs against the state of its parent block.
+///
+/// A transaction is executed on the state left by the transactions before it
+/// in its block, so its witness covers those transactions as well, and they
+/// are part of the transactions to execute.
+///
+/// Class definitions are not included, they can be fetched with
+/// `starknet_getClass`.
+pub async fn get_state_witness(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (block_id, last_transaction): (pathfinder_storage::BlockId, _) = match input {
+            Input::Block(BlockId::Pending) => {
+                return Err(Error::Custom(anyhow::anyhow!(
+                    "The pending block has no witness"
+                )))
+            }
+            Input::Block(other) => (
+                other.try_into().expect("Only pending cast should fail"),
+                None,
+            ),
+            Input::Transaction(transaction_hash) => {
+                let block_hash = db
+                    .transaction_block_hash(transaction_hash)
+                    .context("Querying transaction's block")?
+                    .ok_or(Error::TxnHashNotFound)?;
+                (block_hash.into(), Some(transaction_hash))
+            }
+        };
+
+        let header = db
+            .block_header(block_id)
+            .context("Querying block header")?
+            .ok_or(Error::BlockNotFound)?;
+        let parent = header.number.parent().ok_or_else(|| {
+            Error::Custom(anyhow::anyhow!("The genesis block has no parent state"))
+        })?;
+        crate::version_gate::check_block_execution(header.starknet_version)?;
+
+        let mut transactions = db
+            .transactions_for_block(header.number.into())
+            .context("Querying block transactions")?
+            .context("Block transactions missing")?;
+        if let Some(last_transaction) = last_transaction {
+            let count = transactions
+                .iter()
+                .position(|transaction| transaction.hash == last_transaction)
+                .context("Transaction missing from its block")?
+                + 1;
+            transactions.truncate(count);
+        }
+
+        let executor_transactions = transactions
+            .iter()
+            .map(|transaction| compose_executor_transaction(transaction, &db))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let block_number = header.number;
+        let state = ExecutionState::trace(
+            &db,
+            context.chain_id,
+            header,
+            None,
+            context.config.custom_versioned_constants,
+        );
+        let accesses = pathfinder_executor::state_accesses(state, executor_transactions)?;
+
+        let mut contracts = accesses
+            .contracts
+            .iter()
+            .map(|contract_address| (*contract_address, Vec::new()))
+            .collect::<BTreeMap<_, _>>();
+        for (contract_address, key) in &accesses.storage {
+            contracts.entry(*contract_address).or_default().push(*key);
+        }
+        let proofs = proofs(
+            &db,
+            parent.into(),
+            contracts
+                .into_iter()
+                .map(|(contract_address, keys)| ContractKeys {
+                    contract_address,
+                    keys,
+                })
+                .collect(),
+        )?;
+
+        let storage = accesses
+            .storage
+            .into_iter()
+            .map(|(contract_address, key)| {
+                let value = db
+                    .storage_value(parent.into(), contract_address, key)
+                    .context("Querying storage value")?
+                    .unwrap_or_default();
+                Ok(StorageEntry {
+                    contract_address,
+                    key,
+                    value,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let classes = accesses
+            .classes
+            .into_iter()
+            .map(|class_hash| {
+                let compiled_class_hash = db
+                    .casm_hash_at(parent.into(), class_hash)
+                    .context("Querying compiled class hash")?;
+                Ok(Class {
+                    class_hash,
+                    compiled_class_hash,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Output {
+            block_number,
+            transactions: transactions
+                .into_iter()
+                .map(|transaction| transaction.hash)
+                .collect(),
+            proofs,
+            storage,
+            classes,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::DeserializeForVersion;
+    use crate::RpcVersion;
+
+    #[test]
+    fn input() {
+        let input = Input::deserialize(crate::dto::Value::new(
+            json!({"transaction_hash": "0x1"}),
+            RpcVersion::V07,
+        ))
+        .unwrap();
+        assert_eq!(input, Input::Transaction(transaction_hash!("0x1")));
+
+        let input = Input::deserialize(crate::dto::Value::new(
+            json!({"block_id": {"block_number": 1}}),
+            RpcVersion::V07,
+        ))
+        .unwrap();
+        assert_eq!(
+            input,
+            Input::Block(BlockId::Number(BlockNumber::new_or_panic(1)))
+        );
+    }
+
+    #[tokio::test]
+    async fn genesis_has_no_witness() {
+        let context = RpcContext::for_tests();
+        let error = get_state_witness(context, Input::Block(BlockId::Number(BlockNumber::GENESIS)))
+            .await
+            .unwrap_err();
+        assert_matches::assert_matches!(error, Error::Custom(_));
+    }
+}
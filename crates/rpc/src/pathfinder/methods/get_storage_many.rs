@@ -0,0 +1,310 @@
+use std::collections::HashSet;
+
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, StorageAddress, StorageValue};
+
+use crate::context::RpcContext;
+
+/// Maximum number of storage keys per request, over all contracts.
+const MAX_KEYS: usize = 10_000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    contracts: Vec<ContractKeys>,
+    block_id: BlockId,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ContractKeys {
+    contract_address: ContractAddress,
+    keys: Vec<StorageAddress>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contracts: value.deserialize_array("contracts", |value| {
+                    value.deserialize_map(|value| {
+                        Ok(ContractKeys {
+                            contract_address: ContractAddress(
+                                value.deserialize("contract_address")?,
+                            ),
+                            keys: value.deserialize_array("keys", |value| {
+                                Ok(StorageAddress(value.deserialize()?))
+                            })?,
+                        })
+                    })
+                })?,
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    PageSizeTooBig,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<Error> for crate::error::ApplicationError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Internal(internal) => Self::Internal(internal),
+            Error::Custom(internal) => Self::Custom(internal),
+            Error::BlockNotFound => Self::BlockNotFound,
+            Error::PageSizeTooBig => Self::PageSizeTooBig,
+            Error::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
+
+/// The storage values of each contract, in the order of the request. `None`
+/// for contracts which are not deployed at the block.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output(Vec<Option<Vec<StorageValue>>>);
+
+/// Batch variant of `starknet_getStorageAt`, which reads the storage values of
+/// any number of contracts and keys within a single database transaction.
+///
+/// Unlike `starknet_getStorageAt`, contracts which are not deployed do not
+/// fail the request but have `null` instead of their values.
+pub async fn get_storage_many(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let requested = input
+        .contracts
+        .iter()
+        .map(|contract| contract.keys.len())
+        .sum::<usize>();
+    if requested > MAX_KEYS {
+        return Err(Error::PageSizeTooBig);
+    }
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let pending = match input.block_id {
+            BlockId::Pending => Some(
+                context
+                    .pending_data
+                    .get(&tx)
+                    .context("Querying pending data")?
+                    .state_update,
+            ),
+            _ => None,
+        };
+
+        let block_id = match input.block_id {
+            BlockId::Pending => pathfinder_storage::BlockId::Latest,
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+        let (block_number, _) = tx
+            .block_id(block_id)
+            .context("Querying block id")?
+            .ok_or(Error::BlockNotFound)?;
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_number.into())
+            .context("Checking state pruning")?
+        {
+            return Err(Error::StatePruned {
+                earliest_available_block,
+            });
+        }
+
+        let slots = input
+            .contracts
+            .iter()
+            .flat_map(|contract| {
+                contract
+                    .keys
+                    .iter()
+                    .map(|key| (contract.contract_address, *key))
+            })
+            .collect::<Vec<_>>();
+        let mut values = tx
+            .storage_values(block_number, &slots)
+            .context("Querying storage values")?;
+
+        if let Some(pending) = &pending {
+            for (value, (contract_address, key)) in values.iter_mut().zip(&slots) {
+                if let Some(pending) = pending.storage_value(*contract_address, *key) {
+                    *value = Some(pending);
+                }
+            }
+        }
+
+        // Unset slots read as zero, unless the contract is not deployed.
+        let unset = slots
+            .iter()
+            .zip(&values)
+            .filter(|(_, value)| value.is_none())
+            .map(|((contract_address, _), _)| *contract_address)
+            .filter(|contract_address| {
+                pending
+                    .as_ref()
+                    .and_then(|pending| pending.contract_class(*contract_address))
+                    .is_none()
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let class_hashes = tx
+            .contract_class_hashes(block_number, &unset)
+            .context("Checking contracts exist")?;
+        let not_deployed = unset
+            .into_iter()
+            .zip(class_hashes)
+            .filter_map(|(contract_address, class_hash)| {
+                class_hash.is_none().then_some(contract_address)
+            })
+            .collect::<HashSet<_>>();
+
+        let mut values = values.into_iter();
+        let output = input
+            .contracts
+            .iter()
+            .map(|contract| {
+                let contract_values = values
+                    .by_ref()
+                    .take(contract.keys.len())
+                    .map(Option::unwrap_or_default)
+                    .collect();
+                (!not_deployed.contains(&contract.contract_address)).then_some(contract_values)
+            })
+            .collect();
+
+        Ok(Output(output))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn at_block() {
+        let context = RpcContext::for_tests();
+        let key = storage_address_bytes!(b"storage addr 0");
+
+        let input = Input {
+            contracts: vec![
+                ContractKeys {
+                    contract_address: contract_address_bytes!(b"contract 1"),
+                    keys: vec![key, storage_address_bytes!(b"unset")],
+                },
+                ContractKeys {
+                    contract_address: contract_address_bytes!(b"contract 0"),
+                    keys: vec![key],
+                },
+                ContractKeys {
+                    contract_address: contract_address_bytes!(b"invalid"),
+                    keys: vec![key],
+                },
+            ],
+            block_id: BlockNumber::new_or_panic(1).into(),
+        };
+        let result = get_storage_many(context, input).await.unwrap();
+        assert_eq!(
+            result,
+            Output(vec![
+                Some(vec![
+                    storage_value_bytes!(b"storage value 1"),
+                    StorageValue::ZERO
+                ]),
+                Some(vec![StorageValue::ZERO]),
+                None,
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+
+        let input = Input {
+            contracts: vec![
+                ContractKeys {
+                    contract_address: contract_address_bytes!(b"pending contract 1 address"),
+                    keys: vec![storage_address_bytes!(b"pending storage key 0")],
+                },
+                ContractKeys {
+                    contract_address: contract_address_bytes!(b"pending contract 0 address"),
+                    keys: vec![storage_address_bytes!(b"unset")],
+                },
+            ],
+            block_id: BlockId::Pending,
+        };
+        let result = get_storage_many(context, input).await.unwrap();
+        assert_eq!(
+            result,
+            Output(vec![
+                Some(vec![storage_value_bytes!(b"pending storage value 0")]),
+                Some(vec![StorageValue::ZERO]),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+
+        let input = Input {
+            contracts: vec![],
+            block_id: BlockId::Hash(block_hash_bytes!(b"invalid")),
+        };
+        let result = get_storage_many(context, input).await;
+        assert_matches!(result, Err(Error::BlockNotFound));
+    }
+
+    #[tokio::test]
+    async fn state_pruned() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.prune_state_updates(BlockNumber::GENESIS + 2, 10)
+                .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let input = Input {
+            contracts: vec![ContractKeys {
+                contract_address: contract_address_bytes!(b"contract 1"),
+                keys: vec![storage_address_bytes!(b"storage addr 0")],
+            }],
+            block_id: BlockNumber::new_or_panic(1).into(),
+        };
+        let result = get_storage_many(context, input).await;
+        assert_matches!(
+            result,
+            Err(Error::StatePruned { earliest_available_block }) if earliest_available_block == BlockNumber::GENESIS + 2
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::num::NonZeroUsize;
+
+use anyhow::Context;
+use pathfinder_common::event::Event;
+use pathfinder_common::TransactionHash;
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    transaction_hash: TransactionHash,
+    continuation_token: Option<String>,
+    chunk_size: NonZeroUsize,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: value.deserialize("transaction_hash").map(TransactionHash)?,
+                continuation_token: value.deserialize_optional_serde("continuation_token")?,
+                chunk_size: value.deserialize_serde("chunk_size")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(
+    Error: TxnHashNotFound,
+    PageSizeTooBig,
+    InvalidContinuationToken
+);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output {
+    events: Vec<Event>,
+    continuation_token: Option<String>,
+}
+
+/// Returns a page of the events emitted by a transaction, so that the events
+/// of transactions which emit thousands of them can be fetched in parts
+/// instead of as a single multi-MB receipt.
+///
+/// The continuation token is the index of the first event of the next page.
+pub async fn get_transaction_events(context: RpcContext, input: Input) -> Result<Output, Error> {
+    if input.chunk_size.get() > pathfinder_storage::EVENT_PAGE_SIZE_LIMIT {
+        return Err(Error::PageSizeTooBig);
+    }
+
+    let offset = match &input.continuation_token {
+        Some(token) => token
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidContinuationToken)?,
+        None => 0,
+    };
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let pending = context
+            .pending_data
+            .get(&db)
+            .context("Querying pending data")?;
+
+        let events = match pending
+            .block
+            .transactions
+            .iter()
+            .zip(pending.block.transaction_receipts.iter())
+            .find(|(tx, _)| tx.hash == input.transaction_hash)
+        {
+            Some((_, (_, events))) => events.clone(),
+            None => {
+                let (_, _, events, _) = db
+                    .transaction_with_receipt(input.transaction_hash)
+                    .context("Querying transaction events")?
+                    .ok_or(Error::TxnHashNotFound)?;
+                events
+            }
+        };
+
+        if offset > events.len() {
+            return Err(Error::InvalidContinuationToken);
+        }
+
+        let end = offset
+            .saturating_add(input.chunk_size.get())
+            .min(events.len());
+        let continuation_token = (end < events.len()).then(|| end.to_string());
+
+        Ok(Output {
+            events: events[offset..end].to_vec(),
+            continuation_token,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+
+        serializer.serialize_iter(
+            "events",
+            self.events.len(),
+            &mut self.events.iter().map(|e| dto::Event {
+                address: &e.from_address,
+                keys: &e.keys,
+                data: &e.data,
+            }),
+        )?;
+        serializer.serialize_optional("continuation_token", self.continuation_token.as_ref())?;
+
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn input(transaction_hash: TransactionHash, continuation_token: Option<&str>) -> Input {
+        Input {
+            transaction_hash,
+            continuation_token: continuation_token.map(ToOwned::to_owned),
+            chunk_size: NonZeroUsize::new(2).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn paginated() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let tx_hash = transaction_hash_bytes!(b"pending tx hash 0");
+
+        let first = get_transaction_events(context.clone(), input(tx_hash, None))
+            .await
+            .unwrap();
+        assert_eq!(first.events.len(), 2);
+        assert_eq!(first.continuation_token.as_deref(), Some("2"));
+
+        let second =
+            get_transaction_events(context, input(tx_hash, first.continuation_token.as_deref()))
+                .await
+                .unwrap();
+        assert_eq!(second.events.len(), 1);
+        assert_eq!(second.continuation_token, None);
+        assert_eq!(
+            second.events[0].from_address,
+            contract_address!("0xabcaaaaaaa")
+        );
+    }
+
+    #[tokio::test]
+    async fn from_database() {
+        let context = RpcContext::for_tests();
+        let tx_hash = transaction_hash_bytes!(b"txn 0");
+
+        let output = get_transaction_events(context, input(tx_hash, None))
+            .await
+            .unwrap();
+
+        assert_eq!(output.events.len(), 1);
+        assert_eq!(output.continuation_token, None);
+    }
+
+    #[tokio::test]
+    async fn invalid_continuation_token() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let tx_hash = transaction_hash_bytes!(b"pending tx hash 0");
+
+        for token in ["4", "not a number"] {
+            let result = get_transaction_events(context.clone(), input(tx_hash, Some(token))).await;
+            assert_matches::assert_matches!(result, Err(Error::InvalidContinuationToken));
+        }
+    }
+
+    #[tokio::test]
+    async fn page_size_too_big() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            chunk_size: NonZeroUsize::new(pathfinder_storage::EVENT_PAGE_SIZE_LIMIT + 1).unwrap(),
+            ..input(transaction_hash_bytes!(b"txn 0"), None)
+        };
+
+        let result = get_transaction_events(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::PageSizeTooBig));
+    }
+
+    #[tokio::test]
+    async fn txn_hash_not_found() {
+        let context = RpcContext::for_tests();
+
+        let result =
+            get_transaction_events(context, input(transaction_hash_bytes!(b"missing"), None)).await;
+
+        assert_matches::assert_matches!(result, Err(Error::TxnHashNotFound));
+    }
+}
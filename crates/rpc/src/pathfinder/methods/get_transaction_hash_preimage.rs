@@ -0,0 +1,135 @@
+use anyhow::Context;
+use pathfinder_common::transaction::HashFunction;
+use pathfinder_common::TransactionHash;
+use pathfinder_crypto::Felt;
+
+use crate::context::RpcContext;
+use crate::felt::RpcFelt;
+use crate::v02::types::request::BroadcastedTransaction;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    transaction: BroadcastedTransaction,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction: value.deserialize("transaction")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error);
+
+#[serde_with::serde_as]
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    transaction_hash: TransactionHash,
+    #[serde_as(as = "RpcFelt")]
+    chain_id: Felt,
+    hash_function: &'static str,
+    #[serde_as(as = "Vec<RpcFelt>")]
+    preimage: Vec<Felt>,
+}
+
+/// Computes the hash of a broadcasted transaction for the chain this node is
+/// following, along with the elements that were hashed to obtain it.
+///
+/// Intended for wallet and signer vendors who want to check their hashing
+/// implementation against the node.
+pub async fn get_transaction_hash_preimage(
+    context: RpcContext,
+    input: Input,
+) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    let output = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let query_only = input.transaction.version().has_query_version();
+        // Declare transactions require the class hash to be computed, which can take
+        // a while for large classes.
+        let transaction = input.transaction.into_common(context.chain_id);
+        let preimage = transaction
+            .variant
+            .calculate_hash_preimage(context.chain_id, query_only);
+
+        Output {
+            transaction_hash: transaction.hash,
+            chain_id: context.chain_id.0,
+            hash_function: match preimage.hash_function {
+                HashFunction::Pedersen => "PEDERSEN",
+                HashFunction::Poseidon => "POSEIDON",
+            },
+            preimage: preimage.elements,
+        }
+    })
+    .await
+    .context("Computing transaction hash")?;
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::TransactionVersion;
+    use pathfinder_crypto::hash::HashChain;
+
+    use super::*;
+    use crate::v02::types::request::{
+        BroadcastedInvokeTransaction,
+        BroadcastedInvokeTransactionV1,
+    };
+
+    fn invoke(version: TransactionVersion) -> Input {
+        Input {
+            transaction: BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+                BroadcastedInvokeTransactionV1 {
+                    version,
+                    max_fee: fee!("0x630a0aff77"),
+                    signature: vec![],
+                    nonce: transaction_nonce!("0x2"),
+                    sender_address: contract_address!("0x123"),
+                    calldata: vec![call_param!("0x1"), call_param!("0x2")],
+                },
+            )),
+        }
+    }
+
+    #[tokio::test]
+    async fn preimage_hashes_to_transaction_hash() {
+        let context = RpcContext::for_tests();
+        let chain_id = context.chain_id;
+
+        let output = get_transaction_hash_preimage(context, invoke(TransactionVersion::ONE))
+            .await
+            .unwrap();
+
+        assert_eq!(output.hash_function, "PEDERSEN");
+        assert_eq!(output.chain_id, chain_id.0);
+        assert_eq!(output.preimage[1], TransactionVersion::ONE.0);
+        assert_eq!(output.preimage[6], chain_id.0);
+
+        let hash = output
+            .preimage
+            .iter()
+            .fold(HashChain::default(), |hasher, &element| {
+                hasher.chain_update(element)
+            })
+            .finalize();
+        assert_eq!(TransactionHash(hash), output.transaction_hash);
+    }
+
+    #[tokio::test]
+    async fn query_version() {
+        let version = TransactionVersion::ONE.with_query_version();
+        let output = get_transaction_hash_preimage(RpcContext::for_tests(), invoke(version))
+            .await
+            .unwrap();
+
+        assert_eq!(output.preimage[1], version.0);
+    }
+}
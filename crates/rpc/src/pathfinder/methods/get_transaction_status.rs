@@ -3,6 +3,7 @@ use pathfinder_common::TransactionHash;
 use starknet_gateway_types::reply::PendingBlock;
 
 use crate::context::RpcContext;
+use crate::dropped_transactions::DroppedStatus;
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 pub struct GetGatewayTransactionInput {
@@ -73,6 +74,16 @@ pub async fn get_transaction_status(
         return Ok(db_status);
     }
 
+    let dropped = context
+        .notifications
+        .dropped_transactions
+        .get(&input.transaction_hash);
+    match dropped {
+        Some(DroppedStatus::Dropped) => return Ok(TransactionStatus::Dropped),
+        Some(DroppedStatus::Replaced(_)) => return Ok(TransactionStatus::Replaced),
+        None => {}
+    }
+
     // Check gateway for rejected transactions.
     use starknet_gateway_client::GatewayApi;
     context
@@ -116,6 +127,10 @@ pub enum TransactionStatus {
     Reverted,
     #[serde(rename = "ABORTED")]
     Aborted,
+    #[serde(rename = "DROPPED")]
+    Dropped,
+    #[serde(rename = "REPLACED")]
+    Replaced,
 }
 
 impl From<starknet_gateway_types::reply::Status> for TransactionStatus {
@@ -179,6 +194,31 @@ mod tests {
         assert_eq!(status, TransactionStatus::AcceptedOnL2);
     }
 
+    #[tokio::test]
+    async fn dropped() {
+        let context = RpcContext::for_tests();
+        let tx_hash = transaction_hash_bytes!(b"dropped tx");
+        context
+            .notifications
+            .dropped_transactions
+            .record_pending_update(
+                &PendingBlock {
+                    transactions: vec![pathfinder_common::transaction::Transaction {
+                        hash: tx_hash,
+                        variant: Default::default(),
+                    }],
+                    ..Default::default()
+                },
+                &PendingBlock::default(),
+            );
+        let input = GetGatewayTransactionInput {
+            transaction_hash: tx_hash,
+        };
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(status, TransactionStatus::Dropped);
+    }
+
     #[tokio::test]
     async fn rejected() {
         let input = GetGatewayTransactionInput {
@@ -0,0 +1,252 @@
+use std::num::NonZeroUsize;
+
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, TransactionHash};
+
+use crate::context::RpcContext;
+
+/// Maximum number of transactions returned per request.
+const MAX_CHUNK_SIZE: usize = 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    contract_address: ContractAddress,
+    from_block: Option<BlockId>,
+    to_block: Option<BlockId>,
+    continuation_token: Option<String>,
+    chunk_size: NonZeroUsize,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contract_address: ContractAddress(value.deserialize("contract_address")?),
+                from_block: value.deserialize_optional("from_block")?,
+                to_block: value.deserialize_optional("to_block")?,
+                continuation_token: value.deserialize_optional_serde("continuation_token")?,
+                chunk_size: value.deserialize_serde("chunk_size")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(
+    Error: BlockNotFound,
+    PageSizeTooBig,
+    InvalidContinuationToken
+);
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    transactions: Vec<TransactionEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    continuation_token: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct TransactionEntry {
+    block_number: BlockNumber,
+    transaction_index: usize,
+    transaction_hash: TransactionHash,
+}
+
+/// Returns the transactions sent by an account in a block range, oldest first,
+/// using the index of transactions by sender instead of scanning the blocks.
+///
+/// Deploy and L1 handler transactions are returned for the contract they
+/// deploy or call. The pending block is treated as the latest block since
+/// pending transactions are not indexed. The continuation token is the block
+/// number and index of the last transaction of the page, as
+/// `<block_number>-<transaction_index>`.
+pub async fn get_transactions_by_contract(
+    context: RpcContext,
+    input: Input,
+) -> Result<Output, Error> {
+    if input.chunk_size.get() > MAX_CHUNK_SIZE {
+        return Err(Error::PageSizeTooBig);
+    }
+
+    let after = match &input.continuation_token {
+        Some(token) => {
+            Some(parse_continuation_token(token).ok_or(Error::InvalidContinuationToken)?)
+        }
+        None => None,
+    };
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let block_number = |block_id: BlockId| -> Result<BlockNumber, Error> {
+            let block_id = match block_id {
+                BlockId::Pending => pathfinder_storage::BlockId::Latest,
+                other => other.try_into().expect("Only pending cast should fail"),
+            };
+            let (block_number, _) = db
+                .block_id(block_id)
+                .context("Querying block id")?
+                .ok_or(Error::BlockNotFound)?;
+            Ok(block_number)
+        };
+
+        let from = match input.from_block {
+            Some(block_id) => block_number(block_id)?,
+            None => BlockNumber::GENESIS,
+        };
+        let to = block_number(input.to_block.unwrap_or(BlockId::Latest))?;
+
+        if let Some((block_number, _)) = after {
+            if !(from..=to).contains(&block_number) {
+                return Err(Error::InvalidContinuationToken);
+            }
+        }
+
+        let chunk_size = input.chunk_size.get();
+        let mut transactions = db
+            .transactions_by_sender(input.contract_address, from, to, after, chunk_size + 1)
+            .context("Querying transactions by sender")?;
+
+        let continuation_token = if transactions.len() > chunk_size {
+            transactions.truncate(chunk_size);
+            transactions
+                .last()
+                .map(|(block_number, idx, _)| format!("{block_number}-{idx}"))
+        } else {
+            None
+        };
+
+        let transactions = transactions
+            .into_iter()
+            .map(
+                |(block_number, transaction_index, transaction_hash)| TransactionEntry {
+                    block_number,
+                    transaction_index,
+                    transaction_hash,
+                },
+            )
+            .collect();
+
+        Ok(Output {
+            transactions,
+            continuation_token,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+fn parse_continuation_token(token: &str) -> Option<(BlockNumber, usize)> {
+    let (block_number, idx) = token.split_once('-')?;
+    let block_number = BlockNumber::new(block_number.parse().ok()?)?;
+    let idx = idx.parse().ok()?;
+    Some((block_number, idx))
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn input(contract_address: ContractAddress, continuation_token: Option<String>) -> Input {
+        Input {
+            contract_address,
+            from_block: None,
+            to_block: None,
+            continuation_token,
+            chunk_size: NonZeroUsize::new(4).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn paginated() {
+        let context = RpcContext::for_tests();
+        let contract = contract_address_bytes!(b"contract 1");
+
+        let first = get_transactions_by_contract(context.clone(), input(contract, None))
+            .await
+            .unwrap();
+        assert_eq!(
+            first.transactions[0],
+            TransactionEntry {
+                block_number: BlockNumber::new_or_panic(1),
+                transaction_index: 0,
+                transaction_hash: transaction_hash_bytes!(b"txn 1"),
+            }
+        );
+        assert_eq!(first.transactions.len(), 4);
+        assert_eq!(first.continuation_token.as_deref(), Some("2-2"));
+
+        let second =
+            get_transactions_by_contract(context, input(contract, first.continuation_token))
+                .await
+                .unwrap();
+        let hashes = second
+            .transactions
+            .iter()
+            .map(|transaction| transaction.transaction_hash)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hashes,
+            vec![
+                transaction_hash_bytes!(b"txn 6"),
+                transaction_hash_bytes!(b"txn reverted"),
+            ]
+        );
+        assert_eq!(second.continuation_token, None);
+    }
+
+    #[tokio::test]
+    async fn block_range() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            from_block: Some(BlockId::Number(BlockNumber::GENESIS)),
+            to_block: Some(BlockId::Number(BlockNumber::GENESIS)),
+            ..input(contract_address_bytes!(b"contract 0"), None)
+        };
+
+        let output = get_transactions_by_contract(context, input).await.unwrap();
+
+        assert_eq!(
+            output.transactions,
+            vec![TransactionEntry {
+                block_number: BlockNumber::GENESIS,
+                transaction_index: 0,
+                transaction_hash: transaction_hash_bytes!(b"txn 0"),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_continuation_token() {
+        let context = RpcContext::for_tests();
+        let contract = contract_address_bytes!(b"contract 1");
+
+        for token in ["1", "1-x", "100-0"] {
+            let result =
+                get_transactions_by_contract(context.clone(), input(contract, Some(token.into())))
+                    .await;
+            assert_matches::assert_matches!(result, Err(Error::InvalidContinuationToken));
+        }
+    }
+
+    #[tokio::test]
+    async fn page_size_too_big() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            chunk_size: NonZeroUsize::new(MAX_CHUNK_SIZE + 1).unwrap(),
+            ..input(contract_address_bytes!(b"contract 1"), None)
+        };
+
+        let result = get_transactions_by_contract(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::PageSizeTooBig));
+    }
+}
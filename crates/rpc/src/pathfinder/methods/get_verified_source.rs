@@ -0,0 +1,145 @@
+use anyhow::Context;
+use pathfinder_common::ClassHash;
+use pathfinder_storage::VerifiedSource;
+
+use crate::context::RpcContext;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    class_hash: ClassHash,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                class_hash: ClassHash(value.deserialize("class_hash")?),
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(Error: ClassHashNotFound);
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    class_hash: ClassHash,
+    compiler_version: String,
+    source_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repository_url: Option<String>,
+    provider: String,
+}
+
+/// Returns the verified source metadata imported for a class, or `null` if the
+/// class is known but has not been verified.
+pub async fn get_verified_source(
+    context: RpcContext,
+    input: Input,
+) -> Result<Option<Output>, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let source = db
+            .verified_source(input.class_hash)
+            .context("Querying verified source")?;
+
+        let Some(source) = source else {
+            let exists = db
+                .class_definitions_exist(&[input.class_hash])
+                .context("Querying class existence")?;
+            return match exists.first() {
+                Some(true) => Ok(None),
+                _ => Err(Error::ClassHashNotFound),
+            };
+        };
+
+        let VerifiedSource {
+            compiler_version,
+            source_hash,
+            repository_url,
+            provider,
+        } = source;
+
+        Ok(Some(Output {
+            class_hash: input.class_hash,
+            compiler_version,
+            source_hash,
+            repository_url,
+            provider,
+        }))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn verified() {
+        let context = RpcContext::for_tests();
+        let class_hash = class_hash_bytes!(b"class 0 hash");
+        let source = VerifiedSource {
+            compiler_version: "2.6.3".to_owned(),
+            source_hash: "0xabc".to_owned(),
+            repository_url: Some("https://example.com/repo".to_owned()),
+            provider: "explorer".to_owned(),
+        };
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.upsert_verified_source(class_hash, &source).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let output = get_verified_source(context, Input { class_hash })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            output,
+            Some(Output {
+                class_hash,
+                compiler_version: source.compiler_version,
+                source_hash: source.source_hash,
+                repository_url: source.repository_url,
+                provider: source.provider,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn not_verified() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            class_hash: class_hash_bytes!(b"class 0 hash"),
+        };
+
+        let output = get_verified_source(context, input).await.unwrap();
+
+        assert_eq!(output, None);
+    }
+
+    #[tokio::test]
+    async fn class_not_found() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            class_hash: class_hash_bytes!(b"unknown"),
+        };
+
+        let result = get_verified_source(context, input).await;
+
+        assert_matches::assert_matches!(result, Err(Error::ClassHashNotFound));
+    }
+}
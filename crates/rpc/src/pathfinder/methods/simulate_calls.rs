@@ -0,0 +1,388 @@
+use anyhow::Context;
+use pathfinder_common::{
+    BlockId,
+    CallParam,
+    ContractAddress,
+    ContractNonce,
+    EntryPoint,
+    Tip,
+    TransactionNonce,
+    TransactionVersion,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_executor::types::{
+    ExecuteInvocation,
+    FunctionInvocation,
+    TransactionSimulation,
+    TransactionTrace,
+};
+
+use crate::context::RpcContext;
+use crate::dto;
+use crate::dto::serialize::{self, SerializeForVersion, Serializer};
+use crate::method::simulate_transactions::SimulateTransactionError as Error;
+use crate::v02::types::request::{
+    BroadcastedInvokeTransaction,
+    BroadcastedInvokeTransactionV3,
+    BroadcastedTransaction,
+};
+use crate::v02::types::{DataAvailabilityMode, ResourceBounds};
+
+/// Maximum number of calls per request.
+const MAX_CALLS: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    block_id: BlockId,
+    sender_address: ContractAddress,
+    calls: Vec<Call>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    calldata: Vec<CallParam>,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                sender_address: ContractAddress(value.deserialize("sender_address")?),
+                calls: value.deserialize_array("calls", |value| {
+                    value.deserialize_map(|value| {
+                        Ok(Call {
+                            contract_address: ContractAddress(
+                                value.deserialize("contract_address")?,
+                            ),
+                            entry_point_selector: EntryPoint(
+                                value.deserialize("entry_point_selector")?,
+                            ),
+                            calldata: value.deserialize_array("calldata", |value| {
+                                Ok(CallParam(value.deserialize()?))
+                            })?,
+                        })
+                    })
+                })?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Output {
+    calls: Vec<Call>,
+    simulation: TransactionSimulation,
+}
+
+/// Estimates the cost of a batch of calls by an account before it is signed.
+///
+/// The calls are wrapped into an unsigned version 3 invoke transaction of
+/// `sender_address`, using the multicall calldata of Cairo 1 accounts, which
+/// is simulated without validation and fee charge. The resources of each call
+/// are taken from the matching call made by the account's `__execute__`.
+pub async fn simulate_calls(context: RpcContext, input: Input) -> Result<Output, Error> {
+    if input.calls.is_empty() {
+        return Err(Error::Custom(anyhow::anyhow!("No calls to simulate")));
+    }
+    if input.calls.len() > MAX_CALLS {
+        return Err(Error::Custom(anyhow::anyhow!(
+            "Too many calls, the maximum is {MAX_CALLS}"
+        )));
+    }
+
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending should fail");
+
+                let header = db
+                    .block_header(block_id)
+                    .context("Fetching block header")?
+                    .ok_or(Error::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(Error::StatePruned {
+                        earliest_available_block,
+                    });
+                }
+
+                (header, None)
+            }
+        };
+
+        let nonce = match pending
+            .as_ref()
+            .and_then(|pending| pending.contract_nonce(input.sender_address))
+        {
+            Some(nonce) => nonce,
+            None => db
+                .contract_nonce(input.sender_address, header.number.into())
+                .context("Querying sender nonce")?
+                .unwrap_or(ContractNonce::ZERO),
+        };
+
+        let transaction = invoke_transaction(input.sender_address, nonce, &input.calls);
+        crate::version_gate::check_transactions([&transaction], header.starknet_version)?;
+        let transaction = crate::executor::map_broadcasted_transaction(
+            &transaction,
+            context.chain_id,
+            context.casm_cache.as_ref(),
+        )?;
+
+        let state = pathfinder_executor::ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            pathfinder_executor::L1BlobDataAvailability::Enabled,
+            context.config.custom_versioned_constants.clone(),
+        );
+
+        let simulation = pathfinder_executor::simulate(state, vec![transaction], true, true)?
+            .pop()
+            .context("Simulation result is missing")?;
+
+        Ok(Output {
+            calls: input.calls,
+            simulation,
+        })
+    })
+    .await
+    .context("Simulating calls")?
+}
+
+/// An unsigned query-only invoke transaction executing `calls` from `sender`.
+fn invoke_transaction(
+    sender: ContractAddress,
+    nonce: ContractNonce,
+    calls: &[Call],
+) -> BroadcastedTransaction {
+    BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(
+        BroadcastedInvokeTransactionV3 {
+            version: TransactionVersion::THREE_WITH_QUERY_VERSION,
+            signature: vec![],
+            nonce: TransactionNonce(nonce.0),
+            resource_bounds: ResourceBounds::default(),
+            tip: Tip(0),
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+            sender_address: sender,
+            calldata: multicall_calldata(calls),
+        },
+    ))
+}
+
+/// The calldata of `__execute__(calls: Array<Call>)`.
+fn multicall_calldata(calls: &[Call]) -> Vec<CallParam> {
+    let mut calldata = vec![CallParam(Felt::from_u64(calls.len() as u64))];
+    for call in calls {
+        calldata.push(CallParam(call.contract_address.0));
+        calldata.push(CallParam(call.entry_point_selector.0));
+        calldata.push(CallParam(Felt::from_u64(call.calldata.len() as u64)));
+        calldata.extend_from_slice(&call.calldata);
+    }
+    calldata
+}
+
+/// Matches each call to the next call with the same target made by
+/// `execute`. `None` for calls the account did not make.
+fn attribute<'a>(
+    calls: &[Call],
+    execute: &'a FunctionInvocation,
+) -> Vec<Option<&'a FunctionInvocation>> {
+    let mut next = 0;
+
+    calls
+        .iter()
+        .map(|call| {
+            let position = execute.internal_calls[next..]
+                .iter()
+                .position(|invocation| {
+                    invocation.contract_address == call.contract_address
+                        && invocation.selector == call.entry_point_selector.0
+                })?;
+            next += position + 1;
+            Some(&execute.internal_calls[next - 1])
+        })
+        .collect()
+}
+
+impl SerializeForVersion for Output {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let TransactionTrace::Invoke(trace) = &self.simulation.trace else {
+            return Err(serde::ser::Error::custom("Expected an invoke trace"));
+        };
+
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(
+            "fee_estimation",
+            &dto::FeeEstimate(&self.simulation.fee_estimation),
+        )?;
+        match &trace.execute_invocation {
+            ExecuteInvocation::FunctionInvocation(execute) => {
+                let attributed = match execute {
+                    Some(execute) => attribute(&self.calls, execute),
+                    None => vec![None; self.calls.len()],
+                };
+                serializer.serialize_iter(
+                    "calls",
+                    self.calls.len(),
+                    &mut self
+                        .calls
+                        .iter()
+                        .zip(attributed)
+                        .map(|(call, invocation)| CallCost { call, invocation }),
+                )?;
+                if let Some(execute) = execute {
+                    serializer.serialize_field(
+                        "execute_resources",
+                        &dto::CairoResources(&execute.computation_resources),
+                    )?;
+                }
+            }
+            ExecuteInvocation::RevertedReason(reason) => {
+                serializer.serialize_field("revert_reason", reason)?;
+            }
+        }
+        serializer.end()
+    }
+}
+
+struct CallCost<'a> {
+    call: &'a Call,
+    invocation: Option<&'a FunctionInvocation>,
+}
+
+impl SerializeForVersion for CallCost<'_> {
+    fn serialize(&self, serializer: Serializer) -> Result<serialize::Ok, serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(
+            "contract_address",
+            &dto::Felt(&self.call.contract_address.0),
+        )?;
+        serializer.serialize_field(
+            "entry_point_selector",
+            &dto::Felt(&self.call.entry_point_selector.0),
+        )?;
+        serializer.serialize_optional(
+            "execution_resources",
+            self.invocation
+                .map(|invocation| dto::CairoResources(&invocation.computation_resources)),
+        )?;
+        if let Some(invocation) = self.invocation {
+            serializer.serialize_field("events", &invocation.events.len())?;
+            serializer.serialize_field("messages", &invocation.messages.len())?;
+        }
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_executor::types::{CallType, ComputationResources, EntryPointType};
+
+    use super::*;
+
+    fn call(contract_address: ContractAddress, selector: &[u8]) -> Call {
+        Call {
+            contract_address,
+            entry_point_selector: EntryPoint::hashed(selector),
+            calldata: vec![call_param!("0x1"), call_param!("0x2")],
+        }
+    }
+
+    fn invocation(call: &Call, steps: usize) -> FunctionInvocation {
+        FunctionInvocation {
+            calldata: call.calldata.iter().map(|param| param.0).collect(),
+            contract_address: call.contract_address,
+            selector: call.entry_point_selector.0,
+            call_type: CallType::Call,
+            caller_address: Felt::ZERO,
+            internal_calls: vec![],
+            class_hash: None,
+            entry_point_type: EntryPointType::External,
+            events: vec![],
+            messages: vec![],
+            result: vec![],
+            computation_resources: ComputationResources {
+                steps,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn multicall_calldata() {
+        let calls = [
+            call(contract_address!("0xa"), b"transfer"),
+            Call {
+                contract_address: contract_address!("0xb"),
+                entry_point_selector: EntryPoint::hashed(b"swap"),
+                calldata: vec![],
+            },
+        ];
+
+        assert_eq!(
+            super::multicall_calldata(&calls),
+            vec![
+                call_param!("0x2"),
+                call_param!("0xa"),
+                CallParam(EntryPoint::hashed(b"transfer").0),
+                call_param!("0x2"),
+                call_param!("0x1"),
+                call_param!("0x2"),
+                call_param!("0xb"),
+                CallParam(EntryPoint::hashed(b"swap").0),
+                call_param!("0x0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn calls_are_attributed_in_order() {
+        let approve = call(contract_address!("0xa"), b"approve");
+        let swap = call(contract_address!("0xb"), b"swap");
+        let missing = call(contract_address!("0xc"), b"transfer");
+
+        let mut execute = invocation(&call(contract_address!("0x1"), b"__execute__"), 100);
+        execute.internal_calls = vec![
+            invocation(&approve, 10),
+            invocation(&swap, 20),
+            invocation(&approve, 30),
+        ];
+
+        let attributed = attribute(&[approve.clone(), missing, swap, approve], &execute);
+        let steps = attributed
+            .iter()
+            .map(|invocation| invocation.map(|invocation| invocation.computation_resources.steps))
+            .collect::<Vec<_>>();
+
+        assert_eq!(steps, vec![Some(10), None, Some(20), Some(30)]);
+    }
+}
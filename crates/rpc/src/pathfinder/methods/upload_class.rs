@@ -0,0 +1,97 @@
+use anyhow::Context;
+use pathfinder_common::ClassHash;
+use serde_json::value::RawValue;
+use starknet_gateway_types::class_hash::ComputedClassHash;
+
+use crate::class_uploads::TTL;
+use crate::context::RpcContext;
+use crate::v02::types::SierraContractClass;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    contract_class: SierraContractClass,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contract_class: value.deserialize("contract_class")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Output {
+    class_hash: ClassHash,
+    expires_in_seconds: u64,
+}
+
+crate::error::generate_rpc_error_subset!(Error);
+
+/// Stores a Sierra class so that declare transactions passed to
+/// `starknet_estimateFee`, `starknet_simulateTransactions` and
+/// `starknet_addDeclareTransaction` can reference it by class hash instead of
+/// including the full definition.
+///
+/// Uploading the same class again extends its lifetime.
+pub async fn upload_class(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    let (class_hash, definition) = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let definition = input
+            .contract_class
+            .serialize_to_json()
+            .context("Serializing class")?;
+        let class_hash = match starknet_gateway_types::class_hash::compute_class_hash(&definition)
+            .context("Computing class hash")?
+        {
+            ComputedClassHash::Sierra(class_hash) => class_hash,
+            ComputedClassHash::Cairo(_) => anyhow::bail!("Sierra class hashed as Cairo class"),
+        };
+        let definition = RawValue::from_string(String::from_utf8(definition)?)?;
+
+        anyhow::Ok((class_hash, definition))
+    })
+    .await
+    .context("Joining blocking task")??;
+
+    context.class_uploads.insert(class_hash, definition);
+
+    Ok(Output {
+        class_hash,
+        expires_in_seconds: TTL.as_secs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::method::add_declare_transaction::tests::SIERRA_CLASS;
+
+    #[tokio::test]
+    async fn uploaded_class_can_be_referenced() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            contract_class: SIERRA_CLASS.clone(),
+        };
+        let expected = SIERRA_CLASS.class_hash().unwrap().hash();
+
+        let output = upload_class(context.clone(), input).await.unwrap();
+        assert_eq!(output.class_hash, expected);
+
+        let params = json!([{"type": "DECLARE", "contract_class": expected}]);
+        let params = RawValue::from_string(params.to_string()).unwrap();
+        let params = context.class_uploads.substitute(&params).unwrap().unwrap();
+
+        let params: Value = serde_json::from_str(params.get()).unwrap();
+        let substituted =
+            serde_json::from_value::<SierraContractClass>(params[0]["contract_class"].clone())
+                .unwrap();
+        assert_eq!(substituted, *SIERRA_CLASS);
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use pathfinder_common::{BlockHeader, BlockNumber, StateUpdate};
+use pathfinder_common::{BlockHeader, BlockNumber, GasPrice, StateUpdate};
 use pathfinder_storage::Transaction;
 use starknet_gateway_types::reply::{GasPrices, PendingBlock, Status};
 use tokio::sync::watch::Receiver as WatchReceiver;
@@ -18,6 +18,53 @@ pub struct PendingData {
     pub number: BlockNumber,
 }
 
+/// Where simulations on the pending block take their gas prices from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PendingGasPriceSource {
+    /// The pending block's, which may change whenever it is refreshed.
+    #[default]
+    Pending,
+    /// The latest block's, which only change once per block.
+    Latest,
+}
+
+/// Gas prices supplied with a request, which replace those of the pending
+/// block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasPriceOverrides {
+    pub eth_l1_gas_price: GasPrice,
+    pub strk_l1_gas_price: GasPrice,
+    pub eth_l1_data_gas_price: GasPrice,
+    pub strk_l1_data_gas_price: GasPrice,
+}
+
+impl crate::dto::DeserializeForVersion for GasPriceOverrides {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            let (eth_l1_gas_price, strk_l1_gas_price) =
+                value.deserialize_map("l1_gas_price", deserialize_resource_price)?;
+            let (eth_l1_data_gas_price, strk_l1_data_gas_price) =
+                value.deserialize_map("l1_data_gas_price", deserialize_resource_price)?;
+            Ok(Self {
+                eth_l1_gas_price,
+                strk_l1_gas_price,
+                eth_l1_data_gas_price,
+                strk_l1_data_gas_price,
+            })
+        })
+    }
+}
+
+/// Parses a `RESOURCE_PRICE` into its prices in wei and fri.
+fn deserialize_resource_price(
+    value: &mut crate::dto::Map,
+) -> Result<(GasPrice, GasPrice), serde_json::Error> {
+    Ok((
+        GasPrice(value.deserialize::<crate::dto::U128Hex>("price_in_wei")?.0),
+        GasPrice(value.deserialize::<crate::dto::U128Hex>("price_in_fri")?.0),
+    ))
+}
+
 impl PendingData {
     pub fn header(&self) -> BlockHeader {
         // Be explicit about fields so that we are forced to check
@@ -49,6 +96,40 @@ impl PendingData {
     }
 }
 
+impl PendingData {
+    /// Returns the [header](Self::header) simulations on the pending block are
+    /// executed with, whose gas prices are taken from `overrides` if given, and
+    /// from `source` otherwise.
+    pub fn simulation_header(
+        &self,
+        tx: &Transaction<'_>,
+        source: PendingGasPriceSource,
+        overrides: Option<GasPriceOverrides>,
+    ) -> anyhow::Result<BlockHeader> {
+        let mut header = self.header();
+
+        if let Some(overrides) = overrides {
+            header.eth_l1_gas_price = overrides.eth_l1_gas_price;
+            header.strk_l1_gas_price = overrides.strk_l1_gas_price;
+            header.eth_l1_data_gas_price = overrides.eth_l1_data_gas_price;
+            header.strk_l1_data_gas_price = overrides.strk_l1_data_gas_price;
+        } else if source == PendingGasPriceSource::Latest {
+            // Before the first block the pending block's prices are all there is.
+            if let Some(latest) = tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .context("Querying latest block header")?
+            {
+                header.eth_l1_gas_price = latest.eth_l1_gas_price;
+                header.strk_l1_gas_price = latest.strk_l1_gas_price;
+                header.eth_l1_data_gas_price = latest.eth_l1_data_gas_price;
+                header.strk_l1_data_gas_price = latest.strk_l1_data_gas_price;
+            }
+        }
+
+        Ok(header)
+    }
+}
+
 impl PendingWatcher {
     pub fn new(receiver: WatchReceiver<PendingData>) -> Self {
         Self(receiver)
@@ -219,4 +300,87 @@ mod tests {
 
         pretty_assertions_sorted::assert_eq_sorted!(result, expected);
     }
+
+    #[test]
+    fn simulation_header_gas_prices() {
+        let mut storage = pathfinder_storage::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+
+        let latest = BlockHeader::builder()
+            .eth_l1_gas_price(GasPrice(1234))
+            .strk_l1_gas_price(GasPrice(3377))
+            .eth_l1_data_gas_price(GasPrice(9999))
+            .strk_l1_data_gas_price(GasPrice(8888))
+            .finalize_with_hash(block_hash_bytes!(b"latest hash"));
+
+        let tx = storage.transaction().unwrap();
+        tx.insert_block_header(&latest).unwrap();
+
+        let pending = PendingData {
+            block: PendingBlock {
+                parent_hash: latest.hash,
+                l1_gas_price: GasPrices {
+                    price_in_wei: GasPrice(51123),
+                    price_in_fri: GasPrice(44411),
+                },
+                ..Default::default()
+            }
+            .into(),
+            number: latest.number + 1,
+            ..Default::default()
+        };
+
+        let header = pending
+            .simulation_header(&tx, PendingGasPriceSource::Pending, None)
+            .unwrap();
+        assert_eq!(header, pending.header());
+
+        let header = pending
+            .simulation_header(&tx, PendingGasPriceSource::Latest, None)
+            .unwrap();
+        assert_eq!(header.number, pending.number);
+        assert_eq!(header.eth_l1_gas_price, latest.eth_l1_gas_price);
+        assert_eq!(header.strk_l1_gas_price, latest.strk_l1_gas_price);
+        assert_eq!(header.eth_l1_data_gas_price, latest.eth_l1_data_gas_price);
+        assert_eq!(header.strk_l1_data_gas_price, latest.strk_l1_data_gas_price);
+
+        let overrides = GasPriceOverrides {
+            eth_l1_gas_price: GasPrice(1),
+            strk_l1_gas_price: GasPrice(2),
+            eth_l1_data_gas_price: GasPrice(3),
+            strk_l1_data_gas_price: GasPrice(4),
+        };
+        let header = pending
+            .simulation_header(&tx, PendingGasPriceSource::Latest, Some(overrides))
+            .unwrap();
+        assert_eq!(header.eth_l1_gas_price, GasPrice(1));
+        assert_eq!(header.strk_l1_gas_price, GasPrice(2));
+        assert_eq!(header.eth_l1_data_gas_price, GasPrice(3));
+        assert_eq!(header.strk_l1_data_gas_price, GasPrice(4));
+    }
+
+    #[test]
+    fn gas_price_overrides() {
+        use crate::dto::DeserializeForVersion;
+
+        let overrides = GasPriceOverrides::deserialize(crate::dto::Value::new(
+            serde_json::json!({
+                "l1_gas_price": {"price_in_wei": "0x1", "price_in_fri": "0x2"},
+                "l1_data_gas_price": {"price_in_wei": "0x3", "price_in_fri": "0x4"}
+            }),
+            crate::RpcVersion::V07,
+        ))
+        .unwrap();
+        assert_eq!(
+            overrides,
+            GasPriceOverrides {
+                eth_l1_gas_price: GasPrice(1),
+                strk_l1_gas_price: GasPrice(2),
+                eth_l1_data_gas_price: GasPrice(3),
+                strk_l1_data_gas_price: GasPrice(4),
+            }
+        );
+    }
 }
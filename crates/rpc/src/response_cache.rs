@@ -0,0 +1,834 @@
+//! Caches responses to queries about specific blocks and transactions.
+//!
+//! Responses for a block referenced by hash or number only change if the block
+//! is reorged away or accepted on L1, since they include the block's status.
+//! Only responses about blocks accepted on L1 are therefore cached, and entries
+//! are kept until a reorg affecting them is broadcast. Queries referencing
+//! `latest` or `pending` are never cached.
+//!
+//! The cache consists of a bounded number of entries kept in memory, and an
+//! optional size-bounded SQLite database which survives restarts. Entries are
+//! evicted from both in least recently used order.
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use cached::{Cached, SizedCache};
+use pathfinder_common::{BlockHash, BlockNumber, TransactionHash};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::{BlockId, Storage};
+use rusqlite::OptionalExtension;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::jsonrpc::{ChainEvent, RpcOutput};
+use crate::pending::PendingData;
+use crate::RpcVersion;
+
+/// Methods whose first parameter is a block id.
+const BLOCK_METHODS: [&str; 4] = [
+    "starknet_getBlockWithTxHashes",
+    "starknet_getBlockWithTxs",
+    "starknet_getBlockWithReceipts",
+    "starknet_getStateUpdate",
+];
+
+const TRANSACTION_BY_HASH: &str = "starknet_getTransactionByHash";
+
+/// The number of entries evicted from the disk cache at a time.
+const DISK_EVICTION_BATCH: i64 = 64;
+
+#[derive(Clone, Debug)]
+pub struct ResponseCacheConfig {
+    pub memory_entries: NonZeroUsize,
+    pub disk: Option<DiskCacheConfig>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiskCacheConfig {
+    pub path: PathBuf,
+    /// Maximum total size of the (compressed) responses in bytes.
+    pub max_size: NonZeroU64,
+}
+
+#[derive(Clone)]
+pub struct ResponseCache(Arc<Inner>);
+
+struct Inner {
+    memory: Mutex<SizedCache<String, Entry>>,
+    disk: Option<Mutex<DiskCache>>,
+    /// Incremented by every invalidation, while holding the memory cache's
+    /// lock. Responses computed in an earlier generation may be about reorged
+    /// blocks and are not inserted.
+    generation: AtomicU64,
+}
+
+#[derive(Clone)]
+struct Entry {
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    response: Arc<RpcOutput>,
+}
+
+/// Identifies a request whose response can be cached.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CacheKey {
+    key: String,
+    subject: Subject,
+}
+
+/// What a cacheable request is about.
+#[derive(Clone, Debug, PartialEq)]
+enum Subject {
+    Block(BlockId),
+    Transaction(TransactionHash),
+}
+
+impl ResponseCache {
+    /// Creates the cache and spawns a task which invalidates entries affected
+    /// by the reorgs broadcast on `chain_events`.
+    ///
+    /// Entries persisted on disk are only kept if their block is still
+    /// canonical and accepted on L1 in `storage`, since reorgs may have
+    /// happened while the node was down.
+    pub fn new(
+        config: ResponseCacheConfig,
+        storage: &Storage,
+        chain_events: broadcast::Receiver<ChainEvent>,
+    ) -> anyhow::Result<Self> {
+        let disk = config
+            .disk
+            .map(|disk| {
+                let mut cache =
+                    DiskCache::open(&disk.path, disk.max_size.get()).with_context(|| {
+                        format!("Opening response cache at {}", disk.path.display())
+                    })?;
+                cache
+                    .revalidate(storage)
+                    .context("Revalidating response cache")?;
+                anyhow::Ok(cache)
+            })
+            .transpose()?
+            .map(Mutex::new);
+
+        let cache = Self(Arc::new(Inner {
+            memory: Mutex::new(SizedCache::with_size(config.memory_entries.get())),
+            disk,
+            generation: AtomicU64::new(0),
+        }));
+
+        tokio::spawn(cache.clone().invalidate_on_reorg(chain_events));
+
+        Ok(cache)
+    }
+
+    /// Returns the cache key for a request, or [None] if its response may
+    /// change even after its block is accepted on L1.
+    pub(crate) fn key(
+        version: RpcVersion,
+        method: &str,
+        params: Option<&str>,
+        pending: &PendingData,
+    ) -> Option<CacheKey> {
+        // Avoids parsing the params of other requests.
+        if !BLOCK_METHODS.contains(&method) && method != TRANSACTION_BY_HASH {
+            return None;
+        }
+        let params: Value = serde_json::from_str(params?).ok()?;
+
+        let subject = if BLOCK_METHODS.contains(&method) {
+            let block_id = first_param(&params, "block_id")?.as_object()?;
+            if let Some(number) = block_id.get("block_number") {
+                Subject::Block(BlockNumber::new(number.as_u64()?)?.into())
+            } else if let Some(hash) = block_id.get("block_hash") {
+                let hash: BlockHash = serde_json::from_value(hash.clone()).ok()?;
+                Subject::Block(hash.into())
+            } else {
+                return None;
+            }
+        } else if method == TRANSACTION_BY_HASH {
+            let hash = first_param(&params, "transaction_hash")?.clone();
+            let hash: TransactionHash = serde_json::from_value(hash).ok()?;
+            // Pending transactions may still be dropped.
+            if pending.block.transactions.iter().any(|tx| tx.hash == hash) {
+                return None;
+            }
+            Subject::Transaction(hash)
+        } else {
+            return None;
+        };
+
+        Some(CacheKey {
+            key: format!("{}:{method}:{params}", version.to_str()),
+            subject,
+        })
+    }
+
+    /// The current generation, which must be read before computing a response
+    /// which is to be [inserted](Self::insert).
+    pub(crate) fn generation(&self) -> u64 {
+        self.0.generation.load(Ordering::SeqCst)
+    }
+
+    pub(crate) async fn get(&self, key: &CacheKey) -> Option<RpcOutput> {
+        if let Some(entry) = self.0.memory.lock().unwrap().cache_get(&key.key).cloned() {
+            return Some(RpcOutput::clone(&entry.response));
+        }
+
+        self.0.disk.as_ref()?;
+        let inner = self.0.clone();
+        let disk_key = key.key.clone();
+        let entry = tokio::task::spawn_blocking(move || {
+            let disk = inner.disk.as_ref().expect("Checked above");
+            disk.lock().unwrap().get(&disk_key)
+        })
+        .await;
+
+        match entry {
+            Ok(Ok(Some(entry))) => {
+                let response = RpcOutput::clone(&entry.response);
+                self.0
+                    .memory
+                    .lock()
+                    .unwrap()
+                    .cache_set(key.key.clone(), entry);
+                Some(response)
+            }
+            Ok(Ok(None)) => None,
+            Ok(Err(error)) => {
+                tracing::warn!(%error, "Reading from response cache failed");
+                None
+            }
+            Err(error) => {
+                tracing::warn!(%error, "Response cache task failed");
+                None
+            }
+        }
+    }
+
+    /// Inserts a response about `block` computed in `generation`, unless a
+    /// reorg has been handled since.
+    ///
+    /// The block must be accepted on L1, see [CacheKey::l1_accepted_block].
+    pub(crate) async fn insert(
+        &self,
+        key: CacheKey,
+        generation: u64,
+        (block_number, block_hash): (BlockNumber, BlockHash),
+        response: RpcOutput,
+    ) {
+        let entry = Entry {
+            block_number,
+            block_hash,
+            response: Arc::new(response),
+        };
+
+        {
+            let mut memory = self.0.memory.lock().unwrap();
+            if self.0.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            memory.cache_set(key.key.clone(), entry.clone());
+        }
+
+        if self.0.disk.is_none() {
+            return;
+        }
+        let inner = self.0.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let disk = inner.disk.as_ref().expect("Checked above");
+            // Invalidations bump the generation before deleting from disk, so
+            // checking it under the lock keeps stale entries out.
+            let mut disk = disk.lock().unwrap();
+            if inner.generation.load(Ordering::SeqCst) != generation {
+                return Ok(());
+            }
+            disk.insert(&key.key, &entry)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => tracing::warn!(%error, "Writing to response cache failed"),
+            Err(error) => tracing::warn!(%error, "Response cache task failed"),
+        }
+    }
+
+    /// Removes all entries which may refer to blocks from `first_block`
+    /// onwards.
+    async fn invalidate(&self, first_block: BlockNumber) {
+        // Reorgs are rare enough that rebuilding the in-memory cache is cheap.
+        {
+            let mut memory = self.0.memory.lock().unwrap();
+            self.0.generation.fetch_add(1, Ordering::SeqCst);
+            memory.cache_clear();
+        }
+
+        if self.0.disk.is_none() {
+            return;
+        }
+        let inner = self.0.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let disk = inner.disk.as_ref().expect("Checked above");
+            disk.lock().unwrap().invalidate(first_block)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => tracing::error!(%error, "Invalidating response cache failed"),
+            Err(error) => tracing::error!(%error, "Response cache task failed"),
+        }
+    }
+
+    async fn invalidate_on_reorg(self, mut chain_events: broadcast::Receiver<ChainEvent>) {
+        loop {
+            match chain_events.recv().await {
+                Ok(ChainEvent::Reorg(reorg)) => self.invalidate(reorg.first_block_number).await,
+                Ok(ChainEvent::Commit(_) | ChainEvent::PendingUpdate(_)) => {}
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    // We don't know which blocks were affected.
+                    self.invalidate(BlockNumber::GENESIS).await
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+impl CacheKey {
+    /// Returns the number and hash of the block the request is about, if it
+    /// has been accepted on L1. The response can then only change through a
+    /// reorg.
+    pub(crate) async fn l1_accepted_block(
+        &self,
+        storage: &Storage,
+    ) -> Option<(BlockNumber, BlockHash)> {
+        let key = self.clone();
+        let storage = storage.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+            key.query_l1_accepted_block(&db)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(block)) => block,
+            Ok(Err(error)) => {
+                tracing::warn!(%error, "Querying L1 acceptance of cacheable response failed");
+                None
+            }
+            Err(error) => {
+                tracing::warn!(%error, "Response cache task failed");
+                None
+            }
+        }
+    }
+
+    fn query_l1_accepted_block(
+        &self,
+        db: &pathfinder_storage::Transaction<'_>,
+    ) -> anyhow::Result<Option<(BlockNumber, BlockHash)>> {
+        let block = match self.subject {
+            Subject::Block(block) => block,
+            Subject::Transaction(hash) => {
+                match db
+                    .transaction_block_hash(hash)
+                    .context("Querying transaction's block")?
+                {
+                    Some(block_hash) => block_hash.into(),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let Some((number, hash)) = db.block_id(block).context("Querying block")? else {
+            return Ok(None);
+        };
+        let l1_accepted = db
+            .block_is_l1_accepted(number.into())
+            .context("Querying L1 acceptance")?;
+
+        Ok(l1_accepted.then_some((number, hash)))
+    }
+}
+
+/// Returns the first positional parameter, or the parameter called `name`.
+fn first_param<'a>(params: &'a Value, name: &str) -> Option<&'a Value> {
+    match params {
+        Value::Array(params) => params.first(),
+        Value::Object(params) => params.get(name),
+        _ => None,
+    }
+}
+
+struct DiskCache {
+    connection: rusqlite::Connection,
+    /// Total size of the stored responses.
+    size: u64,
+    max_size: u64,
+    /// Logical clock used to track the last use of entries.
+    clock: i64,
+}
+
+impl DiskCache {
+    fn open(path: &std::path::Path, max_size: u64) -> anyhow::Result<Self> {
+        let connection = rusqlite::Connection::open(path)?;
+        connection
+            .execute_batch(
+                r"PRAGMA journal_mode = WAL;
+PRAGMA synchronous = NORMAL;
+CREATE TABLE IF NOT EXISTS responses (
+    key TEXT PRIMARY KEY,
+    block_number INTEGER NOT NULL,
+    block_hash BLOB NOT NULL,
+    response BLOB NOT NULL,
+    last_used INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS responses_last_used ON responses(last_used);
+CREATE INDEX IF NOT EXISTS responses_block_number ON responses(block_number);",
+            )
+            .context("Creating tables")?;
+
+        let clock = connection
+            .query_row(
+                "SELECT COALESCE(MAX(last_used), 0) FROM responses",
+                [],
+                |row| row.get(0),
+            )
+            .context("Querying last use")?;
+
+        let mut cache = Self {
+            connection,
+            size: 0,
+            max_size,
+            clock,
+        };
+        cache.size = cache.stored_size()?;
+        // The maximum size may have been lowered since the last run.
+        cache.evict()?;
+
+        Ok(cache)
+    }
+
+    fn stored_size(&self) -> anyhow::Result<u64> {
+        let size: i64 = self
+            .connection
+            .query_row(
+                "SELECT COALESCE(SUM(length(response)), 0) FROM responses",
+                [],
+                |row| row.get(0),
+            )
+            .context("Querying cache size")?;
+
+        Ok(size as u64)
+    }
+
+    fn get(&mut self, key: &str) -> anyhow::Result<Option<Entry>> {
+        self.clock += 1;
+
+        let row = self
+            .connection
+            .prepare_cached(
+                "UPDATE responses SET last_used = ? WHERE key = ? RETURNING block_number, \
+                 block_hash, response",
+            )?
+            .query_row(rusqlite::params![self.clock, key], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Vec<u8>>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            })
+            .optional()
+            .context("Querying response")?;
+
+        let Some((block_number, block_hash, response)) = row else {
+            return Ok(None);
+        };
+
+        let response = zstd::decode_all(response.as_slice()).context("Decompressing response")?;
+        let response = serde_json::from_slice(&response).context("Parsing response")?;
+
+        Ok(Some(Entry {
+            block_number: BlockNumber::new(block_number as u64).context("Parsing block number")?,
+            block_hash: BlockHash(Felt::from_be_slice(&block_hash).context("Parsing block hash")?),
+            response: Arc::new(RpcOutput::Raw(response)),
+        }))
+    }
+
+    fn insert(&mut self, key: &str, entry: &Entry) -> anyhow::Result<()> {
+        let response = serde_json::to_vec(&*entry.response).context("Serializing response")?;
+        let response = zstd::encode_all(response.as_slice(), 0).context("Compressing response")?;
+        if response.len() as u64 > self.max_size {
+            return Ok(());
+        }
+
+        self.clock += 1;
+
+        // Replacing an entry must not count its size twice.
+        let previous_size: Option<i64> = self
+            .connection
+            .prepare_cached("SELECT length(response) FROM responses WHERE key = ?")?
+            .query_row([key], |row| row.get(0))
+            .optional()
+            .context("Querying previous response")?;
+
+        self.connection
+            .prepare_cached(
+                "INSERT OR REPLACE INTO responses (key, block_number, block_hash, response, \
+                 last_used) VALUES (?, ?, ?, ?, ?)",
+            )?
+            .execute(rusqlite::params![
+                key,
+                entry.block_number.get() as i64,
+                entry.block_hash.0.as_be_bytes(),
+                response,
+                self.clock,
+            ])
+            .context("Inserting response")?;
+
+        self.size = self.size - previous_size.unwrap_or_default() as u64 + response.len() as u64;
+
+        self.evict()
+    }
+
+    /// Removes the least recently used entries until the cache fits within
+    /// its maximum size.
+    fn evict(&mut self) -> anyhow::Result<()> {
+        while self.size > self.max_size {
+            let freed = self
+                .connection
+                .prepare_cached(
+                    "DELETE FROM responses WHERE key IN (SELECT key FROM responses ORDER BY \
+                     last_used LIMIT ?) RETURNING length(response)",
+                )?
+                .query_map([DISK_EVICTION_BATCH], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()
+                .context("Evicting responses")?;
+
+            if freed.is_empty() {
+                self.size = 0;
+                break;
+            }
+
+            self.size = self
+                .size
+                .saturating_sub(freed.into_iter().sum::<i64>() as u64);
+        }
+
+        Ok(())
+    }
+
+    fn invalidate(&mut self, first_block: BlockNumber) -> anyhow::Result<()> {
+        self.connection
+            .execute(
+                "DELETE FROM responses WHERE block_number >= ?",
+                [first_block.get() as i64],
+            )
+            .context("Deleting responses")?;
+
+        self.size = self.stored_size()?;
+
+        Ok(())
+    }
+
+    /// Removes the entries whose block is no longer canonical or accepted on
+    /// L1 in `storage`.
+    fn revalidate(&mut self, storage: &Storage) -> anyhow::Result<()> {
+        let blocks = self
+            .connection
+            .prepare("SELECT DISTINCT block_number, block_hash FROM responses")?
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Querying cached blocks")?;
+
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        for (number, hash) in blocks {
+            let valid = match BlockNumber::new(number as u64) {
+                Some(number) => {
+                    let canonical = db
+                        .block_hash(number.into())
+                        .context("Querying block hash")?;
+                    canonical.is_some_and(|canonical| canonical.0.as_be_bytes() == hash.as_slice())
+                        && db
+                            .block_is_l1_accepted(number.into())
+                            .context("Querying L1 acceptance")?
+                }
+                None => false,
+            };
+
+            if !valid {
+                self.connection
+                    .execute(
+                        "DELETE FROM responses WHERE block_number = ? AND block_hash = ?",
+                        rusqlite::params![number, hash],
+                    )
+                    .context("Deleting responses")?;
+            }
+        }
+
+        self.size = self.stored_size()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::Transaction;
+    use pathfinder_common::BlockHeader;
+    use serde_json::json;
+    use starknet_gateway_types::reply::PendingBlock;
+
+    use super::*;
+    use crate::jsonrpc::Reorg;
+
+    const METHOD: &str = "starknet_getBlockWithTxHashes";
+
+    fn key(method: &str, params: Value) -> Option<CacheKey> {
+        ResponseCache::key(
+            RpcVersion::V07,
+            method,
+            Some(&params.to_string()),
+            &PendingData::default(),
+        )
+    }
+
+    fn reorg(first_block: u64) -> ChainEvent {
+        ChainEvent::Reorg(Arc::new(Reorg {
+            first_block_number: BlockNumber::new_or_panic(first_block),
+            first_block_hash: block_hash!("0x1"),
+            last_block_number: BlockNumber::new_or_panic(first_block),
+            last_block_hash: block_hash!("0x1"),
+        }))
+    }
+
+    fn block(number: u64) -> (BlockNumber, BlockHash) {
+        (
+            BlockNumber::new_or_panic(number),
+            BlockHash(Felt::from_u64(number + 100)),
+        )
+    }
+
+    /// Storage containing `blocks` blocks, of which the ones up to
+    /// `l1_accepted` are accepted on L1.
+    fn storage(blocks: u64, l1_accepted: u64) -> Storage {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        for number in 0..blocks {
+            let (number, hash) = block(number);
+            let header = BlockHeader::builder()
+                .number(number)
+                .finalize_with_hash(hash);
+            tx.insert_block_header(&header).unwrap();
+        }
+        tx.update_l1_l2_pointer(Some(BlockNumber::new_or_panic(l1_accepted)))
+            .unwrap();
+        tx.commit().unwrap();
+        storage
+    }
+
+    fn config(dir: &tempfile::TempDir) -> ResponseCacheConfig {
+        ResponseCacheConfig {
+            memory_entries: NonZeroUsize::new(10).unwrap(),
+            disk: Some(DiskCacheConfig {
+                path: dir.path().join("cache.sqlite"),
+                max_size: NonZeroU64::new(1024 * 1024).unwrap(),
+            }),
+        }
+    }
+
+    #[test]
+    fn only_specific_blocks_are_cacheable() {
+        let by_number = key(METHOD, json!([{ "block_number": 5 }])).unwrap();
+        assert_eq!(
+            by_number.subject,
+            Subject::Block(BlockNumber::new_or_panic(5).into())
+        );
+
+        let by_name = key(METHOD, json!({ "block_id": { "block_number": 5 } })).unwrap();
+        assert_eq!(
+            by_name.subject,
+            Subject::Block(BlockNumber::new_or_panic(5).into())
+        );
+
+        let by_hash = key(METHOD, json!([{ "block_hash": "0x1" }])).unwrap();
+        assert_eq!(by_hash.subject, Subject::Block(block_hash!("0x1").into()));
+
+        assert_eq!(key(METHOD, json!(["latest"])), None);
+        assert_eq!(key(METHOD, json!(["pending"])), None);
+        assert_eq!(key("starknet_blockNumber", json!([])), None);
+    }
+
+    #[test]
+    fn pending_transactions_are_not_cacheable() {
+        let hash = transaction_hash!("0x123");
+        let params = json!([hash]).to_string();
+        let pending = PendingData {
+            block: Arc::new(PendingBlock {
+                transactions: vec![Transaction {
+                    hash,
+                    variant: Default::default(),
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let key = ResponseCache::key(
+            RpcVersion::V07,
+            TRANSACTION_BY_HASH,
+            Some(&params),
+            &pending,
+        );
+        assert_eq!(key, None);
+
+        let key = ResponseCache::key(
+            RpcVersion::V07,
+            TRANSACTION_BY_HASH,
+            Some(&params),
+            &PendingData::default(),
+        );
+        assert!(key.is_some());
+    }
+
+    #[tokio::test]
+    async fn reorg_invalidates_affected_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = broadcast::channel(1);
+        let cache = ResponseCache::new(config(&dir), &storage(3, 2), rx).unwrap();
+
+        let before = key(METHOD, json!([{ "block_number": 1 }])).unwrap();
+        let after = key(METHOD, json!([{ "block_number": 2 }])).unwrap();
+        let response = RpcOutput::from(json!({ "block_number": 1 }));
+        cache
+            .insert(
+                before.clone(),
+                cache.generation(),
+                block(1),
+                response.clone(),
+            )
+            .await;
+        cache
+            .insert(
+                after.clone(),
+                cache.generation(),
+                block(2),
+                response.clone(),
+            )
+            .await;
+
+        // Clear the memory cache so that the disk cache is exercised as well.
+        cache.invalidate(BlockNumber::new_or_panic(100)).await;
+        assert_eq!(cache.get(&before).await, Some(response.clone()));
+        assert_eq!(cache.get(&after).await, Some(response.clone()));
+
+        tx.send(reorg(2)).unwrap();
+        // Let the invalidation task run.
+        drop(tx);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        assert_eq!(cache.get(&before).await, Some(response));
+        assert_eq!(cache.get(&after).await, None);
+    }
+
+    #[tokio::test]
+    async fn only_blocks_accepted_on_l1_are_cacheable() {
+        let storage = storage(3, 1);
+
+        let accepted = key(METHOD, json!([{ "block_hash": "0x65" }])).unwrap();
+        assert_eq!(accepted.l1_accepted_block(&storage).await, Some(block(1)));
+
+        let not_accepted = key(METHOD, json!([{ "block_number": 2 }])).unwrap();
+        assert_eq!(not_accepted.l1_accepted_block(&storage).await, None);
+
+        let unknown = key(TRANSACTION_BY_HASH, json!(["0x123"])).unwrap();
+        assert_eq!(unknown.l1_accepted_block(&storage).await, None);
+    }
+
+    #[tokio::test]
+    async fn responses_computed_before_a_reorg_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_tx, rx) = broadcast::channel(1);
+        let cache = ResponseCache::new(config(&dir), &storage(3, 2), rx).unwrap();
+
+        let key = key(METHOD, json!([{ "block_number": 1 }])).unwrap();
+        let generation = cache.generation();
+        cache.invalidate(BlockNumber::new_or_panic(1)).await;
+        cache
+            .insert(key.clone(), generation, block(1), json!({}).into())
+            .await;
+
+        assert_eq!(cache.get(&key).await, None);
+    }
+
+    #[tokio::test]
+    async fn disk_cache_is_revalidated_on_startup() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = key(METHOD, json!([{ "block_number": 1 }])).unwrap();
+        let reorged = key(METHOD, json!([{ "block_number": 2 }])).unwrap();
+        let response = RpcOutput::from(json!({}));
+        {
+            let (_tx, rx) = broadcast::channel(1);
+            let cache = ResponseCache::new(config(&dir), &storage(3, 2), rx).unwrap();
+            cache
+                .insert(kept.clone(), cache.generation(), block(1), response.clone())
+                .await;
+            cache
+                .insert(
+                    reorged.clone(),
+                    cache.generation(),
+                    block(2),
+                    response.clone(),
+                )
+                .await;
+        }
+
+        // Block 2 was reorged away while the node was down.
+        let (_tx, rx) = broadcast::channel(1);
+        let cache = ResponseCache::new(config(&dir), &storage(2, 1), rx).unwrap();
+
+        assert_eq!(cache.get(&kept).await, Some(response));
+        assert_eq!(cache.get(&reorged).await, None);
+    }
+
+    #[test]
+    fn disk_cache_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = Entry {
+            block_number: BlockNumber::GENESIS,
+            block_hash: block_hash!("0x1"),
+            response: Arc::new(json!("a".repeat(1000)).into()),
+        };
+        let entry_size =
+            zstd::encode_all(serde_json::to_vec(&*entry.response).unwrap().as_slice(), 0)
+                .unwrap()
+                .len() as u64;
+
+        let mut cache = DiskCache::open(&dir.path().join("cache.sqlite"), 2 * entry_size).unwrap();
+        cache.insert("first", &entry).unwrap();
+        cache.insert("second", &entry).unwrap();
+        // Using the first entry makes the second one the least recently used.
+        assert!(cache.get("first").unwrap().is_some());
+        cache.insert("third", &entry).unwrap();
+
+        assert!(cache.get("first").unwrap().is_some());
+        assert!(cache.get("second").unwrap().is_none());
+        assert!(cache.get("third").unwrap().is_some());
+        assert_eq!(cache.size, 2 * entry_size);
+    }
+}
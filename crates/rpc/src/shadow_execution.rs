@@ -0,0 +1,260 @@
+//! Shadow execution of requests by a second executor version.
+//!
+//! Builds of pathfinder which link an additional executor, typically one
+//! compiled against an upcoming blockifier release, can register it as a
+//! [ShadowExecutor]. A sampled fraction of `starknet_estimateFee` and
+//! `starknet_simulateTransactions` requests is then executed again by it in
+//! the background, after the regular executor has produced the response. The
+//! results are compared, differences are logged and the outcomes are counted by
+//! the `rpc_shadow_executions_total` metric. Results of the shadow executor are
+//! never served.
+use std::sync::Arc;
+
+use anyhow::Context;
+use pathfinder_common::{BlockHeader, StateUpdate};
+use pathfinder_executor::types::{FeeEstimate, TransactionSimulation};
+use pathfinder_executor::{
+    ExecutionState,
+    L1BlobDataAvailability,
+    Transaction,
+    TransactionExecutionError,
+};
+use rand::Rng;
+use serde_json::Value;
+
+use crate::context::RpcContext;
+use crate::dto::serialize::{SerializeForVersion, Serializer};
+use crate::method::simulate_transactions;
+use crate::pathfinder::methods::diff_transaction_traces::{diff, Difference};
+use crate::v02::types::request::BroadcastedTransaction;
+use crate::RpcVersion;
+
+/// An executor whose results are compared with the regular executor's.
+pub trait ShadowExecutor: Send + Sync {
+    /// Identifies the executor in logs and metrics, e.g. by its blockifier
+    /// version.
+    fn version(&self) -> &'static str;
+
+    /// Counterpart of [pathfinder_executor::simulate].
+    fn simulate(
+        &self,
+        state: ExecutionState<'_>,
+        transactions: Vec<Transaction>,
+        skip_validate: bool,
+        skip_fee_charge: bool,
+    ) -> Result<Vec<TransactionSimulation>, TransactionExecutionError>;
+
+    /// Counterpart of [pathfinder_executor::estimate].
+    fn estimate(
+        &self,
+        state: ExecutionState<'_>,
+        transactions: Vec<Transaction>,
+        skip_validate: bool,
+    ) -> Result<Vec<FeeEstimate>, TransactionExecutionError>;
+}
+
+#[derive(Clone)]
+pub struct ShadowExecution {
+    executor: Arc<dyn ShadowExecutor>,
+    sample_rate: f64,
+}
+
+impl ShadowExecution {
+    /// Shadows the given fraction of requests, between 0 and 1.
+    pub fn new(executor: Arc<dyn ShadowExecutor>, sample_rate: f64) -> Self {
+        Self {
+            executor,
+            sample_rate: sample_rate.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Whether to shadow the current request.
+    pub(crate) fn sample(&self) -> bool {
+        rand::thread_rng().gen_bool(self.sample_rate)
+    }
+
+    /// Executes `request` with the shadow executor in the background and
+    /// compares the results with `served`.
+    pub(crate) fn spawn(&self, context: RpcContext, request: ShadowRequest, served: Served<'_>) {
+        let served = match served.serialize() {
+            Ok(served) => served,
+            Err(error) => {
+                tracing::debug!(%error, "Serializing served results for shadow execution");
+                return;
+            }
+        };
+
+        let executor = self.executor.clone();
+        let span = tracing::debug_span!("shadow_execution", version = executor.version());
+        tokio::task::spawn_blocking(move || {
+            let _g = span.enter();
+            let shadow = request.execute(&context, executor.as_ref());
+            record(executor.version(), &served, shadow);
+        });
+    }
+}
+
+/// The execution to repeat with the shadow executor.
+pub(crate) struct ShadowRequest {
+    pub header: BlockHeader,
+    pub pending: Option<Arc<StateUpdate>>,
+    pub transactions: Vec<BroadcastedTransaction>,
+    pub kind: ShadowRequestKind,
+}
+
+pub(crate) enum ShadowRequestKind {
+    Simulate {
+        skip_validate: bool,
+        skip_fee_charge: bool,
+    },
+    Estimate {
+        skip_validate: bool,
+    },
+}
+
+/// The results served by the regular executor.
+pub(crate) enum Served<'a> {
+    Simulations(&'a [TransactionSimulation]),
+    Estimates(&'a [FeeEstimate]),
+}
+
+impl Served<'_> {
+    fn serialize(&self) -> Result<Vec<Value>, crate::dto::serialize::Error> {
+        let serializer = Serializer::new(RpcVersion::PathfinderV01);
+        match self {
+            Self::Simulations(simulations) => simulations
+                .iter()
+                .map(|simulation| {
+                    simulate_transactions::TransactionSimulation(simulation).serialize(serializer)
+                })
+                .collect(),
+            Self::Estimates(estimates) => estimates
+                .iter()
+                .map(|estimate| crate::dto::FeeEstimate(estimate).serialize(serializer))
+                .collect(),
+        }
+    }
+}
+
+impl ShadowRequest {
+    fn execute(
+        self,
+        context: &RpcContext,
+        executor: &dyn ShadowExecutor,
+    ) -> anyhow::Result<Result<Vec<Value>, TransactionExecutionError>> {
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            self.header,
+            self.pending,
+            L1BlobDataAvailability::Enabled,
+            context.config.custom_versioned_constants.clone(),
+        );
+
+        let transactions = self
+            .transactions
+            .iter()
+            .map(|tx| {
+                crate::executor::map_broadcasted_transaction(
+                    tx,
+                    context.chain_id,
+                    context.casm_cache.as_ref(),
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .context("Mapping transactions")?;
+
+        let results = match self.kind {
+            ShadowRequestKind::Simulate {
+                skip_validate,
+                skip_fee_charge,
+            } => executor
+                .simulate(state, transactions, skip_validate, skip_fee_charge)
+                .map(|simulations| Served::Simulations(&simulations).serialize()),
+            ShadowRequestKind::Estimate { skip_validate } => executor
+                .estimate(state, transactions, skip_validate)
+                .map(|estimates| Served::Estimates(&estimates).serialize()),
+        };
+
+        match results {
+            Ok(serialized) => Ok(Ok(serialized.context("Serializing shadow results")?)),
+            Err(error) => Ok(Err(error)),
+        }
+    }
+}
+
+/// Logs and counts the outcome of a shadow execution.
+fn record(
+    version: &'static str,
+    served: &[Value],
+    shadow: anyhow::Result<Result<Vec<Value>, TransactionExecutionError>>,
+) {
+    let result = match shadow {
+        Err(error) => {
+            tracing::debug!(%error, "Shadow execution failed to run");
+            "error"
+        }
+        Ok(Err(error)) => {
+            tracing::warn!(
+                ?error,
+                "Shadow executor failed where the served execution succeeded"
+            );
+            "mismatch"
+        }
+        Ok(Ok(shadow)) => {
+            let differences = compare(served, &shadow);
+            if differences.is_empty() {
+                "match"
+            } else {
+                tracing::warn!(
+                    differences=%serde_json::to_string(&differences).unwrap_or_default(),
+                    "Shadow execution results differ from the served ones"
+                );
+                "mismatch"
+            }
+        }
+    };
+
+    metrics::increment_counter!("rpc_shadow_executions_total", "version" => version, "result" => result);
+}
+
+/// Returns the differences between the results, with paths prefixed by the
+/// index of the transaction. `base` is the served value and `target` the
+/// shadow one.
+fn compare(served: &[Value], shadow: &[Value]) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    diff(
+        &mut String::new(),
+        &Value::Array(served.to_vec()),
+        &Value::Array(shadow.to_vec()),
+        &mut differences,
+    );
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn compare_reports_differing_transactions() {
+        let served = [json!({"overall_fee": "0x1"}), json!({"overall_fee": "0x2"})];
+
+        assert!(compare(&served, &served).is_empty());
+
+        let shadow = [json!({"overall_fee": "0x1"}), json!({"overall_fee": "0x3"})];
+        let differences = compare(&served, &shadow);
+        assert_eq!(
+            serde_json::to_value(differences).unwrap(),
+            json!([{"path": "/1/overall_fee", "base": "0x2", "target": "0x3"}])
+        );
+    }
+}
@@ -0,0 +1,467 @@
+//! Simulation sessions of websocket connections.
+//!
+//! A session simulates transactions on top of the state of a block, one batch
+//! at a time, with each batch executed on the state left by the batches
+//! applied before it. Snapshots record how many transactions have been
+//! applied, and rolling back to a snapshot discards the transactions applied
+//! after it. Sessions are discarded once closed, or when their connection
+//! closes.
+//!
+//! No execution state is kept between batches. Instead, the transactions
+//! applied earlier are executed again before each batch, which is why a
+//! session is limited to [MAX_TRANSACTIONS] transactions.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use pathfinder_common::{BlockHeader, BlockId, StateUpdate};
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+
+use crate::context::RpcContext;
+use crate::dto::serialize::Serializer;
+use crate::jsonrpc::RpcError;
+use crate::method::simulate_transactions::{
+    SimulateTransactionError as Error,
+    TransactionSimulation,
+};
+use crate::v02::types::request::BroadcastedTransaction;
+use crate::v06::method::simulate_transactions::dto::SimulationFlag;
+use crate::RpcVersion;
+
+const OPEN_METHOD: &str = "pathfinder_openSimulationSession";
+const APPLY_METHOD: &str = "pathfinder_applySessionTransactions";
+const SNAPSHOT_METHOD: &str = "pathfinder_snapshotSimulationSession";
+const ROLLBACK_METHOD: &str = "pathfinder_rollbackSimulationSession";
+const CLOSE_METHOD: &str = "pathfinder_closeSimulationSession";
+
+/// The methods handled by [SimulationSessions::handle].
+pub(crate) const METHODS: &[&str] = &[
+    OPEN_METHOD,
+    APPLY_METHOD,
+    SNAPSHOT_METHOD,
+    ROLLBACK_METHOD,
+    CLOSE_METHOD,
+];
+
+/// Maximum number of open sessions per connection.
+const MAX_SESSIONS: usize = 8;
+
+/// Maximum number of transactions applied in a session.
+const MAX_TRANSACTIONS: usize = 256;
+
+/// The simulation sessions of a websocket connection.
+#[derive(Default)]
+pub(crate) struct SimulationSessions {
+    next_id: u64,
+    sessions: HashMap<u64, Session>,
+}
+
+#[derive(Clone)]
+struct Session {
+    header: BlockHeader,
+    pending: Option<Arc<StateUpdate>>,
+    skip_validate: bool,
+    skip_fee_charge: bool,
+    transactions: Vec<BroadcastedTransaction>,
+    /// The number of transactions applied when each snapshot was taken.
+    snapshots: Vec<usize>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OpenParams {
+    block_id: BlockId,
+    #[serde(default)]
+    simulation_flags: Vec<SimulationFlag>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApplyParams {
+    session_id: u64,
+    transactions: Vec<BroadcastedTransaction>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SessionParams {
+    session_id: u64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RollbackParams {
+    session_id: u64,
+    snapshot_id: usize,
+}
+
+impl SimulationSessions {
+    /// Handles a call of one of the [METHODS].
+    pub(crate) async fn handle(
+        &mut self,
+        context: &RpcContext,
+        version: RpcVersion,
+        method: &str,
+        params: Option<&RawValue>,
+    ) -> Result<serde_json::Value, RpcError> {
+        match method {
+            OPEN_METHOD => {
+                let params = parse::<OpenParams>(params)?;
+                if self.sessions.len() >= MAX_SESSIONS {
+                    return Err(Error::Custom(anyhow::anyhow!(
+                        "Too many simulation sessions, the maximum is {MAX_SESSIONS}"
+                    ))
+                    .into());
+                }
+
+                let session = open(context.clone(), params).await?;
+                let session_id = self.next_id;
+                self.next_id += 1;
+                self.sessions.insert(session_id, session);
+
+                Ok(serde_json::json!({ "session_id": session_id }))
+            }
+            APPLY_METHOD => {
+                let params = parse::<ApplyParams>(params)?;
+                let session = self.session(params.session_id)?;
+                if session.transactions.len() + params.transactions.len() > MAX_TRANSACTIONS {
+                    return Err(Error::Custom(anyhow::anyhow!(
+                        "Too many transactions in the session, the maximum is {MAX_TRANSACTIONS}"
+                    ))
+                    .into());
+                }
+
+                let simulations = apply(
+                    context.clone(),
+                    session.clone(),
+                    params.transactions.clone(),
+                )
+                .await?;
+                session.transactions.extend(params.transactions);
+
+                Serializer::new(version)
+                    .serialize_iter(
+                        simulations.len(),
+                        &mut simulations.iter().map(TransactionSimulation),
+                    )
+                    .map_err(|e| RpcError::InternalError(e.into()))
+            }
+            SNAPSHOT_METHOD => {
+                let params = parse::<SessionParams>(params)?;
+                let session = self.session(params.session_id)?;
+                session.snapshots.push(session.transactions.len());
+
+                Ok(serde_json::json!({ "snapshot_id": session.snapshots.len() - 1 }))
+            }
+            ROLLBACK_METHOD => {
+                let params = parse::<RollbackParams>(params)?;
+                let session = self.session(params.session_id)?;
+                let applied = *session
+                    .snapshots
+                    .get(params.snapshot_id)
+                    .ok_or_else(|| RpcError::InvalidParams("Unknown snapshot".to_string()))?;
+                // Later snapshots refer to transactions which are discarded.
+                session.snapshots.truncate(params.snapshot_id + 1);
+                session.transactions.truncate(applied);
+
+                Ok(true.into())
+            }
+            CLOSE_METHOD => {
+                let params = parse::<SessionParams>(params)?;
+                self.sessions
+                    .remove(&params.session_id)
+                    .ok_or_else(unknown_session)?;
+
+                Ok(true.into())
+            }
+            _ => Err(RpcError::MethodNotFound),
+        }
+    }
+
+    fn session(&mut self, session_id: u64) -> Result<&mut Session, RpcError> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or_else(unknown_session)
+    }
+}
+
+fn unknown_session() -> RpcError {
+    RpcError::InvalidParams("Unknown simulation session".to_string())
+}
+
+fn parse<T: DeserializeOwned>(params: Option<&RawValue>) -> Result<T, RpcError> {
+    let params = params.ok_or_else(|| RpcError::InvalidParams("Missing params".to_string()))?;
+    serde_json::from_str(params.get()).map_err(|e| RpcError::InvalidParams(e.to_string()))
+}
+
+/// Creates a session on top of the state of the block, which is fixed for the
+/// lifetime of the session even if the block is pending.
+async fn open(context: RpcContext, params: OpenParams) -> Result<Session, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match params.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                let header = pending.simulation_header(
+                    &db,
+                    context.config.pending_gas_price_source,
+                    None,
+                )?;
+
+                (header, Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending should fail");
+
+                let header = db
+                    .block_header(block_id)
+                    .context("Fetching block header")?
+                    .ok_or(Error::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(Error::StatePruned {
+                        earliest_available_block,
+                    });
+                }
+
+                (header, None)
+            }
+        };
+
+        Ok(Session {
+            header,
+            pending,
+            skip_validate: params
+                .simulation_flags
+                .contains(&SimulationFlag::SkipValidate),
+            skip_fee_charge: params
+                .simulation_flags
+                .contains(&SimulationFlag::SkipFeeCharge),
+            transactions: vec![],
+            snapshots: vec![],
+        })
+    })
+    .await
+    .context("Opening simulation session")?
+}
+
+/// Simulates `transactions` after the transactions already applied in the
+/// session, returning the simulations of `transactions` only.
+async fn apply(
+    context: RpcContext,
+    session: Session,
+    transactions: Vec<BroadcastedTransaction>,
+) -> Result<Vec<pathfinder_executor::types::TransactionSimulation>, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        crate::version_gate::check_transactions(&transactions, session.header.starknet_version)?;
+
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let state = pathfinder_executor::ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            session.header,
+            session.pending,
+            pathfinder_executor::L1BlobDataAvailability::Enabled,
+            context.config.custom_versioned_constants,
+        );
+
+        let applied = session.transactions.len();
+        let transactions = session
+            .transactions
+            .iter()
+            .chain(&transactions)
+            .map(|tx| {
+                crate::executor::map_broadcasted_transaction(
+                    tx,
+                    context.chain_id,
+                    context.casm_cache.as_ref(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut simulations = pathfinder_executor::simulate(
+            state,
+            transactions,
+            session.skip_validate,
+            session.skip_fee_charge,
+        )
+        .map_err(|e| match Error::from(e) {
+            // Report the index within the applied batch.
+            Error::TransactionExecutionError {
+                transaction_index,
+                error,
+            } => Error::TransactionExecutionError {
+                transaction_index: transaction_index.saturating_sub(applied),
+                error,
+            },
+            other => other,
+        })?;
+
+        Ok(simulations.split_off(applied))
+    })
+    .await
+    .context("Simulating session transactions")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use serde_json::json;
+
+    use super::*;
+
+    async fn call(
+        sessions: &mut SimulationSessions,
+        context: &RpcContext,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, RpcError> {
+        let params = serde_json::value::to_raw_value(&params).unwrap();
+        sessions
+            .handle(context, RpcVersion::PathfinderV01, method, Some(&params))
+            .await
+    }
+
+    #[tokio::test]
+    async fn snapshots_and_rollbacks() {
+        let context = RpcContext::for_tests();
+        let mut sessions = SimulationSessions::default();
+
+        let result = call(
+            &mut sessions,
+            &context,
+            OPEN_METHOD,
+            json!({"block_id": "latest", "simulation_flags": ["SKIP_VALIDATE"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, json!({"session_id": 0}));
+        let session = &sessions.sessions[&0];
+        assert!(session.skip_validate);
+        assert!(!session.skip_fee_charge);
+
+        for expected in 0..3 {
+            let result = call(
+                &mut sessions,
+                &context,
+                SNAPSHOT_METHOD,
+                json!({"session_id": 0}),
+            )
+            .await
+            .unwrap();
+            assert_eq!(result, json!({"snapshot_id": expected}));
+        }
+
+        let result = call(
+            &mut sessions,
+            &context,
+            ROLLBACK_METHOD,
+            json!({"session_id": 0, "snapshot_id": 1}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, json!(true));
+        assert_eq!(sessions.sessions[&0].snapshots.len(), 2);
+
+        // Snapshots taken after the one rolled back to are discarded.
+        let result = call(
+            &mut sessions,
+            &context,
+            ROLLBACK_METHOD,
+            json!({"session_id": 0, "snapshot_id": 2}),
+        )
+        .await;
+        assert_matches!(result, Err(RpcError::InvalidParams(_)));
+
+        let result = call(
+            &mut sessions,
+            &context,
+            CLOSE_METHOD,
+            json!({"session_id": 0}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, json!(true));
+
+        let result = call(
+            &mut sessions,
+            &context,
+            APPLY_METHOD,
+            json!({"session_id": 0, "transactions": []}),
+        )
+        .await;
+        assert_matches!(result, Err(RpcError::InvalidParams(_)));
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let mut sessions = SimulationSessions::default();
+
+        let result = call(
+            &mut sessions,
+            &context,
+            OPEN_METHOD,
+            json!({"block_id": {"block_number": 9999}}),
+        )
+        .await;
+        assert_matches!(
+            result,
+            Err(RpcError::ApplicationError(
+                crate::error::ApplicationError::BlockNotFound
+            ))
+        );
+        assert!(sessions.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sessions_are_limited() {
+        let context = RpcContext::for_tests();
+        let mut sessions = SimulationSessions::default();
+
+        for _ in 0..MAX_SESSIONS {
+            call(
+                &mut sessions,
+                &context,
+                OPEN_METHOD,
+                json!({"block_id": "latest"}),
+            )
+            .await
+            .unwrap();
+        }
+
+        let result = call(
+            &mut sessions,
+            &context,
+            OPEN_METHOD,
+            json!({"block_id": "latest"}),
+        )
+        .await;
+        assert_matches!(result, Err(RpcError::ApplicationError(_)));
+    }
+}
@@ -0,0 +1,188 @@
+//! Resumption of websocket subscriptions after a reconnect.
+//!
+//! Each subscription is issued a resumption token, and the last block it
+//! delivered is recorded under that token. Once the connection drops, a client
+//! reconnecting within the grace period can pass the token to
+//! `pathfinder_resumeSubscription`, which restarts the subscription from the
+//! block after the last delivered one instead of leaving a gap.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pathfinder_common::BlockNumber;
+
+use crate::SubscriptionId;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ResumptionToken(String);
+
+impl ResumptionToken {
+    fn random() -> Self {
+        use rand::Rng;
+
+        let bytes: [u8; 16] = rand::thread_rng().gen();
+        Self(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+}
+
+#[derive(Clone)]
+pub struct SubscriptionSessions(Arc<Inner>);
+
+struct Inner {
+    grace_period: Duration,
+    sessions: Mutex<HashMap<ResumptionToken, Session>>,
+}
+
+struct Session {
+    method: &'static str,
+    params: serde_json::Value,
+    /// The subscription currently delivering notifications for this session.
+    subscription_id: SubscriptionId,
+    last_block: Option<BlockNumber>,
+    /// Set once the connection of the subscription has closed.
+    expires_at: Option<Instant>,
+}
+
+/// A subscription to resume, see [SubscriptionSessions::resume].
+pub(crate) struct Resumed {
+    pub method: &'static str,
+    pub params: serde_json::Value,
+    pub first_block: Option<BlockNumber>,
+}
+
+impl SubscriptionSessions {
+    /// Sessions can be resumed for `grace_period` after their connection
+    /// closes.
+    pub fn new(grace_period: Duration) -> Self {
+        Self(Arc::new(Inner {
+            grace_period,
+            sessions: Default::default(),
+        }))
+    }
+
+    /// Issues a token for a new subscription.
+    pub(crate) fn start(
+        &self,
+        method: &'static str,
+        params: serde_json::Value,
+        subscription_id: SubscriptionId,
+    ) -> ResumptionToken {
+        let now = Instant::now();
+        let mut sessions = self.0.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.expires_at.map_or(true, |at| at > now));
+
+        let token = ResumptionToken::random();
+        sessions.insert(
+            token.clone(),
+            Session {
+                method,
+                params,
+                subscription_id,
+                last_block: None,
+                expires_at: None,
+            },
+        );
+        token
+    }
+
+    /// Hands the session over to `subscription_id`. Returns [None] if the
+    /// token is unknown or has expired.
+    ///
+    /// Sessions whose connection has not been noticed to close yet can be
+    /// resumed as well, since clients usually notice a broken connection
+    /// first.
+    pub(crate) fn resume(
+        &self,
+        token: &ResumptionToken,
+        subscription_id: SubscriptionId,
+    ) -> Option<Resumed> {
+        let mut sessions = self.0.sessions.lock().unwrap();
+        let session = sessions
+            .get_mut(token)
+            .filter(|session| session.expires_at.map_or(true, |at| at > Instant::now()))?;
+
+        session.subscription_id = subscription_id;
+        session.expires_at = None;
+        Some(Resumed {
+            method: session.method,
+            params: session.params.clone(),
+            first_block: session.last_block.map(|block| block + 1),
+        })
+    }
+
+    /// Records that `subscription_id` delivered the notification for
+    /// `block_number`.
+    pub(crate) fn delivered(
+        &self,
+        token: &ResumptionToken,
+        subscription_id: SubscriptionId,
+        block_number: BlockNumber,
+    ) {
+        let mut sessions = self.0.sessions.lock().unwrap();
+        if let Some(session) = sessions.get_mut(token) {
+            // The session might have been taken over by another connection.
+            if session.subscription_id == subscription_id {
+                session.last_block = Some(block_number);
+            }
+        }
+    }
+
+    /// Starts the grace period of the sessions of `subscription_ids`, whose
+    /// connection closed.
+    pub(crate) fn disconnected(&self, subscription_ids: &[SubscriptionId]) {
+        let expires_at = Instant::now() + self.0.grace_period;
+        let mut sessions = self.0.sessions.lock().unwrap();
+        for session in sessions.values_mut() {
+            if subscription_ids.contains(&session.subscription_id) {
+                session.expires_at = Some(expires_at);
+            }
+        }
+    }
+
+    /// Forgets the session of `subscription_id`, which ended.
+    pub(crate) fn ended(&self, subscription_id: SubscriptionId) {
+        let mut sessions = self.0.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.subscription_id != subscription_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn resumes_after_last_delivered_block() {
+        let uut = SubscriptionSessions::new(Duration::from_secs(60));
+        let first = SubscriptionId(1);
+        let second = SubscriptionId(2);
+
+        let token = uut.start("starknet_subscribeNewHeads", json!({}), first);
+        uut.delivered(&token, first, BlockNumber::new_or_panic(5));
+        uut.disconnected(&[first]);
+
+        let resumed = uut.resume(&token, second).unwrap();
+        assert_eq!(resumed.method, "starknet_subscribeNewHeads");
+        assert_eq!(resumed.first_block, Some(BlockNumber::new_or_panic(6)));
+
+        // The previous subscription no longer advances the session.
+        uut.delivered(&token, first, BlockNumber::new_or_panic(7));
+        uut.delivered(&token, second, BlockNumber::new_or_panic(6));
+        let resumed = uut.resume(&token, second).unwrap();
+        assert_eq!(resumed.first_block, Some(BlockNumber::new_or_panic(7)));
+    }
+
+    #[test]
+    fn expired_and_ended_sessions_cannot_be_resumed() {
+        let uut = SubscriptionSessions::new(Duration::ZERO);
+
+        let token = uut.start("starknet_subscribeNewHeads", json!({}), SubscriptionId(1));
+        uut.disconnected(&[SubscriptionId(1)]);
+        assert!(uut.resume(&token, SubscriptionId(2)).is_none());
+
+        let token = uut.start("starknet_subscribeNewHeads", json!({}), SubscriptionId(3));
+        uut.ended(SubscriptionId(3));
+        assert!(uut.resume(&token, SubscriptionId(4)).is_none());
+    }
+}
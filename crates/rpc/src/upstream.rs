@@ -0,0 +1,155 @@
+//! Forwards requests for state which has been pruned locally to an upstream
+//! JSON-RPC node, typically an archive node.
+//!
+//! Only requests which fail with
+//! [StatePruned](crate::error::ApplicationError::StatePruned) are forwarded.
+//! Responses served by the upstream node carry a `"provenance": "upstream"`
+//! member next to their result so that clients can tell them apart from local
+//! data, whatever the type of the result.
+use std::time::Duration;
+
+use anyhow::Context;
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+use crate::RpcVersion;
+
+#[derive(Clone, Debug)]
+pub struct Upstream {
+    client: reqwest::Client,
+    url: reqwest::Url,
+}
+
+impl Upstream {
+    /// `url` is the base url of the upstream node, the versioned path of the
+    /// request (e.g. `rpc/v0_7`) is appended to it.
+    pub fn new(mut url: reqwest::Url, timeout: Duration) -> anyhow::Result<Self> {
+        // Otherwise joining the versioned path replaces the last path segment.
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Creating HTTP client")?;
+
+        Ok(Self { client, url })
+    }
+
+    /// Sends the request to the upstream node and returns its result.
+    ///
+    /// Errors returned by the upstream node are reported as an error, since
+    /// the local error is more useful to the client.
+    pub async fn forward(
+        &self,
+        version: RpcVersion,
+        method: &str,
+        params: Option<&RawValue>,
+    ) -> anyhow::Result<Value> {
+        #[derive(serde::Serialize)]
+        struct Request<'a> {
+            jsonrpc: &'static str,
+            id: u64,
+            method: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            params: Option<&'a RawValue>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Response {
+            result: Option<Value>,
+            error: Option<Value>,
+        }
+
+        let url = self
+            .url
+            .join(path(version))
+            .context("Constructing upstream url")?;
+
+        let response: Response = self
+            .client
+            .post(url)
+            .json(&Request {
+                jsonrpc: "2.0",
+                id: 0,
+                method,
+                params,
+            })
+            .send()
+            .await
+            .context("Sending request")?
+            .error_for_status()
+            .context("Upstream status")?
+            .json()
+            .await
+            .context("Parsing response")?;
+
+        match (response.result, response.error) {
+            (Some(result), None) => Ok(result),
+            (_, Some(error)) => Err(anyhow::anyhow!("Upstream returned an error: {error}")),
+            (None, None) => Err(anyhow::anyhow!("Upstream response has no result")),
+        }
+    }
+}
+
+fn path(version: RpcVersion) -> &'static str {
+    match version {
+        RpcVersion::V06 => "rpc/v0_6",
+        RpcVersion::V07 => "rpc/v0_7",
+        RpcVersion::V08 => "rpc/v0_8",
+        RpcVersion::PathfinderV01 => "rpc/pathfinder/v0_1",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::post;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Serves `response` to every request at `/base/rpc/v0_7`.
+    async fn serve(response: Value) -> reqwest::Url {
+        let router = axum::Router::new().route(
+            "/base/rpc/v0_7",
+            post(move || {
+                let response = response.clone();
+                async move { axum::Json(response) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router.into_make_service()).await });
+        format!("http://{addr}/base").parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn result_is_returned() {
+        let url = serve(json!({"jsonrpc": "2.0", "id": 0, "result": {"value": 1}})).await;
+        let upstream = Upstream::new(url, Duration::from_secs(5)).unwrap();
+
+        let result = upstream
+            .forward(RpcVersion::V07, "pathfinder_getProof", None)
+            .await
+            .unwrap();
+
+        assert_eq!(result, json!({"value": 1}));
+    }
+
+    #[tokio::test]
+    async fn error_is_reported() {
+        let url = serve(json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "error": {"code": 10002, "message": "Requested state has been pruned"}
+        }))
+        .await;
+        let upstream = Upstream::new(url, Duration::from_secs(5)).unwrap();
+
+        upstream
+            .forward(RpcVersion::V07, "pathfinder_getProof", None)
+            .await
+            .unwrap_err();
+    }
+}
@@ -1,10 +1,41 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, ContractAddress};
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress};
 
 use crate::context::RpcContext;
 use crate::v02::types::ContractClass;
 
-crate::error::generate_rpc_error_subset!(GetClassAtError: BlockNotFound, ContractNotFound);
+#[derive(Debug)]
+pub enum GetClassAtError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    ContractNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for GetClassAtError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetClassAtError> for crate::error::ApplicationError {
+    fn from(e: GetClassAtError) -> Self {
+        match e {
+            GetClassAtError::Internal(internal) => Self::Internal(internal),
+            GetClassAtError::Custom(internal) => Self::Custom(internal),
+            GetClassAtError::BlockNotFound => Self::BlockNotFound,
+            GetClassAtError::ContractNotFound => Self::ContractNotFound,
+            GetClassAtError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
@@ -55,6 +86,15 @@ pub async fn get_class_at(
             return Err(GetClassAtError::BlockNotFound);
         }
 
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(GetClassAtError::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         let class_hash = match pending_class_hash {
             Some(class_hash) => class_hash,
             None => tx
@@ -1,10 +1,41 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, ClassHash, ContractAddress};
+use pathfinder_common::{BlockId, BlockNumber, ClassHash, ContractAddress};
 
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
 
-crate::error::generate_rpc_error_subset!(GetClassHashAtError: BlockNotFound, ContractNotFound);
+#[derive(Debug)]
+pub enum GetClassHashAtError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    ContractNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for GetClassHashAtError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetClassHashAtError> for crate::error::ApplicationError {
+    fn from(e: GetClassHashAtError) -> Self {
+        match e {
+            GetClassHashAtError::Internal(internal) => Self::Internal(internal),
+            GetClassHashAtError::Custom(internal) => Self::Custom(internal),
+            GetClassHashAtError::BlockNotFound => Self::BlockNotFound,
+            GetClassHashAtError::ContractNotFound => Self::ContractNotFound,
+            GetClassHashAtError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
@@ -61,6 +92,15 @@ pub async fn get_class_hash_at(
             return Err(GetClassHashAtError::BlockNotFound);
         }
 
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(GetClassHashAtError::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         tx.contract_class_hash(block_id, input.contract_address)
             .context("Fetching class hash from database")?
             .ok_or(GetClassHashAtError::ContractNotFound)
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, ContractAddress, ContractNonce};
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, ContractNonce};
 
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
@@ -21,7 +21,38 @@ impl crate::dto::DeserializeForVersion for GetNonceInput {
 #[derive(serde::Serialize, Debug, PartialEq)]
 pub struct GetNonceOutput(#[serde_as(as = "RpcFelt")] ContractNonce);
 
-crate::error::generate_rpc_error_subset!(GetNonceError: BlockNotFound, ContractNotFound);
+#[derive(Debug)]
+pub enum GetNonceError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    ContractNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for GetNonceError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetNonceError> for crate::error::ApplicationError {
+    fn from(e: GetNonceError) -> Self {
+        match e {
+            GetNonceError::Internal(internal) => Self::Internal(internal),
+            GetNonceError::Custom(internal) => Self::Custom(internal),
+            GetNonceError::BlockNotFound => Self::BlockNotFound,
+            GetNonceError::ContractNotFound => Self::ContractNotFound,
+            GetNonceError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 pub async fn get_nonce(
     context: RpcContext,
@@ -62,6 +93,15 @@ pub async fn get_nonce(
             return Err(GetNonceError::BlockNotFound);
         }
 
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(GetNonceError::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         let nonce = tx
             .contract_nonce(contract_address, block_id)
             .context("Querying contract nonce from database")?;
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, ContractAddress, StorageAddress, StorageValue};
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, StorageAddress, StorageValue};
 use serde::Deserialize;
 
 use crate::context::RpcContext;
@@ -23,7 +23,38 @@ impl crate::dto::DeserializeForVersion for GetStorageAtInput {
 #[derive(serde::Serialize, Debug)]
 pub struct GetStorageOutput(#[serde_as(as = "RpcFelt")] StorageValue);
 
-crate::error::generate_rpc_error_subset!(GetStorageAtError: ContractNotFound, BlockNotFound);
+#[derive(Debug)]
+pub enum GetStorageAtError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    ContractNotFound,
+    BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for GetStorageAtError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetStorageAtError> for crate::error::ApplicationError {
+    fn from(e: GetStorageAtError) -> Self {
+        match e {
+            GetStorageAtError::Internal(internal) => Self::Internal(internal),
+            GetStorageAtError::Custom(internal) => Self::Custom(internal),
+            GetStorageAtError::ContractNotFound => Self::ContractNotFound,
+            GetStorageAtError::BlockNotFound => Self::BlockNotFound,
+            GetStorageAtError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 /// Get the value of the storage at the given address and key.
 pub async fn get_storage_at(
@@ -63,6 +94,15 @@ pub async fn get_storage_at(
             return Err(GetStorageAtError::BlockNotFound);
         }
 
+        if let Some(earliest_available_block) = tx
+            .earliest_available_state_block(block_id)
+            .context("Checking state pruning")?
+        {
+            return Err(GetStorageAtError::StatePruned {
+                earliest_available_block,
+            });
+        }
+
         let value = tx
             .storage_value(block_id, input.contract_address, input.key)
             .context("Querying storage value")?;
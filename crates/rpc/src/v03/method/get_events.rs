@@ -180,6 +180,7 @@ pub async fn get_events(
                 &filter,
                 context.config.get_events_max_blocks_to_scan,
                 context.config.get_events_max_uncached_bloom_filters_to_load,
+                context.config.get_events_max_page_bytes,
             )
             .map_err(|e| match e {
                 EventFilterError::PageSizeTooBig(_) => GetEventsError::PageSizeTooBig,
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockId, BlockNumber};
 
 use crate::RpcContext;
 
@@ -15,7 +15,36 @@ impl crate::dto::DeserializeForVersion for GetStateUpdateInput {
     }
 }
 
-crate::error::generate_rpc_error_subset!(GetStateUpdateError: BlockNotFound);
+#[derive(Debug)]
+pub enum GetStateUpdateError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
+}
+
+impl From<anyhow::Error> for GetStateUpdateError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetStateUpdateError> for crate::error::ApplicationError {
+    fn from(e: GetStateUpdateError) -> Self {
+        match e {
+            GetStateUpdateError::Internal(internal) => Self::Internal(internal),
+            GetStateUpdateError::Custom(internal) => Self::Custom(internal),
+            GetStateUpdateError::BlockNotFound => Self::BlockNotFound,
+            GetStateUpdateError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
+        }
+    }
+}
 
 pub async fn get_state_update(
     context: RpcContext,
@@ -59,6 +88,16 @@ fn get_state_update_from_storage(
     tx: &pathfinder_storage::Transaction<'_>,
     block: pathfinder_storage::BlockId,
 ) -> Result<types::StateUpdate, GetStateUpdateError> {
+    // The state diffs of blocks whose state has been pruned are incomplete.
+    if let Some(earliest_available_block) = tx
+        .earliest_available_state_block(block)
+        .context("Checking state pruning")?
+    {
+        return Err(GetStateUpdateError::StatePruned {
+            earliest_available_block,
+        });
+    }
+
     let state_update = tx
         .state_update(block)
         .context("Fetching state diff")?
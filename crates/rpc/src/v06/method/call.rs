@@ -1,5 +1,12 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, CallParam, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_common::{
+    BlockId,
+    BlockNumber,
+    CallParam,
+    CallResultValue,
+    ContractAddress,
+    EntryPoint,
+};
 use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 
 use crate::context::RpcContext;
@@ -11,8 +18,13 @@ pub enum CallError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     ContractNotFound,
-    ContractError { revert_error: String },
+    ContractError {
+        revert_error: String,
+    },
 }
 
 impl From<anyhow::Error> for CallError {
@@ -50,6 +62,11 @@ impl From<CallError> for ApplicationError {
     fn from(value: CallError) -> Self {
         match value {
             CallError::BlockNotFound => ApplicationError::BlockNotFound,
+            CallError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             CallError::ContractNotFound => ApplicationError::ContractNotFound,
             CallError::ContractError { revert_error } => ApplicationError::ContractError {
                 revert_error: Some(revert_error),
@@ -86,6 +103,13 @@ pub struct FunctionCall {
 pub struct CallOutput(#[serde_as(as = "Vec<RpcFelt>")] pub Vec<CallResultValue>);
 
 pub async fn call(context: RpcContext, input: CallInput) -> Result<CallOutput, CallError> {
+    if let Some(call_stats) = &context.call_stats {
+        call_stats.record(
+            input.request.contract_address,
+            input.request.entry_point_selector,
+        );
+    }
+
     let span = tracing::Span::current();
     let result = tokio::task::spawn_blocking(move || {
         let _g = span.enter();
@@ -111,6 +135,14 @@ pub async fn call(context: RpcContext, input: CallInput) -> Result<CallOutput, C
                     .block_header(block_id)
                     .context("Querying block header")?
                     .ok_or(CallError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(CallError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
@@ -123,7 +155,8 @@ pub async fn call(context: RpcContext, input: CallInput) -> Result<CallOutput, C
             pending,
             L1BlobDataAvailability::Disabled,
             context.config.custom_versioned_constants,
-        );
+        )
+        .with_fork(context.fork.clone());
 
         let result = pathfinder_executor::call(
             state,
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::{BlockId, BlockNumber};
 use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 use serde_with::serde_as;
 
@@ -7,6 +7,7 @@ use crate::context::RpcContext;
 use crate::error::ApplicationError;
 use crate::v02::types::request::BroadcastedTransaction;
 use crate::v06::types::PriceUnit;
+use crate::version_gate::UnsupportedVersion;
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
@@ -36,10 +37,14 @@ pub enum EstimateFeeError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     TransactionExecutionError {
         transaction_index: usize,
         error: String,
     },
+    UnsupportedStarknetVersion(UnsupportedVersion),
 }
 
 impl From<anyhow::Error> for EstimateFeeError {
@@ -48,6 +53,12 @@ impl From<anyhow::Error> for EstimateFeeError {
     }
 }
 
+impl From<UnsupportedVersion> for EstimateFeeError {
+    fn from(e: UnsupportedVersion) -> Self {
+        Self::UnsupportedStarknetVersion(e)
+    }
+}
+
 impl From<pathfinder_executor::TransactionExecutionError> for EstimateFeeError {
     fn from(value: pathfinder_executor::TransactionExecutionError) -> Self {
         use pathfinder_executor::TransactionExecutionError::*;
@@ -79,6 +90,11 @@ impl From<EstimateFeeError> for ApplicationError {
     fn from(value: EstimateFeeError) -> Self {
         match value {
             EstimateFeeError::BlockNotFound => ApplicationError::BlockNotFound,
+            EstimateFeeError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             EstimateFeeError::TransactionExecutionError {
                 transaction_index,
                 error,
@@ -88,6 +104,9 @@ impl From<EstimateFeeError> for ApplicationError {
             },
             EstimateFeeError::Internal(e) => ApplicationError::Internal(e),
             EstimateFeeError::Custom(e) => ApplicationError::Custom(e),
+            EstimateFeeError::UnsupportedStarknetVersion(e) => {
+                ApplicationError::UnsupportedStarknetVersion(e)
+            }
         }
     }
 }
@@ -172,11 +191,21 @@ pub async fn estimate_fee_impl(
                     .block_header(block_id)
                     .context("Querying block header")?
                     .ok_or(EstimateFeeError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(EstimateFeeError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
         };
 
+        crate::version_gate::check_transactions(&input.request, header.starknet_version)?;
+
         let state = ExecutionState::simulation(
             &db,
             context.chain_id,
@@ -184,7 +213,8 @@ pub async fn estimate_fee_impl(
             pending,
             l1_blob_data_availability,
             context.config.custom_versioned_constants,
-        );
+        )
+        .with_fork(context.fork.clone());
 
         let skip_validate = input
             .simulation_flags
@@ -195,7 +225,13 @@ pub async fn estimate_fee_impl(
         let transactions = input
             .request
             .into_iter()
-            .map(|tx| crate::executor::map_broadcasted_transaction(&tx, context.chain_id))
+            .map(|tx| {
+                crate::executor::map_broadcasted_transaction(
+                    &tx,
+                    context.chain_id,
+                    context.casm_cache.as_ref(),
+                )
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
         let result = pathfinder_executor::estimate(state, transactions, skip_validate)?;
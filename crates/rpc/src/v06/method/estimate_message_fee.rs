@@ -3,6 +3,7 @@ use std::sync::Arc;
 use anyhow::Context;
 use pathfinder_common::{
     BlockId,
+    BlockNumber,
     CallParam,
     ChainId,
     ContractAddress,
@@ -22,8 +23,13 @@ use crate::v06::method::estimate_fee::FeeEstimate;
 pub enum EstimateMessageFeeError {
     Internal(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     ContractNotFound,
-    ContractError { revert_error: String },
+    ContractError {
+        revert_error: String,
+    },
     Custom(anyhow::Error),
 }
 
@@ -60,6 +66,11 @@ impl From<EstimateMessageFeeError> for ApplicationError {
     fn from(value: EstimateMessageFeeError) -> Self {
         match value {
             EstimateMessageFeeError::BlockNotFound => ApplicationError::BlockNotFound,
+            EstimateMessageFeeError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             EstimateMessageFeeError::ContractNotFound => ApplicationError::ContractNotFound,
             EstimateMessageFeeError::ContractError { revert_error } => {
                 ApplicationError::ContractError {
@@ -140,6 +151,14 @@ pub(crate) async fn estimate_message_fee_impl(
                     .block_header(block_id)
                     .context("Querying block header")?
                     .ok_or(EstimateMessageFeeError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(EstimateMessageFeeError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::{BlockId, CallParam, EntryPoint};
+use pathfinder_common::{BlockId, BlockNumber, CallParam, EntryPoint};
 use pathfinder_crypto::Felt;
 use pathfinder_executor::types::TransactionSimulation;
 use pathfinder_executor::{L1BlobDataAvailability, TransactionExecutionError};
@@ -9,6 +9,7 @@ use self::dto::SimulatedTransaction;
 use crate::context::RpcContext;
 use crate::executor::ExecutionStateError;
 use crate::v02::types::request::BroadcastedTransaction;
+use crate::version_gate::UnsupportedVersion;
 
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
@@ -32,10 +33,14 @@ pub enum SimulateTransactionError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     TransactionExecutionError {
         transaction_index: usize,
         error: String,
     },
+    UnsupportedStarknetVersion(UnsupportedVersion),
 }
 
 impl From<anyhow::Error> for SimulateTransactionError {
@@ -44,12 +49,26 @@ impl From<anyhow::Error> for SimulateTransactionError {
     }
 }
 
+impl From<UnsupportedVersion> for SimulateTransactionError {
+    fn from(e: UnsupportedVersion) -> Self {
+        Self::UnsupportedStarknetVersion(e)
+    }
+}
+
 impl From<SimulateTransactionError> for crate::error::ApplicationError {
     fn from(e: SimulateTransactionError) -> Self {
         match e {
             SimulateTransactionError::Internal(internal) => Self::Internal(internal),
             SimulateTransactionError::Custom(internal) => Self::Custom(internal),
+            SimulateTransactionError::UnsupportedStarknetVersion(e) => {
+                Self::UnsupportedStarknetVersion(e)
+            }
             SimulateTransactionError::BlockNotFound => Self::BlockNotFound,
+            SimulateTransactionError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
             SimulateTransactionError::TransactionExecutionError {
                 transaction_index,
                 error,
@@ -142,11 +161,21 @@ pub async fn simulate_transactions_impl(
                     .block_header(block_id)
                     .context("Fetching block header")?
                     .ok_or(SimulateTransactionError::BlockNotFound)?;
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(block_id)
+                    .context("Checking state pruning")?
+                {
+                    return Err(SimulateTransactionError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
 
                 (header, None)
             }
         };
 
+        crate::version_gate::check_transactions(&input.transactions, header.starknet_version)?;
+
         let state = pathfinder_executor::ExecutionState::simulation(
             &db,
             context.chain_id,
@@ -154,12 +183,19 @@ pub async fn simulate_transactions_impl(
             pending,
             l1_blob_data_availability,
             context.config.custom_versioned_constants,
-        );
+        )
+        .with_fork(context.fork.clone());
 
         let transactions = input
             .transactions
             .into_iter()
-            .map(|tx| crate::executor::map_broadcasted_transaction(&tx, context.chain_id))
+            .map(|tx| {
+                crate::executor::map_broadcasted_transaction(
+                    &tx,
+                    context.chain_id,
+                    context.casm_cache.as_ref(),
+                )
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
         let txs =
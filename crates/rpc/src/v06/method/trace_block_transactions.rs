@@ -1,6 +1,6 @@
 use anyhow::Context;
 use pathfinder_common::transaction::Transaction;
-use pathfinder_common::{BlockId, TransactionHash};
+use pathfinder_common::{BlockId, BlockNumber, TransactionHash};
 use pathfinder_executor::{ExecutionState, TraceCache, TransactionExecutionError};
 use serde::{Deserialize, Serialize};
 use starknet_gateway_client::GatewayApi;
@@ -49,6 +49,9 @@ pub enum TraceBlockTransactionsError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     BlockNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
 }
 
 impl From<anyhow::Error> for TraceBlockTransactionsError {
@@ -62,6 +65,11 @@ impl From<TraceBlockTransactionsError> for crate::error::ApplicationError {
         match value {
             TraceBlockTransactionsError::Internal(e) => Self::Internal(e),
             TraceBlockTransactionsError::BlockNotFound => Self::BlockNotFound,
+            TraceBlockTransactionsError::StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
             TraceBlockTransactionsError::Custom(e) => Self::Custom(e),
         }
     }
@@ -261,6 +269,18 @@ pub async fn trace_block_transactions_impl(
             }
         }
 
+        // Transactions are traced on the parent block's state.
+        if let Some(parent) = header.number.parent() {
+            if let Some(earliest_available_block) = db
+                .earliest_available_state_block(parent.into())
+                .context("Checking state pruning")?
+            {
+                return Err(TraceBlockTransactionsError::StatePruned {
+                    earliest_available_block,
+                });
+            }
+        }
+
         let executor_transactions = transactions
             .iter()
             .map(|transaction| compose_executor_transaction(transaction, &db))
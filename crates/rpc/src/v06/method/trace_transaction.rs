@@ -1,6 +1,6 @@
 use anyhow::Context;
 use pathfinder_common::transaction::Transaction;
-use pathfinder_common::TransactionHash;
+use pathfinder_common::{BlockNumber, TransactionHash};
 use pathfinder_executor::{ExecutionState, TraceCache, TransactionExecutionError};
 use serde::{Deserialize, Serialize};
 use starknet_gateway_client::GatewayApi;
@@ -35,8 +35,13 @@ pub enum TraceTransactionError {
     Internal(anyhow::Error),
     Custom(anyhow::Error),
     TxnHashNotFound,
+    StatePruned {
+        earliest_available_block: BlockNumber,
+    },
     NoTraceAvailable(TraceError),
-    ContractError { revert_error: String },
+    ContractError {
+        revert_error: String,
+    },
 }
 
 impl From<ExecutionStateError> for TraceTransactionError {
@@ -78,6 +83,11 @@ impl From<super::trace_block_transactions::TraceBlockTransactionsError> for Trac
         match e {
             Internal(e) => Self::Internal(e),
             BlockNotFound => Self::Custom(anyhow::anyhow!("Block not found")),
+            StatePruned {
+                earliest_available_block,
+            } => Self::StatePruned {
+                earliest_available_block,
+            },
             Custom(e) => Self::Custom(e),
         }
     }
@@ -93,6 +103,11 @@ impl From<TraceTransactionError> for ApplicationError {
     fn from(value: TraceTransactionError) -> Self {
         match value {
             TraceTransactionError::TxnHashNotFound => ApplicationError::TxnHashNotFound,
+            TraceTransactionError::StatePruned {
+                earliest_available_block,
+            } => ApplicationError::StatePruned {
+                earliest_available_block,
+            },
             TraceTransactionError::NoTraceAvailable(status) => {
                 ApplicationError::NoTraceAvailable(status)
             }
@@ -196,6 +211,18 @@ pub async fn trace_transaction_impl(
                 (header, transactions.clone(), context.cache.clone())
             };
 
+            // Transactions are traced on the parent block's state.
+            if let Some(parent) = header.number.parent() {
+                if let Some(earliest_available_block) = db
+                    .earliest_available_state_block(parent.into())
+                    .context("Checking state pruning")?
+                {
+                    return Err(TraceTransactionError::StatePruned {
+                        earliest_available_block,
+                    });
+                }
+            }
+
             let hash = header.hash;
             let state = ExecutionState::trace(
                 &db,
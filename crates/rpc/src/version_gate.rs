@@ -0,0 +1,145 @@
+//! Checks that the Starknet version of the block transactions are executed on
+//! supports them.
+//!
+//! Executing a transaction on a block whose Starknet version predates its
+//! features fails deep inside the executor, with an error that does not
+//! mention versions at all. Requests are checked up front instead, and fail
+//! with [ApplicationError::UnsupportedStarknetVersion] naming the block's
+//! version and the first version supporting the feature.
+//!
+//! [ApplicationError::UnsupportedStarknetVersion]: crate::error::ApplicationError::UnsupportedStarknetVersion
+use pathfinder_common::StarknetVersion;
+
+use crate::v02::types::request::{
+    BroadcastedDeclareTransaction,
+    BroadcastedDeployAccountTransaction,
+    BroadcastedInvokeTransaction,
+    BroadcastedTransaction,
+};
+
+/// Declaring Sierra classes, with version 2 declare transactions.
+const SIERRA_CLASSES: StarknetVersion = StarknetVersion::new(0, 11, 0, 0);
+/// Version 3 transactions, which pay their fees in STRK.
+const V3_TRANSACTIONS: StarknetVersion = StarknetVersion::new(0, 13, 0, 0);
+
+/// A feature which is not supported by the Starknet version of a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedVersion {
+    pub feature: &'static str,
+    pub block_version: StarknetVersion,
+    pub minimum_version: StarknetVersion,
+}
+
+/// Checks that `feature`, introduced in `minimum_version`, is supported by a
+/// block of `block_version`.
+fn require(
+    feature: &'static str,
+    minimum_version: StarknetVersion,
+    block_version: StarknetVersion,
+) -> Result<(), UnsupportedVersion> {
+    if block_version < minimum_version {
+        return Err(UnsupportedVersion {
+            feature,
+            block_version,
+            minimum_version,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the transactions of a block of `block_version` can be executed
+/// locally, rather than only have their traces fetched from the gateway.
+pub(crate) fn check_block_execution(
+    block_version: StarknetVersion,
+) -> Result<(), UnsupportedVersion> {
+    require(
+        "executing the block's transactions",
+        crate::executor::VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
+        block_version,
+    )
+}
+
+/// Checks that `transactions` can be executed on a block of `block_version`.
+pub(crate) fn check_transactions<'a>(
+    transactions: impl IntoIterator<Item = &'a BroadcastedTransaction>,
+    block_version: StarknetVersion,
+) -> Result<(), UnsupportedVersion> {
+    for transaction in transactions {
+        match transaction {
+            BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(_)) => {
+                require("declaring Sierra classes", SIERRA_CLASSES, block_version)?
+            }
+            BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(_))
+            | BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(_))
+            | BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V3(_)) => {
+                require("version 3 transactions", V3_TRANSACTIONS, block_version)?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::TransactionVersion;
+
+    use super::*;
+    use crate::v02::types::request::{
+        BroadcastedInvokeTransactionV1,
+        BroadcastedInvokeTransactionV3,
+    };
+
+    fn invoke_v1() -> BroadcastedTransaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                version: TransactionVersion::ONE,
+                max_fee: Default::default(),
+                signature: vec![],
+                nonce: Default::default(),
+                sender_address: Default::default(),
+                calldata: vec![],
+            },
+        ))
+    }
+
+    fn invoke_v3() -> BroadcastedTransaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(
+            BroadcastedInvokeTransactionV3 {
+                version: TransactionVersion::THREE,
+                signature: vec![],
+                nonce: Default::default(),
+                resource_bounds: Default::default(),
+                tip: Default::default(),
+                paymaster_data: vec![],
+                account_deployment_data: vec![],
+                nonce_data_availability_mode: Default::default(),
+                fee_data_availability_mode: Default::default(),
+                sender_address: Default::default(),
+                calldata: vec![],
+            },
+        ))
+    }
+
+    #[test]
+    fn v3_transactions_require_0_13_0() {
+        let transactions = [invoke_v1(), invoke_v3()];
+
+        assert_eq!(
+            check_transactions(&transactions, StarknetVersion::new(0, 12, 3, 0)),
+            Err(UnsupportedVersion {
+                feature: "version 3 transactions",
+                block_version: StarknetVersion::new(0, 12, 3, 0),
+                minimum_version: V3_TRANSACTIONS,
+            })
+        );
+        assert_eq!(
+            check_transactions(&transactions, StarknetVersion::new(0, 13, 0, 0)),
+            Ok(())
+        );
+        assert_eq!(
+            check_transactions(&transactions[..1], StarknetVersion::default()),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Throttles clients whose transaction submissions keep failing, protecting
+//! the gateway quota which all users of a public node share.
+//!
+//! Clients are identified by their [API_KEY_HEADER], or otherwise by their IP
+//! address. Every failed `starknet_add*Transaction` request adds a penalty to
+//! the score of the client which sent it:
+//!
+//! - invalid signatures add [INVALID_SIGNATURE],
+//! - reused nonces, including resubmitted transactions, add [NONCE_REUSE],
+//! - other failed validations add [VALIDATION_FAILURE].
+//!
+//! Scores halve every [WriteProtectionConfig::half_life]. While a client's
+//! score exceeds [WriteProtectionConfig::max_score] its submissions fail with
+//! [ApplicationError::Throttled], without being forwarded to the gateway.
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::ApplicationError;
+use crate::jsonrpc::{RpcError, RpcOutput};
+
+/// The header identifying clients by API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The penalty for a transaction with an invalid signature.
+pub const INVALID_SIGNATURE: f64 = 4.0;
+/// The penalty for a transaction reusing a nonce.
+pub const NONCE_REUSE: f64 = 2.0;
+/// The penalty for a transaction failing any other validation.
+pub const VALIDATION_FAILURE: f64 = 1.0;
+
+/// Methods whose requests are scored and throttled.
+const METHODS: &[&str] = &[
+    "starknet_addDeclareTransaction",
+    "starknet_addDeployAccountTransaction",
+    "starknet_addInvokeTransaction",
+];
+
+tokio::task_local! {
+    static CLIENT: Client;
+}
+
+/// The client sending a request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Client {
+    ApiKey(String),
+    Ip(IpAddr),
+}
+
+impl Client {
+    /// Identifies the client by its API key, falling back to its address.
+    pub(crate) fn identify(headers: &http::HeaderMap, address: Option<SocketAddr>) -> Option<Self> {
+        headers
+            .get(API_KEY_HEADER)
+            .and_then(|key| key.to_str().ok())
+            .map(|key| Self::ApiKey(key.to_owned()))
+            .or_else(|| address.map(|address| Self::Ip(address.ip())))
+    }
+
+    /// Runs `future` on behalf of `client`, which it can then retrieve using
+    /// [Client::current].
+    pub(crate) async fn scope<F: Future>(client: Option<Self>, future: F) -> F::Output {
+        match client {
+            Some(client) => CLIENT.scope(client, future).await,
+            None => future.await,
+        }
+    }
+
+    /// The client on whose behalf the current task runs, if known.
+    pub(crate) fn current() -> Option<Self> {
+        CLIENT.try_with(Clone::clone).ok()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WriteProtectionConfig {
+    /// Submissions are throttled while the client's score exceeds this.
+    pub max_score: f64,
+    /// How long it takes for a score to halve.
+    pub half_life: Duration,
+}
+
+#[derive(Clone)]
+pub struct WriteProtection(Arc<Inner>);
+
+struct Inner {
+    config: WriteProtectionConfig,
+    clients: Mutex<Clients>,
+}
+
+#[derive(Default)]
+struct Clients {
+    scores: HashMap<Client, Score>,
+    pruned: Option<Instant>,
+}
+
+struct Score {
+    value: f64,
+    updated: Instant,
+}
+
+impl Score {
+    fn at(&self, now: Instant, half_life: Duration) -> f64 {
+        let halvings =
+            now.saturating_duration_since(self.updated).as_secs_f64() / half_life.as_secs_f64();
+        self.value * 0.5f64.powf(halvings)
+    }
+}
+
+/// A reason to penalize a client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Offense {
+    InvalidSignature,
+    NonceReuse,
+    ValidationFailure,
+}
+
+impl Offense {
+    fn of(error: &RpcError) -> Option<Self> {
+        let RpcError::ApplicationError(error) = error else {
+            return None;
+        };
+        match error {
+            ApplicationError::ValidationFailureV06(message)
+                if message.to_lowercase().contains("signature") =>
+            {
+                Some(Self::InvalidSignature)
+            }
+            ApplicationError::InvalidTransactionNonce | ApplicationError::DuplicateTransaction => {
+                Some(Self::NonceReuse)
+            }
+            ApplicationError::ValidationFailure
+            | ApplicationError::ValidationFailureV06(_)
+            | ApplicationError::InsufficientMaxFee
+            | ApplicationError::InsufficientAccountBalance => Some(Self::ValidationFailure),
+            _ => None,
+        }
+    }
+
+    fn penalty(self) -> f64 {
+        match self {
+            Self::InvalidSignature => INVALID_SIGNATURE,
+            Self::NonceReuse => NONCE_REUSE,
+            Self::ValidationFailure => VALIDATION_FAILURE,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::InvalidSignature => "invalid_signature",
+            Self::NonceReuse => "nonce_reuse",
+            Self::ValidationFailure => "validation_failure",
+        }
+    }
+}
+
+impl WriteProtection {
+    pub fn new(config: WriteProtectionConfig) -> Self {
+        Self(Arc::new(Inner {
+            config,
+            clients: Default::default(),
+        }))
+    }
+
+    /// Checks whether the current client may call `method`. Returns the client
+    /// if the request should be [scored](WriteProtection::record).
+    pub(crate) fn admit(&self, method: &str) -> Result<Option<Client>, ApplicationError> {
+        if !METHODS.contains(&method) {
+            return Ok(None);
+        }
+        let Some(client) = Client::current() else {
+            return Ok(None);
+        };
+        self.admit_at(&client, Instant::now())?;
+        Ok(Some(client))
+    }
+
+    /// Adds the penalty for `output`, if any, to the score of `client`.
+    pub(crate) fn record(&self, client: Client, output: &Result<RpcOutput, RpcError>) {
+        if let Some(offense) = output.as_ref().err().and_then(Offense::of) {
+            metrics::increment_counter!("rpc_write_offenses_total", "offense" => offense.as_str());
+            self.record_at(client, offense, Instant::now());
+        }
+    }
+
+    fn admit_at(&self, client: &Client, now: Instant) -> Result<(), ApplicationError> {
+        let config = &self.0.config;
+        let clients = self.0.clients.lock().unwrap();
+        let Some(score) = clients.scores.get(client) else {
+            return Ok(());
+        };
+
+        let score = score.at(now, config.half_life);
+        if score <= config.max_score {
+            return Ok(());
+        }
+
+        // The time it takes for the score to decay to the maximum.
+        let wait = config.half_life.as_secs_f64() * (score / config.max_score).log2();
+        Err(ApplicationError::Throttled {
+            retry_after_seconds: (wait.ceil() as u64).max(1),
+        })
+    }
+
+    fn record_at(&self, client: Client, offense: Offense, now: Instant) {
+        let half_life = self.0.config.half_life;
+        let mut clients = self.0.clients.lock().unwrap();
+
+        let score = clients.scores.entry(client).or_insert(Score {
+            value: 0.0,
+            updated: now,
+        });
+        score.value = score.at(now, half_life) + offense.penalty();
+        score.updated = now;
+
+        // Forget clients whose scores have mostly decayed, at most once per
+        // half-life.
+        if clients.pruned.map_or(true, |pruned| {
+            now.saturating_duration_since(pruned) >= half_life
+        }) {
+            clients
+                .scores
+                .retain(|_, score| score.at(now, half_life) >= VALIDATION_FAILURE);
+            clients.pruned = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn protection() -> WriteProtection {
+        WriteProtection::new(WriteProtectionConfig {
+            max_score: 10.0,
+            half_life: Duration::from_secs(60),
+        })
+    }
+
+    #[test]
+    fn offenders_are_throttled_until_their_score_decays() {
+        let protection = protection();
+        let offender = Client::Ip([10, 0, 0, 1].into());
+        let other = Client::ApiKey("key".to_owned());
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            protection.admit_at(&offender, now).unwrap();
+            protection.record_at(offender.clone(), Offense::InvalidSignature, now);
+        }
+
+        assert_matches!(
+            protection.admit_at(&offender, now),
+            Err(ApplicationError::Throttled {
+                retry_after_seconds: 16
+            })
+        );
+        protection.admit_at(&other, now).unwrap();
+        protection
+            .admit_at(&offender, now + Duration::from_secs(16))
+            .unwrap();
+    }
+
+    #[test]
+    fn offenses_are_classified() {
+        let failure = RpcError::ApplicationError;
+
+        assert_eq!(
+            Offense::of(&failure(ApplicationError::ValidationFailureV06(
+                "Invalid Signature".to_owned()
+            ))),
+            Some(Offense::InvalidSignature)
+        );
+        assert_eq!(
+            Offense::of(&failure(ApplicationError::ValidationFailureV06(
+                "Out of gas".to_owned()
+            ))),
+            Some(Offense::ValidationFailure)
+        );
+        assert_eq!(
+            Offense::of(&failure(ApplicationError::DuplicateTransaction)),
+            Some(Offense::NonceReuse)
+        );
+        assert_eq!(
+            Offense::of(&failure(ApplicationError::ClassAlreadyDeclared)),
+            None
+        );
+    }
+}
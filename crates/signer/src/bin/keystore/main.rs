@@ -0,0 +1,122 @@
+#![deny(rust_2018_idioms)]
+
+//! Creates and inspects keystores used by [pathfinder_signer::LocalSigner].
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand};
+use pathfinder_crypto::signature::get_pk;
+use pathfinder_crypto::Felt;
+use pathfinder_signer::Keystore;
+use zeroize::Zeroizing;
+
+#[derive(Parser, Debug)]
+#[command(about = "Create and inspect pathfinder signer keystores")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Creates a new keystore, either from a freshly generated key or by
+    /// importing an existing one.
+    New {
+        /// Where to write the keystore. Existing files are never overwritten.
+        path: PathBuf,
+        /// File containing the hex encoded private key to import. A new key is
+        /// generated if this is not set.
+        #[arg(long, value_name = "FILE")]
+        private_key_file: Option<PathBuf>,
+        #[command(flatten)]
+        password: Password,
+    },
+    /// Prints the public key of a keystore.
+    Inspect {
+        path: PathBuf,
+        /// Also decrypt the keystore to check that the password is correct.
+        #[arg(long, requires = "password_file")]
+        verify: bool,
+        #[command(flatten)]
+        password: OptionalPassword,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Password {
+    /// File containing the keystore password.
+    #[arg(long, value_name = "FILE", env = "KEYSTORE_PASSWORD_FILE")]
+    password_file: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct OptionalPassword {
+    /// File containing the keystore password.
+    #[arg(long, value_name = "FILE", env = "KEYSTORE_PASSWORD_FILE")]
+    password_file: Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::New {
+            path,
+            private_key_file,
+            password,
+        } => {
+            let private_key = match private_key_file {
+                Some(file) => read_private_key(&file)?,
+                None => generate_private_key(),
+            };
+            let password = read_password(&password.password_file)?;
+
+            let keystore = Keystore::encrypt(private_key, password.as_bytes())?;
+            keystore.save(&path)?;
+
+            println!("Created keystore at {}", path.display());
+            println!("Public key: {}", keystore.public_key());
+        }
+        Command::Inspect {
+            path,
+            verify,
+            password,
+        } => {
+            let keystore = Keystore::load(&path)?;
+
+            println!("Public key: {}", keystore.public_key());
+            println!("KDF rounds: {}", keystore.kdf_rounds());
+
+            if verify {
+                let password_file = password.password_file.expect("Required by clap");
+                let password = read_password(&password_file)?;
+                keystore.decrypt(password.as_bytes())?;
+                println!("Password is correct");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn generate_private_key() -> Felt {
+    let rng = &mut rand::thread_rng();
+    loop {
+        let private_key = Felt::random(rng);
+        if get_pk(private_key).is_some() {
+            return private_key;
+        }
+    }
+}
+
+fn read_private_key(path: &Path) -> anyhow::Result<Felt> {
+    let hex = Zeroizing::new(std::fs::read_to_string(path).context("Reading private key file")?);
+    Felt::from_hex_str(hex.trim()).context("Parsing private key")
+}
+
+fn read_password(path: &Path) -> anyhow::Result<Zeroizing<String>> {
+    let password = Zeroizing::new(std::fs::read_to_string(path).context("Reading password file")?);
+    let password = password.trim_end_matches(['\r', '\n']);
+    anyhow::ensure!(!password.is_empty(), "Password must not be empty");
+
+    Ok(Zeroizing::new(password.to_owned()))
+}
@@ -0,0 +1,249 @@
+//! Password protected storage of a Stark private key.
+//!
+//! A keystore is a JSON file of the form
+//!
+//! ```json
+//! {
+//!     "version": 1,
+//!     "public_key": "0x...",
+//!     "crypto": {
+//!         "kdf": "pbkdf2-hmac-sha256",
+//!         "kdf_params": { "rounds": 600000, "salt": "<hex>" },
+//!         "cipher": "chacha20-poly1305",
+//!         "nonce": "<hex>",
+//!         "ciphertext": "<hex>"
+//!     }
+//! }
+//! ```
+//!
+//! The private key is encrypted with a key derived from the password, and the
+//! public key is authenticated as associated data so that it cannot be swapped
+//! without invalidating the keystore.
+use std::path::Path;
+
+use anyhow::Context;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use pathfinder_crypto::signature::get_pk;
+use pathfinder_crypto::Felt;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+/// Number of PBKDF2 rounds used for new keystores.
+pub const DEFAULT_KDF_ROUNDS: u32 = 600_000;
+
+const VERSION: u32 = 1;
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keystore {
+    version: u32,
+    public_key: Felt,
+    crypto: Crypto,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Crypto {
+    kdf: Kdf,
+    kdf_params: KdfParams,
+    cipher: Cipher,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Kdf {
+    #[serde(rename = "pbkdf2-hmac-sha256")]
+    Pbkdf2HmacSha256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KdfParams {
+    rounds: u32,
+    salt: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Cipher {
+    #[serde(rename = "chacha20-poly1305")]
+    ChaCha20Poly1305,
+}
+
+impl Keystore {
+    /// Encrypts `private_key` with `password` using [DEFAULT_KDF_ROUNDS].
+    pub fn encrypt(private_key: Felt, password: &[u8]) -> anyhow::Result<Self> {
+        Self::encrypt_with_rounds(private_key, password, DEFAULT_KDF_ROUNDS)
+    }
+
+    pub fn encrypt_with_rounds(
+        private_key: Felt,
+        password: &[u8],
+        rounds: u32,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(rounds > 0, "KDF rounds must be positive");
+        let public_key = get_pk(private_key).context("Private key is not a valid Stark key")?;
+
+        let mut rng = rand::thread_rng();
+        let mut salt = [0u8; SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+
+        let key = derive_key(password, &salt, rounds);
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: private_key.as_be_bytes(),
+                    aad: public_key.as_be_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Encrypting private key"))?;
+
+        Ok(Self {
+            version: VERSION,
+            public_key,
+            crypto: Crypto {
+                kdf: Kdf::Pbkdf2HmacSha256,
+                kdf_params: KdfParams {
+                    rounds,
+                    salt: hex::encode(salt),
+                },
+                cipher: Cipher::ChaCha20Poly1305,
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            },
+        })
+    }
+
+    /// Decrypts the private key, failing if the password is wrong or the
+    /// keystore has been tampered with.
+    pub fn decrypt(&self, password: &[u8]) -> anyhow::Result<Felt> {
+        let Crypto {
+            kdf: Kdf::Pbkdf2HmacSha256,
+            kdf_params,
+            cipher: Cipher::ChaCha20Poly1305,
+            nonce,
+            ciphertext,
+        } = &self.crypto;
+
+        let salt = hex::decode(&kdf_params.salt).context("Decoding salt")?;
+        let nonce = hex::decode(nonce).context("Decoding nonce")?;
+        anyhow::ensure!(nonce.len() == NONCE_LEN, "Invalid nonce length");
+        let ciphertext = hex::decode(ciphertext).context("Decoding ciphertext")?;
+
+        let key = derive_key(password, &salt, kdf_params.rounds);
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(key.as_slice()))
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: self.public_key.as_be_bytes(),
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| anyhow::anyhow!("Invalid password or corrupted keystore"))?;
+
+        let private_key = Felt::from_be_slice(&plaintext).context("Parsing private key")?;
+        anyhow::ensure!(
+            get_pk(private_key) == Some(self.public_key),
+            "Private key does not match the public key"
+        );
+
+        Ok(private_key)
+    }
+
+    pub fn public_key(&self) -> Felt {
+        self.public_key
+    }
+
+    pub fn kdf_rounds(&self) -> u32 {
+        self.crypto.kdf_params.rounds
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path).context("Opening keystore file")?;
+        let keystore: Self = serde_json::from_reader(std::io::BufReader::new(file))
+            .context("Parsing keystore file")?;
+        anyhow::ensure!(
+            keystore.version == VERSION,
+            "Unsupported keystore version {}",
+            keystore.version
+        );
+
+        Ok(keystore)
+    }
+
+    /// Writes the keystore to a new file at `path`, which is only readable by
+    /// the current user. Existing files are never overwritten.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let file = options.open(path).context("Creating keystore file")?;
+        serde_json::to_writer_pretty(file, self).context("Writing keystore file")?;
+
+        Ok(())
+    }
+}
+
+fn derive_key(password: &[u8], salt: &[u8], rounds: u32) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(password, salt, rounds, key.as_mut_slice());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUNDS: u32 = 16;
+
+    fn private_key() -> Felt {
+        Felt::from_hex_str("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc")
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let keystore = Keystore::encrypt_with_rounds(private_key(), b"password", ROUNDS).unwrap();
+
+        assert_eq!(keystore.public_key(), get_pk(private_key()).unwrap());
+        assert_eq!(keystore.decrypt(b"password").unwrap(), private_key());
+    }
+
+    #[test]
+    fn wrong_password() {
+        let keystore = Keystore::encrypt_with_rounds(private_key(), b"password", ROUNDS).unwrap();
+
+        keystore.decrypt(b"wrong").unwrap_err();
+    }
+
+    #[test]
+    fn swapped_public_key() {
+        let mut keystore =
+            Keystore::encrypt_with_rounds(private_key(), b"password", ROUNDS).unwrap();
+        keystore.public_key = get_pk(Felt::from(1u64)).unwrap();
+
+        keystore.decrypt(b"password").unwrap_err();
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let keystore = Keystore::encrypt_with_rounds(private_key(), b"password", ROUNDS).unwrap();
+
+        keystore.save(&path).unwrap();
+        assert_eq!(Keystore::load(&path).unwrap(), keystore);
+
+        // Existing keystores are not overwritten.
+        keystore.save(&path).unwrap_err();
+    }
+}
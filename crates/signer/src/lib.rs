@@ -0,0 +1,42 @@
+//! Signing backends for node duties which require a Stark key, such as block
+//! attestations.
+//!
+//! Key management is hidden behind the [Signer] trait so that callers do not
+//! need to care whether the key lives in a local [Keystore] file or is held by
+//! a remote signing service.
+#![deny(rust_2018_idioms)]
+
+use pathfinder_crypto::signature::ecdsa_verify_partial;
+use pathfinder_crypto::Felt;
+
+mod keystore;
+mod local;
+mod remote;
+
+pub use keystore::Keystore;
+pub use local::LocalSigner;
+pub use remote::RemoteSigner;
+
+/// An ECDSA signature over the Stark curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Signature {
+    pub r: Felt,
+    pub s: Felt,
+}
+
+impl Signature {
+    /// Checks that this is a valid signature of `hash` by `public_key`.
+    pub fn verify(&self, public_key: Felt, hash: Felt) -> anyhow::Result<()> {
+        ecdsa_verify_partial(public_key, hash, self.r, self.s)?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+pub trait Signer: Send + Sync {
+    /// The public key corresponding to the key used for signing.
+    fn public_key(&self) -> Felt;
+
+    /// Signs the given hash.
+    async fn sign(&self, hash: Felt) -> anyhow::Result<Signature>;
+}
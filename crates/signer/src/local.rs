@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use anyhow::Context;
+use pathfinder_crypto::signature::{ecdsa_sign, get_pk};
+use pathfinder_crypto::Felt;
+
+use crate::{Keystore, Signature, Signer};
+
+/// Signs with a private key held in memory, usually loaded from a [Keystore].
+pub struct LocalSigner {
+    private_key: Felt,
+    public_key: Felt,
+}
+
+impl std::fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LocalSigner {
+    pub fn new(private_key: Felt) -> anyhow::Result<Self> {
+        let public_key = get_pk(private_key).context("Private key is not a valid Stark key")?;
+
+        Ok(Self {
+            private_key,
+            public_key,
+        })
+    }
+
+    /// Loads and decrypts the keystore at `path`.
+    pub fn from_keystore(path: &Path, password: &[u8]) -> anyhow::Result<Self> {
+        let private_key = Keystore::load(path)?
+            .decrypt(password)
+            .context("Decrypting keystore")?;
+
+        Self::new(private_key)
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for LocalSigner {
+    fn public_key(&self) -> Felt {
+        self.public_key
+    }
+
+    async fn sign(&self, hash: Felt) -> anyhow::Result<Signature> {
+        let (r, s) = ecdsa_sign(self.private_key, hash).context("Signing hash")?;
+
+        Ok(Signature { r, s })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sign_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let private_key = Felt::from_hex_str("0x1234").unwrap();
+        Keystore::encrypt_with_rounds(private_key, b"password", 16)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let signer = LocalSigner::from_keystore(&path, b"password").unwrap();
+        let hash = Felt::from_hex_str("0xabcdef").unwrap();
+        let signature = signer.sign(hash).await.unwrap();
+
+        signature.verify(signer.public_key(), hash).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_crypto::Felt;
+
+use crate::{Signature, Signer};
+
+/// Delegates signing to a remote signing service.
+///
+/// Signing requests are sent as a `POST` to the configured URL with a JSON body
+/// of `{ "public_key": "0x...", "hash": "0x..." }`, to which the service is
+/// expected to reply with `{ "r": "0x...", "s": "0x..." }`. Signatures are
+/// verified against the public key before being returned.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    public_key: Felt,
+}
+
+#[derive(serde::Serialize)]
+struct SignRequest {
+    public_key: Felt,
+    hash: Felt,
+}
+
+impl RemoteSigner {
+    pub fn new(url: reqwest::Url, public_key: Felt, timeout: Duration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("Creating HTTP client")?;
+
+        Ok(Self {
+            client,
+            url,
+            public_key,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> Felt {
+        self.public_key
+    }
+
+    async fn sign(&self, hash: Felt) -> anyhow::Result<Signature> {
+        let signature: Signature = self
+            .client
+            .post(self.url.clone())
+            .json(&SignRequest {
+                public_key: self.public_key,
+                hash,
+            })
+            .send()
+            .await
+            .context("Sending signing request")?
+            .error_for_status()
+            .context("Remote signer rejected the request")?
+            .json()
+            .await
+            .context("Parsing remote signer response")?;
+
+        signature
+            .verify(self.public_key, hash)
+            .context("Remote signer returned an invalid signature")?;
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::prelude::*;
+    use pathfinder_crypto::signature::{ecdsa_sign, get_pk};
+    use serde_json::json;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[tokio::test]
+    async fn sign() {
+        let server = MockServer::start_async().await;
+        let private_key = Felt::from_hex_str("0x1234").unwrap();
+        let public_key = get_pk(private_key).unwrap();
+        let hash = Felt::from_hex_str("0xabcdef").unwrap();
+        let (r, s) = ecdsa_sign(private_key, hash).unwrap();
+
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .json_body(json!({ "public_key": public_key, "hash": hash }));
+            then.status(200).json_body(json!({ "r": r, "s": s }));
+        });
+
+        let signer =
+            RemoteSigner::new(server.base_url().parse().unwrap(), public_key, TIMEOUT).unwrap();
+        let signature = signer.sign(hash).await.unwrap();
+
+        mock.assert();
+        assert_eq!(signature, Signature { r, s });
+    }
+
+    #[tokio::test]
+    async fn invalid_signature() {
+        let server = MockServer::start_async().await;
+        let public_key = get_pk(Felt::from_hex_str("0x1234").unwrap()).unwrap();
+        let hash = Felt::from_hex_str("0xabcdef").unwrap();
+        let (r, s) = ecdsa_sign(Felt::from_hex_str("0x5678").unwrap(), hash).unwrap();
+
+        server.mock(|when, then| {
+            when.method(POST);
+            then.status(200).json_body(json!({ "r": r, "s": s }));
+        });
+
+        let signer =
+            RemoteSigner::new(server.base_url().parse().unwrap(), public_key, TIMEOUT).unwrap();
+
+        signer.sign(hash).await.unwrap_err();
+    }
+}
@@ -13,18 +13,45 @@ use crate::ReorgCounter;
 // filter.
 pub const EVENT_KEY_FILTER_LIMIT: usize = 16;
 
-#[derive(Clone)]
-pub(crate) struct BloomFilter(Bloom<Felt>);
+/// The number of consecutive blocks covered by an aggregate Bloom filter.
+///
+/// Aggregate filters let event queries skip whole ranges of blocks whose
+/// events cannot match with a single lookup, instead of one lookup per block.
+pub(crate) const AGGREGATE_BLOCK_RANGE: u64 = 64;
+
+/// The first block of the aggregate Bloom filter range containing `block`.
+pub(crate) fn aggregate_range_start(block: BlockNumber) -> BlockNumber {
+    BlockNumber::new_or_panic(block.get() - block.get() % AGGREGATE_BLOCK_RANGE)
+}
 
-impl BloomFilter {
+/// The sizes of a Bloom filter, which are needed to re-create the filter from
+/// its bitmap.
+struct Params {
     // The size of the bitmap used by the Bloom filter (in bytes).
-    const BITMAP_BYTES: u64 = 2048;
+    bitmap_bytes: u64,
     // The maximal number of items anticipated to be inserted into the Bloom filter.
-    const ITEMS_COUNT: u32 = 1024;
+    items_count: u32,
     // The number of hash functions used by the Bloom filter.
-    // We need this value to be able to re-create the filter with the deserialized
-    // bitmap.
-    const K_NUM: u32 = 12;
+    k_num: u32,
+}
+
+#[derive(Clone)]
+pub(crate) struct BloomFilter(Bloom<Felt>);
+
+impl BloomFilter {
+    /// Filters of the events of a single block.
+    const BLOCK: Params = Params {
+        bitmap_bytes: 2048,
+        items_count: 1024,
+        k_num: 12,
+    };
+    /// Filters of the events of [AGGREGATE_BLOCK_RANGE] blocks. Keeps the bits
+    /// per item of block filters, and so their false positive rate.
+    const AGGREGATE: Params = Params {
+        bitmap_bytes: 128 * 1024,
+        items_count: 64 * 1024,
+        k_num: 12,
+    };
     // The seed used by the hash functions of the filter.
     // This is a randomly generated vector of 32 bytes.
     const SEED: [u8; 32] = [
@@ -34,31 +61,48 @@ impl BloomFilter {
     ];
 
     pub fn new() -> Self {
+        Self::with_params(&Self::BLOCK)
+    }
+
+    /// A filter for the events of [AGGREGATE_BLOCK_RANGE] blocks.
+    pub fn new_aggregate() -> Self {
+        Self::with_params(&Self::AGGREGATE)
+    }
+
+    fn with_params(params: &Params) -> Self {
         let bloom = Bloom::new_with_seed(
-            Self::BITMAP_BYTES as usize,
-            Self::ITEMS_COUNT as usize,
+            params.bitmap_bytes as usize,
+            params.items_count as usize,
             &Self::SEED,
         );
-        assert_eq!(bloom.number_of_hash_functions(), Self::K_NUM);
+        assert_eq!(bloom.number_of_hash_functions(), params.k_num);
 
         Self(bloom)
     }
 
     pub fn from_compressed_bytes(bytes: &[u8]) -> Self {
-        let bytes = zstd::bulk::decompress(bytes, Self::BITMAP_BYTES as usize * 2)
+        Self::decompress(bytes, &Self::BLOCK)
+    }
+
+    pub fn from_compressed_aggregate_bytes(bytes: &[u8]) -> Self {
+        Self::decompress(bytes, &Self::AGGREGATE)
+    }
+
+    fn decompress(bytes: &[u8], params: &Params) -> Self {
+        let bytes = zstd::bulk::decompress(bytes, params.bitmap_bytes as usize * 2)
             .expect("Decompressing Bloom filter");
-        Self::from_bytes(&bytes)
+        Self::from_bytes(&bytes, params)
     }
 
-    fn from_bytes(bytes: &[u8]) -> Self {
+    fn from_bytes(bytes: &[u8], params: &Params) -> Self {
         let k1 = u64::from_le_bytes(Self::SEED[0..8].try_into().unwrap());
         let k2 = u64::from_le_bytes(Self::SEED[8..16].try_into().unwrap());
         let k3 = u64::from_le_bytes(Self::SEED[16..24].try_into().unwrap());
         let k4 = u64::from_le_bytes(Self::SEED[24..32].try_into().unwrap());
         let bloom = Bloom::from_existing(
             bytes,
-            Self::BITMAP_BYTES * 8,
-            Self::K_NUM,
+            params.bitmap_bytes * 8,
+            params.k_num,
             [(k1, k2), (k3, k4)],
         );
         Self(bloom)
@@ -179,4 +223,15 @@ mod tests {
         assert!(bloom.check(&KEY));
         assert!(!bloom.check(&KEY_NOT_IN_FILTER));
     }
+
+    #[test]
+    fn aggregate_serialize_roundtrip() {
+        let mut bloom = BloomFilter::new_aggregate();
+        bloom.set(&KEY);
+
+        let bytes = bloom.to_compressed_bytes();
+        let bloom = BloomFilter::from_compressed_aggregate_bytes(&bytes);
+        assert!(bloom.check(&KEY));
+        assert!(!bloom.check(&KEY_NOT_IN_FILTER));
+    }
 }
@@ -1,16 +1,27 @@
 use std::sync::Arc;
 
+mod analytics;
 mod block;
+mod block_stats;
+mod casm_cache;
 mod class;
+pub(crate) mod class_selector;
 mod ethereum;
 mod event;
+mod l1_message;
 mod reference;
+mod reorg_log;
 mod reorg_counter;
 mod signature;
+mod state_pruning;
 mod state_update;
+mod statistics;
 pub(crate) mod transaction;
 mod trie;
+mod verified_source;
 
+pub use analytics::DailyAnalytics;
+pub use block_stats::{count_transactions_by_type, TransactionTypeCount};
 pub use event::{
     EmittedEvent,
     EventFilter,
@@ -24,9 +35,11 @@ use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::Transaction as StarknetTransaction;
 use pathfinder_common::{BlockNumber, TransactionHash};
 pub(crate) use reorg_counter::ReorgCounter;
+pub use reorg_log::RecordedReorg;
 // Re-export this so users don't require rusqlite as a direct dep.
 pub use rusqlite::TransactionBehavior;
 pub use trie::{Node, NodeRef, RootIndexUpdate, StoredNode, TrieUpdate};
+pub use verified_source::VerifiedSource;
 
 type PooledConnection = r2d2::PooledConnection<r2d2_sqlite::SqliteConnectionManager>;
 
@@ -0,0 +1,381 @@
+//! Daily chain analytics for dashboards.
+//!
+//! Aggregates are materialized into the `analytics_daily` table by
+//! [Transaction::refresh_analytics], which continues from the last block
+//! included by the previous refresh. Reorgs remove the affected days, which
+//! are then recomputed by the next refresh. Days are UTC days of the block
+//! timestamps, counted from the UNIX epoch.
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Context;
+use pathfinder_common::transaction::TransactionKind;
+use pathfinder_common::{BlockNumber, ContractAddress, Fee, TransactionVersion};
+
+use crate::prelude::*;
+use crate::BlockId;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DailyAnalytics {
+    /// Days since the UNIX epoch.
+    pub day: u64,
+    pub first_block: BlockNumber,
+    pub last_block: BlockNumber,
+    pub transactions: u64,
+    /// Distinct accounts which sent transactions.
+    pub active_accounts: u64,
+    /// Fees paid by transactions before version 3.
+    pub fees_wei: Fee,
+    /// Fees paid by version 3 transactions.
+    pub fees_fri: Fee,
+    pub class_declarations: u64,
+}
+
+impl DailyAnalytics {
+    fn new(day: u64, first_block: BlockNumber) -> Self {
+        Self {
+            day,
+            first_block,
+            last_block: first_block,
+            transactions: 0,
+            active_accounts: 0,
+            fees_wei: Fee::ZERO,
+            fees_fri: Fee::ZERO,
+            class_declarations: 0,
+        }
+    }
+}
+
+impl Transaction<'_> {
+    /// The last block included in the analytics, or [None] if they are empty.
+    pub fn analytics_refreshed_to(&self) -> anyhow::Result<Option<BlockNumber>> {
+        self.inner()
+            .query_row(
+                "SELECT analytics_refreshed_to FROM refs WHERE idx = 1",
+                [],
+                |row| row.get_optional_block_number(0),
+            )
+            .map_err(Into::into)
+    }
+
+    fn update_analytics_refreshed_to(&self, block: Option<BlockNumber>) -> anyhow::Result<()> {
+        self.inner().execute(
+            "UPDATE refs SET analytics_refreshed_to = ? WHERE idx = 1",
+            params![&block],
+        )?;
+
+        Ok(())
+    }
+
+    /// Adds up to `max_blocks` blocks following the last refresh to the
+    /// analytics. Returns the last block included.
+    pub fn refresh_analytics(&self, max_blocks: u64) -> anyhow::Result<Option<BlockNumber>> {
+        let refreshed_to = self.analytics_refreshed_to()?;
+        let Some(latest) = self.block_number(BlockId::Latest)? else {
+            return Ok(refreshed_to);
+        };
+        let first = refreshed_to.map_or(BlockNumber::GENESIS, |block| block + 1);
+        if first > latest || max_blocks == 0 {
+            return Ok(refreshed_to);
+        }
+        let last = latest.min(first + (max_blocks - 1));
+
+        let mut days = BTreeMap::<u64, DailyAnalytics>::new();
+        let mut accounts = BTreeSet::<(u64, ContractAddress)>::new();
+
+        for number in first.get()..=last.get() {
+            let block = BlockNumber::new_or_panic(number);
+            let header = self
+                .block_header(block.into())
+                .context("Querying block header")?
+                .context("Block header is missing")?;
+            let transactions = self
+                .transactions_with_receipts_for_block(block.into())
+                .context("Querying transactions")?
+                .unwrap_or_default();
+
+            let day = header.timestamp.get() / SECONDS_PER_DAY;
+            let daily = match days.entry(day) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let existing = self.daily_analytics(day, day)?.pop();
+                    entry.insert(existing.unwrap_or_else(|| DailyAnalytics::new(day, block)))
+                }
+            };
+
+            daily.last_block = block;
+            daily.transactions += transactions.len() as u64;
+            for (transaction, receipt) in transactions {
+                match transaction.version() {
+                    TransactionVersion::ZERO
+                    | TransactionVersion::ONE
+                    | TransactionVersion::TWO => {
+                        daily.fees_wei = Fee(daily.fees_wei.0 + receipt.actual_fee.0)
+                    }
+                    _ => daily.fees_fri = Fee(daily.fees_fri.0 + receipt.actual_fee.0),
+                }
+
+                let kind = transaction.variant.kind();
+                if kind == TransactionKind::Declare {
+                    daily.class_declarations += 1;
+                }
+                // Version 0 transactions were not sent by accounts.
+                if matches!(
+                    kind,
+                    TransactionKind::Declare
+                        | TransactionKind::DeployAccount
+                        | TransactionKind::Invoke
+                ) && transaction.version() != TransactionVersion::ZERO
+                {
+                    accounts.insert((day, transaction.variant.contract_address()));
+                }
+            }
+        }
+
+        let mut insert_account = self
+            .inner()
+            .prepare_cached(
+                "INSERT OR IGNORE INTO analytics_daily_accounts (day, contract_address) VALUES \
+                 (?, ?)",
+            )
+            .context("Preparing insert account statement")?;
+        for (day, account) in accounts {
+            let inserted = insert_account
+                .execute(params![&day.try_into_sql_int()?, &account])
+                .context("Inserting active account")?;
+            if let Some(daily) = days.get_mut(&day) {
+                daily.active_accounts += inserted as u64;
+            }
+        }
+
+        let mut upsert_day = self
+            .inner()
+            .prepare_cached(
+                "INSERT OR REPLACE INTO analytics_daily (day, first_block, last_block, \
+                 transactions, active_accounts, fees_wei, fees_fri, class_declarations) VALUES (?, \
+                 ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .context("Preparing upsert day statement")?;
+        for daily in days.values() {
+            upsert_day
+                .execute(params![
+                    &daily.day.try_into_sql_int()?,
+                    &daily.first_block,
+                    &daily.last_block,
+                    &daily.transactions.try_into_sql_int()?,
+                    &daily.active_accounts.try_into_sql_int()?,
+                    &daily.fees_wei,
+                    &daily.fees_fri,
+                    &daily.class_declarations.try_into_sql_int()?,
+                ])
+                .context("Upserting daily analytics")?;
+        }
+
+        self.update_analytics_refreshed_to(Some(last))?;
+
+        Ok(Some(last))
+    }
+
+    /// Returns the analytics of the days from `from_day` to `to_day`
+    /// inclusive, oldest first. Days without blocks are omitted.
+    pub fn daily_analytics(
+        &self,
+        from_day: u64,
+        to_day: u64,
+    ) -> anyhow::Result<Vec<DailyAnalytics>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                "SELECT day, first_block, last_block, transactions, active_accounts, fees_wei, \
+                 fees_fri, class_declarations FROM analytics_daily WHERE day >= ? AND day <= ? \
+                 ORDER BY day",
+            )
+            .context("Preparing query statement")?;
+
+        let days = stmt
+            .query_map(
+                params![&from_day.try_into_sql_int()?, &to_day.try_into_sql_int()?],
+                |row| {
+                    Ok(DailyAnalytics {
+                        day: row.get_i64(0)? as u64,
+                        first_block: row.get_block_number(1)?,
+                        last_block: row.get_block_number(2)?,
+                        transactions: row.get_i64(3)? as u64,
+                        active_accounts: row.get_i64(4)? as u64,
+                        fees_wei: Fee(row.get_felt(5)?),
+                        fees_fri: Fee(row.get_felt(6)?),
+                        class_declarations: row.get_i64(7)? as u64,
+                    })
+                },
+            )
+            .context("Querying daily analytics")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Iterating over daily analytics")?;
+
+        Ok(days)
+    }
+
+    /// Removes the days including `block` or later blocks from the analytics,
+    /// so that the next refresh recomputes them.
+    pub(super) fn rollback_analytics(&self, block: BlockNumber) -> anyhow::Result<()> {
+        let Some(refreshed_to) = self.analytics_refreshed_to()? else {
+            return Ok(());
+        };
+        if refreshed_to < block {
+            return Ok(());
+        }
+
+        let first_removed = self
+            .inner()
+            .query_row(
+                "SELECT day, first_block FROM analytics_daily WHERE last_block >= ? ORDER BY day \
+                 LIMIT 1",
+                params![&block],
+                |row| Ok((row.get_i64(0)?, row.get_block_number(1)?)),
+            )
+            .optional()
+            .context("Querying first day to remove")?;
+
+        let refreshed_to = match first_removed {
+            Some((day, first_block)) => {
+                self.inner()
+                    .execute("DELETE FROM analytics_daily WHERE day >= ?", params![&day])
+                    .context("Deleting daily analytics")?;
+                self.inner()
+                    .execute(
+                        "DELETE FROM analytics_daily_accounts WHERE day >= ?",
+                        params![&day],
+                    )
+                    .context("Deleting active accounts")?;
+
+                first_block.parent()
+            }
+            None => block.parent(),
+        };
+
+        self.update_analytics_refreshed_to(refreshed_to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+    use pathfinder_common::transaction::*;
+    use pathfinder_common::{BlockHash, BlockHeader, BlockTimestamp, TransactionHash};
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+
+    #[test]
+    fn refresh_and_rollback() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let account = contract_address!("0x1");
+        let other_account = contract_address!("0x2");
+        let transactions = |block: u64| {
+            [
+                TransactionVariant::InvokeV1(InvokeTransactionV1 {
+                    sender_address: account,
+                    ..Default::default()
+                }),
+                TransactionVariant::InvokeV3(InvokeTransactionV3 {
+                    sender_address: other_account,
+                    ..Default::default()
+                }),
+                TransactionVariant::DeclareV2(DeclareTransactionV2 {
+                    sender_address: account,
+                    ..Default::default()
+                }),
+                TransactionVariant::L1Handler(Default::default()),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(i, variant)| {
+                (
+                    StarknetTransaction {
+                        hash: TransactionHash(Felt::from_u64(block * 10 + i as u64)),
+                        variant,
+                    },
+                    Receipt {
+                        actual_fee: Fee(Felt::from_u64(100)),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+        };
+
+        // Blocks 0 and 1 on day 1, block 2 on day 2.
+        for (number, timestamp) in [
+            (0, SECONDS_PER_DAY),
+            (1, SECONDS_PER_DAY + 1),
+            (2, 2 * SECONDS_PER_DAY),
+        ] {
+            let header = BlockHeader {
+                number: BlockNumber::new_or_panic(number),
+                hash: BlockHash(Felt::from_u64(number)),
+                timestamp: BlockTimestamp::new_or_panic(timestamp),
+                ..Default::default()
+            };
+            tx.insert_block_header(&header).unwrap();
+            tx.insert_transaction_data(header.number, &transactions(number), None)
+                .unwrap();
+        }
+
+        // Refresh in two steps, splitting day 1.
+        assert_eq!(tx.refresh_analytics(1).unwrap(), Some(BlockNumber::GENESIS));
+        assert_eq!(
+            tx.refresh_analytics(10).unwrap(),
+            Some(BlockNumber::GENESIS + 2)
+        );
+
+        let day1 = DailyAnalytics {
+            day: 1,
+            first_block: BlockNumber::GENESIS,
+            last_block: BlockNumber::GENESIS + 1,
+            transactions: 8,
+            active_accounts: 2,
+            fees_wei: Fee(Felt::from_u64(600)),
+            fees_fri: Fee(Felt::from_u64(200)),
+            class_declarations: 2,
+        };
+        let day2 = DailyAnalytics {
+            day: 2,
+            first_block: BlockNumber::GENESIS + 2,
+            last_block: BlockNumber::GENESIS + 2,
+            transactions: 4,
+            active_accounts: 2,
+            fees_wei: Fee(Felt::from_u64(300)),
+            fees_fri: Fee(Felt::from_u64(100)),
+            class_declarations: 1,
+        };
+        assert_eq!(
+            tx.daily_analytics(0, 10).unwrap(),
+            vec![day1.clone(), day2.clone()]
+        );
+
+        // Purging block 1 removes both days, which are then recomputed.
+        tx.purge_block(BlockNumber::GENESIS + 2).unwrap();
+        tx.purge_block(BlockNumber::GENESIS + 1).unwrap();
+        assert_eq!(tx.analytics_refreshed_to().unwrap(), None);
+        assert_eq!(tx.daily_analytics(0, 10).unwrap(), vec![]);
+
+        tx.refresh_analytics(10).unwrap();
+        assert_eq!(
+            tx.daily_analytics(0, 10).unwrap(),
+            vec![DailyAnalytics {
+                last_block: BlockNumber::GENESIS,
+                transactions: 4,
+                fees_wei: Fee(Felt::from_u64(300)),
+                fees_fri: Fee(Felt::from_u64(100)),
+                class_declarations: 1,
+                ..day1
+            }]
+        );
+    }
+}
@@ -110,10 +110,59 @@ impl Transaction<'_> {
             .map_err(|x| x.into())
     }
 
+    /// Walks the stored headers from `from` upwards and returns the first
+    /// block which does not follow on from the header before it, either
+    /// because its number is not one more than its predecessor's or because
+    /// its parent hash does not match its predecessor's hash.
+    ///
+    /// The header of `from` is checked against its parent if the parent is
+    /// stored. Returns `None` if the chain is consistent.
+    pub fn first_hash_chain_break(&self, from: BlockNumber) -> anyhow::Result<Option<BlockNumber>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                "SELECT number, hash, parent_hash FROM block_headers WHERE number >= ? ORDER BY \
+                 number ASC",
+            )
+            .context("Preparing hash chain query")?;
+        let start = from.parent().unwrap_or(from);
+        let mut rows = stmt
+            .query(params![&start])
+            .context("Querying block headers")?;
+
+        let mut previous: Option<(BlockNumber, BlockHash)> = None;
+        while let Some(row) = rows.next()? {
+            let number = row.get_block_number(0)?;
+            let hash = row.get_block_hash(1)?;
+            let parent_hash = row.get_block_hash(2)?;
+
+            if let Some((previous_number, previous_hash)) = previous {
+                if number != previous_number + 1 || parent_hash != previous_hash {
+                    return Ok(Some(number));
+                }
+            }
+
+            previous = Some((number, hash));
+        }
+
+        Ok(None)
+    }
+
     /// Removes all data related to this block.
     ///
     /// This includes block header, block body and state update information.
+    /// Tables which reference the block with `ON DELETE CASCADE` are purged by
+    /// deleting the block itself, all others are purged explicitly.
     pub fn purge_block(&self, block: BlockNumber) -> anyhow::Result<()> {
+        // The state before the block is needed to revert it.
+        if let Some(pruned_to) = self.state_pruned_to()? {
+            anyhow::ensure!(
+                block > pruned_to,
+                "Cannot purge block {block}, state updates have been pruned up to block \
+                 {pruned_to}"
+            );
+        }
+
         self.inner()
             .execute(
                 "DELETE FROM starknet_events_filters WHERE block_number = ?",
@@ -121,6 +170,16 @@ impl Transaction<'_> {
             )
             .context("Deleting bloom filter")?;
 
+        // The block's events remain in the aggregate filter, which is harmless
+        // as Bloom filters may have false positives anyway.
+        self.inner()
+            .execute(
+                "UPDATE starknet_events_filters_aggregate SET next_block = ?1 WHERE from_block = ?2 \
+                 AND next_block > ?1",
+                params![&block, &crate::bloom::aggregate_range_start(block)],
+            )
+            .context("Truncating aggregate bloom filter")?;
+
         self.inner()
             .execute(
                 "DELETE FROM transactions WHERE block_number = ?",
@@ -128,6 +187,51 @@ impl Transaction<'_> {
             )
             .context("Deleting transactions")?;
 
+        self.inner()
+            .execute(
+                "DELETE FROM transaction_receipts WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting receipts")?;
+
+        self.inner()
+            .execute(
+                "DELETE FROM transaction_events WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting events")?;
+
+        self.inner()
+            .execute(
+                "DELETE FROM transaction_messages WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting messages")?;
+
+        self.inner()
+            .execute(
+                "DELETE FROM transaction_senders WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting transaction senders")?;
+
+        self.inner()
+            .execute(
+                "DELETE FROM l1_handler_messages WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting L1 handler messages")?;
+
+        self.inner()
+            .execute(
+                "DELETE FROM block_transaction_stats WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting block transaction stats")?;
+
+        self.rollback_analytics(block)
+            .context("Rolling back analytics")?;
+
         self.inner()
             .execute(
                 "DELETE FROM canonical_blocks WHERE number = ?",
@@ -808,6 +912,134 @@ mod tests {
         assert_eq!(class_exists, None);
     }
 
+    #[test]
+    fn purge_block_removes_all_block_data() {
+        use pathfinder_common::event::Event;
+        use pathfinder_common::receipt::{L2ToL1Message, Receipt};
+        use pathfinder_common::transaction::{Transaction, TransactionVariant};
+        use pathfinder_crypto::Felt;
+
+        use crate::RootIndexUpdate;
+
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let contract = contract_address_bytes!(b"contract");
+        for header in &headers {
+            let transaction = Transaction {
+                hash: TransactionHash(Felt::from_u64(header.number.get())),
+                variant: TransactionVariant::InvokeV0(Default::default()),
+            };
+            let receipt = Receipt {
+                transaction_hash: transaction.hash,
+                l2_to_l1_messages: vec![L2ToL1Message {
+                    from_address: contract,
+                    payload: vec![],
+                    to_address: contract_address_bytes!(b"to"),
+                }],
+                ..Default::default()
+            };
+            let events = vec![vec![Event {
+                data: vec![],
+                from_address: contract,
+                keys: vec![],
+            }]];
+            tx.insert_transaction_data(header.number, &[(transaction, receipt)], Some(&events))
+                .unwrap();
+            tx.insert_state_update(
+                header.number,
+                &StateUpdate::default()
+                    .with_contract_nonce(contract, contract_nonce!("0x1"))
+                    .with_storage_update(contract, storage_address!("0x1"), storage_value!("0x1")),
+            )
+            .unwrap();
+            tx.insert_signature(header.number, &Default::default())
+                .unwrap();
+            tx.insert_class_root(header.number, RootIndexUpdate::Updated(1))
+                .unwrap();
+            tx.insert_storage_root(header.number, RootIndexUpdate::Updated(1))
+                .unwrap();
+            tx.insert_contract_root(header.number, contract, RootIndexUpdate::Updated(1))
+                .unwrap();
+            tx.insert_contract_state_hash(
+                header.number,
+                contract,
+                ContractStateHash::ZERO,
+                ClassHash::ZERO,
+                ContractNonce::ZERO,
+            )
+            .unwrap();
+        }
+
+        let latest = headers.last().unwrap();
+        tx.purge_block(latest.number).unwrap();
+
+        let tables = [
+            "block_signatures",
+            "class_roots",
+            "contract_roots",
+            "contract_state_hashes",
+            "nonce_updates",
+            "starknet_events_filters",
+            "storage_roots",
+            "storage_updates",
+            "transaction_events",
+            "transaction_hashes",
+            "transaction_messages",
+            "transaction_receipts",
+            "transaction_senders",
+            "transactions",
+        ];
+        let count = |table: &str, block: BlockNumber| -> i64 {
+            tx.inner()
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {table} WHERE block_number = ?"),
+                    params![&block],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        for table in tables {
+            assert_eq!(count(table, latest.number), 0, "{table} was not purged");
+            assert_eq!(
+                count(table, latest.number - 1),
+                1,
+                "{table} of parent was purged"
+            );
+        }
+    }
+
+    #[test]
+    fn first_hash_chain_break() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let result = tx.first_hash_chain_break(BlockNumber::GENESIS).unwrap();
+        assert_eq!(result, None);
+
+        let mut header3 = headers[2]
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 3 hash"));
+        header3.parent_hash = block_hash_bytes!(b"wrong parent");
+        tx.insert_block_header(&header3).unwrap();
+
+        let result = tx.first_hash_chain_break(BlockNumber::GENESIS).unwrap();
+        assert_eq!(result, Some(header3.number));
+        // The first block checked is compared against its parent.
+        let result = tx.first_hash_chain_break(header3.number).unwrap();
+        assert_eq!(result, Some(header3.number));
+
+        tx.purge_block(header3.number).unwrap();
+        let header4 = headers[2]
+            .child_builder()
+            .number(headers[2].number + 2)
+            .finalize_with_hash(block_hash_bytes!(b"block 4 hash"));
+        tx.insert_block_header(&header4).unwrap();
+
+        let result = tx.first_hash_chain_break(BlockNumber::GENESIS).unwrap();
+        assert_eq!(result, Some(header4.number));
+    }
+
     #[test]
     fn block_id() {
         let (mut connection, headers) = setup();
@@ -0,0 +1,218 @@
+//! Per block transaction statistics.
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use pathfinder_common::transaction::{Transaction as StarknetTransaction, TransactionKind};
+use pathfinder_common::{BlockNumber, TransactionVersion};
+use pathfinder_crypto::Felt;
+
+use crate::prelude::*;
+use crate::BlockId;
+
+/// Number of transactions of a given type and version in a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionTypeCount {
+    pub kind: TransactionKind,
+    pub version: TransactionVersion,
+    pub count: u64,
+}
+
+/// Counts `transactions` by type and version, ordered by type and then
+/// version.
+pub fn count_transactions_by_type<'a>(
+    transactions: impl IntoIterator<Item = &'a StarknetTransaction>,
+) -> Vec<TransactionTypeCount> {
+    let mut counts = BTreeMap::<(&'static str, u64), u64>::new();
+    for transaction in transactions {
+        let key = (
+            kind_to_sql(transaction.variant.kind()),
+            transaction.version().without_query_version() as u64,
+        );
+        *counts.entry(key).or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|((kind, version), count)| TransactionTypeCount {
+            kind: kind_from_sql(kind).expect("Kind was produced by kind_to_sql"),
+            version: TransactionVersion(Felt::from_u64(version)),
+            count,
+        })
+        .collect()
+}
+
+impl Transaction<'_> {
+    pub(super) fn insert_block_transaction_stats(
+        &self,
+        block_number: BlockNumber,
+        transactions: impl IntoIterator<Item = &'_ StarknetTransaction>,
+    ) -> anyhow::Result<()> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                "INSERT INTO block_transaction_stats (block_number, kind, version, count) VALUES \
+                 (?, ?, ?, ?)",
+            )
+            .context("Preparing insert statement")?;
+
+        for stats in count_transactions_by_type(transactions) {
+            stmt.execute(params![
+                &block_number,
+                &kind_to_sql(stats.kind),
+                &(stats.version.without_query_version() as i64),
+                &stats.count.try_into_sql_int()?,
+            ])
+            .context("Inserting block transaction stats")?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of transactions in the block by type and version, or
+    /// [None] if the block does not exist.
+    ///
+    /// Blocks stored before the stats table was introduced are counted from
+    /// their transactions.
+    pub fn transaction_counts_by_type(
+        &self,
+        block: BlockId,
+    ) -> anyhow::Result<Option<Vec<TransactionTypeCount>>> {
+        let Some(block_number) = self.block_number(block)? else {
+            return Ok(None);
+        };
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                "SELECT kind, version, count FROM block_transaction_stats WHERE block_number = ? \
+                 ORDER BY kind, version",
+            )
+            .context("Preparing query statement")?;
+
+        let counts = stmt
+            .query_map(params![&block_number], |row| {
+                let kind: String = row.get(0)?;
+                let version = row.get_i64(1)?;
+                let count = row.get_i64(2)?;
+                Ok((kind, version, count))
+            })
+            .context("Querying block transaction stats")?
+            .map(|row| {
+                let (kind, version, count) = row?;
+                Ok(TransactionTypeCount {
+                    kind: kind_from_sql(&kind)?,
+                    version: TransactionVersion(Felt::from_u64(version as u64)),
+                    count: count as u64,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if !counts.is_empty() {
+            return Ok(Some(counts));
+        }
+
+        let transactions = self
+            .transactions_for_block(block_number.into())
+            .context("Querying transactions")?
+            .unwrap_or_default();
+
+        Ok(Some(count_transactions_by_type(&transactions)))
+    }
+}
+
+fn kind_to_sql(kind: TransactionKind) -> &'static str {
+    match kind {
+        TransactionKind::Declare => "DECLARE",
+        TransactionKind::Deploy => "DEPLOY",
+        TransactionKind::DeployAccount => "DEPLOY_ACCOUNT",
+        TransactionKind::Invoke => "INVOKE",
+        TransactionKind::L1Handler => "L1_HANDLER",
+    }
+}
+
+fn kind_from_sql(kind: &str) -> anyhow::Result<TransactionKind> {
+    Ok(match kind {
+        "DECLARE" => TransactionKind::Declare,
+        "DEPLOY" => TransactionKind::Deploy,
+        "DEPLOY_ACCOUNT" => TransactionKind::DeployAccount,
+        "INVOKE" => TransactionKind::Invoke,
+        "L1_HANDLER" => TransactionKind::L1Handler,
+        other => anyhow::bail!("Unknown transaction kind {other}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+    use pathfinder_common::transaction::*;
+    use pathfinder_common::{BlockHeader, TransactionHash};
+
+    use super::*;
+
+    #[test]
+    fn counts_by_type() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let header = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash_bytes!(b"genesis"));
+        tx.insert_block_header(&header).unwrap();
+
+        let transactions = [
+            TransactionVariant::InvokeV1(Default::default()),
+            TransactionVariant::InvokeV3(Default::default()),
+            TransactionVariant::InvokeV1(Default::default()),
+            TransactionVariant::L1Handler(Default::default()),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, variant)| {
+            (
+                StarknetTransaction {
+                    hash: TransactionHash(Felt::from_u64(i as u64)),
+                    variant,
+                },
+                Receipt::default(),
+            )
+        })
+        .collect::<Vec<_>>();
+        tx.insert_transaction_data(header.number, &transactions, None)
+            .unwrap();
+
+        let expected = vec![
+            TransactionTypeCount {
+                kind: TransactionKind::Invoke,
+                version: TransactionVersion::ONE,
+                count: 2,
+            },
+            TransactionTypeCount {
+                kind: TransactionKind::Invoke,
+                version: TransactionVersion::THREE,
+                count: 1,
+            },
+            TransactionTypeCount {
+                kind: TransactionKind::L1Handler,
+                version: TransactionVersion::ZERO,
+                count: 1,
+            },
+        ];
+
+        let result = tx.transaction_counts_by_type(header.number.into()).unwrap();
+        assert_eq!(result, Some(expected.clone()));
+
+        // Blocks without stats fall back to counting transactions.
+        tx.inner()
+            .execute("DELETE FROM block_transaction_stats", [])
+            .unwrap();
+        let result = tx.transaction_counts_by_type(header.number.into()).unwrap();
+        assert_eq!(result, Some(expected));
+
+        let result = tx
+            .transaction_counts_by_type(BlockNumber::new_or_panic(1).into())
+            .unwrap();
+        assert_eq!(result, None);
+    }
+}
@@ -0,0 +1,95 @@
+//! CASM compiled from Sierra classes which are not part of the chain, such as
+//! those of simulated declare transactions, kept so that they are compiled
+//! only once.
+use anyhow::Context;
+use pathfinder_common::SierraHash;
+
+use crate::prelude::*;
+
+impl Transaction<'_> {
+    /// Returns the uncompressed CASM compiled from the Sierra class
+    /// `sierra_hash` by `compiler_version`.
+    ///
+    /// Entries compiled by other compiler versions are ignored, as their
+    /// output may differ.
+    pub fn cached_casm(
+        &self,
+        sierra_hash: &SierraHash,
+        compiler_version: &str,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let mut stmt = self.inner().prepare_cached(
+            "SELECT definition FROM casm_cache WHERE hash = ? AND compiler_version = ?",
+        )?;
+        let definition = stmt
+            .query_row(params![sierra_hash, &compiler_version], |row| {
+                row.get_blob(0).map(|x| x.to_vec())
+            })
+            .optional()
+            .context("Querying for cached compiled class definition")?;
+
+        let Some(definition) = definition else {
+            return Ok(None);
+        };
+        let definition = zstd::decode_all(definition.as_slice())
+            .context("Decompressing cached compiled class definition")?;
+
+        Ok(Some(definition))
+    }
+
+    /// Stores the uncompressed CASM compiled from the Sierra class
+    /// `sierra_hash` by `compiler_version`, replacing any earlier entry.
+    pub fn insert_cached_casm(
+        &self,
+        sierra_hash: &SierraHash,
+        compiler_version: &str,
+        casm_definition: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
+        let compressed_casm_definition = compressor
+            .compress(casm_definition)
+            .context("Compressing casm definition")?;
+
+        self.inner()
+            .execute(
+                "INSERT OR REPLACE INTO casm_cache (hash, compiler_version, definition) VALUES \
+                 (?, ?, ?)",
+                params![sierra_hash, &compiler_version, &compressed_casm_definition],
+            )
+            .context("Inserting cached casm definition")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn cached_casm_is_keyed_by_compiler_version() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let sierra_hash = sierra_hash!("0x1");
+        assert_eq!(tx.cached_casm(&sierra_hash, "2.0.0").unwrap(), None);
+
+        tx.insert_cached_casm(&sierra_hash, "2.0.0", b"casm")
+            .unwrap();
+        assert_eq!(
+            tx.cached_casm(&sierra_hash, "2.0.0").unwrap(),
+            Some(b"casm".to_vec())
+        );
+        assert_eq!(tx.cached_casm(&sierra_hash, "2.1.0").unwrap(), None);
+
+        tx.insert_cached_casm(&sierra_hash, "2.1.0", b"recompiled")
+            .unwrap();
+        assert_eq!(
+            tx.cached_casm(&sierra_hash, "2.1.0").unwrap(),
+            Some(b"recompiled".to_vec())
+        );
+        assert_eq!(tx.cached_casm(&sierra_hash, "2.0.0").unwrap(), None);
+    }
+}
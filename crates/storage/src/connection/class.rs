@@ -12,8 +12,9 @@ impl Transaction<'_> {
         casm_hash: &CasmHash,
         casm_definition: &[u8],
     ) -> anyhow::Result<()> {
+        let compiler_version = casm_compiler_version(casm_definition);
         let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
-        let sierra_definition = compressor
+        let compressed_sierra_definition = compressor
             .compress(sierra_definition)
             .context("Compressing sierra definition")?;
         let casm_definition = compressor
@@ -23,20 +24,22 @@ impl Transaction<'_> {
         self.inner()
             .execute(
                 r"INSERT OR IGNORE INTO class_definitions (hash,  definition) VALUES (?, ?)",
-                params![sierra_hash, &sierra_definition],
+                params![sierra_hash, &compressed_sierra_definition],
             )
             .context("Inserting sierra definition")?;
+        self.index_class_selectors(ClassHash(sierra_hash.0), sierra_definition)?;
 
         self.inner()
             .execute(
                 r"INSERT OR REPLACE INTO casm_definitions
-                (hash, definition, compiled_class_hash)
+                (hash, definition, compiled_class_hash, compiler_version)
             VALUES
-                (:hash, :definition, :compiled_class_hash)",
+                (:hash, :definition, :compiled_class_hash, :compiler_version)",
                 named_params! {
                     ":hash": sierra_hash,
                     ":definition": &casm_definition,
                     ":compiled_class_hash": casm_hash,
+                    ":compiler_version": &compiler_version,
                 },
             )
             .context("Inserting casm definition")?;
@@ -51,8 +54,9 @@ impl Transaction<'_> {
         casm_hash: &CasmHash,
         casm_definition: &[u8],
     ) -> anyhow::Result<()> {
+        let compiler_version = casm_compiler_version(casm_definition);
         let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
-        let sierra_definition = compressor
+        let compressed_sierra_definition = compressor
             .compress(sierra_definition)
             .context("Compressing sierra definition")?;
         let casm_definition = compressor
@@ -62,16 +66,18 @@ impl Transaction<'_> {
         self.inner()
             .execute(
                 r"UPDATE class_definitions SET definition=? WHERE hash=?",
-                params![&sierra_definition, sierra_hash],
+                params![&compressed_sierra_definition, sierra_hash],
             )
             .context("Updating sierra definition")?;
+        self.index_class_selectors(ClassHash(sierra_hash.0), sierra_definition)?;
 
         self.inner()
             .execute(
-                r"UPDATE casm_definitions SET definition=:definition, compiled_class_hash=:compiled_class_hash WHERE hash=:hash",
+                r"UPDATE casm_definitions SET definition=:definition, compiled_class_hash=:compiled_class_hash, compiler_version=:compiler_version WHERE hash=:hash",
                 named_params! {
                     ":definition": &casm_definition,
                     ":compiled_class_hash": casm_hash,
+                    ":compiler_version": &compiler_version,
                     ":hash": sierra_hash,
                 },
             )
@@ -86,16 +92,17 @@ impl Transaction<'_> {
         definition: &[u8],
     ) -> anyhow::Result<()> {
         let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
-        let definition = compressor
+        let compressed_definition = compressor
             .compress(definition)
             .context("Compressing cairo definition")?;
 
         self.inner()
             .execute(
                 r"INSERT OR IGNORE INTO class_definitions (hash,  definition) VALUES (?, ?)",
-                params![&cairo_hash, &definition],
+                params![&cairo_hash, &compressed_definition],
             )
             .context("Inserting cairo definition")?;
+        self.index_class_selectors(cairo_hash, definition)?;
 
         Ok(())
     }
@@ -106,16 +113,17 @@ impl Transaction<'_> {
         definition: &[u8],
     ) -> anyhow::Result<()> {
         let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
-        let definition = compressor
+        let compressed_definition = compressor
             .compress(definition)
             .context("Compressing cairo definition")?;
 
         self.inner()
             .execute(
                 r"UPDATE class_definitions SET definition=? WHERE hash=?",
-                params![&definition, &cairo_hash],
+                params![&compressed_definition, &cairo_hash],
             )
             .context("Updating cairo definition")?;
+        self.index_class_selectors(cairo_hash, definition)?;
 
         Ok(())
     }
@@ -280,6 +288,25 @@ impl Transaction<'_> {
         Ok(Some(definition))
     }
 
+    /// Returns the version of the compiler which produced the compiled class
+    /// definition.
+    ///
+    /// [None] if the class is unknown, or if its compiled definition does not
+    /// record the compiler version.
+    pub fn casm_compiler_version(&self, class_hash: ClassHash) -> anyhow::Result<Option<String>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached("SELECT compiler_version FROM casm_definitions WHERE hash = ?")?;
+        let compiler_version = stmt
+            .query_row(params![&class_hash], |row| {
+                row.get_optional_str(0).map(|x| x.map(ToOwned::to_owned))
+            })
+            .optional()
+            .context("Querying for compiler version")?;
+
+        Ok(compiler_version.flatten())
+    }
+
     /// Returns the uncompressed compiled class definition, as well as the block
     /// number at which it  was declared.
     pub fn casm_definition_with_block_number(
@@ -517,6 +544,18 @@ impl Transaction<'_> {
     }
 }
 
+/// The `compiler_version` field of a compiled class definition, if any.
+fn casm_compiler_version(casm_definition: &[u8]) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct CompilerVersion {
+        compiler_version: String,
+    }
+
+    serde_json::from_slice::<CompilerVersion>(casm_definition)
+        .ok()
+        .map(|x| x.compiler_version)
+}
+
 #[cfg(test)]
 mod tests {
     use pathfinder_common::macro_prelude::*;
@@ -616,6 +655,52 @@ mod tests {
             .unwrap()
             .unwrap();
         assert_eq!(definition, sierra_definition);
+
+        // The definition does not record a compiler version.
+        assert_eq!(
+            tx.casm_compiler_version(ClassHash(sierra_hash.0)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn compiler_version() {
+        let mut connection = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let sierra_hash = sierra_hash_bytes!(b"sierra hash");
+        let class_hash = ClassHash(sierra_hash.0);
+        let casm_hash = casm_hash_bytes!(b"casm hash");
+        let sierra_definition = b"example sierra program";
+
+        assert_eq!(tx.casm_compiler_version(class_hash).unwrap(), None);
+
+        tx.insert_sierra_class(
+            &sierra_hash,
+            sierra_definition,
+            &casm_hash,
+            br#"{"compiler_version": "2.6.2", "bytecode": []}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tx.casm_compiler_version(class_hash).unwrap().as_deref(),
+            Some("2.6.2")
+        );
+
+        tx.update_sierra_class(
+            &sierra_hash,
+            sierra_definition,
+            &casm_hash,
+            br#"{"compiler_version": "2.7.0", "bytecode": []}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tx.casm_compiler_version(class_hash).unwrap().as_deref(),
+            Some("2.7.0")
+        );
     }
 
     #[test]
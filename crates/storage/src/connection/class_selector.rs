@@ -0,0 +1,157 @@
+//! Reverse index of entry point selectors to the classes exposing them.
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Context;
+use pathfinder_common::{ClassHash, EntryPoint};
+
+use crate::prelude::*;
+
+/// The part of a Cairo or Sierra class definition listing its entry points.
+#[derive(serde::Deserialize)]
+struct EntryPoints {
+    entry_points_by_type: HashMap<String, Vec<SelectorOnly>>,
+}
+
+#[derive(serde::Deserialize)]
+struct SelectorOnly {
+    selector: EntryPoint,
+}
+
+/// Returns the selectors of all entry points in the uncompressed class
+/// `definition`, irrespective of their type.
+pub(crate) fn entry_point_selectors(definition: &[u8]) -> anyhow::Result<BTreeSet<EntryPoint>> {
+    let definition = serde_json::from_slice::<EntryPoints>(definition)
+        .context("Parsing class definition entry points")?;
+
+    Ok(definition
+        .entry_points_by_type
+        .into_values()
+        .flatten()
+        .map(|entry_point| entry_point.selector)
+        .collect())
+}
+
+impl Transaction<'_> {
+    /// Indexes the entry point selectors of the uncompressed class
+    /// `definition`.
+    ///
+    /// Definitions whose entry points cannot be parsed are not indexed, as
+    /// this must not prevent storing them.
+    pub(crate) fn index_class_selectors(
+        &self,
+        class_hash: ClassHash,
+        definition: &[u8],
+    ) -> anyhow::Result<()> {
+        let selectors = match entry_point_selectors(definition) {
+            Ok(selectors) => selectors,
+            Err(error) => {
+                tracing::debug!(%class_hash, %error, "Not indexing class selectors");
+                return Ok(());
+            }
+        };
+
+        let mut stmt = self.inner().prepare_cached(
+            "INSERT OR IGNORE INTO class_selectors (selector, class_hash) VALUES (?, ?)",
+        )?;
+        for selector in selectors {
+            stmt.execute(params![&selector, &class_hash])
+                .context("Inserting class selector")?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the hashes of classes with an entry point of `selector`, in
+    /// ascending order, starting after `after`.
+    pub fn classes_by_selector(
+        &self,
+        selector: EntryPoint,
+        after: Option<ClassHash>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ClassHash>> {
+        let mut stmt = self.inner().prepare_cached(
+            "SELECT class_hash FROM class_selectors WHERE selector = ? AND class_hash > ? ORDER BY \
+             class_hash LIMIT ?",
+        )?;
+
+        let after = after.unwrap_or(ClassHash::ZERO);
+        let class_hashes = stmt
+            .query_map(
+                params![&selector, &after, &limit.try_into_sql_int()?],
+                |row| row.get_class_hash(0),
+            )
+            .context("Querying classes by selector")?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(class_hashes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{CasmHash, SierraHash};
+
+    use super::*;
+
+    fn definition(selectors: &[&str]) -> Vec<u8> {
+        let entry_points = selectors
+            .iter()
+            .enumerate()
+            .map(|(i, selector)| serde_json::json!({ "selector": selector, "function_idx": i }))
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "entry_points_by_type": {
+                "EXTERNAL": entry_points,
+                "L1_HANDLER": [],
+                "CONSTRUCTOR": [{ "selector": "0xc0", "offset": "0x0" }],
+            },
+            "program": {},
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn classes_are_found_by_selector() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let cairo = class_hash!("0x1");
+        let sierra = class_hash!("0x2");
+        let other = class_hash!("0x3");
+        tx.insert_cairo_class(cairo, &definition(&["0xa", "0xb"]))
+            .unwrap();
+        tx.insert_sierra_class(
+            &SierraHash(sierra.0),
+            &definition(&["0xb"]),
+            &CasmHash::ZERO,
+            b"casm",
+        )
+        .unwrap();
+        tx.insert_cairo_class(other, &definition(&["0xa"])).unwrap();
+        tx.insert_cairo_class(class_hash!("0x4"), b"not json")
+            .unwrap();
+
+        let selector = entry_point!("0xb");
+        assert_eq!(
+            tx.classes_by_selector(selector, None, 10).unwrap(),
+            vec![cairo, sierra]
+        );
+        assert_eq!(
+            tx.classes_by_selector(selector, Some(cairo), 10).unwrap(),
+            vec![sierra]
+        );
+        assert_eq!(
+            tx.classes_by_selector(entry_point!("0xa"), None, 1)
+                .unwrap(),
+            vec![cairo]
+        );
+        assert_eq!(
+            tx.classes_by_selector(entry_point!("0xc0"), None, 10)
+                .unwrap(),
+            vec![cairo, sierra, other]
+        );
+    }
+}
@@ -10,7 +10,7 @@ use pathfinder_common::{
     TransactionHash,
 };
 
-use crate::bloom::BloomFilter;
+use crate::bloom::{aggregate_range_start, BloomFilter, AGGREGATE_BLOCK_RANGE};
 use crate::prelude::*;
 use crate::ReorgCounter;
 
@@ -37,6 +37,20 @@ pub struct EmittedEvent {
     pub transaction_hash: TransactionHash,
 }
 
+impl EmittedEvent {
+    /// Approximate size of the event serialized as JSON, with felts written
+    /// out in full.
+    pub fn approximate_size(&self) -> usize {
+        // Quoted 0x-prefixed hex string followed by a comma.
+        const FELT_SIZE: usize = 69;
+        // Field names, block number and punctuation.
+        const OVERHEAD: usize = 128;
+
+        let felts = 3 + self.keys.len() + self.data.len();
+        OVERHEAD + felts * FELT_SIZE
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum EventFilterError {
     #[error(transparent)]
@@ -79,22 +93,81 @@ impl Transaction<'_> {
         )?;
 
         let mut bloom = BloomFilter::new();
+        let mut aggregate = self.aggregate_bloom_for_update(block_number)?;
         for event in events {
             bloom.set_keys(&event.keys);
             bloom.set_address(&event.from_address);
+            if let Some(aggregate) = &mut aggregate {
+                aggregate.set_keys(&event.keys);
+                aggregate.set_address(&event.from_address);
+            }
         }
 
         stmt.execute(params![&block_number, &bloom.to_compressed_bytes()])?;
 
+        if let Some(aggregate) = aggregate {
+            let mut stmt = self.inner().prepare_cached(
+                "INSERT INTO starknet_events_filters_aggregate (from_block, next_block, bloom) \
+                 VALUES (?, ?, ?) ON CONFLICT DO UPDATE SET \
+                 next_block=max(next_block, excluded.next_block), bloom=excluded.bloom",
+            )?;
+            stmt.execute(params![
+                &aggregate_range_start(block_number),
+                &(block_number + 1),
+                &aggregate.to_compressed_bytes()
+            ])?;
+        }
+
         Ok(())
     }
 
+    /// The aggregate Bloom filter to add the events of `block_number` to, or
+    /// [None] if the filter of its range cannot cover the block.
+    ///
+    /// An aggregate filter covers the blocks of its range from the first one
+    /// up to `next_block`. Blocks can only be added in order, as the filter
+    /// would otherwise claim to cover the blocks skipped. Blocks of ranges
+    /// which were partially stored before aggregate filters existed are never
+    /// covered.
+    fn aggregate_bloom_for_update(
+        &self,
+        block_number: BlockNumber,
+    ) -> anyhow::Result<Option<BloomFilter>> {
+        let from_block = aggregate_range_start(block_number);
+
+        let mut stmt = self.inner().prepare_cached(
+            "SELECT next_block, bloom FROM starknet_events_filters_aggregate WHERE from_block = ?",
+        )?;
+        let aggregate = stmt
+            .query_row(params![&from_block], |row| {
+                let next_block = row.get_block_number(0)?;
+                let bytes = row.get_blob(1)?;
+                Ok((
+                    next_block,
+                    BloomFilter::from_compressed_aggregate_bytes(bytes),
+                ))
+            })
+            .optional()?;
+
+        Ok(match aggregate {
+            None if block_number == from_block => Some(BloomFilter::new_aggregate()),
+            Some((next_block, bloom)) if block_number <= next_block => Some(bloom),
+            _ => None,
+        })
+    }
+
+    /// Returns a page of the events matching `filter`.
+    ///
+    /// Pages hold up to `filter.page_size` events, and are cut short once the
+    /// [approximate size](EmittedEvent::approximate_size) of their events
+    /// exceeds `max_page_bytes`. A page always holds at least one event.
     #[tracing::instrument(skip(self))]
     pub fn events(
         &self,
         filter: &EventFilter,
         max_blocks_to_scan: NonZeroUsize,
         max_uncached_bloom_filters_to_load: NonZeroUsize,
+        max_page_bytes: Option<NonZeroUsize>,
     ) -> Result<PageOfEvents, EventFilterError> {
         if filter.page_size > PAGE_SIZE_LIMIT {
             return Err(EventFilterError::PageSizeTooBig(PAGE_SIZE_LIMIT));
@@ -112,9 +185,16 @@ impl Transaction<'_> {
 
         let mut emitted_events = Vec::new();
         let mut bloom_filters_loaded: usize = 0;
+        // Blocks before this one are covered by an aggregate filter which
+        // matched, so need not be checked against it again.
+        let mut aggregate_checked_until = from_block;
         let mut blocks_scanned: usize = 0;
         let mut block_number = from_block;
         let mut offset = filter.offset;
+        let mut page_size = filter.page_size;
+        // Events of the page whose size has been accounted for.
+        let mut sized_events: usize = 0;
+        let mut page_bytes: usize = 0;
 
         enum ScanResult {
             Done,
@@ -130,6 +210,28 @@ impl Transaction<'_> {
 
             // Check bloom filter
             if !key_filter_is_empty || filter.contract_address.is_some() {
+                if block_number >= aggregate_checked_until {
+                    match self.load_aggregate_bloom(block_number)? {
+                        Some(aggregate) => {
+                            bloom_filters_loaded += 1;
+                            if !aggregate.bloom.check_filter(filter) {
+                                tracing::trace!(
+                                    next_block=%aggregate.next_block,
+                                    "Aggregate Bloom filter did not match"
+                                );
+                                block_number = aggregate.next_block;
+                                continue;
+                            }
+                            aggregate_checked_until = aggregate.next_block;
+                        }
+                        // Nothing covers the rest of the range either.
+                        None => {
+                            aggregate_checked_until =
+                                aggregate_range_start(block_number) + AGGREGATE_BLOCK_RANGE
+                        }
+                    }
+                }
+
                 let bloom = self.load_bloom(reorg_counter, block_number)?;
                 match bloom {
                     Filter::Missing => {}
@@ -171,9 +273,21 @@ impl Transaction<'_> {
                 }
             }
 
+            if let Some(max_page_bytes) = max_page_bytes {
+                for event in &emitted_events[sized_events..] {
+                    page_bytes += event.approximate_size();
+                    if page_bytes > max_page_bytes.get() && sized_events > 0 {
+                        tracing::trace!(%sized_events, "Page byte limit reached");
+                        page_size = sized_events;
+                        break;
+                    }
+                    sized_events += 1;
+                }
+            }
+
             // Stop if we have a page of events plus an extra one to decide if we're on the
             // last page.
-            if emitted_events.len() > filter.page_size {
+            if emitted_events.len() > page_size {
                 break ScanResult::PageFull;
             }
 
@@ -194,16 +308,16 @@ impl Transaction<'_> {
                 })
             }
             ScanResult::PageFull => {
-                assert!(emitted_events.len() > filter.page_size);
+                assert!(emitted_events.len() > page_size);
                 let continuation_token = continuation_token(
-                    &emitted_events,
+                    &emitted_events[..=page_size],
                     ContinuationToken {
                         block_number: from_block,
                         offset: filter.offset,
                     },
                 )
                 .unwrap();
-                emitted_events.truncate(filter.page_size);
+                emitted_events.truncate(page_size);
 
                 return Ok(PageOfEvents {
                     events: emitted_events,
@@ -300,6 +414,29 @@ impl Transaction<'_> {
         Ok(BlockScanResult::Done { new_offset: offset })
     }
 
+    /// Loads the aggregate Bloom filter covering `block_number`, if any.
+    fn load_aggregate_bloom(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<AggregateFilter>, EventFilterError> {
+        let mut stmt = self.inner().prepare_cached(
+            "SELECT next_block, bloom FROM starknet_events_filters_aggregate WHERE from_block = ?",
+        )?;
+
+        let aggregate = stmt
+            .query_row(params![&aggregate_range_start(block_number)], |row| {
+                let next_block = row.get_block_number(0)?;
+                let bytes = row.get_blob(1)?;
+                Ok(AggregateFilter {
+                    next_block,
+                    bloom: BloomFilter::from_compressed_aggregate_bytes(bytes),
+                })
+            })
+            .optional()?;
+
+        Ok(aggregate.filter(|aggregate| block_number < aggregate.next_block))
+    }
+
     fn load_bloom(
         &self,
         reorg_counter: ReorgCounter,
@@ -377,6 +514,12 @@ enum Filter {
     Loaded(BloomFilter),
 }
 
+struct AggregateFilter {
+    /// The filter covers the blocks of its range before this one.
+    next_block: BlockNumber,
+    bloom: BloomFilter,
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::LazyLock;
@@ -415,7 +558,12 @@ mod tests {
         };
 
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -426,6 +574,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn aggregate_bloom_covers_consecutive_blocks() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let first = BlockNumber::GENESIS;
+        let last = BlockNumber::new_or_panic(test_utils::NUM_BLOCKS as u64 - 1);
+        let aggregate = tx.load_aggregate_bloom(first).unwrap().unwrap();
+        assert_eq!(aggregate.next_block, last + 1);
+
+        let event = &test_data.events[test_utils::NUM_EVENTS - 1];
+        assert!(aggregate.bloom.check_filter(&EventFilter {
+            from_block: None,
+            to_block: None,
+            contract_address: Some(event.from_address),
+            keys: vec![vec![event.keys[0]]],
+            page_size: 1,
+            offset: 0,
+        }));
+
+        tx.purge_block(last).unwrap();
+        assert!(tx.load_aggregate_bloom(last).unwrap().is_none());
+        let aggregate = tx.load_aggregate_bloom(first).unwrap().unwrap();
+        assert_eq!(aggregate.next_block, last);
+
+        // Blocks after a gap are not covered.
+        assert!(tx.aggregate_bloom_for_update(last + 1).unwrap().is_none());
+        assert!(tx.aggregate_bloom_for_update(last).unwrap().is_some());
+    }
+
     #[test]
     fn events_are_ordered() {
         // This is a regression test where events were incorrectly ordered by
@@ -520,6 +699,7 @@ mod tests {
                 },
                 *MAX_BLOCKS_TO_SCAN,
                 *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
             )
             .unwrap()
             .events
@@ -555,7 +735,12 @@ mod tests {
         let expected_events = &emitted_events[test_utils::EVENTS_PER_BLOCK * BLOCK_NUMBER
             ..test_utils::EVENTS_PER_BLOCK * (BLOCK_NUMBER + 1)];
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -586,7 +771,12 @@ mod tests {
         let expected_events =
             &emitted_events[..test_utils::EVENTS_PER_BLOCK * (UNTIL_BLOCK_NUMBER + 1)];
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -615,7 +805,12 @@ mod tests {
 
         let expected_events = &emitted_events[..test_utils::EVENTS_PER_BLOCK + 1];
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         pretty_assertions_sorted::assert_eq!(
             events,
@@ -641,7 +836,12 @@ mod tests {
         let expected_events =
             &emitted_events[test_utils::EVENTS_PER_BLOCK + 1..test_utils::EVENTS_PER_BLOCK * 2];
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         pretty_assertions_sorted::assert_eq!(
             events,
@@ -671,7 +871,12 @@ mod tests {
 
         let expected_events = &emitted_events[test_utils::EVENTS_PER_BLOCK * FROM_BLOCK_NUMBER..];
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -701,7 +906,12 @@ mod tests {
         };
 
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -730,7 +940,12 @@ mod tests {
         };
 
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -746,7 +961,12 @@ mod tests {
             ..filter
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -774,7 +994,12 @@ mod tests {
         };
 
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -801,7 +1026,12 @@ mod tests {
             offset: 0,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -823,7 +1053,12 @@ mod tests {
             offset: 10,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -845,7 +1080,12 @@ mod tests {
             offset: 30,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -873,7 +1113,12 @@ mod tests {
             offset: test_utils::NUM_BLOCKS * test_utils::EVENTS_PER_BLOCK,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -898,7 +1143,12 @@ mod tests {
             page_size: 0,
             offset: 0,
         };
-        let result = tx.events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD);
+        let result = tx.events(
+            &filter,
+            *MAX_BLOCKS_TO_SCAN,
+            *MAX_BLOOM_FILTERS_TO_LOAD,
+            None,
+        );
         assert!(result.is_err());
         assert_matches!(result.unwrap_err(), EventFilterError::PageSizeTooSmall);
 
@@ -910,7 +1160,12 @@ mod tests {
             page_size: PAGE_SIZE_LIMIT + 1,
             offset: 0,
         };
-        let result = tx.events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD);
+        let result = tx.events(
+            &filter,
+            *MAX_BLOCKS_TO_SCAN,
+            *MAX_BLOOM_FILTERS_TO_LOAD,
+            None,
+        );
         assert!(result.is_err());
         assert_matches!(
             result.unwrap_err(),
@@ -940,7 +1195,12 @@ mod tests {
             offset: 0,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -963,7 +1223,12 @@ mod tests {
             offset: 2,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -986,7 +1251,12 @@ mod tests {
             offset: 2,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -1009,7 +1279,12 @@ mod tests {
             offset: 4,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -1029,7 +1304,12 @@ mod tests {
             offset: 1,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -1040,6 +1320,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn page_byte_limit() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        // Fits three events per page.
+        let max_page_bytes = emitted_events[..3]
+            .iter()
+            .map(EmittedEvent::approximate_size)
+            .sum::<usize>();
+        let max_page_bytes = NonZeroUsize::new(max_page_bytes).unwrap();
+
+        let mut filter = EventFilter {
+            from_block: None,
+            to_block: None,
+            contract_address: None,
+            keys: vec![],
+            page_size: test_utils::NUM_EVENTS,
+            offset: 0,
+        };
+        let mut events = Vec::new();
+        loop {
+            let page = tx
+                .events(
+                    &filter,
+                    *MAX_BLOCKS_TO_SCAN,
+                    *MAX_BLOOM_FILTERS_TO_LOAD,
+                    Some(max_page_bytes),
+                )
+                .unwrap();
+            assert!(page.events.len() <= 3);
+            events.extend(page.events);
+
+            let Some(token) = page.continuation_token else {
+                break;
+            };
+            filter.from_block = Some(token.block_number);
+            filter.offset = token.offset;
+        }
+        assert_eq!(events, emitted_events);
+
+        // Events bigger than the limit are returned one at a time.
+        let filter = EventFilter {
+            from_block: None,
+            offset: 0,
+            ..filter
+        };
+        let page = tx
+            .events(
+                &filter,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                Some(NonZeroUsize::MIN),
+            )
+            .unwrap();
+        assert_eq!(
+            page,
+            PageOfEvents {
+                events: emitted_events[..1].to_vec(),
+                continuation_token: Some(ContinuationToken {
+                    block_number: BlockNumber::GENESIS,
+                    offset: 1
+                }),
+            }
+        );
+    }
+
     #[test]
     fn scan_limit() {
         let (storage, test_data) = test_utils::setup_test_storage();
@@ -1056,7 +1405,12 @@ mod tests {
             offset: 0,
         };
         let events = tx
-            .events(&filter, 1.try_into().unwrap(), *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                1.try_into().unwrap(),
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -1078,7 +1432,12 @@ mod tests {
             offset: 0,
         };
         let events = tx
-            .events(&filter, 1.try_into().unwrap(), *MAX_BLOOM_FILTERS_TO_LOAD)
+            .events(
+                &filter,
+                1.try_into().unwrap(),
+                *MAX_BLOOM_FILTERS_TO_LOAD,
+                None,
+            )
             .unwrap();
         assert_eq!(
             events,
@@ -1108,7 +1467,7 @@ mod tests {
             offset: 0,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap())
+            .events(&filter, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap(), None)
             .unwrap();
         assert_eq!(
             events,
@@ -1130,7 +1489,7 @@ mod tests {
             offset: 0,
         };
         let events = tx
-            .events(&filter, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap())
+            .events(&filter, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap(), None)
             .unwrap();
         assert_eq!(
             events,
@@ -0,0 +1,173 @@
+//! L1 to L2 messages: the L1 transactions which sent them, as seen by the L1
+//! sync, and the L1 handler transactions which consumed them on L2.
+use anyhow::Context;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::transaction::{Transaction as StarknetTransaction, TransactionVariant};
+use pathfinder_common::{BlockNumber, L1ToL2MessageLog, TransactionHash};
+use primitive_types::H256;
+
+use crate::prelude::*;
+
+impl Transaction<'_> {
+    /// Records the L1 transaction which sent a message.
+    ///
+    /// Logs seen again, for example after reconnecting to L1, replace the
+    /// previous entry.
+    pub fn insert_l1_message(&self, message: &L1ToL2MessageLog) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                "INSERT OR REPLACE INTO l1_messages (message_hash, l1_transaction_hash) VALUES (?, \
+                 ?)",
+                params![
+                    &message.message_hash.as_bytes(),
+                    &message.l1_tx_hash.as_bytes()
+                ],
+            )
+            .context("Inserting L1 message")?;
+
+        Ok(())
+    }
+
+    /// Returns the hash of the L1 transaction which sent the message, if it
+    /// has been seen by the L1 sync.
+    pub fn l1_message_transaction(&self, message_hash: H256) -> anyhow::Result<Option<H256>> {
+        self.inner()
+            .query_row(
+                "SELECT l1_transaction_hash FROM l1_messages WHERE message_hash = ?",
+                params![&message_hash.as_bytes()],
+                |row| Ok(H256::from_slice(row.get_blob(0)?)),
+            )
+            .optional()
+            .context("Querying L1 message")
+    }
+
+    /// Returns the L1 handler transactions which consumed the message, along
+    /// with their block, in chain order.
+    pub fn l1_handler_transactions(
+        &self,
+        message_hash: H256,
+    ) -> anyhow::Result<Vec<(BlockNumber, TransactionHash)>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT l1_handler_messages.block_number, hash
+            FROM l1_handler_messages
+            JOIN transaction_hashes
+                ON transaction_hashes.block_number = l1_handler_messages.block_number
+                AND transaction_hashes.idx = l1_handler_messages.transaction_idx
+            WHERE message_hash = ?
+            ORDER BY l1_handler_messages.block_number, l1_handler_messages.transaction_idx
+            ",
+        )?;
+
+        let transactions = stmt
+            .query_map(params![&message_hash.as_bytes()], |row| {
+                Ok((row.get_block_number(0)?, row.get_transaction_hash(1)?))
+            })
+            .context("Querying L1 handler transactions")?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(transactions)
+    }
+
+    /// Indexes the L1 handler transactions of a block by the hash of the
+    /// message they consume.
+    pub(super) fn insert_l1_handler_messages(
+        &self,
+        block_number: BlockNumber,
+        transactions: &[(StarknetTransaction, Receipt)],
+    ) -> anyhow::Result<()> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            INSERT INTO l1_handler_messages (message_hash, block_number, transaction_idx)
+            VALUES (:message_hash, :block_number, :transaction_idx)
+            ",
+        )?;
+
+        for (transaction_idx, (transaction, _)) in transactions.iter().enumerate() {
+            let TransactionVariant::L1Handler(l1_handler) = &transaction.variant else {
+                continue;
+            };
+            stmt.execute(named_params![
+                ":message_hash": &l1_handler.calculate_message_hash().as_bytes(),
+                ":block_number": &block_number,
+                ":transaction_idx": &transaction_idx.try_into_sql_int()?,
+            ])?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::L1HandlerTransaction;
+    use pathfinder_common::BlockHeader;
+
+    use super::*;
+
+    #[test]
+    fn l1_messages() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+
+        let l1_handler = L1HandlerTransaction {
+            contract_address: contract_address_bytes!(b"contract"),
+            calldata: vec![call_param_bytes!(b"from"), call_param_bytes!(b"payload")],
+            ..Default::default()
+        };
+        let message_hash = l1_handler.calculate_message_hash();
+
+        assert_eq!(tx.l1_message_transaction(message_hash).unwrap(), None);
+        assert_eq!(tx.l1_handler_transactions(message_hash).unwrap(), vec![]);
+
+        let l1_tx_hash = H256::from_low_u64_be(1);
+        tx.insert_l1_message(&L1ToL2MessageLog {
+            message_hash,
+            l1_tx_hash,
+        })
+        .unwrap();
+        assert_eq!(
+            tx.l1_message_transaction(message_hash).unwrap(),
+            Some(l1_tx_hash)
+        );
+
+        let header = BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"genesis"));
+        tx.insert_block_header(&header).unwrap();
+        let transactions = [
+            StarknetTransaction {
+                hash: transaction_hash_bytes!(b"invoke"),
+                variant: TransactionVariant::InvokeV0(Default::default()),
+            },
+            StarknetTransaction {
+                hash: transaction_hash_bytes!(b"l1 handler"),
+                variant: TransactionVariant::L1Handler(l1_handler),
+            },
+        ]
+        .map(|transaction| {
+            let receipt = Receipt {
+                transaction_hash: transaction.hash,
+                ..Default::default()
+            };
+            (transaction, receipt)
+        });
+        tx.insert_transaction_data(header.number, &transactions, None)
+            .unwrap();
+
+        assert_eq!(
+            tx.l1_handler_transactions(message_hash).unwrap(),
+            vec![(header.number, transaction_hash_bytes!(b"l1 handler"))]
+        );
+
+        tx.purge_block(header.number).unwrap();
+        assert_eq!(tx.l1_handler_transactions(message_hash).unwrap(), vec![]);
+        // Messages sent on L1 are not affected by L2 reorgs.
+        assert_eq!(
+            tx.l1_message_transaction(message_hash).unwrap(),
+            Some(l1_tx_hash)
+        );
+    }
+}
@@ -20,6 +20,27 @@ impl Transaction<'_> {
             })
             .map_err(|e| e.into())
     }
+
+    /// Records the last Ethereum block which has been scanned for Starknet
+    /// state update logs.
+    pub fn update_l1_scanned_to(&self, eth_block: u64) -> anyhow::Result<()> {
+        self.inner().execute(
+            "UPDATE refs SET l1_scanned_to = ? WHERE idx = 1",
+            params![&eth_block.try_into_sql_int()?],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn l1_scanned_to(&self) -> anyhow::Result<Option<u64>> {
+        let eth_block =
+            self.inner()
+                .query_row("SELECT l1_scanned_to FROM refs WHERE idx = 1", [], |row| {
+                    row.get_optional_i64(0)
+                })?;
+
+        Ok(eth_block.map(|eth_block| eth_block as u64))
+    }
 }
 
 #[cfg(test)]
@@ -56,4 +77,16 @@ mod tests {
         let result = tx.l1_l2_pointer().unwrap();
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn l1_scanned_to() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        assert_eq!(tx.l1_scanned_to().unwrap(), None);
+
+        tx.update_l1_scanned_to(20_000_000).unwrap();
+        assert_eq!(tx.l1_scanned_to().unwrap(), Some(20_000_000));
+    }
 }
@@ -0,0 +1,179 @@
+//! A log of the L2 reorgs detected by sync, kept so that downstream indexers
+//! can reconcile their data after the fact.
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber};
+
+use crate::prelude::*;
+use crate::BlockId;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedReorg {
+    /// Increases with every reorg.
+    pub id: u64,
+    /// UNIX timestamp of when the reorg was detected.
+    pub detected_at: u64,
+    /// The latest block before the reorg.
+    pub old_head_number: BlockNumber,
+    pub old_head_hash: BlockHash,
+    /// The latest block kept, or [None] if genesis was reorged away as well.
+    pub new_head_number: Option<BlockNumber>,
+    pub new_head_hash: Option<BlockHash>,
+    /// The number of blocks reorged away.
+    pub depth: u64,
+}
+
+impl Transaction<'_> {
+    /// Logs the reorg of the blocks from `first_block` up to `old_head`.
+    ///
+    /// Must be called before these blocks are purged, as their hashes are
+    /// read from the database.
+    pub fn record_reorg(
+        &self,
+        detected_at: u64,
+        first_block: BlockNumber,
+        old_head: BlockNumber,
+    ) -> anyhow::Result<RecordedReorg> {
+        let old_head_hash = self
+            .block_hash(BlockId::Number(old_head))?
+            .context("Old head is missing")?;
+        let new_head_number = first_block.parent();
+        let new_head_hash = match new_head_number {
+            Some(number) => Some(
+                self.block_hash(BlockId::Number(number))?
+                    .context("New head is missing")?,
+            ),
+            None => None,
+        };
+        let depth = old_head.get() + 1 - first_block.get();
+
+        self.inner()
+            .execute(
+                r"INSERT INTO reorg_log
+                (detected_at, old_head_number, old_head_hash, new_head_number, new_head_hash,
+                 depth)
+            VALUES
+                (:detected_at, :old_head_number, :old_head_hash, :new_head_number,
+                 :new_head_hash, :depth)",
+                named_params! {
+                    ":detected_at": &detected_at.try_into_sql_int()?,
+                    ":old_head_number": &old_head,
+                    ":old_head_hash": &old_head_hash,
+                    ":new_head_number": &new_head_number,
+                    ":new_head_hash": &new_head_hash,
+                    ":depth": &depth.try_into_sql_int()?,
+                },
+            )
+            .context("Inserting reorg")?;
+
+        Ok(RecordedReorg {
+            id: self.inner().last_insert_rowid().try_into()?,
+            detected_at,
+            old_head_number: old_head,
+            old_head_hash,
+            new_head_number,
+            new_head_hash,
+            depth,
+        })
+    }
+
+    /// The id of the latest reorg, or [None] if there were none.
+    pub fn latest_reorg_id(&self) -> anyhow::Result<Option<u64>> {
+        self.inner()
+            .query_row("SELECT max(id) FROM reorg_log", [], |row| {
+                row.get_optional_i64(0)
+            })
+            .context("Querying latest reorg")
+            .map(|id| id.map(|id| id as u64))
+    }
+
+    /// Returns up to `limit` reorgs starting from `from_id`, oldest first.
+    pub fn recorded_reorgs(
+        &self,
+        from_id: u64,
+        limit: usize,
+    ) -> anyhow::Result<Vec<RecordedReorg>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"SELECT id, detected_at, old_head_number, old_head_hash, new_head_number,
+                new_head_hash, depth
+            FROM reorg_log WHERE id >= ? ORDER BY id LIMIT ?",
+        )?;
+        let reorgs = stmt
+            .query_map(
+                params![&from_id.try_into_sql_int()?, &limit.try_into_sql_int()?],
+                |row| {
+                    Ok(RecordedReorg {
+                        id: row.get_i64(0)? as u64,
+                        detected_at: row.get_i64(1)? as u64,
+                        old_head_number: row.get_block_number(2)?,
+                        old_head_hash: row.get_block_hash(3)?,
+                        new_head_number: row.get_optional_block_number(4)?,
+                        new_head_hash: row.get_optional_felt(5)?.map(BlockHash),
+                        depth: row.get_i64(6)? as u64,
+                    })
+                },
+            )
+            .context("Querying reorgs")?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(reorgs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockHeader;
+
+    use super::*;
+
+    #[test]
+    fn reorgs_are_listed_in_order() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        assert_eq!(tx.latest_reorg_id().unwrap(), None);
+
+        for (number, hash) in [(0, block_hash!("0xa")), (1, block_hash!("0xb"))] {
+            tx.insert_block_header(&BlockHeader {
+                number: BlockNumber::new_or_panic(number),
+                hash,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+
+        let first = tx
+            .record_reorg(
+                100,
+                BlockNumber::new_or_panic(1),
+                BlockNumber::new_or_panic(1),
+            )
+            .unwrap();
+        let second = tx
+            .record_reorg(200, BlockNumber::GENESIS, BlockNumber::new_or_panic(1))
+            .unwrap();
+
+        assert_eq!(
+            first,
+            RecordedReorg {
+                id: first.id,
+                detected_at: 100,
+                old_head_number: BlockNumber::new_or_panic(1),
+                old_head_hash: block_hash!("0xb"),
+                new_head_number: Some(BlockNumber::GENESIS),
+                new_head_hash: Some(block_hash!("0xa")),
+                depth: 1,
+            }
+        );
+        assert_eq!(second.new_head_number, None);
+        assert_eq!(second.depth, 2);
+        assert_eq!(tx.latest_reorg_id().unwrap(), Some(second.id));
+
+        assert_eq!(
+            tx.recorded_reorgs(0, 10).unwrap(),
+            vec![first.clone(), second.clone()]
+        );
+        assert_eq!(tx.recorded_reorgs(second.id, 10).unwrap(), vec![second]);
+        assert_eq!(tx.recorded_reorgs(0, 1).unwrap(), vec![first]);
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::Context;
-use pathfinder_common::{BlockCommitmentSignature, BlockNumber};
+use pathfinder_common::{BlockCommitmentSignature, BlockNumber, PublicKey};
 
 use crate::prelude::*;
 use crate::BlockId;
@@ -26,6 +26,66 @@ impl Transaction<'_> {
         Ok(())
     }
 
+    /// Records that the signature of the block was verified against
+    /// `public_key`.
+    pub fn insert_signature_attestation(
+        &self,
+        block_number: BlockNumber,
+        public_key: PublicKey,
+    ) -> anyhow::Result<()> {
+        let updated = self
+            .inner()
+            .execute(
+                "UPDATE block_signatures SET attested_by = ? WHERE block_number = ?",
+                params![&public_key, &block_number],
+            )
+            .context("Updating signature attestation")?;
+        anyhow::ensure!(updated == 1, "Signature of block {block_number} not found");
+
+        Ok(())
+    }
+
+    /// Returns the signature of the block and the public key it was verified
+    /// against, if the signature has been verified.
+    pub fn signature_attestation(
+        &self,
+        block: BlockId,
+    ) -> anyhow::Result<Option<(BlockCommitmentSignature, PublicKey)>> {
+        let map_row = |row: &rusqlite::Row<'_>| {
+            let r = row.get_block_commitment_signature_elem(0)?;
+            let s = row.get_block_commitment_signature_elem(1)?;
+            let attested_by = row.get_optional_felt(2)?.map(PublicKey);
+            Ok((BlockCommitmentSignature { r, s }, attested_by))
+        };
+
+        let row = match block {
+            BlockId::Latest => self.inner().query_row(
+                "SELECT signature_r, signature_s, attested_by FROM block_signatures ORDER BY \
+                 block_number DESC LIMIT 1",
+                [],
+                map_row,
+            ),
+            BlockId::Number(number) => self.inner().query_row(
+                "SELECT signature_r, signature_s, attested_by FROM block_signatures WHERE \
+                 block_number = ?",
+                params![&number],
+                map_row,
+            ),
+            BlockId::Hash(hash) => self.inner().query_row(
+                r"SELECT signature_r, signature_s, attested_by
+                FROM block_signatures
+                JOIN block_headers ON block_signatures.block_number = block_headers.number
+                WHERE block_headers.hash = ?",
+                params![&hash],
+                map_row,
+            ),
+        }
+        .optional()
+        .context("Querying signature attestation")?;
+
+        Ok(row.and_then(|(signature, attested_by)| Some((signature, attested_by?))))
+    }
+
     pub fn signature(&self, block: BlockId) -> anyhow::Result<Option<BlockCommitmentSignature>> {
         match block {
             BlockId::Latest => self.inner().query_row(
@@ -147,4 +207,27 @@ mod tests {
         let result = tx.signature(past_head.into()).unwrap();
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn attestation() {
+        let (mut connection, headers, signatures) = setup();
+        let tx = connection.transaction().unwrap();
+        let public_key = public_key_bytes!(b"public key");
+
+        tx.insert_signature_attestation(headers[1].number, public_key)
+            .unwrap();
+
+        let result = tx.signature_attestation(headers[0].number.into()).unwrap();
+        assert_eq!(result, None);
+
+        let result = tx.signature_attestation(headers[1].hash.into()).unwrap();
+        assert_eq!(result, Some((signatures[1].clone(), public_key)));
+
+        let result = tx.signature_attestation(BlockId::Latest).unwrap();
+        assert_eq!(result, Some((signatures[1].clone(), public_key)));
+
+        let past_head = headers.last().unwrap().number + 1;
+        tx.insert_signature_attestation(past_head, public_key)
+            .unwrap_err();
+    }
 }
@@ -0,0 +1,233 @@
+//! Pruning of state updates which are no longer needed to serve the state of
+//! the retained blocks.
+//!
+//! A state update is superseded once the same storage slot, nonce or class
+//! hash has been updated again. Superseded updates are only needed to serve
+//! the state of the blocks before the superseding update, so once they are
+//! deleted up to some block, the state of all earlier blocks is incomplete.
+//! The state of that block and all later ones is unaffected.
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+
+use crate::prelude::*;
+use crate::BlockId;
+
+impl Transaction<'_> {
+    /// The block up to which superseded state updates have been pruned, or
+    /// [None] if they have not been pruned. The state of earlier blocks is
+    /// incomplete.
+    pub fn state_pruned_to(&self) -> anyhow::Result<Option<BlockNumber>> {
+        self.inner()
+            .query_row(
+                "SELECT state_pruned_to FROM refs WHERE idx = 1",
+                [],
+                |row| row.get_optional_block_number(0),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Whether the state of `block` is incomplete because of pruning.
+    ///
+    /// Returns `false` for blocks which do not exist.
+    pub fn is_state_pruned(&self, block: BlockId) -> anyhow::Result<bool> {
+        Ok(self.earliest_available_state_block(block)?.is_some())
+    }
+
+    /// If the state of `block` is incomplete because of pruning, returns the
+    /// earliest block whose state is complete.
+    ///
+    /// Returns [None] for blocks which do not exist.
+    pub fn earliest_available_state_block(
+        &self,
+        block: BlockId,
+    ) -> anyhow::Result<Option<BlockNumber>> {
+        let Some(pruned_to) = self.state_pruned_to()? else {
+            return Ok(None);
+        };
+
+        let number = match block {
+            BlockId::Latest => return Ok(None),
+            BlockId::Number(number) => number,
+            BlockId::Hash(_) => match self.block_number(block)? {
+                Some(number) => number,
+                None => return Ok(None),
+            },
+        };
+
+        Ok((number < pruned_to).then_some(pruned_to))
+    }
+
+    /// Deletes the state updates superseded by the updates of up to
+    /// `max_blocks` blocks following the last pruned block, but not beyond
+    /// `until`. Returns the last block pruned.
+    pub fn prune_state_updates(
+        &self,
+        until: BlockNumber,
+        max_blocks: u64,
+    ) -> anyhow::Result<Option<BlockNumber>> {
+        let pruned_to = self.state_pruned_to()?;
+        let first = pruned_to.map_or(BlockNumber::GENESIS, |block| block + 1);
+        if first > until || max_blocks == 0 {
+            return Ok(pruned_to);
+        }
+        let last = until.min(first + (max_blocks - 1));
+
+        let mut delete_storage_updates = self
+            .inner()
+            .prepare_cached(
+                "DELETE FROM storage_updates WHERE rowid IN (SELECT old.rowid FROM \
+                 storage_updates new JOIN storage_updates old ON old.contract_address_id = \
+                 new.contract_address_id AND old.storage_address_id = new.storage_address_id AND \
+                 old.block_number < new.block_number WHERE new.block_number = ?)",
+            )
+            .context("Preparing storage updates delete statement")?;
+        let mut delete_nonce_updates = self
+            .inner()
+            .prepare_cached(
+                "DELETE FROM nonce_updates WHERE rowid IN (SELECT old.rowid FROM nonce_updates \
+                 new JOIN nonce_updates old ON old.contract_address_id = new.contract_address_id \
+                 AND old.block_number < new.block_number WHERE new.block_number = ?)",
+            )
+            .context("Preparing nonce updates delete statement")?;
+        let mut delete_contract_updates = self
+            .inner()
+            .prepare_cached(
+                "DELETE FROM contract_updates WHERE rowid IN (SELECT old.rowid FROM \
+                 contract_updates new JOIN contract_updates old ON old.contract_address = \
+                 new.contract_address AND old.block_number < new.block_number WHERE \
+                 new.block_number = ?)",
+            )
+            .context("Preparing contract updates delete statement")?;
+
+        let mut deleted = 0;
+        for number in first.get()..=last.get() {
+            let block = BlockNumber::new_or_panic(number);
+            deleted += delete_storage_updates
+                .execute(params![&block])
+                .context("Deleting superseded storage updates")?;
+            deleted += delete_nonce_updates
+                .execute(params![&block])
+                .context("Deleting superseded nonce updates")?;
+            deleted += delete_contract_updates
+                .execute(params![&block])
+                .context("Deleting superseded contract updates")?;
+        }
+
+        self.inner()
+            .execute(
+                "UPDATE refs SET state_pruned_to = ? WHERE idx = 1",
+                params![&last],
+            )
+            .context("Updating state pruning checkpoint")?;
+
+        tracing::trace!(%first, %last, %deleted, "Pruned superseded state updates");
+
+        Ok(Some(last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHash, BlockHeader, ContractNonce, StateUpdate, StorageValue};
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+
+    #[test]
+    fn superseded_updates_are_pruned() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let contract = contract_address!("0x1");
+        let key = storage_address!("0x10");
+        let other_key = storage_address!("0x20");
+
+        for number in 0..4u64 {
+            let header = BlockHeader {
+                number: BlockNumber::new_or_panic(number),
+                hash: BlockHash(Felt::from_u64(number)),
+                ..Default::default()
+            };
+            tx.insert_block_header(&header).unwrap();
+
+            let mut state_update = StateUpdate::default()
+                .with_storage_update(contract, key, StorageValue(Felt::from_u64(number)))
+                .with_contract_nonce(contract, ContractNonce(Felt::from_u64(number)));
+            if number == 0 {
+                state_update = state_update
+                    .with_deployed_contract(contract, class_hash!("0xc1"))
+                    .with_storage_update(contract, other_key, storage_value!("0x99"));
+            }
+            tx.insert_state_update(header.number, &state_update)
+                .unwrap();
+        }
+
+        assert_eq!(tx.state_pruned_to().unwrap(), None);
+        assert!(!tx.is_state_pruned(BlockNumber::GENESIS.into()).unwrap());
+
+        assert_eq!(
+            tx.prune_state_updates(BlockNumber::GENESIS + 2, 10)
+                .unwrap(),
+            Some(BlockNumber::GENESIS + 2)
+        );
+        assert!(tx
+            .is_state_pruned((BlockNumber::GENESIS + 1).into())
+            .unwrap());
+        assert!(!tx
+            .is_state_pruned((BlockNumber::GENESIS + 2).into())
+            .unwrap());
+        assert!(!tx.is_state_pruned(BlockId::Latest).unwrap());
+        assert_eq!(
+            tx.earliest_available_state_block(BlockNumber::GENESIS.into())
+                .unwrap(),
+            Some(BlockNumber::GENESIS + 2)
+        );
+        assert_eq!(
+            tx.earliest_available_state_block((BlockNumber::GENESIS + 2).into())
+                .unwrap(),
+            None
+        );
+
+        // The state of the retained blocks is unaffected.
+        for number in 2..4u64 {
+            let block = BlockNumber::new_or_panic(number);
+            assert_eq!(
+                tx.storage_value(block.into(), contract, key).unwrap(),
+                Some(StorageValue(Felt::from_u64(number)))
+            );
+            assert_eq!(
+                tx.contract_nonce(contract, block.into()).unwrap(),
+                Some(ContractNonce(Felt::from_u64(number)))
+            );
+            assert_eq!(
+                tx.storage_value(block.into(), contract, other_key).unwrap(),
+                Some(storage_value!("0x99"))
+            );
+            assert_eq!(
+                tx.contract_class_hash(block.into(), contract).unwrap(),
+                Some(class_hash!("0xc1"))
+            );
+        }
+
+        // Only the updates of the pruned blocks which were superseded are gone.
+        assert_eq!(
+            tx.storage_value(BlockNumber::GENESIS.into(), contract, key)
+                .unwrap(),
+            None
+        );
+
+        // Pruning continues from the last pruned block.
+        assert_eq!(
+            tx.prune_state_updates(BlockNumber::GENESIS + 3, 10)
+                .unwrap(),
+            Some(BlockNumber::GENESIS + 3)
+        );
+        assert_eq!(
+            tx.prune_state_updates(BlockNumber::GENESIS + 3, 10)
+                .unwrap(),
+            Some(BlockNumber::GENESIS + 3)
+        );
+    }
+}
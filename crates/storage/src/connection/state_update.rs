@@ -649,6 +649,36 @@ impl Transaction<'_> {
         .map_err(|e| e.into())
     }
 
+    /// Returns the value of each storage slot at `block`, or [None] for slots
+    /// which were never written.
+    pub fn storage_values(
+        &self,
+        block: BlockNumber,
+        slots: &[(ContractAddress, StorageAddress)],
+    ) -> anyhow::Result<Vec<Option<StorageValue>>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT storage_value
+            FROM storage_updates
+            JOIN contract_addresses ON contract_addresses.id = storage_updates.contract_address_id
+            JOIN storage_addresses ON storage_addresses.id = storage_updates.storage_address_id
+            WHERE contract_address = ? AND storage_address = ? AND block_number <= ?
+            ORDER BY block_number DESC LIMIT 1
+            ",
+        )?;
+
+        slots
+            .iter()
+            .map(|(contract_address, key)| {
+                stmt.query_row(params![contract_address, key, &block], |row| {
+                    row.get_storage_value(0)
+                })
+                .optional()
+                .context("Querying storage value")
+            })
+            .collect()
+    }
+
     pub fn contract_exists(
         &self,
         contract_address: ContractAddress,
@@ -740,6 +770,59 @@ impl Transaction<'_> {
         .map_err(|e| e.into())
     }
 
+    /// Returns the nonce of each contract at `block` using a single query, or
+    /// [None] for contracts whose nonce was never set. These are either not
+    /// deployed or still at the initial nonce of zero.
+    pub fn contract_nonces(
+        &self,
+        block: BlockNumber,
+        contract_addresses: &[ContractAddress],
+    ) -> anyhow::Result<Vec<Option<ContractNonce>>> {
+        if contract_addresses.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Not cached since the statement depends on the number of contracts.
+        // SQLite takes the bare columns from the row with the latest block.
+        let placeholders = vec!["?"; contract_addresses.len()].join(", ");
+        let mut stmt = self
+            .inner()
+            .prepare(&format!(
+                r"
+                SELECT contract_address, nonce, MAX(block_number) FROM nonce_updates
+                JOIN contract_addresses ON contract_addresses.id = nonce_updates.contract_address_id
+                WHERE block_number <= ? AND contract_address IN ({placeholders})
+                GROUP BY contract_address_id
+                "
+            ))
+            .context("Preparing nonces query statement")?;
+
+        let params = std::iter::once(crate::params::ToSql::to_sql(&block))
+            .chain(contract_addresses.iter().map(crate::params::ToSql::to_sql));
+        let mut rows = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                let contract_address = row.get_contract_address(0)?;
+                let nonce = row.get_contract_nonce(1)?;
+
+                Ok((contract_address, nonce))
+            })
+            .context("Querying nonces")?;
+
+        let mut nonces = HashMap::new();
+        while let Some((address, nonce)) = rows
+            .next()
+            .transpose()
+            .context("Iterating over nonce query rows")?
+        {
+            nonces.insert(address, nonce);
+        }
+
+        Ok(contract_addresses
+            .iter()
+            .map(|address| nonces.get(address).copied())
+            .collect())
+    }
+
     pub fn contract_class_hash(
         &self,
         block_id: BlockId,
@@ -781,6 +864,31 @@ impl Transaction<'_> {
         .map_err(|e| e.into())
     }
 
+    /// Returns the class hash of each contract at `block`, or [None] for
+    /// contracts not deployed at that block.
+    pub fn contract_class_hashes(
+        &self,
+        block: BlockNumber,
+        contract_addresses: &[ContractAddress],
+    ) -> anyhow::Result<Vec<Option<ClassHash>>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"SELECT class_hash FROM contract_updates
+            WHERE contract_address = ? AND block_number <= ?
+            ORDER BY block_number DESC LIMIT 1",
+        )?;
+
+        contract_addresses
+            .iter()
+            .map(|contract_address| {
+                stmt.query_row(params![contract_address, &block], |row| {
+                    row.get_class_hash(0)
+                })
+                .optional()
+                .context("Querying contract class hash")
+            })
+            .collect()
+    }
+
     pub fn reverse_contract_updates(
         &self,
         from: BlockNumber,
@@ -1165,6 +1273,11 @@ mod tests {
             .contract_class_hash(header_4.hash.into(), contract)
             .unwrap();
         assert_eq!(is_replaced, Some(replaced_class));
+
+        let batch = tx
+            .contract_class_hashes(header_2.number, &[contract, contract_address!("0xaaaaa")])
+            .unwrap();
+        assert_eq!(batch, vec![Some(replaced_class), None]);
     }
 
     mod state_update {
@@ -1458,6 +1571,31 @@ mod tests {
             assert_eq!(invalid_by_number, None);
         }
 
+        #[test]
+        fn get_contract_nonces() {
+            let (mut db, _, header) = setup();
+            let tx = db.transaction().unwrap();
+
+            let nonces = tx
+                .contract_nonces(
+                    header.number,
+                    &[
+                        contract_address_bytes!(b"contract address 2"),
+                        contract_address_bytes!(b"invalid"),
+                        contract_address_bytes!(b"contract address"),
+                    ],
+                )
+                .unwrap();
+            assert_eq!(
+                nonces,
+                vec![
+                    Some(contract_nonce_bytes!(b"nonce value 2")),
+                    None,
+                    Some(contract_nonce_bytes!(b"nonce value")),
+                ]
+            );
+        }
+
         #[test]
         fn get_storage_value() {
             let (mut db, state_update, header) = setup();
@@ -1522,5 +1660,30 @@ mod tests {
                 .unwrap();
             assert_eq!(by_number, None);
         }
+
+        #[test]
+        fn get_storage_values() {
+            let (mut db, _, header) = setup();
+            let tx = db.transaction().unwrap();
+
+            let contract = contract_address_bytes!(b"contract address");
+            let values = tx
+                .storage_values(
+                    header.number,
+                    &[
+                        (contract, storage_address_bytes!(b"invalid key")),
+                        (contract, storage_address_bytes!(b"storage address")),
+                        (
+                            contract_address_bytes!(b"invalid"),
+                            storage_address_bytes!(b"storage address"),
+                        ),
+                    ],
+                )
+                .unwrap();
+            assert_eq!(
+                values,
+                vec![None, Some(storage_value_bytes!(b"storage value")), None]
+            );
+        }
     }
 }
@@ -0,0 +1,51 @@
+//! Statistics used by SQLite's query planner to choose between indexes.
+use anyhow::Context;
+
+use crate::prelude::*;
+
+/// Rows sampled per index, which keeps refreshing the statistics of large
+/// databases fast at little cost in accuracy.
+const ANALYSIS_LIMIT: u32 = 1000;
+
+impl Transaction<'_> {
+    /// Refreshes the statistics of all tables and indexes, see
+    /// [ANALYZE](https://sqlite.org/lang_analyze.html).
+    ///
+    /// Without them the planner can pick an index which matches fewer columns
+    /// of a query, e.g. scanning events by block instead of by address.
+    pub fn refresh_planner_statistics(&self) -> anyhow::Result<()> {
+        self.inner()
+            .pragma_update(None, "analysis_limit", ANALYSIS_LIMIT)
+            .context("Setting analysis limit")?;
+        self.inner()
+            .execute_batch("ANALYZE")
+            .context("Analyzing database")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::BlockHeader;
+
+    use super::*;
+
+    #[test]
+    fn statistics_are_stored() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        tx.insert_block_header(&BlockHeader::default()).unwrap();
+
+        tx.refresh_planner_statistics().unwrap();
+
+        let analyzed: bool = tx
+            .inner()
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_stat1 WHERE tbl = 'block_headers')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(analyzed);
+    }
+}
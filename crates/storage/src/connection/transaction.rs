@@ -2,9 +2,20 @@
 
 use anyhow::Context;
 use pathfinder_common::event::Event;
-use pathfinder_common::receipt::Receipt;
+use pathfinder_common::receipt::{ExecutionStatus, L2ToL1Message, Receipt};
 use pathfinder_common::transaction::Transaction as StarknetTransaction;
-use pathfinder_common::{BlockHash, BlockNumber, TransactionHash};
+use pathfinder_common::{
+    BlockHash,
+    BlockNumber,
+    ContractAddress,
+    EventData,
+    EventKey,
+    Fee,
+    L2ToL1MessagePayloadElem,
+    TransactionHash,
+    TransactionIndex,
+};
+use pathfinder_crypto::Felt;
 
 use super::{EventsForBlock, TransactionDataForBlock, TransactionWithReceipt};
 use crate::prelude::*;
@@ -21,8 +32,6 @@ pub(crate) mod compression {
     /// The maximum allowed uncompressed size of a serialized blob of
     /// transactions.
     const MAX_TRANSACTIONS_UNCOMPRESSED_SIZE: usize = 128usize * 1024 * 1024;
-    /// The maximum allowed uncompressed size of a serialized blob of events.
-    const MAX_EVENTS_UNCOMPRESSED_SIZE: usize = 128usize * 1024 * 1024;
 
     static ZSTD_TXS_ENCODER_DICTIONARY: LazyLock<zstd::dict::EncoderDictionary<'static>> =
         LazyLock::new(|| {
@@ -41,10 +50,6 @@ pub(crate) mod compression {
 
     static ZSTD_TXS_DECODER_DICTIONARY: LazyLock<zstd::dict::DecoderDictionary<'static>> =
         LazyLock::new(|| zstd::dict::DecoderDictionary::new(include_bytes!("../assets/txs.zdict")));
-    static ZSTD_EVENTS_DECODER_DICTIONARY: LazyLock<zstd::dict::DecoderDictionary<'static>> =
-        LazyLock::new(|| {
-            zstd::dict::DecoderDictionary::new(include_bytes!("../assets/events.zdict"))
-        });
 
     pub(super) fn compress_transactions(input: &[u8]) -> std::io::Result<Vec<u8>> {
         let mut compressor = new_txs_compressor()?;
@@ -55,16 +60,11 @@ pub(crate) mod compression {
         zstd::bulk::Compressor::with_prepared_dictionary(&ZSTD_TXS_ENCODER_DICTIONARY)
     }
 
-    pub(super) fn compress_events(input: &[u8]) -> std::io::Result<Vec<u8>> {
-        let mut compressor = new_events_compressor()?;
-        compressor.compress(input)
-    }
-
     pub(crate) fn new_events_compressor() -> std::io::Result<zstd::bulk::Compressor<'static>> {
         zstd::bulk::Compressor::with_prepared_dictionary(&ZSTD_EVENTS_ENCODER_DICTIONARY)
     }
 
-    pub(crate) fn decompress_transactions(input: &[u8]) -> std::io::Result<Vec<u8>> {
+    pub(super) fn decompress_transactions(input: &[u8]) -> std::io::Result<Vec<u8>> {
         let mut decompressor = new_txs_decompressor()?;
         decompressor.decompress(input, MAX_TRANSACTIONS_UNCOMPRESSED_SIZE)
     }
@@ -72,15 +72,6 @@ pub(crate) mod compression {
     fn new_txs_decompressor() -> std::io::Result<zstd::bulk::Decompressor<'static>> {
         zstd::bulk::Decompressor::with_prepared_dictionary(&ZSTD_TXS_DECODER_DICTIONARY)
     }
-
-    pub(crate) fn decompress_events(input: &[u8]) -> std::io::Result<Vec<u8>> {
-        let mut decompressor = new_events_decompressor()?;
-        decompressor.decompress(input, MAX_EVENTS_UNCOMPRESSED_SIZE)
-    }
-
-    fn new_events_decompressor() -> std::io::Result<zstd::bulk::Decompressor<'static>> {
-        zstd::bulk::Decompressor::with_prepared_dictionary(&ZSTD_EVENTS_DECODER_DICTIONARY)
-    }
 }
 
 type TransactionsAndEventsByBlock = (Vec<(StarknetTransaction, Receipt)>, Option<Vec<Vec<Event>>>);
@@ -106,8 +97,8 @@ impl Transaction<'_> {
         let mut insert_transaction_stmt = self
             .inner()
             .prepare_cached(
-                "INSERT INTO transactions (block_number, transactions, events) VALUES \
-                 (:block_number, :transactions, :events)",
+                "INSERT INTO transactions (block_number, transactions, events_transaction_count) \
+                 VALUES (:block_number, :transactions, :events_transaction_count)",
             )
             .context("Preparing insert transaction statement")?;
         let mut insert_transaction_hash_stmt = self
@@ -126,49 +117,53 @@ impl Transaction<'_> {
                 ":idx": &idx,
             ])?;
         }
-        let transactions_with_receipts: Vec<_> = transactions
-            .iter()
-            .map(|(transaction, receipt)| dto::TransactionWithReceiptV2 {
-                transaction: dto::TransactionV1::from(transaction),
-                receipt: receipt.into(),
-            })
-            .collect();
-        let transactions_with_receipts = dto::TransactionsWithReceiptsForBlock::V2 {
-            transactions_with_receipts,
+        // Receipts are stored in the transaction_receipts table.
+        let transactions_for_block = dto::TransactionsForBlock::V0 {
+            transactions: transactions
+                .iter()
+                .map(|(transaction, _)| dto::TransactionV1::from(transaction))
+                .collect(),
         };
-        let transactions_with_receipts =
-            bincode::serde::encode_to_vec(transactions_with_receipts, bincode::config::standard())
+        let transactions_for_block =
+            bincode::serde::encode_to_vec(transactions_for_block, bincode::config::standard())
                 .context("Serializing transaction")?;
-        let transactions_with_receipts =
-            compression::compress_transactions(&transactions_with_receipts)
-                .context("Compressing transaction")?;
-
-        let encoded_events = match events {
-            Some(events) => {
-                let events = dto::EventsForBlock::V0 {
-                    events: events
-                        .iter()
-                        .map(|events| events.iter().cloned().map(Into::into).collect())
-                        .collect(),
-                };
-                let events = bincode::serde::encode_to_vec(events, bincode::config::standard())
-                    .context("Serializing events")?;
-                Some(compression::compress_events(&events).context("Compressing events")?)
-            }
-            None => None,
-        };
+        let transactions_for_block = compression::compress_transactions(&transactions_for_block)
+            .context("Compressing transaction")?;
+
+        let events_transaction_count = events
+            .map(|events| events.len().try_into_sql_int())
+            .transpose()?;
 
         insert_transaction_stmt
             .execute(named_params![
                 ":block_number": &block_number,
-                ":transactions": &transactions_with_receipts,
-                ":events": &encoded_events,
+                ":transactions": &transactions_for_block,
+                ":events_transaction_count": &events_transaction_count,
             ])
             .context("Inserting transaction data")?;
 
+        self.insert_receipts(block_number, transactions)
+            .context("Inserting receipts")?;
+
+        self.insert_messages(block_number, transactions)
+            .context("Inserting messages")?;
+
+        self.insert_senders(block_number, transactions)
+            .context("Inserting transaction senders")?;
+
+        self.insert_l1_handler_messages(block_number, transactions)
+            .context("Inserting L1 handler messages")?;
+
+        self.insert_block_transaction_stats(
+            block_number,
+            transactions.iter().map(|(transaction, _)| transaction),
+        )
+        .context("Inserting block transaction stats")?;
+
         if let Some(events) = events {
-            let events = events.iter().flatten();
-            self.upsert_block_events(block_number, events)
+            self.insert_events(block_number, events)
+                .context("Inserting events")?;
+            self.upsert_block_events(block_number, events.iter().flatten())
                 .context("Inserting events into Bloom filter")?;
         }
 
@@ -185,30 +180,29 @@ impl Transaction<'_> {
             .prepare_cached(
                 r"
                 UPDATE transactions
-                SET events = :events
+                SET events_transaction_count = :events_transaction_count
                 WHERE block_number = :block_number
                 ",
             )
             .context("Preparing update events statement")?;
 
-        let encoded_events = dto::EventsForBlock::V0 {
-            events: events
-                .iter()
-                .cloned()
-                .map(|events| events.into_iter().map(Into::into).collect())
-                .collect(),
-        };
-        let encoded_events =
-            bincode::serde::encode_to_vec(encoded_events, bincode::config::standard())
-                .context("Serializing events")?;
-        let encoded_events =
-            compression::compress_events(&encoded_events).context("Compressing events")?;
-
-        stmt.execute(named_params![
-            ":block_number": &block_number,
-            ":events": &encoded_events,
-        ])
-        .context("Updating events")?;
+        let updated = stmt
+            .execute(named_params![
+                ":block_number": &block_number,
+                ":events_transaction_count": &events.len().try_into_sql_int()?,
+            ])
+            .context("Updating events")?;
+
+        if updated > 0 {
+            self.inner()
+                .execute(
+                    "DELETE FROM transaction_events WHERE block_number = ?",
+                    params![&block_number],
+                )
+                .context("Deleting existing events")?;
+            self.insert_events(block_number, &events)
+                .context("Inserting events")?;
+        }
 
         self.upsert_block_events(block_number, events.iter().flatten())
             .context("Inserting events into Bloom filter")?;
@@ -220,7 +214,7 @@ impl Transaction<'_> {
         &self,
         transaction: TransactionHash,
     ) -> anyhow::Result<Option<StarknetTransaction>> {
-        let Some((_, transaction, _)) = self.query_transaction_by_hash(transaction)? else {
+        let Some((_, _, transaction, _)) = self.query_transaction_by_hash(transaction)? else {
             return Ok(None);
         };
         Ok(Some(transaction))
@@ -377,6 +371,68 @@ impl Transaction<'_> {
             .map_err(|e| e.into())
     }
 
+    /// Returns the transactions sent by `sender_address` in a block range, in
+    /// chain order, along with their block number and index in their block.
+    ///
+    /// Transactions before `after`, and the transaction at `after` itself,
+    /// are skipped, which allows continuing from the last transaction of a
+    /// previous page. Deploy and L1 handler transactions are returned for the
+    /// contract they deploy or call.
+    pub fn transactions_by_sender(
+        &self,
+        sender_address: ContractAddress,
+        from: BlockNumber,
+        to: BlockNumber,
+        after: Option<(BlockNumber, usize)>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(BlockNumber, usize, TransactionHash)>> {
+        let (after_block, after_idx) = match after {
+            Some((block_number, idx)) => (Some(block_number), Some(idx.try_into_sql_int()?)),
+            None => (None, None),
+        };
+
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT transaction_senders.block_number, transaction_senders.transaction_idx, hash
+            FROM transaction_senders
+            JOIN transaction_hashes
+                ON transaction_hashes.block_number = transaction_senders.block_number
+                AND transaction_hashes.idx = transaction_senders.transaction_idx
+            WHERE sender_address = :sender_address
+                AND transaction_senders.block_number >= :from
+                AND transaction_senders.block_number <= :to
+                AND (
+                    :after_block IS NULL
+                    OR (transaction_senders.block_number, transaction_senders.transaction_idx)
+                        > (:after_block, :after_idx)
+                )
+            ORDER BY transaction_senders.block_number, transaction_senders.transaction_idx
+            LIMIT :limit
+            ",
+        )?;
+
+        let mut rows = stmt
+            .query(named_params![
+                ":sender_address": &sender_address,
+                ":from": &from,
+                ":to": &to,
+                ":after_block": &after_block,
+                ":after_idx": &after_idx,
+                ":limit": &limit.try_into_sql_int()?,
+            ])
+            .context("Querying transactions by sender")?;
+
+        let mut transactions = Vec::new();
+        while let Some(row) = rows.next()? {
+            let block_number = row.get_block_number(0)?;
+            let idx: usize = row.get_i64(1)?.try_into()?;
+            let hash = row.get_transaction_hash(2)?;
+            transactions.push((block_number, idx, hash));
+        }
+
+        Ok(transactions)
+    }
+
     fn query_transactions_by_block(
         &self,
         block_number: BlockNumber,
@@ -392,26 +448,30 @@ impl Transaction<'_> {
         let Some(row) = rows.next()? else {
             return Ok(None);
         };
-        let transactions = row.get_blob(0)?;
-        let transactions = compression::decompress_transactions(transactions)
-            .context("Decompressing transactions")?;
-        let transactions: dto::TransactionsWithReceiptsForBlock =
-            bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
-                .context("Deserializing transactions")?
-                .0;
-        let transactions = transactions.transactions_with_receipts();
+        let transactions = decode_transactions(row.get_blob(0)?)?;
+        let receipts = self.query_receipts(block_number, None)?;
+        anyhow::ensure!(
+            transactions.len() == receipts.len(),
+            "Receipt list and transaction list mismatch"
+        );
+        let mut transactions: Vec<(StarknetTransaction, Receipt)> = transactions
+            .into_iter()
+            .zip(receipts)
+            .map(|(transaction, mut receipt)| {
+                let transaction = StarknetTransaction::from(transaction);
+                receipt.transaction_hash = transaction.hash;
+                (transaction, receipt)
+            })
+            .collect();
 
-        Ok(Some(
-            transactions
-                .into_iter()
-                .map(
-                    |dto::TransactionWithReceiptV2 {
-                         transaction,
-                         receipt,
-                     }| { (transaction.into(), receipt.into()) },
-                )
-                .collect(),
-        ))
+        for (idx, message) in self.query_messages(block_number, None)? {
+            let (_, receipt) = transactions
+                .get_mut(idx)
+                .context("Message transaction index out of range")?;
+            receipt.l2_to_l1_messages.push(message);
+        }
+
+        Ok(Some(transactions))
     }
 
     fn query_transaction_hashes_by_block(
@@ -438,101 +498,296 @@ impl Transaction<'_> {
         &self,
         block_number: BlockNumber,
     ) -> anyhow::Result<Option<TransactionsAndEventsByBlock>> {
+        let Some(transactions) = self.query_transactions_by_block(block_number)? else {
+            return Ok(None);
+        };
+        let events = self.query_events_by_block(block_number)?;
+
+        Ok(Some((transactions, events)))
+    }
+
+    /// Returns the events of the block grouped by transaction, or [None] if
+    /// the block's events have not been stored yet.
+    fn query_events_by_block(
+        &self,
+        block_number: BlockNumber,
+    ) -> anyhow::Result<Option<Vec<Vec<Event>>>> {
+        let Some(transaction_count) = self.query_events_transaction_count(block_number)? else {
+            return Ok(None);
+        };
+
         let mut stmt = self.inner().prepare_cached(
             r"
-            SELECT transactions, events
-            FROM transactions
+            SELECT transaction_idx, from_address, keys, data
+            FROM transaction_events
             WHERE block_number = ?
+            ORDER BY transaction_idx, event_idx
             ",
         )?;
         let mut rows = stmt.query(params![&block_number])?;
-        let Some(row) = rows.next()? else {
+
+        let mut events = vec![Vec::new(); transaction_count];
+        while let Some(row) = rows.next()? {
+            let idx: usize = row.get_i64(0)?.try_into()?;
+            events
+                .get_mut(idx)
+                .context("Event transaction index out of range")?
+                .push(event_from_row(row)?);
+        }
+
+        Ok(Some(events))
+    }
+
+    /// Returns the events emitted by the transaction at `idx` in the block, or
+    /// [None] if the block's events have not been stored yet.
+    fn query_events_by_transaction(
+        &self,
+        block_number: BlockNumber,
+        idx: usize,
+    ) -> anyhow::Result<Option<Vec<Event>>> {
+        let Some(transaction_count) = self.query_events_transaction_count(block_number)? else {
             return Ok(None);
         };
-        let transactions = row.get_blob(0)?;
-        let transactions = compression::decompress_transactions(transactions)
-            .context("Decompressing transactions")?;
-        let transactions: dto::TransactionsWithReceiptsForBlock =
-            bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
-                .context("Deserializing transactions")?
-                .0;
-        let transactions = transactions.transactions_with_receipts();
-        let events: Option<dto::EventsForBlock> = match row.get_optional_blob(1)? {
-            Some(events) => {
-                let events =
-                    compression::decompress_events(events).context("Decompressing events")?;
-                Some(
-                    bincode::serde::decode_from_slice(&events, bincode::config::standard())
-                        .context("Deserializing events")?
-                        .0,
-                )
-            }
-            None => None,
-        };
-        let events = events.map(|events| match events {
-            dto::EventsForBlock::V0 { events } => events,
-        });
-        Ok(Some((
-            transactions
-                .into_iter()
-                .map(
-                    |dto::TransactionWithReceiptV2 {
-                         transaction,
-                         receipt,
-                     }| { (transaction.into(), receipt.into()) },
-                )
-                .collect(),
-            events.map(|events| {
-                events
+        anyhow::ensure!(idx < transaction_count, "Events missing");
+
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT transaction_idx, from_address, keys, data
+            FROM transaction_events
+            WHERE block_number = ? AND transaction_idx = ?
+            ORDER BY event_idx
+            ",
+        )?;
+        let mut rows = stmt.query(params![&block_number, &idx.try_into_sql_int()?])?;
+
+        let mut events = Vec::new();
+        while let Some(row) = rows.next()? {
+            events.push(event_from_row(row)?);
+        }
+
+        Ok(Some(events))
+    }
+
+    /// The number of transactions events were stored for, or [None] if the
+    /// block's events have not been stored yet.
+    fn query_events_transaction_count(
+        &self,
+        block_number: BlockNumber,
+    ) -> anyhow::Result<Option<usize>> {
+        let count = self
+            .inner()
+            .query_row(
+                "SELECT events_transaction_count FROM transactions WHERE block_number = ?",
+                params![&block_number],
+                |row| row.get_optional_i64(0),
+            )
+            .optional()
+            .context("Querying events transaction count")?
+            .flatten();
+
+        Ok(count.map(usize::try_from).transpose()?)
+    }
+
+    /// Returns the receipts of the block, or of only the transaction at `idx`,
+    /// in transaction order.
+    ///
+    /// Transaction hashes and L2 to L1 messages are not stored with the
+    /// receipts and are left empty.
+    fn query_receipts(
+        &self,
+        block_number: BlockNumber,
+        idx: Option<usize>,
+    ) -> anyhow::Result<Vec<Receipt>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT transaction_index, actual_fee, execution_resources, revert_reason
+            FROM transaction_receipts
+            WHERE block_number = ?1 AND (?2 IS NULL OR transaction_idx = ?2)
+            ORDER BY transaction_idx
+            ",
+        )?;
+        let idx = idx.map(|idx| idx.try_into_sql_int()).transpose()?;
+        let mut rows = stmt.query(params![&block_number, &idx])?;
+
+        let mut receipts = Vec::new();
+        while let Some(row) = rows.next()? {
+            let transaction_index = TransactionIndex::new(row.get_i64(0)?.try_into()?)
+                .context("Transaction index out of range")?;
+            let execution_resources: dto::ExecutionResourcesV1 =
+                bincode::serde::decode_from_slice(row.get_blob(2)?, bincode::config::standard())
+                    .context("Deserializing execution resources")?
+                    .0;
+            let execution_status = match row.get_optional_str(3)? {
+                Some(reason) => ExecutionStatus::Reverted {
+                    reason: reason.to_owned(),
+                },
+                None => ExecutionStatus::Succeeded,
+            };
+            receipts.push(Receipt {
+                actual_fee: Fee(row.get_felt(1)?),
+                execution_resources: execution_resources.into(),
+                execution_status,
+                transaction_index,
+                ..Default::default()
+            });
+        }
+
+        Ok(receipts)
+    }
+
+    /// Returns the L2 to L1 messages of the block, or of only the transaction
+    /// at `idx`, along with the index of the transaction that sent them.
+    fn query_messages(
+        &self,
+        block_number: BlockNumber,
+        idx: Option<usize>,
+    ) -> anyhow::Result<Vec<(usize, L2ToL1Message)>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT transaction_idx, from_address, to_address, payload
+            FROM transaction_messages
+            WHERE block_number = ?1 AND (?2 IS NULL OR transaction_idx = ?2)
+            ORDER BY transaction_idx, message_idx
+            ",
+        )?;
+        let idx = idx.map(|idx| idx.try_into_sql_int()).transpose()?;
+        let mut rows = stmt.query(params![&block_number, &idx])?;
+
+        let mut messages = Vec::new();
+        while let Some(row) = rows.next()? {
+            let idx: usize = row.get_i64(0)?.try_into()?;
+            let message = L2ToL1Message {
+                from_address: row.get_contract_address(1)?,
+                to_address: row.get_contract_address(2)?,
+                payload: decode_felts(row.get_blob(3)?)?
                     .into_iter()
-                    .map(|e| e.into_iter().map(Into::into).collect())
-                    .collect()
-            }),
-        )))
+                    .map(L2ToL1MessagePayloadElem)
+                    .collect(),
+            };
+            messages.push((idx, message));
+        }
+
+        Ok(messages)
     }
 
-    fn query_events_by_block(
+    fn insert_events(
         &self,
         block_number: BlockNumber,
-    ) -> anyhow::Result<Option<Vec<Vec<Event>>>> {
+        events: &[Vec<Event>],
+    ) -> anyhow::Result<()> {
         let mut stmt = self.inner().prepare_cached(
             r"
-            SELECT events
-            FROM transactions
-            WHERE block_number = ?
+            INSERT INTO transaction_events
+                (block_number, transaction_idx, event_idx, from_address, keys, data)
+            VALUES (:block_number, :transaction_idx, :event_idx, :from_address, :keys, :data)
             ",
         )?;
-        let mut rows = stmt.query(params![&block_number])?;
-        let Some(row) = rows.next()? else {
-            return Ok(None);
-        };
-        let events: Option<dto::EventsForBlock> = match row.get_optional_blob(0)? {
-            Some(events) => {
-                let events =
-                    compression::decompress_events(events).context("Decompressing events")?;
-                let events: dto::EventsForBlock =
-                    bincode::serde::decode_from_slice(&events, bincode::config::standard())
-                        .context("Deserializing events")?
-                        .0;
-
-                Some(events)
+
+        for (transaction_idx, events) in events.iter().enumerate() {
+            for (event_idx, event) in events.iter().enumerate() {
+                stmt.execute(named_params![
+                    ":block_number": &block_number,
+                    ":transaction_idx": &transaction_idx.try_into_sql_int()?,
+                    ":event_idx": &event_idx.try_into_sql_int()?,
+                    ":from_address": &event.from_address,
+                    ":keys": &encode_felts(event.keys.iter().map(|key| &key.0)),
+                    ":data": &encode_felts(event.data.iter().map(|data| &data.0)),
+                ])?;
             }
-            None => None,
-        };
-        let events = events.map(|e| e.events());
+        }
 
-        Ok(events.map(|events| {
-            events
-                .into_iter()
-                .map(|e| e.into_iter().map(Into::into).collect())
-                .collect()
-        }))
+        Ok(())
+    }
+
+    fn insert_receipts(
+        &self,
+        block_number: BlockNumber,
+        transactions: &[(StarknetTransaction, Receipt)],
+    ) -> anyhow::Result<()> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            INSERT INTO transaction_receipts
+                (block_number, transaction_idx, transaction_index, actual_fee, execution_resources, revert_reason)
+            VALUES (:block_number, :transaction_idx, :transaction_index, :actual_fee, :execution_resources, :revert_reason)
+            ",
+        )?;
+
+        for (transaction_idx, (_, receipt)) in transactions.iter().enumerate() {
+            let execution_resources = bincode::serde::encode_to_vec(
+                dto::ExecutionResourcesV1::from(&receipt.execution_resources),
+                bincode::config::standard(),
+            )
+            .context("Serializing execution resources")?;
+            stmt.execute(named_params![
+                ":block_number": &block_number,
+                ":transaction_idx": &transaction_idx.try_into_sql_int()?,
+                ":transaction_index": &receipt.transaction_index.get(),
+                ":actual_fee": &receipt.actual_fee,
+                ":execution_resources": &execution_resources,
+                ":revert_reason": &receipt.revert_reason(),
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    fn insert_messages(
+        &self,
+        block_number: BlockNumber,
+        transactions: &[(StarknetTransaction, Receipt)],
+    ) -> anyhow::Result<()> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            INSERT INTO transaction_messages
+                (block_number, transaction_idx, message_idx, from_address, to_address, payload)
+            VALUES (:block_number, :transaction_idx, :message_idx, :from_address, :to_address, :payload)
+            ",
+        )?;
+
+        for (transaction_idx, (_, receipt)) in transactions.iter().enumerate() {
+            for (message_idx, message) in receipt.l2_to_l1_messages.iter().enumerate() {
+                stmt.execute(named_params![
+                    ":block_number": &block_number,
+                    ":transaction_idx": &transaction_idx.try_into_sql_int()?,
+                    ":message_idx": &message_idx.try_into_sql_int()?,
+                    ":from_address": &message.from_address,
+                    ":to_address": &message.to_address,
+                    ":payload": &encode_felts(message.payload.iter().map(|elem| &elem.0)),
+                ])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn insert_senders(
+        &self,
+        block_number: BlockNumber,
+        transactions: &[(StarknetTransaction, Receipt)],
+    ) -> anyhow::Result<()> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            INSERT INTO transaction_senders (sender_address, block_number, transaction_idx)
+            VALUES (:sender_address, :block_number, :transaction_idx)
+            ",
+        )?;
+
+        for (transaction_idx, (transaction, _)) in transactions.iter().enumerate() {
+            stmt.execute(named_params![
+                ":sender_address": &transaction.variant.contract_address(),
+                ":block_number": &block_number,
+                ":transaction_idx": &transaction_idx.try_into_sql_int()?,
+            ])?;
+        }
+
+        Ok(())
     }
 
+    /// Also returns the index of the transaction in its block.
     fn query_transaction_by_hash(
         &self,
         hash: TransactionHash,
-    ) -> anyhow::Result<Option<(BlockNumber, StarknetTransaction, Receipt)>> {
+    ) -> anyhow::Result<Option<(BlockNumber, usize, StarknetTransaction, Receipt)>> {
         let mut stmt = self.inner().prepare_cached(
             r"
             SELECT transactions.block_number, transactions, idx
@@ -546,88 +801,87 @@ impl Transaction<'_> {
             return Ok(None);
         };
         let block_number = row.get_block_number(0)?;
-        let transactions = row.get_blob(1)?;
+        let transactions = decode_transactions(row.get_blob(1)?)?;
         let idx: usize = row.get_i64(2)?.try_into()?;
 
-        let transactions = compression::decompress_transactions(transactions)
-            .context("Decompressing transactions")?;
-        let transactions: dto::TransactionsWithReceiptsForBlock =
-            bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
-                .context("Deserializing transactions")?
-                .0;
-        let transactions = transactions.transactions_with_receipts();
-        let dto::TransactionWithReceiptV2 {
-            transaction,
-            receipt,
-        } = transactions.get(idx).context("Transaction not found")?;
-
-        Ok(Some((
-            block_number,
-            transaction.clone().into(),
-            receipt.clone().into(),
-        )))
+        let transaction: StarknetTransaction = transactions
+            .get(idx)
+            .context("Transaction not found")?
+            .clone()
+            .into();
+        let mut receipt = self
+            .query_receipts(block_number, Some(idx))?
+            .pop()
+            .context("Receipt not found")?;
+        receipt.transaction_hash = transaction.hash;
+        receipt.l2_to_l1_messages = self
+            .query_messages(block_number, Some(idx))?
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect();
+
+        Ok(Some((block_number, idx, transaction, receipt)))
     }
 
     fn query_transaction_and_events_by_hash(
         &self,
         hash: TransactionHash,
     ) -> anyhow::Result<Option<TransactionAndEventsByHash>> {
-        let mut stmt = self.inner().prepare_cached(
-            r"
-            SELECT transactions.block_number, transactions, events, idx
-            FROM transactions
-            JOIN transaction_hashes ON transactions.block_number = transaction_hashes.block_number
-            WHERE hash = ?
-            ",
-        )?;
-        let mut rows = stmt.query(params![&hash])?;
-        let Some(row) = rows.next()? else {
+        let Some((block_number, idx, transaction, receipt)) =
+            self.query_transaction_by_hash(hash)?
+        else {
             return Ok(None);
         };
-        let block_number = row.get_block_number(0)?;
-        let idx: usize = row.get_i64(3)?.try_into()?;
-        let transactions = row.get_blob(1)?;
-
-        let transactions = compression::decompress_transactions(transactions)
-            .context("Decompressing transactions")?;
-        let transactions: dto::TransactionsWithReceiptsForBlock =
-            bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
-                .context("Deserializing transactions")?
-                .0;
-        let transactions = transactions.transactions_with_receipts();
-
-        let events: Option<Vec<Vec<dto::Event>>> = match row.get_optional_blob(2)? {
-            Some(events) => {
-                let events =
-                    compression::decompress_events(events).context("Decompressing events")?;
-                let events: dto::EventsForBlock =
-                    bincode::serde::decode_from_slice(&events, bincode::config::standard())
-                        .context("Deserializing events")?
-                        .0;
-                Some(events.events())
-            }
-            None => None,
-        };
-        let dto::TransactionWithReceiptV2 {
-            transaction,
-            receipt,
-        } = transactions.get(idx).context("Transaction not found")?;
-        let events = match events {
-            Some(events) => {
-                let events = events.get(idx).context("Events missing")?;
-                Some(events.iter().cloned().map(Into::into).collect())
-            }
-            None => None,
-        };
-        Ok(Some((
-            block_number,
-            transaction.clone().into(),
-            receipt.clone().into(),
-            events,
-        )))
+        let events = self.query_events_by_transaction(block_number, idx)?;
+
+        Ok(Some((block_number, transaction, receipt, events)))
     }
 }
 
+/// Decompresses and deserializes a block's transactions blob.
+fn decode_transactions(blob: &[u8]) -> anyhow::Result<Vec<dto::TransactionV1>> {
+    let transactions =
+        compression::decompress_transactions(blob).context("Decompressing transactions")?;
+    let transactions: dto::TransactionsForBlock =
+        bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
+            .context("Deserializing transactions")?
+            .0;
+
+    Ok(transactions.transactions())
+}
+
+fn event_from_row(row: &rusqlite::Row<'_>) -> anyhow::Result<Event> {
+    Ok(Event {
+        from_address: row.get_contract_address(1)?,
+        keys: decode_felts(row.get_blob(2)?)?
+            .into_iter()
+            .map(EventKey)
+            .collect(),
+        data: decode_felts(row.get_blob(3)?)?
+            .into_iter()
+            .map(EventData)
+            .collect(),
+    })
+}
+
+/// Encodes a list of felts as their concatenated big-endian bytes.
+fn encode_felts<'a>(felts: impl ExactSizeIterator<Item = &'a Felt>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(felts.len() * 32);
+    for felt in felts {
+        bytes.extend_from_slice(felt.as_be_bytes());
+    }
+    bytes
+}
+
+fn decode_felts(bytes: &[u8]) -> anyhow::Result<Vec<Felt>> {
+    anyhow::ensure!(bytes.len() % 32 == 0, "Invalid felt list length");
+
+    bytes
+        .chunks_exact(32)
+        .map(|chunk| Felt::from_be_slice(chunk).context("Felt out of range"))
+        .collect()
+}
+
 pub(crate) mod dto {
     use std::fmt;
 
@@ -731,73 +985,6 @@ pub(crate) mod dto {
         }
     }
 
-    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
-    pub enum EventsForBlock {
-        V0 { events: Vec<Vec<Event>> },
-    }
-
-    impl EventsForBlock {
-        pub fn events(self) -> Vec<Vec<Event>> {
-            match self {
-                EventsForBlock::V0 { events } => events,
-            }
-        }
-    }
-
-    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
-    pub struct Event {
-        pub data: Vec<MinimalFelt>,
-        pub from_address: MinimalFelt,
-        pub keys: Vec<MinimalFelt>,
-    }
-
-    impl From<pathfinder_common::event::Event> for Event {
-        fn from(value: pathfinder_common::event::Event) -> Self {
-            let pathfinder_common::event::Event {
-                data,
-                from_address,
-                keys,
-            } = value;
-            Self {
-                data: data
-                    .into_iter()
-                    .map(|x| x.as_inner().to_owned().into())
-                    .collect(),
-                from_address: from_address.as_inner().to_owned().into(),
-                keys: keys
-                    .into_iter()
-                    .map(|x| x.as_inner().to_owned().into())
-                    .collect(),
-            }
-        }
-    }
-
-    impl From<Event> for pathfinder_common::event::Event {
-        fn from(value: Event) -> Self {
-            Self {
-                data: value
-                    .data
-                    .into_iter()
-                    .map(|x| EventData(x.into()))
-                    .collect(),
-                from_address: ContractAddress::new_or_panic(value.from_address.into()),
-                keys: value.keys.into_iter().map(|x| EventKey(x.into())).collect(),
-            }
-        }
-    }
-
-    /// Represents execution resources for L2 transaction.
-    #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
-    pub struct ExecutionResourcesV0 {
-        pub builtins: BuiltinCountersV0,
-        pub n_steps: u64,
-        pub n_memory_holes: u64,
-        pub data_availability: L1Gas,
-    }
-
     /// Represents execution resources for L2 transaction.
     #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
@@ -817,18 +1004,6 @@ pub(crate) mod dto {
         pub l1_data_gas: Option<u128>,
     }
 
-    impl From<ExecutionResourcesV0> for ExecutionResourcesV1 {
-        fn from(value: ExecutionResourcesV0) -> Self {
-            Self {
-                builtins: value.builtins.into(),
-                n_steps: value.n_steps,
-                n_memory_holes: value.n_memory_holes,
-                data_availability: value.data_availability,
-                total_gas_consumed: Default::default(),
-            }
-        }
-    }
-
     impl From<ExecutionResourcesV1> for pathfinder_common::receipt::ExecutionResources {
         fn from(value: ExecutionResourcesV1) -> Self {
             Self {
@@ -860,38 +1035,18 @@ pub(crate) mod dto {
     }
 
     impl From<&pathfinder_common::receipt::ExecutionResources> for ExecutionResourcesV1 {
-        fn from(value: &pathfinder_common::receipt::ExecutionResources) -> Self {
-            Self {
-                builtins: (&value.builtins).into(),
-                n_steps: value.n_steps,
-                n_memory_holes: value.n_memory_holes,
-                data_availability: L1Gas {
-                    l1_gas: Some(value.data_availability.l1_gas),
-                    l1_data_gas: Some(value.data_availability.l1_data_gas),
-                },
-                total_gas_consumed: L1Gas {
-                    l1_gas: Some(value.total_gas_consumed.l1_gas),
-                    l1_data_gas: Some(value.total_gas_consumed.l1_data_gas),
-                },
-            }
-        }
-    }
-
-    impl<T> Dummy<T> for ExecutionResourcesV0 {
-        fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &T, rng: &mut R) -> Self {
-            let (l1_gas, l1_data_gas) = if rng.gen() {
-                (Some(rng.next_u32() as u128), Some(rng.next_u32() as u128))
-            } else {
-                (None, None)
-            };
-
-            Self {
-                builtins: Faker.fake_with_rng(rng),
-                n_steps: rng.next_u32() as u64,
-                n_memory_holes: rng.next_u32() as u64,
+        fn from(value: &pathfinder_common::receipt::ExecutionResources) -> Self {
+            Self {
+                builtins: (&value.builtins).into(),
+                n_steps: value.n_steps,
+                n_memory_holes: value.n_memory_holes,
                 data_availability: L1Gas {
-                    l1_gas,
-                    l1_data_gas,
+                    l1_gas: Some(value.data_availability.l1_gas),
+                    l1_data_gas: Some(value.data_availability.l1_data_gas),
+                },
+                total_gas_consumed: L1Gas {
+                    l1_gas: Some(value.total_gas_consumed.l1_gas),
+                    l1_data_gas: Some(value.total_gas_consumed.l1_data_gas),
                 },
             }
         }
@@ -921,20 +1076,6 @@ pub(crate) mod dto {
         }
     }
 
-    #[derive(Copy, Clone, Default, Debug, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
-    pub struct BuiltinCountersV0 {
-        pub output: u64,
-        pub pedersen: u64,
-        pub range_check: u64,
-        pub ecdsa: u64,
-        pub bitwise: u64,
-        pub ec_op: u64,
-        pub keccak: u64,
-        pub poseidon: u64,
-        pub segment_arena: u64,
-    }
-
     #[derive(Copy, Clone, Default, Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
     pub struct BuiltinCountersV1 {
@@ -952,35 +1093,6 @@ pub(crate) mod dto {
         pub range_check96: u64,
     }
 
-    impl From<BuiltinCountersV0> for BuiltinCountersV1 {
-        fn from(value: BuiltinCountersV0) -> Self {
-            // Use deconstruction to ensure these structs remain in-sync.
-            let BuiltinCountersV0 {
-                output,
-                pedersen,
-                range_check,
-                ecdsa,
-                bitwise,
-                ec_op,
-                keccak,
-                poseidon,
-                segment_arena,
-            } = value;
-            Self {
-                output,
-                pedersen,
-                range_check,
-                ecdsa,
-                bitwise,
-                ec_op,
-                keccak,
-                poseidon,
-                segment_arena,
-                ..Default::default()
-            }
-        }
-    }
-
     impl From<BuiltinCountersV1> for pathfinder_common::receipt::BuiltinCounters {
         fn from(value: BuiltinCountersV1) -> Self {
             // Use deconstruction to ensure these structs remain in-sync.
@@ -1049,22 +1161,6 @@ pub(crate) mod dto {
         }
     }
 
-    impl<T> Dummy<T> for BuiltinCountersV0 {
-        fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &T, rng: &mut R) -> Self {
-            Self {
-                output: rng.next_u32() as u64,
-                pedersen: rng.next_u32() as u64,
-                range_check: rng.next_u32() as u64,
-                ecdsa: rng.next_u32() as u64,
-                bitwise: rng.next_u32() as u64,
-                ec_op: rng.next_u32() as u64,
-                keccak: rng.next_u32() as u64,
-                poseidon: rng.next_u32() as u64,
-                segment_arena: 0, // Not used in p2p
-            }
-        }
-    }
-
     impl<T> Dummy<T> for BuiltinCountersV1 {
         fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &T, rng: &mut R) -> Self {
             Self {
@@ -1084,15 +1180,6 @@ pub(crate) mod dto {
         }
     }
 
-    /// Represents deserialized L2 to L1 message.
-    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Dummy)]
-    #[serde(deny_unknown_fields)]
-    pub struct L2ToL1MessageV0 {
-        pub from_address: MinimalFelt,
-        pub payload: Vec<MinimalFelt>,
-        pub to_address: EthereumAddress,
-    }
-
     /// Represents deserialized L2 to L1 message.
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
@@ -1114,18 +1201,6 @@ pub(crate) mod dto {
         }
     }
 
-    impl From<L2ToL1MessageV0> for L2ToL1MessageV1 {
-        fn from(value: L2ToL1MessageV0) -> Self {
-            Self {
-                from_address: value.from_address,
-                payload: value.payload,
-                to_address: Felt::from_be_slice(value.to_address.0.as_bytes())
-                    .expect("H160 will always fit into a Felt")
-                    .into(),
-            }
-        }
-    }
-
     impl From<L2ToL1MessageV1> for pathfinder_common::receipt::L2ToL1Message {
         fn from(value: L2ToL1MessageV1) -> Self {
             let L2ToL1MessageV1 {
@@ -1168,30 +1243,6 @@ pub(crate) mod dto {
         Reverted { reason: String },
     }
 
-    /// Represents deserialized L2 transaction receipt data.
-    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Dummy)]
-    #[serde(deny_unknown_fields)]
-    pub struct ReceiptV0 {
-        pub actual_fee: MinimalFelt,
-        pub execution_resources: Option<ExecutionResourcesV0>,
-        pub l2_to_l1_messages: Vec<L2ToL1MessageV0>,
-        pub transaction_hash: MinimalFelt,
-        pub transaction_index: TransactionIndex,
-        pub execution_status: ExecutionStatus,
-    }
-
-    /// Represents deserialized L2 transaction receipt data.
-    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Dummy)]
-    #[serde(deny_unknown_fields)]
-    pub struct ReceiptV1 {
-        pub actual_fee: MinimalFelt,
-        pub execution_resources: Option<ExecutionResourcesV0>,
-        pub l2_to_l1_messages: Vec<L2ToL1MessageV1>,
-        pub transaction_hash: MinimalFelt,
-        pub transaction_index: TransactionIndex,
-        pub execution_status: ExecutionStatus,
-    }
-
     /// Represents deserialized L2 transaction receipt data.
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Dummy)]
     #[serde(deny_unknown_fields)]
@@ -1204,40 +1255,6 @@ pub(crate) mod dto {
         pub execution_status: ExecutionStatus,
     }
 
-    impl From<ReceiptV0> for ReceiptV2 {
-        fn from(value: ReceiptV0) -> Self {
-            Self {
-                actual_fee: value.actual_fee,
-                execution_resources: value.execution_resources.map(Into::into),
-                l2_to_l1_messages: value
-                    .l2_to_l1_messages
-                    .into_iter()
-                    .map(Into::into)
-                    .collect(),
-                transaction_hash: value.transaction_hash,
-                transaction_index: value.transaction_index,
-                execution_status: value.execution_status,
-            }
-        }
-    }
-
-    impl From<ReceiptV1> for ReceiptV2 {
-        fn from(value: ReceiptV1) -> Self {
-            Self {
-                actual_fee: value.actual_fee,
-                execution_resources: value.execution_resources.map(Into::into),
-                l2_to_l1_messages: value
-                    .l2_to_l1_messages
-                    .into_iter()
-                    .map(Into::into)
-                    .collect(),
-                transaction_hash: value.transaction_hash,
-                transaction_index: value.transaction_index,
-                execution_status: value.execution_status,
-            }
-        }
-    }
-
     impl From<ReceiptV2> for pathfinder_common::receipt::Receipt {
         fn from(value: ReceiptV2) -> Self {
             use pathfinder_common::receipt as common;
@@ -1358,106 +1375,22 @@ pub(crate) mod dto {
         }
     }
 
+    /// The transactions of a block, whose receipts are stored in the
+    /// `transaction_receipts` table.
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
-    pub enum TransactionsWithReceiptsForBlock {
-        V0 {
-            transactions_with_receipts: Vec<TransactionWithReceiptV0>,
-        },
-        V1 {
-            transactions_with_receipts: Vec<TransactionWithReceiptV1>,
-        },
-        V2 {
-            transactions_with_receipts: Vec<TransactionWithReceiptV2>,
-        },
-    }
-
-    impl TransactionsWithReceiptsForBlock {
-        pub fn transactions_with_receipts(self) -> Vec<TransactionWithReceiptV2> {
-            match self {
-                TransactionsWithReceiptsForBlock::V0 {
-                    transactions_with_receipts: v0,
-                } => v0.into_iter().map(Into::into).collect(),
-                TransactionsWithReceiptsForBlock::V1 {
-                    transactions_with_receipts: v1,
-                } => v1.into_iter().map(Into::into).collect(),
-                TransactionsWithReceiptsForBlock::V2 {
-                    transactions_with_receipts,
-                } => transactions_with_receipts,
-            }
-        }
-    }
-
-    #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-    pub struct TransactionWithReceiptV0 {
-        pub transaction: TransactionV0,
-        pub receipt: ReceiptV0,
-    }
-
-    #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-    pub struct TransactionWithReceiptV1 {
-        pub transaction: TransactionV1,
-        pub receipt: ReceiptV1,
-    }
-
-    #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-    pub struct TransactionWithReceiptV2 {
-        pub transaction: TransactionV1,
-        pub receipt: ReceiptV2,
-    }
-
-    impl From<TransactionWithReceiptV0> for TransactionWithReceiptV2 {
-        fn from(v0: TransactionWithReceiptV0) -> Self {
-            Self {
-                transaction: v0.transaction.into(),
-                receipt: v0.receipt.into(),
-            }
-        }
+    pub enum TransactionsForBlock {
+        V0 { transactions: Vec<TransactionV1> },
     }
 
-    impl From<TransactionWithReceiptV1> for TransactionWithReceiptV2 {
-        fn from(v0: TransactionWithReceiptV1) -> Self {
-            Self {
-                transaction: v0.transaction,
-                receipt: v0.receipt.into(),
+    impl TransactionsForBlock {
+        pub fn transactions(self) -> Vec<TransactionV1> {
+            match self {
+                TransactionsForBlock::V0 { transactions } => transactions,
             }
         }
     }
 
-    #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Dummy)]
-    #[serde(deny_unknown_fields)]
-    pub struct TransactionV0 {
-        hash: MinimalFelt,
-        variant: TransactionVariantV0,
-    }
-
-    impl TransactionV0 {
-        /// Returns hash of the transaction
-        pub fn hash(&self) -> TransactionHash {
-            TransactionHash(self.hash.to_owned().into())
-        }
-    }
-
-    /// Represents deserialized L2 transaction data.
-    #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Dummy)]
-    #[serde(deny_unknown_fields)]
-    pub enum TransactionVariantV0 {
-        DeclareV0(DeclareTransactionV0V1),
-        DeclareV1(DeclareTransactionV0V1),
-        DeclareV2(DeclareTransactionV2),
-        DeclareV3(DeclareTransactionV3),
-        // FIXME regenesis: remove Deploy txn type after regenesis
-        // We are keeping this type of transaction until regenesis
-        // only to support older pre-0.11.0 blocks
-        Deploy(DeployTransaction),
-        DeployAccountV1(DeployAccountTransactionV1),
-        DeployAccountV3(DeployAccountTransactionV3),
-        InvokeV0(InvokeTransactionV0),
-        InvokeV1(InvokeTransactionV1),
-        InvokeV3(InvokeTransactionV3),
-        L1HandlerV0(L1HandlerTransactionV0),
-    }
-
     #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Dummy)]
     #[serde(deny_unknown_fields)]
     pub struct TransactionV1 {
@@ -1493,51 +1426,6 @@ pub(crate) mod dto {
         L1HandlerV0(L1HandlerTransactionV0),
     }
 
-    impl From<TransactionVariantV0> for TransactionVariantV1 {
-        fn from(value: TransactionVariantV0) -> Self {
-            match value {
-                TransactionVariantV0::DeclareV0(tx) => Self::DeclareV0(tx),
-                TransactionVariantV0::DeclareV1(tx) => Self::DeclareV1(tx),
-                TransactionVariantV0::DeclareV2(tx) => Self::DeclareV2(tx),
-                TransactionVariantV0::DeclareV3(tx) => Self::DeclareV3(tx),
-                TransactionVariantV0::Deploy(tx) if tx.version.0 == Felt::ZERO => {
-                    Self::DeployV0(DeployTransactionV0 {
-                        contract_address: tx.contract_address,
-                        contract_address_salt: tx.contract_address_salt,
-                        class_hash: tx.class_hash,
-                        constructor_calldata: tx.constructor_calldata,
-                    })
-                }
-                TransactionVariantV0::Deploy(tx) if tx.version.0 == Felt::ONE => {
-                    Self::DeployV1(DeployTransactionV1 {
-                        contract_address: tx.contract_address,
-                        contract_address_salt: tx.contract_address_salt,
-                        class_hash: tx.class_hash,
-                        constructor_calldata: tx.constructor_calldata,
-                    })
-                }
-                TransactionVariantV0::Deploy(tx) => {
-                    panic!("Unexpected deploy transaction version {}", tx.version.0)
-                }
-                TransactionVariantV0::DeployAccountV1(tx) => Self::DeployAccountV1(tx),
-                TransactionVariantV0::DeployAccountV3(tx) => Self::DeployAccountV3(tx),
-                TransactionVariantV0::InvokeV0(tx) => Self::InvokeV0(tx),
-                TransactionVariantV0::InvokeV1(tx) => Self::InvokeV1(tx),
-                TransactionVariantV0::InvokeV3(tx) => Self::InvokeV3(tx),
-                TransactionVariantV0::L1HandlerV0(tx) => Self::L1HandlerV0(tx),
-            }
-        }
-    }
-
-    impl From<TransactionV0> for TransactionV1 {
-        fn from(value: TransactionV0) -> Self {
-            Self {
-                hash: value.hash,
-                variant: value.variant.into(),
-            }
-        }
-    }
-
     impl From<&pathfinder_common::transaction::Transaction> for TransactionV1 {
         fn from(value: &pathfinder_common::transaction::Transaction) -> Self {
             use pathfinder_common::transaction::TransactionVariant::*;
@@ -2254,42 +2142,6 @@ pub(crate) mod dto {
         }
     }
 
-    /// Represents deserialized L2 deploy transaction data.
-    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
-    pub struct DeployTransaction {
-        pub contract_address: MinimalFelt,
-        pub version: MinimalFelt,
-        pub contract_address_salt: MinimalFelt,
-        pub class_hash: MinimalFelt,
-        pub constructor_calldata: Vec<MinimalFelt>,
-    }
-
-    impl<T> Dummy<T> for DeployTransaction {
-        fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &T, rng: &mut R) -> Self {
-            let class_hash: MinimalFelt = Faker.fake_with_rng(rng);
-            let constructor_calldata: Vec<MinimalFelt> = Faker.fake_with_rng(rng);
-            let contract_address_salt: MinimalFelt = Faker.fake_with_rng(rng);
-
-            let contract_address = ContractAddress::deployed_contract_address(
-                constructor_calldata.iter().map(|f| CallParam(f.0)),
-                &ContractAddressSalt(contract_address_salt.0),
-                &ClassHash(class_hash.0),
-            )
-            .as_inner()
-            .to_owned()
-            .into();
-
-            Self {
-                version: Felt::from_u64(rng.gen_range(0..=1)).into(),
-                contract_address,
-                contract_address_salt,
-                class_hash,
-                constructor_calldata,
-            }
-        }
-    }
-
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields)]
     pub struct DeployTransactionV0 {
@@ -2893,4 +2745,150 @@ mod tests {
             .unwrap();
         assert_eq!(invalid, None);
     }
+
+    #[test]
+    fn transactions_by_sender() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let sender = body[0].0.variant.contract_address();
+        let header1 = header
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block hash 1"));
+        tx.insert_block_header(&header1).unwrap();
+        let body1 = [
+            transaction_hash_bytes!(b"sender tx 0"),
+            transaction_hash_bytes!(b"sender tx 1"),
+        ]
+        .map(|hash| {
+            (
+                StarknetTransaction {
+                    hash,
+                    ..body[0].0.clone()
+                },
+                Receipt {
+                    transaction_hash: hash,
+                    ..Default::default()
+                },
+            )
+        });
+        tx.insert_transaction_data(header1.number, &body1, None)
+            .unwrap();
+
+        let expected = vec![
+            (header.number, 0, body[0].0.hash),
+            (header1.number, 0, body1[0].0.hash),
+            (header1.number, 1, body1[1].0.hash),
+        ];
+        let result = tx
+            .transactions_by_sender(sender, header.number, header1.number, None, 10)
+            .unwrap();
+        assert_eq!(result, expected);
+
+        let result = tx
+            .transactions_by_sender(sender, header.number, header1.number, None, 2)
+            .unwrap();
+        assert_eq!(result, expected[..2]);
+
+        let result = tx
+            .transactions_by_sender(
+                sender,
+                header.number,
+                header1.number,
+                Some((header1.number, 0)),
+                10,
+            )
+            .unwrap();
+        assert_eq!(result, expected[2..]);
+
+        let result = tx
+            .transactions_by_sender(sender, header1.number, header1.number, None, 10)
+            .unwrap();
+        assert_eq!(result, expected[1..]);
+
+        let result = tx
+            .transactions_by_sender(
+                contract_address_bytes!(b"no transactions"),
+                header.number,
+                header1.number,
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(result, vec![]);
+    }
+
+    #[test]
+    fn events_and_messages() {
+        let (mut db, header, mut body) = setup();
+        let tx = db.transaction().unwrap();
+
+        // Store a second block whose events are only added later on.
+        let header = header
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block hash 1"));
+        tx.insert_block_header(&header).unwrap();
+        for (i, (transaction, receipt)) in body.iter_mut().enumerate() {
+            transaction.hash = TransactionHash(Felt::from(i as u64 + 1000));
+            receipt.transaction_hash = transaction.hash;
+        }
+        body[1].1.l2_to_l1_messages = vec![
+            L2ToL1Message {
+                from_address: contract_address_bytes!(b"message 0 from"),
+                payload: vec![l2_to_l1_message_payload_elem_bytes!(b"message 0 payload")],
+                to_address: contract_address_bytes!(b"message 0 to"),
+            },
+            L2ToL1Message {
+                from_address: contract_address_bytes!(b"message 1 from"),
+                payload: vec![],
+                to_address: contract_address_bytes!(b"message 1 to"),
+            },
+        ];
+        tx.insert_transaction_data(header.number, &body, None)
+            .unwrap();
+
+        let result = tx.transaction_with_receipt(body[1].0.hash);
+        assert!(result.is_err(), "Events have not been stored yet");
+        assert_eq!(tx.events_for_block(header.number.into()).unwrap(), None);
+
+        let mut events = body.iter().map(|_| vec![]).collect::<Vec<_>>();
+        events[1] = vec![Event {
+            data: vec![event_data_bytes!(b"event data")],
+            from_address: contract_address_bytes!(b"event from"),
+            keys: vec![
+                event_key_bytes!(b"event key 0"),
+                event_key_bytes!(b"event key 1"),
+            ],
+        }];
+        tx.update_events(header.number, events.clone()).unwrap();
+
+        let (transaction, receipt, transaction_events, _) = tx
+            .transaction_with_receipt(body[1].0.hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(transaction, body[1].0);
+        assert_eq!(receipt, body[1].1);
+        assert_eq!(transaction_events, events[1]);
+
+        let expected = body
+            .iter()
+            .cloned()
+            .zip(events.clone())
+            .map(|((transaction, receipt), events)| (transaction, receipt, events))
+            .collect();
+        assert_eq!(
+            tx.transaction_data_for_block(header.number.into()).unwrap(),
+            Some(expected)
+        );
+
+        let expected = body
+            .iter()
+            .map(|(transaction, _)| transaction.hash)
+            .zip(events)
+            .collect();
+        assert_eq!(
+            tx.events_for_block(header.number.into()).unwrap(),
+            Some(expected)
+        );
+    }
 }
@@ -90,6 +90,61 @@ impl Transaction<'_> {
         .map_err(Into::into)
     }
 
+    /// Returns the storage roots of `contract` in `from..=to`, as the blocks at
+    /// which the root changed along with the new root, in ascending order.
+    ///
+    /// The first entry is the root in effect at `from`, which may have been
+    /// set before `from`. Empty tries have a root of zero, and roots whose trie
+    /// has been pruned are `None`.
+    pub fn contract_root_history(
+        &self,
+        contract: ContractAddress,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(BlockNumber, Option<ContractRoot>)>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT contract_roots.block_number, contract_roots.root_index, trie_contracts.hash
+            FROM contract_roots
+            LEFT JOIN trie_contracts ON trie_contracts.idx = contract_roots.root_index
+            WHERE contract_address = :contract_address
+                AND block_number >= COALESCE(
+                    (
+                        SELECT block_number FROM contract_roots
+                        WHERE contract_address = :contract_address AND block_number <= :from
+                        ORDER BY block_number DESC LIMIT 1
+                    ),
+                    :from
+                )
+                AND block_number <= :to
+            ORDER BY block_number
+            LIMIT :limit
+            ",
+        )?;
+
+        let history = stmt
+            .query_map(
+                named_params![
+                    ":contract_address": &contract,
+                    ":from": &from,
+                    ":to": &to,
+                    ":limit": &limit.try_into_sql_int()?,
+                ],
+                |row| {
+                    let block_number = row.get_block_number(0)?;
+                    let root = match row.get_optional_i64(1)? {
+                        None => Some(ContractRoot::ZERO),
+                        Some(_) => row.get_optional_felt(2)?.map(ContractRoot),
+                    };
+                    Ok((block_number, root))
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(history)
+    }
+
     pub fn insert_class_root(
         &self,
         block_number: BlockNumber,
@@ -138,16 +193,20 @@ impl Transaction<'_> {
         Ok(())
     }
 
+    /// Stores the state hash of `contract` at `block_number` along with the
+    /// class hash and nonce it was computed from.
     pub fn insert_contract_state_hash(
         &self,
         block_number: BlockNumber,
         contract: ContractAddress,
         state_hash: ContractStateHash,
+        class_hash: ClassHash,
+        nonce: ContractNonce,
     ) -> anyhow::Result<()> {
         self.inner().execute(
             "INSERT OR REPLACE INTO contract_state_hashes(block_number, contract_address, \
-             state_hash) VALUES(?,?,?)",
-            params![&block_number, &contract, &state_hash],
+             state_hash, class_hash, nonce) VALUES(?,?,?,?,?)",
+            params![&block_number, &contract, &state_hash, &class_hash, &nonce],
         )?;
 
         if let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode {
@@ -202,6 +261,34 @@ impl Transaction<'_> {
             .map_err(Into::into)
     }
 
+    /// Returns the class hash and nonce of the contract state hash of
+    /// `contract` at `block_number`. Together with the contract's root this
+    /// is the preimage of its state hash.
+    ///
+    /// Returns `None` if the contract has no state at this block, or if the
+    /// state was stored before class hashes and nonces were recorded.
+    pub fn contract_state_preimage(
+        &self,
+        block_number: BlockNumber,
+        contract: ContractAddress,
+    ) -> anyhow::Result<Option<(ClassHash, ContractNonce)>> {
+        let preimage = self
+            .inner()
+            .query_row(
+                "SELECT class_hash, nonce FROM contract_state_hashes WHERE contract_address = ? \
+                 AND block_number <= ? ORDER BY block_number DESC LIMIT 1",
+                params![&contract, &block_number],
+                |row| {
+                    let class_hash = row.get_optional_class_hash(0)?;
+                    let nonce = row.get_optional_nonce(1)?;
+                    Ok(class_hash.zip(nonce))
+                },
+            )
+            .optional()?;
+
+        Ok(preimage.flatten())
+    }
+
     pub fn insert_storage_root(
         &self,
         block_number: BlockNumber,
@@ -350,6 +437,23 @@ impl Transaction<'_> {
         self.trie_node_hash(index, "trie_storage")
     }
 
+    /// Returns the earliest block whose Merkle tries are still available.
+    ///
+    /// `None` if trie history is not pruned or there are no blocks yet.
+    pub fn earliest_unpruned_trie_block(&self) -> anyhow::Result<Option<BlockNumber>> {
+        let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode else {
+            return Ok(None);
+        };
+        let Some(latest) = self.block_number(BlockId::Latest)? else {
+            return Ok(None);
+        };
+        Ok(Some(
+            latest
+                .checked_sub(num_blocks_kept)
+                .unwrap_or(BlockNumber::GENESIS),
+        ))
+    }
+
     /// Prune tries by removing nodes that are no longer needed at the given
     /// block.
     pub fn prune_tries(&self) -> anyhow::Result<()> {
@@ -1013,6 +1117,107 @@ mod tests {
         assert_eq!(hash1, None);
     }
 
+    #[test]
+    fn contract_root_history() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+
+        let contract = contract_address_bytes!(b"contract");
+
+        let root0 = contract_root_bytes!(b"root 0");
+        let update = TrieUpdate {
+            nodes_added: vec![(root0.0, Node::LeafBinary)],
+            ..Default::default()
+        };
+        let idx0 = tx
+            .insert_contract_trie(&update, BlockNumber::GENESIS)
+            .unwrap();
+        tx.insert_contract_root(BlockNumber::GENESIS, contract, idx0)
+            .unwrap();
+
+        let root2 = contract_root_bytes!(b"root 2");
+        let update = TrieUpdate {
+            nodes_added: vec![(root2.0, Node::LeafBinary)],
+            ..Default::default()
+        };
+        let idx2 = tx
+            .insert_contract_trie(&update, BlockNumber::new_or_panic(2))
+            .unwrap();
+        tx.insert_contract_root(BlockNumber::new_or_panic(2), contract, idx2)
+            .unwrap();
+        tx.insert_contract_root(
+            BlockNumber::new_or_panic(4),
+            contract,
+            RootIndexUpdate::TrieEmpty,
+        )
+        .unwrap();
+        // Points to a trie node which does not exist, as if it had been pruned.
+        tx.insert_contract_root(
+            BlockNumber::new_or_panic(6),
+            contract,
+            RootIndexUpdate::Updated(999),
+        )
+        .unwrap();
+
+        let history = tx
+            .contract_root_history(
+                contract,
+                BlockNumber::GENESIS,
+                BlockNumber::new_or_panic(10),
+                10,
+            )
+            .unwrap();
+        assert_eq!(
+            history,
+            vec![
+                (BlockNumber::GENESIS, Some(root0)),
+                (BlockNumber::new_or_panic(2), Some(root2)),
+                (BlockNumber::new_or_panic(4), Some(ContractRoot::ZERO)),
+                (BlockNumber::new_or_panic(6), None),
+            ]
+        );
+
+        // The root in effect at the start of the range is included.
+        let history = tx
+            .contract_root_history(
+                contract,
+                BlockNumber::new_or_panic(3),
+                BlockNumber::new_or_panic(5),
+                10,
+            )
+            .unwrap();
+        assert_eq!(
+            history,
+            vec![
+                (BlockNumber::new_or_panic(2), Some(root2)),
+                (BlockNumber::new_or_panic(4), Some(ContractRoot::ZERO)),
+            ]
+        );
+
+        let history = tx
+            .contract_root_history(
+                contract,
+                BlockNumber::GENESIS,
+                BlockNumber::new_or_panic(10),
+                1,
+            )
+            .unwrap();
+        assert_eq!(history, vec![(BlockNumber::GENESIS, Some(root0))]);
+
+        let history = tx
+            .contract_root_history(
+                contract_address_bytes!(b"missing"),
+                BlockNumber::GENESIS,
+                BlockNumber::new_or_panic(10),
+                10,
+            )
+            .unwrap();
+        assert_eq!(history, vec![]);
+    }
+
     #[rstest::rstest]
     #[case::binary(StoredNode::Binary {
         left: 12, right: 34
@@ -1051,9 +1256,17 @@ mod tests {
 
         let contract = contract_address_bytes!(b"address");
         let state_hash = contract_state_hash_bytes!(b"state hash");
+        let class_hash = class_hash_bytes!(b"class hash");
+        let nonce = contract_nonce_bytes!(b"nonce");
 
-        tx.insert_contract_state_hash(BlockNumber::GENESIS + 2, contract, state_hash)
-            .unwrap();
+        tx.insert_contract_state_hash(
+            BlockNumber::GENESIS + 2,
+            contract,
+            state_hash,
+            class_hash,
+            nonce,
+        )
+        .unwrap();
 
         let result = tx
             .contract_state_hash(BlockNumber::GENESIS, contract)
@@ -1070,6 +1283,16 @@ mod tests {
             .unwrap();
         assert_eq!(result, Some(state_hash));
 
+        let result = tx
+            .contract_state_preimage(BlockNumber::GENESIS, contract)
+            .unwrap();
+        assert!(result.is_none());
+
+        let result = tx
+            .contract_state_preimage(BlockNumber::GENESIS + 10, contract)
+            .unwrap();
+        assert_eq!(result, Some((class_hash, nonce)));
+
         let result = tx
             .contract_state_hash(
                 BlockNumber::GENESIS + 2,
@@ -1522,12 +1745,20 @@ mod tests {
         let tx = db.transaction().unwrap();
 
         let contract = contract_address!("0xdeadbeef");
-        tx.insert_contract_state_hash(BlockNumber::GENESIS, contract, contract_state_hash!("0x01"))
-            .unwrap();
+        tx.insert_contract_state_hash(
+            BlockNumber::GENESIS,
+            contract,
+            contract_state_hash!("0x01"),
+            ClassHash::ZERO,
+            ContractNonce::ZERO,
+        )
+        .unwrap();
         tx.insert_contract_state_hash(
             BlockNumber::new_or_panic(1),
             contract,
             contract_state_hash!("0x02"),
+            ClassHash::ZERO,
+            ContractNonce::ZERO,
         )
         .unwrap();
         // no new state hash for block 2
@@ -1535,6 +1766,8 @@ mod tests {
             BlockNumber::new_or_panic(3),
             contract,
             contract_state_hash!("0x03"),
+            ClassHash::ZERO,
+            ContractNonce::ZERO,
         )
         .unwrap();
 
@@ -1566,12 +1799,20 @@ mod tests {
         let tx = db.transaction().unwrap();
 
         let contract = contract_address!("0xdeadbeef");
-        tx.insert_contract_state_hash(BlockNumber::GENESIS, contract, contract_state_hash!("0x01"))
-            .unwrap();
+        tx.insert_contract_state_hash(
+            BlockNumber::GENESIS,
+            contract,
+            contract_state_hash!("0x01"),
+            ClassHash::ZERO,
+            ContractNonce::ZERO,
+        )
+        .unwrap();
         tx.insert_contract_state_hash(
             BlockNumber::new_or_panic(1),
             contract,
             contract_state_hash!("0x02"),
+            ClassHash::ZERO,
+            ContractNonce::ZERO,
         )
         .unwrap();
 
@@ -1710,4 +1951,43 @@ mod tests {
             Some(2)
         );
     }
+
+    #[test]
+    fn earliest_unpruned_trie_block() {
+        let mut db = crate::StorageBuilder::in_memory_with_trie_pruning(TriePruneMode::Prune {
+            num_blocks_kept: 2,
+        })
+        .unwrap()
+        .connection()
+        .unwrap();
+        let tx = db.transaction().unwrap();
+
+        assert_eq!(tx.earliest_unpruned_trie_block().unwrap(), None);
+
+        tx.insert_block_header(&BlockHeader {
+            number: BlockNumber::GENESIS + 1,
+            hash: block_hash_bytes!(b"block 1"),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            tx.earliest_unpruned_trie_block().unwrap(),
+            Some(BlockNumber::GENESIS)
+        );
+
+        tx.insert_block_header(&BlockHeader {
+            number: BlockNumber::GENESIS + 5,
+            hash: block_hash_bytes!(b"block 5"),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            tx.earliest_unpruned_trie_block().unwrap(),
+            Some(BlockNumber::GENESIS + 3)
+        );
+
+        // Archive nodes keep all trie history.
+        tx.trie_prune_mode = TriePruneMode::Archive;
+        assert_eq!(tx.earliest_unpruned_trie_block().unwrap(), None);
+    }
 }
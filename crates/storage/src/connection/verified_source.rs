@@ -0,0 +1,98 @@
+//! Verified source metadata of classes, imported from contract verification
+//! providers.
+use anyhow::Context;
+use pathfinder_common::ClassHash;
+
+use crate::prelude::*;
+
+/// Metadata describing the verified source code of a class.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedSource {
+    pub compiler_version: String,
+    /// Hash of the verified sources, as reported by the provider.
+    pub source_hash: String,
+    pub repository_url: Option<String>,
+    /// The verification provider this metadata was imported from.
+    pub provider: String,
+}
+
+impl Transaction<'_> {
+    /// Inserts verified source metadata for `class_hash`, replacing any
+    /// previously imported metadata.
+    pub fn upsert_verified_source(
+        &self,
+        class_hash: ClassHash,
+        source: &VerifiedSource,
+    ) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                r"INSERT OR REPLACE INTO verified_sources
+                (class_hash, compiler_version, source_hash, repository_url, provider)
+            VALUES
+                (:class_hash, :compiler_version, :source_hash, :repository_url, :provider)",
+                named_params! {
+                    ":class_hash": &class_hash,
+                    ":compiler_version": &source.compiler_version,
+                    ":source_hash": &source.source_hash,
+                    ":repository_url": &source.repository_url,
+                    ":provider": &source.provider,
+                },
+            )
+            .context("Inserting verified source")?;
+
+        Ok(())
+    }
+
+    pub fn verified_source(&self, class_hash: ClassHash) -> anyhow::Result<Option<VerifiedSource>> {
+        self.inner()
+            .query_row(
+                "SELECT compiler_version, source_hash, repository_url, provider FROM \
+                 verified_sources WHERE class_hash = ?",
+                params![&class_hash],
+                |row| {
+                    Ok(VerifiedSource {
+                        compiler_version: row.get(0)?,
+                        source_hash: row.get(1)?,
+                        repository_url: row.get(2)?,
+                        provider: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+            .context("Querying verified source")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn upsert_replaces_existing() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let class_hash = class_hash_bytes!(b"class");
+        let mut source = VerifiedSource {
+            compiler_version: "2.6.3".to_owned(),
+            source_hash: "0xabcdef".to_owned(),
+            repository_url: None,
+            provider: "provider".to_owned(),
+        };
+
+        assert_eq!(tx.verified_source(class_hash).unwrap(), None);
+
+        tx.upsert_verified_source(class_hash, &source).unwrap();
+        assert_eq!(
+            tx.verified_source(class_hash).unwrap(),
+            Some(source.clone())
+        );
+
+        source.repository_url = Some("https://example.com/repo".to_owned());
+        tx.upsert_verified_source(class_hash, &source).unwrap();
+        assert_eq!(tx.verified_source(class_hash).unwrap(), Some(source));
+    }
+}
@@ -9,8 +9,11 @@ mod bloom;
 mod connection;
 pub mod fake;
 mod params;
+pub mod repack;
 mod schema;
+pub mod snapshot;
 pub mod test_utils;
+mod tuning;
 
 use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
@@ -22,6 +25,7 @@ use pathfinder_common::{BlockHash, BlockNumber};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{OpenFlags, OptionalExtension};
+pub use tuning::{SqliteTuning, StorageProfile, TempStore};
 
 /// Sqlite key used for the PRAGMA user version.
 const VERSION_KEY: &str = "user_version";
@@ -95,8 +99,10 @@ struct Inner {
 pub struct StorageManager {
     database_path: PathBuf,
     journal_mode: JournalMode,
+    tuning: SqliteTuning,
     bloom_filter_cache: Arc<bloom::Cache>,
     trie_prune_mode: TriePruneMode,
+    read_only: bool,
 }
 
 impl std::fmt::Debug for StorageManager {
@@ -104,7 +110,9 @@ impl std::fmt::Debug for StorageManager {
         f.debug_struct("StorageManager")
             .field("database_path", &self.database_path)
             .field("journal_mode", &self.journal_mode)
+            .field("tuning", &self.tuning)
             .field("trie_prune_mode", &self.trie_prune_mode)
+            .field("read_only", &self.read_only)
             .finish()
     }
 }
@@ -116,9 +124,10 @@ impl StorageManager {
         open_flags: OpenFlags,
     ) -> anyhow::Result<Storage> {
         let journal_mode = self.journal_mode;
+        let tuning = self.tuning;
         let pool_manager = SqliteConnectionManager::file(&self.database_path)
             .with_flags(open_flags)
-            .with_init(move |connection| setup_connection(connection, journal_mode));
+            .with_init(move |connection| setup_connection(connection, journal_mode, tuning));
         let pool = Pool::builder()
             .max_size(capacity.get())
             .build(pool_manager)?;
@@ -131,7 +140,12 @@ impl StorageManager {
         }))
     }
 
+    /// Creates a pool of read-write connections, or of read-only connections
+    /// if the database was [opened read-only](StorageBuilder::open_read_only).
     pub fn create_pool(&self, capacity: NonZeroU32) -> anyhow::Result<Storage> {
+        if self.read_only {
+            return self.create_read_only_pool(capacity);
+        }
         self.create_pool_with_flags(capacity, OpenFlags::default())
     }
 
@@ -146,6 +160,7 @@ impl StorageManager {
 pub struct StorageBuilder {
     database_path: PathBuf,
     journal_mode: JournalMode,
+    tuning: SqliteTuning,
     bloom_filter_cache_size: usize,
     trie_prune_mode: Option<TriePruneMode>,
 }
@@ -155,6 +170,7 @@ impl StorageBuilder {
         Self {
             database_path,
             journal_mode: JournalMode::WAL,
+            tuning: SqliteTuning::default(),
             bloom_filter_cache_size: 16,
             trie_prune_mode: None,
         }
@@ -165,6 +181,11 @@ impl StorageBuilder {
         self
     }
 
+    pub fn tuning(mut self, tuning: SqliteTuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
     pub fn bloom_filter_cache_size(mut self, bloom_filter_cache_size: usize) -> Self {
         self.bloom_filter_cache_size = bloom_filter_cache_size;
         self
@@ -238,12 +259,19 @@ impl StorageBuilder {
                 )
                 .context("Opening DB for migration")?;
 
+        // The page size can only be changed before the database is written to.
+        if is_new_database {
+            connection
+                .pragma_update(None, "page_size", self.tuning.page_size)
+                .context("Setting page size")?;
+        }
+
         // Migration is done with rollback journal mode. Otherwise dropped tables
         // get copied into the WAL which is prohibitively expensive for large
         // tables.
         setup_journal_mode(&mut connection, JournalMode::Rollback)
             .context("Setting journal mode to rollback")?;
-        setup_connection(&mut connection, JournalMode::Rollback)
+        setup_connection(&mut connection, JournalMode::Rollback, self.tuning)
             .context("Setting up database connection")?;
 
         migrate_database(&mut connection).context("Migrate database")?;
@@ -267,8 +295,75 @@ impl StorageBuilder {
         Ok(StorageManager {
             database_path: self.database_path,
             journal_mode: self.journal_mode,
+            tuning: self.tuning,
+            bloom_filter_cache: Arc::new(bloom::Cache::with_size(self.bloom_filter_cache_size)),
+            trie_prune_mode,
+            read_only: false,
+        })
+    }
+
+    /// Opens an existing database which is written to by another process
+    /// without modifying it, and returns a [storage manager](StorageManager)
+    /// whose pools are all read-only.
+    ///
+    /// The database must already be migrated to the schema version of this
+    /// application and be in WAL mode, so that readers see a consistent
+    /// snapshot of it without blocking its writer.
+    pub fn open_read_only(self) -> anyhow::Result<StorageManager> {
+        let connection = rusqlite::Connection::open_with_flags(
+            &self.database_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+        )
+        .context("Opening DB read-only")?;
+
+        let current_revision = schema_version(&connection)?;
+        let latest_revision = schema::BASE_SCHEMA_REVISION + schema::migrations().len();
+        if current_revision != latest_revision {
+            anyhow::bail!(
+                "Database version {current_revision} does not match the version \
+                 {latest_revision} expected by this application. The database must be migrated \
+                 by a node running the same version."
+            );
+        }
+
+        let journal_mode: String = connection
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .context("Querying journal mode")?;
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            anyhow::bail!(
+                "Database journal mode is {journal_mode}, but WAL mode is required to share it"
+            );
+        }
+
+        let prune_flag_is_set = connection
+            .query_row(
+                "SELECT 1 FROM storage_flags WHERE flag = 'prune_tries'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|x| x.is_some())?;
+        // The number of blocks kept only matters when writing tries.
+        let trie_prune_mode = if prune_flag_is_set {
+            TriePruneMode::Prune {
+                num_blocks_kept: 20,
+            }
+        } else {
+            TriePruneMode::Archive
+        };
+
+        connection
+            .close()
+            .map_err(|(_connection, error)| error)
+            .context("Closing DB")?;
+
+        Ok(StorageManager {
+            database_path: self.database_path,
+            journal_mode: JournalMode::WAL,
+            tuning: self.tuning,
             bloom_filter_cache: Arc::new(bloom::Cache::with_size(self.bloom_filter_cache_size)),
             trie_prune_mode,
+            read_only: true,
         })
     }
 
@@ -370,6 +465,7 @@ fn setup_journal_mode(
 fn setup_connection(
     connection: &mut rusqlite::Connection,
     journal_mode: JournalMode,
+    tuning: SqliteTuning,
 ) -> Result<(), rusqlite::Error> {
     // Enable foreign keys.
     connection.set_db_config(
@@ -392,7 +488,7 @@ fn setup_connection(
         }
     };
 
-    Ok(())
+    tuning.apply(connection)
 }
 
 /// Migrates the database to the latest version. This __MUST__ be called
@@ -510,7 +606,7 @@ mod tests {
     #[test]
     fn full_migration() {
         let mut conn = rusqlite::Connection::open_in_memory().unwrap();
-        setup_connection(&mut conn, JournalMode::Rollback).unwrap();
+        setup_connection(&mut conn, JournalMode::Rollback, SqliteTuning::default()).unwrap();
         migrate_database(&mut conn).unwrap();
         let version = schema_version(&conn).unwrap();
         let expected = schema::migrations().len() + schema::BASE_SCHEMA_REVISION;
@@ -520,7 +616,7 @@ mod tests {
     #[test]
     fn migration_fails_if_db_is_newer() {
         let mut conn = rusqlite::Connection::open_in_memory().unwrap();
-        setup_connection(&mut conn, JournalMode::Rollback).unwrap();
+        setup_connection(&mut conn, JournalMode::Rollback, SqliteTuning::default()).unwrap();
 
         // Force the schema to a newer version
         let current_version = schema::migrations().len();
@@ -571,6 +667,38 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn page_size_only_applies_to_new_databases() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let db_path = db_dir.path().join("db.sqlite");
+        let page_size = |path: &Path| -> u32 {
+            rusqlite::Connection::open(path)
+                .unwrap()
+                .pragma_query_value(None, "page_size", |row| row.get(0))
+                .unwrap()
+        };
+
+        let tuning = SqliteTuning {
+            page_size: 8192,
+            ..Default::default()
+        };
+        StorageBuilder::file(db_path.clone())
+            .tuning(tuning)
+            .migrate()
+            .unwrap();
+        assert_eq!(page_size(&db_path), 8192);
+
+        let tuning = SqliteTuning {
+            page_size: 16384,
+            ..Default::default()
+        };
+        StorageBuilder::file(db_path.clone())
+            .tuning(tuning)
+            .migrate()
+            .unwrap();
+        assert_eq!(page_size(&db_path), 8192);
+    }
+
     #[test]
     fn rpc_test_db_is_migrated() {
         let (_db_dir, db_path) = rpc_test_db_fixture();
@@ -595,6 +723,51 @@ mod tests {
         (db_dir, db_path)
     }
 
+    #[test]
+    fn open_read_only() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let db_path = db_dir.path().join("db.sqlite");
+
+        assert!(StorageBuilder::file(db_path.clone())
+            .open_read_only()
+            .is_err());
+
+        StorageBuilder::file(db_path.clone())
+            .journal_mode(JournalMode::Rollback)
+            .migrate()
+            .unwrap();
+        assert_eq!(
+            StorageBuilder::file(db_path.clone())
+                .open_read_only()
+                .unwrap_err()
+                .to_string(),
+            "Database journal mode is delete, but WAL mode is required to share it"
+        );
+
+        let primary = StorageBuilder::file(db_path.clone())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let replica = StorageBuilder::file(db_path)
+            .open_read_only()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+
+        let header = pathfinder_common::BlockHeader::default();
+        let mut db = primary.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.insert_block_header(&header).unwrap();
+        tx.commit().unwrap();
+
+        let mut db = replica.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        assert_eq!(tx.block_header(BlockId::Latest).unwrap(), Some(header));
+        tx.insert_block_header(&pathfinder_common::BlockHeader::default())
+            .unwrap_err();
+    }
+
     #[test]
     fn enabling_merkle_trie_pruning_fails_without_flag() {
         let (_db_dir, db_path) = rpc_test_db_fixture();
@@ -27,6 +27,7 @@ use pathfinder_common::{
     L1ToL2MessageNonce,
     L1ToL2MessagePayloadElem,
     L2ToL1MessagePayloadElem,
+    PublicKey,
     ReceiptCommitment,
     SequencerAddress,
     SierraHash,
@@ -109,6 +110,7 @@ to_sql_felt!(
     L1ToL2MessageNonce,
     L1ToL2MessagePayloadElem,
     L2ToL1MessagePayloadElem,
+    PublicKey,
     ReceiptCommitment,
     SequencerAddress,
     SierraHash,
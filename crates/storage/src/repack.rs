@@ -0,0 +1,577 @@
+//! Repacking of a database into a fresh file.
+//!
+//! Years of incremental writes leave a database fragmented: the pages of a
+//! table or index end up scattered across the file and many B-tree pages are
+//! only partly filled. Repacking copies every table into a new database in key
+//! order, which fills the table pages completely and lays them out
+//! contiguously, and then builds all indexes from scratch.
+//!
+//! Repacking is deterministic, a database is always repacked into the same
+//! file for a given page size, and resumable. The new database is staged next
+//! to the output together with the progress of the copy, which is committed
+//! in chunks, so that an interrupted repack continues where it stopped.
+//!
+//! The node must not run while its database is repacked.
+use std::path::Path;
+
+use anyhow::Context;
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::snapshot::sibling;
+
+const STAGING_SUFFIX: &str = ".repack-tmp";
+
+/// The number of rows copied per transaction.
+const CHUNK_ROWS: u64 = 100_000;
+
+/// The sizes of the database before and after repacking.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepackInfo {
+    pub source_size: u64,
+    pub size: u64,
+}
+
+/// The progress of a repack.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Progress<'a> {
+    /// `fraction` of `table` has been copied.
+    Copying { table: &'a str, fraction: f64 },
+    /// `index` is being built.
+    Indexing { index: &'a str },
+}
+
+/// A table being copied, as recorded in the staged database.
+struct Table {
+    name: String,
+    sql: String,
+    last_rowid: Option<i64>,
+    done: bool,
+}
+
+/// Repacks the database at `database` into a new database at `output`,
+/// reporting the progress to `progress`.
+///
+/// The page size of the new database defaults to the one of `database`.
+/// Resumes the repack staged next to `output`, if any.
+pub fn repack(
+    database: &Path,
+    output: &Path,
+    page_size: Option<u32>,
+    mut progress: impl FnMut(Progress<'_>),
+) -> anyhow::Result<RepackInfo> {
+    repack_with(database, output, page_size, CHUNK_ROWS, &mut progress)
+}
+
+fn repack_with(
+    database: &Path,
+    output: &Path,
+    page_size: Option<u32>,
+    chunk_rows: u64,
+    progress: &mut dyn FnMut(Progress<'_>),
+) -> anyhow::Result<RepackInfo> {
+    anyhow::ensure!(
+        !output.exists(),
+        "Database {} already exists",
+        output.display()
+    );
+    if let Some(page_size) = page_size {
+        anyhow::ensure!(
+            page_size.is_power_of_two() && (512..=65536).contains(&page_size),
+            "Page size must be a power of two between 512 and 65536"
+        );
+    }
+
+    let staging = sibling(output, STAGING_SUFFIX);
+    let mut connection = open_staging(database, &staging, page_size)?;
+
+    for mut table in staged_tables(&connection)? {
+        copy_table(&mut connection, &mut table, chunk_rows, progress)?;
+    }
+    create_remaining_schema(&connection, progress)?;
+
+    anyhow::ensure!(
+        source_identity(&connection)? == staged_identity(&connection)?,
+        "Database {} changed while it was repacked, remove {} to start over",
+        database.display(),
+        staging.display()
+    );
+    connection
+        .execute_batch("DROP TABLE repack_progress; DROP TABLE repack_source;")
+        .context("Removing repack progress")?;
+    connection
+        .close()
+        .map_err(|(_, error)| error)
+        .context("Closing staged database")?;
+
+    std::fs::rename(&staging, output).context("Moving repacked database into place")?;
+
+    Ok(RepackInfo {
+        source_size: std::fs::metadata(database)
+            .context("Reading database size")?
+            .len(),
+        size: std::fs::metadata(output)
+            .context("Reading repacked database size")?
+            .len(),
+    })
+}
+
+/// Opens the staged database with the database attached as `source`, creating
+/// the staged database unless there is a repack to resume.
+fn open_staging(
+    database: &Path,
+    staging: &Path,
+    page_size: Option<u32>,
+) -> anyhow::Result<Connection> {
+    anyhow::ensure!(
+        database.exists(),
+        "Database {} does not exist",
+        database.display()
+    );
+
+    let staged = staging.exists();
+    let mut connection = open_attached(database, staging)?;
+    let resumable = connection
+        .query_row(
+            "SELECT 1 FROM main.sqlite_master WHERE name = 'repack_source'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()
+        .context("Checking for a repack to resume")?
+        .is_some();
+
+    if resumable {
+        anyhow::ensure!(
+            source_identity(&connection)? == staged_identity(&connection)?,
+            "Database {} changed since the repack started, remove {} to start over",
+            database.display(),
+            staging.display()
+        );
+        return Ok(connection);
+    }
+
+    // Left over by an attempt which was interrupted before it began copying.
+    if staged {
+        drop(connection);
+        std::fs::remove_file(staging).context("Removing stale staged database")?;
+        connection = open_attached(database, staging)?;
+    }
+
+    let page_size = match page_size {
+        Some(page_size) => page_size,
+        None => connection
+            .query_row("PRAGMA source.page_size", [], |row| row.get(0))
+            .context("Reading page size")?,
+    };
+    // Must be set before the first table is created.
+    connection
+        .pragma_update(None, "page_size", page_size)
+        .context("Setting page size")?;
+
+    let (schema_version, latest_block) = source_identity(&connection)?;
+    let tx = connection
+        .transaction()
+        .context("Creating database transaction")?;
+    tx.execute_batch(
+        "CREATE TABLE repack_source (schema_version INTEGER NOT NULL, latest_block BLOB);
+         CREATE TABLE repack_progress (
+             name       TEXT    PRIMARY KEY NOT NULL,
+             last_rowid INTEGER,
+             done       INTEGER NOT NULL DEFAULT 0
+         );",
+    )
+    .context("Creating repack progress")?;
+    tx.execute(
+        "INSERT INTO repack_source (schema_version, latest_block) VALUES (?, ?)",
+        rusqlite::params![schema_version, latest_block],
+    )
+    .context("Recording database")?;
+
+    // Tables are created in order of their names, rather than of the source's
+    // schema, which depends on the migrations the database went through.
+    let mut stmt = tx
+        .prepare(
+            "SELECT name, sql FROM source.sqlite_master WHERE type = 'table' AND sql IS NOT NULL \
+             AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' ORDER BY name",
+        )
+        .context("Preparing schema query")?;
+    let tables = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))
+        .context("Querying schema")?
+        .collect::<Result<Vec<(String, String)>, _>>()
+        .context("Reading schema")?;
+    drop(stmt);
+
+    for (name, sql) in tables {
+        anyhow::ensure!(
+            !sql.to_uppercase().starts_with("CREATE VIRTUAL"),
+            "Repacking virtual table {name} is not supported"
+        );
+        tx.execute_batch(&sql)
+            .with_context(|| format!("Creating table {name}"))?;
+        tx.execute("INSERT INTO repack_progress (name) VALUES (?)", [&name])
+            .context("Recording table")?;
+    }
+
+    tx.pragma_update(None, "user_version", schema_version)
+        .context("Setting schema version")?;
+    tx.commit().context("Committing database transaction")?;
+
+    Ok(connection)
+}
+
+fn open_attached(database: &Path, staging: &Path) -> anyhow::Result<Connection> {
+    let connection = Connection::open(staging).context("Opening staged database")?;
+    // The database is only ever read from.
+    connection
+        .execute(
+            "ATTACH DATABASE ? AS source",
+            [database
+                .to_str()
+                .context("Database path is not valid UTF-8")?],
+        )
+        .context("Attaching database")?;
+    Ok(connection)
+}
+
+/// The schema version and latest block hash of the database, which identify
+/// the state it was in when the repack started.
+fn source_identity(connection: &Connection) -> anyhow::Result<(i64, Option<Vec<u8>>)> {
+    let schema_version = connection
+        .query_row("PRAGMA source.user_version", [], |row| row.get(0))
+        .context("Reading schema version")?;
+    let latest_block = connection
+        .query_row(
+            "SELECT hash FROM source.block_headers ORDER BY number DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Reading latest block")?;
+    Ok((schema_version, latest_block))
+}
+
+/// The identity of the database, as recorded when the repack started.
+fn staged_identity(connection: &Connection) -> anyhow::Result<(i64, Option<Vec<u8>>)> {
+    connection
+        .query_row(
+            "SELECT schema_version, latest_block FROM repack_source",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .context("Reading recorded database")
+}
+
+fn staged_tables(connection: &Connection) -> anyhow::Result<Vec<Table>> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT p.name, m.sql, p.last_rowid, p.done FROM repack_progress p
+             JOIN main.sqlite_master m ON m.name = p.name ORDER BY p.name",
+        )
+        .context("Preparing progress query")?;
+    let tables = stmt
+        .query_map([], |row| {
+            Ok(Table {
+                name: row.get(0)?,
+                sql: row.get(1)?,
+                last_rowid: row.get(2)?,
+                done: row.get(3)?,
+            })
+        })
+        .context("Querying progress")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Reading progress")?;
+    Ok(tables)
+}
+
+/// Copies the rest of `table`, committing every `chunk_rows` rows.
+fn copy_table(
+    connection: &mut Connection,
+    table: &mut Table,
+    chunk_rows: u64,
+    progress: &mut dyn FnMut(Progress<'_>),
+) -> anyhow::Result<()> {
+    let name = quote(&table.name);
+
+    if !table.done && table.sql.to_uppercase().contains("WITHOUT ROWID") {
+        // Scanning a table without rowids yields its rows in key order, but
+        // there is no rowid to resume from, so it is copied at once.
+        progress(Progress::Copying {
+            table: &table.name,
+            fraction: 0.0,
+        });
+        let tx = connection
+            .transaction()
+            .context("Creating database transaction")?;
+        tx.execute(
+            &format!("INSERT INTO main.{name} SELECT * FROM source.{name}"),
+            [],
+        )
+        .with_context(|| format!("Copying table {}", table.name))?;
+        tx.execute(
+            "UPDATE repack_progress SET done = 1 WHERE name = ?",
+            [&table.name],
+        )
+        .context("Recording progress")?;
+        tx.commit().context("Committing database transaction")?;
+        table.done = true;
+    }
+
+    if table.done {
+        progress(Progress::Copying {
+            table: &table.name,
+            fraction: 1.0,
+        });
+        return Ok(());
+    }
+
+    let max_rowid: Option<i64> = connection
+        .query_row(
+            &format!("SELECT MAX(rowid) FROM source.{name}"),
+            [],
+            |row| row.get(0),
+        )
+        .context("Reading table size")?;
+
+    while !table.done {
+        progress(Progress::Copying {
+            table: &table.name,
+            fraction: match (table.last_rowid, max_rowid) {
+                (Some(last), Some(max)) if max > 0 => last as f64 / max as f64,
+                _ => 0.0,
+            },
+        });
+
+        copy_chunk(connection, table, chunk_rows)?;
+    }
+
+    progress(Progress::Copying {
+        table: &table.name,
+        fraction: 1.0,
+    });
+    Ok(())
+}
+
+/// Copies the next `chunk_rows` rows of `table`, in order of their rowids.
+fn copy_chunk(
+    connection: &mut Connection,
+    table: &mut Table,
+    chunk_rows: u64,
+) -> anyhow::Result<()> {
+    let name = quote(&table.name);
+    let columns = copied_columns(connection, &table.name)?;
+
+    let tx = connection
+        .transaction()
+        .context("Creating database transaction")?;
+    let copied = tx
+        .execute(
+            &format!(
+                "INSERT INTO main.{name} ({columns}) SELECT {columns} FROM source.{name} WHERE \
+                 rowid > ? ORDER BY rowid LIMIT ?"
+            ),
+            rusqlite::params![table.last_rowid.unwrap_or(i64::MIN), chunk_rows],
+        )
+        .with_context(|| format!("Copying table {}", table.name))?;
+    table.last_rowid = tx
+        .query_row(&format!("SELECT MAX(rowid) FROM main.{name}"), [], |row| {
+            row.get(0)
+        })
+        .context("Reading copied rows")?;
+    table.done = (copied as u64) < chunk_rows;
+    tx.execute(
+        "UPDATE repack_progress SET last_rowid = ?, done = ? WHERE name = ?",
+        rusqlite::params![table.last_rowid, table.done, table.name],
+    )
+    .context("Recording progress")?;
+    tx.commit().context("Committing database transaction")
+}
+
+/// The columns to copy from `table`, including its rowid unless one of the
+/// columns is an alias for it.
+fn copied_columns(connection: &Connection, table: &str) -> anyhow::Result<String> {
+    let mut stmt = connection
+        .prepare("SELECT name, type, pk FROM pragma_table_info(?, 'main')")
+        .context("Preparing column query")?;
+    let columns = stmt
+        .query_map([table], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .context("Querying columns")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Reading columns")?;
+
+    let primary_key = columns
+        .iter()
+        .filter(|(_, _, pk)| *pk > 0)
+        .collect::<Vec<_>>();
+    let rowid_alias = matches!(
+        primary_key.as_slice(),
+        [(_, kind, _)] if kind.eq_ignore_ascii_case("INTEGER")
+    );
+
+    let names = columns.iter().map(|(name, _, _)| quote(name));
+    let columns = if rowid_alias {
+        names.collect::<Vec<_>>()
+    } else {
+        std::iter::once("rowid".to_owned()).chain(names).collect()
+    };
+    Ok(columns.join(", "))
+}
+
+/// Creates the indexes, views and triggers of the database which do not exist
+/// yet. Each is committed separately, so that a resumed repack does not have to
+/// build them again.
+fn create_remaining_schema(
+    connection: &Connection,
+    progress: &mut dyn FnMut(Progress<'_>),
+) -> anyhow::Result<()> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT s.type, s.name, s.sql FROM source.sqlite_master s
+             WHERE s.type IN ('index', 'view', 'trigger') AND s.sql IS NOT NULL
+             AND NOT EXISTS (SELECT 1 FROM main.sqlite_master m WHERE m.name = s.name)
+             ORDER BY s.type = 'trigger', s.type = 'view', s.name",
+        )
+        .context("Preparing schema query")?;
+    let remaining = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .context("Querying schema")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Reading schema")?;
+
+    for (kind, name, sql) in remaining {
+        if kind == "index" {
+            progress(Progress::Indexing { index: &name });
+        }
+        connection
+            .execute_batch(&sql)
+            .with_context(|| format!("Creating {kind} {name}"))?;
+    }
+
+    Ok(())
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use pathfinder_common::{BlockHash, BlockHeader, BlockNumber};
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+    use crate::StorageBuilder;
+
+    fn database_with_blocks(path: &Path) -> Vec<BlockHeader> {
+        let storage = StorageBuilder::file(path.to_owned())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let headers = (0..10)
+            .map(|i| BlockHeader {
+                number: BlockNumber::new_or_panic(i),
+                hash: BlockHash(Felt::from_u64(i + 1)),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        for header in &headers {
+            tx.insert_block_header(header).unwrap();
+        }
+        tx.commit().unwrap();
+        headers
+    }
+
+    fn read_headers(path: &Path) -> Vec<BlockHeader> {
+        let storage = StorageBuilder::file(path.to_owned())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        (0..10)
+            .map(|i| {
+                tx.block_header(BlockNumber::new_or_panic(i).into())
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repack_is_deterministic() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("source.sqlite");
+        let headers = database_with_blocks(&database);
+
+        let first = dir.path().join("first.sqlite");
+        let second = dir.path().join("second.sqlite");
+        repack(&database, &first, Some(4096), |_| {}).unwrap();
+        repack(&database, &second, Some(4096), |_| {}).unwrap();
+        assert!(!sibling(&first, STAGING_SUFFIX).exists());
+
+        assert_eq!(
+            std::fs::read(&first).unwrap(),
+            std::fs::read(&second).unwrap()
+        );
+        assert_eq!(read_headers(&first), headers);
+    }
+
+    #[test]
+    fn interrupted_repack_is_resumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("source.sqlite");
+        let headers = database_with_blocks(&database);
+        let output = dir.path().join("repacked.sqlite");
+
+        // Copy a single row of the block headers before stopping.
+        let staging = sibling(&output, STAGING_SUFFIX);
+        let mut connection = open_staging(&database, &staging, None).unwrap();
+        let mut table = staged_tables(&connection)
+            .unwrap()
+            .into_iter()
+            .find(|table| table.name == "block_headers")
+            .unwrap();
+        copy_chunk(&mut connection, &mut table, 1).unwrap();
+        drop(connection);
+
+        let mut reported = Vec::new();
+        repack_with(&database, &output, None, 3, &mut |progress| {
+            if let Progress::Copying { table, fraction } = progress {
+                if table == "block_headers" {
+                    reported.push(fraction);
+                }
+            }
+        })
+        .unwrap();
+
+        assert_eq!(reported.first(), Some(&0.1));
+        assert_eq!(read_headers(&output), headers);
+    }
+
+    #[test]
+    fn existing_database_is_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("source.sqlite");
+        database_with_blocks(&database);
+
+        repack(&database, &database, None, |_| {}).unwrap_err();
+    }
+}
@@ -24,6 +24,22 @@ mod revision_0061;
 mod revision_0062;
 mod revision_0063;
 mod revision_0064;
+mod revision_0065;
+mod revision_0066;
+mod revision_0067;
+mod revision_0068;
+mod revision_0069;
+mod revision_0070;
+mod revision_0071;
+mod revision_0072;
+mod revision_0073;
+mod revision_0074;
+mod revision_0075;
+mod revision_0076;
+mod revision_0077;
+mod revision_0078;
+mod revision_0079;
+mod revision_0080;
 
 pub(crate) use base::base_schema;
 
@@ -56,6 +72,22 @@ pub fn migrations() -> &'static [MigrationFn] {
         revision_0062::migrate,
         revision_0063::migrate,
         revision_0064::migrate,
+        revision_0065::migrate,
+        revision_0066::migrate,
+        revision_0067::migrate,
+        revision_0068::migrate,
+        revision_0069::migrate,
+        revision_0070::migrate,
+        revision_0071::migrate,
+        revision_0072::migrate,
+        revision_0073::migrate,
+        revision_0074::migrate,
+        revision_0075::migrate,
+        revision_0076::migrate,
+        revision_0077::migrate,
+        revision_0078::migrate,
+        revision_0079::migrate,
+        revision_0080::migrate,
     ]
 }
 
@@ -0,0 +1,24 @@
+use anyhow::Context;
+
+/// Adds a table holding the number of transactions per block broken down by
+/// transaction type and version.
+///
+/// The table is filled in as new blocks are inserted. Existing blocks are not
+/// backfilled, as that would require decompressing all transactions -- queries
+/// for blocks without stats fall back to counting the stored transactions.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding block_transaction_stats table");
+
+    tx.execute_batch(
+        r"CREATE TABLE block_transaction_stats (
+            block_number INTEGER NOT NULL REFERENCES block_headers(number) ON DELETE CASCADE,
+            kind TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            count INTEGER NOT NULL,
+            PRIMARY KEY (block_number, kind, version)
+        );",
+    )
+    .context("Adding block_transaction_stats table")?;
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anyhow::Context;
+
+/// Adds a table holding verified source metadata of classes, as imported from
+/// a contract verification provider.
+///
+/// Entries are keyed by class hash only, as metadata may be imported before the
+/// class itself has been synced.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding verified_sources table");
+
+    tx.execute_batch(
+        r"CREATE TABLE verified_sources (
+            class_hash BLOB PRIMARY KEY,
+            compiler_version TEXT NOT NULL,
+            source_hash TEXT NOT NULL,
+            repository_url TEXT,
+            provider TEXT NOT NULL
+        );",
+    )
+    .context("Adding verified_sources table")?;
+
+    Ok(())
+}
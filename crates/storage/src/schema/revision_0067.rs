@@ -0,0 +1,12 @@
+use anyhow::Context;
+
+/// Records which public key a block's signature was verified against when the
+/// block was received over P2P. `NULL` for signatures that were not verified.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding attested_by column to block_signatures");
+
+    tx.execute_batch("ALTER TABLE block_signatures ADD COLUMN attested_by BLOB;")
+        .context("Adding attested_by column to block_signatures")?;
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use anyhow::Context;
+
+/// Adds the class hash and nonce of each contract state to
+/// `contract_state_hashes`, so that the preimage of a contract state hash can
+/// be read without looking up the class and nonce updates of the contract.
+///
+/// States stored before this migration are not backfilled and have `NULL`
+/// class hash and nonce.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding class hash and nonce columns to contract_state_hashes table");
+
+    tx.execute_batch(
+        r"
+        ALTER TABLE contract_state_hashes ADD COLUMN class_hash BLOB;
+        ALTER TABLE contract_state_hashes ADD COLUMN nonce BLOB;
+        ",
+    )
+    .context("Adding class hash and nonce columns to contract_state_hashes")?;
+
+    Ok(())
+}
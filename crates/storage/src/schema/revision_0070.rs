@@ -0,0 +1,12 @@
+use anyhow::Context;
+
+/// Adds the last Ethereum block scanned for state update logs to the `refs`
+/// table, so that L1 sync can resume scanning after a restart.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding L1 log scan checkpoint to refs table");
+
+    tx.execute("ALTER TABLE refs ADD COLUMN l1_scanned_to INTEGER", [])
+        .context("Adding l1_scanned_to column to refs")?;
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use anyhow::Context;
+
+/// Adds the tables holding daily chain analytics, and the last block included
+/// in them to the `refs` table.
+///
+/// The tables are only filled in if analytics are enabled, see
+/// [Transaction::refresh_analytics](crate::Transaction::refresh_analytics).
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding analytics tables");
+
+    tx.execute_batch(
+        r"CREATE TABLE analytics_daily (
+            day INTEGER PRIMARY KEY,
+            first_block INTEGER NOT NULL,
+            last_block INTEGER NOT NULL,
+            transactions INTEGER NOT NULL,
+            active_accounts INTEGER NOT NULL,
+            fees_wei BLOB NOT NULL,
+            fees_fri BLOB NOT NULL,
+            class_declarations INTEGER NOT NULL
+        );
+        CREATE TABLE analytics_daily_accounts (
+            day INTEGER NOT NULL,
+            contract_address BLOB NOT NULL,
+            PRIMARY KEY (day, contract_address)
+        ) WITHOUT ROWID;",
+    )
+    .context("Adding analytics tables")?;
+
+    tx.execute("ALTER TABLE refs ADD COLUMN analytics_refreshed_to INTEGER", [])
+        .context("Adding analytics_refreshed_to column to refs")?;
+
+    Ok(())
+}
@@ -0,0 +1,11 @@
+use anyhow::Context;
+
+/// Adds the last block up to which superseded state updates have been pruned
+/// to the `refs` table, see
+/// [Transaction::prune_state_updates](crate::Transaction::prune_state_updates).
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute("ALTER TABLE refs ADD COLUMN state_pruned_to INTEGER", [])
+        .context("Adding state_pruned_to column to refs")?;
+
+    Ok(())
+}
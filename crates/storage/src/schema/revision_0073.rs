@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, EventKey};
+use pathfinder_crypto::Felt;
+
+use crate::bloom::{aggregate_range_start, BloomFilter};
+use crate::params::{params, RowExt};
+
+/// Adds aggregate Bloom filters covering [AGGREGATE_BLOCK_RANGE] blocks each
+/// and creates them for the events already stored.
+///
+/// [AGGREGATE_BLOCK_RANGE]: crate::bloom::AGGREGATE_BLOCK_RANGE
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE starknet_events_filters_aggregate (
+            from_block INTEGER NOT NULL PRIMARY KEY,
+            next_block INTEGER NOT NULL,
+            bloom BLOB NOT NULL
+        );
+    ",
+    )
+    .context("Creating aggregate event Bloom filter table")?;
+
+    tracing::info!("Creating aggregate Bloom filters for events");
+
+    let mut blocks_statement =
+        tx.prepare("SELECT block_number FROM starknet_events_filters ORDER BY block_number")?;
+    let mut events_statement =
+        tx.prepare("SELECT from_address, keys FROM transaction_events WHERE block_number = ?")?;
+    let mut insert_statement = tx.prepare(
+        "INSERT INTO starknet_events_filters_aggregate (from_block, next_block, bloom) VALUES (?, \
+         ?, ?)",
+    )?;
+
+    let mut insert = |aggregate: Aggregate| -> anyhow::Result<()> {
+        insert_statement
+            .execute(params![
+                &aggregate.from_block,
+                &aggregate.next_block,
+                &aggregate.bloom.to_compressed_bytes()
+            ])
+            .context("Inserting aggregate Bloom filter")?;
+        Ok(())
+    };
+
+    let mut current: Option<Aggregate> = None;
+    let mut progress_logged = Instant::now();
+    const LOG_RATE: Duration = Duration::from_secs(10);
+
+    let mut blocks = blocks_statement.query([])?;
+    while let Some(row) = blocks.next().context("Fetching next block")? {
+        let block_number = row.get_block_number(0)?;
+        let from_block = aggregate_range_start(block_number);
+
+        if current.as_ref().map(|aggregate| aggregate.from_block) != Some(from_block) {
+            if let Some(aggregate) = current.take() {
+                insert(aggregate)?;
+            }
+
+            if progress_logged.elapsed() > LOG_RATE {
+                tracing::debug!(%block_number, "Processing events");
+                progress_logged = Instant::now();
+            }
+
+            // Filters only cover ranges from their first block.
+            if block_number == from_block {
+                current = Some(Aggregate {
+                    from_block,
+                    next_block: from_block,
+                    bloom: BloomFilter::new_aggregate(),
+                });
+            }
+        }
+
+        let Some(aggregate) = &mut current else {
+            continue;
+        };
+        // Filters only cover consecutive blocks.
+        if aggregate.next_block != block_number {
+            continue;
+        }
+
+        let mut events = events_statement.query(params![&block_number])?;
+        while let Some(event) = events.next().context("Fetching next event")? {
+            let from_address = event.get_contract_address(0)?;
+            let keys = decode_felts(event.get_blob(1)?)?
+                .into_iter()
+                .map(EventKey)
+                .collect::<Vec<_>>();
+
+            aggregate.bloom.set_keys(&keys);
+            aggregate.bloom.set_address(&from_address);
+        }
+        aggregate.next_block = block_number + 1;
+    }
+
+    if let Some(aggregate) = current {
+        insert(aggregate)?;
+    }
+
+    Ok(())
+}
+
+struct Aggregate {
+    from_block: BlockNumber,
+    next_block: BlockNumber,
+    bloom: BloomFilter,
+}
+
+/// Decodes a list of felts stored as their concatenated big-endian bytes.
+fn decode_felts(bytes: &[u8]) -> anyhow::Result<Vec<Felt>> {
+    anyhow::ensure!(bytes.len() % 32 == 0, "Invalid felt list length");
+
+    bytes
+        .chunks_exact(32)
+        .map(|chunk| Felt::from_be_slice(chunk).context("Felt out of range"))
+        .collect()
+}
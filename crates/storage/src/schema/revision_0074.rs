@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+/// Replaces the index of transaction hashes by block with one which also
+/// covers the hash, so that the hashes of a block's transactions are read in
+/// order from the index alone.
+///
+/// Lookups of events by address and block already use
+/// `transaction_events_from_address_idx`, and events are matched by key
+/// using their Bloom filters, as their keys are stored as a single blob.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        DROP INDEX transaction_hashes_block_number_idx;
+        CREATE INDEX transaction_hashes_block_number_idx_hash
+            ON transaction_hashes(block_number, idx, hash);
+        ",
+    )
+    .context("Replacing transaction hashes index")
+}
@@ -0,0 +1,25 @@
+use anyhow::Context;
+
+/// Adds a table logging the L2 reorgs detected by sync, see
+/// [Transaction::record_reorg](crate::Transaction::record_reorg).
+///
+/// Entries do not reference the blocks involved, as these are removed by the
+/// reorg they describe.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding reorg_log table");
+
+    tx.execute_batch(
+        r"CREATE TABLE reorg_log (
+            id INTEGER PRIMARY KEY,
+            detected_at INTEGER NOT NULL,
+            old_head_number INTEGER NOT NULL,
+            old_head_hash BLOB NOT NULL,
+            new_head_number INTEGER,
+            new_head_hash BLOB,
+            depth INTEGER NOT NULL
+        );",
+    )
+    .context("Adding reorg_log table")?;
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+
+use crate::connection::class_selector::entry_point_selectors;
+use crate::params::{params, RowExt};
+
+/// Adds a reverse index of entry point selectors to the classes exposing
+/// them, see [Transaction::classes_by_selector], and fills it from the class
+/// definitions already stored.
+///
+/// [Transaction::classes_by_selector]: crate::Transaction::classes_by_selector
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE class_selectors (
+            selector BLOB NOT NULL,
+            class_hash BLOB NOT NULL,
+            PRIMARY KEY (selector, class_hash)
+        ) WITHOUT ROWID;
+    ",
+    )
+    .context("Creating class_selectors table")?;
+
+    tracing::info!("Indexing class entry point selectors");
+
+    let mut classes_statement =
+        tx.prepare("SELECT hash, definition FROM class_definitions WHERE definition IS NOT NULL")?;
+    let mut insert_statement =
+        tx.prepare("INSERT OR IGNORE INTO class_selectors (selector, class_hash) VALUES (?, ?)")?;
+
+    let mut progress_logged = Instant::now();
+    const LOG_RATE: Duration = Duration::from_secs(10);
+
+    let mut classes = classes_statement.query([])?;
+    let mut indexed = 0usize;
+    while let Some(row) = classes.next().context("Fetching next class")? {
+        let class_hash = row.get_class_hash(0)?;
+        let definition =
+            zstd::decode_all(row.get_blob(1)?).context("Decompressing class definition")?;
+
+        match entry_point_selectors(&definition) {
+            Ok(selectors) => {
+                for selector in selectors {
+                    insert_statement
+                        .execute(params![&selector, &class_hash])
+                        .context("Inserting class selector")?;
+                }
+            }
+            Err(error) => {
+                tracing::debug!(%class_hash, %error, "Not indexing class selectors");
+            }
+        }
+
+        indexed += 1;
+        if progress_logged.elapsed() > LOG_RATE {
+            tracing::debug!(%indexed, "Indexing class selectors");
+            progress_logged = Instant::now();
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+/// Adds a table persisting the CASM compiled from Sierra classes which are
+/// not part of the chain, such as those of simulated declare transactions,
+/// see [Transaction::cached_casm](crate::Transaction::cached_casm).
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Adding casm_cache table");
+
+    tx.execute_batch(
+        r"CREATE TABLE casm_cache (
+            hash BLOB PRIMARY KEY,
+            compiler_version TEXT NOT NULL,
+            definition BLOB NOT NULL
+        );",
+    )
+    .context("Adding casm_cache table")?;
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use pathfinder_common::transaction::Transaction;
+
+use super::revision_0068::{compression, dto};
+use crate::params::{params, RowExt, TryIntoSqlInt};
+
+/// Adds the `transaction_senders` table, which indexes transactions by the
+/// address of their sender, and fills it from the stored transactions.
+///
+/// Deploy and L1 handler transactions have no sender and are indexed by the
+/// address of the contract deployed or called instead, see
+/// [TransactionVariant::contract_address](pathfinder_common::transaction::TransactionVariant::contract_address).
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE transaction_senders (
+            sender_address  BLOB NOT NULL,
+            block_number    INTEGER NOT NULL REFERENCES block_headers(number) ON DELETE CASCADE,
+            transaction_idx INTEGER NOT NULL,
+            PRIMARY KEY (sender_address, block_number, transaction_idx)
+        ) WITHOUT ROWID;
+        CREATE INDEX transaction_senders_block_number_idx
+            ON transaction_senders(block_number);
+        ",
+    )
+    .context("Creating transaction_senders table")?;
+
+    let block_numbers = tx
+        .prepare("SELECT block_number FROM transactions ORDER BY block_number")?
+        .query_map([], |row| row.get_i64(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Querying block numbers")?;
+
+    tracing::info!(
+        block_count=%block_numbers.len(),
+        "Indexing transactions by sender, this may take a while"
+    );
+
+    let mut query_stmt =
+        tx.prepare("SELECT transactions FROM transactions WHERE block_number = ?")?;
+    let mut insert_stmt = tx.prepare(
+        "INSERT INTO transaction_senders (sender_address, block_number, transaction_idx) VALUES \
+         (?, ?, ?)",
+    )?;
+
+    const LOG_RATE: Duration = Duration::from_secs(10);
+    let mut last_log = Instant::now();
+
+    for (i, block_number) in block_numbers.iter().enumerate() {
+        let transactions =
+            query_stmt.query_row([block_number], |row| Ok(row.get_blob(0)?.to_vec()))?;
+        let transactions = compression::decompress_transactions(&transactions)
+            .context("Decompressing transactions")?;
+        let transactions: dto::TransactionsForBlock =
+            bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
+                .context("Deserializing transactions")?
+                .0;
+
+        for (transaction_idx, transaction) in transactions.transactions().into_iter().enumerate() {
+            let transaction = Transaction::from(transaction);
+            insert_stmt.execute(params![
+                &transaction.variant.contract_address(),
+                block_number,
+                &transaction_idx.try_into_sql_int()?,
+            ])?;
+        }
+
+        if last_log.elapsed() > LOG_RATE {
+            last_log = Instant::now();
+            tracing::info!(
+                "Indexing transactions by sender: {:.2}%",
+                (i as f64 / block_numbers.len() as f64) * 100.0
+            );
+        }
+    }
+
+    Ok(())
+}
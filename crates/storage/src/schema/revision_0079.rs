@@ -0,0 +1,88 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use pathfinder_common::transaction::{Transaction, TransactionVariant};
+
+use super::revision_0068::{compression, dto};
+use crate::params::{params, RowExt, TryIntoSqlInt};
+
+/// Adds the tables tracking L1 to L2 messages: `l1_messages` stores the L1
+/// transactions which sent messages, as seen by the L1 sync, and
+/// `l1_handler_messages` indexes L1 handler transactions by the hash of the
+/// message they consume. The latter is filled from the stored transactions.
+///
+/// Messages sent before this migration are not known to have been sent, as
+/// the L1 sync only follows new messages.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE l1_messages (
+            message_hash        BLOB PRIMARY KEY,
+            l1_transaction_hash BLOB NOT NULL
+        );
+
+        CREATE TABLE l1_handler_messages (
+            message_hash    BLOB NOT NULL,
+            block_number    INTEGER NOT NULL REFERENCES block_headers(number) ON DELETE CASCADE,
+            transaction_idx INTEGER NOT NULL,
+            PRIMARY KEY (message_hash, block_number, transaction_idx)
+        ) WITHOUT ROWID;
+        CREATE INDEX l1_handler_messages_block_number_idx
+            ON l1_handler_messages(block_number);
+        ",
+    )
+    .context("Creating L1 message tables")?;
+
+    let block_numbers = tx
+        .prepare("SELECT block_number FROM transactions ORDER BY block_number")?
+        .query_map([], |row| row.get_i64(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Querying block numbers")?;
+
+    tracing::info!(
+        block_count=%block_numbers.len(),
+        "Indexing L1 handler transactions by message hash, this may take a while"
+    );
+
+    let mut query_stmt =
+        tx.prepare("SELECT transactions FROM transactions WHERE block_number = ?")?;
+    let mut insert_stmt = tx.prepare(
+        "INSERT INTO l1_handler_messages (message_hash, block_number, transaction_idx) VALUES \
+         (?, ?, ?)",
+    )?;
+
+    const LOG_RATE: Duration = Duration::from_secs(10);
+    let mut last_log = Instant::now();
+
+    for (i, block_number) in block_numbers.iter().enumerate() {
+        let transactions =
+            query_stmt.query_row([block_number], |row| Ok(row.get_blob(0)?.to_vec()))?;
+        let transactions = compression::decompress_transactions(&transactions)
+            .context("Decompressing transactions")?;
+        let transactions: dto::TransactionsForBlock =
+            bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
+                .context("Deserializing transactions")?
+                .0;
+
+        for (transaction_idx, transaction) in transactions.transactions().into_iter().enumerate() {
+            let transaction = Transaction::from(transaction);
+            if let TransactionVariant::L1Handler(l1_handler) = transaction.variant {
+                insert_stmt.execute(params![
+                    &l1_handler.calculate_message_hash().as_bytes(),
+                    block_number,
+                    &transaction_idx.try_into_sql_int()?,
+                ])?;
+            }
+        }
+
+        if last_log.elapsed() > LOG_RATE {
+            last_log = Instant::now();
+            tracing::info!(
+                "Indexing L1 handler transactions: {:.2}%",
+                (i as f64 / block_numbers.len() as f64) * 100.0
+            );
+        }
+    }
+
+    Ok(())
+}
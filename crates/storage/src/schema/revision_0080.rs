@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+
+use crate::params::{params, RowExt};
+
+/// Adds the `compiler_version` column to `casm_definitions`, and fills it from
+/// the `compiler_version` field of the stored CASM definitions.
+///
+/// Definitions without the field, such as the empty ones stored for classes
+/// which failed to compile, are left with a `NULL` version.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch("ALTER TABLE casm_definitions ADD COLUMN compiler_version TEXT")
+        .context("Adding compiler_version column")?;
+
+    let hashes = tx
+        .prepare("SELECT hash FROM casm_definitions WHERE definition IS NOT NULL")?
+        .query_map([], |row| Ok(row.get_blob(0)?.to_vec()))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Querying CASM definitions")?;
+
+    tracing::info!(
+        class_count=%hashes.len(),
+        "Recording CASM compiler versions, this may take a while"
+    );
+
+    let mut query_stmt = tx.prepare("SELECT definition FROM casm_definitions WHERE hash = ?")?;
+    let mut update_stmt =
+        tx.prepare("UPDATE casm_definitions SET compiler_version = ? WHERE hash = ?")?;
+
+    #[derive(serde::Deserialize)]
+    struct CompilerVersion {
+        compiler_version: String,
+    }
+
+    const LOG_RATE: Duration = Duration::from_secs(10);
+    let mut last_log = Instant::now();
+
+    for (i, hash) in hashes.iter().enumerate() {
+        let definition =
+            query_stmt.query_row(params![hash], |row| Ok(row.get_blob(0)?.to_vec()))?;
+        let definition =
+            zstd::decode_all(definition.as_slice()).context("Decompressing CASM definition")?;
+
+        if let Ok(version) = serde_json::from_slice::<CompilerVersion>(&definition) {
+            update_stmt.execute(params![&version.compiler_version, hash])?;
+        }
+
+        if last_log.elapsed() > LOG_RATE {
+            last_log = Instant::now();
+            tracing::info!(
+                "Recording CASM compiler versions: {:.2}%",
+                (i as f64 / hashes.len() as f64) * 100.0
+            );
+        }
+    }
+
+    Ok(())
+}
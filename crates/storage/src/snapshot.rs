@@ -0,0 +1,280 @@
+//! Compressed snapshots of a database, for bootstrapping new nodes.
+//!
+//! A snapshot is a consistent copy of the whole database, taken with `VACUUM
+//! INTO` so that the node can keep running while it is created. It consists
+//! of a header with the size and SHA3-256 checksum of the database, followed
+//! by the zstd compressed database. The checksum is verified when the
+//! snapshot is restored.
+//!
+//! Snapshots are written to and read from streams, so they can be piped to and
+//! from object stores.
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rusqlite::OpenFlags;
+use sha3::{Digest, Sha3_256};
+
+/// Identifies snapshots and their format version.
+const MAGIC: &[u8; 8] = b"PFSNAP\x00\x01";
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The size and checksum of the database in a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    pub size: u64,
+    pub checksum: [u8; 32],
+}
+
+/// Writes a snapshot of the database at `database` to `output`.
+///
+/// The uncompressed copy of the database is staged next to the database, so
+/// this needs as much free disk space as the database takes up.
+pub fn create(database: &Path, output: impl Write) -> anyhow::Result<SnapshotInfo> {
+    let copy = sibling(database, ".snapshot-tmp");
+    // Left over by an earlier attempt.
+    if copy.exists() {
+        std::fs::remove_file(&copy).context("Removing stale snapshot copy")?;
+    }
+
+    let result = create_from_copy(database, &copy, output);
+    std::fs::remove_file(&copy).ok();
+    result
+}
+
+fn create_from_copy(
+    database: &Path,
+    copy: &Path,
+    output: impl Write,
+) -> anyhow::Result<SnapshotInfo> {
+    let connection = rusqlite::Connection::open_with_flags(
+        database,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .context("Opening database")?;
+    connection
+        .execute(
+            "VACUUM INTO ?",
+            [copy.to_str().context("Database path is not valid UTF-8")?],
+        )
+        .context("Copying database")?;
+    drop(connection);
+
+    let mut hasher = Sha3_256::new();
+    let size = std::io::copy(
+        &mut BufReader::new(File::open(copy).context("Opening database copy")?),
+        &mut hasher,
+    )
+    .context("Hashing database copy")?;
+    let info = SnapshotInfo {
+        size,
+        checksum: hasher.finalize().into(),
+    };
+
+    let mut output = BufWriter::new(output);
+    output.write_all(MAGIC).context("Writing snapshot header")?;
+    output
+        .write_all(&info.size.to_le_bytes())
+        .context("Writing snapshot header")?;
+    output
+        .write_all(&info.checksum)
+        .context("Writing snapshot header")?;
+
+    let mut encoder =
+        zstd::stream::Encoder::new(output, COMPRESSION_LEVEL).context("Creating encoder")?;
+    std::io::copy(
+        &mut BufReader::new(File::open(copy).context("Opening database copy")?),
+        &mut encoder,
+    )
+    .context("Compressing database")?;
+    encoder
+        .finish()
+        .context("Finishing compression")?
+        .flush()
+        .context("Flushing snapshot")?;
+
+    Ok(info)
+}
+
+/// Restores the snapshot read from `input` to a new database at `database`.
+///
+/// Fails without creating the database if the checksum does not match.
+pub fn restore(input: impl Read, database: &Path) -> anyhow::Result<SnapshotInfo> {
+    anyhow::ensure!(
+        !database.exists(),
+        "Database {} already exists",
+        database.display()
+    );
+
+    let staged = sibling(database, ".restore-tmp");
+    let result = restore_to(input, &staged).and_then(|info| {
+        std::fs::rename(&staged, database).context("Moving restored database into place")?;
+        Ok(info)
+    });
+    if result.is_err() {
+        std::fs::remove_file(&staged).ok();
+    }
+    result
+}
+
+fn restore_to(input: impl Read, staged: &Path) -> anyhow::Result<SnapshotInfo> {
+    let mut input = BufReader::new(input);
+
+    let mut magic = [0u8; 8];
+    input
+        .read_exact(&mut magic)
+        .context("Reading snapshot header")?;
+    anyhow::ensure!(&magic == MAGIC, "Not a database snapshot");
+    let mut size = [0u8; 8];
+    input
+        .read_exact(&mut size)
+        .context("Reading snapshot header")?;
+    let mut checksum = [0u8; 32];
+    input
+        .read_exact(&mut checksum)
+        .context("Reading snapshot header")?;
+    let expected = SnapshotInfo {
+        size: u64::from_le_bytes(size),
+        checksum,
+    };
+
+    let mut writer = HashingWriter {
+        inner: BufWriter::new(File::create(staged).context("Creating database")?),
+        hasher: Sha3_256::new(),
+    };
+    let size = std::io::copy(
+        &mut zstd::stream::Decoder::with_buffer(input).context("Creating decoder")?,
+        &mut writer,
+    )
+    .context("Decompressing database")?;
+    writer.inner.flush().context("Writing database")?;
+    let actual = SnapshotInfo {
+        size,
+        checksum: writer.hasher.finalize().into(),
+    };
+
+    anyhow::ensure!(
+        actual == expected,
+        "Snapshot checksum mismatch, expected {} bytes with checksum {} but got {} bytes with \
+         checksum {}",
+        expected.size,
+        hex::encode(expected.checksum),
+        actual.size,
+        hex::encode(actual.checksum)
+    );
+
+    Ok(actual)
+}
+
+/// Passes writes to `inner` while hashing them.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha3_256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// `path` with `suffix` appended to its file name.
+pub(crate) fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use pathfinder_common::{BlockHash, BlockHeader, BlockNumber};
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+    use crate::StorageBuilder;
+
+    fn database_with_block(path: &Path) -> BlockHeader {
+        let storage = StorageBuilder::file(path.to_owned())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let header = BlockHeader {
+            number: BlockNumber::GENESIS,
+            hash: BlockHash(Felt::from_u64(1)),
+            ..Default::default()
+        };
+        tx.insert_block_header(&header).unwrap();
+        tx.commit().unwrap();
+        header
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("source.sqlite");
+        let header = database_with_block(&database);
+
+        let mut snapshot = Vec::new();
+        let created = create(&database, &mut snapshot).unwrap();
+        assert!(!sibling(&database, ".snapshot-tmp").exists());
+
+        let restored_path = dir.path().join("restored.sqlite");
+        let restored = restore(snapshot.as_slice(), &restored_path).unwrap();
+        assert_eq!(restored, created);
+
+        let storage = StorageBuilder::file(restored_path)
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        assert_eq!(
+            tx.block_header(BlockNumber::GENESIS.into()).unwrap(),
+            Some(header)
+        );
+    }
+
+    #[test]
+    fn checksum_mismatch_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("source.sqlite");
+        database_with_block(&database);
+
+        let mut snapshot = Vec::new();
+        create(&database, &mut snapshot).unwrap();
+        // Corrupt the checksum in the header.
+        snapshot[16] ^= 0xff;
+
+        let restored_path = dir.path().join("restored.sqlite");
+        restore(snapshot.as_slice(), &restored_path).unwrap_err();
+        assert!(!restored_path.exists());
+        assert!(!sibling(&restored_path, ".restore-tmp").exists());
+    }
+
+    #[test]
+    fn existing_database_is_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("source.sqlite");
+        database_with_block(&database);
+
+        let mut snapshot = Vec::new();
+        create(&database, &mut snapshot).unwrap();
+
+        restore(snapshot.as_slice(), &database).unwrap_err();
+    }
+}
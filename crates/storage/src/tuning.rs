@@ -0,0 +1,136 @@
+//! Performance related SQLite settings.
+
+const MIB: u64 = 1024 * 1024;
+const GIB: u64 = 1024 * MIB;
+
+/// How the database is used, which determines how aggressively SQLite is
+/// allowed to use memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageProfile {
+    /// The node runs with `--low-memory`.
+    LowMemory,
+    /// Merkle tries are pruned.
+    Pruned,
+    /// All Merkle trie history is kept, so the database is much larger.
+    Archive,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TempStore {
+    File,
+    Memory,
+}
+
+/// SQLite [pragmas](https://sqlite.org/pragma.html) applied to every
+/// connection.
+///
+/// The defaults are SQLite's own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SqliteTuning {
+    /// Maximum number of bytes of the database file each connection memory
+    /// maps. SQLite caps this at its compile time limit.
+    pub mmap_size: u64,
+    /// Size of the page cache of each connection in bytes.
+    pub cache_size: u64,
+    /// Page size of newly created databases. Existing databases keep the page
+    /// size they were created with.
+    pub page_size: u32,
+    pub temp_store: TempStore,
+}
+
+impl Default for SqliteTuning {
+    fn default() -> Self {
+        Self {
+            mmap_size: 0,
+            cache_size: 2000 * 1024,
+            page_size: 4096,
+            temp_store: TempStore::File,
+        }
+    }
+}
+
+impl SqliteTuning {
+    /// Settings for the `profile` on a machine with `total_memory` bytes of
+    /// RAM.
+    ///
+    /// The page cache is per connection, of which there are dozens, so it is
+    /// kept small compared to the memory map, which is shared through the OS
+    /// page cache.
+    pub fn auto(profile: StorageProfile, total_memory: u64) -> Self {
+        match profile {
+            StorageProfile::LowMemory => Self::default(),
+            StorageProfile::Pruned => Self {
+                mmap_size: (total_memory / 8).min(16 * GIB),
+                cache_size: (total_memory / 2048).clamp(2 * MIB, 32 * MIB),
+                page_size: 4096,
+                temp_store: TempStore::Memory,
+            },
+            StorageProfile::Archive => Self {
+                mmap_size: (total_memory / 4).min(64 * GIB),
+                cache_size: (total_memory / 1024).clamp(2 * MIB, 64 * MIB),
+                page_size: 8192,
+                temp_store: TempStore::Memory,
+            },
+        }
+    }
+
+    pub(crate) fn apply(&self, connection: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+        connection.pragma_update(None, "mmap_size", self.mmap_size.to_string())?;
+        // Negative values are in KiB instead of pages.
+        connection.pragma_update(None, "cache_size", format!("-{}", self.cache_size / 1024))?;
+        let temp_store = match self.temp_store {
+            TempStore::File => "FILE",
+            TempStore::Memory => "MEMORY",
+        };
+        connection.pragma_update(None, "temp_store", temp_store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_memory_uses_sqlite_defaults() {
+        assert_eq!(
+            SqliteTuning::auto(StorageProfile::LowMemory, 64 * GIB),
+            SqliteTuning::default()
+        );
+    }
+
+    #[test]
+    fn scales_with_memory() {
+        let small = SqliteTuning::auto(StorageProfile::Pruned, 4 * GIB);
+        assert_eq!(small.mmap_size, 512 * MIB);
+        assert_eq!(small.cache_size, 2 * MIB);
+
+        let large = SqliteTuning::auto(StorageProfile::Pruned, 256 * GIB);
+        assert_eq!(large.mmap_size, 16 * GIB);
+        assert_eq!(large.cache_size, 32 * MIB);
+
+        let archive = SqliteTuning::auto(StorageProfile::Archive, 256 * GIB);
+        assert_eq!(archive.mmap_size, 64 * GIB);
+        assert_eq!(archive.cache_size, 64 * MIB);
+    }
+
+    #[test]
+    fn applied_to_connection() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        let tuning = SqliteTuning {
+            mmap_size: 0,
+            cache_size: 8 * MIB,
+            page_size: 4096,
+            temp_store: TempStore::Memory,
+        };
+        tuning.apply(&connection).unwrap();
+
+        let cache_size: i64 = connection
+            .pragma_query_value(None, "cache_size", |row| row.get(0))
+            .unwrap();
+        assert_eq!(cache_size, -8192);
+        let temp_store: i64 = connection
+            .pragma_query_value(None, "temp_store", |row| row.get(0))
+            .unwrap();
+        assert_eq!(temp_store, 2);
+    }
+}